@@ -5,26 +5,41 @@ mod access;
 mod block;
 mod call;
 mod execution;
+mod gas;
+mod hooks;
 mod input_state_ref;
 #[cfg(test)]
 mod tracer_tests;
 mod transaction;
 
 use self::access::gen_state_access_trace;
-use crate::error::Error;
+use crate::error::{CapacityOverflow, CircuitResource, Error, GasMismatch};
 use crate::evm::opcodes::{gen_associated_ops, gen_begin_tx_ops, gen_end_tx_ops};
-use crate::operation::{CallContextField, RW};
-use crate::rpc::GethClient;
+use crate::operation::{
+    AccountField, AccountOp, CallContextField, Operation, RWCounter, TxReceiptField, RW,
+};
+use crate::rpc::{BatchJsonRpcClient, GethClient, GethLoggerConfig};
 use crate::state_db::{self, CodeDB, StateDB};
 pub use access::{Access, AccessSet, AccessValue, CodeSource};
 pub use block::{Block, BlockContext};
 pub use call::{Call, CallContext, CallKind};
 use core::fmt::Debug;
-use eth_types::{self, Address, GethExecStep, GethExecTrace, Word};
-use ethers_providers::JsonRpcClient;
-pub use execution::{CopyDataType, CopyEvent, CopyStep, ExecState, ExecStep, NumberOrHash};
+pub use eth_types::circuits_params::CircuitsParams;
+use eth_types::{
+    self,
+    evm_types::GasCost,
+    geth_types::{self, ChainConfig},
+    Address, GethExecStep, GethExecTrace, Withdrawal, Word, H256,
+};
+pub use execution::{
+    CopyDataType, CopyEvent, CopyStep, ExecState, ExecStep, ExpEvent, ExpStep, NumberOrHash,
+};
+pub use hooks::{BuilderHooks, DefaultHooks};
 pub use input_state_ref::CircuitInputStateRef;
-use std::collections::HashMap;
+use itertools::Itertools;
+use keccak256::EMPTY_HASH;
+use rayon::prelude::*;
+use std::{collections::HashMap, sync::Arc};
 pub use transaction::{Transaction, TransactionContext};
 
 /// Builder to generate a complete circuit input from data gathered from a geth
@@ -55,17 +70,30 @@ pub struct CircuitInputBuilder {
     pub block: Block,
     /// Block Context
     pub block_ctx: BlockContext,
+    /// Hooks of the rollup specific behaviour
+    hooks: Arc<dyn BuilderHooks>,
 }
 
 impl<'a> CircuitInputBuilder {
     /// Create a new CircuitInputBuilder from the given `eth_block` and
     /// `constants`.
     pub fn new(sdb: StateDB, code_db: CodeDB, block: Block) -> Self {
+        Self::new_with_hooks(sdb, code_db, block, Arc::new(DefaultHooks))
+    }
+
+    /// Create a new CircuitInputBuilder customized for a rollup by `hooks`.
+    pub fn new_with_hooks(
+        sdb: StateDB,
+        code_db: CodeDB,
+        block: Block,
+        hooks: Arc<dyn BuilderHooks>,
+    ) -> Self {
         Self {
             sdb,
             code_db,
             block,
             block_ctx: BlockContext::new(),
+            hooks,
         }
     }
 
@@ -94,19 +122,21 @@ impl<'a> CircuitInputBuilder {
         is_success: bool,
     ) -> Result<Transaction, Error> {
         let call_id = self.block_ctx.rwc.0;
+        let tx_index = eth_tx
+            .transaction_index
+            .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?
+            .as_u64() as usize;
 
-        self.block_ctx.call_map.insert(
-            call_id,
-            (
-                eth_tx
-                    .transaction_index
-                    .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?
-                    .as_u64() as usize,
-                0,
-            ),
-        );
+        self.block_ctx.call_map.insert(call_id, (tx_index, 0));
 
-        Transaction::new(call_id, &self.sdb, &mut self.code_db, eth_tx, is_success)
+        let mut tx = Transaction::new(call_id, &self.sdb, &mut self.code_db, eth_tx, is_success)?;
+        // The web3 transactions don't carry the blob fields, which are queried
+        // separately with the block.
+        if let Some(blobs) = self.block.tx_blobs.get(tx_index) {
+            tx.max_fee_per_blob_gas = blobs.max_fee_per_blob_gas;
+            tx.blob_versioned_hashes = blobs.blob_versioned_hashes.clone();
+        }
+        Ok(tx)
     }
 
     /// Iterate over all generated CallContext RwCounterEndOfReversion
@@ -130,19 +160,365 @@ impl<'a> CircuitInputBuilder {
         }
     }
 
+    /// Return the hashes of the bytecodes used in the block, which are the
+    /// bytecodes executed and the ones copied from or to by a copy event.
+    fn bytecode_hashes(&self) -> Vec<H256> {
+        self.block
+            .txs
+            .iter()
+            .flat_map(|tx| tx.calls().iter().map(|call| call.code_hash))
+            .chain(self.block.copy_events.iter().flat_map(|copy_event| {
+                [
+                    (copy_event.src_type, &copy_event.src_id),
+                    (copy_event.dst_type, &copy_event.dst_id),
+                ]
+                .into_iter()
+                .filter_map(|(tag, id)| match (tag, id) {
+                    (CopyDataType::Bytecode, NumberOrHash::Hash(code_hash)) => Some(*code_hash),
+                    _ => None,
+                })
+            }))
+            .unique()
+            .collect()
+    }
+
+    /// Add the bytecodes used in the block as inputs of keccak hashes.
+    fn add_bytecode_sha3_inputs(&mut self) {
+        for code_hash in self.bytecode_hashes() {
+            let code = self.code_db.0.get(&code_hash).cloned().unwrap_or_default();
+            self.block.add_sha3_input(code);
+        }
+    }
+
+    /// Check that the handled block fits in the capacities of the circuits
+    /// set by `params`, returning the resources it overflows otherwise.  This
+    /// lets a block be rejected before proving it.  The rows of the EVM
+    /// circuit, which depend on the layout of its execution states, the
+    /// keccak rows of the hashes of the tx circuit and the PI circuit, and the
+    /// unusable rows of the circuits aren't checked.
+    pub fn check_capacity(&self, params: &CircuitsParams) -> Result<(), Error> {
+        let num_bytecode_rows: usize = self
+            .bytecode_hashes()
+            .iter()
+            .map(|code_hash| self.code_db.0.get(code_hash).map_or(0, |code| code.len()) + 1)
+            .sum();
+        let usages = [
+            (CircuitResource::Txs, self.block.txs.len(), params.max_txs),
+            (
+                CircuitResource::Calldata,
+                self.block.txs.iter().map(|tx| tx.input.len()).sum(),
+                params.max_calldata,
+            ),
+            (
+                CircuitResource::Rws,
+                self.block_ctx.rwc.0 - 1,
+                params.max_rws,
+            ),
+            (
+                CircuitResource::Bytecode,
+                num_bytecode_rows,
+                params.max_bytecode,
+            ),
+            (
+                CircuitResource::CopyRows,
+                self.block
+                    .copy_events
+                    .iter()
+                    .map(|copy_event| copy_event.steps.len())
+                    .sum(),
+                params.max_copy_rows,
+            ),
+            (
+                CircuitResource::ExpRows,
+                self.block
+                    .exp_events
+                    .iter()
+                    .map(|exp_event| exp_event.steps.len())
+                    .sum(),
+                params.max_exp_rows,
+            ),
+            // The keccak circuit takes a permutation of 50 rows per block of
+            // 136 bytes of each input, including at least one byte of padding.
+            (
+                CircuitResource::KeccakRows,
+                self.block
+                    .sha3_inputs
+                    .iter()
+                    .map(|input| (input.len() / 136 + 1) * 50)
+                    .sum(),
+                params.max_keccak_rows,
+            ),
+        ];
+        let overflows: Vec<_> = usages
+            .into_iter()
+            .filter(|(_, used, capacity)| used > capacity)
+            .map(|(resource, used, capacity)| CapacityOverflow {
+                resource,
+                used,
+                capacity,
+            })
+            .collect();
+        if overflows.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::CapacityExceeded(overflows))
+        }
+    }
+
     /// Handle a block by handling each transaction to generate all the
-    /// associated operations.
+    /// associated operations, followed by the withdrawals of the block.
     pub fn handle_block(
         &mut self,
         eth_block: &EthBlock,
         geth_traces: &[eth_types::GethExecTrace],
     ) -> Result<(), Error> {
-        // accumulates gas across all txs in the block
-        for (tx_index, tx) in eth_block.transactions.iter().enumerate() {
+        let hooks = self.hooks.clone();
+        hooks.set_block_fields(&mut self.block, eth_block)?;
+
+        // The associated operations are generated one transaction after the
+        // other, as each transaction reads the state written by the previous
+        // ones and its rw counters follow theirs.  See
+        // `handle_block_with_tx_states` to generate them in parallel.
+        let tx_ctxs = self.tx_contexts(eth_block, geth_traces)?;
+        for (tx_index, (tx_ctx, sign_msg)) in tx_ctxs.into_iter().enumerate() {
+            let eth_tx = &eth_block.transactions[tx_index];
             let geth_trace = &geth_traces[tx_index];
-            self.handle_tx(tx, geth_trace, tx_index + 1 == eth_block.transactions.len())?;
+            if !hooks.handle_system_tx(self, eth_tx, geth_trace)? {
+                self.handle_tx(eth_tx, geth_trace, tx_ctx, sign_msg)?;
+            }
         }
+        self.end_block()
+    }
+
+    /// Handle a block like [`Self::handle_block`], but generating the
+    /// operations of the transactions in parallel, given the state before
+    /// each transaction in `tx_states` (e.g. from the `prestateTracer` of
+    /// geth), which needs to hold the accounts and storage slots accessed by
+    /// the transaction.  Each transaction is handled on its own state db,
+    /// code db and rw counter starting at 1, and the results are merged in
+    /// the order of the block by moving the rw counters and call ids of each
+    /// transaction after the ones of the previous transactions, so that the
+    /// circuit inputs are identical to the ones of [`Self::handle_block`].
+    pub fn handle_block_with_tx_states(
+        &mut self,
+        eth_block: &EthBlock,
+        geth_traces: &[eth_types::GethExecTrace],
+        tx_states: Vec<StateDB>,
+    ) -> Result<(), Error> {
+        if tx_states.len() != eth_block.transactions.len() {
+            return Err(Error::TxStatesMismatch(
+                tx_states.len(),
+                eth_block.transactions.len(),
+            ));
+        }
+        let hooks = self.hooks.clone();
+        hooks.set_block_fields(&mut self.block, eth_block)?;
+
+        let tx_ctxs = self.tx_contexts(eth_block, geth_traces)?;
+        let tx_builders = tx_ctxs
+            .into_par_iter()
+            .zip(tx_states.into_par_iter())
+            .enumerate()
+            .map(|(tx_index, ((tx_ctx, sign_msg), sdb))| {
+                let mut tx_builder = Self::new_with_hooks(
+                    sdb,
+                    self.code_db.clone(),
+                    self.block.without_txs(),
+                    hooks.clone(),
+                );
+                let eth_tx = &eth_block.transactions[tx_index];
+                let geth_trace = &geth_traces[tx_index];
+                if !hooks.handle_system_tx(&mut tx_builder, eth_tx, geth_trace)? {
+                    tx_builder.handle_tx(eth_tx, geth_trace, tx_ctx, sign_msg)?;
+                }
+                Ok(tx_builder)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        for tx_builder in tx_builders {
+            self.append_tx_builder(tx_builder);
+        }
+        self.end_block()
+    }
+
+    /// Return the context of each transaction of the block and the message
+    /// signed by its sender, which only depend on the transaction and its
+    /// trace, so they are derived for all the transactions in parallel.
+    fn tx_contexts(
+        &self,
+        eth_block: &EthBlock,
+        geth_traces: &[eth_types::GethExecTrace],
+    ) -> Result<Vec<(TransactionContext, Vec<u8>)>, Error> {
+        let chain_id = self.block.chain_id.as_u64();
+        let num_txs = eth_block.transactions.len();
+        let tx_blobs = &self.block.tx_blobs;
+        (0..num_txs)
+            .into_par_iter()
+            .map(|tx_index| {
+                let eth_tx = &eth_block.transactions[tx_index];
+                let tx_ctx = TransactionContext::new(
+                    eth_tx,
+                    &geth_traces[tx_index],
+                    tx_index + 1 == num_txs,
+                )?;
+                let mut tx = geth_types::Transaction::from_eth_tx(eth_tx);
+                if let Some(blobs) = tx_blobs.get(tx_index) {
+                    tx.max_fee_per_blob_gas = blobs.max_fee_per_blob_gas;
+                    tx.blob_versioned_hashes = blobs.blob_versioned_hashes.clone();
+                }
+                let sign_msg = tx.sign_rlp(chain_id);
+                Ok((tx_ctx, sign_msg))
+            })
+            .collect()
+    }
+
+    /// Append the circuit inputs generated by `tx_builder` for a transaction
+    /// handled on its own after the ones of the previous transactions,
+    /// moving its rw counters, call ids, references to operations and
+    /// cumulative gas used after theirs.
+    fn append_tx_builder(&mut self, tx_builder: Self) {
+        let offset = self.block_ctx.rwc.0 - 1;
+        // The zero of the caller of a root call and of the end of reversion of
+        // a persistent call isn't a rw counter nor a call id.
+        let rebase = |id: usize| if id == 0 { 0 } else { id + offset };
+        let Self {
+            sdb,
+            code_db,
+            block,
+            block_ctx,
+            ..
+        } = tx_builder;
+        let Block {
+            mut container,
+            txs,
+            mut copy_events,
+            mut exp_events,
+            sha3_inputs,
+            ..
+        } = block;
+
+        for mut tx in txs {
+            for call in tx.calls_mut().iter_mut() {
+                call.call_id = rebase(call.call_id);
+                call.caller_id = rebase(call.caller_id);
+                call.rw_counter_end_of_reversion = rebase(call.rw_counter_end_of_reversion);
+            }
+            for step in tx.steps_mut().iter_mut() {
+                step.rwc = RWCounter(step.rwc.0 + offset);
+                for op_ref in step.bus_mapping_instance.iter_mut() {
+                    op_ref.1 += self.block.container.num_ops(op_ref.0);
+                }
+            }
+            let tx_index = self.block.txs.len();
+            for (call_index, call) in tx.calls().iter().enumerate() {
+                self.block_ctx
+                    .call_map
+                    .insert(call.call_id, (tx_index, call_index));
+            }
+            self.block.txs.push(tx);
+        }
+        for operation in container.tx_receipt.iter_mut() {
+            let op = operation.op_mut();
+            if op.field == TxReceiptField::CumulativeGasUsed {
+                op.value += self.block_ctx.cumulative_gas_used;
+            }
+        }
+        self.block.container.append_rebased(container, offset);
+
+        for copy_event in copy_events.iter_mut() {
+            copy_event.call_id = rebase(copy_event.call_id);
+            for (tag, id) in [
+                (copy_event.src_type, &mut copy_event.src_id),
+                (copy_event.dst_type, &mut copy_event.dst_id),
+            ] {
+                if let (CopyDataType::Memory, NumberOrHash::Number(call_id)) = (tag, id) {
+                    *call_id = rebase(*call_id);
+                }
+            }
+            for step in copy_event.steps.iter_mut() {
+                step.rwc = RWCounter(step.rwc.0 + offset);
+            }
+        }
+        for exp_event in exp_events.iter_mut() {
+            exp_event.identifier += offset;
+        }
+        self.block.copy_events.extend(copy_events);
+        self.block.exp_events.extend(exp_events);
+        self.block.sha3_inputs.extend(sha3_inputs);
+
+        self.block_ctx.rwc = RWCounter(block_ctx.rwc.0 + offset);
+        self.block_ctx.cumulative_gas_used += block_ctx.cumulative_gas_used;
+        self.code_db.0.extend(code_db.0);
+        self.sdb.update_accounts(sdb);
+    }
+
+    /// End the handling of a block after its transactions, with its
+    /// withdrawals.
+    fn end_block(&mut self) -> Result<(), Error> {
+        self.handle_withdrawals()?;
         self.set_value_ops_call_context_rwc_eor();
+        self.add_bytecode_sha3_inputs();
+        Ok(())
+    }
+
+    /// Handle the withdrawals of the block by crediting the withdrawn amount
+    /// to the balance of each recipient.  The generated balance writes don't
+    /// belong to any execution step.
+    fn handle_withdrawals(&mut self) -> Result<(), Error> {
+        for withdrawal in self.block.withdrawals.iter() {
+            let (found, account) = self.sdb.get_account_mut(&withdrawal.address);
+            if !found {
+                return Err(Error::AccountNotFound(withdrawal.address));
+            }
+            let balance_prev = account.balance;
+            account.balance = balance_prev + withdrawal.amount_wei();
+            self.block.container.insert(Operation::new(
+                self.block_ctx.rwc.inc_pre(),
+                RW::WRITE,
+                AccountOp::new(
+                    withdrawal.address,
+                    AccountField::Balance,
+                    account.balance,
+                    balance_prev,
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Charge the L1 data fee of a transaction, when the rollup has one, to its
+    /// sender and credit it to the recipient of the fee.  The generated
+    /// balance writes don't belong to any execution step.
+    fn charge_l1_fee(&mut self, eth_tx: &eth_types::Transaction) -> Result<(), Error> {
+        let (fee, fee_recipient) = match self.hooks.l1_fee(eth_tx) {
+            Some(l1_fee) => l1_fee,
+            None => return Ok(()),
+        };
+        for (address, is_credit) in [(eth_tx.from, false), (fee_recipient, true)] {
+            let (found, account) = self.sdb.get_account_mut(&address);
+            if !found {
+                return Err(Error::AccountNotFound(address));
+            }
+            let balance_prev = account.balance;
+            account.balance = if is_credit {
+                balance_prev + fee
+            } else {
+                balance_prev
+                    .checked_sub(fee)
+                    .ok_or(Error::InvalidGethExecTrace(
+                        "sender balance lower than the L1 fee",
+                    ))?
+            };
+            self.block.container.insert(Operation::new(
+                self.block_ctx.rwc.inc_pre(),
+                RW::WRITE,
+                AccountOp::new(
+                    address,
+                    AccountField::Balance,
+                    account.balance,
+                    balance_prev,
+                ),
+            ));
+        }
         Ok(())
     }
 
@@ -155,16 +531,32 @@ impl<'a> CircuitInputBuilder {
         &mut self,
         eth_tx: &eth_types::Transaction,
         geth_trace: &GethExecTrace,
-        is_last_tx: bool,
+        mut tx_ctx: TransactionContext,
+        sign_msg: Vec<u8>,
     ) -> Result<(), Error> {
+        self.charge_l1_fee(eth_tx)?;
         let mut tx = self.new_tx(eth_tx, !geth_trace.failed)?;
-        let mut tx_ctx = TransactionContext::new(eth_tx, geth_trace, is_last_tx)?;
+
+        // The message signed by the sender is hashed to verify the signature.
+        self.block.add_sha3_input(sign_msg);
 
         // TODO: Move into gen_associated_steps with
         // - execution_state: BeginTx
         // - op: None
         // Generate BeginTx step
         let begin_tx_step = gen_begin_tx_ops(&mut self.state_ref(&mut tx, &mut tx_ctx))?;
+        // The intrinsic gas is charged before the first step of the trace.
+        if let Some(geth_step) = geth_trace.struct_logs.first() {
+            let geth_gas_cost = GasCost(eth_tx.gas.as_u64().checked_sub(geth_step.gas.0).ok_or(
+                Error::InvalidGethExecTrace("gas of the first step above the gas of the tx"),
+            )?);
+            check_gas(
+                tx_ctx.id(),
+                &begin_tx_step,
+                Some(begin_tx_step.gas_cost),
+                geth_gas_cost,
+            )?;
+        }
         tx.steps_mut().push(begin_tx_step);
 
         for (index, geth_step) in geth_trace.struct_logs.iter().enumerate() {
@@ -175,6 +567,20 @@ impl<'a> CircuitInputBuilder {
                 &mut state_ref,
                 &geth_trace.struct_logs[index..],
             )?;
+            // The gas cost of the opcode is computed from the operations
+            // generated for its step and checked against the geth trace.
+            for exec_step in exec_steps
+                .iter()
+                .filter(|exec_step| exec_step.exec_state == ExecState::Op(geth_step.op))
+            {
+                let gas_cost = gas::expected_gas_cost(
+                    &self.block.container,
+                    self.block.hardfork(),
+                    geth_step,
+                    exec_step,
+                )?;
+                check_gas(tx_ctx.id(), exec_step, gas_cost, geth_step.gas_cost)?;
+            }
             tx.steps_mut().extend(exec_steps);
         }
 
@@ -192,6 +598,26 @@ impl<'a> CircuitInputBuilder {
     }
 }
 
+/// Check that the gas cost of an execution step computed by the builder, when
+/// known, matches the one of the geth trace.
+fn check_gas(
+    tx_id: usize,
+    exec_step: &ExecStep,
+    gas_cost: Option<GasCost>,
+    geth_gas_cost: GasCost,
+) -> Result<(), Error> {
+    match gas_cost {
+        Some(gas_cost) if gas_cost != geth_gas_cost => Err(Error::GasMismatch(GasMismatch {
+            tx_id,
+            exec_state: exec_step.exec_state.clone(),
+            gas_left: exec_step.gas_left,
+            gas_cost,
+            geth_gas_cost,
+        })),
+        _ => Ok(()),
+    }
+}
+
 /// Retrieve the init_code from memory for {CREATE, CREATE2}
 pub fn get_create_init_code(step: &GethExecStep) -> Result<&[u8], Error> {
     let offset = step.stack.nth_last(1)?;
@@ -215,22 +641,37 @@ type EthBlock = eth_types::Block<eth_types::Transaction>;
 /// Struct that wraps a GethClient and contains methods to perform all the steps
 /// necessary to generate the circuit inputs for a block by querying geth for
 /// the necessary information and using the CircuitInputBuilder.
-pub struct BuilderClient<P: JsonRpcClient> {
+pub struct BuilderClient<P: BatchJsonRpcClient> {
     cli: GethClient<P>,
-    chain_id: Word,
-    history_hashes: Vec<Word>,
+    chain_config: ChainConfig,
+    circuits_params: CircuitsParams,
+    hooks: Arc<dyn BuilderHooks>,
 }
 
-impl<P: JsonRpcClient> BuilderClient<P> {
-    /// Create a new BuilderClient
-    pub async fn new(client: GethClient<P>) -> Result<Self, Error> {
-        let chain_id = client.get_chain_id().await?;
+impl<P: BatchJsonRpcClient> BuilderClient<P> {
+    /// Create a new BuilderClient building the inputs of circuits with the
+    /// capacities of `circuits_params`.
+    pub async fn new(
+        client: GethClient<P>,
+        circuits_params: CircuitsParams,
+    ) -> Result<Self, Error> {
+        Self::new_with_hooks(client, circuits_params, Arc::new(DefaultHooks)).await
+    }
+
+    /// Create a new BuilderClient building the inputs of circuits customized
+    /// for a rollup by `hooks`.
+    pub async fn new_with_hooks(
+        client: GethClient<P>,
+        circuits_params: CircuitsParams,
+        hooks: Arc<dyn BuilderHooks>,
+    ) -> Result<Self, Error> {
+        let chain_config = client.get_chain_config().await?;
 
         Ok(Self {
             cli: client,
-            chain_id: chain_id.into(),
-            // TODO: Get history hashes
-            history_hashes: Vec::new(),
+            chain_config,
+            circuits_params,
+            hooks,
         })
     }
 
@@ -240,14 +681,32 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         block_num: u64,
     ) -> Result<(EthBlock, Vec<eth_types::GethExecTrace>), Error> {
         let eth_block = self.cli.get_block_by_number(block_num.into()).await?;
-        let geth_traces = self.cli.trace_block_by_number(block_num.into()).await?;
+        let geth_traces = self
+            .cli
+            .trace_block_by_number(block_num.into(), &GethLoggerConfig::default())
+            .await?;
         Ok((eth_block, geth_traces))
     }
 
-    /// Step 2. Get State Accesses from TxExecTraces
+    /// Step 1b. Query geth for the constants of the block, the hashes of the
+    /// most recent 256 blocks preceding it, the state root of its parent block,
+    /// at which the proofs of step 3 are taken, and its withdrawals.
+    pub async fn get_block_context(&self, eth_block: &EthBlock) -> Result<Block, Error> {
+        Block::from_rpc(
+            &self.cli,
+            self.chain_config.clone(),
+            eth_block,
+            self.circuits_params,
+        )
+        .await
+    }
+
+    /// Step 2. Get State Accesses from TxExecTraces and the recipients of the
+    /// withdrawals
     pub fn get_state_accesses(
         &self,
         eth_block: &EthBlock,
+        withdrawals: &[Withdrawal],
         geth_traces: &[eth_types::GethExecTrace],
     ) -> Result<AccessSet, Error> {
         let mut block_access_trace = vec![Access::new(
@@ -257,11 +716,23 @@ impl<P: JsonRpcClient> BuilderClient<P> {
                 address: eth_block.author,
             },
         )];
-        for (tx_index, tx) in eth_block.transactions.iter().enumerate() {
-            let geth_trace = &geth_traces[tx_index];
-            let tx_access_trace = gen_state_access_trace(eth_block, tx, geth_trace)?;
-            block_access_trace.extend(tx_access_trace);
-        }
+        // The accesses of each transaction are derived from its trace only.
+        let tx_access_traces = eth_block
+            .transactions
+            .par_iter()
+            .enumerate()
+            .map(|(tx_index, tx)| gen_state_access_trace(eth_block, tx, &geth_traces[tx_index]))
+            .collect::<Result<Vec<_>, Error>>()?;
+        block_access_trace.extend(tx_access_traces.into_iter().flatten());
+        block_access_trace.extend(withdrawals.iter().map(|withdrawal| {
+            Access::new(
+                None,
+                RW::WRITE,
+                AccessValue::Account {
+                    address: withdrawal.address,
+                },
+            )
+        }));
 
         Ok(AccessSet::from(block_access_trace))
     }
@@ -279,27 +750,25 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         ),
         Error,
     > {
-        let mut proofs = Vec::new();
-        for (address, key_set) in access_set.state {
-            let mut keys: Vec<Word> = key_set.iter().cloned().collect();
-            keys.sort();
-            let proof = self
-                .cli
-                .get_proof(address, keys, (block_num - 1).into())
-                .await
-                .unwrap();
-            proofs.push(proof);
-        }
-        let mut codes: HashMap<Address, Vec<u8>> = HashMap::new();
-        for address in access_set.code {
-            let code = self
-                .cli
-                .get_code(address, (block_num - 1).into())
-                .await
-                .unwrap();
-            codes.insert(address, code);
-        }
-        Ok((proofs, codes))
+        let accounts: Vec<(Address, Vec<Word>)> = access_set
+            .state
+            .into_iter()
+            .map(|(address, key_set)| {
+                let mut keys: Vec<Word> = key_set.into_iter().collect();
+                keys.sort();
+                (address, keys)
+            })
+            .collect();
+        let proofs = self
+            .cli
+            .get_proofs(&accounts, (block_num - 1).into())
+            .await?;
+        let addresses: Vec<Address> = access_set.code.into_iter().collect();
+        let codes = self
+            .cli
+            .get_codes(&addresses, (block_num - 1).into())
+            .await?;
+        Ok((proofs, addresses.into_iter().zip(codes).collect()))
     }
 
     /// Step 4. Build a partial StateDB from step 3
@@ -314,13 +783,20 @@ impl<P: JsonRpcClient> BuilderClient<P> {
             for storage_proof in proof.storage_proof {
                 storage.insert(storage_proof.key, storage_proof.value);
             }
+            // geth reports a zero code hash for the accounts that don't exist,
+            // which have the code hash of the empty code in the StateDB.
+            let code_hash = if proof.code_hash.is_zero() {
+                H256(*EMPTY_HASH)
+            } else {
+                proof.code_hash
+            };
             sdb.set_account(
                 &proof.address,
                 state_db::Account {
                     nonce: proof.nonce,
                     balance: proof.balance,
                     storage,
-                    code_hash: proof.code_hash,
+                    code_hash,
                 },
             )
         }
@@ -338,22 +814,410 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         &self,
         sdb: StateDB,
         code_db: CodeDB,
+        block: Block,
         eth_block: &EthBlock,
         geth_traces: &[eth_types::GethExecTrace],
     ) -> Result<CircuitInputBuilder, Error> {
-        let block = Block::new(self.chain_id, self.history_hashes.clone(), eth_block)?;
-        let mut builder = CircuitInputBuilder::new(sdb, code_db, block);
+        let mut builder =
+            CircuitInputBuilder::new_with_hooks(sdb, code_db, block, self.hooks.clone());
         builder.handle_block(eth_block, geth_traces)?;
         Ok(builder)
     }
 
-    /// Perform all the steps to generate the circuit inputs
+    /// Perform all the steps to generate the circuit inputs of the block
+    /// `block_num`, returning the builder holding the circuit inputs and the
+    /// code database of the bytecodes executed in the block.
     pub async fn gen_inputs(&self, block_num: u64) -> Result<CircuitInputBuilder, Error> {
         let (eth_block, geth_traces) = self.get_block(block_num).await?;
-        let access_set = self.get_state_accesses(&eth_block, &geth_traces)?;
+        let block = self.get_block_context(&eth_block).await?;
+        let access_set = self.get_state_accesses(&eth_block, &block.withdrawals, &geth_traces)?;
         let (proofs, codes) = self.get_state(block_num, access_set).await?;
-        let (state_db, code_db) = self.build_state_code_db(proofs, codes);
-        let builder = self.gen_inputs_from_state(state_db, code_db, &eth_block, &geth_traces)?;
+        let (state_db, code_db) = self.build_state_code_db(proofs.clone(), codes);
+        let mut builder =
+            self.gen_inputs_from_state(state_db, code_db, block, &eth_block, &geth_traces)?;
+        // The proofs are kept for the witness of the MPT circuit.
+        builder.block.set_state_proofs(proofs);
         Ok(builder)
     }
 }
+
+#[cfg(test)]
+mod withdrawals_tests {
+    use super::*;
+    use crate::mock::BlockData;
+    use crate::operation::RWCounter;
+    use eth_types::{bytecode, geth_types::GethData, U64};
+    use mock::{test_ctx::TestContext, MOCK_ACCOUNTS};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn withdrawals_credit_balances() {
+        let mut block: GethData = TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode! {
+            STOP
+        })
+        .unwrap()
+        .into();
+        // Two withdrawals of 1 ETH and 2 Gwei to a fresh account.
+        block.withdrawals = [1_000_000_000u64, 2]
+            .into_iter()
+            .enumerate()
+            .map(|(index, amount)| Withdrawal {
+                index: U64::from(index),
+                validator_index: U64::from(0x7a),
+                address: MOCK_ACCOUNTS[2],
+                amount: U64::from(amount),
+            })
+            .collect();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let rwc = builder.block_ctx.rwc.0;
+        let eth = Word::exp10(18);
+        let gwei = Word::exp10(9);
+        assert_eq!(
+            builder.block.container.account[builder.block.container.account.len() - 2..],
+            [(rwc - 2, eth, Word::zero()), (rwc - 1, eth + gwei * 2, eth)].map(
+                |(rwc, value, value_prev)| Operation::new(
+                    RWCounter(rwc),
+                    RW::WRITE,
+                    AccountOp::new(MOCK_ACCOUNTS[2], AccountField::Balance, value, value_prev)
+                )
+            )
+        );
+        assert_eq!(
+            builder.sdb.get_account(&MOCK_ACCOUNTS[2]).1.balance,
+            eth + gwei * 2
+        );
+    }
+
+    #[test]
+    fn withdrawals_unknown_account() {
+        let block: GethData = TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode! {
+            STOP
+        })
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder.block.withdrawals = vec![Withdrawal {
+            address: MOCK_ACCOUNTS[2],
+            amount: U64::from(1),
+            ..Withdrawal::default()
+        }];
+        assert!(matches!(
+            builder.handle_block(&block.eth_block, &block.geth_traces),
+            Err(Error::AccountNotFound(address)) if address == MOCK_ACCOUNTS[2]
+        ));
+    }
+}
+
+#[cfg(test)]
+mod sha3_inputs_tests {
+    use super::*;
+    use crate::mock::BlockData;
+    use eth_types::{bytecode, geth_types::GethData, ToBigEndian};
+    use mock::test_ctx::TestContext;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sha3_inputs_of_block() {
+        let code = bytecode! {
+            PUSH32(Word::from(0x1234))
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x20)
+            PUSH1(0x00)
+            SHA3
+            STOP
+        };
+        let block: GethData = TestContext::<2, 1>::simple_ctx_with_bytecode(code.clone())
+            .unwrap()
+            .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let sign_msg = geth_types::Transaction::from_eth_tx(&block.eth_block.transactions[0])
+            .sign_rlp(block.chain_id.as_u64());
+        assert_eq!(
+            builder.block.sha3_inputs,
+            vec![
+                sign_msg,
+                Word::from(0x1234).to_be_bytes().to_vec(),
+                code.to_vec()
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod parallel_tests {
+    use super::*;
+    use crate::mock::BlockData;
+    use eth_types::{bytecode, geth_types::GethData, ToWord, U64};
+    use mock::{eth, test_ctx::TestContext, MOCK_ACCOUNTS};
+    use pretty_assertions::assert_eq;
+    use std::sync::Mutex;
+
+    /// Hooks recording the state before each transaction.
+    #[derive(Debug, Default)]
+    struct TxStatesHooks {
+        tx_states: Mutex<Vec<StateDB>>,
+    }
+
+    impl BuilderHooks for TxStatesHooks {
+        fn handle_system_tx(
+            &self,
+            builder: &mut CircuitInputBuilder,
+            _eth_tx: &eth_types::Transaction,
+            _geth_trace: &GethExecTrace,
+        ) -> Result<bool, Error> {
+            self.tx_states.lock().unwrap().push(builder.sdb.clone());
+            Ok(false)
+        }
+    }
+
+    #[test]
+    fn parallel_txs_match_sequential() {
+        // Each tx calls B, which increments a storage slot read by the next tx,
+        // emits a log and returns the slot, hashed by A, and then calls C,
+        // which reverts its storage write.
+        let code_b = bytecode! {
+            PUSH1(0x00) // key
+            SLOAD
+            PUSH1(0x01)
+            ADD
+            PUSH1(0x00) // key
+            SSTORE
+            PUSH1(0x00) // size
+            PUSH1(0x00) // offset
+            LOG0
+            PUSH1(0x00) // key
+            SLOAD
+            PUSH1(0x00) // offset
+            MSTORE
+            PUSH1(0x20) // size
+            PUSH1(0x00) // offset
+            RETURN
+        };
+        let code_c = bytecode! {
+            PUSH1(0x01) // value
+            PUSH1(0x00) // key
+            SSTORE
+            PUSH1(0x00) // size
+            PUSH1(0x00) // offset
+            REVERT
+        };
+        let code_a = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH32(MOCK_ACCOUNTS[1].to_word()) // addr
+            PUSH32(0x1_0000) // gas
+            CALL
+            POP
+            PUSH1(0x20) // size
+            PUSH1(0x00) // offset
+            PUSH1(0x00) // dest_offset
+            RETURNDATACOPY
+            PUSH1(0x20) // size
+            PUSH1(0x00) // offset
+            SHA3
+            POP
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH32(MOCK_ACCOUNTS[2].to_word()) // addr
+            PUSH32(0x1_0000) // gas
+            CALL
+            POP
+            STOP
+        };
+        let mut block: GethData = TestContext::<4, 3>::new(
+            None,
+            |accs| {
+                accs[0].address(MOCK_ACCOUNTS[0]).code(code_a);
+                accs[1].address(MOCK_ACCOUNTS[1]).code(code_b);
+                accs[2].address(MOCK_ACCOUNTS[2]).code(code_c);
+                accs[3].address(MOCK_ACCOUNTS[3]).balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[3].address);
+                txs[1].to(accs[0].address).from(accs[3].address);
+                txs[2].to(accs[0].address).from(accs[3].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+        block.withdrawals = vec![Withdrawal {
+            address: MOCK_ACCOUNTS[3],
+            amount: U64::from(1),
+            ..Withdrawal::default()
+        }];
+
+        let block_data = BlockData::new_from_geth_data(block.clone());
+        let hooks = Arc::new(TxStatesHooks::default());
+        let builder = block_data.new_circuit_input_builder();
+        let mut sequential = CircuitInputBuilder::new_with_hooks(
+            builder.sdb,
+            builder.code_db,
+            builder.block,
+            hooks.clone(),
+        );
+        sequential
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        let tx_states = hooks.tx_states.lock().unwrap().clone();
+        assert_eq!(tx_states.len(), 3);
+
+        let mut parallel = block_data.new_circuit_input_builder();
+        parallel
+            .handle_block_with_tx_states(&block.eth_block, &block.geth_traces, tx_states)
+            .unwrap();
+
+        assert_eq!(parallel.block.container, sequential.block.container);
+        assert_eq!(
+            serde_json::to_value(&parallel.block).unwrap(),
+            serde_json::to_value(&sequential.block).unwrap()
+        );
+        assert_eq!(parallel.block_ctx.rwc, sequential.block_ctx.rwc);
+        assert_eq!(parallel.block_ctx.call_map, sequential.block_ctx.call_map);
+        assert_eq!(
+            parallel.block_ctx.cumulative_gas_used,
+            sequential.block_ctx.cumulative_gas_used
+        );
+        for address in MOCK_ACCOUNTS[..4].iter() {
+            assert_eq!(
+                parallel.sdb.get_account(address),
+                sequential.sdb.get_account(address)
+            );
+        }
+    }
+
+    #[test]
+    fn parallel_txs_need_tx_states() {
+        let block: GethData = TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode! {
+            STOP
+        })
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        assert!(matches!(
+            builder.handle_block_with_tx_states(&block.eth_block, &block.geth_traces, Vec::new()),
+            Err(Error::TxStatesMismatch(0, 1))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod serde_tests {
+    use super::*;
+    use crate::mock::BlockData;
+    use eth_types::{bytecode, geth_types::GethData};
+    use mock::test_ctx::TestContext;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn block_and_code_db_round_trip() {
+        let code = bytecode! {
+            PUSH1(0x03)
+            PUSH1(0x02)
+            EXP
+            PUSH1(0x20) // size
+            PUSH1(0x00) // offset
+            PUSH1(0x00) // dest_offset
+            CALLDATACOPY
+            PUSH1(0x00) // key
+            SSTORE
+            STOP
+        };
+        let block: GethData = TestContext::<2, 1>::simple_ctx_with_bytecode(code)
+            .unwrap()
+            .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        assert!(!builder.block.copy_events.is_empty());
+        assert!(!builder.block.exp_events.is_empty());
+
+        // The inputs of the circuits are written to a file next to the node and
+        // read back on the machine proving them.
+        let json = serde_json::to_string(&(&builder.block, &builder.code_db)).unwrap();
+        let (block, code_db): (Block, CodeDB) = serde_json::from_str(&json).unwrap();
+        assert_eq!(block.container, builder.block.container);
+        assert_eq!(
+            serde_json::to_value(&block).unwrap(),
+            serde_json::to_value(&builder.block).unwrap()
+        );
+        assert_eq!(code_db.0, builder.code_db.0);
+    }
+}
+
+#[cfg(test)]
+mod capacity_tests {
+    use super::*;
+    use crate::mock::BlockData;
+    use eth_types::{bytecode, geth_types::GethData};
+    use mock::test_ctx::TestContext;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn check_capacity_reports_overflows() {
+        let code = bytecode! {
+            PUSH1(0x20) // size
+            PUSH1(0x00) // offset
+            PUSH1(0x00) // dest_offset
+            CODECOPY
+            STOP
+        };
+        let block: GethData = TestContext::<2, 1>::simple_ctx_with_bytecode(code.clone())
+            .unwrap()
+            .into();
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        assert!(builder.check_capacity(&CircuitsParams::default()).is_ok());
+
+        let num_rws = builder.block_ctx.rwc.0 - 1;
+        let params = CircuitsParams {
+            max_rws: num_rws - 1,
+            max_bytecode: code.to_vec().len(),
+            ..CircuitsParams::default()
+        };
+        let overflows = match builder.check_capacity(&params) {
+            Err(Error::CapacityExceeded(overflows)) => overflows,
+            result => panic!("unexpected result {:?}", result),
+        };
+        assert_eq!(
+            overflows,
+            vec![
+                CapacityOverflow {
+                    resource: CircuitResource::Rws,
+                    used: num_rws,
+                    capacity: num_rws - 1,
+                },
+                // The bytecode takes a row per byte and a header row.
+                CapacityOverflow {
+                    resource: CircuitResource::Bytecode,
+                    used: code.to_vec().len() + 1,
+                    capacity: code.to_vec().len(),
+                },
+            ]
+        );
+    }
+}
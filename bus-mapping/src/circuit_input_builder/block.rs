@@ -1,13 +1,22 @@
 //! Block-related utility module
 
-use super::{transaction::Transaction, CopyEvent};
+use super::{transaction::Transaction, CircuitsParams, CopyEvent, ExpEvent};
 use crate::{
     operation::{OperationContainer, RWCounter},
+    rpc::{BatchJsonRpcClient, GethClient},
     Error,
 };
-use eth_types::{Address, Hash, Word};
+use eth_types::{
+    evm_types::Hardfork, geth_types::ChainConfig, Address, EIP1186ProofResponse, Hash, ToBigEndian,
+    ToWord, TransactionBlobs, Withdrawal, Word,
+};
+use ethers_providers::ProviderError;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Number of most recent block hashes available to the BLOCKHASH opcode.
+const NUM_HISTORY_HASHES: u64 = 256;
+
 /// Context of a [`Block`] which can mutate in a [`Transaction`].
 #[derive(Debug)]
 pub struct BlockContext {
@@ -40,10 +49,12 @@ impl BlockContext {
 }
 
 /// Circuit Input related to a block.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Block {
     /// chain id
     pub chain_id: Word,
+    /// Hardfork activation of the chain
+    pub chain_config: ChainConfig,
     /// history hashes contains most recent 256 block hashes in history, where
     /// the lastest one is at history_hashes[history_hashes.len() - 1].
     pub history_hashes: Vec<Word>,
@@ -59,12 +70,39 @@ pub struct Block {
     pub difficulty: Word,
     /// base fee
     pub base_fee: Word,
+    /// State root of the parent block, being the state root before the block.
+    pub prev_state_root: Hash,
+    /// State root after the block.
+    pub state_root: Hash,
+    /// Hash of the block.
+    pub hash: Hash,
+    /// Proofs of the accounts and the storage slots accessed by the block, at
+    /// the state root of the parent block.
+    pub state_proofs: Vec<EIP1186ProofResponse>,
     /// Container of operations done in this block.
     pub container: OperationContainer,
     /// Transactions contained in the block
     pub txs: Vec<Transaction>,
+    /// Withdrawals of the block, credited to their recipients after the
+    /// transactions.
+    pub withdrawals: Vec<Withdrawal>,
+    /// Blob gas used by the transactions of the block (EIP-4844).
+    pub blob_gas_used: u64,
+    /// Blob gas in excess of the target accumulated before the block
+    /// (EIP-4844).
+    pub excess_blob_gas: u64,
+    /// Blob fields of the transactions of the block (EIP-4844), indexed by
+    /// their position in the block, which are empty before Cancun.
+    pub tx_blobs: Vec<TransactionBlobs>,
     /// Copy events in this block.
     pub copy_events: Vec<CopyEvent>,
+    /// Exponentiation events in this block.
+    pub exp_events: Vec<ExpEvent>,
+    /// Inputs of all the keccak hashes computed in this block, which are
+    /// proven by the keccak circuit.
+    pub sha3_inputs: Vec<Vec<u8>>,
+    /// Capacities of the circuits proving this block.
+    pub circuits_params: CircuitsParams,
     code: HashMap<Hash, Vec<u8>>,
 }
 
@@ -74,6 +112,7 @@ impl Block {
         chain_id: Word,
         history_hashes: Vec<Word>,
         eth_block: &eth_types::Block<TX>,
+        circuits_params: CircuitsParams,
     ) -> Result<Self, Error> {
         if eth_block.base_fee_per_gas.is_none() {
             // FIXME: resolve this once we have proper EIP-1559 support
@@ -83,6 +122,10 @@ impl Block {
         }
 
         Ok(Self {
+            chain_config: ChainConfig {
+                chain_id: chain_id.low_u64(),
+                ..ChainConfig::default()
+            },
             chain_id,
             history_hashes,
             coinbase: eth_block.author,
@@ -95,13 +138,129 @@ impl Block {
             timestamp: eth_block.timestamp,
             difficulty: eth_block.difficulty,
             base_fee: eth_block.base_fee_per_gas.unwrap_or_default(),
+            prev_state_root: Hash::zero(),
+            state_root: eth_block.state_root,
+            hash: eth_block.hash.unwrap_or_default(),
+            state_proofs: Vec::new(),
             container: OperationContainer::new(),
             txs: Vec::new(),
+            withdrawals: Vec::new(),
+            blob_gas_used: 0,
+            excess_blob_gas: 0,
+            tx_blobs: Vec::new(),
             copy_events: Vec::new(),
+            exp_events: Vec::new(),
+            sha3_inputs: Vec::new(),
+            circuits_params,
             code: HashMap::new(),
         })
     }
 
+    /// Create a new block from `eth_block` of the chain of `chain_config`,
+    /// querying geth for the hashes of the most recent 256 blocks preceding
+    /// it, the state root of its parent block, its withdrawals and its blob
+    /// fields.
+    pub async fn from_rpc<P: BatchJsonRpcClient, TX>(
+        cli: &GethClient<P>,
+        chain_config: ChainConfig,
+        eth_block: &eth_types::Block<TX>,
+        circuits_params: CircuitsParams,
+    ) -> Result<Self, Error> {
+        let block_num = eth_block
+            .number
+            .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?
+            .as_u64();
+        if chain_config.is_london(block_num) && eth_block.base_fee_per_gas.is_none() {
+            return Err(Error::EthTypeError(eth_types::Error::IncompleteBlock));
+        }
+
+        // The headers of the history are fetched by number in batches, so
+        // check that they are the ancestors of the block.
+        let history = cli
+            .get_history(block_num, NUM_HISTORY_HASHES.min(block_num))
+            .await?;
+        let mut parent_hash = eth_block.parent_hash;
+        for header in history.iter().rev() {
+            if header.hash != Some(parent_hash) {
+                return Err(Error::JSONRpcError(ProviderError::CustomError(format!(
+                    "block {:?} isn't the parent {:?} of the next block",
+                    header.number, parent_hash
+                ))));
+            }
+            parent_hash = header.parent_hash;
+        }
+        let prev_state_root = history
+            .last()
+            .map(|header| header.state_root)
+            .unwrap_or_default();
+        let history_hashes = history
+            .iter()
+            .map(|header| header.hash.unwrap_or_default().to_word())
+            .collect();
+
+        let mut block = Self::new(
+            chain_config.chain_id.into(),
+            history_hashes,
+            eth_block,
+            circuits_params,
+        )?;
+        block.prev_state_root = prev_state_root;
+        // After the merge, the DIFFICULTY opcode returns the prevrandao of the
+        // block, found in its mix hash (EIP-4399).
+        if chain_config.terminal_total_difficulty_passed {
+            block.difficulty = eth_block.mix_hash.unwrap_or_default().to_word();
+        }
+        if chain_config.is_shanghai(eth_block.timestamp.low_u64()) {
+            block.withdrawals = cli.get_withdrawals_by_number(block_num.into()).await?;
+        }
+        if chain_config.is_cancun(eth_block.timestamp.low_u64()) {
+            let blobs = cli.get_blobs_by_number(block_num.into()).await?;
+            block.blob_gas_used = blobs.blob_gas_used.as_u64();
+            block.excess_blob_gas = blobs.excess_blob_gas.as_u64();
+            block.tx_blobs = blobs.transactions;
+        }
+        block.chain_config = chain_config;
+        Ok(block)
+    }
+
+    /// Return a copy of the constants of the block, without the state proofs
+    /// nor anything generated by handling its transactions, on which a
+    /// transaction is handled on its own.
+    pub(crate) fn without_txs(&self) -> Self {
+        Self {
+            chain_id: self.chain_id,
+            chain_config: self.chain_config.clone(),
+            history_hashes: self.history_hashes.clone(),
+            coinbase: self.coinbase,
+            gas_limit: self.gas_limit,
+            number: self.number,
+            timestamp: self.timestamp,
+            difficulty: self.difficulty,
+            base_fee: self.base_fee,
+            prev_state_root: self.prev_state_root,
+            state_root: self.state_root,
+            hash: self.hash,
+            state_proofs: Vec::new(),
+            container: OperationContainer::new(),
+            txs: Vec::new(),
+            withdrawals: self.withdrawals.clone(),
+            blob_gas_used: self.blob_gas_used,
+            excess_blob_gas: self.excess_blob_gas,
+            tx_blobs: self.tx_blobs.clone(),
+            copy_events: Vec::new(),
+            exp_events: Vec::new(),
+            sha3_inputs: Vec::new(),
+            circuits_params: self.circuits_params,
+            code: HashMap::new(),
+        }
+    }
+
+    /// Return the hardfork of the EVM rules followed by this block.
+    pub fn hardfork(&self) -> Hardfork {
+        self.chain_config
+            .hardfork(self.number.low_u64(), self.timestamp.low_u64())
+    }
+
     /// Return the list of transactions of this block.
     pub fn txs(&self) -> &[Transaction] {
         &self.txs
@@ -118,4 +277,30 @@ impl Block {
     pub fn add_copy_event(&mut self, copy: CopyEvent) {
         self.copy_events.push(copy);
     }
+
+    /// Push an exponentiation event to the block.
+    pub fn add_exp_event(&mut self, event: ExpEvent) {
+        self.exp_events.push(event);
+    }
+
+    /// Push the input of a keccak hash to the block.
+    pub fn add_sha3_input(&mut self, input: Vec<u8>) {
+        self.sha3_inputs.push(input);
+    }
+
+    /// Set the proofs of the accounts and the storage slots accessed by the
+    /// block, whose trie nodes and hashed keys are inputs of keccak hashes.
+    pub fn set_state_proofs(&mut self, state_proofs: Vec<EIP1186ProofResponse>) {
+        for proof in state_proofs.iter() {
+            self.add_sha3_input(proof.address.as_bytes().to_vec());
+            self.sha3_inputs
+                .extend(proof.account_proof.iter().map(|node| node.to_vec()));
+            for storage_proof in proof.storage_proof.iter() {
+                self.add_sha3_input(storage_proof.key.to_be_bytes().to_vec());
+                self.sha3_inputs
+                    .extend(storage_proof.proof.iter().map(|node| node.to_vec()));
+            }
+        }
+        self.state_proofs = state_proofs;
+    }
 }
@@ -1,9 +1,10 @@
 use super::CodeSource;
 use crate::{exec_trace::OperationRef, Error};
 use eth_types::{evm_types::OpcodeId, Address, Hash, Word};
+use serde::{Deserialize, Serialize};
 
 /// Type of a *CALL*/CREATE* Function.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CallKind {
     /// CALL
     Call,
@@ -48,7 +49,7 @@ impl TryFrom<OpcodeId> for CallKind {
 }
 
 /// Circuit Input related to an Ethereum Call
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Call {
     /// Unique call identifier within the Block.
     pub call_id: usize,
@@ -108,6 +109,14 @@ pub struct CallContext {
     /// Call data (copy of tx input or caller's
     /// memory[call_data_offset..call_data_offset + call_data_length])
     pub call_data: Vec<u8>,
+    /// Return data of the last callee, which is the callee's
+    /// memory[last_callee_return_data_offset..last_callee_return_data_offset +
+    /// return_data.len()], or empty if the last callee didn't return any.
+    pub return_data: Vec<u8>,
+    /// Call id of the last callee
+    pub last_callee_id: usize,
+    /// Offset of the return data in the memory of the last callee
+    pub last_callee_return_data_offset: u64,
 }
 
 /// A reversion group is the collection of calls and the operations which are
@@ -3,14 +3,15 @@
 use crate::{error::ExecError, exec_trace::OperationRef, operation::RWCounter, operation::RW};
 use eth_types::{
     evm_types::{Gas, GasCost, OpcodeId, ProgramCounter},
-    GethExecStep, H256,
+    GethExecStep, Word, H256,
 };
 use gadgets::impl_expr;
 use halo2_proofs::{arithmetic::FieldExt, plonk::Expression};
+use serde::{Deserialize, Serialize};
 use strum_macros::EnumIter;
 
 /// An execution step of the EVM.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExecStep {
     /// Execution state
     pub exec_state: ExecState,
@@ -91,7 +92,7 @@ impl Default for ExecStep {
 }
 
 /// Execution state
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ExecState {
     /// EVM Opcode ID
     Op(OpcodeId),
@@ -140,7 +141,7 @@ impl ExecState {
 }
 
 /// Defines the various source/destination types for a copy event.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, Serialize, Deserialize)]
 pub enum CopyDataType {
     /// When the source for the copy event is the bytecode table.
     Bytecode = 1,
@@ -150,6 +151,10 @@ pub enum CopyDataType {
     TxCalldata,
     /// When the destination for the copy event is tx's log.
     TxLog,
+    /// When the destination for the copy event is the accumulated random
+    /// linear combination of the copied bytes, used by SHA3 and CREATE2 to
+    /// look up the keccak table.
+    RlcAcc,
 }
 
 impl From<CopyDataType> for usize {
@@ -168,7 +173,7 @@ impl_expr!(CopyDataType);
 
 /// Defines a single copy step in a copy event. This type is unified over the
 /// source/destination row in the copy table.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CopyStep {
     /// Address (source/destination) for the copy step.
     pub addr: u64,
@@ -191,7 +196,7 @@ pub struct CopyStep {
 }
 
 /// Defines an enum type that can hold either a number or a hash value.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum NumberOrHash {
     /// Variant to indicate a number value.
     Number(usize),
@@ -202,7 +207,7 @@ pub enum NumberOrHash {
 /// Defines a copy event associated with EVM opcodes such as CALLDATACOPY,
 /// CODECOPY, CREATE, etc. More information:
 /// https://github.com/privacy-scaling-explorations/zkevm-specs/blob/master/specs/copy-proof.md.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CopyEvent {
     /// Represents the start address at the source of the copy event.
     pub src_addr: u64,
@@ -232,3 +237,87 @@ pub struct CopyEvent {
     /// Helper field for witness generation.
     pub pc: ProgramCounter,
 }
+
+/// Defines an exponentiation event associated with the EXP opcode, holding
+/// the intermediate steps of computing `base^exponent (mod 2^256)` by
+/// square-and-multiply.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpEvent {
+    /// Identifier of the exponentiation, which is the RW counter of the EXP
+    /// step that triggered it.
+    pub identifier: usize,
+    /// The base of the exponentiation.
+    pub base: Word,
+    /// The exponent of the exponentiation.
+    pub exponent: Word,
+    /// The result of the exponentiation.
+    pub exponentiation: Word,
+    /// Intermediate steps, starting from the full exponent and ending with
+    /// the exponent 1.
+    pub steps: Vec<ExpStep>,
+}
+
+impl ExpEvent {
+    /// Create the exponentiation event of `base^exponent` for a non-zero
+    /// exponent.
+    pub fn new(identifier: usize, base: Word, exponent: Word) -> Self {
+        assert!(!exponent.is_zero(), "exponent must be non-zero");
+
+        // Walk down from the exponent, halving it when even and decrementing
+        // it when odd, until reaching the exponent 1.
+        let mut exponents = Vec::new();
+        let mut e = exponent;
+        while e > Word::one() {
+            exponents.push(e);
+            e = if e.low_u64() & 1 == 1 {
+                e - Word::one()
+            } else {
+                e >> 1
+            };
+        }
+
+        // Then compute the intermediate exponentiations from the bottom up.
+        let mut steps = vec![ExpStep {
+            exponent: Word::one(),
+            a: base,
+            b: Word::one(),
+            d: base,
+        }];
+        for e in exponents.into_iter().rev() {
+            let prev = steps.last().unwrap().d;
+            let b = if e.low_u64() & 1 == 1 { base } else { prev };
+            steps.push(ExpStep {
+                exponent: e,
+                a: prev,
+                b,
+                d: prev.overflowing_mul(b).0,
+            });
+        }
+        steps.reverse();
+
+        Self {
+            identifier,
+            base,
+            exponent,
+            exponentiation: steps[0].d,
+            steps,
+        }
+    }
+}
+
+/// Defines a single step of an exponentiation event, where `a * b == d (mod
+/// 2^256)` and `d == base^exponent (mod 2^256)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpStep {
+    /// The exponent at this step.
+    pub exponent: Word,
+    /// The multiplicand, which is the exponentiation of the next step, or the
+    /// base at the last step.
+    pub a: Word,
+    /// The multiplier, which is the base when the exponent is odd and the
+    /// exponentiation of the next step when it is even, or 1 at the last
+    /// step.
+    pub b: Word,
+    /// The exponentiation at this step.
+    pub d: Word,
+}
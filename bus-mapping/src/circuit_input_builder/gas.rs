@@ -0,0 +1,291 @@
+//! Gas costs of the execution steps computed from the operations generated by
+//! the builder, which are cross-checked against the geth trace.
+
+use super::ExecStep;
+use crate::{
+    operation::{AccountField, Operation, OperationContainer, Target},
+    Error,
+};
+use eth_types::{
+    evm_types::{
+        gas_utils::{eip150_gas, memory_expansion_gas_cost},
+        GasCost, Hardfork, OpcodeId,
+    },
+    GethExecStep, ToWord, Word, H256,
+};
+use keccak256::EMPTY_HASH;
+
+/// Return the gas cost of the successful step `exec_step` of `geth_step`, from
+/// the constant gas of its opcode, the expansion of the memory, the warm and
+/// cold accesses of the access list written by the builder and the dynamic
+/// gas of the calls, or `None` for the steps whose cost isn't modelled: the
+/// failed steps and the opcodes whose access list or account operations
+/// aren't generated.  The cost of a *CALL* includes the gas passed to the
+/// callee, as in the geth trace.
+pub(crate) fn expected_gas_cost(
+    container: &OperationContainer,
+    hardfork: Hardfork,
+    geth_step: &GethExecStep,
+    exec_step: &ExecStep,
+) -> Result<Option<GasCost>, Error> {
+    if exec_step.error.is_some() {
+        return Ok(None);
+    }
+    let op = geth_step.op;
+    let stack = &geth_step.stack;
+    let curr_memory_word_size = geth_step.memory.word_size() as u64;
+    let memory_expansion = |accesses: &[(Word, Word)]| -> Option<u64> {
+        let mut next_memory_word_size = curr_memory_word_size;
+        for (offset, length) in accesses.iter().filter(|(_, length)| !length.is_zero()) {
+            // A successful step can't expand the memory beyond 64 bits.
+            let end = offset.checked_add(*length).filter(|end| end.bits() <= 64)?;
+            next_memory_word_size = next_memory_word_size.max((end.low_u64() + 31) / 32);
+        }
+        Some(memory_expansion_gas_cost(
+            curr_memory_word_size,
+            next_memory_word_size,
+        ))
+    };
+    let words = |length: Word| (length.low_u64() + 31) / 32;
+    // The constant gas of the opcodes accessing an account is the warm
+    // access, so only the surplus of a cold access is dynamic.
+    let account_access = || {
+        step_ops(
+            exec_step,
+            Target::TxAccessListAccount,
+            &container.tx_access_list_account,
+        )
+        .next()
+        .map(|op| {
+            access_cost(op.is_warm_prev, GasCost::COLD_ACCOUNT_ACCESS)
+                - GasCost::WARM_ACCESS.as_u64()
+        })
+    };
+
+    let dynamic_gas = match op {
+        OpcodeId::MLOAD | OpcodeId::MSTORE => memory_expansion(&[(stack.last()?, 32.into())]),
+        OpcodeId::MSTORE8 => memory_expansion(&[(stack.last()?, 1.into())]),
+        OpcodeId::SHA3 => {
+            let length = stack.nth_last(1)?;
+            memory_expansion(&[(stack.last()?, length)])
+                .map(|gas| gas + GasCost::COPY_SHA3.as_u64() * words(length))
+        }
+        OpcodeId::CALLDATACOPY | OpcodeId::CODECOPY | OpcodeId::RETURNDATACOPY => {
+            let length = stack.nth_last(2)?;
+            memory_expansion(&[(stack.last()?, length)])
+                .map(|gas| gas + GasCost::COPY.as_u64() * words(length))
+        }
+        OpcodeId::EXP => {
+            let exponent_byte_size = (stack.nth_last(1)?.bits() as u64 + 7) / 8;
+            Some(GasCost::EXP_BYTE_TIMES.as_u64() * exponent_byte_size)
+        }
+        OpcodeId::LOG0 | OpcodeId::LOG1 | OpcodeId::LOG2 | OpcodeId::LOG3 | OpcodeId::LOG4 => {
+            let num_topics = op.as_u64() - OpcodeId::LOG0.as_u64();
+            let length = stack.nth_last(1)?;
+            memory_expansion(&[(stack.last()?, length)])
+                .map(|gas| gas + GasCost::LOG.as_u64() * (1 + num_topics) + 8 * length.low_u64())
+        }
+        OpcodeId::RETURN | OpcodeId::REVERT => {
+            memory_expansion(&[(stack.last()?, stack.nth_last(1)?)])
+        }
+        OpcodeId::SLOAD => step_ops(
+            exec_step,
+            Target::TxAccessListAccountStorage,
+            &container.tx_access_list_account_storage,
+        )
+        .next()
+        .map(|op| access_cost(op.is_warm_prev, GasCost::COLD_SLOAD)),
+        OpcodeId::SSTORE => {
+            let is_warm_prev = step_ops(
+                exec_step,
+                Target::TxAccessListAccountStorage,
+                &container.tx_access_list_account_storage,
+            )
+            .next()
+            .map(|op| op.is_warm_prev);
+            let storage_op = step_ops(exec_step, Target::Storage, &container.storage).next();
+            is_warm_prev.zip(storage_op).map(|(is_warm_prev, op)| {
+                let cold_cost = if is_warm_prev {
+                    0
+                } else {
+                    GasCost::COLD_SLOAD.as_u64()
+                };
+                cold_cost
+                    + if op.value == op.value_prev || op.committed_value != op.value_prev {
+                        GasCost::WARM_ACCESS.as_u64()
+                    } else if op.committed_value.is_zero() {
+                        GasCost::SSTORE_SET.as_u64()
+                    } else {
+                        GasCost::SSTORE_RESET.as_u64()
+                    }
+            })
+        }
+        OpcodeId::BALANCE | OpcodeId::EXTCODESIZE | OpcodeId::EXTCODEHASH => account_access(),
+        OpcodeId::EXTCODECOPY => {
+            let length = stack.nth_last(3)?;
+            account_access()
+                .zip(memory_expansion(&[(stack.nth_last(1)?, length)]))
+                .map(|(access, expansion)| {
+                    access + expansion + GasCost::COPY.as_u64() * words(length)
+                })
+        }
+        OpcodeId::CREATE | OpcodeId::CREATE2 => {
+            let length = stack.nth_last(2)?;
+            let hash_cost = if op == OpcodeId::CREATE2 {
+                GasCost::COPY_SHA3.as_u64() * words(length)
+            } else {
+                0
+            };
+            // The init code is charged per word from Shanghai (EIP-3860).
+            let init_code_cost = if hardfork >= Hardfork::Shanghai {
+                2 * words(length)
+            } else {
+                0
+            };
+            memory_expansion(&[(stack.nth_last(1)?, length)])
+                .map(|gas| gas + hash_cost + init_code_cost)
+        }
+        OpcodeId::CALL | OpcodeId::CALLCODE | OpcodeId::DELEGATECALL | OpcodeId::STATICCALL => {
+            let has_value = matches!(op, OpcodeId::CALL | OpcodeId::CALLCODE);
+            let value = if has_value {
+                stack.nth_last(2)?
+            } else {
+                Word::zero()
+            };
+            let args_offset = if has_value { 3 } else { 2 };
+            let memory_accesses = [
+                (
+                    stack.nth_last(args_offset)?,
+                    stack.nth_last(args_offset + 1)?,
+                ),
+                (
+                    stack.nth_last(args_offset + 2)?,
+                    stack.nth_last(args_offset + 3)?,
+                ),
+            ];
+            let gas_specified = stack.last()?;
+            let callee = stack.nth_last(1)?;
+            let new_account_cost = if op == OpcodeId::CALL && !value.is_zero() {
+                is_account_empty(container, exec_step, callee).map(|is_empty| {
+                    if is_empty {
+                        GasCost::NEW_ACCOUNT.as_u64()
+                    } else {
+                        0
+                    }
+                })
+            } else {
+                Some(0)
+            };
+            let value_cost = if value.is_zero() {
+                0
+            } else {
+                GasCost::CALL_WITH_VALUE.as_u64()
+            };
+            account_access()
+                .zip(memory_expansion(&memory_accesses))
+                .zip(new_account_cost)
+                .map(|((access, expansion), new_account_cost)| {
+                    let gas = access + expansion + value_cost + new_account_cost;
+                    let gas_left = geth_step
+                        .gas
+                        .0
+                        .saturating_sub(op.constant_gas_cost().as_u64() + gas);
+                    gas + eip150_gas(gas_left, gas_specified)
+                })
+        }
+        OpcodeId::SELFDESTRUCT => {
+            let beneficiary = stack.last()?;
+            let (sender_balance, beneficiary_balance) =
+                step_ops(exec_step, Target::Account, &container.account)
+                    .filter(|op| op.field == AccountField::Balance)
+                    .fold((None, None), |(sender, beneficiary_balance), op| {
+                        if op.address.to_word() == beneficiary {
+                            (sender, Some(op.value_prev))
+                        } else {
+                            (Some(op.value_prev), beneficiary_balance)
+                        }
+                    });
+            // The beneficiary is a new account when it has no balance, nonce
+            // nor code, which can't be known from the operations of the step.
+            let new_account_cost = match (sender_balance, beneficiary_balance) {
+                (Some(sender_balance), Some(beneficiary_balance))
+                    if !sender_balance.is_zero() && beneficiary_balance.is_zero() =>
+                {
+                    None
+                }
+                _ => Some(0),
+            };
+            // Only the cold access to the beneficiary is charged.
+            step_ops(
+                exec_step,
+                Target::TxAccessListAccount,
+                &container.tx_access_list_account,
+            )
+            .next()
+            .zip(new_account_cost)
+            .map(|(op, new_account_cost)| {
+                let cold_cost = if op.is_warm_prev {
+                    0
+                } else {
+                    GasCost::COLD_ACCOUNT_ACCESS.as_u64()
+                };
+                cold_cost + new_account_cost
+            })
+        }
+        _ => Some(0),
+    };
+
+    Ok(dynamic_gas.map(|gas| GasCost(op.constant_gas_cost().as_u64() + gas)))
+}
+
+/// Return the cost of an access to the access list, with `cold_cost` when the
+/// accessed account or storage slot isn't warm.
+fn access_cost(is_warm_prev: bool, cold_cost: GasCost) -> u64 {
+    if is_warm_prev {
+        GasCost::WARM_ACCESS.as_u64()
+    } else {
+        cold_cost.as_u64()
+    }
+}
+
+/// Return the operations of `target` in `ops` generated by `exec_step`.
+fn step_ops<'a, T: crate::operation::Op + 'a>(
+    exec_step: &'a ExecStep,
+    target: Target,
+    ops: &'a [Operation<T>],
+) -> impl Iterator<Item = &'a T> + 'a {
+    exec_step
+        .bus_mapping_instance
+        .iter()
+        .filter(move |op_ref| op_ref.target() == target)
+        .map(move |op_ref| ops[op_ref.as_usize()].op())
+}
+
+/// Return whether the account `address` called by `exec_step` is empty
+/// before the call, from the balance written by the transfer and the nonce
+/// and code hash read from the callee, or `None` if they aren't generated.
+fn is_account_empty(
+    container: &OperationContainer,
+    exec_step: &ExecStep,
+    address: Word,
+) -> Option<bool> {
+    let mut balance = None;
+    let mut nonce = None;
+    let mut code_hash = None;
+    for op in step_ops(exec_step, Target::Account, &container.account)
+        .filter(|op| op.address.to_word() == address)
+    {
+        match op.field {
+            AccountField::Balance => balance = balance.or(Some(op.value_prev)),
+            AccountField::Nonce => nonce = Some(op.value),
+            AccountField::CodeHash => code_hash = Some(op.value),
+            _ => (),
+        }
+    }
+    let empty_code_hash = H256(*EMPTY_HASH).to_word();
+    Some(
+        balance?.is_zero()
+            && nonce?.is_zero()
+            && (code_hash?.is_zero() || code_hash? == empty_code_hash),
+    )
+}
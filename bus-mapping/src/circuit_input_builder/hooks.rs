@@ -0,0 +1,129 @@
+//! Hooks customizing the circuit inputs built for a rollup.
+
+use super::{Block, CircuitInputBuilder, EthBlock};
+use crate::Error;
+use core::fmt::Debug;
+use eth_types::{Address, GethExecTrace, Word};
+
+/// Points where the rollup specific behaviour of the chain is inserted into
+/// the building of the circuit inputs, so that the forks for a rollup don't
+/// need to patch the builder.  Every hook defaults to the behaviour of
+/// Ethereum.
+pub trait BuilderHooks: Debug + Send + Sync {
+    /// Set the fields of `block` that are specific to the rollup, once it's
+    /// built from `eth_block`.
+    fn set_block_fields(&self, _block: &mut Block, _eth_block: &EthBlock) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Return the L1 data fee charged to the sender of `eth_tx` on top of its
+    /// gas fee, with the address it's credited to.
+    fn l1_fee(&self, _eth_tx: &eth_types::Transaction) -> Option<(Word, Address)> {
+        None
+    }
+
+    /// Handle `eth_tx` when it's a system transaction of the rollup, in place
+    /// of the handling of a user transaction, returning whether it has been
+    /// handled.
+    fn handle_system_tx(
+        &self,
+        _builder: &mut CircuitInputBuilder,
+        _eth_tx: &eth_types::Transaction,
+        _geth_trace: &GethExecTrace,
+    ) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+/// Hooks of Ethereum, which change nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultHooks;
+
+impl BuilderHooks for DefaultHooks {}
+
+#[cfg(test)]
+mod hooks_tests {
+    use super::*;
+    use crate::{
+        circuit_input_builder::ExecState,
+        mock::BlockData,
+        operation::{AccountField, AccountOp, RW},
+    };
+    use eth_types::{bytecode, geth_types::GethData};
+    use mock::test_ctx::TestContext;
+    use pretty_assertions::assert_eq;
+    use std::sync::Arc;
+
+    /// Hooks of a rollup charging an L1 data fee of 1 Gwei to every sender,
+    /// credited to the coinbase.
+    #[derive(Debug)]
+    struct L1FeeHooks {
+        coinbase: Address,
+    }
+
+    impl BuilderHooks for L1FeeHooks {
+        fn l1_fee(&self, _eth_tx: &eth_types::Transaction) -> Option<(Word, Address)> {
+            Some((Word::exp10(9), self.coinbase))
+        }
+    }
+
+    #[test]
+    fn l1_fee_charged_before_tx() {
+        let block: GethData = TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode! {
+            STOP
+        })
+        .unwrap()
+        .into();
+
+        let builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        let sender = block.eth_block.transactions[0].from;
+        let coinbase = block.eth_block.author;
+        let (_, sender_account) = builder.sdb.get_account(&sender);
+        let sender_balance = sender_account.balance;
+        let (_, coinbase_account) = builder.sdb.get_account(&coinbase);
+        let coinbase_balance = coinbase_account.balance;
+
+        let mut builder = CircuitInputBuilder::new_with_hooks(
+            builder.sdb,
+            builder.code_db,
+            builder.block,
+            Arc::new(L1FeeHooks { coinbase }),
+        );
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let fee = Word::exp10(9);
+        let balance_ops = builder.block.container.account[..2]
+            .iter()
+            .map(|operation| (operation.rw(), operation.op().clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            balance_ops,
+            vec![
+                (
+                    RW::WRITE,
+                    AccountOp::new(
+                        sender,
+                        AccountField::Balance,
+                        sender_balance - fee,
+                        sender_balance
+                    )
+                ),
+                (
+                    RW::WRITE,
+                    AccountOp::new(
+                        coinbase,
+                        AccountField::Balance,
+                        coinbase_balance + fee,
+                        coinbase_balance
+                    )
+                ),
+            ]
+        );
+        // The fee isn't part of the execution of the transaction.
+        let begin_tx_step = &builder.block.txs()[0].steps()[0];
+        assert_eq!(begin_tx_step.exec_state, ExecState::BeginTx);
+        assert_eq!(begin_tx_step.rwc.0, 3);
+    }
+}
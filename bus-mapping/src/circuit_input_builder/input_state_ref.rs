@@ -2,22 +2,24 @@
 
 use super::{
     get_call_memory_offset_length, get_create_init_code, Block, BlockContext, Call, CallContext,
-    CallKind, CodeSource, CopyEvent, ExecState, ExecStep, Transaction, TransactionContext,
+    CallKind, CodeSource, CopyEvent, ExecState, ExecStep, ExpEvent, Transaction,
+    TransactionContext,
 };
 use crate::{
     error::{get_step_reported_error, ExecError},
     exec_trace::OperationRef,
     operation::{
         AccountField, AccountOp, CallContextField, CallContextOp, MemoryOp, Op, OpEnum, Operation,
-        StackOp, Target, TxAccessListAccountOp, TxLogField, TxLogOp, TxReceiptField, TxReceiptOp,
-        RW,
+        StackOp, Target, TxAccessListAccountOp, TxAccessListAccountStorageOp, TxLogField, TxLogOp,
+        TxReceiptField, TxReceiptOp, RW,
     },
+    precompile::PrecompileCalls,
     state_db::{CodeDB, StateDB},
     Error,
 };
 use eth_types::{
     evm_types::{Gas, MemoryAddress, OpcodeId, StackAddress},
-    Address, GethExecStep, ToAddress, ToBigEndian, Word, H256,
+    Address, GethExecStep, ToAddress, ToBigEndian, ToWord, Word, H256,
 };
 use ethers_core::utils::{get_contract_address, get_create2_address};
 
@@ -397,6 +399,35 @@ impl<'a> CircuitInputStateRef<'a> {
         Ok(())
     }
 
+    /// Push a write type [`TxAccessListAccountStorageOp`] into the
+    /// [`OperationContainer`](crate::operation::OperationContainer) with the
+    /// next [`RWCounter`](crate::operation::RWCounter), and then
+    /// adds a reference to the stored operation ([`OperationRef`]) inside
+    /// the bus-mapping instance of the current [`ExecStep`].  Then increase
+    /// the `block_ctx` [`RWCounter`](crate::operation::RWCounter)  by one.
+    pub fn tx_accesslist_account_storage_write(
+        &mut self,
+        step: &mut ExecStep,
+        tx_id: usize,
+        address: Address,
+        key: Word,
+        is_warm: bool,
+        is_warm_prev: bool,
+    ) -> Result<(), Error> {
+        self.push_op(
+            step,
+            RW::WRITE,
+            TxAccessListAccountStorageOp {
+                tx_id,
+                address,
+                key,
+                is_warm,
+                is_warm_prev,
+            },
+        );
+        Ok(())
+    }
+
     /// Push 2 reversible [`AccountOp`] to update `sender` and `receiver`'s
     /// balance by `value`, with `sender` being extraly charged with `fee`.
     pub fn transfer_with_fee(
@@ -547,7 +578,7 @@ impl<'a> CircuitInputStateRef<'a> {
 
     /// Check if address is a precompiled or not.
     pub fn is_precompiled(&self, address: &Address) -> bool {
-        address.0[0..19] == [0u8; 19] && (1..=9).contains(&address.0[19])
+        PrecompileCalls::from_address(address).is_some()
     }
 
     // TODO: Remove unwrap() and add err handling.
@@ -736,7 +767,14 @@ impl<'a> CircuitInputStateRef<'a> {
             OpEnum::TxRefund(op) => {
                 self.sdb.set_refund(op.value);
             }
-            OpEnum::AccountDestructed(_) => unimplemented!(),
+            OpEnum::AccountDestructed(op) => {
+                if !op.is_destructed_prev && op.is_destructed {
+                    self.sdb.destruct_account(op.address);
+                }
+                if op.is_destructed_prev && !op.is_destructed {
+                    self.sdb.revert_destruct_account(&op.address);
+                }
+            }
             _ => unreachable!(),
         };
     }
@@ -773,33 +811,108 @@ impl<'a> CircuitInputStateRef<'a> {
         }
     }
 
+    /// Generate the operations that restore the caller's context when the
+    /// current internal call halts, which corresponds to
+    /// `Instruction.step_state_transition_to_restored_context` in the python
+    /// spec.  Nothing is generated for a root call.
+    pub(crate) fn gen_restore_context_ops(
+        &mut self,
+        exec_step: &mut ExecStep,
+        geth_steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let call = self.call()?.clone();
+        if call.is_root {
+            return Ok(());
+        }
+
+        let caller = self.caller()?.clone();
+        self.call_context_read(
+            exec_step,
+            call.call_id,
+            CallContextField::CallerId,
+            caller.call_id.into(),
+        );
+
+        let geth_step = &geth_steps[0];
+        let geth_step_next = &geth_steps[1];
+        // The gas left of the callee is only returned to the caller when it
+        // halts without an error.
+        let caller_gas_left = if exec_step.error.is_none() {
+            geth_step_next.gas.0 - geth_step.gas.0
+        } else {
+            geth_step_next.gas.0
+        };
+        for (field, value) in [
+            (CallContextField::IsRoot, (caller.is_root as u64).into()),
+            (
+                CallContextField::IsCreate,
+                (caller.is_create() as u64).into(),
+            ),
+            (CallContextField::CodeHash, caller.code_hash.to_word()),
+            (CallContextField::ProgramCounter, geth_step_next.pc.0.into()),
+            (
+                CallContextField::StackPointer,
+                geth_step_next.stack.stack_pointer().0.into(),
+            ),
+            (CallContextField::GasLeft, caller_gas_left.into()),
+            (
+                CallContextField::MemorySize,
+                geth_step_next.memory.word_size().into(),
+            ),
+            (
+                CallContextField::ReversibleWriteCounter,
+                self.caller_ctx()?.reversible_write_counter.into(),
+            ),
+        ] {
+            self.call_context_read(exec_step, caller.call_id, field, value);
+        }
+
+        for (field, value) in [
+            (CallContextField::LastCalleeId, call.call_id.into()),
+            (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+            (CallContextField::LastCalleeReturnDataLength, 0.into()),
+        ] {
+            self.call_context_write(exec_step, caller.call_id, field, value);
+        }
+
+        Ok(())
+    }
+
     /// Handle a return step caused by any opcode that causes a return to the
     /// previous call context.
     pub fn handle_return(&mut self, step: &GethExecStep) -> Result<(), Error> {
         let call = self.call()?.clone();
 
-        // Store deployed code if it's a successful create
-        if call.is_create() && call.is_success {
-            let offset = step.stack.nth_last(0)?;
-            let length = step.stack.nth_last(1)?;
-            let code = step
-                .memory
-                .read_chunk(offset.low_u64().into(), length.low_u64().into());
-            let code_hash = self.code_db.insert(code);
-            let (found, callee_account) = self.sdb.get_account_mut(&call.address);
-            if !found {
-                return Err(Error::AccountNotFound(call.address));
-            }
-            callee_account.code_hash = code_hash;
-        }
-
         // Handle reversion if this call doens't end successfully
         if !self.call()?.is_success {
             self.handle_reversion();
         }
 
+        // Return data is only kept for RETURN and REVERT, except for a
+        // successful create whose returned bytes become the deployed code.
+        // Nothing is returned when the RETURN or REVERT itself fails with an
+        // error, and a failed RETURN can only be caused by an error.
+        let is_halted_by_error =
+            step.error.is_some() || (step.op == OpcodeId::RETURN && !call.is_success);
+        let (return_data_offset, return_data) = match step.op {
+            OpcodeId::RETURN | OpcodeId::REVERT
+                if !is_halted_by_error && !(call.is_create() && call.is_success) =>
+            {
+                let offset = step.stack.nth_last(0)?.low_u64();
+                let length = step.stack.nth_last(1)?.low_u64();
+                (offset, step.memory.read_chunk(offset.into(), length.into()))
+            }
+            _ => (0, Vec::new()),
+        };
+
         self.tx_ctx.pop_call_ctx();
 
+        if let Ok(caller_ctx) = self.tx_ctx.call_ctx_mut() {
+            caller_ctx.return_data = return_data;
+            caller_ctx.last_callee_id = call.call_id;
+            caller_ctx.last_callee_return_data_offset = return_data_offset;
+        }
+
         Ok(())
     }
 
@@ -808,6 +921,16 @@ impl<'a> CircuitInputStateRef<'a> {
         self.block.add_copy_event(copy);
     }
 
+    /// Push an exponentiation event to the state.
+    pub fn push_exponentiation(&mut self, event: ExpEvent) {
+        self.block.add_exp_event(event);
+    }
+
+    /// Push the input of a keccak hash to the state.
+    pub fn push_sha3_input(&mut self, input: Vec<u8>) {
+        self.block.add_sha3_input(input);
+    }
+
     pub(crate) fn get_step_err(
         &self,
         step: &GethExecStep,
@@ -839,7 +962,7 @@ impl<'a> CircuitInputStateRef<'a> {
         let call = self.call()?;
 
         // Return from a call with a failure
-        if step.depth != next_depth && next_result.is_zero() {
+        if step.depth == next_depth + 1 && next_result.is_zero() {
             if !matches!(step.op, OpcodeId::RETURN) {
                 // Without calling RETURN
                 return Ok(match step.op {
@@ -901,12 +1024,15 @@ impl<'a> CircuitInputStateRef<'a> {
 
         // Return from a call without calling RETURN or STOP and having success
         // is unexpected.
-        if step.depth != next_depth
+        if step.depth == next_depth + 1
             && next_result != Word::zero()
-            && !matches!(step.op, OpcodeId::RETURN | OpcodeId::STOP)
+            && !matches!(
+                step.op,
+                OpcodeId::RETURN | OpcodeId::STOP | OpcodeId::SELFDESTRUCT
+            )
         {
             return Err(Error::UnexpectedExecStepError(
-                "success result without {RETURN, STOP}",
+                "success result without {RETURN, STOP, SELFDESTRUCT}",
                 step.clone(),
             ));
         }
@@ -940,6 +1066,15 @@ impl<'a> CircuitInputStateRef<'a> {
                 return Ok(Some(ExecError::WriteProtection));
             }
 
+            // Failure of a precompiled contract is not an error of this step
+            if matches!(
+                step.op,
+                OpcodeId::CALL | OpcodeId::CALLCODE | OpcodeId::DELEGATECALL | OpcodeId::STATICCALL
+            ) && self.is_precompiled(&step.stack.nth_last(1)?.to_address())
+            {
+                return Ok(None);
+            }
+
             let sender = self.call()?.address;
             let (found, account) = self.sdb.get_account(&sender);
             if !found {
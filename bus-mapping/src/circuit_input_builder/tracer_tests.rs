@@ -1422,6 +1422,76 @@ fn tracer_err_stack_underflow() {
 // Circuit Input Builder tests
 //
 
+#[test]
+fn tracer_gas_mismatch() {
+    let code = bytecode! {
+        PUSH1(0x1)
+        PUSH1(0x2)
+        ADD
+        STOP
+    };
+    let mut block: GethData = TestContext::<2, 1>::new(
+        None,
+        account_0_code_account_1_no_code(code),
+        tx_from_1_to_0,
+        |block, _tx| block.number(0xcafeu64),
+    )
+    .unwrap()
+    .into();
+
+    // Drift the gas cost of the ADD step by one
+    let struct_logs = &mut block.geth_traces[0].struct_logs;
+    assert_eq!(struct_logs[2].op, OpcodeId::ADD);
+    struct_logs[2].gas_cost.0 += 1;
+    let gas_left = struct_logs[2].gas;
+
+    let mut builder =
+        crate::mock::BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+    let err = builder
+        .handle_block(&block.eth_block, &block.geth_traces)
+        .unwrap_err();
+    match err {
+        Error::GasMismatch(mismatch) => assert_eq!(
+            mismatch,
+            GasMismatch {
+                tx_id: 1,
+                exec_state: ExecState::Op(OpcodeId::ADD),
+                gas_left,
+                gas_cost: GasCost::FASTEST,
+                geth_gas_cost: GasCost(GasCost::FASTEST.as_u64() + 1),
+            }
+        ),
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[test]
+fn tracer_step_gas_above_tx_gas() {
+    let code = bytecode! {
+        PUSH1(0x1)
+        STOP
+    };
+    let mut block: GethData = TestContext::<2, 1>::new(
+        None,
+        account_0_code_account_1_no_code(code),
+        tx_from_1_to_0,
+        |block, _tx| block.number(0xcafeu64),
+    )
+    .unwrap()
+    .into();
+
+    // The first step has more gas than the tx
+    let tx_gas = block.eth_block.transactions[0].gas.as_u64();
+    block.geth_traces[0].struct_logs[0].gas.0 = tx_gas + 1;
+
+    let mut builder =
+        crate::mock::BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+    let err = builder
+        .handle_block(&block.eth_block, &block.geth_traces)
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidGethExecTrace(_)), "{:?}", err);
+}
+
 #[test]
 fn create2_address() {
     // code_creator outputs 0x6050.
@@ -2,8 +2,12 @@
 
 use std::collections::BTreeMap;
 
-use eth_types::{Address, GethExecTrace, Word};
+use eth_types::{
+    geth_types::{self, TransactionType},
+    AccessList, Address, GethExecTrace, Hash, Word,
+};
 use ethers_core::utils::get_contract_address;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     state_db::{CodeDB, StateDB},
@@ -159,6 +163,7 @@ impl TransactionContext {
             index: call_idx,
             reversible_write_counter: 0,
             call_data,
+            ..Default::default()
         });
     }
 
@@ -174,15 +179,21 @@ impl TransactionContext {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Result of the parsing of an Ethereum Transaction.
 pub struct Transaction {
     /// Nonce
     pub nonce: u64,
     /// Gas
     pub gas: u64,
-    /// Gas price
+    /// Gas price.  For EIP-1559 transactions this is the effective gas price
     pub gas_price: Word,
+    /// Transaction type (EIP-2718)
+    pub transaction_type: TransactionType,
+    /// Max fee per gas (EIP-1559)
+    pub max_fee_per_gas: Word,
+    /// Max priority fee per gas (EIP-1559)
+    pub max_priority_fee_per_gas: Word,
     /// From / Caller Address
     pub from: Address,
     /// To / Callee Address
@@ -191,6 +202,18 @@ pub struct Transaction {
     pub value: Word,
     /// Input / Call Data
     pub input: Vec<u8>,
+    /// Access list (EIP-2930)
+    pub access_list: AccessList,
+    /// Max fee per blob gas (EIP-4844)
+    pub max_fee_per_blob_gas: Word,
+    /// Versioned hashes of the blobs carried by the transaction (EIP-4844)
+    pub blob_versioned_hashes: Vec<Hash>,
+    /// "v" value of the transaction signature
+    pub v: u64,
+    /// "r" value of the transaction signature
+    pub r: Word,
+    /// "s" value of the transaction signature
+    pub s: Word,
     /// Calls made in the transaction
     calls: Vec<Call>,
     /// Execution steps
@@ -256,10 +279,22 @@ impl Transaction {
             nonce: eth_tx.nonce.as_u64(),
             gas: eth_tx.gas.as_u64(),
             gas_price: eth_tx.gas_price.unwrap_or_default(),
+            transaction_type: TransactionType::try_from(
+                eth_tx.transaction_type.unwrap_or_default(),
+            )
+            .unwrap_or_default(),
+            max_fee_per_gas: eth_tx.max_fee_per_gas.unwrap_or_default(),
+            max_priority_fee_per_gas: eth_tx.max_priority_fee_per_gas.unwrap_or_default(),
             from: eth_tx.from,
             to: eth_tx.to.unwrap_or_default(),
             value: eth_tx.value,
             input: eth_tx.input.to_vec(),
+            access_list: eth_tx.access_list.clone().unwrap_or_default(),
+            max_fee_per_blob_gas: Word::zero(),
+            blob_versioned_hashes: Vec::new(),
+            v: eth_tx.v.as_u64(),
+            r: eth_tx.r,
+            s: eth_tx.s,
             calls: vec![call],
             steps: Vec::new(),
         })
@@ -310,3 +345,28 @@ impl Transaction {
         self.steps.is_empty()
     }
 }
+
+impl From<&Transaction> for geth_types::Transaction {
+    fn from(tx: &Transaction) -> Self {
+        Self {
+            from: tx.from,
+            to: if tx.is_create() { None } else { Some(tx.to) },
+            nonce: tx.nonce.into(),
+            gas_limit: tx.gas.into(),
+            value: tx.value,
+            gas_price: tx.gas_price,
+            gas_fee_cap: tx.max_fee_per_gas,
+            gas_tip_cap: tx.max_priority_fee_per_gas,
+            call_data: tx.input.clone().into(),
+            transaction_type: tx.transaction_type,
+            // Legacy transactions don't have an access list
+            access_list: (tx.transaction_type != TransactionType::Legacy)
+                .then(|| tx.access_list.clone()),
+            max_fee_per_blob_gas: tx.max_fee_per_blob_gas,
+            blob_versioned_hashes: tx.blob_versioned_hashes.clone(),
+            v: tx.v,
+            r: tx.r,
+            s: tx.s,
+        }
+    }
+}
@@ -1,10 +1,15 @@
 //! Error module for the bus-mapping crate
 
 use core::fmt::{Display, Formatter, Result as FmtResult};
-use eth_types::{evm_types::OpcodeId, Address, GethExecStep, Word, H256};
+use eth_types::{
+    evm_types::{Gas, GasCost, OpcodeId},
+    Address, GethExecStep, Word, H256,
+};
 use ethers_providers::ProviderError;
+use serde::{Deserialize, Serialize};
 use std::error::Error as StdError;
 
+use crate::circuit_input_builder::ExecState;
 use crate::geth_errors::{
     GETH_ERR_GAS_UINT_OVERFLOW, GETH_ERR_OUT_OF_GAS, GETH_ERR_STACK_OVERFLOW,
     GETH_ERR_STACK_UNDERFLOW,
@@ -36,6 +41,61 @@ pub enum Error {
     EthTypeError(eth_types::Error),
     /// EVM Execution error
     ExecutionError(ExecError),
+    /// Gas of an execution step computed by the builder doesn't match the geth
+    /// trace.
+    GasMismatch(GasMismatch),
+    /// Block doesn't fit in the capacities of the circuits.
+    CapacityExceeded(Vec<CapacityOverflow>),
+    /// Number of states given before the transactions of a block, followed by
+    /// its number of transactions, which differ.
+    TxStatesMismatch(usize, usize),
+}
+
+/// Mismatch between the gas of an execution step computed by the builder and
+/// the gas found in the geth trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GasMismatch {
+    /// Id of the transaction
+    pub tx_id: usize,
+    /// Execution state of the step
+    pub exec_state: ExecState,
+    /// Gas left before the step
+    pub gas_left: Gas,
+    /// Gas cost computed by the builder
+    pub gas_cost: GasCost,
+    /// Gas cost in the geth trace
+    pub geth_gas_cost: GasCost,
+}
+
+/// Resource of the circuits whose capacity is set by the
+/// [`CircuitsParams`](crate::circuit_input_builder::CircuitsParams).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitResource {
+    /// Transactions
+    Txs,
+    /// Bytes of call data of the transactions
+    Calldata,
+    /// Rows of the rw table
+    Rws,
+    /// Rows of the bytecode table
+    Bytecode,
+    /// Rows of the copy table
+    CopyRows,
+    /// Rows of the exp table
+    ExpRows,
+    /// Rows of the keccak table
+    KeccakRows,
+}
+
+/// Usage of a resource of the circuits by a block exceeding its capacity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityOverflow {
+    /// Resource of the circuits
+    pub resource: CircuitResource,
+    /// Usage of the resource by the block
+    pub used: usize,
+    /// Capacity of the resource
+    pub capacity: usize,
 }
 
 impl From<eth_types::Error> for Error {
@@ -59,7 +119,7 @@ impl Display for Error {
 impl StdError for Error {}
 
 /// Out of Gas errors by opcode
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum OogError {
     /// Out of Gas for opcodes which have non-zero constant gas cost
     Constant,
@@ -105,7 +165,7 @@ pub enum OogError {
 }
 
 /// EVM Execution Error
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ExecError {
     /// Invalid Opcode
     InvalidOpcode,
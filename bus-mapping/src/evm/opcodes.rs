@@ -1,17 +1,20 @@
 //! Definition of each opcode of the EVM.
 use crate::{
-    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    circuit_input_builder::{
+        CircuitInputStateRef, CopyDataType, CopyEvent, CopyStep, ExecStep, NumberOrHash,
+    },
+    error::ExecError,
     evm::OpcodeId,
     operation::{
         AccountField, AccountOp, CallContextField, TxAccessListAccountOp, TxReceiptField,
         TxRefundOp, RW,
     },
+    state_db::Account,
     Error,
 };
 use core::fmt::Debug;
 use eth_types::{
-    evm_types::{GasCost, MAX_REFUND_QUOTIENT_OF_GAS_USED},
-    GethExecStep, ToAddress, ToWord, Word,
+    evm_types::GasCost, geth_types::create_address_rlp, Address, GethExecStep, ToWord, Word,
 };
 use keccak256::EMPTY_HASH;
 use log::warn;
@@ -25,7 +28,10 @@ mod callvalue;
 mod chainid;
 mod codecopy;
 mod codesize;
+mod create;
 mod dup;
+mod exp;
+mod extcodecopy;
 mod extcodehash;
 mod gasprice;
 mod logs;
@@ -34,14 +40,17 @@ mod mstore;
 mod number;
 mod origin;
 mod r#return;
+mod returndatacopy;
 mod selfbalance;
+mod selfdestruct;
+mod sha3;
 mod sload;
 mod sstore;
 mod stackonlyop;
 mod stop;
 mod swap;
 
-use call::Call;
+use call::{gen_precompile_call_ops, Call};
 use calldatacopy::Calldatacopy;
 use calldataload::Calldataload;
 use calldatasize::Calldatasize;
@@ -49,7 +58,10 @@ use caller::Caller;
 use callvalue::Callvalue;
 use codecopy::Codecopy;
 use codesize::Codesize;
+use create::Create;
 use dup::Dup;
+use exp::Exponentiation;
+use extcodecopy::Extcodecopy;
 use extcodehash::Extcodehash;
 use gasprice::GasPrice;
 use logs::Log;
@@ -57,7 +69,10 @@ use mload::Mload;
 use mstore::Mstore;
 use origin::Origin;
 use r#return::Return;
+use returndatacopy::Returndatacopy;
 use selfbalance::Selfbalance;
+use selfdestruct::Selfdestruct;
+use sha3::Sha3;
 use sload::Sload;
 use sstore::Sstore;
 use stackonlyop::StackOnlyOpcode;
@@ -107,7 +122,7 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::SMOD => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::ADDMOD => StackOnlyOpcode::<3, 1>::gen_associated_ops,
         OpcodeId::MULMOD => StackOnlyOpcode::<3, 1>::gen_associated_ops,
-        OpcodeId::EXP => StackOnlyOpcode::<2, 1>::gen_associated_ops,
+        OpcodeId::EXP => Exponentiation::gen_associated_ops,
         OpcodeId::SIGNEXTEND => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::LT => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::GT => StackOnlyOpcode::<2, 1>::gen_associated_ops,
@@ -123,7 +138,7 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::SHL => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::SHR => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::SAR => StackOnlyOpcode::<2, 1>::gen_associated_ops,
-        OpcodeId::SHA3 => StackOnlyOpcode::<2, 1>::gen_associated_ops,
+        OpcodeId::SHA3 => Sha3::gen_associated_ops,
         OpcodeId::ADDRESS => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         OpcodeId::BALANCE => StackOnlyOpcode::<1, 1>::gen_associated_ops,
         OpcodeId::ORIGIN => Origin::gen_associated_ops,
@@ -136,9 +151,9 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::CODECOPY => Codecopy::gen_associated_ops,
         OpcodeId::CODESIZE => Codesize::gen_associated_ops,
         OpcodeId::EXTCODESIZE => StackOnlyOpcode::<1, 1>::gen_associated_ops,
-        OpcodeId::EXTCODECOPY => StackOnlyOpcode::<4, 0>::gen_associated_ops,
+        OpcodeId::EXTCODECOPY => Extcodecopy::gen_associated_ops,
         OpcodeId::RETURNDATASIZE => StackOnlyOpcode::<0, 1>::gen_associated_ops,
-        OpcodeId::RETURNDATACOPY => StackOnlyOpcode::<3, 0>::gen_associated_ops,
+        OpcodeId::RETURNDATACOPY => Returndatacopy::gen_associated_ops,
         OpcodeId::EXTCODEHASH => Extcodehash::gen_associated_ops,
         OpcodeId::BLOCKHASH => StackOnlyOpcode::<1, 1>::gen_associated_ops,
         OpcodeId::COINBASE => StackOnlyOpcode::<0, 1>::gen_associated_ops,
@@ -149,6 +164,9 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::CHAINID => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         OpcodeId::SELFBALANCE => Selfbalance::gen_associated_ops,
         OpcodeId::BASEFEE => StackOnlyOpcode::<0, 1>::gen_associated_ops,
+        // Not supported by the EVM circuit yet, which rejects their steps.
+        OpcodeId::BLOBHASH => StackOnlyOpcode::<1, 1>::gen_associated_ops,
+        OpcodeId::BLOBBASEFEE => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         OpcodeId::POP => StackOnlyOpcode::<1, 0>::gen_associated_ops,
         OpcodeId::MLOAD => Mload::gen_associated_ops,
         OpcodeId::MSTORE => Mstore::<false>::gen_associated_ops,
@@ -198,30 +216,23 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::LOG2 => Log::gen_associated_ops,
         OpcodeId::LOG3 => Log::gen_associated_ops,
         OpcodeId::LOG4 => Log::gen_associated_ops,
-        // OpcodeId::CREATE => {},
+        OpcodeId::CREATE => Create::<false>::gen_associated_ops,
         OpcodeId::CALL => Call::gen_associated_ops,
         // OpcodeId::CALLCODE => {},
         // OpcodeId::RETURN => {},
         // OpcodeId::DELEGATECALL => {},
-        // OpcodeId::CREATE2 => {},
+        OpcodeId::CREATE2 => Create::<true>::gen_associated_ops,
         // OpcodeId::STATICCALL => {},
         // OpcodeId::REVERT => {},
         OpcodeId::REVERT | OpcodeId::RETURN => {
             warn!("Using dummy gen_associated_ops for opcode {:?}", opcode_id);
             Return::gen_associated_ops
         }
-        OpcodeId::SELFDESTRUCT => {
-            warn!("Using dummy gen_selfdestruct_ops for opcode SELFDESTRUCT");
-            dummy_gen_selfdestruct_ops
-        }
+        OpcodeId::SELFDESTRUCT => Selfdestruct::gen_associated_ops,
         OpcodeId::CALLCODE | OpcodeId::DELEGATECALL | OpcodeId::STATICCALL => {
             warn!("Using dummy gen_call_ops for opcode {:?}", opcode_id);
             dummy_gen_call_ops
         }
-        OpcodeId::CREATE | OpcodeId::CREATE2 => {
-            warn!("Using dummy gen_create_ops for opcode {:?}", opcode_id);
-            dummy_gen_create_ops
-        }
         _ => {
             warn!("Using dummy gen_associated_ops for opcode {:?}", opcode_id);
             dummy_gen_associated_ops
@@ -230,14 +241,46 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
 }
 
 /// Generate the associated operations according to the particular
-/// [`OpcodeId`].
+/// [`OpcodeId`].  A step that fails with an error generates an error step
+/// instead, with the operations of the call it halts.
 pub fn gen_associated_ops(
     opcode_id: &OpcodeId,
     state: &mut CircuitInputStateRef,
     geth_steps: &[GethExecStep],
 ) -> Result<Vec<ExecStep>, Error> {
-    let fn_gen_associated_ops = fn_gen_associated_ops(opcode_id);
-    fn_gen_associated_ops(state, geth_steps)
+    let geth_step = &geth_steps[0];
+    let exec_error = match state.get_step_err(geth_step, geth_steps.get(1))? {
+        Some(exec_error) => exec_error,
+        // If no errors, continue as normal
+        None => return fn_gen_associated_ops(opcode_id)(state, geth_steps),
+    };
+    log::trace!("geth error {:?} occurred in {:?}", exec_error, geth_step.op);
+
+    let mut exec_step = state.new_step(geth_step)?;
+    exec_step.error = Some(exec_error.clone());
+    match exec_error {
+        // The callee of *CALL*/CREATE* fails before its code is executed, so
+        // its call is entered and returned at once, and the current call
+        // continues.
+        ExecError::Depth | ExecError::InsufficientBalance | ExecError::ContractAddressCollision => {
+            let call = state.parse_call(geth_step)?;
+            state.push_call(call, geth_step);
+        }
+        // Any other error halts the current call.
+        _ => {
+            let call_id = state.call()?.call_id;
+            state.call_context_read(
+                &mut exec_step,
+                call_id,
+                CallContextField::IsSuccess,
+                0.into(),
+            );
+            state.gen_restore_context_ops(&mut exec_step, geth_steps)?;
+        }
+    }
+    state.handle_return(geth_step)?;
+
+    Ok(vec![exec_step])
 }
 
 pub fn gen_begin_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Error> {
@@ -269,8 +312,11 @@ pub fn gen_begin_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Er
         nonce_prev.into(),
     )?;
 
-    // Add caller and callee into access list
-    for address in [call.caller_address, call.address] {
+    // Add the precompiled contracts of the hardfork, which are always warm
+    // (EIP-2929), into the access list.  They are added first, into the empty
+    // access list of the transaction.
+    for address in 1..=state.block.hardfork().num_precompiles() {
+        let address = Address::from_low_u64_be(address);
         state.sdb.add_account_to_access_list(address);
         state.tx_accesslist_account_write(
             &mut exec_step,
@@ -281,19 +327,85 @@ pub fn gen_begin_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Er
         )?;
     }
 
+    // Add caller and callee into access list, in which the callee is already
+    // if it's a precompiled contract or the caller
+    for address in [call.caller_address, call.address] {
+        let is_warm_prev = !state.sdb.add_account_to_access_list(address);
+        state.tx_accesslist_account_write(
+            &mut exec_step,
+            state.tx_ctx.id(),
+            address,
+            true,
+            is_warm_prev,
+        )?;
+    }
+
+    // Add the accounts and storage slots of the transaction access list (EIP-2930)
+    // into the access list
+    let access_list = state.tx.access_list.clone();
+    for item in access_list.0.iter() {
+        let is_warm_prev = !state.sdb.add_account_to_access_list(item.address);
+        state.tx_accesslist_account_write(
+            &mut exec_step,
+            state.tx_ctx.id(),
+            item.address,
+            true,
+            is_warm_prev,
+        )?;
+        for key in item.storage_keys.iter() {
+            let key = key.to_word();
+            let is_warm_prev = !state
+                .sdb
+                .add_account_storage_to_access_list((item.address, key));
+            state.tx_accesslist_account_storage_write(
+                &mut exec_step,
+                state.tx_ctx.id(),
+                item.address,
+                key,
+                true,
+                is_warm_prev,
+            )?;
+        }
+    }
+
     // Calculate intrinsic gas cost
     let call_data_gas_cost = state
         .tx
         .input
         .iter()
         .fold(0, |acc, byte| acc + if *byte == 0 { 4 } else { 16 });
+    let access_list_gas_cost = access_list.0.iter().fold(0, |acc, item| {
+        acc + GasCost::ACCESS_LIST_PER_ADDRESS.as_u64()
+            + GasCost::ACCESS_LIST_PER_STORAGE_KEY.as_u64() * item.storage_keys.len() as u64
+    });
     let intrinsic_gas_cost = if state.tx.is_create() {
         GasCost::CREATION_TX.as_u64()
     } else {
         GasCost::TX.as_u64()
-    } + call_data_gas_cost;
+    } + call_data_gas_cost
+        + access_list_gas_cost;
     exec_step.gas_cost = GasCost(intrinsic_gas_cost);
 
+    // The account created by a contract creation transaction usually doesn't
+    // exist in the state before the transaction, so insert an empty one whose
+    // nonce is increased (EIP-161) before receiving the value transfer, as
+    // done by CREATE.  Its address is taken from the hash of rlp([caller,
+    // nonce]).
+    if call.is_create() {
+        state.sdb.get_account_mut(&call.address);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            AccountOp {
+                address: call.address,
+                field: AccountField::Nonce,
+                value: 1.into(),
+                value_prev: 0.into(),
+            },
+        )?;
+        state.push_sha3_input(create_address_rlp(&caller_address, nonce_prev));
+    }
+
     // Transfer with fee
     state.transfer_with_fee(
         &mut exec_step,
@@ -315,7 +427,79 @@ pub fn gen_begin_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Er
     ) {
         // 1. Creation transaction.
         (true, _, _) => {
-            warn!("Creation transaction is left unimplemented");
+            // Execute the init code of the transaction, which has been inserted
+            // into the code db with `call.code_hash`.
+            for (field, value) in [
+                (CallContextField::Depth, call.depth.into()),
+                (
+                    CallContextField::CallerAddress,
+                    call.caller_address.to_word(),
+                ),
+                (CallContextField::CalleeAddress, call.address.to_word()),
+                (
+                    CallContextField::CallDataOffset,
+                    call.call_data_offset.into(),
+                ),
+                (
+                    CallContextField::CallDataLength,
+                    call.call_data_length.into(),
+                ),
+                (CallContextField::Value, call.value),
+                (CallContextField::IsStatic, (call.is_static as usize).into()),
+                (CallContextField::LastCalleeId, 0.into()),
+                (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+                (CallContextField::LastCalleeReturnDataLength, 0.into()),
+                (CallContextField::IsRoot, 1.into()),
+                (CallContextField::IsCreate, 1.into()),
+                (CallContextField::CodeHash, call.code_hash.to_word()),
+            ] {
+                state.call_context_read(&mut exec_step, call.call_id, field, value);
+            }
+
+            // The random linear combination of the init code is accumulated
+            // from the tx call data by the copy circuit, to look up its hash.
+            let init_code = state.tx.input.clone();
+            if !init_code.is_empty() {
+                let rwc = state.block_ctx.rwc;
+                let steps = init_code
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(idx, byte)| {
+                        [
+                            (CopyDataType::TxCalldata, RW::READ),
+                            (CopyDataType::RlcAcc, RW::WRITE),
+                        ]
+                        .map(|(tag, rw)| CopyStep {
+                            addr: idx as u64,
+                            tag,
+                            rw,
+                            value: *byte,
+                            is_code: None,
+                            is_pad: false,
+                            rwc,
+                            rwc_inc_left: 0,
+                        })
+                    })
+                    .collect();
+                state.push_copy(CopyEvent {
+                    src_type: CopyDataType::TxCalldata,
+                    src_id: NumberOrHash::Number(state.tx_ctx.id()),
+                    src_addr: 0,
+                    src_addr_end: init_code.len() as u64,
+                    dst_type: CopyDataType::RlcAcc,
+                    dst_id: NumberOrHash::Number(0),
+                    dst_addr: 0,
+                    log_id: None,
+                    length: init_code.len() as u64,
+                    steps,
+                    tx_id: state.tx_ctx.id(),
+                    // Keyed apart from the copy event of the first step of the
+                    // call, which has the same program counter.
+                    call_id: 0,
+                    pc: exec_step.pc,
+                });
+            }
+
             Ok(exec_step)
         }
         // 2. Call to precompiled.
@@ -399,8 +583,11 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Erro
         },
     );
 
-    let effective_refund =
-        refund.min((state.tx.gas - exec_step.gas_left.0) / MAX_REFUND_QUOTIENT_OF_GAS_USED as u64);
+    let effective_refund = refund
+        .min((state.tx.gas - exec_step.gas_left.0) / state.block.hardfork().max_refund_quotient());
+    // The gas used by the transaction, as reported in its receipt, is net of
+    // the refund.
+    let gas_used = state.tx.gas - exec_step.gas_left.0 - effective_refund;
     let (found, caller_account) = state.sdb.get_account_mut(&call.caller_address);
     if !found {
         return Err(Error::AccountNotFound(call.caller_address));
@@ -423,8 +610,7 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Erro
         return Err(Error::AccountNotFound(state.block.coinbase));
     }
     let coinbase_balance_prev = coinbase_account.balance;
-    let coinbase_balance =
-        coinbase_account.balance + effective_tip * (state.tx.gas - exec_step.gas_left.0);
+    let coinbase_balance = coinbase_account.balance + effective_tip * gas_used;
     state.account_write(
         &mut exec_step,
         state.block.coinbase,
@@ -433,6 +619,24 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Erro
         coinbase_balance_prev,
     )?;
 
+    // The accounts self destructed in the transaction are deleted at the end
+    // of it.
+    let account_zero = Account::zero();
+    for address in state.sdb.destructed_accounts() {
+        let (_, account) = state.sdb.get_account(&address);
+        for (field, value, value_prev) in [
+            (AccountField::Nonce, account_zero.nonce, account.nonce),
+            (AccountField::Balance, account_zero.balance, account.balance),
+            (
+                AccountField::CodeHash,
+                account_zero.code_hash.to_word(),
+                account.code_hash.to_word(),
+            ),
+        ] {
+            state.account_write(&mut exec_step, address, field, value, value_prev)?;
+        }
+    }
+
     // handle tx receipt tag
     state.tx_receipt_write(
         &mut exec_step,
@@ -459,7 +663,7 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Erro
         )?;
     }
 
-    state.block_ctx.cumulative_gas_used += state.tx.gas - exec_step.gas_left.0;
+    state.block_ctx.cumulative_gas_used += gas_used;
     state.tx_receipt_write(
         &mut exec_step,
         state.tx_ctx.id(),
@@ -511,7 +715,10 @@ fn dummy_gen_call_ops(
         callee_code_hash.to_fixed_bytes() == *EMPTY_HASH,
     ) {
         // 1. Call to precompiled.
-        (true, _) => Ok(vec![exec_step]),
+        (true, _) => {
+            gen_precompile_call_ops(state, &mut exec_step, geth_steps, &call)?;
+            Ok(vec![exec_step])
+        }
         // 2. Call to account with empty code.
         (_, true) => {
             state.handle_return(geth_step)?;
@@ -522,106 +729,473 @@ fn dummy_gen_call_ops(
     }
 }
 
-fn dummy_gen_create_ops(
-    state: &mut CircuitInputStateRef,
-    geth_steps: &[GethExecStep],
-) -> Result<Vec<ExecStep>, Error> {
-    let geth_step = &geth_steps[0];
-    let mut exec_step = state.new_step(geth_step)?;
+#[cfg(test)]
+mod begin_tx_tests {
+    use super::*;
+    use crate::{
+        circuit_input_builder::ExecState,
+        mock::BlockData,
+        operation::{Target, TxAccessListAccountStorageOp},
+    };
+    use eth_types::{bytecode, geth_types::GethData, AccessList, Address, H256};
+    use ethers_core::{types::transaction::eip2930::AccessListItem, utils::get_contract_address};
+    use mock::{
+        eth,
+        test_ctx::{helpers::*, TestContext},
+        MOCK_ACCOUNTS,
+    };
+    use pretty_assertions::assert_eq;
 
-    let tx_id = state.tx_ctx.id();
-    let call = state.parse_call(geth_step)?;
+    #[test]
+    fn begin_tx_access_list() {
+        let code = bytecode! {
+            STOP
+        };
+        let access_list = AccessList(vec![
+            AccessListItem {
+                address: MOCK_ACCOUNTS[2],
+                storage_keys: vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)],
+            },
+            // The callee is already warm when the access list is applied.
+            AccessListItem {
+                address: MOCK_ACCOUNTS[0],
+                storage_keys: vec![H256::from_low_u64_be(3)],
+            },
+        ]);
 
-    // Increase caller's nonce
-    let nonce_prev = state.sdb.get_nonce(&call.caller_address);
-    state.push_op_reversible(
-        &mut exec_step,
-        RW::WRITE,
-        AccountOp {
-            address: call.caller_address,
-            field: AccountField::Nonce,
-            value: (nonce_prev + 1).into(),
-            value_prev: nonce_prev.into(),
-        },
-    )?;
+        // Get the execution steps from the external tracer
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(code),
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[1].address)
+                    .to(accs[0].address)
+                    .transaction_type(1)
+                    .access_list(access_list.clone());
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
 
-    // Add callee into access list
-    let is_warm = state.sdb.check_account_in_access_list(&call.address);
-    state.push_op_reversible(
-        &mut exec_step,
-        RW::WRITE,
-        TxAccessListAccountOp {
-            tx_id,
-            address: call.address,
-            is_warm: true,
-            is_warm_prev: is_warm,
-        },
-    )?;
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
 
-    state.push_call(call.clone(), geth_step);
+        let step = &builder.block.txs()[0].steps()[0];
+        assert_eq!(step.exec_state, ExecState::BeginTx);
 
-    // Increase callee's nonce
-    let nonce_prev = state.sdb.get_nonce(&call.address);
-    debug_assert!(nonce_prev == 0);
-    state.push_op_reversible(
-        &mut exec_step,
-        RW::WRITE,
-        AccountOp {
-            address: call.address,
-            field: AccountField::Nonce,
-            value: 1.into(),
-            value_prev: 0.into(),
-        },
-    )?;
+        let account_ops: Vec<(Address, bool)> = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::TxAccessListAccount)
+            .map(|op_ref| {
+                let op = builder.block.container.tx_access_list_account[op_ref.as_usize()].op();
+                (op.address, op.is_warm_prev)
+            })
+            .collect();
+        assert_eq!(
+            account_ops,
+            (1..=9)
+                .map(|address| (Address::from_low_u64_be(address), false))
+                .chain([
+                    (MOCK_ACCOUNTS[1], false),
+                    (MOCK_ACCOUNTS[0], false),
+                    (MOCK_ACCOUNTS[2], false),
+                    (MOCK_ACCOUNTS[0], true)
+                ])
+                .collect::<Vec<_>>()
+        );
 
-    state.transfer(
-        &mut exec_step,
-        call.caller_address,
-        call.address,
-        call.value,
-    )?;
+        let storage_ops: Vec<TxAccessListAccountStorageOp> = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::TxAccessListAccountStorage)
+            .map(|op_ref| {
+                builder.block.container.tx_access_list_account_storage[op_ref.as_usize()]
+                    .op()
+                    .clone()
+            })
+            .collect();
+        assert_eq!(
+            storage_ops,
+            [
+                (MOCK_ACCOUNTS[2], 1u64),
+                (MOCK_ACCOUNTS[2], 2),
+                (MOCK_ACCOUNTS[0], 3)
+            ]
+            .map(|(address, key)| TxAccessListAccountStorageOp {
+                tx_id: 1,
+                address,
+                key: Word::from(key),
+                is_warm: true,
+                is_warm_prev: false,
+            })
+            .to_vec()
+        );
 
-    if call.code_hash.to_fixed_bytes() == *EMPTY_HASH {
-        // 1. Create with empty initcode.
-        state.handle_return(geth_step)?;
-        Ok(vec![exec_step])
-    } else {
-        // 2. Create with non-empty initcode.
-        Ok(vec![exec_step])
+        assert_eq!(
+            step.gas_cost,
+            GasCost(
+                GasCost::TX.as_u64()
+                    + 2 * GasCost::ACCESS_LIST_PER_ADDRESS.as_u64()
+                    + 3 * GasCost::ACCESS_LIST_PER_STORAGE_KEY.as_u64()
+            )
+        );
+    }
+
+    #[test]
+    fn begin_tx_create() {
+        let init_code = bytecode! {
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block: GethData = TestContext::<1, 1>::new(
+            None,
+            |accs| {
+                accs[0].address(MOCK_ACCOUNTS[0]).balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[0].address)
+                    .value(eth(1))
+                    .input(init_code.to_vec().into());
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let tx = &builder.block.txs()[0];
+        assert!(tx.is_create());
+        let created_address = get_contract_address(MOCK_ACCOUNTS[0], Word::zero());
+        assert_eq!(tx.calls()[0].address, created_address);
+
+        let step = &tx.steps()[0];
+        assert_eq!(step.exec_state, ExecState::BeginTx);
+
+        let account_ops: Vec<(Address, AccountField, Word, Word)> = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::Account)
+            .map(|op_ref| {
+                let op = builder.block.container.account[op_ref.as_usize()].op();
+                (op.address, op.field.clone(), op.value, op.value_prev)
+            })
+            .collect();
+        let caller_balance = eth(10) - eth(1) - tx.gas_price * tx.gas;
+        assert_eq!(
+            account_ops,
+            vec![
+                (MOCK_ACCOUNTS[0], AccountField::Nonce, 1.into(), 0.into()),
+                (created_address, AccountField::Nonce, 1.into(), 0.into()),
+                (
+                    MOCK_ACCOUNTS[0],
+                    AccountField::Balance,
+                    caller_balance,
+                    eth(10)
+                ),
+                (created_address, AccountField::Balance, eth(1), 0.into()),
+            ]
+        );
+
+        let call_context_ops: Vec<(CallContextField, Word)> = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::CallContext)
+            .map(|op_ref| {
+                let op = builder.block.container.call_context[op_ref.as_usize()].op();
+                (op.field.clone(), op.value)
+            })
+            .filter(|(field, _)| {
+                matches!(
+                    field,
+                    CallContextField::CalleeAddress
+                        | CallContextField::IsCreate
+                        | CallContextField::CodeHash
+                )
+            })
+            .collect();
+        assert_eq!(
+            call_context_ops,
+            vec![
+                (CallContextField::CalleeAddress, created_address.to_word()),
+                (CallContextField::IsCreate, 1.into()),
+                (
+                    CallContextField::CodeHash,
+                    tx.calls()[0].code_hash.to_word()
+                ),
+            ]
+        );
+
+        assert_eq!(
+            step.gas_cost,
+            GasCost(GasCost::CREATION_TX.as_u64() + 16 * init_code.to_vec().len() as u64)
+        );
+
+        assert!(builder
+            .block
+            .sha3_inputs
+            .contains(&create_address_rlp(&MOCK_ACCOUNTS[0], 0)));
+        let copy_event = &builder.block.copy_events[0];
+        assert_eq!(
+            (copy_event.src_type, copy_event.dst_type, copy_event.length),
+            (
+                CopyDataType::TxCalldata,
+                CopyDataType::RlcAcc,
+                init_code.to_vec().len() as u64
+            )
+        );
     }
 }
 
-fn dummy_gen_selfdestruct_ops(
-    state: &mut CircuitInputStateRef,
-    geth_steps: &[GethExecStep],
-) -> Result<Vec<ExecStep>, Error> {
-    let geth_step = &geth_steps[0];
-    let mut exec_step = state.new_step(geth_step)?;
-    let sender = state.call()?.address;
-    let receiver = geth_step.stack.last()?.to_address();
+#[cfg(test)]
+mod exceptional_halt_tests {
+    use super::*;
+    use crate::{circuit_input_builder::ExecState, mock::BlockData, operation::Target};
+    use eth_types::{bytecode, geth_types::GethData, Bytecode};
+    use mock::{eth, test_ctx::TestContext, MOCK_ACCOUNTS};
+    use pretty_assertions::assert_eq;
 
-    let is_warm = state.sdb.check_account_in_access_list(&receiver);
-    state.push_op_reversible(
-        &mut exec_step,
-        RW::WRITE,
-        TxAccessListAccountOp {
-            tx_id: state.tx_ctx.id(),
-            address: receiver,
-            is_warm: true,
-            is_warm_prev: is_warm,
-        },
-    )?;
+    fn call_code(addr: Word, value: Word) -> Bytecode {
+        bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH32(value) // value
+            PUSH32(addr) // addr
+            PUSH32(0x1_0000) // gas
+            CALL
+            STOP
+        }
+    }
 
-    let (found, receiver_account) = state.sdb.get_account(&receiver);
-    if !found {
-        return Err(Error::AccountNotFound(receiver));
+    fn build_block(code_a: Bytecode, code_b: Bytecode) -> (GethData, BlockData) {
+        let block: GethData = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].address(MOCK_ACCOUNTS[0]).code(code_a);
+                accs[1].address(MOCK_ACCOUNTS[1]).code(code_b);
+                accs[2].address(MOCK_ACCOUNTS[2]).balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[2].address);
+            },
+            |block, _tx| block,
+        )
+        .unwrap()
+        .into();
+        let block_data = BlockData::new_from_geth_data(block.clone());
+        (block, block_data)
+    }
+
+    #[test]
+    fn invalid_opcode_in_internal_call() {
+        let mut code_b = bytecode! {
+            PUSH1(0x01)
+        };
+        code_b.write(0xfe, true);
+
+        let (block, block_data) =
+            build_block(call_code(MOCK_ACCOUNTS[1].to_word(), Word::zero()), code_b);
+        let mut builder = block_data.new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let (index, step) = tx
+            .steps()
+            .iter()
+            .enumerate()
+            .find(|(_, step)| step.error.is_some())
+            .unwrap();
+        assert_eq!(step.exec_state, ExecState::Op(OpcodeId::INVALID(0xfe)));
+        assert_eq!(step.error, Some(ExecError::InvalidOpcode));
+        assert_eq!(step.call_index, 1);
+
+        let caller_call_id = tx.calls()[0].call_id;
+        let callee_call_id = tx.calls()[1].call_id;
+        let call_context_ops: Vec<_> = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::CallContext)
+            .map(|op_ref| {
+                let operation = &builder.block.container.call_context[op_ref.as_usize()];
+                (
+                    operation.rw(),
+                    operation.op().call_id,
+                    operation.op().field.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            call_context_ops[..2],
+            [
+                (RW::READ, callee_call_id, CallContextField::IsSuccess),
+                (RW::READ, callee_call_id, CallContextField::CallerId),
+            ]
+        );
+        assert_eq!(
+            call_context_ops[call_context_ops.len() - 3..],
+            [
+                (RW::WRITE, caller_call_id, CallContextField::LastCalleeId),
+                (
+                    RW::WRITE,
+                    caller_call_id,
+                    CallContextField::LastCalleeReturnDataOffset
+                ),
+                (
+                    RW::WRITE,
+                    caller_call_id,
+                    CallContextField::LastCalleeReturnDataLength
+                ),
+            ]
+        );
+
+        // The caller continues after the failed call.
+        let next_step = &tx.steps()[index + 1];
+        assert_eq!(next_step.exec_state, ExecState::Op(OpcodeId::STOP));
+        assert_eq!(next_step.call_index, 0);
     }
-    let value = receiver_account.balance;
-    state.transfer(&mut exec_step, sender, receiver, value)?;
 
-    if state.call()?.is_persistent {
-        state.sdb.destruct_account(sender);
+    #[test]
+    fn call_with_insufficient_balance() {
+        let (block, block_data) = build_block(
+            call_code(MOCK_ACCOUNTS[1].to_word(), eth(1)),
+            bytecode! { STOP },
+        );
+        let mut builder = block_data.new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let (index, step) = tx
+            .steps()
+            .iter()
+            .enumerate()
+            .find(|(_, step)| step.error.is_some())
+            .unwrap();
+        assert_eq!(step.exec_state, ExecState::Op(OpcodeId::CALL));
+        assert_eq!(step.error, Some(ExecError::InsufficientBalance));
+
+        // The callee is entered and returned at once.
+        assert_eq!(tx.calls().len(), 2);
+        assert!(!tx.calls()[1].is_success);
+        let next_step = &tx.steps()[index + 1];
+        assert_eq!(next_step.exec_state, ExecState::Op(OpcodeId::STOP));
+        assert_eq!(next_step.call_index, 0);
     }
+}
 
-    Ok(vec![exec_step])
+#[cfg(test)]
+mod end_tx_tests {
+    use super::*;
+    use crate::{circuit_input_builder::ExecState, mock::BlockData, operation::Target};
+    use eth_types::{bytecode, geth_types::GethData};
+    use mock::{eth, test_ctx::TestContext, MOCK_ACCOUNTS};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn end_tx_receipts() {
+        // Each tx emits a log, and the first one clears a storage slot, which
+        // gives a refund.
+        let code = bytecode! {
+            PUSH1(0x00) // size
+            PUSH1(0x00) // offset
+            LOG0
+            PUSH1(0x00) // value
+            PUSH1(0x00) // key
+            SSTORE
+            STOP
+        };
+        let block: GethData = TestContext::<2, 2>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(MOCK_ACCOUNTS[0])
+                    .balance(eth(10))
+                    .code(code)
+                    .storage(vec![(Word::zero(), Word::one())].into_iter());
+                accs[1].address(MOCK_ACCOUNTS[1]).balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[1].address);
+                txs[1].to(accs[0].address).from(accs[1].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let receipt_ops = |tx_index: usize| -> Vec<(RW, usize, TxReceiptField, u64)> {
+            let step = builder.block.txs()[tx_index].steps().last().unwrap();
+            assert_eq!(step.exec_state, ExecState::EndTx);
+            step.bus_mapping_instance
+                .iter()
+                .filter(|op_ref| op_ref.target() == Target::TxReceipt)
+                .map(|op_ref| &builder.block.container.tx_receipt[op_ref.as_usize()])
+                .map(|operation| {
+                    let op = operation.op();
+                    (operation.rw(), op.tx_id, op.field.clone(), op.value)
+                })
+                .collect()
+        };
+
+        // The cumulative gas used is net of the refund, as reported by geth.
+        let gas_used = block.geth_traces.iter().map(|trace| trace.gas.0);
+        let cumulative_gas_used: Vec<u64> = gas_used
+            .scan(0, |cumulative_gas_used, gas_used| {
+                *cumulative_gas_used += gas_used;
+                Some(*cumulative_gas_used)
+            })
+            .collect();
+        assert_eq!(
+            receipt_ops(0),
+            vec![
+                (RW::WRITE, 1, TxReceiptField::PostStateOrStatus, 1),
+                (RW::WRITE, 1, TxReceiptField::LogLength, 1),
+                (
+                    RW::WRITE,
+                    1,
+                    TxReceiptField::CumulativeGasUsed,
+                    cumulative_gas_used[0]
+                ),
+            ]
+        );
+        assert_eq!(
+            receipt_ops(1),
+            vec![
+                (RW::WRITE, 2, TxReceiptField::PostStateOrStatus, 1),
+                (RW::WRITE, 2, TxReceiptField::LogLength, 1),
+                (
+                    RW::READ,
+                    1,
+                    TxReceiptField::CumulativeGasUsed,
+                    cumulative_gas_used[0]
+                ),
+                (
+                    RW::WRITE,
+                    2,
+                    TxReceiptField::CumulativeGasUsed,
+                    cumulative_gas_used[1]
+                ),
+            ]
+        );
+    }
 }
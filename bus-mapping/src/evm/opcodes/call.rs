@@ -1,7 +1,11 @@
 use super::Opcode;
 use crate::{
-    circuit_input_builder::{CircuitInputStateRef, ExecStep},
-    operation::{AccountField, CallContextField, TxAccessListAccountOp, RW},
+    circuit_input_builder::{
+        Call as CallInput, CircuitInputStateRef, CopyDataType, CopyEvent, CopyStep, ExecStep,
+        NumberOrHash,
+    },
+    operation::{AccountField, CallContextField, MemoryOp, TxAccessListAccountOp, RW},
+    precompile::PrecompileCalls,
     Error,
 };
 use eth_types::{
@@ -12,7 +16,6 @@ use eth_types::{
     GethExecStep, ToWord,
 };
 use keccak256::EMPTY_HASH;
-use log::warn;
 
 /// Placeholder structure used to implement [`Opcode`] trait over it
 /// corresponding to the `OpcodeId::CALL` `OpcodeId`.
@@ -148,7 +151,7 @@ impl Opcode for Call {
         ) {
             // 1. Call to precompiled.
             (true, _) => {
-                warn!("Call to precompiled is left unimplemented");
+                gen_precompile_call_ops(state, &mut exec_step, geth_steps, &call)?;
                 Ok(vec![exec_step])
             }
             // 2. Call to account with empty code.
@@ -230,3 +233,293 @@ impl Opcode for Call {
         }
     }
 }
+
+/// Generate the operations of a call to a precompiled contract, whose callee
+/// context has already been pushed.  The precompile is executed natively by
+/// geth, so there are no struct logs of the callee and the step following the
+/// call is back in the caller context.  The call data is copied into the
+/// memory of the callee, where the output of the precompile is written and
+/// then returned to the caller.
+pub(crate) fn gen_precompile_call_ops(
+    state: &mut CircuitInputStateRef,
+    exec_step: &mut ExecStep,
+    geth_steps: &[GethExecStep],
+    call: &CallInput,
+) -> Result<(), Error> {
+    let geth_step = &geth_steps[0];
+    let geth_next_step = geth_steps.get(1).ok_or(Error::InvalidGethExecTrace(
+        "Call to precompiled is not followed by a step of the caller",
+    ))?;
+    let precompile = PrecompileCalls::from_address(&call.address).ok_or(
+        Error::InvalidGethExecTrace("Callee of call to precompiled is not a precompile"),
+    )?;
+
+    // Copy the call data into the memory of the callee.
+    let input = state.call_ctx()?.call_data.clone();
+    let copy_steps = gen_memory_copy_steps(
+        state,
+        exec_step,
+        call.caller_id,
+        call.call_data_offset,
+        0,
+        &input,
+    )?;
+    if !input.is_empty() {
+        state.push_copy(CopyEvent {
+            src_type: CopyDataType::Memory,
+            src_id: NumberOrHash::Number(call.caller_id),
+            src_addr: call.call_data_offset,
+            src_addr_end: call.call_data_offset + call.call_data_length,
+            dst_type: CopyDataType::Memory,
+            dst_id: NumberOrHash::Number(call.call_id),
+            dst_addr: 0,
+            log_id: None,
+            length: call.call_data_length,
+            steps: copy_steps,
+            tx_id: state.tx_ctx.id(),
+            call_id: call.caller_id,
+            pc: exec_step.pc,
+        });
+    }
+
+    // The output is computed natively, and only its first `return_data_length`
+    // bytes are visible in the memory of the caller after the call.  A failed
+    // call returns nothing.
+    let output = if call.is_success {
+        precompile
+            .execute(&input)
+            .ok_or(Error::InvalidGethExecTrace(
+                "Successful call to precompiled fails on its input",
+            ))?
+    } else {
+        vec![]
+    };
+    let copy_length = (output.len() as u64).min(call.return_data_length);
+    for (idx, byte) in output.iter().enumerate() {
+        state.memory_write(exec_step, idx.into(), *byte)?;
+    }
+
+    state.handle_return(geth_step)?;
+    state.call_ctx_mut()?.return_data = output.clone();
+    for (field, value) in [
+        (CallContextField::LastCalleeId, call.call_id.into()),
+        (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+        (
+            CallContextField::LastCalleeReturnDataLength,
+            output.len().into(),
+        ),
+    ] {
+        state.call_context_write(exec_step, call.caller_id, field, value);
+    }
+
+    // Copy the output into the memory of the caller.
+    let copy_steps = gen_memory_copy_steps(
+        state,
+        exec_step,
+        call.call_id,
+        0,
+        call.return_data_offset,
+        &output[..copy_length as usize],
+    )?;
+    if copy_length != 0 {
+        state.push_copy(CopyEvent {
+            src_type: CopyDataType::Memory,
+            src_id: NumberOrHash::Number(call.call_id),
+            src_addr: 0,
+            src_addr_end: output.len() as u64,
+            dst_type: CopyDataType::Memory,
+            dst_id: NumberOrHash::Number(call.caller_id),
+            dst_addr: call.return_data_offset,
+            log_id: None,
+            length: copy_length,
+            steps: copy_steps,
+            tx_id: state.tx_ctx.id(),
+            call_id: call.caller_id,
+            pc: exec_step.pc,
+        });
+    }
+
+    // The gas used by the precompile is not reported by a step of its own, so
+    // that the cost of the call step is what the caller lost across it.
+    exec_step.gas_cost = GasCost(geth_step.gas.0 - geth_next_step.gas.0);
+
+    Ok(())
+}
+
+/// Generate the copy steps of `data` from the memory of the call `src_id` to
+/// the memory of the current call.
+fn gen_memory_copy_steps(
+    state: &mut CircuitInputStateRef,
+    exec_step: &mut ExecStep,
+    src_id: usize,
+    src_addr: u64,
+    dst_addr: u64,
+    data: &[u8],
+) -> Result<Vec<CopyStep>, Error> {
+    let mut steps = Vec::with_capacity(2 * data.len());
+    for (idx, byte) in data.iter().enumerate() {
+        let idx = idx as u64;
+        // Read
+        steps.push(CopyStep {
+            addr: src_addr + idx,
+            tag: CopyDataType::Memory,
+            rw: RW::READ,
+            value: *byte,
+            is_code: None,
+            is_pad: false,
+            rwc: state.block_ctx.rwc,
+            rwc_inc_left: 0,
+        });
+        state.push_op(
+            exec_step,
+            RW::READ,
+            MemoryOp::new(src_id, (src_addr + idx).into(), *byte),
+        );
+        // Write
+        steps.push(CopyStep {
+            addr: dst_addr + idx,
+            tag: CopyDataType::Memory,
+            rw: RW::WRITE,
+            value: *byte,
+            is_code: None,
+            is_pad: false,
+            rwc: state.block_ctx.rwc,
+            rwc_inc_left: 0,
+        });
+        state.memory_write(exec_step, (dst_addr + idx).into(), *byte)?;
+    }
+
+    for cs in steps.iter_mut() {
+        cs.rwc_inc_left = state.block_ctx.rwc.0 as u64 - cs.rwc.0 as u64;
+    }
+
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod call_tests {
+    use crate::{
+        circuit_input_builder::{CopyDataType, ExecState, NumberOrHash},
+        mock::BlockData,
+        operation::{CallContextField, CallContextOp, Target, RW},
+    };
+    use eth_types::{bytecode, evm_types::OpcodeId, geth_types::GethData, Address, Word};
+    use mock::test_ctx::{helpers::*, TestContext};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn call_to_precompiled_identity() {
+        let code = bytecode! {
+            PUSH4(0x12345678)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x02) // retLength
+            PUSH1(0x40) // retOffset
+            PUSH1(0x04) // argsLength
+            PUSH1(0x1c) // argsOffset
+            PUSH1(0x00) // value
+            PUSH1(0x04) // addr
+            PUSH2(0xffff) // gas
+            CALL
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(code),
+            tx_from_1_to_0,
+            |block, _tx| block,
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let (step_index, step) = tx
+            .steps()
+            .iter()
+            .enumerate()
+            .find(|(_, step)| step.exec_state == ExecState::Op(OpcodeId::CALL))
+            .unwrap();
+        let caller_id = tx.calls()[0].call_id;
+        let callee = &tx.calls()[1];
+        assert_eq!(callee.address, Address::from_low_u64_be(4));
+        assert!(callee.is_success);
+
+        // The caller context is back in place for the steps after the call.
+        let next_step = &tx.steps()[step_index + 1];
+        assert_eq!(next_step.exec_state, ExecState::Op(OpcodeId::STOP));
+        assert_eq!(next_step.call_index, 0);
+
+        let last_callee_ops: Vec<(RW, CallContextOp)> = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::CallContext)
+            .map(|op_ref| &builder.block.container.call_context[op_ref.as_usize()])
+            .filter(|operation| {
+                matches!(
+                    operation.op().field,
+                    CallContextField::LastCalleeId
+                        | CallContextField::LastCalleeReturnDataOffset
+                        | CallContextField::LastCalleeReturnDataLength
+                )
+            })
+            .map(|operation| (operation.rw(), operation.op().clone()))
+            .collect();
+        assert_eq!(
+            last_callee_ops,
+            [
+                (CallContextField::LastCalleeId, callee.call_id.into()),
+                (CallContextField::LastCalleeReturnDataOffset, Word::zero()),
+                (CallContextField::LastCalleeReturnDataLength, Word::from(4)),
+            ]
+            .map(|(field, value)| (
+                RW::WRITE,
+                CallContextOp {
+                    call_id: caller_id,
+                    field,
+                    value,
+                }
+            ))
+            .to_vec()
+        );
+
+        // Input copied to the memory of the callee, from which the first two
+        // bytes of the output are copied back to the memory of the caller.
+        let copy_events = &builder.block.copy_events;
+        assert_eq!(copy_events.len(), 2);
+        assert_eq!(copy_events[0].src_type, CopyDataType::Memory);
+        assert_eq!(copy_events[0].src_id, NumberOrHash::Number(caller_id));
+        assert_eq!(copy_events[0].src_addr, 0x1c);
+        assert_eq!(copy_events[0].dst_id, NumberOrHash::Number(callee.call_id));
+        assert_eq!(copy_events[0].dst_addr, 0);
+        assert_eq!(copy_events[0].length, 4);
+        assert_eq!(copy_events[1].src_id, NumberOrHash::Number(callee.call_id));
+        assert_eq!(copy_events[1].src_addr_end, 4);
+        assert_eq!(copy_events[1].dst_type, CopyDataType::Memory);
+        assert_eq!(copy_events[1].dst_id, NumberOrHash::Number(caller_id));
+        assert_eq!(copy_events[1].dst_addr, 0x40);
+        assert_eq!(copy_events[1].length, 2);
+        assert_eq!(
+            copy_events[1]
+                .steps
+                .iter()
+                .filter(|step| step.rw == RW::WRITE)
+                .map(|step| step.value)
+                .collect::<Vec<u8>>(),
+            vec![0x12, 0x34]
+        );
+
+        // The cost of the call includes the gas used by the precompile.
+        assert!(step.gas_cost.as_u64() > 0);
+        assert_eq!(
+            step.gas_left.0 - step.gas_cost.as_u64(),
+            next_step.gas_left.0
+        );
+    }
+}
@@ -0,0 +1,467 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{
+        Call as CallInput, CircuitInputStateRef, CopyDataType, CopyEvent, CopyStep, ExecStep,
+        NumberOrHash,
+    },
+    operation::{AccountField, AccountOp, CallContextField, MemoryOp, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{
+    evm_types::{gas_utils::memory_expansion_gas_cost, GasCost},
+    Bytecode, GethExecStep, ToBigEndian, ToWord, Word,
+};
+use keccak256::EMPTY_HASH;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the `OpcodeId::CREATE` and `OpcodeId::CREATE2` `OpcodeId`.
+/// `IS_CREATE2` selects the latter, which takes the salt as an extra
+/// argument.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Create<const IS_CREATE2: bool>;
+
+impl<const IS_CREATE2: bool> Opcode for Create<IS_CREATE2> {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+
+        let n_pop = if IS_CREATE2 { 4 } else { 3 };
+        let tx_id = state.tx_ctx.id();
+        let current_call = state.call()?.clone();
+        // The created address is computed from the nonce of the caller (and
+        // the salt and init code hash for CREATE2) before it's increased.
+        let call = state.parse_call(geth_step)?;
+
+        // NOTE: For `RwCounterEndOfReversion` we use the `0` value as a placeholder,
+        // and later set the proper value in
+        // `CircuitInputBuilder::set_value_ops_call_context_rwc_eor`
+        for (field, value) in [
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::RwCounterEndOfReversion, 0.into()),
+            (
+                CallContextField::IsPersistent,
+                (current_call.is_persistent as u64).into(),
+            ),
+            (
+                CallContextField::CalleeAddress,
+                current_call.address.to_word(),
+            ),
+            (
+                CallContextField::IsStatic,
+                (current_call.is_static as u64).into(),
+            ),
+            (CallContextField::Depth, current_call.depth.into()),
+        ] {
+            state.call_context_read(&mut exec_step, current_call.call_id, field, value);
+        }
+
+        for i in 0..n_pop {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(i),
+                geth_step.stack.nth_last(i)?,
+            )?;
+        }
+
+        state.stack_write(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(n_pop - 1),
+            if call.is_success {
+                call.address.to_word()
+            } else {
+                Word::zero()
+            },
+        )?;
+
+        if IS_CREATE2 {
+            // The created address is taken from the hash of
+            // 0xff ++ caller ++ salt ++ keccak(init_code).
+            let salt = geth_step.stack.nth_last(3)?;
+            state.push_sha3_input(
+                [
+                    &[0xff][..],
+                    call.caller_address.as_bytes(),
+                    &salt.to_be_bytes(),
+                    call.code_hash.as_bytes(),
+                ]
+                .concat(),
+            );
+        }
+
+        // Increase caller's nonce
+        let nonce_prev = state.sdb.get_nonce(&call.caller_address);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            AccountOp {
+                address: call.caller_address,
+                field: AccountField::Nonce,
+                value: (nonce_prev + 1).into(),
+                value_prev: nonce_prev.into(),
+            },
+        )?;
+
+        // Add callee into access list
+        let is_warm = state.sdb.check_account_in_access_list(&call.address);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            TxAccessListAccountOp {
+                tx_id,
+                address: call.address,
+                is_warm: true,
+                is_warm_prev: is_warm,
+            },
+        )?;
+
+        // Switch to callee's call context
+        state.push_call(call.clone(), geth_step);
+
+        for (field, value) in [
+            (CallContextField::RwCounterEndOfReversion, 0.into()),
+            (
+                CallContextField::IsPersistent,
+                (call.is_persistent as u64).into(),
+            ),
+        ] {
+            state.call_context_read(&mut exec_step, call.call_id, field, value);
+        }
+
+        // Increase callee's nonce
+        let nonce_prev = state.sdb.get_nonce(&call.address);
+        debug_assert!(nonce_prev == 0);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            AccountOp {
+                address: call.address,
+                field: AccountField::Nonce,
+                value: 1.into(),
+                value_prev: 0.into(),
+            },
+        )?;
+
+        state.transfer(
+            &mut exec_step,
+            call.caller_address,
+            call.address,
+            call.value,
+        )?;
+
+        // Calculate next_memory_word_size and callee_gas_left manually in case
+        // there isn't next geth_step (e.g. the init code is empty).
+        let offset = geth_step.stack.nth_last(1)?.low_u64();
+        let length = geth_step.stack.nth_last(2)?.low_u64();
+        let curr_memory_word_size = geth_step.memory.word_size() as u64;
+        let next_memory_word_size = if length == 0 {
+            curr_memory_word_size
+        } else {
+            curr_memory_word_size.max((offset + length + 31) / 32)
+        };
+        let gas_cost = GasCost::CREATE.as_u64()
+            + if IS_CREATE2 {
+                (length + 31) / 32 * GasCost::COPY_SHA3.as_u64()
+            } else {
+                0
+            }
+            + memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size);
+        // All but one 64th of the gas left is passed to the callee.
+        let gas_left = geth_step.gas.0 - gas_cost;
+        let callee_gas_left = gas_left - gas_left / 64;
+
+        // There are 2 branches from here.
+        if call.code_hash.to_fixed_bytes() == *EMPTY_HASH {
+            // 1. Create with empty init code.
+            for (field, value) in [
+                (CallContextField::LastCalleeId, 0.into()),
+                (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+                (CallContextField::LastCalleeReturnDataLength, 0.into()),
+            ] {
+                state.call_context_write(&mut exec_step, current_call.call_id, field, value);
+            }
+            state.handle_return(geth_step)?;
+            Ok(vec![exec_step])
+        } else {
+            // 2. Create with non-empty init code.
+            for (field, value) in [
+                (
+                    CallContextField::ProgramCounter,
+                    (geth_step.pc.0 + 1).into(),
+                ),
+                (
+                    CallContextField::StackPointer,
+                    (geth_step.stack.stack_pointer().0 + n_pop - 1).into(),
+                ),
+                (
+                    CallContextField::GasLeft,
+                    (geth_step.gas.0 - gas_cost - callee_gas_left).into(),
+                ),
+                (CallContextField::MemorySize, next_memory_word_size.into()),
+                (
+                    CallContextField::ReversibleWriteCounter,
+                    (exec_step.reversible_write_counter + 2).into(),
+                ),
+            ] {
+                state.call_context_write(&mut exec_step, current_call.call_id, field, value);
+            }
+
+            for (field, value) in [
+                (CallContextField::CallerId, current_call.call_id.into()),
+                (CallContextField::TxId, tx_id.into()),
+                (CallContextField::Depth, call.depth.into()),
+                (
+                    CallContextField::CallerAddress,
+                    call.caller_address.to_word(),
+                ),
+                (CallContextField::CalleeAddress, call.address.to_word()),
+                (CallContextField::CallDataOffset, 0.into()),
+                (CallContextField::CallDataLength, 0.into()),
+                (CallContextField::ReturnDataOffset, 0.into()),
+                (CallContextField::ReturnDataLength, 0.into()),
+                (CallContextField::Value, call.value),
+                (CallContextField::IsSuccess, (call.is_success as u64).into()),
+                (CallContextField::IsStatic, (call.is_static as u64).into()),
+                (CallContextField::LastCalleeId, 0.into()),
+                (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+                (CallContextField::LastCalleeReturnDataLength, 0.into()),
+                (CallContextField::IsRoot, 0.into()),
+                (CallContextField::IsCreate, 1.into()),
+                (CallContextField::CodeHash, call.code_hash.to_word()),
+            ] {
+                state.call_context_read(&mut exec_step, call.call_id, field, value);
+            }
+
+            let copy_event = gen_copy_event(state, geth_step, &call, offset, length)?;
+            state.push_copy(copy_event);
+            Ok(vec![exec_step])
+        }
+    }
+}
+
+/// Generate the copy event of the init code, which is copied from the memory
+/// of the caller to the bytecode of the created call.
+fn gen_copy_event(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+    call: &CallInput,
+    offset: u64,
+    length: u64,
+) -> Result<CopyEvent, Error> {
+    let init_code = geth_step.memory.read_chunk(offset.into(), length.into());
+    let bytecode = Bytecode::from(init_code.clone());
+
+    let mut exec_step = state.new_step(geth_step)?;
+    let mut copy_steps = Vec::with_capacity(2 * init_code.len());
+    for (idx, byte) in init_code.iter().enumerate() {
+        let idx = idx as u64;
+        let rwc = state.block_ctx.rwc;
+        state.push_op(
+            &mut exec_step,
+            RW::READ,
+            MemoryOp::new(call.caller_id, (offset + idx).into(), *byte),
+        );
+        // Read
+        copy_steps.push(CopyStep {
+            addr: offset + idx,
+            tag: CopyDataType::Memory,
+            rw: RW::READ,
+            value: *byte,
+            is_code: None,
+            is_pad: false,
+            rwc,
+            rwc_inc_left: 0,
+        });
+        // Write
+        copy_steps.push(CopyStep {
+            addr: idx,
+            tag: CopyDataType::Bytecode,
+            rw: RW::WRITE,
+            value: *byte,
+            is_code: bytecode.get(idx as usize).map(|e| e.is_code),
+            is_pad: false,
+            rwc: state.block_ctx.rwc,
+            rwc_inc_left: 0,
+        });
+    }
+
+    for cs in copy_steps.iter_mut() {
+        cs.rwc_inc_left = state.block_ctx.rwc.0 as u64 - cs.rwc.0 as u64;
+    }
+
+    Ok(CopyEvent {
+        src_type: CopyDataType::Memory,
+        src_id: NumberOrHash::Number(call.caller_id),
+        src_addr: offset,
+        src_addr_end: offset + length,
+        dst_type: CopyDataType::Bytecode,
+        dst_id: NumberOrHash::Hash(call.code_hash),
+        dst_addr: 0,
+        log_id: None,
+        length,
+        steps: copy_steps,
+        tx_id: state.tx_ctx.id(),
+        call_id: call.caller_id,
+        pc: exec_step.pc,
+    })
+}
+
+#[cfg(test)]
+mod create_tests {
+    use crate::{
+        circuit_input_builder::{CopyDataType, ExecState, NumberOrHash},
+        mock::BlockData,
+        operation::{AccountField, StackOp, Target, RW},
+    };
+    use eth_types::{
+        bytecode, evm_types::OpcodeId, geth_types::GethData, Bytecode, ToWord, Word, H256,
+    };
+    use ethers_core::utils::{get_contract_address, keccak256};
+    use keccak256::EMPTY_HASH;
+    use mock::{
+        test_ctx::{helpers::*, TestContext},
+        MOCK_ACCOUNTS,
+    };
+    use pretty_assertions::assert_eq;
+
+    /// Return the code that creates a contract with `init_code`, which must be
+    /// at most 32 bytes long, and stops.
+    fn create_code(init_code: &Bytecode) -> Bytecode {
+        let init_code = init_code.to_vec();
+        bytecode! {
+            PUSH32(Word::from_big_endian(&init_code))
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(init_code.len()) // length
+            PUSH1(32 - init_code.len()) // offset
+            PUSH1(0x00) // value
+            CREATE
+            STOP
+        }
+    }
+
+    #[test]
+    fn create_deploys_returned_code() {
+        let deployed_code = bytecode! {
+            PUSH1(0x2a)
+            STOP
+        };
+        let deployed_code_bytes = deployed_code.to_vec();
+        let init_code = bytecode! {
+            PUSH3(Word::from_big_endian(&deployed_code_bytes))
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(deployed_code_bytes.len()) // length
+            PUSH1(32 - deployed_code_bytes.len()) // offset
+            RETURN
+        };
+        let init_code_bytes = init_code.to_vec();
+
+        // Get the execution steps from the external tracer
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(create_code(&init_code)),
+            tx_from_1_to_0,
+            |block, _tx| block,
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let callee = &tx.calls()[1];
+        assert!(callee.is_create());
+        assert!(callee.is_success);
+        assert_eq!(
+            callee.address,
+            get_contract_address(MOCK_ACCOUNTS[0], Word::zero())
+        );
+
+        // The init code and the deployed code are both in the code db.
+        let init_code_hash = H256(keccak256(&init_code_bytes));
+        let deployed_code_hash = H256(keccak256(&deployed_code_bytes));
+        assert_eq!(callee.code_hash, init_code_hash);
+        assert_eq!(builder.code_db.0[&init_code_hash], init_code_bytes);
+        assert_eq!(builder.code_db.0[&deployed_code_hash], deployed_code_bytes);
+        assert_eq!(
+            builder.sdb.get_account(&callee.address).1.code_hash,
+            deployed_code_hash
+        );
+
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::CREATE))
+            .unwrap();
+
+        // The created address is pushed into the stack of the caller.
+        let stack_write: Vec<StackOp> = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::Stack)
+            .map(|op_ref| &builder.block.container.stack[op_ref.as_usize()])
+            .filter(|operation| operation.rw() == RW::WRITE)
+            .map(|operation| operation.op().clone())
+            .collect();
+        assert_eq!(stack_write.len(), 1);
+        assert_eq!(stack_write[0].value, callee.address.to_word());
+
+        // The nonces of the caller and the callee are increased.
+        let nonce_ops: Vec<_> = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::Account)
+            .map(|op_ref| builder.block.container.account[op_ref.as_usize()].op())
+            .filter(|op| op.field == AccountField::Nonce)
+            .map(|op| (op.address, op.value - op.value_prev))
+            .collect();
+        assert_eq!(
+            nonce_ops,
+            vec![
+                (MOCK_ACCOUNTS[0], Word::one()),
+                (callee.address, Word::one())
+            ]
+        );
+
+        // The init code is copied from the memory of the caller.
+        let copy_event = &builder.block.copy_events[0];
+        assert_eq!(copy_event.src_type, CopyDataType::Memory);
+        assert_eq!(
+            copy_event.src_id,
+            NumberOrHash::Number(tx.calls()[0].call_id)
+        );
+        assert_eq!(copy_event.src_addr, 32 - init_code_bytes.len() as u64);
+        assert_eq!(copy_event.dst_type, CopyDataType::Bytecode);
+        assert_eq!(copy_event.dst_id, NumberOrHash::Hash(init_code_hash));
+        assert_eq!(copy_event.length, init_code_bytes.len() as u64);
+
+        // The returned code is deposited into the created account.
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::RETURN))
+            .unwrap();
+        let code_hash_ops: Vec<_> = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::Account)
+            .map(|op_ref| builder.block.container.account[op_ref.as_usize()].op())
+            .map(|op| (op.address, op.field.clone(), op.value, op.value_prev))
+            .collect();
+        assert_eq!(
+            code_hash_ops,
+            vec![(
+                callee.address,
+                AccountField::CodeHash,
+                deployed_code_hash.to_word(),
+                H256(*EMPTY_HASH).to_word()
+            )]
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use super::Opcode;
+use crate::circuit_input_builder::{CircuitInputStateRef, ExecStep, ExpEvent};
+use crate::Error;
+use eth_types::{evm_types::GasCost, GethExecStep};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::EXP`](crate::evm::OpcodeId::EXP).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Exponentiation;
+
+impl Opcode for Exponentiation {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+
+        let base = geth_step.stack.nth_last(0)?;
+        let exponent = geth_step.stack.nth_last(1)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(0), base)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(1), exponent)?;
+
+        let exponentiation = geth_steps[1].stack.last()?;
+        state.stack_write(
+            &mut exec_step,
+            geth_steps[1].stack.last_filled(),
+            exponentiation,
+        )?;
+
+        // The dynamic gas depends on the byte length of the exponent.
+        let exponent_byte_size = ((exponent.bits() + 7) / 8) as u64;
+        let gas_cost =
+            GasCost::SLOW.as_u64() + GasCost::EXP_BYTE_TIMES.as_u64() * exponent_byte_size;
+        if gas_cost != geth_step.gas_cost.as_u64() {
+            return Err(Error::InvalidGethExecStep(
+                "EXP gas cost doesn't match the byte size of the exponent",
+                geth_step.clone(),
+            ));
+        }
+
+        // A zero exponent is handled by the EVM circuit without any lookup.
+        if !exponent.is_zero() {
+            let event = ExpEvent::new(exec_step.rwc.0, base, exponent);
+            if event.exponentiation != exponentiation {
+                return Err(Error::InvalidGethExecStep(
+                    "EXP result doesn't match the exponentiation",
+                    geth_step.clone(),
+                ));
+            }
+            state.push_exponentiation(event);
+        }
+
+        Ok(vec![exec_step])
+    }
+}
+
+#[cfg(test)]
+mod exp_tests {
+    use eth_types::{
+        bytecode,
+        evm_types::{OpcodeId, StackAddress},
+        geth_types::GethData,
+        Word,
+    };
+    use mock::{
+        test_ctx::helpers::{account_0_code_account_1_no_code, tx_from_1_to_0},
+        TestContext,
+    };
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        circuit_input_builder::{ExecState, ExpEvent},
+        mock::BlockData,
+        operation::{StackOp, RW},
+    };
+
+    #[test]
+    fn exp_opcode_impl() {
+        test_ok(Word::from(2), Word::from(5));
+        test_ok(Word::from(3), Word::from(0x0101));
+        test_ok(Word::MAX, Word::MAX);
+        test_ok(Word::from(7), Word::zero());
+    }
+
+    fn test_ok(base: Word, exponent: Word) {
+        let code = bytecode! {
+            PUSH32(exponent)
+            PUSH32(base)
+            EXP
+            STOP
+        };
+
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(code),
+            tx_from_1_to_0,
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::EXP))
+            .unwrap();
+
+        let call_id = builder.block.txs()[0].calls()[step.call_index].call_id;
+        let exponentiation = base.overflowing_pow(exponent).0;
+
+        assert_eq!(
+            [0, 1, 2]
+                .map(|idx| &builder.block.container.stack[step.bus_mapping_instance[idx].as_usize()])
+                .map(|op| (op.rw(), op.op())),
+            [
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, StackAddress::from(1022), base),
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, StackAddress::from(1023), exponent),
+                ),
+                (
+                    RW::WRITE,
+                    &StackOp::new(call_id, StackAddress::from(1023), exponentiation),
+                ),
+            ]
+        );
+
+        let exp_events = &builder.block.exp_events;
+        if exponent.is_zero() {
+            assert!(exp_events.is_empty());
+        } else {
+            assert_eq!(exp_events.len(), 1);
+            assert_eq!(exp_events[0].identifier, step.rwc.0);
+            assert_eq!(exp_events[0].exponentiation, exponentiation);
+            assert_eq!(
+                exp_events[0].steps,
+                ExpEvent::new(step.rwc.0, base, exponent).steps
+            );
+        }
+    }
+}
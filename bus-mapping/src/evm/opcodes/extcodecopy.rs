@@ -0,0 +1,281 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{
+        CircuitInputStateRef, CopyDataType, CopyEvent, CopyStep, ExecStep, NumberOrHash,
+    },
+    operation::RW,
+    Error,
+};
+use eth_types::{Bytecode, GethExecStep, ToAddress, H256};
+use keccak256::EMPTY_HASH;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the
+/// [`OpcodeId::EXTCODECOPY`](crate::evm::OpcodeId::EXTCODECOPY).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Extcodecopy;
+
+impl Opcode for Extcodecopy {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let exec_steps = vec![gen_extcodecopy_step(state, geth_step)?];
+        let copy_event = gen_copy_event(state, geth_step)?;
+        state.push_copy(copy_event);
+        Ok(exec_steps)
+    }
+}
+
+fn gen_extcodecopy_step(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+) -> Result<ExecStep, Error> {
+    let mut exec_step = state.new_step(geth_step)?;
+
+    // stack reads of external address, dest_offset, code_offset and length
+    for i in 0..4 {
+        state.stack_read(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(i),
+            geth_step.stack.nth_last(i)?,
+        )?;
+    }
+
+    Ok(exec_step)
+}
+
+fn gen_copy_steps(
+    state: &mut CircuitInputStateRef,
+    exec_step: &mut ExecStep,
+    src_addr: u64,
+    dst_addr: u64,
+    bytes_left: u64,
+    src_addr_end: u64,
+    bytecode: &Bytecode,
+) -> Result<Vec<CopyStep>, Error> {
+    let mut steps = Vec::with_capacity(2 * bytes_left as usize);
+    for idx in 0..bytes_left {
+        let addr = src_addr + idx;
+        let (value, is_code, is_pad) = if addr < src_addr_end {
+            bytecode
+                .get(addr as usize)
+                .map_or((0, None, true), |e| (e.value, Some(e.is_code), false))
+        } else {
+            (0, None, true)
+        };
+        // Read
+        steps.push(CopyStep {
+            addr,
+            tag: CopyDataType::Bytecode,
+            rw: RW::READ,
+            value,
+            is_code,
+            is_pad,
+            rwc: state.block_ctx.rwc,
+            rwc_inc_left: bytes_left - idx,
+        });
+        // Write
+        steps.push(CopyStep {
+            addr: dst_addr + idx,
+            tag: CopyDataType::Memory,
+            rw: RW::WRITE,
+            value,
+            is_code: None,
+            is_pad: false,
+            rwc: state.block_ctx.rwc,
+            rwc_inc_left: bytes_left - idx,
+        });
+        state.memory_write(exec_step, (dst_addr + idx).into(), value)?;
+    }
+    Ok(steps)
+}
+
+fn gen_copy_event(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+) -> Result<CopyEvent, Error> {
+    let external_address = geth_step.stack.nth_last(0)?.to_address();
+    let dst_offset = geth_step.stack.nth_last(1)?.as_u64();
+    let code_offset = geth_step.stack.nth_last(2)?.as_u64();
+    let length = geth_step.stack.nth_last(3)?.as_u64();
+
+    // A non-existing account is copied from as if it had empty code.
+    let (exists, account) = state.sdb.get_account(&external_address);
+    let (code_hash, bytecode) = if exists {
+        let code_hash = account.code_hash;
+        (code_hash, Bytecode::from(state.code(code_hash)?))
+    } else {
+        (H256(*EMPTY_HASH), Bytecode::default())
+    };
+    let src_addr_end = bytecode.to_vec().len() as u64;
+
+    let mut exec_step = state.new_step(geth_step)?;
+    let copy_steps = gen_copy_steps(
+        state,
+        &mut exec_step,
+        code_offset,
+        dst_offset,
+        length,
+        src_addr_end,
+        &bytecode,
+    )?;
+
+    Ok(CopyEvent {
+        src_type: CopyDataType::Bytecode,
+        src_id: NumberOrHash::Hash(code_hash),
+        src_addr: code_offset,
+        src_addr_end,
+        dst_type: CopyDataType::Memory,
+        dst_id: NumberOrHash::Number(state.call()?.call_id),
+        dst_addr: dst_offset,
+        log_id: None,
+        length,
+        steps: copy_steps,
+        tx_id: state.tx_ctx.id(),
+        call_id: state.call()?.call_id,
+        pc: exec_step.pc,
+    })
+}
+
+#[cfg(test)]
+mod extcodecopy_tests {
+    use eth_types::{
+        address, bytecode,
+        evm_types::{MemoryAddress, OpcodeId, StackAddress},
+        geth_types::GethData,
+        Bytes, ToWord, Word, H256,
+    };
+    use ethers_core::utils::keccak256;
+    use mock::TestContext;
+
+    use crate::{
+        circuit_input_builder::{CopyDataType, ExecState, NumberOrHash},
+        mock::BlockData,
+        operation::{MemoryOp, StackOp, RW},
+    };
+
+    #[test]
+    fn extcodecopy_opcode_impl() {
+        test_ok(0x00, 0x00, 0x20);
+        test_ok(0x20, 0x02, 0x08);
+    }
+
+    fn test_ok(dst_offset: usize, code_offset: usize, size: usize) {
+        let external_address = address!("0xaabbccddee000000000000000000000000000000");
+        let code_ext = Bytes::from([0x60, 0x01, 0x60, 0x02, 0x01, 0x00]);
+
+        let code = bytecode! {
+            PUSH32(size)
+            PUSH32(code_offset)
+            PUSH32(dst_offset)
+            PUSH20(external_address.to_word())
+            EXTCODECOPY
+            STOP
+        };
+
+        let block: GethData = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(address!("0x0000000000000000000000000000000000000010"))
+                    .balance(Word::from(1u64 << 20))
+                    .code(code.clone());
+                accs[1].address(external_address).code(code_ext.clone());
+                accs[2]
+                    .address(address!("0x0000000000000000000000000000000000cafe01"))
+                    .balance(Word::from(1u64 << 20));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[2].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::EXTCODECOPY))
+            .unwrap();
+
+        let call_id = builder.block.txs()[0].calls()[step.call_index].call_id;
+
+        assert_eq!(
+            [0, 1, 2, 3]
+                .map(|idx| &builder.block.container.stack[step.bus_mapping_instance[idx].as_usize()])
+                .map(|op| (op.rw(), op.op())),
+            [
+                (
+                    RW::READ,
+                    &StackOp::new(
+                        call_id,
+                        StackAddress::from(1020),
+                        external_address.to_word()
+                    ),
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, StackAddress::from(1021), Word::from(dst_offset)),
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, StackAddress::from(1022), Word::from(code_offset)),
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, StackAddress::from(1023), Word::from(size)),
+                ),
+            ]
+        );
+
+        let expected_byte = |idx: usize| code_ext.get(code_offset + idx).copied().unwrap_or(0);
+
+        // RW table memory writes.
+        assert_eq!(
+            (0..size)
+                .map(|idx| &builder.block.container.memory[idx])
+                .map(|op| (op.rw(), op.op().clone()))
+                .collect::<Vec<(RW, MemoryOp)>>(),
+            (0..size)
+                .map(|idx| (
+                    RW::WRITE,
+                    MemoryOp::new(
+                        call_id,
+                        MemoryAddress::from(dst_offset + idx),
+                        expected_byte(idx)
+                    ),
+                ))
+                .collect::<Vec<(RW, MemoryOp)>>(),
+        );
+
+        let copy_events = builder.block.copy_events.clone();
+        assert_eq!(copy_events.len(), 1);
+        assert_eq!(copy_events[0].steps.len(), 2 * size);
+        assert_eq!(
+            copy_events[0].src_id,
+            NumberOrHash::Hash(H256(keccak256(&code_ext)))
+        );
+        assert_eq!(copy_events[0].src_type, CopyDataType::Bytecode);
+        assert_eq!(copy_events[0].src_addr as usize, code_offset);
+        assert_eq!(copy_events[0].src_addr_end as usize, code_ext.len());
+        assert_eq!(copy_events[0].dst_type, CopyDataType::Memory);
+        assert_eq!(copy_events[0].dst_id, NumberOrHash::Number(call_id));
+        assert_eq!(copy_events[0].dst_addr as usize, dst_offset);
+        assert_eq!(copy_events[0].length as usize, size);
+
+        for (idx, copy_rw_pair) in copy_events[0].steps.chunks(2).enumerate() {
+            let (read_step, write_step) = (&copy_rw_pair[0], &copy_rw_pair[1]);
+            assert_eq!(read_step.value, expected_byte(idx));
+            assert_eq!(read_step.is_pad, code_offset + idx >= code_ext.len());
+            assert_eq!(write_step.value, expected_byte(idx));
+        }
+    }
+}
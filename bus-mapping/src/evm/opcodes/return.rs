@@ -1,7 +1,11 @@
 use super::Opcode;
-use crate::circuit_input_builder::{CircuitInputStateRef, ExecStep};
+use crate::circuit_input_builder::{
+    CircuitInputStateRef, CopyDataType, CopyEvent, CopyStep, ExecStep, NumberOrHash,
+};
+use crate::operation::{AccountField, AccountOp, MemoryOp, RW};
 use crate::Error;
-use eth_types::GethExecStep;
+use eth_types::{Bytecode, GethExecStep, ToWord, H256};
+use ethers_core::utils::keccak256;
 
 /// Placeholder structure used to implement [`Opcode`] trait over it
 /// corresponding to the [`OpcodeId::RETURN`](crate::evm::OpcodeId::RETURN).
@@ -14,11 +18,165 @@ impl Opcode for Return {
         geth_steps: &[GethExecStep],
     ) -> Result<Vec<ExecStep>, Error> {
         let geth_step = &geth_steps[0];
-        let exec_step = state.new_step(geth_step)?;
+        let mut exec_step = state.new_step(geth_step)?;
 
         // TODO: Generate associated operations of RETURN
 
+        // Deposit the returned bytes as the code of the created account if
+        // it's a successful create.
+        let call = state.call()?.clone();
+        if call.is_create() && call.is_success {
+            let offset = geth_step.stack.nth_last(0)?.low_u64();
+            let length = geth_step.stack.nth_last(1)?.low_u64();
+            let code = geth_step.memory.read_chunk(offset.into(), length.into());
+            let code_hash = state.code_db.insert(code);
+            let (found, callee_account) = state.sdb.get_account(&call.address);
+            if !found {
+                return Err(Error::AccountNotFound(call.address));
+            }
+            let code_hash_prev = callee_account.code_hash;
+            state.push_op_reversible(
+                &mut exec_step,
+                RW::WRITE,
+                AccountOp {
+                    address: call.address,
+                    field: AccountField::CodeHash,
+                    value: code_hash.to_word(),
+                    value_prev: code_hash_prev.to_word(),
+                },
+            )?;
+        }
+
+        if let Some(copy_event) = gen_copy_event(state, geth_step)? {
+            state.push_copy(copy_event);
+        }
+
         state.handle_return(geth_step)?;
         Ok(vec![exec_step])
     }
 }
+
+/// Generate the copy event of the returned bytes, which go to the bytecode of
+/// the created contract for a successful create, or to the memory of the
+/// caller for an internal call.  No copy event is generated when no bytes are
+/// copied.
+fn gen_copy_event(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+) -> Result<Option<CopyEvent>, Error> {
+    let offset = geth_step.stack.nth_last(0)?.low_u64();
+    let length = geth_step.stack.nth_last(1)?.low_u64();
+    let call = state.call()?.clone();
+
+    let (dst_type, dst_id, dst_addr, length) = if call.is_create() && call.is_success {
+        let code = geth_step.memory.read_chunk(offset.into(), length.into());
+        (
+            CopyDataType::Bytecode,
+            NumberOrHash::Hash(H256(keccak256(&code))),
+            0,
+            length,
+        )
+    } else if !call.is_root {
+        (
+            CopyDataType::Memory,
+            NumberOrHash::Number(call.caller_id),
+            call.return_data_offset,
+            length.min(call.return_data_length),
+        )
+    } else {
+        return Ok(None);
+    };
+    if length == 0 {
+        return Ok(None);
+    }
+
+    let mut exec_step = state.new_step(geth_step)?;
+    let copy_steps = gen_copy_steps(
+        state,
+        geth_step,
+        &mut exec_step,
+        offset,
+        dst_type,
+        &dst_id,
+        dst_addr,
+        length,
+    )?;
+
+    Ok(Some(CopyEvent {
+        src_type: CopyDataType::Memory,
+        src_id: NumberOrHash::Number(call.call_id),
+        src_addr: offset,
+        src_addr_end: offset + length,
+        dst_type,
+        dst_id,
+        dst_addr,
+        log_id: None,
+        length,
+        steps: copy_steps,
+        tx_id: state.tx_ctx.id(),
+        call_id: call.call_id,
+        pc: exec_step.pc,
+    }))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn gen_copy_steps(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+    exec_step: &mut ExecStep,
+    src_addr: u64,
+    dst_type: CopyDataType,
+    dst_id: &NumberOrHash,
+    dst_addr: u64,
+    bytes_left: u64,
+) -> Result<Vec<CopyStep>, Error> {
+    let data = geth_step
+        .memory
+        .read_chunk(src_addr.into(), bytes_left.into());
+    let bytecode = Bytecode::from(data.clone());
+    let mut copy_steps = Vec::with_capacity(2 * bytes_left as usize);
+    for (idx, byte) in data.iter().enumerate() {
+        let idx = idx as u64;
+        let rwc = state.block_ctx.rwc;
+        state.memory_read(exec_step, (src_addr + idx).into(), *byte)?;
+        // Read
+        copy_steps.push(CopyStep {
+            addr: src_addr + idx,
+            tag: CopyDataType::Memory,
+            rw: RW::READ,
+            value: *byte,
+            is_code: None,
+            is_pad: false,
+            rwc,
+            rwc_inc_left: 0,
+        });
+        // Write
+        let is_code = match dst_type {
+            CopyDataType::Bytecode => bytecode.get(idx as usize).map(|e| e.is_code),
+            _ => None,
+        };
+        copy_steps.push(CopyStep {
+            addr: dst_addr + idx,
+            tag: dst_type,
+            rw: RW::WRITE,
+            value: *byte,
+            is_code,
+            is_pad: false,
+            rwc: state.block_ctx.rwc,
+            rwc_inc_left: 0,
+        });
+        if let NumberOrHash::Number(caller_id) = dst_id {
+            state.push_op(
+                exec_step,
+                RW::WRITE,
+                MemoryOp::new(*caller_id, (dst_addr + idx).into(), *byte),
+            );
+        }
+    }
+
+    for cs in copy_steps.iter_mut() {
+        cs.rwc_inc_left = state.block_ctx.rwc.0 as u64 - cs.rwc.0 as u64;
+    }
+
+    Ok(copy_steps)
+}
@@ -0,0 +1,284 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{
+        CircuitInputStateRef, CopyDataType, CopyEvent, CopyStep, ExecStep, NumberOrHash,
+    },
+    operation::{MemoryOp, RW},
+    Error,
+};
+use eth_types::GethExecStep;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the
+/// [`OpcodeId::RETURNDATACOPY`](crate::evm::OpcodeId::RETURNDATACOPY).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Returndatacopy;
+
+impl Opcode for Returndatacopy {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let exec_steps = vec![gen_returndatacopy_step(state, geth_step)?];
+        let copy_event = gen_copy_event(state, geth_step)?;
+        state.push_copy(copy_event);
+        Ok(exec_steps)
+    }
+}
+
+fn gen_returndatacopy_step(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+) -> Result<ExecStep, Error> {
+    let mut exec_step = state.new_step(geth_step)?;
+
+    // stack reads of dest_offset, offset and length
+    for i in 0..3 {
+        state.stack_read(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(i),
+            geth_step.stack.nth_last(i)?,
+        )?;
+    }
+
+    Ok(exec_step)
+}
+
+fn gen_copy_steps(
+    state: &mut CircuitInputStateRef,
+    exec_step: &mut ExecStep,
+    src_addr: u64,
+    dst_addr: u64,
+    data: &[u8],
+) -> Result<Vec<CopyStep>, Error> {
+    let callee_id = state.call_ctx()?.last_callee_id;
+    let mut steps = Vec::with_capacity(2 * data.len());
+    for (idx, byte) in data.iter().enumerate() {
+        let idx = idx as u64;
+        // Read
+        steps.push(CopyStep {
+            addr: src_addr + idx,
+            tag: CopyDataType::Memory,
+            rw: RW::READ,
+            value: *byte,
+            is_code: None,
+            is_pad: false,
+            rwc: state.block_ctx.rwc,
+            rwc_inc_left: 0,
+        });
+        state.push_op(
+            exec_step,
+            RW::READ,
+            MemoryOp::new(callee_id, (src_addr + idx).into(), *byte),
+        );
+        // Write
+        steps.push(CopyStep {
+            addr: dst_addr + idx,
+            tag: CopyDataType::Memory,
+            rw: RW::WRITE,
+            value: *byte,
+            is_code: None,
+            is_pad: false,
+            rwc: state.block_ctx.rwc,
+            rwc_inc_left: 0,
+        });
+        state.memory_write(exec_step, (dst_addr + idx).into(), *byte)?;
+    }
+
+    for cs in steps.iter_mut() {
+        cs.rwc_inc_left = state.block_ctx.rwc.0 as u64 - cs.rwc.0 as u64;
+    }
+
+    Ok(steps)
+}
+
+fn gen_copy_event(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+) -> Result<CopyEvent, Error> {
+    let dst_offset = geth_step.stack.nth_last(0)?.as_u64();
+    let data_offset = geth_step.stack.nth_last(1)?.as_u64();
+    let length = geth_step.stack.nth_last(2)?.as_u64();
+
+    // The return data is copied from the memory of the last callee, which has
+    // been checked by geth to contain `data_offset + length` bytes.
+    let call_ctx = state.call_ctx()?;
+    let callee_id = call_ctx.last_callee_id;
+    let src_addr = call_ctx.last_callee_return_data_offset + data_offset;
+    let src_addr_end = call_ctx.last_callee_return_data_offset + call_ctx.return_data.len() as u64;
+    let data = call_ctx.return_data[data_offset as usize..(data_offset + length) as usize].to_vec();
+
+    let mut exec_step = state.new_step(geth_step)?;
+    let copy_steps = gen_copy_steps(state, &mut exec_step, src_addr, dst_offset, &data)?;
+
+    Ok(CopyEvent {
+        src_type: CopyDataType::Memory,
+        src_id: NumberOrHash::Number(callee_id),
+        src_addr,
+        src_addr_end,
+        dst_type: CopyDataType::Memory,
+        dst_id: NumberOrHash::Number(state.call()?.call_id),
+        dst_addr: dst_offset,
+        log_id: None,
+        length,
+        steps: copy_steps,
+        tx_id: state.tx_ctx.id(),
+        call_id: state.call()?.call_id,
+        pc: exec_step.pc,
+    })
+}
+
+#[cfg(test)]
+mod returndatacopy_tests {
+    use crate::{
+        circuit_input_builder::{CopyDataType, ExecState, NumberOrHash},
+        mock::BlockData,
+        operation::{MemoryOp, StackOp, RW},
+    };
+    use eth_types::{
+        bytecode,
+        evm_types::{OpcodeId, StackAddress},
+        geth_types::GethData,
+        ToWord, Word,
+    };
+    use mock::test_ctx::TestContext;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn returndatacopy_opcode_impl() {
+        let (addr_a, addr_b) = (mock::MOCK_ACCOUNTS[0], mock::MOCK_ACCOUNTS[1]);
+
+        // code B returns 0x20 bytes from offset 0x20 of its memory.
+        let return_data_offset = 0x20usize;
+        let return_data_length = 0x20usize;
+        let value = Word::from_big_endian(&[0xcd; 32]);
+        let code_b = bytecode! {
+            PUSH32(value)
+            PUSH1(return_data_offset)
+            MSTORE
+            PUSH1(return_data_length)
+            PUSH1(return_data_offset)
+            RETURN
+        };
+
+        // code A calls code B and copies part of its return data.
+        let dst_offset = 0x00usize;
+        let data_offset = 0x08usize;
+        let size = 0x10usize;
+        let code_a = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH32(addr_b.to_word()) // addr
+            PUSH32(0x1_0000) // gas
+            CALL
+            PUSH1(size)
+            PUSH1(data_offset)
+            PUSH1(dst_offset)
+            RETURNDATACOPY
+            STOP
+        };
+
+        let block: GethData = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].address(addr_b).code(code_b);
+                accs[1].address(addr_a).code(code_a);
+                accs[2]
+                    .address(mock::MOCK_ACCOUNTS[2])
+                    .balance(Word::from(1u64 << 30));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[1].address).from(accs[2].address);
+            },
+            |block, _tx| block,
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::RETURNDATACOPY))
+            .unwrap();
+
+        let call_id = builder.block.txs()[0].calls()[step.call_index].call_id;
+        let callee_id = builder.block.txs()[0].calls()[1].call_id;
+
+        assert_eq!(
+            [0, 1, 2]
+                .map(|idx| &builder.block.container.stack[step.bus_mapping_instance[idx].as_usize()])
+                .map(|op| (op.rw(), op.op())),
+            [
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, StackAddress::from(1020), Word::from(dst_offset)),
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, StackAddress::from(1021), Word::from(data_offset)),
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, StackAddress::from(1022), Word::from(size)),
+                ),
+            ]
+        );
+
+        let copy_events = builder.block.copy_events.clone();
+        assert_eq!(copy_events.len(), 1);
+        assert_eq!(copy_events[0].src_type, CopyDataType::Memory);
+        assert_eq!(copy_events[0].src_id, NumberOrHash::Number(callee_id));
+        assert_eq!(
+            copy_events[0].src_addr as usize,
+            return_data_offset + data_offset
+        );
+        assert_eq!(
+            copy_events[0].src_addr_end as usize,
+            return_data_offset + return_data_length
+        );
+        assert_eq!(copy_events[0].dst_type, CopyDataType::Memory);
+        assert_eq!(copy_events[0].dst_id, NumberOrHash::Number(call_id));
+        assert_eq!(copy_events[0].dst_addr as usize, dst_offset);
+        assert_eq!(copy_events[0].length as usize, size);
+        assert_eq!(copy_events[0].steps.len(), 2 * size);
+
+        // The reads from the callee memory are followed by the writes to the
+        // caller memory.
+        let memory_ops = builder
+            .block
+            .container
+            .memory
+            .iter()
+            .filter(|op| op.rwc().0 > step.rwc.0)
+            .map(|op| (op.rw(), op.op().clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            memory_ops,
+            (0..size)
+                .flat_map(|idx| [
+                    (
+                        RW::READ,
+                        MemoryOp::new(
+                            callee_id,
+                            (return_data_offset + data_offset + idx).into(),
+                            0xcd
+                        )
+                    ),
+                    (
+                        RW::WRITE,
+                        MemoryOp::new(call_id, (dst_offset + idx).into(), 0xcd)
+                    ),
+                ])
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,398 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    operation::{
+        AccountDestructedOp, AccountField, AccountOp, CallContextField, TxAccessListAccountOp,
+        TxRefundOp, RW,
+    },
+    Error,
+};
+use eth_types::{GethExecStep, ToAddress, ToWord, Word};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the `OpcodeId::SELFDESTRUCT` `OpcodeId`.
+///
+/// The balance of the account is moved to the beneficiary and the account is
+/// marked as destructed, which is reverted with the other writes of the call
+/// when the call or one of its callers fails.  The destructed accounts are
+/// only deleted at the end of the transaction, in
+/// [`gen_end_tx_ops`](super::gen_end_tx_ops).  The first destruction of an
+/// account in a transaction is refunded before EIP-3529 (London), and since
+/// EIP-6780 (Cancun) only the accounts created in the same transaction are
+/// destructed, the others only moving their balance to the beneficiary.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Selfdestruct;
+
+impl Opcode for Selfdestruct {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+
+        let tx_id = state.tx_ctx.id();
+        let call = state.call()?.clone();
+        let sender = call.address;
+        let beneficiary = geth_step.stack.last()?.to_address();
+
+        for (field, value) in [
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::CalleeAddress, sender.to_word()),
+        ] {
+            state.call_context_read(&mut exec_step, call.call_id, field, value);
+        }
+
+        state.stack_read(
+            &mut exec_step,
+            geth_step.stack.last_filled(),
+            beneficiary.to_word(),
+        )?;
+
+        let is_warm = state.sdb.check_account_in_access_list(&beneficiary);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            TxAccessListAccountOp {
+                tx_id,
+                address: beneficiary,
+                is_warm: true,
+                is_warm_prev: is_warm,
+            },
+        )?;
+
+        let hardfork = state.block.hardfork();
+        let is_destructed = !hardfork.selfdestruct_only_in_creation_tx()
+            || state
+                .tx
+                .calls()
+                .iter()
+                .any(|call| call.is_create() && call.address == sender);
+
+        // The balance of the sender is zeroed and credited to the beneficiary.
+        // When the beneficiary is the sender itself, the balance is burnt if
+        // the account is destructed, and kept otherwise.
+        let (found, sender_account) = state.sdb.get_account(&sender);
+        if !found {
+            return Err(Error::AccountNotFound(sender));
+        }
+        let value = sender_account.balance;
+        if beneficiary != sender || is_destructed {
+            state.push_op_reversible(
+                &mut exec_step,
+                RW::WRITE,
+                AccountOp {
+                    address: sender,
+                    field: AccountField::Balance,
+                    value: Word::zero(),
+                    value_prev: value,
+                },
+            )?;
+        }
+        if beneficiary != sender {
+            let (_, beneficiary_account) = state.sdb.get_account(&beneficiary);
+            let beneficiary_balance_prev = beneficiary_account.balance;
+            state.push_op_reversible(
+                &mut exec_step,
+                RW::WRITE,
+                AccountOp {
+                    address: beneficiary,
+                    field: AccountField::Balance,
+                    value: beneficiary_balance_prev + value,
+                    value_prev: beneficiary_balance_prev,
+                },
+            )?;
+        }
+
+        if is_destructed {
+            let is_destructed_prev = state.sdb.check_account_destructed(&sender);
+            let refund = hardfork.selfdestruct_refund().as_u64();
+            if refund > 0 && !is_destructed_prev {
+                let refund_prev = state.sdb.refund();
+                state.push_op_reversible(
+                    &mut exec_step,
+                    RW::WRITE,
+                    TxRefundOp {
+                        tx_id,
+                        value_prev: refund_prev,
+                        value: refund_prev + refund,
+                    },
+                )?;
+            }
+            state.push_op_reversible(
+                &mut exec_step,
+                RW::WRITE,
+                AccountDestructedOp {
+                    tx_id,
+                    address: sender,
+                    is_destructed: true,
+                    is_destructed_prev,
+                },
+            )?;
+        }
+
+        state.gen_restore_context_ops(&mut exec_step, geth_steps)?;
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
+
+#[cfg(test)]
+mod selfdestruct_tests {
+    use super::*;
+    use crate::{
+        circuit_input_builder::ExecState, evm::OpcodeId, mock::BlockData, operation::Target,
+        state_db::Account,
+    };
+    use eth_types::{bytecode, geth_types::GethData, Address, H256};
+    use ethers_core::utils::keccak256;
+    use mock::{
+        eth,
+        test_ctx::{helpers::*, TestContext},
+        MOCK_ACCOUNTS,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn selfdestruct_reverted_by_caller() {
+        let addr_a = MOCK_ACCOUNTS[0];
+        let addr_b = MOCK_ACCOUNTS[1];
+        let beneficiary = MOCK_ACCOUNTS[3];
+
+        let code_b = bytecode! {
+            PUSH32(beneficiary.to_word())
+            SELFDESTRUCT
+        };
+        // code A calls code B, then reverts.
+        let code_a = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH32(addr_b.to_word()) // addr
+            PUSH32(0x1_0000) // gas
+            CALL
+            PUSH1(0x00)
+            PUSH1(0x00)
+            REVERT
+        };
+
+        // Get the execution steps from the external tracer
+        let block: GethData = TestContext::<4, 1>::new(
+            None,
+            |accs| {
+                accs[0].address(addr_a).code(code_a);
+                accs[1].address(addr_b).code(code_b).balance(eth(1));
+                accs[2].address(MOCK_ACCOUNTS[2]).balance(eth(10));
+                accs[3].address(beneficiary);
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[2].address);
+            },
+            |block, _tx| block,
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::SELFDESTRUCT))
+            .unwrap();
+
+        // The destruction and its reversion, both recorded in the SELFDESTRUCT
+        // step.
+        let destructed_ops: Vec<(bool, bool, bool)> = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::AccountDestructed)
+            .map(|op_ref| {
+                let operation = &builder.block.container.account_destructed[op_ref.as_usize()];
+                let op = operation.op();
+                assert_eq!(op.address, addr_b);
+                (
+                    operation.reversible(),
+                    op.is_destructed,
+                    op.is_destructed_prev,
+                )
+            })
+            .collect();
+        assert_eq!(
+            destructed_ops,
+            vec![(true, true, false), (false, false, true)]
+        );
+
+        // The balance transferred to the beneficiary is reverted as well.
+        let balance_ops: Vec<(Address, Word, Word)> = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::Account)
+            .map(|op_ref| {
+                let op = builder.block.container.account[op_ref.as_usize()].op();
+                (op.address, op.value, op.value_prev)
+            })
+            .collect();
+        assert_eq!(
+            balance_ops,
+            vec![
+                (addr_b, Word::zero(), eth(1)),
+                (beneficiary, eth(1), Word::zero()),
+                (beneficiary, Word::zero(), eth(1)),
+                (addr_b, eth(1), Word::zero()),
+            ]
+        );
+
+        // The account is not reset when the transaction is committed.
+        let (found, account) = builder.sdb.get_account(&addr_b);
+        assert!(found);
+        assert_eq!(account.balance, eth(1));
+        assert!(!account.code_hash.is_zero());
+    }
+
+    #[test]
+    fn selfdestruct_deleted_at_end_tx() {
+        let code = bytecode! {
+            PUSH32(MOCK_ACCOUNTS[0].to_word())
+            SELFDESTRUCT
+        };
+
+        // Get the execution steps from the external tracer
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(code),
+            tx_from_1_to_0,
+            |block, _tx| block,
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let account_ops = |exec_state: ExecState| -> Vec<(AccountField, Word, Word)> {
+            let step = tx
+                .steps()
+                .iter()
+                .find(|step| step.exec_state == exec_state)
+                .unwrap();
+            step.bus_mapping_instance
+                .iter()
+                .filter(|op_ref| op_ref.target() == Target::Account)
+                .map(|op_ref| builder.block.container.account[op_ref.as_usize()].op())
+                .filter(|op| op.address == MOCK_ACCOUNTS[0])
+                .map(|op| (op.field.clone(), op.value, op.value_prev))
+                .collect()
+        };
+
+        // The balance sent to the account itself is burnt.
+        assert_eq!(
+            account_ops(ExecState::Op(OpcodeId::SELFDESTRUCT)),
+            vec![(AccountField::Balance, Word::zero(), eth(10) + tx.value)]
+        );
+
+        // The account is deleted at the end of the transaction.
+        let account = block
+            .accounts
+            .iter()
+            .find(|account| account.address == MOCK_ACCOUNTS[0])
+            .unwrap();
+        assert_eq!(
+            account_ops(ExecState::EndTx),
+            vec![
+                (AccountField::Nonce, Word::zero(), account.nonce),
+                (AccountField::Balance, Word::zero(), Word::zero()),
+                (
+                    AccountField::CodeHash,
+                    Account::zero().code_hash.to_word(),
+                    H256(keccak256(&account.code)).to_word()
+                ),
+            ]
+        );
+        let (_, account) = builder.sdb.get_account(&MOCK_ACCOUNTS[0]);
+        assert!(account.is_empty());
+    }
+
+    #[test]
+    fn selfdestruct_hardforks() {
+        let code = bytecode! {
+            PUSH32(MOCK_ACCOUNTS[0].to_word())
+            SELFDESTRUCT
+        };
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(code),
+            tx_from_1_to_0,
+            |block, _tx| block,
+        )
+        .unwrap()
+        .into();
+
+        // Returns the targets of the operations of the SELFDESTRUCT step on the
+        // accounts and the refund, the refund of the transaction and whether
+        // the account is deleted, when the block is handled with London active
+        // from `london_block` and Cancun from `cancun_time`.
+        let handle_block = |london_block: Option<u64>, cancun_time: Option<u64>| {
+            let mut builder =
+                BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+            builder.block.chain_config.london_block = london_block;
+            builder.block.chain_config.cancun_time = cancun_time;
+            builder
+                .handle_block(&block.eth_block, &block.geth_traces)
+                .unwrap();
+            let step = builder.block.txs()[0]
+                .steps()
+                .iter()
+                .find(|step| step.exec_state == ExecState::Op(OpcodeId::SELFDESTRUCT))
+                .unwrap();
+            let targets: Vec<Target> = step
+                .bus_mapping_instance
+                .iter()
+                .map(|op_ref| op_ref.target())
+                .filter(|target| {
+                    matches!(
+                        target,
+                        Target::Account | Target::TxRefund | Target::AccountDestructed
+                    )
+                })
+                .collect();
+            let refund = builder
+                .block
+                .container
+                .tx_refund
+                .iter()
+                .map(|op| op.op().value)
+                .max();
+            let (_, account) = builder.sdb.get_account(&MOCK_ACCOUNTS[0]);
+            (targets, refund, account.is_empty())
+        };
+
+        // Before London, the destruction is refunded.
+        let (targets, refund, is_deleted) = handle_block(None, None);
+        assert_eq!(
+            targets,
+            vec![Target::Account, Target::TxRefund, Target::AccountDestructed]
+        );
+        assert_eq!(refund, Some(24000));
+        assert!(is_deleted);
+
+        // Since London, it isn't.
+        let (targets, _, is_deleted) = handle_block(Some(0), None);
+        assert_eq!(targets, vec![Target::Account, Target::AccountDestructed]);
+        assert!(is_deleted);
+
+        // Since Cancun, the account not created in the transaction is kept
+        // along with its balance.
+        let (targets, _, is_deleted) = handle_block(Some(0), Some(0));
+        assert!(targets.is_empty());
+        assert!(!is_deleted);
+    }
+}
@@ -0,0 +1,239 @@
+use super::Opcode;
+use crate::circuit_input_builder::{CircuitInputStateRef, ExecStep};
+use crate::circuit_input_builder::{CopyDataType, CopyEvent, CopyStep, NumberOrHash};
+use crate::operation::RW;
+use crate::Error;
+use eth_types::GethExecStep;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::SHA3`](crate::evm::OpcodeId::SHA3).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Sha3;
+
+impl Opcode for Sha3 {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+
+        let offset = geth_step.stack.nth_last(0)?;
+        let size = geth_step.stack.nth_last(1)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(0), offset)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(1), size)?;
+
+        // The hash is read from the stack of the next step.
+        state.stack_write(
+            &mut exec_step,
+            geth_steps[1].stack.last_filled(),
+            geth_steps[1].stack.last()?,
+        )?;
+
+        let input = geth_step
+            .memory
+            .read_chunk(offset.low_u64().into(), size.low_u64().into());
+        state.push_sha3_input(input);
+
+        let copy_event = gen_copy_event(state, geth_step)?;
+        state.push_copy(copy_event);
+
+        Ok(vec![exec_step])
+    }
+}
+
+fn gen_copy_steps(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+    exec_step: &mut ExecStep,
+    src_addr: u64,
+    bytes_left: u64,
+) -> Result<Vec<CopyStep>, Error> {
+    let mem = geth_step
+        .memory
+        .read_chunk(src_addr.into(), bytes_left.into());
+    let mut copy_steps = Vec::with_capacity(2 * bytes_left as usize);
+    for (idx, byte) in mem.iter().enumerate() {
+        let addr = src_addr + idx as u64;
+        let rwc = state.block_ctx.rwc;
+        state.memory_read(exec_step, addr.into(), *byte)?;
+        // Read
+        copy_steps.push(CopyStep {
+            addr,
+            tag: CopyDataType::Memory,
+            rw: RW::READ,
+            value: *byte,
+            is_code: None,
+            is_pad: false,
+            rwc,
+            rwc_inc_left: 0,
+        });
+        // Write
+        copy_steps.push(CopyStep {
+            addr: idx as u64,
+            tag: CopyDataType::RlcAcc,
+            rw: RW::WRITE,
+            value: *byte,
+            is_code: None,
+            is_pad: false,
+            rwc: state.block_ctx.rwc,
+            rwc_inc_left: 0,
+        });
+    }
+
+    for cs in copy_steps.iter_mut() {
+        cs.rwc_inc_left = state.block_ctx.rwc.0 as u64 - cs.rwc.0 as u64;
+    }
+
+    Ok(copy_steps)
+}
+
+fn gen_copy_event(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+) -> Result<CopyEvent, Error> {
+    let offset = geth_step.stack.nth_last(0)?.as_u64();
+    let size = geth_step.stack.nth_last(1)?.as_u64();
+
+    let mut exec_step = state.new_step(geth_step)?;
+    let copy_steps = gen_copy_steps(state, geth_step, &mut exec_step, offset, size)?;
+
+    Ok(CopyEvent {
+        src_type: CopyDataType::Memory,
+        src_id: NumberOrHash::Number(state.call()?.call_id),
+        src_addr: offset,
+        src_addr_end: offset + size,
+        dst_type: CopyDataType::RlcAcc,
+        dst_id: NumberOrHash::Number(0),
+        dst_addr: 0,
+        log_id: None,
+        length: size,
+        steps: copy_steps,
+        tx_id: state.tx_ctx.id(),
+        call_id: state.call()?.call_id,
+        pc: exec_step.pc,
+    })
+}
+
+#[cfg(test)]
+mod sha3_tests {
+    use eth_types::{
+        bytecode,
+        evm_types::{OpcodeId, StackAddress},
+        geth_types::GethData,
+        Word,
+    };
+    use ethers_core::utils::keccak256;
+    use mock::{
+        test_ctx::helpers::{account_0_code_account_1_no_code, tx_from_1_to_0},
+        TestContext,
+    };
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        circuit_input_builder::{CopyDataType, ExecState, NumberOrHash},
+        mock::BlockData,
+        operation::{MemoryOp, StackOp, RW},
+    };
+
+    #[test]
+    fn sha3_opcode_impl() {
+        test_ok(0x00, 0x20);
+        test_ok(0x10, 0x28);
+    }
+
+    fn test_ok(offset: usize, size: usize) {
+        let value = Word::from_big_endian(&[0xab; 32]);
+        let code = bytecode! {
+            PUSH32(value)
+            PUSH32(0x00)
+            MSTORE
+            PUSH32(size)
+            PUSH32(offset)
+            SHA3
+            STOP
+        };
+
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(code),
+            tx_from_1_to_0,
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::SHA3))
+            .unwrap();
+
+        let call_id = builder.block.txs()[0].calls()[step.call_index].call_id;
+
+        let mut memory = vec![0u8; offset + size];
+        memory[..32].copy_from_slice(&[0xab; 32]);
+        let data = memory[offset..offset + size].to_vec();
+        let hash = Word::from_big_endian(&keccak256(&data));
+
+        assert_eq!(
+            [0, 1, 2]
+                .map(|idx| &builder.block.container.stack[step.bus_mapping_instance[idx].as_usize()])
+                .map(|op| (op.rw(), op.op())),
+            [
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, StackAddress::from(1022), Word::from(offset)),
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, StackAddress::from(1023), Word::from(size)),
+                ),
+                (
+                    RW::WRITE,
+                    &StackOp::new(call_id, StackAddress::from(1023), hash),
+                ),
+            ]
+        );
+
+        let copy_events = builder.block.copy_events.clone();
+        assert_eq!(copy_events.len(), 1);
+        assert_eq!(copy_events[0].src_type, CopyDataType::Memory);
+        assert_eq!(copy_events[0].src_id, NumberOrHash::Number(call_id));
+        assert_eq!(copy_events[0].src_addr as usize, offset);
+        assert_eq!(copy_events[0].src_addr_end as usize, offset + size);
+        assert_eq!(copy_events[0].dst_type, CopyDataType::RlcAcc);
+        assert_eq!(copy_events[0].length as usize, size);
+        assert_eq!(copy_events[0].steps.len(), 2 * size);
+
+        for (idx, copy_rw_pair) in copy_events[0].steps.chunks(2).enumerate() {
+            let (read_step, write_step) = (&copy_rw_pair[0], &copy_rw_pair[1]);
+            assert_eq!(read_step.value, data[idx]);
+            assert_eq!(write_step.value, data[idx]);
+            assert_eq!(write_step.addr as usize, idx);
+        }
+
+        let memory_reads = builder
+            .block
+            .container
+            .memory
+            .iter()
+            .filter(|op| op.rw() == RW::READ)
+            .map(|op| (op.rw(), op.op().clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            memory_reads,
+            (0..size)
+                .map(|idx| (
+                    RW::READ,
+                    MemoryOp::new(call_id, (offset + idx).into(), data[idx])
+                ))
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,10 +1,10 @@
 //! Mock types and functions to generate mock data useful for tests
 
 use crate::{
-    circuit_input_builder::{Block, CircuitInputBuilder},
+    circuit_input_builder::{Block, CircuitInputBuilder, CircuitsParams},
     state_db::{self, CodeDB, StateDB},
 };
-use eth_types::{geth_types::GethData, Word};
+use eth_types::{geth_types::GethData, Withdrawal, Word};
 
 /// BlockData is a type that contains all the information from a block required
 /// to build the circuit inputs.
@@ -23,21 +23,39 @@ pub struct BlockData {
     pub eth_block: eth_types::Block<eth_types::Transaction>,
     /// Execution Trace from geth
     pub geth_traces: Vec<eth_types::GethExecTrace>,
+    /// Withdrawals of the block
+    pub withdrawals: Vec<Withdrawal>,
+    /// Capacities of the circuits proving the block
+    pub circuits_params: CircuitsParams,
 }
 
 impl BlockData {
     /// Generate a new CircuitInputBuilder initialized with the context of the
     /// BlockData.
     pub fn new_circuit_input_builder(&self) -> CircuitInputBuilder {
-        CircuitInputBuilder::new(
-            self.sdb.clone(),
-            self.code_db.clone(),
-            Block::new(self.chain_id, self.history_hashes.clone(), &self.eth_block).unwrap(),
+        let mut block = Block::new(
+            self.chain_id,
+            self.history_hashes.clone(),
+            &self.eth_block,
+            self.circuits_params,
         )
+        .unwrap();
+        block.withdrawals = self.withdrawals.clone();
+        CircuitInputBuilder::new(self.sdb.clone(), self.code_db.clone(), block)
     }
 
-    /// Create a new block from the given Geth data.
+    /// Create a new block from the given Geth data, with the default
+    /// capacities of the circuits.
     pub fn new_from_geth_data(geth_data: GethData) -> Self {
+        Self::new_from_geth_data_with_params(geth_data, CircuitsParams::default())
+    }
+
+    /// Create a new block from the given Geth data, proven by circuits with
+    /// the capacities of `circuits_params`.
+    pub fn new_from_geth_data_with_params(
+        geth_data: GethData,
+        circuits_params: CircuitsParams,
+    ) -> Self {
         let mut sdb = StateDB::new();
         let mut code_db = CodeDB::new();
 
@@ -48,6 +66,9 @@ impl BlockData {
                 sdb.set_account(to, state_db::Account::zero());
             }
         }
+        for withdrawal in geth_data.withdrawals.iter() {
+            sdb.set_account(&withdrawal.address, state_db::Account::zero());
+        }
 
         for account in geth_data.accounts {
             let code_hash = code_db.insert(account.code.to_vec());
@@ -69,6 +90,8 @@ impl BlockData {
             history_hashes: geth_data.history_hashes,
             eth_block: geth_data.eth_block,
             geth_traces: geth_data.geth_traces,
+            withdrawals: geth_data.withdrawals,
+            circuits_params,
         }
     }
 }
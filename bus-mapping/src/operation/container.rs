@@ -1,10 +1,11 @@
 use super::{
-    AccountDestructedOp, AccountOp, CallContextOp, MemoryOp, Op, OpEnum, Operation, RWCounter,
-    StackOp, StorageOp, Target, TxAccessListAccountOp, TxAccessListAccountStorageOp, TxLogOp,
-    TxReceiptOp, TxRefundOp, RW,
+    AccountDestructedOp, AccountOp, CallContextField, CallContextOp, MemoryOp, Op, OpEnum,
+    Operation, RWCounter, StackOp, StorageOp, Target, TxAccessListAccountOp,
+    TxAccessListAccountStorageOp, TxLogOp, TxReceiptOp, TxRefundOp, RW,
 };
 use crate::exec_trace::OperationRef;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 /// The `OperationContainer` is meant to store all of the [`Operation`]s that an
 /// [`ExecStep`](crate::circuit_input_builder::ExecStep) performs during its
@@ -20,7 +21,7 @@ use itertools::Itertools;
 /// they have specified.
 /// That serves as a way to get an input with which is easy to work with in
 /// order to construct the State proof.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OperationContainer {
     /// Operations of MemoryOp
     pub memory: Vec<Operation<MemoryOp>>,
@@ -171,6 +172,110 @@ impl OperationContainer {
         }
     }
 
+    /// Returns the number of operations of `target` contained inside of the
+    /// container, which is the index of the next one inserted.
+    pub fn num_ops(&self, target: Target) -> usize {
+        match target {
+            Target::Memory => self.memory.len(),
+            Target::Stack => self.stack.len(),
+            Target::Storage => self.storage.len(),
+            Target::TxAccessListAccount => self.tx_access_list_account.len(),
+            Target::TxAccessListAccountStorage => self.tx_access_list_account_storage.len(),
+            Target::TxRefund => self.tx_refund.len(),
+            Target::Account => self.account.len(),
+            Target::AccountDestructed => self.account_destructed.len(),
+            Target::CallContext => self.call_context.len(),
+            Target::TxReceipt => self.tx_receipt.len(),
+            Target::TxLog => self.tx_log.len(),
+        }
+    }
+
+    /// Appends the operations of `other`, generated for a transaction handled
+    /// on its own with the rw counter starting at 1, after the ones of the
+    /// previous transactions by moving their rw counters and call ids by
+    /// `offset`.  The call ids found in the values of the CallContext
+    /// operations are moved too, except the zero of a root call's caller.
+    pub(crate) fn append_rebased(&mut self, other: Self, offset: usize) {
+        fn rebased<T: Op>(mut operation: Operation<T>, offset: usize) -> Operation<T> {
+            operation.rwc = RWCounter(operation.rwc.0 + offset);
+            operation
+        }
+
+        self.memory
+            .extend(other.memory.into_iter().map(|mut operation| {
+                operation.op.call_id += offset;
+                rebased(operation, offset)
+            }));
+        self.stack
+            .extend(other.stack.into_iter().map(|mut operation| {
+                operation.op.call_id += offset;
+                rebased(operation, offset)
+            }));
+        self.storage.extend(
+            other
+                .storage
+                .into_iter()
+                .map(|operation| rebased(operation, offset)),
+        );
+        self.tx_access_list_account.extend(
+            other
+                .tx_access_list_account
+                .into_iter()
+                .map(|operation| rebased(operation, offset)),
+        );
+        self.tx_access_list_account_storage.extend(
+            other
+                .tx_access_list_account_storage
+                .into_iter()
+                .map(|operation| rebased(operation, offset)),
+        );
+        self.tx_refund.extend(
+            other
+                .tx_refund
+                .into_iter()
+                .map(|operation| rebased(operation, offset)),
+        );
+        self.account.extend(
+            other
+                .account
+                .into_iter()
+                .map(|operation| rebased(operation, offset)),
+        );
+        self.account_destructed.extend(
+            other
+                .account_destructed
+                .into_iter()
+                .map(|operation| rebased(operation, offset)),
+        );
+        self.call_context
+            .extend(other.call_context.into_iter().map(|mut operation| {
+                let op = &mut operation.op;
+                op.call_id += offset;
+                if matches!(
+                    op.field,
+                    CallContextField::CallerId
+                        | CallContextField::LastCalleeId
+                        | CallContextField::RwCounterEndOfReversion
+                ) && !op.value.is_zero()
+                {
+                    op.value = op.value + offset;
+                }
+                rebased(operation, offset)
+            }));
+        self.tx_receipt.extend(
+            other
+                .tx_receipt
+                .into_iter()
+                .map(|operation| rebased(operation, offset)),
+        );
+        self.tx_log.extend(
+            other
+                .tx_log
+                .into_iter()
+                .map(|operation| rebased(operation, offset)),
+        );
+    }
+
     /// Returns a sorted vector of all of the [`MemoryOp`]s contained inside of
     /// the container.
     pub fn sorted_memory(&self) -> Vec<Operation<MemoryOp>> {
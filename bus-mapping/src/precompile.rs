@@ -0,0 +1,465 @@
+//! Precompiled contracts, which are executed natively by the EVM and thus
+//! don't leave any struct log in the execution trace.
+
+use bn::Group;
+use eth_types::{Address, Word};
+use ethers_core::utils::keccak256;
+use num_bigint::BigUint;
+use ripemd160::Ripemd160;
+use sha2::{Digest, Sha256};
+use strum_macros::EnumIter;
+
+/// The precompiled contracts of the Berlin hard fork, by address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum PrecompileCalls {
+    /// Elliptic curve public key recovery
+    ECRecover = 0x01,
+    /// SHA2-256 hash function
+    Sha256 = 0x02,
+    /// Ripemd-160 hash function
+    Ripemd160 = 0x03,
+    /// Identity function
+    Identity = 0x04,
+    /// Modular exponentiation
+    Modexp = 0x05,
+    /// Point addition on the alt_bn128 curve
+    Bn128Add = 0x06,
+    /// Scalar multiplication on the alt_bn128 curve
+    Bn128Mul = 0x07,
+    /// Pairing check on the alt_bn128 curve
+    Bn128Pairing = 0x08,
+    /// Compression function F of the BLAKE2 hash function
+    Blake2F = 0x09,
+}
+
+impl PrecompileCalls {
+    /// Return the precompiled contract at `address`, if any.
+    pub fn from_address(address: &Address) -> Option<Self> {
+        if address.0[0..19] != [0u8; 19] {
+            return None;
+        }
+        Some(match address.0[19] {
+            0x01 => Self::ECRecover,
+            0x02 => Self::Sha256,
+            0x03 => Self::Ripemd160,
+            0x04 => Self::Identity,
+            0x05 => Self::Modexp,
+            0x06 => Self::Bn128Add,
+            0x07 => Self::Bn128Mul,
+            0x08 => Self::Bn128Pairing,
+            0x09 => Self::Blake2F,
+            _ => return None,
+        })
+    }
+
+    /// Return the address of the precompiled contract.
+    pub fn address(&self) -> Address {
+        Address::from_low_u64_be(*self as u64)
+    }
+
+    /// Execute the precompiled contract natively on `input`, returning its
+    /// output, or `None` if the call fails on this input.  An invalid
+    /// signature doesn't fail `ECRecover`, which returns nothing instead.
+    pub fn execute(&self, input: &[u8]) -> Option<Vec<u8>> {
+        match self {
+            Self::ECRecover => Some(ecrecover(input)),
+            Self::Sha256 => Some(Sha256::digest(input).to_vec()),
+            Self::Ripemd160 => {
+                let mut output = vec![0u8; 12];
+                output.extend_from_slice(&Ripemd160::digest(input));
+                Some(output)
+            }
+            Self::Identity => Some(input.to_vec()),
+            Self::Modexp => modexp(input),
+            Self::Bn128Add => {
+                let input = right_padded::<128>(input);
+                let sum = read_g1_point(&input[..64])? + read_g1_point(&input[64..])?;
+                Some(write_g1_point(sum))
+            }
+            Self::Bn128Mul => {
+                let input = right_padded::<96>(input);
+                let scalar = bn::Fr::from_slice(&input[64..]).ok()?;
+                Some(write_g1_point(read_g1_point(&input[..64])? * scalar))
+            }
+            Self::Bn128Pairing => bn128_pairing(input),
+            Self::Blake2F => blake2f(input),
+        }
+    }
+}
+
+/// Return `input` padded with zeros, or truncated, to `N` bytes.
+fn right_padded<const N: usize>(input: &[u8]) -> [u8; N] {
+    let mut padded = [0u8; N];
+    let len = input.len().min(N);
+    padded[..len].copy_from_slice(&input[..len]);
+    padded
+}
+
+/// Return the address recovered from the hash, v, r and s of `input`, left
+/// padded to 32 bytes, or nothing if the signature is invalid.
+fn ecrecover(input: &[u8]) -> Vec<u8> {
+    let input = right_padded::<128>(input);
+    // v is 27 or 28 as a word.
+    if input[32..63] != [0u8; 31] || !matches!(input[63], 27 | 28) {
+        return vec![];
+    }
+    let recovery_id = match libsecp256k1::RecoveryId::parse(input[63] - 27) {
+        Ok(recovery_id) => recovery_id,
+        Err(_) => return vec![],
+    };
+    let signature = match libsecp256k1::Signature::parse_standard_slice(&input[64..]) {
+        Ok(signature) => signature,
+        Err(_) => return vec![],
+    };
+    let message = libsecp256k1::Message::parse_slice(&input[..32]).expect("32 bytes message");
+    match libsecp256k1::recover(&message, &signature, &recovery_id) {
+        Ok(public_key) => {
+            let mut output = keccak256(&public_key.serialize()[1..]);
+            output[..12].fill(0);
+            output.to_vec()
+        }
+        Err(_) => vec![],
+    }
+}
+
+/// Return `base^exp % mod` left padded to the length of `mod`, whose lengths
+/// are the 3 first words of `input`, followed by their big endian values.
+fn modexp(input: &[u8]) -> Option<Vec<u8>> {
+    let lengths = right_padded::<96>(input);
+    let [base_len, exp_len, mod_len] =
+        [0, 1, 2].map(|idx| Word::from_big_endian(&lengths[32 * idx..32 * (idx + 1)]));
+    // A call with an empty modulus returns nothing whatever the exponent, the
+    // other lengths being otherwise bounded by the gas of the call.
+    if mod_len.is_zero() {
+        return Some(vec![]);
+    }
+    let [base_len, exp_len, mod_len] = [base_len, exp_len, mod_len]
+        .map(|len| (len <= Word::from(u32::MAX)).then(|| len.as_usize()));
+    let (base_len, exp_len, mod_len) = (base_len?, exp_len?, mod_len?);
+
+    let mut values = input.get(96..).unwrap_or_default().to_vec();
+    values.resize(base_len + exp_len + mod_len, 0);
+    let (base, values) = values.split_at(base_len);
+    let (exp, modulus) = values.split_at(exp_len);
+
+    // The result is 0 for a zero modulus.
+    let result = if modulus.iter().all(|byte| *byte == 0) {
+        vec![]
+    } else {
+        BigUint::from_bytes_be(base)
+            .modpow(
+                &BigUint::from_bytes_be(exp),
+                &BigUint::from_bytes_be(modulus),
+            )
+            .to_bytes_be()
+    };
+    let mut output = vec![0u8; mod_len - result.len()];
+    output.extend_from_slice(&result);
+    Some(output)
+}
+
+/// Read a point of G1 from the big endian coordinates of `input`, in which
+/// `(0, 0)` is the point at infinity.
+fn read_g1_point(input: &[u8]) -> Option<bn::G1> {
+    let x = bn::Fq::from_slice(&input[..32]).ok()?;
+    let y = bn::Fq::from_slice(&input[32..64]).ok()?;
+    if x == bn::Fq::zero() && y == bn::Fq::zero() {
+        Some(bn::G1::zero())
+    } else {
+        bn::AffineG1::new(x, y).ok().map(Into::into)
+    }
+}
+
+/// Write the big endian coordinates of `point`, in which the point at
+/// infinity is `(0, 0)`.
+fn write_g1_point(point: bn::G1) -> Vec<u8> {
+    let mut output = vec![0u8; 64];
+    if let Some(point) = bn::AffineG1::from_jacobian(point) {
+        point
+            .x()
+            .to_big_endian(&mut output[..32])
+            .expect("32 bytes coordinate");
+        point
+            .y()
+            .to_big_endian(&mut output[32..])
+            .expect("32 bytes coordinate");
+    }
+    output
+}
+
+/// Return 1 as a word if the product of the pairings of the pairs of points
+/// of G1 and G2 of `input` is 1, and 0 otherwise.
+fn bn128_pairing(input: &[u8]) -> Option<Vec<u8>> {
+    if input.len() % 192 != 0 {
+        return None;
+    }
+    let mut pairs = Vec::with_capacity(input.len() / 192);
+    for chunk in input.chunks(192) {
+        let g1 = read_g1_point(&chunk[..64])?;
+        // The coordinates of G2 are encoded with the imaginary part first.
+        let [x_im, x_re, y_im, y_re] =
+            [64, 96, 128, 160].map(|offset| bn::Fq::from_slice(&chunk[offset..offset + 32]).ok());
+        let x = bn::Fq2::new(x_re?, x_im?);
+        let y = bn::Fq2::new(y_re?, y_im?);
+        let g2 = if x == bn::Fq2::zero() && y == bn::Fq2::zero() {
+            bn::G2::zero()
+        } else {
+            bn::AffineG2::new(x, y).ok()?.into()
+        };
+        pairs.push((g1, g2));
+    }
+    let mut output = vec![0u8; 32];
+    output[31] = (bn::pairing_batch(&pairs) == bn::Gt::one()) as u8;
+    Some(output)
+}
+
+/// Initialization vector of BLAKE2b.
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// Permutations of the message words of the rounds of BLAKE2b.
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// Return the state of the compression function F of BLAKE2b (EIP-152) with
+/// the rounds, the state, the message, the offset counters and the final
+/// block flag of `input`, which must be 213 bytes long.
+fn blake2f(input: &[u8]) -> Option<Vec<u8>> {
+    if input.len() != 213 || input[212] > 1 {
+        return None;
+    }
+    let rounds = u32::from_be_bytes(input[..4].try_into().unwrap());
+    let read_u64 =
+        |offset: usize| u64::from_le_bytes(input[offset..offset + 8].try_into().unwrap());
+    let mut h = [0u64; 8];
+    for (idx, word) in h.iter_mut().enumerate() {
+        *word = read_u64(4 + 8 * idx);
+    }
+    let mut m = [0u64; 16];
+    for (idx, word) in m.iter_mut().enumerate() {
+        *word = read_u64(68 + 8 * idx);
+    }
+    let t = [read_u64(196), read_u64(204)];
+
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(&h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= t[0];
+    v[13] ^= t[1];
+    if input[212] == 1 {
+        v[14] = !v[14];
+    }
+    for round in 0..rounds as usize {
+        let s = &BLAKE2B_SIGMA[round % 10];
+        for (idx, [a, b, c, d]) in [
+            [0, 4, 8, 12],
+            [1, 5, 9, 13],
+            [2, 6, 10, 14],
+            [3, 7, 11, 15],
+            [0, 5, 10, 15],
+            [1, 6, 11, 12],
+            [2, 7, 8, 13],
+            [3, 4, 9, 14],
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let (x, y) = (m[s[2 * idx]], m[s[2 * idx + 1]]);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+            v[d] = (v[d] ^ v[a]).rotate_right(32);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(24);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+            v[d] = (v[d] ^ v[a]).rotate_right(16);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(63);
+        }
+    }
+    for idx in 0..8 {
+        h[idx] ^= v[idx] ^ v[idx + 8];
+    }
+    Some(h.iter().flat_map(|word| word.to_le_bytes()).collect())
+}
+
+#[cfg(test)]
+mod precompile_tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn precompile_from_address() {
+        assert_eq!(
+            PrecompileCalls::from_address(&Address::from_low_u64_be(1)),
+            Some(PrecompileCalls::ECRecover)
+        );
+        assert_eq!(
+            PrecompileCalls::from_address(&Address::from_low_u64_be(9)),
+            Some(PrecompileCalls::Blake2F)
+        );
+        assert_eq!(PrecompileCalls::from_address(&Address::zero()), None);
+        assert_eq!(
+            PrecompileCalls::from_address(&Address::from_low_u64_be(10)),
+            None
+        );
+        assert_eq!(
+            PrecompileCalls::from_address(&Address::from_low_u64_be(0x0100_0001)),
+            None
+        );
+        for precompile in PrecompileCalls::iter() {
+            assert_eq!(
+                PrecompileCalls::from_address(&precompile.address()),
+                Some(precompile)
+            );
+        }
+    }
+
+    #[test]
+    fn precompile_execute_hashes() {
+        assert_eq!(
+            PrecompileCalls::Sha256.execute(b"abc"),
+            Some(
+                hex::decode("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+                    .unwrap()
+            )
+        );
+        assert_eq!(
+            PrecompileCalls::Ripemd160.execute(b"abc"),
+            Some(
+                hex::decode("0000000000000000000000008eb208f7e05d987a9b044a8e98c6b087f15a0bfc")
+                    .unwrap()
+            )
+        );
+        assert_eq!(
+            PrecompileCalls::Identity.execute(&[1, 2, 3]),
+            Some(vec![1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn precompile_execute_ecrecover() {
+        let secret_key = libsecp256k1::SecretKey::parse(&[1; 32]).unwrap();
+        let hash = keccak256(b"abc");
+        let (signature, recovery_id) =
+            libsecp256k1::sign(&libsecp256k1::Message::parse(&hash), &secret_key);
+        let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key);
+        let mut address = keccak256(&public_key.serialize()[1..]);
+        address[..12].fill(0);
+
+        let mut input = hash.to_vec();
+        input.extend_from_slice(&[0; 31]);
+        input.push(27 + recovery_id.serialize());
+        input.extend_from_slice(&signature.serialize());
+        assert_eq!(
+            PrecompileCalls::ECRecover.execute(&input),
+            Some(address.to_vec())
+        );
+
+        // An invalid v returns nothing, without failing.
+        input[63] = 29;
+        assert_eq!(PrecompileCalls::ECRecover.execute(&input), Some(vec![]));
+    }
+
+    #[test]
+    fn precompile_execute_modexp() {
+        // 3^5 % 7 with base_len = 1, exp_len = 1 and mod_len = 2.
+        let mut input = vec![0u8; 96];
+        input[31] = 1;
+        input[63] = 1;
+        input[95] = 2;
+        input.extend_from_slice(&[3, 5, 0, 7]);
+        assert_eq!(PrecompileCalls::Modexp.execute(&input), Some(vec![0, 5]));
+        // The modulus is truncated and padded with zeros.
+        assert_eq!(
+            PrecompileCalls::Modexp.execute(&input[..99]),
+            Some(vec![0, 0])
+        );
+        // Huge base and exponent lengths with an empty modulus.
+        let mut input = vec![0xffu8; 64];
+        input.extend_from_slice(&[0; 32]);
+        assert_eq!(PrecompileCalls::Modexp.execute(&input), Some(vec![]));
+    }
+
+    #[test]
+    fn precompile_execute_bn128() {
+        let mut generator = vec![0u8; 64];
+        generator[31] = 1;
+        generator[63] = 2;
+        let double = hex::decode(
+            "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3\
+             15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4",
+        )
+        .unwrap();
+        assert_eq!(
+            PrecompileCalls::Bn128Add.execute(&[generator.clone(), generator.clone()].concat()),
+            Some(double.clone())
+        );
+        let mut scalar = vec![0u8; 32];
+        scalar[31] = 2;
+        assert_eq!(
+            PrecompileCalls::Bn128Mul.execute(&[generator.clone(), scalar].concat()),
+            Some(double)
+        );
+        // The point at infinity is the neutral element.
+        assert_eq!(
+            PrecompileCalls::Bn128Add.execute(&generator),
+            Some(generator.clone())
+        );
+        // A point which isn't on the curve fails the call.
+        generator[63] = 3;
+        assert_eq!(PrecompileCalls::Bn128Add.execute(&generator), None);
+
+        // The empty product of pairings is 1.
+        let mut one = vec![0u8; 32];
+        one[31] = 1;
+        assert_eq!(PrecompileCalls::Bn128Pairing.execute(&[]), Some(one));
+        assert_eq!(PrecompileCalls::Bn128Pairing.execute(&[0; 191]), None);
+    }
+
+    #[test]
+    fn precompile_execute_blake2f() {
+        // The 12 rounds of BLAKE2b-512 of "abc" (EIP-152).
+        let input = hex::decode(
+            "0000000c48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f\
+             3af54fa5d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e13\
+             19cde05b61626300000000000000000000000000000000000000000000000000\
+             0000000000000000000000000000000000000000000000000000000000000000\
+             0000000000000000000000000000000000000000000000000000000000000000\
+             0000000000000000000000000000000000000000000000000000000000000000\
+             000000000300000000000000000000000000000001",
+        )
+        .unwrap();
+        assert_eq!(
+            PrecompileCalls::Blake2F.execute(&input),
+            Some(
+                hex::decode(
+                    "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+                     7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+                )
+                .unwrap()
+            )
+        );
+        // The final block flag is a boolean.
+        let mut invalid_input = input.clone();
+        invalid_input[212] = 2;
+        assert_eq!(PrecompileCalls::Blake2F.execute(&invalid_input), None);
+        assert_eq!(PrecompileCalls::Blake2F.execute(&input[..212]), None);
+    }
+}
@@ -2,13 +2,27 @@
 //! query a Geth node in order to get a Block, Tx or Trace info.
 
 use crate::Error;
+use async_trait::async_trait;
 use eth_types::{
-    Address, Block, Bytes, EIP1186ProofResponse, GethExecTrace, Hash, ResultGethExecTraces,
-    Transaction, Word, U64,
+    geth_types::ChainConfig, vm_trace::TraceReplay, Address, Block, BlockBlobs, BlockWithdrawals,
+    Bytes, EIP1186ProofResponse, GethExecTrace, Hash, ResultGethExecTraces, Transaction,
+    Withdrawal, Word, U64,
 };
 pub use ethers_core::types::BlockNumber;
-use ethers_providers::JsonRpcClient;
-use serde::Serialize;
+use ethers_providers::{JsonRpcClient, ProviderError};
+use futures::{
+    future::{self, Either},
+    Future, TryFutureExt,
+};
+use futures_timer::Delay;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fmt::Debug,
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use url::Url;
 
 /// Serialize a type.
 ///
@@ -19,21 +33,193 @@ pub fn serialize<T: serde::Serialize>(t: &T) -> serde_json::Value {
     serde_json::to_value(t).expect("Types never fail to serialize.")
 }
 
-#[derive(Serialize)]
-#[doc(hidden)]
-pub(crate) struct GethLoggerConfig {
+/// JSON-RPC client able to send several requests at once in a JSON-RPC batch.
+#[async_trait]
+pub trait BatchJsonRpcClient: JsonRpcClient {
+    /// Send the `requests`, made of a method and its params, in a single
+    /// batch, returning their results in the same order.
+    async fn request_batch(
+        &self,
+        requests: &[(&str, serde_json::Value)],
+    ) -> Result<Vec<serde_json::Value>, ProviderError>;
+}
+
+/// JSON-RPC request sent by a [`HttpBatchProvider`].
+#[derive(Debug, Serialize)]
+struct JsonRpcRequest<'a, T> {
+    id: u64,
+    jsonrpc: &'a str,
+    method: &'a str,
+    params: T,
+}
+
+/// JSON-RPC error returned by the node.
+#[derive(Debug, Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+/// JSON-RPC response received by a [`HttpBatchProvider`].
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse {
+    id: u64,
+    #[serde(default)]
+    result: Option<serde_json::Value>,
+    #[serde(default)]
+    error: Option<JsonRpcError>,
+}
+
+impl JsonRpcResponse {
+    fn into_result(self) -> Result<serde_json::Value, ProviderError> {
+        match self.error {
+            Some(err) => Err(ProviderError::CustomError(format!(
+                "(code: {}, message: {})",
+                err.code, err.message
+            ))),
+            None => Ok(self.result.unwrap_or(serde_json::Value::Null)),
+        }
+    }
+}
+
+/// Return the results of the `num_requests` responses `resps` of the batch
+/// whose requests have the ids from `first_id`, in the order of the requests,
+/// as the node may answer a batch in any order.
+fn batch_results(
+    first_id: u64,
+    num_requests: usize,
+    resps: Vec<JsonRpcResponse>,
+) -> Result<Vec<serde_json::Value>, ProviderError> {
+    let mut results = vec![None; num_requests];
+    for resp in resps {
+        let result = resp
+            .id
+            .checked_sub(first_id)
+            .and_then(|idx| results.get_mut(idx as usize))
+            .filter(|result| result.is_none())
+            .ok_or_else(|| {
+                ProviderError::CustomError(format!("unexpected response with id {}", resp.id))
+            })?;
+        *result = Some(resp.into_result()?);
+    }
+    results
+        .into_iter()
+        .zip(first_id..)
+        .map(|(result, id)| {
+            result.ok_or_else(|| {
+                ProviderError::CustomError(format!("missing response with id {}", id))
+            })
+        })
+        .collect()
+}
+
+/// HTTP transport of JSON-RPC requests supporting batches.
+#[derive(Debug)]
+pub struct HttpBatchProvider {
+    id: AtomicU64,
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl HttpBatchProvider {
+    /// Generates a new `HttpBatchProvider` sending its requests to `url`.
+    pub fn new(url: Url) -> Self {
+        Self {
+            id: AtomicU64::new(0),
+            client: reqwest::Client::new(),
+            url,
+        }
+    }
+
+    /// Post `body` and deserialize the response.
+    async fn post<T, R>(&self, body: &T) -> Result<R, ProviderError>
+    where
+        T: Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let http_error = |err: reqwest::Error| ProviderError::CustomError(err.to_string());
+        self.client
+            .post(self.url.clone())
+            .json(body)
+            .send()
+            .await
+            .map_err(http_error)?
+            .json()
+            .await
+            .map_err(http_error)
+    }
+}
+
+impl FromStr for HttpBatchProvider {
+    type Err = url::ParseError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(Url::parse(src)?))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for HttpBatchProvider {
+    type Error = ProviderError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let request = JsonRpcRequest {
+            id: self.id.fetch_add(1, Ordering::SeqCst),
+            jsonrpc: "2.0",
+            method,
+            params,
+        };
+        let resp: JsonRpcResponse = self.post(&request).await?;
+        Ok(serde_json::from_value(resp.into_result()?)?)
+    }
+}
+
+#[async_trait]
+impl BatchJsonRpcClient for HttpBatchProvider {
+    async fn request_batch(
+        &self,
+        requests: &[(&str, serde_json::Value)],
+    ) -> Result<Vec<serde_json::Value>, ProviderError> {
+        // Nodes reject empty batches.
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+        let first_id = self.id.fetch_add(requests.len() as u64, Ordering::SeqCst);
+        let batch: Vec<_> = requests
+            .iter()
+            .zip(first_id..)
+            .map(|((method, params), id)| JsonRpcRequest {
+                id,
+                jsonrpc: "2.0",
+                method: *method,
+                params,
+            })
+            .collect();
+        let resps = self.post(&batch).await?;
+        batch_results(first_id, requests.len(), resps)
+    }
+}
+
+/// Options of the struct logger of geth used by the `debug_trace*` methods.
+/// The default options capture everything the circuit input builder needs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct GethLoggerConfig {
     /// enable memory capture
     #[serde(rename = "EnableMemory")]
-    enable_memory: bool,
+    pub enable_memory: bool,
     /// disable stack capture
     #[serde(rename = "DisableStack")]
-    disable_stack: bool,
+    pub disable_stack: bool,
     /// disable storage capture
     #[serde(rename = "DisableStorage")]
-    disable_storage: bool,
+    pub disable_storage: bool,
     /// enable return data capture
     #[serde(rename = "EnableReturnData")]
-    enable_return_data: bool,
+    pub enable_return_data: bool,
 }
 
 impl Default for GethLoggerConfig {
@@ -47,43 +233,119 @@ impl Default for GethLoggerConfig {
     }
 }
 
+/// Options of the requests sent by a [`GethClient`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GethClientConfig {
+    /// Number of times a failed or timed out request is sent again
+    pub max_retries: usize,
+    /// Delay before the first retry of a request, doubled at each retry
+    pub initial_backoff: Duration,
+    /// Time after which a request without response is considered as failed
+    pub timeout: Duration,
+    /// Number of requests of a batch that are sent at once
+    pub batch_size: usize,
+}
+
+impl Default for GethClientConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            timeout: Duration::from_secs(60),
+            batch_size: 32,
+        }
+    }
+}
+
 /// Placeholder structure designed to contain the methods that the BusMapping
 /// needs in order to enable Geth queries.
-pub struct GethClient<P: JsonRpcClient>(pub P);
+pub struct GethClient<P: JsonRpcClient> {
+    provider: P,
+    config: GethClientConfig,
+}
 
 impl<P: JsonRpcClient> GethClient<P> {
     /// Generates a new `GethClient` instance.
     pub fn new(provider: P) -> Self {
-        Self(provider)
+        Self::new_with_config(provider, GethClientConfig::default())
+    }
+
+    /// Generates a new `GethClient` instance sending its requests with the
+    /// options of `config`.
+    pub fn new_with_config(provider: P, config: GethClientConfig) -> Self {
+        Self { provider, config }
+    }
+
+    /// Send the request of `method` with `params`, retrying with an
+    /// exponential backoff the requests that fail or time out.
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        self.with_retries(method, || {
+            self.provider.request(method, &params).map_err(Into::into)
+        })
+        .await
+    }
+
+    /// Await the request of `method` sent by `send`, sending it again with an
+    /// exponential backoff when it fails or times out.
+    async fn with_retries<F, Fut, R>(&self, method: &str, send: F) -> Result<R, Error>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<R, ProviderError>>,
+    {
+        let mut backoff = self.config.initial_backoff;
+        let mut retries = 0;
+        loop {
+            let request = send();
+            futures::pin_mut!(request);
+            let err = match future::select(request, Delay::new(self.config.timeout)).await {
+                Either::Left((Ok(resp), _)) => return Ok(resp),
+                Either::Left((Err(err), _)) => err,
+                Either::Right(_) => {
+                    ProviderError::CustomError(format!("request {} timed out", method))
+                }
+            };
+            if retries == self.config.max_retries {
+                return Err(Error::JSONRpcError(err));
+            }
+            log::warn!(
+                "request {} failed: {}, retrying in {:?}",
+                method,
+                err,
+                backoff
+            );
+            Delay::new(backoff).await;
+            backoff *= 2;
+            retries += 1;
+        }
     }
 
     /// Calls `eth_coinbase` via JSON-RPC returning the coinbase of the network.
     pub async fn get_coinbase(&self) -> Result<Address, Error> {
-        self.0
-            .request("eth_coinbase", ())
-            .await
-            .map_err(|e| Error::JSONRpcError(e.into()))
+        self.request("eth_coinbase", ()).await
     }
 
     /// Calls `eth_chainId` via JSON-RPC returning the chain id of the network.
     pub async fn get_chain_id(&self) -> Result<u64, Error> {
-        let net_id: U64 = self
-            .0
-            .request("eth_chainId", ())
-            .await
-            .map_err(|e| Error::JSONRpcError(e.into()))?;
+        let net_id: U64 = self.request("eth_chainId", ()).await?;
         Ok(net_id.as_u64())
     }
 
+    /// Calls `debug_chainConfig` via JSON-RPC returning the [`ChainConfig`] of
+    /// the network, with the activation of its hardforks.
+    pub async fn get_chain_config(&self) -> Result<ChainConfig, Error> {
+        self.request("debug_chainConfig", ()).await
+    }
+
     /// Calls `eth_getBlockByHash` via JSON-RPC returning a [`Block`] returning
     /// all the block information including it's transaction's details.
     pub async fn get_block_by_hash(&self, hash: Hash) -> Result<Block<Transaction>, Error> {
         let hash = serialize(&hash);
         let flag = serialize(&true);
-        self.0
-            .request("eth_getBlockByHash", [hash, flag])
-            .await
-            .map_err(|e| Error::JSONRpcError(e.into()))
+        self.request("eth_getBlockByHash", [hash, flag]).await
     }
 
     /// Calls `eth_getBlockByNumber` via JSON-RPC returning a [`Block`]
@@ -95,43 +357,80 @@ impl<P: JsonRpcClient> GethClient<P> {
     ) -> Result<Block<Transaction>, Error> {
         let num = serialize(&block_num);
         let flag = serialize(&true);
-        self.0
-            .request("eth_getBlockByNumber", [num, flag])
-            .await
-            .map_err(|e| Error::JSONRpcError(e.into()))
+        self.request("eth_getBlockByNumber", [num, flag]).await
     }
 
-    /// Calls `debug_traceBlockByHash` via JSON-RPC returning a
-    /// [`Vec<GethExecTrace>`] with each GethTrace corresponding to 1
-    /// transaction of the block.
-    pub async fn trace_block_by_hash(&self, hash: Hash) -> Result<Vec<GethExecTrace>, Error> {
+    /// Calls `eth_getBlockByNumber` via JSON-RPC returning the
+    /// [`Withdrawal`]s of the block, which are empty before Shanghai.
+    pub async fn get_withdrawals_by_number(
+        &self,
+        block_num: BlockNumber,
+    ) -> Result<Vec<Withdrawal>, Error> {
+        let num = serialize(&block_num);
+        let flag = serialize(&false);
+        let resp: BlockWithdrawals = self.request("eth_getBlockByNumber", [num, flag]).await?;
+        Ok(resp.withdrawals)
+    }
+
+    /// Calls `eth_getBlockByNumber` via JSON-RPC returning the blob gas fields
+    /// of the block and the blob fields of its transactions (EIP-4844), which
+    /// are empty before Cancun.
+    pub async fn get_blobs_by_number(&self, block_num: BlockNumber) -> Result<BlockBlobs, Error> {
+        let num = serialize(&block_num);
+        let flag = serialize(&true);
+        self.request("eth_getBlockByNumber", [num, flag]).await
+    }
+
+    /// Calls `debug_traceBlockByHash` via JSON-RPC with the logger options
+    /// `cfg` returning a [`Vec<GethExecTrace>`] with each GethTrace
+    /// corresponding to 1 transaction of the block.
+    pub async fn trace_block_by_hash(
+        &self,
+        hash: Hash,
+        cfg: &GethLoggerConfig,
+    ) -> Result<Vec<GethExecTrace>, Error> {
         let hash = serialize(&hash);
-        let cfg = serialize(&GethLoggerConfig::default());
-        let resp: ResultGethExecTraces = self
-            .0
-            .request("debug_traceBlockByHash", [hash, cfg])
-            .await
-            .map_err(|e| Error::JSONRpcError(e.into()))?;
+        let cfg = serialize(cfg);
+        let resp: ResultGethExecTraces =
+            self.request("debug_traceBlockByHash", [hash, cfg]).await?;
         Ok(resp.0.into_iter().map(|step| step.result).collect())
     }
 
-    /// Calls `debug_traceBlockByNumber` via JSON-RPC returning a
-    /// [`Vec<GethExecTrace>`] with each GethTrace corresponding to 1
-    /// transaction of the block.
+    /// Calls `debug_traceBlockByNumber` via JSON-RPC with the logger options
+    /// `cfg` returning a [`Vec<GethExecTrace>`] with each GethTrace
+    /// corresponding to 1 transaction of the block.
     pub async fn trace_block_by_number(
         &self,
         block_num: BlockNumber,
+        cfg: &GethLoggerConfig,
     ) -> Result<Vec<GethExecTrace>, Error> {
         let num = serialize(&block_num);
-        let cfg = serialize(&GethLoggerConfig::default());
-        let resp: ResultGethExecTraces = self
-            .0
-            .request("debug_traceBlockByNumber", [num, cfg])
-            .await
-            .map_err(|e| Error::JSONRpcError(e.into()))?;
+        let cfg = serialize(cfg);
+        let resp: ResultGethExecTraces =
+            self.request("debug_traceBlockByNumber", [num, cfg]).await?;
         Ok(resp.0.into_iter().map(|step| step.result).collect())
     }
 
+    /// Calls `trace_replayBlockTransactions` via JSON-RPC, which is supported
+    /// by Erigon and Nethermind, returning a [`Vec<GethExecTrace>`] with each
+    /// GethTrace rebuilt from the `vmTrace` of 1 transaction of the block,
+    /// which fails for the traces whose refunds can't be reconstructed.
+    pub async fn trace_replay_block_transactions(
+        &self,
+        block_num: BlockNumber,
+    ) -> Result<Vec<GethExecTrace>, Error> {
+        let block = self.get_block_by_number(block_num).await?;
+        let num = serialize(&block_num);
+        let trace_types = serialize(&["trace", "vmTrace"]);
+        let resp: Vec<TraceReplay> = self
+            .request("trace_replayBlockTransactions", [num, trace_types])
+            .await?;
+        resp.into_iter()
+            .zip(block.transactions.iter())
+            .map(|(replay, tx)| Ok(GethExecTrace::try_from((replay, tx))?))
+            .collect()
+    }
+
     /// Calls `eth_getCode` via JSON-RPC returning a contract code
     pub async fn get_code(
         &self,
@@ -140,11 +439,7 @@ impl<P: JsonRpcClient> GethClient<P> {
     ) -> Result<Vec<u8>, Error> {
         let address = serialize(&contract_address);
         let num = serialize(&block_num);
-        let resp: Bytes = self
-            .0
-            .request("eth_getCode", [address, num])
-            .await
-            .map_err(|e| Error::JSONRpcError(e.into()))?;
+        let resp: Bytes = self.request("eth_getCode", [address, num]).await?;
         Ok(resp.to_vec())
     }
 
@@ -160,29 +455,218 @@ impl<P: JsonRpcClient> GethClient<P> {
         let account = serialize(&account);
         let keys = serialize(&keys);
         let num = serialize(&block_num);
-        self.0
-            .request("eth_getProof", [account, keys, num])
-            .await
-            .map_err(|e| Error::JSONRpcError(e.into()))
+        self.request("eth_getProof", [account, keys, num]).await
     }
 
     /// Calls `miner_stop` via JSON-RPC, which makes the node stop mining
     /// blocks.  Useful for integration tests.
     pub async fn miner_stop(&self) -> Result<(), Error> {
-        self.0
-            .request("miner_stop", ())
-            .await
-            .map_err(|e| Error::JSONRpcError(e.into()))
+        self.request("miner_stop", ()).await
     }
 
     /// Calls `miner_start` via JSON-RPC, which makes the node start mining
     /// blocks.  Useful for integration tests.
     pub async fn miner_start(&self) -> Result<(), Error> {
-        self.0
-            .request("miner_start", [serialize(&1)])
-            .await
-            .map_err(|e| Error::JSONRpcError(e.into()))
+        self.request("miner_start", [serialize(&1)]).await
+    }
+}
+
+impl<P: BatchJsonRpcClient> GethClient<P> {
+    /// Send the requests of `method` with each of the `params` in JSON-RPC
+    /// batches of up to `batch_size` requests, retrying the failed batches,
+    /// returning the responses in the same order.
+    async fn request_batch<T, R>(&self, method: &str, params: &[T]) -> Result<Vec<R>, Error>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let mut resps = Vec::with_capacity(params.len());
+        for chunk in params.chunks(self.config.batch_size.max(1)) {
+            let requests: Vec<_> = chunk
+                .iter()
+                .map(|params| (method, serialize(params)))
+                .collect();
+            let chunk_resps = self
+                .with_retries(method, || self.provider.request_batch(&requests))
+                .await?;
+            for resp in chunk_resps {
+                resps.push(serde_json::from_value(resp).map_err(Error::SerdeError)?);
+            }
+        }
+        Ok(resps)
+    }
+
+    /// Calls `eth_getBlockByNumber` via JSON-RPC for each of the `num_blocks`
+    /// blocks preceding `block_num`, in batches, returning their headers
+    /// from the oldest one.
+    pub async fn get_history(
+        &self,
+        block_num: u64,
+        num_blocks: u64,
+    ) -> Result<Vec<Block<Hash>>, Error> {
+        let flag = serialize(&false);
+        let params: Vec<_> = (block_num.saturating_sub(num_blocks)..block_num)
+            .map(|num| [serialize(&BlockNumber::from(num)), flag.clone()])
+            .collect();
+        self.request_batch("eth_getBlockByNumber", &params).await
+    }
+
+    /// Calls `eth_getCode` via JSON-RPC for each of the `contract_addresses`,
+    /// in batches, returning their codes in the same order.
+    pub async fn get_codes(
+        &self,
+        contract_addresses: &[Address],
+        block_num: BlockNumber,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let num = serialize(&block_num);
+        let params: Vec<_> = contract_addresses
+            .iter()
+            .map(|address| [serialize(address), num.clone()])
+            .collect();
+        let resps: Vec<Bytes> = self.request_batch("eth_getCode", &params).await?;
+        Ok(resps.into_iter().map(|code| code.to_vec()).collect())
+    }
+
+    /// Calls `eth_getProof` via JSON-RPC for each of the accounts with its
+    /// storage keys in `accounts`, in batches, returning their
+    /// [`EIP1186ProofResponse`]s in the same order.
+    pub async fn get_proofs(
+        &self,
+        accounts: &[(Address, Vec<Word>)],
+        block_num: BlockNumber,
+    ) -> Result<Vec<EIP1186ProofResponse>, Error> {
+        let num = serialize(&block_num);
+        let params: Vec<_> = accounts
+            .iter()
+            .map(|(account, keys)| [serialize(account), serialize(keys), num.clone()])
+            .collect();
+        self.request_batch("eth_getProof", &params).await
     }
 }
 
 // Integration tests found in `integration-tests/tests/rpc.rs`.
+
+#[cfg(test)]
+mod rpc_tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Provider failing the first `failures` requests, and then answering a
+    /// chain id of 1.
+    #[derive(Debug)]
+    struct FlakyProvider {
+        failures: usize,
+        requests: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl JsonRpcClient for FlakyProvider {
+        type Error = ProviderError;
+
+        async fn request<T, R>(&self, _method: &str, _params: T) -> Result<R, Self::Error>
+        where
+            T: Debug + Serialize + Send + Sync,
+            R: DeserializeOwned,
+        {
+            if self.requests.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(ProviderError::CustomError("unavailable".to_string()));
+            }
+            Ok(serde_json::from_value(serde_json::json!("0x1"))?)
+        }
+    }
+
+    #[async_trait]
+    impl BatchJsonRpcClient for FlakyProvider {
+        async fn request_batch(
+            &self,
+            requests: &[(&str, serde_json::Value)],
+        ) -> Result<Vec<serde_json::Value>, ProviderError> {
+            if self.requests.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(ProviderError::CustomError("unavailable".to_string()));
+            }
+            Ok(vec![serde_json::json!("0x1"); requests.len()])
+        }
+    }
+
+    fn flaky_client(failures: usize, max_retries: usize) -> GethClient<FlakyProvider> {
+        GethClient::new_with_config(
+            FlakyProvider {
+                failures,
+                requests: AtomicUsize::new(0),
+            },
+            GethClientConfig {
+                max_retries,
+                initial_backoff: Duration::from_millis(1),
+                ..GethClientConfig::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn request_retried_until_success() {
+        let client = flaky_client(2, 2);
+        assert_eq!(client.get_chain_id().await.unwrap(), 1);
+        assert_eq!(client.provider.requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn request_failed_after_max_retries() {
+        let client = flaky_client(3, 2);
+        assert!(matches!(
+            client.get_chain_id().await,
+            Err(Error::JSONRpcError(ProviderError::CustomError(_)))
+        ));
+        assert_eq!(client.provider.requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn request_batch_in_order() {
+        let client = flaky_client(0, 0);
+        let params: Vec<u64> = (0..5).collect();
+        let resps: Vec<U64> = client.request_batch("eth_chainId", &params).await.unwrap();
+        assert_eq!(resps, vec![U64::one(); 5]);
+        assert_eq!(client.provider.requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn request_batch_retried_until_success() {
+        let client = flaky_client(1, 1);
+        let params: Vec<u64> = (0..5).collect();
+        let resps: Vec<U64> = client.request_batch("eth_chainId", &params).await.unwrap();
+        assert_eq!(resps, vec![U64::one(); 5]);
+        assert_eq!(client.provider.requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn batch_results_ordered_by_id() {
+        let resps = serde_json::from_value(serde_json::json!([
+            { "jsonrpc": "2.0", "id": 12, "result": "0x2" },
+            { "jsonrpc": "2.0", "id": 10, "result": "0x0" },
+            { "jsonrpc": "2.0", "id": 11, "result": null },
+        ]))
+        .unwrap();
+        assert_eq!(
+            batch_results(10, 3, resps).unwrap(),
+            vec![
+                serde_json::json!("0x0"),
+                serde_json::Value::Null,
+                serde_json::json!("0x2")
+            ]
+        );
+    }
+
+    #[test]
+    fn batch_results_missing_or_failed() {
+        let resps = serde_json::from_value(serde_json::json!([
+            { "jsonrpc": "2.0", "id": 10, "result": "0x0" },
+        ]))
+        .unwrap();
+        assert!(batch_results(10, 2, resps).is_err());
+        let resps = serde_json::from_value(serde_json::json!([
+            { "jsonrpc": "2.0", "id": 10, "result": "0x0" },
+            { "jsonrpc": "2.0", "id": 11, "error": { "code": -32000, "message": "missing trie node" } },
+        ]))
+        .unwrap();
+        assert!(batch_results(10, 2, resps).is_err());
+    }
+}
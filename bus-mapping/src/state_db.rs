@@ -4,6 +4,7 @@
 use eth_types::{Address, Hash, Word, H256, U256};
 use ethers_core::utils::keccak256;
 use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
 lazy_static! {
@@ -13,7 +14,7 @@ lazy_static! {
 }
 
 /// Memory storage for contract code by code hash.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeDB(pub HashMap<Hash, Vec<u8>>);
 
 impl Default for CodeDB {
@@ -83,8 +84,9 @@ pub struct StateDB {
     // state before current transaction, to calculate gas cost for some opcodes like sstore.
     // So both dirty storage and committed storage are needed.
     dirty_storage: HashMap<(Address, Word), Word>,
-    // Accounts that have been through `SELFDESTRUCT` under the situation that `is_persistent` is
-    // `true`. These accounts will be reset once `commit_tx` is called.
+    // Accounts that have been through `SELFDESTRUCT` in the current transaction, the self
+    // destructs of reverted calls being removed. These accounts will be reset once `commit_tx` is
+    // called.
     destructed_account: HashSet<Address>,
     refund: u64,
 }
@@ -225,11 +227,31 @@ impl StateDB {
         debug_assert!(exist);
     }
 
+    /// Check whether `addr` has been self destructed in the current
+    /// transaction.
+    pub fn check_account_destructed(&self, addr: &Address) -> bool {
+        self.destructed_account.contains(addr)
+    }
+
     /// Set account as self destructed.
     pub fn destruct_account(&mut self, addr: Address) {
         self.destructed_account.insert(addr);
     }
 
+    /// Unset account as self destructed, when the self destruct is reverted.
+    pub fn revert_destruct_account(&mut self, addr: &Address) {
+        let exist = self.destructed_account.remove(addr);
+        debug_assert!(exist);
+    }
+
+    /// Return the accounts self destructed in the current transaction, sorted
+    /// by address.
+    pub fn destructed_accounts(&self) -> Vec<Address> {
+        let mut addresses: Vec<Address> = self.destructed_account.iter().copied().collect();
+        addresses.sort();
+        addresses
+    }
+
     /// Retrieve refund.
     pub fn refund(&self) -> u64 {
         self.refund
@@ -240,6 +262,24 @@ impl StateDB {
         self.refund = value;
     }
 
+    /// Update the accounts found in `other`, the state after a transaction
+    /// handled on a [`StateDB`] holding the accounts it accesses.  The
+    /// storage slots missing from `other` are kept, unless the account has
+    /// been reset to an empty one by a self destruct.
+    pub fn update_accounts(&mut self, other: StateDB) {
+        for (addr, acc) in other.state {
+            if acc.is_empty() {
+                self.state.insert(addr, acc);
+                continue;
+            }
+            let (_, self_acc) = self.get_account_mut(&addr);
+            self_acc.nonce = acc.nonce;
+            self_acc.balance = acc.balance;
+            self_acc.code_hash = acc.code_hash;
+            self_acc.storage.extend(acc.storage);
+        }
+    }
+
     /// Clear access list and refund, and commit dirty storage.
     /// It should be invoked before processing
     /// with new transaction with the same [`StateDB`].
@@ -255,6 +295,7 @@ impl StateDB {
             let (_, account) = self.get_account_mut(&addr);
             *account = ACCOUNT_ZERO.clone();
         }
+        self.destructed_account = HashSet::new();
         self.refund = 0;
     }
 }
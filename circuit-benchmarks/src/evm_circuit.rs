@@ -1,6 +1,6 @@
 //! Evm circuit benchmarks
 
-use eth_types::Field;
+use eth_types::{evm_types::Hardfork, Field};
 use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner},
     plonk::{Circuit, ConstraintSystem, Error, Expression},
@@ -25,13 +25,14 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
         let rw_table = [(); 11].map(|_| meta.advice_column());
         let bytecode_table = [(); 5].map(|_| meta.advice_column());
         let block_table = [(); 3].map(|_| meta.advice_column());
-        let copy_table = [(); 11].map(|_| meta.advice_column());
+        let copy_table = [(); 12].map(|_| meta.advice_column());
         // Use constant expression to mock constant instance column for a more
         // reasonable benchmark.
         let power_of_randomness = [(); 31].map(|_| Expression::Constant(F::one()));
 
         EvmCircuit::configure(
             meta,
+            Hardfork::default(),
             power_of_randomness,
             &tx_table,
             &rw_table,
@@ -74,6 +74,7 @@ impl<F: Field> Circuit<F> for KeccakRoundTestCircuit<F> {
 mod tests {
     use super::*;
     use ark_std::{end_timer, start_timer};
+    use eth_types::ToScalar;
     use halo2_proofs::plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, SingleVerifier};
     use halo2_proofs::{
         pairing::bn256::{Bn256, Fr, G1Affine},
@@ -85,7 +86,6 @@ mod tests {
     use keccak256::{
         arith_helpers::*,
         common::{State, ROUND_CONSTANTS},
-        gate_helpers::biguint_to_f,
     };
     use rand::SeedableRng;
     use rand_xorshift::XorShiftRng;
@@ -114,7 +114,7 @@ mod tests {
         // Generate in_state as `[Fr;25]`
         let mut in_state_fp: [Fr; 25] = [Fr::zero(); 25];
         for (x, y) in (0..5).cartesian_product(0..5) {
-            in_state_fp[5 * x + y] = biguint_to_f(&convert_b2_to_b13(in_state[x][y]));
+            in_state_fp[5 * x + y] = convert_b2_to_b13(in_state[x][y]).to_scalar().unwrap();
             in_state_biguint[(x, y)] = convert_b2_to_b13(in_state[x][y]);
         }
 
@@ -131,12 +131,12 @@ mod tests {
 
         let constants_b13: Vec<Fr> = ROUND_CONSTANTS
             .iter()
-            .map(|num| biguint_to_f(&convert_b2_to_b13(*num)))
+            .map(|num| convert_b2_to_b13(*num).to_scalar().unwrap())
             .collect();
 
         let constants_b9: Vec<Fr> = ROUND_CONSTANTS
             .iter()
-            .map(|num| biguint_to_f(&convert_b2_to_b9(*num)))
+            .map(|num| convert_b2_to_b9(*num).to_scalar().unwrap())
             .collect();
 
         // Build the circuit
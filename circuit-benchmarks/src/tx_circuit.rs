@@ -5,7 +5,11 @@ mod tests {
     use crate::bench_params::DEGREE;
     use ark_std::{end_timer, start_timer};
     use env_logger::Env;
-    use eth_types::{address, geth_types::Transaction, word, Bytes};
+    use eth_types::{
+        address,
+        geth_types::{Transaction, TransactionType},
+        word, Bytes,
+    };
     use group::{Curve, Group};
     use halo2_proofs::arithmetic::{BaseExt, CurveAffine, Field};
     use halo2_proofs::plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, SingleVerifier};
@@ -17,9 +21,8 @@ mod tests {
     use rand::SeedableRng;
     use rand_xorshift::XorShiftRng;
     use secp256k1::Secp256k1Affine;
-    use std::marker::PhantomData;
     use zkevm_circuits::tx_circuit::{
-        sign_verify::{SignVerifyChip, POW_RAND_SIZE, VERIF_HEIGHT},
+        sign_verify::{POW_RAND_SIZE, VERIF_HEIGHT},
         TxCircuit,
     };
 
@@ -53,6 +56,7 @@ mod tests {
             gas_fee_cap: word!("0x0"),
             gas_tip_cap: word!("0x0"),
             call_data: Bytes::from(b"hello"),
+            transaction_type: TransactionType::Legacy,
             access_list: None,
             v: 2710,
             r: word!("0xaf180d27f90b2b20808bc7670ce0aca862bc2b5fa39c195ab7b1a96225ee14d7"),
@@ -65,16 +69,14 @@ mod tests {
             .collect();
         // SignVerifyChip -> ECDSAChip -> MainGate instance column
         instance.push(vec![]);
-        let circuit = TxCircuit::<Fr, MAX_TXS, MAX_CALLDATA> {
-            sign_verify: SignVerifyChip {
-                aux_generator,
-                window_size: 2,
-                _marker: PhantomData,
-            },
+        let circuit = TxCircuit::<Fr>::new(
+            aux_generator,
             randomness,
-            txs,
             chain_id,
-        };
+            txs,
+            MAX_TXS,
+            MAX_CALLDATA,
+        );
 
         // Bench setup generation
         let setup_message = format!(
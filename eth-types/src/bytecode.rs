@@ -86,6 +86,12 @@ impl Bytecode {
         self
     }
 
+    /// Push a value with the smallest PUSHX opcode it fits in
+    pub fn push_value(&mut self, value: Word) -> &mut Self {
+        let n = std::cmp::max((value.bits() + 7) / 8, 1);
+        self.push(n, value)
+    }
+
     /// Add marker
     pub fn add_marker(&mut self, marker: String) -> &mut Self {
         self.insert_marker(&marker, self.num_opcodes);
@@ -110,6 +116,19 @@ impl Bytecode {
             .unwrap_or_else(|| panic!("marker '{}' not found", marker))
     }
 
+    /// Get the position in the code of the opcode at a marker, which is the
+    /// destination to use when jumping to it
+    pub fn get_code_pos(&self, marker: &str) -> usize {
+        let pos = self.get_pos(marker);
+        self.code
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.is_code)
+            .nth(pos)
+            .map(|(idx, _)| idx)
+            .unwrap_or(self.code.len())
+    }
+
     /// Setup state
     pub fn setup_state(&mut self) -> &mut Self {
         self.append(&crate::bytecode! {
@@ -193,6 +212,11 @@ macro_rules! bytecode {
 macro_rules! bytecode_internal {
     // Nothing left to do
     ($code:ident, ) => {};
+    // PUSH of a value with the smallest PUSHX op code
+    ($code:ident, PUSH ($v:expr) $($rest:tt)*) => {{
+        $code.push_value($v.into());
+        $crate::bytecode_internal!($code, $($rest)*);
+    }};
     // PUSHX op codes
     ($code:ident, $x:ident ($v:expr) $($rest:tt)*) => {{
         debug_assert!($crate::evm_types::OpcodeId::$x.is_push(), "invalid push");
@@ -241,4 +265,37 @@ mod tests {
         };
         assert_eq!(Bytecode::try_from(code.to_vec()).unwrap(), code);
     }
+
+    #[test]
+    fn test_bytecode_push_value() {
+        let code = bytecode! {
+            PUSH(0)
+            PUSH(0xff)
+            PUSH(0x100)
+            PUSH(crate::Word::MAX)
+        };
+        let expected = bytecode! {
+            PUSH1(0)
+            PUSH1(0xff)
+            PUSH2(0x100)
+            PUSH32(crate::Word::MAX)
+        };
+        assert_eq!(code, expected);
+    }
+
+    #[test]
+    fn test_bytecode_code_pos() {
+        let code = bytecode! {
+            PUSH32(0)
+            #[dest]
+            JUMPDEST
+            PUSH(0x1234)
+            #[end]
+            STOP
+        };
+        assert_eq!(code.get_pos("dest"), 1);
+        assert_eq!(code.get_code_pos("dest"), 33);
+        assert_eq!(code.get_code_pos("end"), 37);
+        assert_eq!(code.to_vec()[code.get_code_pos("dest")], 0x5b);
+    }
 }
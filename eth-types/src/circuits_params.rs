@@ -0,0 +1,48 @@
+//! Capacities of the circuits proving a block.
+
+use serde::{Deserialize, Serialize};
+
+/// Capacities of the circuits proving a block, which fix the shape of the
+/// proof independently of the content of the block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitsParams {
+    /// Maximum number of transactions
+    pub max_txs: usize,
+    /// Maximum number of bytes of call data of all the transactions
+    pub max_calldata: usize,
+    /// Maximum number of rows of the rw table
+    pub max_rws: usize,
+    /// Maximum number of rows of the bytecode table
+    pub max_bytecode: usize,
+    /// Maximum number of rows of the copy table
+    pub max_copy_rows: usize,
+    /// Maximum number of rows of the exp circuit, which takes a row per step
+    /// of each exponentiation
+    pub max_exp_rows: usize,
+    /// Maximum number of rows of the MPT circuit, which takes 64 rows per
+    /// path of each update of the state trie, and a row per byte of the
+    /// decoded nodes
+    pub max_mpt_rows: usize,
+    /// Maximum number of rows of the keccak circuit, which takes 50 rows per
+    /// block of 136 bytes of each hashed input
+    pub max_keccak_rows: usize,
+    /// Maximum number of rows of the execution steps in the EVM circuit
+    pub max_evm_rows: usize,
+}
+
+impl Default for CircuitsParams {
+    /// Default values for the tests
+    fn default() -> Self {
+        CircuitsParams {
+            max_txs: 1,
+            max_calldata: 256,
+            max_rws: 1000,
+            max_bytecode: 512,
+            max_copy_rows: 1000,
+            max_exp_rows: 1000,
+            max_mpt_rows: 1 << 15,
+            max_keccak_rows: 1 << 15,
+            max_evm_rows: 10000,
+        }
+    }
+}
@@ -31,6 +31,16 @@ pub enum Error {
     /// Error when an EvmWord is too big to be converted into a
     /// `MemoryAddress`.
     WordToMemAddr,
+    /// Error while decoding an RLP encoding.
+    RlpDecodingError(rlp::DecoderError),
+    /// Denotes that the transaction type (EIP-2718) is unknown.
+    UnknownTransactionType(u64),
+}
+
+impl From<rlp::DecoderError> for Error {
+    fn from(err: rlp::DecoderError) -> Self {
+        Error::RlpDecodingError(err)
+    }
 }
 
 impl Display for Error {
@@ -4,12 +4,14 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 
 pub mod gas_utils;
+pub mod hardfork;
 pub mod memory;
 pub mod opcode_ids;
 pub mod stack;
 pub mod storage;
 
 pub use {
+    hardfork::{BerlinSpec, CancunSpec, Hardfork, HardforkSpec, LondonSpec, ShanghaiSpec},
     memory::{Memory, MemoryAddress},
     opcode_ids::OpcodeId,
     stack::{Stack, StackAddress},
@@ -102,6 +104,8 @@ impl GasCost {
     pub const CREATE: Self = Self(32000);
     /// Constant cost for copying every word
     pub const COPY: Self = Self(3);
+    /// Constant cost for hashing every word
+    pub const COPY_SHA3: Self = Self(6);
     /// Constant cost for accessing account or storage key
     pub const WARM_ACCESS: Self = Self(100);
     /// Constant cost for a cold SLOAD
@@ -119,6 +123,12 @@ impl GasCost {
     pub const TX: Self = Self(21000);
     /// Constant cost for a creation transaction
     pub const CREATION_TX: Self = Self(53000);
+    /// Constant cost for each address in the access list of a transaction
+    /// (EIP-2930)
+    pub const ACCESS_LIST_PER_ADDRESS: Self = Self(2400);
+    /// Constant cost for each storage key in the access list of a transaction
+    /// (EIP-2930)
+    pub const ACCESS_LIST_PER_STORAGE_KEY: Self = Self(1900);
     /// Constant cost for calling with non-zero value
     pub const CALL_WITH_VALUE: Self = Self(9000);
     /// Constant cost for turning empty account into non-empty account
@@ -129,6 +139,8 @@ impl GasCost {
     pub const MEMORY_EXPANSION_LINEAR_COEFF: Self = Self(3);
     /// constant gas for logs op codes
     pub const LOG: Self = Self(375);
+    /// Gas charged for each byte of the exponent of EXP
+    pub const EXP_BYTE_TIMES: Self = Self(50);
 }
 
 impl GasCost {
@@ -0,0 +1,102 @@
+//! Hardforks of the EVM rules followed by the proven blocks
+
+use super::{GasCost, MAX_REFUND_QUOTIENT_OF_GAS_USED};
+use serde::{Deserialize, Serialize};
+
+/// Hardfork of the EVM rules followed by a block.  Only the hardforks from
+/// Berlin are supported, as the circuits assume the access lists of EIP-2929.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Hardfork {
+    /// Berlin, with the warm and cold accesses of EIP-2929
+    Berlin,
+    /// London, reducing the gas refunds with EIP-3529
+    London,
+    /// Shanghai
+    Shanghai,
+    /// Cancun, restricting SELFDESTRUCT to the accounts created in the same
+    /// transaction with EIP-6780
+    Cancun,
+}
+
+impl Default for Hardfork {
+    /// The hardfork of the chain traced by the external tracer.
+    fn default() -> Self {
+        Self::London
+    }
+}
+
+impl Hardfork {
+    /// Quotient of the gas used by a transaction bounding its gas refund,
+    /// raised from 2 to 5 by EIP-3529.
+    pub fn max_refund_quotient(&self) -> u64 {
+        if *self >= Self::London {
+            MAX_REFUND_QUOTIENT_OF_GAS_USED as u64
+        } else {
+            2
+        }
+    }
+
+    /// Gas refunded when a storage slot is cleared, lowered from 15000 to 4800
+    /// by EIP-3529.
+    pub fn sstore_clears_schedule(&self) -> GasCost {
+        if *self >= Self::London {
+            GasCost::SSTORE_CLEARS_SCHEDULE
+        } else {
+            GasCost(15000)
+        }
+    }
+
+    /// Gas refunded when an account is self destructed, removed by EIP-3529.
+    pub fn selfdestruct_refund(&self) -> GasCost {
+        if *self >= Self::London {
+            GasCost::ZERO
+        } else {
+            GasCost(24000)
+        }
+    }
+
+    /// Whether SELFDESTRUCT only deletes the accounts created in the same
+    /// transaction, and otherwise only moves their balance (EIP-6780).
+    pub fn selfdestruct_only_in_creation_tx(&self) -> bool {
+        *self >= Self::Cancun
+    }
+
+    /// Number of precompiled contracts, at the addresses from 1, which are
+    /// warm from the start of the transactions (EIP-2929).  The point
+    /// evaluation precompile is added at the address 10 by EIP-4844.
+    pub fn num_precompiles(&self) -> u64 {
+        if *self >= Self::Cancun {
+            10
+        } else {
+            9
+        }
+    }
+}
+
+/// Hardfork fixed at the configuration of a circuit, the constraints of the
+/// EVM circuit depending on the hardfork of the proven blocks.
+pub trait HardforkSpec: Clone + Copy + Default + Send + Sync + 'static {
+    /// Hardfork of the blocks proven by the circuit
+    const HARDFORK: Hardfork;
+}
+
+macro_rules! impl_hardfork_spec {
+    ($($spec:ident => $hardfork:ident),*) => {
+        $(
+            #[doc = concat!("[`HardforkSpec`] of the ", stringify!($hardfork), " blocks.")]
+            #[derive(Clone, Copy, Debug, Default)]
+            pub struct $spec;
+
+            impl HardforkSpec for $spec {
+                const HARDFORK: Hardfork = Hardfork::$hardfork;
+            }
+        )*
+    };
+}
+
+impl_hardfork_spec!(
+    BerlinSpec => Berlin,
+    LondonSpec => London,
+    ShanghaiSpec => Shanghai,
+    CancunSpec => Cancun
+);
@@ -8,7 +8,7 @@ use std::fmt;
 use std::str::FromStr;
 
 /// Opcode enum. One-to-one corresponding to an `u8` value.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum OpcodeId {
     /// `STOP`
     STOP,
@@ -279,6 +279,10 @@ pub enum OpcodeId {
     SELFBALANCE,
     /// `BASEFEE`
     BASEFEE,
+    /// `BLOBHASH` (EIP-4844)
+    BLOBHASH,
+    /// `BLOBBASEFEE` (EIP-7516)
+    BLOBBASEFEE,
     /// `SLOAD`
     SLOAD,
     /// `SSTORE`
@@ -467,6 +471,8 @@ impl OpcodeId {
             OpcodeId::CHAINID => 0x46u8,
             OpcodeId::SELFBALANCE => 0x47u8,
             OpcodeId::BASEFEE => 0x48u8,
+            OpcodeId::BLOBHASH => 0x49u8,
+            OpcodeId::BLOBBASEFEE => 0x4au8,
             OpcodeId::SLOAD => 0x54u8,
             OpcodeId::SSTORE => 0x55u8,
             OpcodeId::GAS => 0x5au8,
@@ -545,6 +551,8 @@ impl OpcodeId {
             OpcodeId::CHAINID => GasCost::QUICK,
             OpcodeId::SELFBALANCE => GasCost::FAST,
             OpcodeId::BASEFEE => GasCost::QUICK,
+            OpcodeId::BLOBHASH => GasCost::FASTEST,
+            OpcodeId::BLOBBASEFEE => GasCost::QUICK,
             OpcodeId::POP => GasCost::QUICK,
             OpcodeId::MLOAD => GasCost::FASTEST,
             OpcodeId::MSTORE => GasCost::FASTEST,
@@ -773,6 +781,8 @@ impl TryFrom<u8> for OpcodeId {
             0x46u8 => OpcodeId::CHAINID,
             0x47u8 => OpcodeId::SELFBALANCE,
             0x48u8 => OpcodeId::BASEFEE,
+            0x49u8 => OpcodeId::BLOBHASH,
+            0x4au8 => OpcodeId::BLOBBASEFEE,
             0x54u8 => OpcodeId::SLOAD,
             0x55u8 => OpcodeId::SSTORE,
             0x5au8 => OpcodeId::GAS,
@@ -922,7 +932,7 @@ impl FromStr for OpcodeId {
             "COINBASE" => OpcodeId::COINBASE,
             "TIMESTAMP" => OpcodeId::TIMESTAMP,
             "NUMBER" => OpcodeId::NUMBER,
-            "DIFFICULTY" => OpcodeId::DIFFICULTY,
+            "DIFFICULTY" | "PREVRANDAO" => OpcodeId::DIFFICULTY,
             "GASLIMIT" => OpcodeId::GASLIMIT,
             "SLOAD" => OpcodeId::SLOAD,
             "SSTORE" => OpcodeId::SSTORE,
@@ -938,9 +948,11 @@ impl FromStr for OpcodeId {
             "CALLCODE" => OpcodeId::CALLCODE,
             "DELEGATECALL" => OpcodeId::DELEGATECALL,
             "STATICCALL" => OpcodeId::STATICCALL,
-            "SELFDESTRUCT" => OpcodeId::SELFDESTRUCT,
+            "SELFDESTRUCT" | "SUICIDE" => OpcodeId::SELFDESTRUCT,
             "CHAINID" => OpcodeId::CHAINID,
             "BASEFEE" => OpcodeId::BASEFEE,
+            "BLOBHASH" => OpcodeId::BLOBHASH,
+            "BLOBBASEFEE" => OpcodeId::BLOBBASEFEE,
             _ => {
                 // Parse an invalid opcode value as reported by geth
                 lazy_static! {
@@ -960,6 +972,21 @@ impl FromStr for OpcodeId {
     }
 }
 
+impl Serialize for OpcodeId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            // Serialize an invalid opcode as reported by geth, keeping its value
+            OpcodeId::INVALID(b) => {
+                serializer.serialize_str(&format!("opcode 0x{:02x} not defined", b))
+            }
+            _ => serializer.serialize_str(&self.to_string()),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for OpcodeId {
     fn deserialize<D>(deserializer: D) -> Result<OpcodeId, D::Error>
     where
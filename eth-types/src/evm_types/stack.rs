@@ -2,12 +2,12 @@
 use crate::Error;
 use crate::{DebugWord, Word};
 use core::str::FromStr;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// Represents a `StackAddress` of the EVM.
 /// The address range goes `TOP -> DOWN (1024, 0]`.
-#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct StackAddress(pub usize);
 
 impl fmt::Debug for StackAddress {
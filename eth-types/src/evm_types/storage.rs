@@ -50,4 +50,9 @@ impl Storage {
     pub fn get_or_err(&self, key: &Word) -> Result<Word, Error> {
         self.get(key).cloned().ok_or(Error::InvalidStorageKey)
     }
+
+    /// Returns an iterator over the keys and words of the EVM storage.
+    pub fn iter(&self) -> impl Iterator<Item = (&Word, &Word)> + '_ {
+        self.0.iter()
+    }
 }
@@ -1,9 +1,13 @@
 //! Types needed for generating Ethereum traces
 
 use crate::{
-    AccessList, Address, Block, Bytes, Error, GethExecTrace, Hash, ToBigEndian, Word, U64,
+    evm_types::Hardfork, AccessList, Address, Block, Bytes, Error, GethExecTrace, Hash,
+    ToBigEndian, Withdrawal, Word, U64,
 };
-use serde::{Serialize, Serializer};
+use ethers_core::types::transaction::eip2930::AccessListItem;
+use ethers_core::utils::keccak256;
+use rlp::{DecoderError, Rlp, RlpStream};
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::HashMap;
 
 /// Definition of all of the data related to an account.
@@ -87,8 +91,154 @@ impl BlockConstants {
     }
 }
 
+/// Hardfork activation of a chain, as returned by `debug_chainConfig`. Only
+/// the hardforks from London are kept, the earlier ones up to Berlin being
+/// assumed active.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainConfig {
+    /// chain id
+    pub chain_id: u64,
+    /// Number of the first London block, if London is scheduled
+    pub london_block: Option<u64>,
+    /// Whether the chain has transitioned to proof of stake, after which the
+    /// difficulty of the blocks is replaced by their prevrandao
+    #[serde(default)]
+    pub terminal_total_difficulty_passed: bool,
+    /// Timestamp of the first Shanghai block, if Shanghai is scheduled
+    pub shanghai_time: Option<u64>,
+    /// Timestamp of the first Cancun block, if Cancun is scheduled
+    pub cancun_time: Option<u64>,
+}
+
+impl Default for ChainConfig {
+    /// The config of the chain traced by the external tracer, with all the
+    /// hardforks up to London active from the genesis.
+    fn default() -> Self {
+        Self {
+            chain_id: 0,
+            london_block: Some(0),
+            terminal_total_difficulty_passed: false,
+            shanghai_time: None,
+            cancun_time: None,
+        }
+    }
+}
+
+impl ChainConfig {
+    /// Whether London is active at the block `number`.
+    pub fn is_london(&self, number: u64) -> bool {
+        self.london_block
+            .map(|london_block| number >= london_block)
+            .unwrap_or(false)
+    }
+
+    /// Whether Shanghai is active at the block of `timestamp`.
+    pub fn is_shanghai(&self, timestamp: u64) -> bool {
+        self.shanghai_time
+            .map(|shanghai_time| timestamp >= shanghai_time)
+            .unwrap_or(false)
+    }
+
+    /// Whether Cancun is active at the block of `timestamp`.
+    pub fn is_cancun(&self, timestamp: u64) -> bool {
+        self.cancun_time
+            .map(|cancun_time| timestamp >= cancun_time)
+            .unwrap_or(false)
+    }
+
+    /// Returns the hardfork active at the block `number` of `timestamp`.
+    pub fn hardfork(&self, number: u64, timestamp: u64) -> Hardfork {
+        if self.is_cancun(timestamp) {
+            Hardfork::Cancun
+        } else if self.is_shanghai(timestamp) {
+            Hardfork::Shanghai
+        } else if self.is_london(number) {
+            Hardfork::London
+        } else {
+            Hardfork::Berlin
+        }
+    }
+}
+
+/// Type of a transaction (EIP-2718).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "U64", into = "U64")]
+pub enum TransactionType {
+    /// Legacy transaction
+    Legacy = 0,
+    /// EIP-2930 access list transaction
+    AccessList = 1,
+    /// EIP-1559 dynamic fee transaction
+    DynamicFee = 2,
+    /// EIP-4844 blob transaction
+    Blob = 3,
+}
+
+impl Default for TransactionType {
+    fn default() -> Self {
+        Self::Legacy
+    }
+}
+
+impl TryFrom<u64> for TransactionType {
+    type Error = Error;
+
+    fn try_from(tx_type: u64) -> Result<Self, Self::Error> {
+        match tx_type {
+            LEGACY_TX_TYPE => Ok(Self::Legacy),
+            ACCESS_LIST_TX_TYPE => Ok(Self::AccessList),
+            DYNAMIC_FEE_TX_TYPE => Ok(Self::DynamicFee),
+            BLOB_TX_TYPE => Ok(Self::Blob),
+            _ => Err(Error::UnknownTransactionType(tx_type)),
+        }
+    }
+}
+
+impl TryFrom<U64> for TransactionType {
+    type Error = Error;
+
+    fn try_from(tx_type: U64) -> Result<Self, Self::Error> {
+        Self::try_from(tx_type.as_u64())
+    }
+}
+
+impl From<TransactionType> for U64 {
+    fn from(tx_type: TransactionType) -> Self {
+        U64::from(tx_type as u64)
+    }
+}
+
+/// Transaction type of legacy transactions.
+pub const LEGACY_TX_TYPE: u64 = TransactionType::Legacy as u64;
+/// Transaction type of EIP-2930 access list transactions.
+pub const ACCESS_LIST_TX_TYPE: u64 = TransactionType::AccessList as u64;
+/// Transaction type of EIP-1559 dynamic fee transactions.
+pub const DYNAMIC_FEE_TX_TYPE: u64 = TransactionType::DynamicFee as u64;
+/// Transaction type of EIP-4844 blob transactions.
+pub const BLOB_TX_TYPE: u64 = TransactionType::Blob as u64;
+
+/// (De)serialization of a `u64` as a hex quantity, as in the JSON-RPC API.
+mod serde_hex_u64 {
+    use crate::U64;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        U64::from(*value).serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        U64::deserialize(deserializer).map(|value| value.as_u64())
+    }
+}
+
 /// Definition of all of the constants related to an Ethereum transaction.
-#[derive(Debug, Default, Clone, Serialize)]
+///
+/// It's serialized in the form read by the external tracer, and deserialized
+/// from this form or from the form of the transactions returned by the
+/// JSON-RPC API of geth.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Transaction {
     /// Sender address
     pub from: Address,
@@ -97,23 +247,39 @@ pub struct Transaction {
     /// Transaction nonce
     pub nonce: Word,
     /// Gas Limit / Supplied gas
+    #[serde(alias = "gas")]
     pub gas_limit: Word,
     /// Transfered value
     pub value: Word,
     /// Gas Price
+    #[serde(alias = "gasPrice")]
     pub gas_price: Word,
     /// Gas fee cap
+    #[serde(alias = "maxFeePerGas")]
     pub gas_fee_cap: Word,
     /// Gas tip cap
+    #[serde(alias = "maxPriorityFeePerGas")]
     pub gas_tip_cap: Word,
     /// The compiled code of a contract OR the first 4 bytes of the hash of the
     /// invoked method signature and encoded parameters. For details see
     /// Ethereum Contract ABI
+    #[serde(alias = "input")]
     pub call_data: Bytes,
+    /// Transaction type
+    #[serde(alias = "type")]
+    pub transaction_type: TransactionType,
     /// Access list
+    #[serde(alias = "accessList")]
     pub access_list: Option<AccessList>,
+    /// Max fee per blob gas (EIP-4844)
+    #[serde(alias = "maxFeePerBlobGas")]
+    pub max_fee_per_blob_gas: Word,
+    /// Versioned hashes of the blobs carried by the transaction (EIP-4844)
+    #[serde(alias = "blobVersionedHashes")]
+    pub blob_versioned_hashes: Vec<Hash>,
 
     /// "v" value of the transaction signature
+    #[serde(with = "serde_hex_u64")]
     pub v: u64,
     /// "r" value of the transaction signature
     pub r: Word,
@@ -122,7 +288,10 @@ pub struct Transaction {
 }
 
 impl Transaction {
-    /// Create Self from a web3 transaction
+    /// Create Self from a web3 transaction.  The transactions of a type unknown
+    /// to Ethereum, like the system transactions of a rollup, are read as
+    /// legacy transactions.  The web3 transactions don't carry the blob fields
+    /// of the blob transactions, which are left empty.
     pub fn from_eth_tx(tx: &crate::Transaction) -> Self {
         Self {
             from: tx.from,
@@ -131,15 +300,274 @@ impl Transaction {
             gas_limit: tx.gas,
             value: tx.value,
             gas_price: tx.gas_price.unwrap_or_default(),
-            gas_fee_cap: tx.max_priority_fee_per_gas.unwrap_or_default(),
-            gas_tip_cap: tx.max_fee_per_gas.unwrap_or_default(),
+            gas_fee_cap: tx.max_fee_per_gas.unwrap_or_default(),
+            gas_tip_cap: tx.max_priority_fee_per_gas.unwrap_or_default(),
             call_data: tx.input.clone(),
+            transaction_type: TransactionType::try_from(tx.transaction_type.unwrap_or_default())
+                .unwrap_or_default(),
             access_list: tx.access_list.clone(),
+            max_fee_per_blob_gas: Word::zero(),
+            blob_versioned_hashes: Vec::new(),
             v: tx.v.as_u64(),
             r: tx.r,
             s: tx.s,
         }
     }
+
+    /// Returns whether the transaction is a legacy transaction signed without
+    /// chain id, as done before EIP-155, where `v` is 27 or 28.
+    pub fn is_pre_eip155(&self) -> bool {
+        self.transaction_type == TransactionType::Legacy && (self.v == 27 || self.v == 28)
+    }
+
+    /// Returns whether the transaction replaces the gas price by the fee caps
+    /// `[maxPriorityFeePerGas, maxFeePerGas]` of EIP-1559.
+    pub fn has_fee_caps(&self) -> bool {
+        matches!(
+            self.transaction_type,
+            TransactionType::DynamicFee | TransactionType::Blob
+        )
+    }
+
+    /// Return the RLP encoding of each item `[address, [storage_key, ...]]` of
+    /// the access list of the transaction.
+    pub fn access_list_items_rlp(&self) -> Vec<Vec<u8>> {
+        self.access_list
+            .as_ref()
+            .map(|access_list| {
+                access_list
+                    .0
+                    .iter()
+                    .map(|item| {
+                        let mut stream = RlpStream::new_list(2);
+                        stream.append(&item.address);
+                        stream.begin_list(item.storage_keys.len());
+                        for key in item.storage_keys.iter() {
+                            stream.append(key);
+                        }
+                        stream.out().to_vec()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Return the RLP encoding of the transaction without signature, which is
+    /// hashed to obtain the message signed by the sender.
+    pub fn sign_rlp(&self, chain_id: u64) -> Vec<u8> {
+        if self.transaction_type != TransactionType::Legacy {
+            // msg = tx_type || rlp([chainId, nonce, gasPrice, gas, to, value, data,
+            // accessList]), where gasPrice is replaced by [maxPriorityFeePerGas,
+            // maxFeePerGas] in EIP-1559 and EIP-4844 transactions, and
+            // accessList is followed by [maxFeePerBlobGas, blobVersionedHashes]
+            // in EIP-4844 transactions.
+            let mut stream = RlpStream::new();
+            stream.begin_unbounded_list();
+            append_typed_tx_fields(&mut stream, self, chain_id);
+            stream.finalize_unbounded_list();
+            return [&[self.transaction_type as u8][..], stream.out().as_ref()].concat();
+        }
+        // msg = rlp([nonce, gasPrice, gas, to, value, data, chain_id, 0, 0]), where the
+        // last 3 fields are omitted before EIP-155.
+        let pre_eip155 = self.is_pre_eip155();
+        let mut stream = RlpStream::new_list(if pre_eip155 { 6 } else { 9 });
+        stream
+            .append(&self.nonce)
+            .append(&self.gas_price)
+            .append(&self.gas_limit);
+        append_tx_to(&mut stream, self);
+        stream.append(&self.value).append(&self.call_data.0);
+        if !pre_eip155 {
+            stream.append(&chain_id).append(&0u32).append(&0u32);
+        }
+        stream.out().to_vec()
+    }
+
+    /// Return the RLP encoding of the signed transaction, which is hashed to
+    /// obtain the transaction hash.
+    pub fn rlp(&self, chain_id: u64) -> Vec<u8> {
+        if self.transaction_type != TransactionType::Legacy {
+            // tx_type || rlp([chainId, nonce, gasPrice, gas, to, value, data, accessList,
+            // yParity, r, s]), where gasPrice is replaced by [maxPriorityFeePerGas,
+            // maxFeePerGas] in EIP-1559 and EIP-4844 transactions, and accessList
+            // is followed by [maxFeePerBlobGas, blobVersionedHashes] in EIP-4844
+            // transactions.
+            let mut stream = RlpStream::new();
+            stream.begin_unbounded_list();
+            append_typed_tx_fields(&mut stream, self, chain_id);
+            stream.append(&self.v).append(&self.r).append(&self.s);
+            stream.finalize_unbounded_list();
+            return [&[self.transaction_type as u8][..], stream.out().as_ref()].concat();
+        }
+        // rlp([nonce, gasPrice, gas, to, value, data, v, r, s])
+        let mut stream = RlpStream::new_list(9);
+        stream
+            .append(&self.nonce)
+            .append(&self.gas_price)
+            .append(&self.gas_limit);
+        append_tx_to(&mut stream, self);
+        stream
+            .append(&self.value)
+            .append(&self.call_data.0)
+            .append(&self.v)
+            .append(&self.r)
+            .append(&self.s);
+        stream.out().to_vec()
+    }
+
+    /// Return the message signed by the sender, the hash of
+    /// [`Transaction::sign_rlp`].
+    pub fn sign_hash(&self, chain_id: u64) -> Hash {
+        Hash::from(keccak256(self.sign_rlp(chain_id)))
+    }
+
+    /// Return the transaction hash, the hash of [`Transaction::rlp`].
+    pub fn hash(&self, chain_id: u64) -> Hash {
+        Hash::from(keccak256(self.rlp(chain_id)))
+    }
+
+    /// Decode a signed transaction from its encoding, as returned by
+    /// [`Transaction::rlp`], with the chain id it's signed for, which is
+    /// `None` for the legacy transactions signed before EIP-155.  The sender
+    /// isn't part of the encoding and is left to zero, to be recovered from
+    /// the signature.
+    pub fn decode_rlp(bytes: &[u8]) -> Result<(Self, Option<u64>), Error> {
+        let (transaction_type, bytes) = match bytes.first() {
+            Some(&tx_type) if tx_type < 0x80 => {
+                (TransactionType::try_from(tx_type as u64)?, &bytes[1..])
+            }
+            _ => (TransactionType::Legacy, bytes),
+        };
+        let rlp = Rlp::new(bytes);
+        let mut tx = Self {
+            transaction_type,
+            ..Default::default()
+        };
+        if transaction_type == TransactionType::Legacy {
+            // rlp([nonce, gasPrice, gas, to, value, data, v, r, s])
+            if rlp.item_count()? != 9 {
+                return Err(DecoderError::RlpIncorrectListLen.into());
+            }
+            tx.nonce = rlp.val_at(0)?;
+            tx.gas_price = rlp.val_at(1)?;
+            tx.gas_limit = rlp.val_at(2)?;
+            tx.to = decode_tx_to(&rlp.at(3)?)?;
+            tx.value = rlp.val_at(4)?;
+            tx.call_data = rlp.val_at::<Vec<u8>>(5)?.into();
+            tx.v = rlp.val_at(6)?;
+            tx.r = rlp.val_at(7)?;
+            tx.s = rlp.val_at(8)?;
+            let chain_id = (!tx.is_pre_eip155()).then(|| tx.v.saturating_sub(35) / 2);
+            return Ok((tx, chain_id));
+        }
+        // rlp([chainId, nonce, gasPrice, gas, to, value, data, accessList, yParity,
+        // r, s]), where gasPrice is replaced by [maxPriorityFeePerGas,
+        // maxFeePerGas] in EIP-1559 and EIP-4844 transactions, and accessList is
+        // followed by [maxFeePerBlobGas, blobVersionedHashes] in EIP-4844
+        // transactions.
+        let num_fee_fields = if tx.has_fee_caps() { 2 } else { 1 };
+        let num_blob_fields = if transaction_type == TransactionType::Blob {
+            2
+        } else {
+            0
+        };
+        if rlp.item_count()? != 10 + num_fee_fields + num_blob_fields {
+            return Err(DecoderError::RlpIncorrectListLen.into());
+        }
+        let chain_id = rlp.val_at(0)?;
+        tx.nonce = rlp.val_at(1)?;
+        if tx.has_fee_caps() {
+            tx.gas_tip_cap = rlp.val_at(2)?;
+            tx.gas_fee_cap = rlp.val_at(3)?;
+        } else {
+            tx.gas_price = rlp.val_at(2)?;
+        }
+        let field = |idx: usize| idx + num_fee_fields;
+        tx.gas_limit = rlp.val_at(field(2))?;
+        tx.to = decode_tx_to(&rlp.at(field(3))?)?;
+        tx.value = rlp.val_at(field(4))?;
+        tx.call_data = rlp.val_at::<Vec<u8>>(field(5))?.into();
+        tx.access_list = Some(AccessList(
+            rlp.at(field(6))?
+                .iter()
+                .map(|item| {
+                    Ok(AccessListItem {
+                        address: item.val_at(0)?,
+                        storage_keys: item.list_at(1)?,
+                    })
+                })
+                .collect::<Result<_, DecoderError>>()?,
+        ));
+        if transaction_type == TransactionType::Blob {
+            tx.max_fee_per_blob_gas = rlp.val_at(field(7))?;
+            tx.blob_versioned_hashes = rlp.list_at(field(8))?;
+        }
+        let sig_field = |idx: usize| field(idx) + num_blob_fields;
+        tx.v = rlp.val_at(sig_field(7))?;
+        tx.r = rlp.val_at(sig_field(8))?;
+        tx.s = rlp.val_at(sig_field(9))?;
+        Ok((tx, Some(chain_id)))
+    }
+}
+
+/// Return the RLP encoding of `[sender, nonce]`, whose keccak hash holds in its
+/// low 20 bytes the address of the contract created by `sender` with `nonce`
+/// in a contract creation transaction or with CREATE.
+pub fn create_address_rlp(sender: &Address, nonce: u64) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(2);
+    stream.append(sender).append(&nonce);
+    stream.out().to_vec()
+}
+
+/// Return the RLP encoding of the access list of the transaction.
+fn access_list_rlp(tx: &Transaction) -> Vec<u8> {
+    let items = tx.access_list_items_rlp();
+    let mut stream = RlpStream::new_list(items.len());
+    for item in items.iter() {
+        stream.append_raw(item, 1);
+    }
+    stream.out().to_vec()
+}
+
+/// Append the `to` field of the transaction to the RLP stream, which is
+/// encoded as an empty string in contract creation transactions.
+fn append_tx_to(stream: &mut RlpStream, tx: &Transaction) {
+    match tx.to {
+        Some(to) => stream.append(&to),
+        None => stream.append_empty_data(),
+    };
+}
+
+/// Decode the `to` field of a transaction, which is an empty string in
+/// contract creation transactions.
+fn decode_tx_to(rlp: &Rlp) -> Result<Option<Address>, DecoderError> {
+    if rlp.is_empty() {
+        Ok(None)
+    } else {
+        rlp.as_val().map(Some)
+    }
+}
+
+/// Append the fields of a typed transaction (EIP-2718) preceding the
+/// signature to the RLP stream.
+fn append_typed_tx_fields(stream: &mut RlpStream, tx: &Transaction, chain_id: u64) {
+    stream.append(&chain_id).append(&tx.nonce);
+    if tx.has_fee_caps() {
+        stream.append(&tx.gas_tip_cap).append(&tx.gas_fee_cap);
+    } else {
+        stream.append(&tx.gas_price);
+    }
+    stream.append(&tx.gas_limit);
+    append_tx_to(stream, tx);
+    stream
+        .append(&tx.value)
+        .append(&tx.call_data.0)
+        .append_raw(&access_list_rlp(tx), 1);
+    if tx.transaction_type == TransactionType::Blob {
+        stream
+            .append(&tx.max_fee_per_blob_gas)
+            .append_list(&tx.blob_versioned_hashes);
+    }
 }
 
 /// GethData is a type that contains all the information of a Ethereum block
@@ -156,4 +584,198 @@ pub struct GethData {
     pub geth_traces: Vec<GethExecTrace>,
     /// Accounts
     pub accounts: Vec<Account>,
+    /// Withdrawals of the block
+    pub withdrawals: Vec<Withdrawal>,
+}
+
+#[cfg(test)]
+mod chain_config_tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_chain_config() {
+        let config_json = r#"
+  {
+    "chainId": 1337,
+    "homesteadBlock": 0,
+    "eip150Block": 0,
+    "berlinBlock": 0,
+    "londonBlock": 5,
+    "terminalTotalDifficulty": 0,
+    "terminalTotalDifficultyPassed": true,
+    "shanghaiTime": 1681338455,
+    "cancunTime": 1710338135
+  }
+        "#;
+        let config: ChainConfig =
+            serde_json::from_str(config_json).expect("json-deserialize ChainConfig");
+        assert_eq!(
+            config,
+            ChainConfig {
+                chain_id: 1337,
+                london_block: Some(5),
+                terminal_total_difficulty_passed: true,
+                shanghai_time: Some(1681338455),
+                cancun_time: Some(1710338135),
+            }
+        );
+        assert!(!config.is_london(4));
+        assert!(config.is_london(5));
+        assert!(!config.is_shanghai(1681338454));
+        assert!(config.is_shanghai(1681338455));
+        assert!(!config.is_cancun(1710338134));
+        assert!(config.is_cancun(1710338135));
+        assert_eq!(config.hardfork(4, 0), Hardfork::Berlin);
+        assert_eq!(config.hardfork(5, 1681338454), Hardfork::London);
+        assert_eq!(config.hardfork(5, 1681338455), Hardfork::Shanghai);
+        assert_eq!(config.hardfork(5, 1710338135), Hardfork::Cancun);
+
+        // a chain still on proof of work has no Shanghai
+        let config: ChainConfig = serde_json::from_str(r#"{"chainId": 1, "londonBlock": 0}"#)
+            .expect("json-deserialize pre-merge ChainConfig");
+        assert_eq!(
+            config,
+            ChainConfig {
+                chain_id: 1,
+                ..ChainConfig::default()
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod transaction_tests {
+    use super::*;
+    use crate::{address, word, H256};
+
+    fn access_list_tx() -> Transaction {
+        Transaction {
+            to: Some(address!("0x701653d7ae8ddaa5c8cee1ee056849f271827926")),
+            nonce: word!("0x3"),
+            gas_limit: word!("0x7a120"),
+            value: word!("0x3e8"),
+            gas_price: word!("0x4d2"),
+            call_data: Bytes::from(b"hello"),
+            transaction_type: TransactionType::AccessList,
+            access_list: Some(AccessList(vec![AccessListItem {
+                address: address!("0x5f9b7e36af4ff81688f712fb738bbbc1b7348aae"),
+                storage_keys: vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)],
+            }])),
+            v: 1,
+            r: word!("0xaf180d27f90b2b20808bc7670ce0aca862bc2b5fa39c195ab7b1a96225ee14d7"),
+            s: word!("0x61159fa4664b698ea7d518526c96cd94cf4d8adf418000754be106a3a133f866"),
+            ..Transaction::default()
+        }
+    }
+
+    #[test]
+    fn create_address_rlp_hash() {
+        let sender = address!("0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        for nonce in [0, 1, 0x7f, 0x80, 0x1234] {
+            let hash = keccak256(create_address_rlp(&sender, nonce));
+            assert_eq!(
+                Address::from_slice(&hash[12..]),
+                ethers_core::utils::get_contract_address(sender, nonce)
+            );
+        }
+    }
+
+    #[test]
+    fn decode_rlp_round_trip() {
+        let chain_id = 1337;
+        let dynamic_fee_tx = Transaction {
+            transaction_type: TransactionType::DynamicFee,
+            gas_price: Word::zero(),
+            gas_fee_cap: word!("0x59682f00"),
+            gas_tip_cap: word!("0x3b9aca00"),
+            to: None,
+            ..access_list_tx()
+        };
+        let legacy_tx = Transaction {
+            transaction_type: TransactionType::Legacy,
+            access_list: None,
+            v: 35 + 2 * chain_id,
+            ..access_list_tx()
+        };
+        let pre_eip155_tx = Transaction {
+            v: 27,
+            ..legacy_tx.clone()
+        };
+        let blob_tx = Transaction {
+            transaction_type: TransactionType::Blob,
+            max_fee_per_blob_gas: word!("0x3b9aca00"),
+            blob_versioned_hashes: vec![
+                H256::from_low_u64_be(0x0100),
+                H256::from_low_u64_be(0x0101),
+            ],
+            ..dynamic_fee_tx.clone()
+        };
+        for (tx, tx_chain_id) in [
+            (access_list_tx(), Some(chain_id)),
+            (dynamic_fee_tx, Some(chain_id)),
+            (blob_tx, Some(chain_id)),
+            (legacy_tx, Some(chain_id)),
+            (pre_eip155_tx, None),
+        ] {
+            let decoded = Transaction::decode_rlp(&tx.rlp(chain_id)).unwrap();
+            assert_eq!(decoded, (tx.clone(), tx_chain_id));
+            assert_eq!(decoded.0.hash(chain_id), tx.hash(chain_id));
+        }
+
+        assert!(matches!(
+            Transaction::decode_rlp(&[0x05, 0xc0]),
+            Err(Error::UnknownTransactionType(5))
+        ));
+        assert!(matches!(
+            Transaction::decode_rlp(&[0xc0]),
+            Err(Error::RlpDecodingError(_))
+        ));
+    }
+
+    #[test]
+    fn deserialize_rpc_transaction() {
+        let tx_json = r#"
+  {
+    "blockHash": "0x2c3d1e3d3b6e0e7f6d6d9a4c1e5a4f0f5bff8e7a5b5d6e0f6a4e2a6e7f0b1c2d",
+    "blockNumber": "0x5",
+    "from": "0x00000000000000000000000000000000000000fe",
+    "gas": "0x7a120",
+    "gasPrice": "0x4d2",
+    "hash": "0x6b4b5e0a1f1e0a9d8d7c6b5a493827161504f3e2d1c0b0a0908070605040302",
+    "input": "0x68656c6c6f",
+    "nonce": "0x3",
+    "to": "0x701653d7ae8ddaa5c8cee1ee056849f271827926",
+    "transactionIndex": "0x0",
+    "value": "0x3e8",
+    "type": "0x1",
+    "accessList": [
+      {
+        "address": "0x5f9b7e36af4ff81688f712fb738bbbc1b7348aae",
+        "storageKeys": [
+          "0x0000000000000000000000000000000000000000000000000000000000000001",
+          "0x0000000000000000000000000000000000000000000000000000000000000002"
+        ]
+      }
+    ],
+    "chainId": "0x539",
+    "v": "0x1",
+    "r": "0xaf180d27f90b2b20808bc7670ce0aca862bc2b5fa39c195ab7b1a96225ee14d7",
+    "s": "0x61159fa4664b698ea7d518526c96cd94cf4d8adf418000754be106a3a133f866"
+  }
+        "#;
+        let tx: Transaction = serde_json::from_str(tx_json).expect("json-deserialize Transaction");
+        assert_eq!(
+            tx,
+            Transaction {
+                from: address!("0x00000000000000000000000000000000000000fe"),
+                ..access_list_tx()
+            }
+        );
+
+        // The form read by the external tracer is deserialized back.
+        let tx_json = serde_json::to_string(&tx).expect("json-serialize Transaction");
+        let tracer_tx: Transaction =
+            serde_json::from_str(&tx_json).expect("json-deserialize Transaction");
+        assert_eq!(tracer_tx, tx);
+    }
 }
@@ -0,0 +1,211 @@
+//! Signed 256-bit integers, as the signed opcodes of the EVM interpret a
+//! [`Word`].
+
+use crate::Word;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Div, Neg, Rem};
+
+/// Signed 256-bit integer stored as a [`Word`] in two's complement.  The
+/// arithmetic wraps around and follows the semantics of the EVM: the division
+/// and the modulo by zero are zero, and `I256::MIN / -1` overflows to
+/// `I256::MIN`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct I256(Word);
+
+impl I256 {
+    /// The smallest value, -2^255.
+    pub const MIN: I256 = I256(Word([0, 0, 0, 1 << 63]));
+    /// The largest value, 2^255 - 1.
+    pub const MAX: I256 = I256(Word([u64::MAX, u64::MAX, u64::MAX, u64::MAX >> 1]));
+    /// The value -1.
+    pub const MINUS_ONE: I256 = I256(Word::MAX);
+
+    /// Interpret the two's complement `word` as a signed integer.
+    pub fn from_raw(word: Word) -> Self {
+        Self(word)
+    }
+
+    /// Return the two's complement encoding of the value as a [`Word`].
+    pub fn into_raw(self) -> Word {
+        self.0
+    }
+
+    /// Build the value from its sign and its absolute value `abs`, wrapping
+    /// around if `abs` overflows.
+    pub fn from_sign_abs(is_negative: bool, abs: Word) -> Self {
+        let value = Self(abs);
+        if is_negative {
+            -value
+        } else {
+            value
+        }
+    }
+
+    /// Return whether the value is negative, which is when the most
+    /// significant bit is set.
+    pub fn is_negative(&self) -> bool {
+        self.0.bit(255)
+    }
+
+    /// Return whether the value is zero.
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// Return the absolute value as an unsigned [`Word`].  The absolute value
+    /// of [`I256::MIN`] is 2^255.
+    pub fn unsigned_abs(&self) -> Word {
+        if self.is_negative() {
+            (-*self).0
+        } else {
+            self.0
+        }
+    }
+
+    /// Shift the value right by `shift` bits, filling with the sign bit, as
+    /// SAR does.
+    pub fn sar(&self, shift: Word) -> Self {
+        if shift >= Word::from(256) {
+            return if self.is_negative() {
+                Self::MINUS_ONE
+            } else {
+                Self::default()
+            };
+        }
+        let shift = shift.as_usize();
+        if self.is_negative() && shift > 0 {
+            Self(!(!self.0 >> shift))
+        } else {
+            Self(self.0 >> shift)
+        }
+    }
+}
+
+impl From<i64> for I256 {
+    fn from(value: i64) -> Self {
+        Self::from_sign_abs(value < 0, Word::from(value.unsigned_abs()))
+    }
+}
+
+impl Neg for I256 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self((!self.0).overflowing_add(Word::one()).0)
+    }
+}
+
+impl Div for I256 {
+    type Output = Self;
+
+    /// Divide as SDIV does, rounding the quotient towards zero.
+    fn div(self, other: Self) -> Self {
+        if other.is_zero() {
+            return Self::default();
+        }
+        Self::from_sign_abs(
+            self.is_negative() != other.is_negative(),
+            self.unsigned_abs() / other.unsigned_abs(),
+        )
+    }
+}
+
+impl Rem for I256 {
+    type Output = Self;
+
+    /// Take the remainder as SMOD does, with the sign of the dividend.
+    fn rem(self, other: Self) -> Self {
+        if other.is_zero() {
+            return Self::default();
+        }
+        Self::from_sign_abs(
+            self.is_negative(),
+            self.unsigned_abs() % other.unsigned_abs(),
+        )
+    }
+}
+
+impl Ord for I256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.is_negative(), other.is_negative()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            // Two's complement preserves the order of values of the same sign.
+            _ => self.0.cmp(&other.0),
+        }
+    }
+}
+
+impl PartialOrd for I256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Debug for I256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for I256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_negative() {
+            write!(f, "-{}", self.unsigned_abs())
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod i256_tests {
+    use super::*;
+
+    #[test]
+    fn twos_complement() {
+        assert_eq!(I256::from(-1).into_raw(), Word::MAX);
+        assert_eq!(I256::from(-2).into_raw(), Word::MAX - 1);
+        assert_eq!(I256::from_raw(Word::MAX - 1), I256::from(-2));
+        assert_eq!(-I256::from(5), I256::from(-5));
+        assert_eq!(-I256::default(), I256::default());
+        assert_eq!(-I256::MIN, I256::MIN);
+        assert_eq!(I256::MIN.unsigned_abs(), Word::one() << 255);
+        assert_eq!(I256::from(-7).unsigned_abs(), Word::from(7));
+        assert_eq!(I256::from(-7).to_string(), "-7");
+    }
+
+    #[test]
+    fn signed_comparison() {
+        assert!(I256::from(-1) < I256::from(0));
+        assert!(I256::from(-2) < I256::from(-1));
+        assert!(I256::from(1) < I256::from(2));
+        assert!(I256::MIN < I256::from(-1));
+        assert!(I256::MAX > I256::from(1));
+        assert_eq!(I256::from(3).cmp(&I256::from(3)), Ordering::Equal);
+    }
+
+    #[test]
+    fn division_and_modulo() {
+        assert_eq!(I256::from(7) / I256::from(-2), I256::from(-3));
+        assert_eq!(I256::from(-7) / I256::from(-2), I256::from(3));
+        assert_eq!(I256::from(7) % I256::from(-2), I256::from(1));
+        assert_eq!(I256::from(-7) % I256::from(2), I256::from(-1));
+        assert_eq!(I256::from(7) / I256::default(), I256::default());
+        assert_eq!(I256::from(-7) % I256::default(), I256::default());
+        assert_eq!(I256::MIN / I256::MINUS_ONE, I256::MIN);
+        assert_eq!(I256::MIN % I256::MINUS_ONE, I256::default());
+    }
+
+    #[test]
+    fn arithmetic_shift() {
+        assert_eq!(I256::from(-8).sar(Word::from(2)), I256::from(-2));
+        assert_eq!(I256::from(-7).sar(Word::from(1)), I256::from(-4));
+        assert_eq!(I256::from(8).sar(Word::from(2)), I256::from(2));
+        assert_eq!(I256::from(-8).sar(Word::zero()), I256::from(-8));
+        assert_eq!(I256::from(-8).sar(Word::from(256)), I256::MINUS_ONE);
+        assert_eq!(I256::MAX.sar(Word::MAX), I256::default());
+    }
+}
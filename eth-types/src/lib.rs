@@ -18,10 +18,15 @@ pub mod macros;
 pub mod error;
 #[macro_use]
 pub mod bytecode;
+pub mod circuits_params;
 pub mod evm_types;
 pub mod geth_types;
+pub mod i256;
+pub mod public_data;
+pub mod vm_trace;
 
 pub use bytecode::Bytecode;
+pub use circuits_params::CircuitsParams;
 pub use error::Error;
 use halo2_proofs::{
     arithmetic::{Field as Halo2Field, FieldExt},
@@ -30,6 +35,7 @@ use halo2_proofs::{
         group::ff::PrimeField,
     },
 };
+pub use i256::I256;
 
 use crate::evm_types::{memory::Memory, stack::Stack, storage::Storage};
 use crate::evm_types::{Gas, GasCost, OpcodeId, ProgramCounter};
@@ -40,8 +46,10 @@ pub use ethers_core::types::{
     Address, Block, Bytes, H160, H256, U256, U64,
 };
 
-use serde::{de, Deserialize};
+use num_bigint::BigUint;
+use serde::{de, Deserialize, Serialize};
 use std::collections::HashMap;
+use std::convert::TryInto;
 use std::fmt;
 use std::str::FromStr;
 
@@ -87,6 +95,34 @@ pub trait ToLittleEndian {
     fn to_le_bytes(&self) -> [u8; 32];
 }
 
+/// Trait used to compute the random linear combination of a sequence of
+/// bytes.
+pub trait Rlc {
+    /// Return the random linear combination of the bytes, with the first byte
+    /// multiplied by the lowest power of `randomness`: `b_0 + b_1 * r + ...`.
+    fn rlc<F: FieldExt>(&self, randomness: F) -> F;
+
+    /// Return the random linear combination of the bytes, with the first byte
+    /// multiplied by the highest power of `randomness`, as they're
+    /// accumulated when absorbed one after the other: `b_0 * r^(n-1) + ... +
+    /// b_(n-1)`.
+    fn rlc_stream<F: FieldExt>(&self, randomness: F) -> F;
+}
+
+impl Rlc for [u8] {
+    fn rlc<F: FieldExt>(&self, randomness: F) -> F {
+        self.iter().rev().fold(F::zero(), |acc, byte| {
+            acc * randomness + F::from(*byte as u64)
+        })
+    }
+
+    fn rlc_stream<F: FieldExt>(&self, randomness: F) -> F {
+        self.iter().fold(F::zero(), |acc, byte| {
+            acc * randomness + F::from(*byte as u64)
+        })
+    }
+}
+
 // We use our own declaration of another U256 in order to implement a custom
 // deserializer that can parse U256 when returned by structLogs fields in geth
 // debug_trace* methods, which don't contain the `0x` prefix.
@@ -167,6 +203,17 @@ impl ToAddress for U256 {
     }
 }
 
+impl<F: Field> ToScalar<F> for BigUint {
+    fn to_scalar(&self) -> Option<F> {
+        let mut bytes = self.to_bytes_le();
+        if bytes.len() > 32 {
+            return None;
+        }
+        bytes.resize(32, 0);
+        F::from_repr(bytes.try_into().unwrap()).into()
+    }
+}
+
 /// Ethereum Hash (256 bits).
 pub type Hash = types::H256;
 
@@ -224,6 +271,9 @@ pub struct EIP1186ProofResponse {
     pub storage_proof: Vec<StorageProof>,
 }
 
+// The optional fields are missing in the steps returned by some geth versions,
+// or when their capture is disabled in the logger config, and the unknown
+// fields added by newer versions are ignored.
 #[derive(Deserialize)]
 #[doc(hidden)]
 struct GethExecStepInternal {
@@ -235,8 +285,10 @@ struct GethExecStepInternal {
     #[serde(rename = "gasCost")]
     gas_cost: GasCost,
     depth: u16,
+    #[serde(default)]
     error: Option<String>,
     // stack is in hex 0x prefixed
+    #[serde(default)]
     stack: Vec<DebugU256>,
     // memory is in chunks of 32 bytes, in hex
     #[serde(default)]
@@ -314,7 +366,8 @@ impl<'de> Deserialize<'de> for GethExecStep {
             refund: s.refund,
             gas_cost: s.gas_cost,
             depth: s.depth,
-            error: s.error,
+            // some geth versions report the absence of error as an empty string
+            error: s.error.filter(|error| !error.is_empty()),
             stack: Stack(s.stack.iter().map(|dw| dw.to_word()).collect::<Vec<Word>>()),
             memory: Memory::from(
                 s.memory
@@ -332,6 +385,66 @@ impl<'de> Deserialize<'de> for GethExecStep {
     }
 }
 
+/// Withdrawal of a validator from the beacon chain (EIP-4895), crediting
+/// `amount` Gwei to `address` after the transactions of the block.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Withdrawal {
+    /// Monotonically increasing index of the withdrawal
+    pub index: U64,
+    /// Index of the validator withdrawing
+    pub validator_index: U64,
+    /// Recipient of the withdrawn amount
+    pub address: Address,
+    /// Withdrawn amount in Gwei
+    pub amount: U64,
+}
+
+impl Withdrawal {
+    /// Returns the withdrawn amount in Wei.
+    pub fn amount_wei(&self) -> Word {
+        Word::from(self.amount.as_u64()) * Word::exp10(9)
+    }
+}
+
+/// Helper type built to deal with the `withdrawals` field of the blocks
+/// returned by `eth_getBlockByHash` and `eth_getBlockByNumber`, which is
+/// missing in the blocks before Shanghai.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[doc(hidden)]
+pub struct BlockWithdrawals {
+    #[serde(default)]
+    pub withdrawals: Vec<Withdrawal>,
+}
+
+/// Blob fields of a transaction (EIP-4844), which are empty in the
+/// transactions of the other types.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionBlobs {
+    /// Max fee per blob gas
+    #[serde(default)]
+    pub max_fee_per_blob_gas: Word,
+    /// Versioned hashes of the blobs carried by the transaction
+    #[serde(default)]
+    pub blob_versioned_hashes: Vec<Hash>,
+}
+
+/// Helper type built to deal with the blob gas fields (EIP-4844) of the blocks
+/// returned by `eth_getBlockByHash` and `eth_getBlockByNumber`, and with the
+/// blob fields of their transactions, which are missing before Cancun.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[doc(hidden)]
+pub struct BlockBlobs {
+    #[serde(default)]
+    pub blob_gas_used: U64,
+    #[serde(default)]
+    pub excess_blob_gas: U64,
+    #[serde(default)]
+    pub transactions: Vec<TransactionBlobs>,
+}
+
 /// Helper type built to deal with the weird `result` field added between
 /// `GethExecutionTrace`s in `debug_traceBlockByHash` and
 /// `debug_traceBlockByNumber` Geth JSON-RPC calls.
@@ -341,13 +454,35 @@ pub struct ResultGethExecTraces(pub Vec<ResultGethExecTrace>);
 
 /// Helper type built to deal with the weird `result` field added between
 /// `GethExecutionTrace`s in `debug_traceBlockByHash` and
-/// `debug_traceBlockByNumber` Geth JSON-RPC calls.
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+/// `debug_traceBlockByNumber` Geth JSON-RPC calls.  Nethermind returns the
+/// `GethExecutionTrace`s of its geth-like tracer without this field.
+#[derive(Clone, Debug, Eq, PartialEq)]
 #[doc(hidden)]
 pub struct ResultGethExecTrace {
     pub result: GethExecTrace,
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+#[doc(hidden)]
+enum ResultGethExecTraceInternal {
+    Result { result: GethExecTrace },
+    Trace(GethExecTrace),
+}
+
+impl<'de> Deserialize<'de> for ResultGethExecTrace {
+    fn deserialize<D>(deserializer: D) -> Result<ResultGethExecTrace, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let result = match ResultGethExecTraceInternal::deserialize(deserializer)? {
+            ResultGethExecTraceInternal::Result { result } => result,
+            ResultGethExecTraceInternal::Trace(trace) => trace,
+        };
+        Ok(Self { result })
+    }
+}
+
 /// The execution trace type returned by geth RPC debug_trace* methods.
 /// Corresponds to `ExecutionResult` in `go-ethereum/internal/ethapi/api.go`.
 /// The deserialization truncates the memory of each step in `struct_logs` to
@@ -358,9 +493,10 @@ pub struct GethExecTrace {
     /// Used gas
     pub gas: Gas,
     /// True when the transaction has failed.
+    #[serde(default)]
     pub failed: bool,
     /// Return value of execution which is a hex encoded byte array
-    #[serde(rename = "returnValue")]
+    #[serde(rename = "returnValue", default)]
     pub return_value: String,
     /// Vector of geth execution steps of the trace.
     #[serde(rename = "structLogs")]
@@ -541,6 +677,171 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn deserialize_block_withdrawals() {
+        let block_json = r#"
+  {
+    "number": "0x1",
+    "withdrawals": [
+      {
+        "index": "0x1f",
+        "validatorIndex": "0x7a",
+        "address": "0x00000000000000000000000000000000000000fe",
+        "amount": "0x3b9aca00"
+      }
+    ]
+  }
+        "#;
+        let block: BlockWithdrawals =
+            serde_json::from_str(block_json).expect("json-deserialize BlockWithdrawals");
+        assert_eq!(
+            block.withdrawals,
+            vec![Withdrawal {
+                index: U64::from(0x1f),
+                validator_index: U64::from(0x7a),
+                address: Address::from_low_u64_be(0xfe),
+                amount: U64::from(1_000_000_000),
+            }]
+        );
+        assert_eq!(block.withdrawals[0].amount_wei(), Word::exp10(18));
+
+        // blocks before Shanghai have no withdrawals
+        let block: BlockWithdrawals =
+            serde_json::from_str(r#"{"number": "0x1"}"#).expect("json-deserialize empty");
+        assert_eq!(block, BlockWithdrawals::default());
+    }
+
+    #[test]
+    fn deserialize_block_blobs() {
+        let block_json = r#"
+  {
+    "number": "0x1",
+    "blobGasUsed": "0x40000",
+    "excessBlobGas": "0x20000",
+    "transactions": [
+      {
+        "type": "0x2",
+        "maxFeePerGas": "0x59682f00"
+      },
+      {
+        "type": "0x3",
+        "maxFeePerGas": "0x59682f00",
+        "maxFeePerBlobGas": "0x3b9aca00",
+        "blobVersionedHashes": [
+          "0x0100000000000000000000000000000000000000000000000000000000000001",
+          "0x0100000000000000000000000000000000000000000000000000000000000002"
+        ]
+      }
+    ]
+  }
+        "#;
+        let block: BlockBlobs =
+            serde_json::from_str(block_json).expect("json-deserialize BlockBlobs");
+        // versioned hashes of KZG commitments start with the version 0x01
+        let versioned_hash = |idx: u64| {
+            let mut hash = Hash::from_low_u64_be(idx);
+            hash.0[0] = 1;
+            hash
+        };
+        assert_eq!(
+            block,
+            BlockBlobs {
+                blob_gas_used: U64::from(0x40000),
+                excess_blob_gas: U64::from(0x20000),
+                transactions: vec![
+                    TransactionBlobs::default(),
+                    TransactionBlobs {
+                        max_fee_per_blob_gas: Word::from(1_000_000_000),
+                        blob_versioned_hashes: vec![versioned_hash(1), versioned_hash(2)],
+                    },
+                ],
+            }
+        );
+
+        // blocks before Cancun have no blob gas fields
+        let block: BlockBlobs =
+            serde_json::from_str(r#"{"number": "0x1"}"#).expect("json-deserialize empty");
+        assert_eq!(block, BlockBlobs::default());
+    }
+
+    #[test]
+    fn deserialize_geth_exec_trace_missing_and_extra_fields() {
+        // Trace with the stack capture disabled, without the optional fields
+        // of the trace and the steps, and with fields unknown to the parser.
+        let trace_json = r#"
+  {
+    "gas": 21000,
+    "structLogs": [
+      {
+        "pc": 0,
+        "op": "PREVRANDAO",
+        "gas": 22705,
+        "gasCost": 2,
+        "depth": 1,
+        "error": "",
+        "returnData": "0x"
+      },
+      {
+        "pc": 1,
+        "op": "STOP",
+        "gas": 22703,
+        "gasCost": 0,
+        "depth": 1,
+        "error": "out of gas"
+      }
+    ],
+    "txHash": "0x0000000000000000000000000000000000000000000000000000000000000000"
+  }
+        "#;
+        let trace: GethExecTrace =
+            serde_json::from_str(trace_json).expect("json-deserialize GethExecTrace");
+        let step = |pc, op, gas, gas_cost, error: Option<&str>| GethExecStep {
+            pc: ProgramCounter(pc),
+            op,
+            gas: Gas(gas),
+            refund: Gas(0),
+            gas_cost: GasCost(gas_cost),
+            depth: 1,
+            error: error.map(str::to_owned),
+            stack: Stack::new(),
+            memory: Memory::new(),
+            storage: Storage::empty(),
+        };
+        assert_eq!(
+            trace,
+            GethExecTrace {
+                gas: Gas(21000),
+                failed: false,
+                return_value: "".to_owned(),
+                struct_logs: vec![
+                    step(0, OpcodeId::DIFFICULTY, 22705, 2, None),
+                    step(1, OpcodeId::STOP, 22703, 0, Some("out of gas")),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn deserialize_result_geth_exec_traces_with_and_without_result() {
+        // geth wraps each trace in a `result` field, while Nethermind doesn't.
+        let traces_json = r#"
+  [
+    { "result": { "gas": 21000, "failed": false, "returnValue": "", "structLogs": [] } },
+    { "gas": 22000, "failed": true, "returnValue": "", "structLogs": [] }
+  ]
+        "#;
+        let traces: ResultGethExecTraces =
+            serde_json::from_str(traces_json).expect("json-deserialize ResultGethExecTraces");
+        assert_eq!(
+            traces
+                .0
+                .into_iter()
+                .map(|trace| (trace.result.gas, trace.result.failed))
+                .collect::<Vec<_>>(),
+            vec![(Gas(21000), false), (Gas(22000), true)]
+        );
+    }
 }
 
 #[cfg(test)]
@@ -593,6 +894,30 @@ mod eth_types_test {
         )
     }
 
+    #[test]
+    fn biguint_to_scalar() {
+        assert_eq!(
+            BigUint::from(u128::MAX).to_scalar(),
+            Some(Fr::from_u128(u128::MAX))
+        );
+        // Values that don't fit in the field have no scalar.
+        let modulus = BigUint::parse_bytes(&Fr::MODULUS.as_bytes()[2..], 16).unwrap();
+        assert_eq!(ToScalar::<Fr>::to_scalar(&modulus), None);
+        assert_eq!(
+            ToScalar::<Fr>::to_scalar(&(BigUint::from(1u64) << 256)),
+            None
+        );
+    }
+
+    #[test]
+    fn bytes_rlc() {
+        let randomness = Fr::from(0x100);
+        let bytes = [1u8, 2, 3];
+        assert_eq!(bytes.rlc(randomness), Fr::from(0x030201));
+        assert_eq!(bytes.rlc_stream(randomness), Fr::from(0x010203));
+        assert_eq!(Vec::<u8>::new().rlc(randomness), Fr::zero());
+    }
+
     #[test]
     fn word_bytes_serialization_trip() -> Result<(), Error> {
         let first_usize = 64536usize;
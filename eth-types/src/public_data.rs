@@ -0,0 +1,516 @@
+//! Public data of the blocks proven by the circuits, from which the verifier
+//! recomputes the public inputs of a proof.
+//!
+//! This module only depends on the types of this crate, so that verifying a
+//! proof doesn't pull the circuits in.
+
+use crate::{Address, Field, Rlc, ToLittleEndian, ToScalar, Withdrawal, Word, H256};
+use ethers_core::utils::keccak256;
+use itertools::Itertools;
+
+/// Number of ancestor block hashes in the block table, which are the ones
+/// available to the BLOCKHASH opcode.
+pub const MAX_HISTORY_HASHES: usize = 256;
+
+/// Tag of a field of a transaction in the tx table.
+#[derive(Clone, Copy, Debug)]
+pub enum TxContextFieldTag {
+    /// Nonce of the sender
+    Nonce = 1,
+    /// Gas limit
+    Gas,
+    /// Gas price
+    GasPrice,
+    /// Address of the sender
+    CallerAddress,
+    /// Address of the callee
+    CalleeAddress,
+    /// Whether the transaction creates a contract
+    IsCreate,
+    /// Ether amount transferred
+    Value,
+    /// Length of the call data
+    CallDataLength,
+    /// Gas cost of the call data
+    CallDataGasCost,
+    /// Transaction type (EIP-2718)
+    TxType,
+    /// Max fee per gas (EIP-1559)
+    MaxFeePerGas,
+    /// Max priority fee per gas (EIP-1559)
+    MaxPriorityFeePerGas,
+    /// Byte of the call data, indexed by its position
+    CallData,
+}
+
+/// Tag of a field of a block in the block table.
+// Keep the sequence consistent with OpcodeId for scalar
+#[derive(Clone, Copy, Debug)]
+pub enum BlockContextFieldTag {
+    /// Address of the miner
+    Coinbase = 1,
+    /// Timestamp
+    Timestamp,
+    /// Number
+    Number,
+    /// Difficulty
+    Difficulty,
+    /// Gas limit
+    GasLimit,
+    /// Base fee
+    BaseFee = 8,
+    /// Hash of an ancestor block, indexed by its number
+    BlockHash,
+    /// Chain id
+    ChainId,
+}
+
+/// The constants and the history hashes of a block, which fill the block
+/// table.
+#[derive(Debug, Default, Clone)]
+pub struct BlockContext {
+    /// The address of the miner for the block
+    pub coinbase: Address,
+    /// The gas limit of the block
+    pub gas_limit: u64,
+    /// The number of the block
+    pub number: Word,
+    /// The timestamp of the block
+    pub timestamp: Word,
+    /// The difficulty of the blcok
+    pub difficulty: Word,
+    /// The base fee, the minimum amount of gas fee for a transaction
+    pub base_fee: Word,
+    /// The hash of previous blocks, the latest one being the last
+    pub history_hashes: Vec<Word>,
+    /// The chain id
+    pub chain_id: Word,
+}
+
+impl BlockContext {
+    /// Returns the rows of the block table holding the fields of the block
+    /// followed by its history hashes, the latest one first.
+    pub fn table_assignments<F: Field>(&self, randomness: F) -> Vec<[F; 3]> {
+        [
+            vec![
+                [
+                    F::from(BlockContextFieldTag::Coinbase as u64),
+                    F::zero(),
+                    self.coinbase.to_scalar().unwrap(),
+                ],
+                [
+                    F::from(BlockContextFieldTag::Timestamp as u64),
+                    F::zero(),
+                    self.timestamp.to_scalar().unwrap(),
+                ],
+                [
+                    F::from(BlockContextFieldTag::Number as u64),
+                    F::zero(),
+                    self.number.to_scalar().unwrap(),
+                ],
+                [
+                    F::from(BlockContextFieldTag::Difficulty as u64),
+                    F::zero(),
+                    self.difficulty.to_le_bytes().rlc(randomness),
+                ],
+                [
+                    F::from(BlockContextFieldTag::GasLimit as u64),
+                    F::zero(),
+                    F::from(self.gas_limit),
+                ],
+                [
+                    F::from(BlockContextFieldTag::BaseFee as u64),
+                    F::zero(),
+                    self.base_fee.to_le_bytes().rlc(randomness),
+                ],
+                [
+                    F::from(BlockContextFieldTag::ChainId as u64),
+                    F::zero(),
+                    self.chain_id.to_scalar().unwrap(),
+                ],
+            ],
+            self.history_hashes
+                .iter()
+                .rev()
+                .enumerate()
+                .map(|(idx, hash)| {
+                    [
+                        F::from(BlockContextFieldTag::BlockHash as u64),
+                        (self.number - idx - 1).to_scalar().unwrap(),
+                        hash.to_le_bytes().rlc(randomness),
+                    ]
+                })
+                .collect(),
+        ]
+        .concat()
+    }
+
+    /// Returns all the rows of the block table: the all-zero row followed by
+    /// the rows of the block, padded with all-zero rows up to
+    /// `MAX_HISTORY_HASHES` hashes.
+    pub fn padded_table_assignments<F: Field>(&self, randomness: F) -> Vec<[F; 3]> {
+        assert!(self.history_hashes.len() <= MAX_HISTORY_HASHES);
+        let num_rows = 1 + BlockContext::default().table_assignments(randomness).len();
+        std::iter::once([F::zero(); 3])
+            .chain(self.table_assignments(randomness))
+            .pad_using(num_rows + MAX_HISTORY_HASHES, |_| [F::zero(); 3])
+            .collect()
+    }
+}
+
+/// The fields of a transaction committed in the tx table.
+#[derive(Debug, Default, Clone)]
+pub struct TxPublicData {
+    /// The transaction identifier in the block
+    pub id: usize,
+    /// The sender account nonce of the transaction
+    pub nonce: u64,
+    /// The gas limit of the transaction
+    pub gas: u64,
+    /// The gas price
+    pub gas_price: Word,
+    /// The transaction type (EIP-2718)
+    pub tx_type: u64,
+    /// The max fee per gas (EIP-1559)
+    pub max_fee_per_gas: Word,
+    /// The max priority fee per gas (EIP-1559)
+    pub max_priority_fee_per_gas: Word,
+    /// The caller address
+    pub caller_address: Address,
+    /// The callee address
+    pub callee_address: Address,
+    /// Whether it's a create transaction
+    pub is_create: bool,
+    /// The ether amount of the transaction
+    pub value: Word,
+    /// The call data
+    pub call_data: Vec<u8>,
+    /// The call data length
+    pub call_data_length: usize,
+    /// The gas cost for transaction call data
+    pub call_data_gas_cost: u64,
+}
+
+impl TxPublicData {
+    /// Returns the rows of the tx table holding the fields of the transaction
+    /// followed by a row per byte of its call data.
+    pub fn table_assignments<F: Field>(&self, randomness: F) -> Vec<[F; 4]> {
+        let id = F::from(self.id as u64);
+        [
+            vec![
+                [
+                    id,
+                    F::from(TxContextFieldTag::Nonce as u64),
+                    F::zero(),
+                    F::from(self.nonce),
+                ],
+                [
+                    id,
+                    F::from(TxContextFieldTag::Gas as u64),
+                    F::zero(),
+                    F::from(self.gas),
+                ],
+                [
+                    id,
+                    F::from(TxContextFieldTag::GasPrice as u64),
+                    F::zero(),
+                    self.gas_price.to_le_bytes().rlc(randomness),
+                ],
+                [
+                    id,
+                    F::from(TxContextFieldTag::CallerAddress as u64),
+                    F::zero(),
+                    self.caller_address.to_scalar().unwrap(),
+                ],
+                [
+                    id,
+                    F::from(TxContextFieldTag::CalleeAddress as u64),
+                    F::zero(),
+                    self.callee_address.to_scalar().unwrap(),
+                ],
+                [
+                    id,
+                    F::from(TxContextFieldTag::IsCreate as u64),
+                    F::zero(),
+                    F::from(self.is_create as u64),
+                ],
+                [
+                    id,
+                    F::from(TxContextFieldTag::Value as u64),
+                    F::zero(),
+                    self.value.to_le_bytes().rlc(randomness),
+                ],
+                [
+                    id,
+                    F::from(TxContextFieldTag::CallDataLength as u64),
+                    F::zero(),
+                    F::from(self.call_data_length as u64),
+                ],
+                [
+                    id,
+                    F::from(TxContextFieldTag::CallDataGasCost as u64),
+                    F::zero(),
+                    F::from(self.call_data_gas_cost),
+                ],
+                [
+                    id,
+                    F::from(TxContextFieldTag::TxType as u64),
+                    F::zero(),
+                    F::from(self.tx_type),
+                ],
+                [
+                    id,
+                    F::from(TxContextFieldTag::MaxFeePerGas as u64),
+                    F::zero(),
+                    self.max_fee_per_gas.to_le_bytes().rlc(randomness),
+                ],
+                [
+                    id,
+                    F::from(TxContextFieldTag::MaxPriorityFeePerGas as u64),
+                    F::zero(),
+                    self.max_priority_fee_per_gas.to_le_bytes().rlc(randomness),
+                ],
+            ],
+            self.call_data
+                .iter()
+                .enumerate()
+                .map(|(idx, byte)| {
+                    [
+                        id,
+                        F::from(TxContextFieldTag::CallData as u64),
+                        F::from(idx as u64),
+                        F::from(*byte as u64),
+                    ]
+                })
+                .collect(),
+        ]
+        .concat()
+    }
+}
+
+/// Public data of a block committed by the PI circuit.
+#[derive(Clone, Debug, Default)]
+pub struct BlockPublicData {
+    /// The constants and the history hashes of the block.
+    pub context: BlockContext,
+    /// State root before the block.
+    pub prev_state_root: H256,
+    /// State root after the block.
+    pub state_root: H256,
+    /// Hash of the block.
+    pub hash: H256,
+    /// Transactions of the block.
+    pub txs: Vec<TxPublicData>,
+    /// Withdrawals of the block.
+    pub withdrawals: Vec<Withdrawal>,
+    /// Blob gas used by the transactions of the block (EIP-4844).
+    pub blob_gas_used: u64,
+    /// Excess blob gas of the block (EIP-4844).
+    pub excess_blob_gas: u64,
+}
+
+impl BlockPublicData {
+    /// Returns the public data of an empty block following this one, which
+    /// pads the chunk up to its maximum number of blocks.
+    pub fn padding_successor(&self) -> Self {
+        let history_hashes = &self.context.history_hashes;
+        let history_hashes = history_hashes
+            [(history_hashes.len() + 1).saturating_sub(MAX_HISTORY_HASHES)..]
+            .iter()
+            .cloned()
+            .chain([Word::from_big_endian(self.hash.as_bytes())])
+            .collect();
+        Self {
+            context: BlockContext {
+                number: self.context.number + 1,
+                history_hashes,
+                chain_id: self.context.chain_id,
+                ..BlockContext::default()
+            },
+            prev_state_root: self.state_root,
+            state_root: self.state_root,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the raw public inputs following the cells of the block table:
+    /// the state roots before and after the block and the hash of the block.
+    pub fn roots_and_hash<F: Field>(&self, randomness: F) -> [F; 3] {
+        [self.prev_state_root, self.state_root, self.hash].map(|hash| {
+            let mut bytes = hash.to_fixed_bytes();
+            bytes.reverse();
+            bytes.rlc(randomness)
+        })
+    }
+
+    /// Returns the raw public inputs following the state roots and the hash of
+    /// the block: the blob gas used by the block and its excess blob gas.
+    pub fn blob_gas<F: Field>(&self) -> [F; 2] {
+        [self.blob_gas_used, self.excess_blob_gas].map(F::from)
+    }
+}
+
+/// Public data of a chunk of consecutive blocks committed by the PI circuit.
+#[derive(Clone, Debug, Default)]
+pub struct PublicData {
+    /// The blocks of the chunk, each one following the previous one.
+    pub blocks: Vec<BlockPublicData>,
+}
+
+impl PublicData {
+    /// Returns the blocks of the chunk followed by empty padding blocks up to
+    /// `max_blocks` blocks.
+    pub fn padded_blocks(&self, max_blocks: usize) -> Vec<BlockPublicData> {
+        assert!(self.blocks.len() <= max_blocks);
+        let mut blocks = self.blocks.clone();
+        while blocks.len() < max_blocks {
+            blocks.push(match blocks.last() {
+                Some(block) => block.padding_successor(),
+                None => BlockPublicData::default(),
+            });
+        }
+        blocks
+    }
+
+    /// Returns the rows of the tx table: the all-zero row followed by the rows
+    /// of each transaction of the chunk, numbered across the blocks, the
+    /// unused transaction slots up to `max_txs` being padding transactions
+    /// whose fields are all zero, and padded with all-zero rows up to
+    /// `max_calldata` call data bytes.
+    pub fn tx_table_rows<F: Field>(
+        &self,
+        max_txs: usize,
+        max_calldata: usize,
+        randomness: F,
+    ) -> Vec<[F; 4]> {
+        let txs = self
+            .blocks
+            .iter()
+            .flat_map(|block| block.txs.iter())
+            .collect::<Vec<_>>();
+        assert!(txs.len() <= max_txs);
+        let calldata_len: usize = txs.iter().map(|tx| tx.call_data.len()).sum();
+        assert!(calldata_len <= max_calldata);
+
+        let rows: Vec<[F; 4]> = std::iter::once([F::zero(); 4])
+            .chain(
+                txs.into_iter()
+                    .cloned()
+                    .pad_using(max_txs, |_| TxPublicData::default())
+                    .enumerate()
+                    .flat_map(|(idx, tx)| {
+                        TxPublicData { id: idx + 1, ..tx }.table_assignments(randomness)
+                    }),
+            )
+            .collect();
+        let num_rows = rows.len() + max_calldata - calldata_len;
+        rows.into_iter()
+            .pad_using(num_rows, |_| [F::zero(); 4])
+            .collect()
+    }
+
+    /// Returns the rows of the withdrawal table, each row holding the index,
+    /// the validator index, the recipient and the amount in Gwei of a
+    /// withdrawal of the chunk, padded with all-zero rows up to
+    /// `max_withdrawals` withdrawals.
+    pub fn withdrawal_table_rows<F: Field>(&self, max_withdrawals: usize) -> Vec<[F; 4]> {
+        let withdrawals = self
+            .blocks
+            .iter()
+            .flat_map(|block| block.withdrawals.iter())
+            .collect::<Vec<_>>();
+        assert!(withdrawals.len() <= max_withdrawals);
+        withdrawals
+            .into_iter()
+            .map(|withdrawal| {
+                [
+                    F::from(withdrawal.index.as_u64()),
+                    F::from(withdrawal.validator_index.as_u64()),
+                    withdrawal.address.to_scalar().unwrap(),
+                    F::from(withdrawal.amount.as_u64()),
+                ]
+            })
+            .pad_using(max_withdrawals, |_| [F::zero(); 4])
+            .collect()
+    }
+
+    /// Returns the raw public inputs, made of the cells of the block table row
+    /// by row followed by the state roots before and after the block, the
+    /// hash of the block, the blob gas used by the block and its excess blob
+    /// gas, for each of the `max_blocks` blocks, then the cells
+    /// of the tx table row by row and the cells of the withdrawal table row by
+    /// row.
+    pub fn raw_public_inputs<F: Field>(
+        &self,
+        max_blocks: usize,
+        max_txs: usize,
+        max_calldata: usize,
+        max_withdrawals: usize,
+        randomness: F,
+    ) -> Vec<F> {
+        self.padded_blocks(max_blocks)
+            .iter()
+            .flat_map(|block| {
+                block
+                    .context
+                    .padded_table_assignments(randomness)
+                    .into_iter()
+                    .flatten()
+                    .chain(block.roots_and_hash(randomness))
+                    .chain(block.blob_gas())
+            })
+            .chain(
+                self.tx_table_rows(max_txs, max_calldata, randomness)
+                    .into_iter()
+                    .flatten(),
+            )
+            .chain(
+                self.withdrawal_table_rows(max_withdrawals)
+                    .into_iter()
+                    .flatten(),
+            )
+            .collect()
+    }
+
+    /// Returns the bytes of the raw public inputs hashed into the digest,
+    /// each input being given by its 32 bytes in big endian.
+    pub fn rpi_bytes<F: Field>(
+        &self,
+        max_blocks: usize,
+        max_txs: usize,
+        max_calldata: usize,
+        max_withdrawals: usize,
+        randomness: F,
+    ) -> Vec<u8> {
+        self.raw_public_inputs(
+            max_blocks,
+            max_txs,
+            max_calldata,
+            max_withdrawals,
+            randomness,
+        )
+        .into_iter()
+        .flat_map(|rpi| rpi.to_repr().into_iter().rev())
+        .collect()
+    }
+
+    /// Returns the digest of the raw public inputs, as its high and low 128
+    /// bits, which is the instance of the PI circuit.
+    pub fn digest<F: Field>(
+        &self,
+        max_blocks: usize,
+        max_txs: usize,
+        max_calldata: usize,
+        max_withdrawals: usize,
+        randomness: F,
+    ) -> [F; 2] {
+        let digest = keccak256(self.rpi_bytes(
+            max_blocks,
+            max_txs,
+            max_calldata,
+            max_withdrawals,
+            randomness,
+        ));
+        [&digest[..16], &digest[16..]].map(|half| Word::from_big_endian(half).to_scalar().unwrap())
+    }
+}
@@ -0,0 +1,735 @@
+//! Types of the `trace_replay*` RPC methods of Erigon and Nethermind, which
+//! trace the executed instructions in the `vmTrace` format of OpenEthereum,
+//! and their conversion into the [`GethExecTrace`] of the struct logger of
+//! geth.
+
+use crate::evm_types::{
+    memory::Memory, stack::Stack, storage::Storage, Gas, GasCost, OpcodeId, ProgramCounter,
+};
+use crate::{Bytes, Error, GethExecStep, GethExecTrace, Transaction, Word};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Maximum number of items of the stack.
+const STACK_LIMIT: usize = 1024;
+
+/// Replay of a transaction returned by `trace_replayBlockTransactions` with
+/// the `trace` and `vmTrace` trace types.
+#[derive(Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct TraceReplay {
+    /// Output of the transaction
+    pub output: Bytes,
+    /// Traces of the calls, the first one being the call of the transaction.
+    #[serde(default)]
+    pub trace: Vec<CallTrace>,
+    /// Trace of the executed instructions
+    #[serde(rename = "vmTrace")]
+    pub vm_trace: Option<VmTrace>,
+}
+
+/// Trace of a call.  Only the fields needed by the conversion are kept.
+#[derive(Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct CallTrace {
+    /// Error of the call, if it has failed.
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Result of the call, missing if it has failed.
+    #[serde(default)]
+    pub result: Option<CallTraceResult>,
+}
+
+/// Result of a successful call.
+#[derive(Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct CallTraceResult {
+    /// Gas used by the call
+    #[serde(rename = "gasUsed")]
+    pub gas_used: Word,
+}
+
+/// Trace of the instructions executed in the bytecode of a call.
+#[derive(Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct VmTrace {
+    /// Bytecode of the call
+    pub code: Bytes,
+    /// Executed instructions
+    pub ops: Vec<VmOperation>,
+}
+
+/// Instruction of a [`VmTrace`].
+#[derive(Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct VmOperation {
+    /// Program counter
+    pub pc: usize,
+    /// Gas cost, which includes the gas given to the callee for the *CALL*
+    /// and CREATE* instructions.
+    pub cost: u64,
+    /// Effects of the instruction, missing if it has failed.
+    #[serde(default)]
+    pub ex: Option<VmExecutedOperation>,
+    /// Trace of the call made by the instruction, if any.
+    #[serde(default)]
+    pub sub: Option<VmTrace>,
+}
+
+/// Effects of a [`VmOperation`].
+#[derive(Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct VmExecutedOperation {
+    /// Gas left after the instruction
+    pub used: u64,
+    /// Items at the top of the stack after the instruction that have been
+    /// pushed by it, the top one being the last.
+    pub push: Vec<Word>,
+    /// Bytes written to the memory
+    #[serde(default)]
+    pub mem: Option<MemoryDiff>,
+    /// Storage slot written
+    #[serde(default)]
+    pub store: Option<StorageDiff>,
+}
+
+/// Bytes written to the memory by a [`VmOperation`].
+#[derive(Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct MemoryDiff {
+    /// Offset of the bytes in the memory
+    pub off: usize,
+    /// Bytes written
+    pub data: Bytes,
+}
+
+/// Storage slot written by a [`VmOperation`].
+#[derive(Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct StorageDiff {
+    /// Storage key
+    pub key: Word,
+    /// Value written
+    pub val: Word,
+}
+
+/// The struct logs are rebuilt by replaying the effects of the instructions
+/// on the stack, the memory and the storage of each call.  The refund counter
+/// isn't part of the `vmTrace` format, so the traces are rejected unless none
+/// of their instructions is known to change it, and the errors reported by
+/// geth in the struct logs are recovered from the failed instructions.  The
+/// gas limit of the transaction of the trace gives the gas it has used.
+impl TryFrom<(TraceReplay, &Transaction)> for GethExecTrace {
+    type Error = Error;
+
+    fn try_from((replay, tx): (TraceReplay, &Transaction)) -> Result<Self, Self::Error> {
+        let mut state = ReplayState::default();
+        let gas_left = match &replay.vm_trace {
+            Some(vm_trace) if !vm_trace.ops.is_empty() => replay_vm_trace(vm_trace, 1, &mut state)?,
+            // Without instructions, only the intrinsic gas is used.
+            _ => tx.gas.low_u64().saturating_sub(intrinsic_gas(tx)),
+        };
+        Ok(Self {
+            gas: Gas(tx.gas.low_u64().saturating_sub(gas_left)),
+            failed: replay
+                .trace
+                .first()
+                .map(|call_trace| call_trace.error.is_some())
+                .unwrap_or_default(),
+            return_value: hex::encode(&replay.output),
+            struct_logs: state.struct_logs,
+        })
+    }
+}
+
+/// Return the intrinsic gas of `tx`, without the cost of its init code
+/// (EIP-3860), which is only charged for creation transactions with code.
+fn intrinsic_gas(tx: &Transaction) -> u64 {
+    let tx_cost = if tx.to.is_none() {
+        GasCost::CREATION_TX
+    } else {
+        GasCost::TX
+    };
+    let call_data_cost = tx
+        .input
+        .iter()
+        .map(|byte| if *byte == 0 { 4 } else { 16 })
+        .sum::<u64>();
+    let access_list_cost = tx
+        .access_list
+        .iter()
+        .flat_map(|access_list| access_list.0.iter())
+        .map(|item| {
+            GasCost::ACCESS_LIST_PER_ADDRESS.as_u64()
+                + GasCost::ACCESS_LIST_PER_STORAGE_KEY.as_u64() * item.storage_keys.len() as u64
+        })
+        .sum::<u64>();
+    tx_cost.as_u64() + call_data_cost + access_list_cost
+}
+
+/// State shared by the replays of the calls of a transaction.
+#[derive(Default)]
+struct ReplayState {
+    struct_logs: Vec<GethExecStep>,
+    /// Number of SSTOREs executed, after the first of which the values read
+    /// from the storage aren't known to be the original ones.
+    num_sstores: usize,
+}
+
+/// Replay the instructions of `vm_trace` executed at `depth`, returning the
+/// gas left at the end of the call.
+fn replay_vm_trace(vm_trace: &VmTrace, depth: u16, state: &mut ReplayState) -> Result<u64, Error> {
+    let mut stack: Vec<Word> = Vec::new();
+    let mut memory: Vec<u8> = Vec::new();
+    let mut storage: HashMap<Word, Word> = HashMap::new();
+    // Values of the storage slots at the beginning of the transaction and
+    // current values, when they're known.
+    let mut original_storage: HashMap<Word, Word> = HashMap::new();
+    let mut current_storage: HashMap<Word, Word> = HashMap::new();
+    let mut gas_left = None;
+
+    for (idx, op) in vm_trace.ops.iter().enumerate() {
+        let opcode = vm_trace
+            .code
+            .get(op.pc)
+            .map(|byte| OpcodeId::try_from(*byte).unwrap_or(OpcodeId::INVALID(*byte)))
+            .unwrap_or(OpcodeId::STOP);
+
+        // The storage of the struct logs only contains the slot accessed by
+        // SLOAD and SSTORE.
+        let step_storage = match (opcode, &op.ex) {
+            (OpcodeId::SLOAD, Some(ex)) => {
+                if let (Some(key), Some(value)) = (stack.last(), ex.push.last()) {
+                    storage.insert(*key, *value);
+                    current_storage.insert(*key, *value);
+                    if state.num_sstores == 0 {
+                        original_storage.entry(*key).or_insert(*value);
+                    }
+                }
+                Storage::new(storage.clone())
+            }
+            (OpcodeId::SSTORE, ex) => {
+                if let [.., value, key] = stack.as_slice() {
+                    storage.insert(*key, *value);
+                    if ex.is_some() {
+                        if !is_sstore_refund_unchanged(
+                            original_storage.get(key).copied(),
+                            current_storage.get(key).copied(),
+                            state.num_sstores == 0,
+                            *value,
+                        ) {
+                            return Err(Error::TracingError(format!(
+                                "refund of SSTORE at pc {} can't be reconstructed",
+                                op.pc
+                            )));
+                        }
+                        current_storage.insert(*key, *value);
+                        state.num_sstores += 1;
+                    }
+                }
+                Storage::new(storage.clone())
+            }
+            // The refund of SELFDESTRUCT depends on the hardfork (EIP-3529).
+            (OpcodeId::SELFDESTRUCT, Some(_)) => {
+                return Err(Error::TracingError(format!(
+                    "refund of SELFDESTRUCT at pc {} can't be reconstructed",
+                    op.pc
+                )));
+            }
+            _ => Storage::empty(),
+        };
+
+        // The gas left after the previous instruction is used when available,
+        // since the cost of a call includes the gas returned by the callee.
+        let gas = gas_left
+            .or_else(|| op.ex.as_ref().map(|ex| ex.used + op.cost))
+            .unwrap_or(op.cost);
+        let error = match op.ex {
+            Some(_) => None,
+            None => step_error(opcode, op, &stack, gas)?,
+        };
+        let is_reverted = opcode == OpcodeId::REVERT && error.is_none();
+        state.struct_logs.push(GethExecStep {
+            pc: ProgramCounter(op.pc),
+            op: opcode,
+            gas: Gas(gas),
+            gas_cost: GasCost(op.cost),
+            refund: Gas(0),
+            depth,
+            error,
+            stack: Stack(stack.clone()),
+            memory: Memory(memory.clone()),
+            storage: step_storage,
+        });
+
+        if let Some(sub) = &op.sub {
+            let num_sstores = state.num_sstores;
+            replay_vm_trace(sub, depth + 1, state)?;
+            // The callee may have written the storage of the caller.
+            if state.num_sstores != num_sstores {
+                current_storage.clear();
+            }
+        }
+
+        let ex = match &op.ex {
+            Some(ex) => ex,
+            None => {
+                // The failed instruction ends the call, returning the gas
+                // left only when reverting.
+                if idx + 1 == vm_trace.ops.len() {
+                    return Ok(if is_reverted {
+                        gas.saturating_sub(op.cost)
+                    } else {
+                        0
+                    });
+                }
+                gas_left = None;
+                continue;
+            }
+        };
+        for (offset, length) in memory_ranges(opcode, &stack) {
+            if !length.is_zero() {
+                let end = offset.low_u64() as usize + length.low_u64() as usize;
+                if memory.len() < end {
+                    memory.resize((end + 31) / 32 * 32, 0);
+                }
+            }
+        }
+        if let Some(mem) = &ex.mem {
+            let end = mem.off + mem.data.len();
+            if memory.len() < end {
+                memory.resize((end + 31) / 32 * 32, 0);
+            }
+            memory[mem.off..end].copy_from_slice(&mem.data);
+        }
+        stack.truncate(stack.len().saturating_sub(stack_inputs(opcode)));
+        stack.extend(ex.push.iter().copied());
+        gas_left = Some(ex.used);
+    }
+
+    Ok(gas_left.unwrap_or_default())
+}
+
+/// Return the error reported by geth in the struct log of the failed
+/// instruction `op` of `opcode`, given the `stack` and the `gas` left before
+/// it.  Geth only reports the stack and gas errors, the others being found
+/// from the instruction and the call.
+fn step_error(
+    opcode: OpcodeId,
+    op: &VmOperation,
+    stack: &[Word],
+    gas: u64,
+) -> Result<Option<String>, Error> {
+    let inputs = stack_inputs(opcode);
+    if matches!(opcode, OpcodeId::INVALID(_)) {
+        Ok(None)
+    } else if stack.len() < inputs {
+        Ok(Some(format!(
+            "stack underflow ({} <=> {})",
+            stack.len(),
+            inputs
+        )))
+    } else if stack.len() >= STACK_LIMIT && is_stack_growing(opcode) {
+        Ok(Some(format!(
+            "stack limit reached {} ({})",
+            stack.len(),
+            STACK_LIMIT - 1
+        )))
+    } else if op.cost > gas {
+        Ok(Some("out of gas".to_string()))
+    } else if matches!(
+        opcode,
+        OpcodeId::REVERT
+            | OpcodeId::RETURN
+            | OpcodeId::JUMP
+            | OpcodeId::JUMPI
+            | OpcodeId::RETURNDATACOPY
+            | OpcodeId::SSTORE
+            | OpcodeId::CREATE
+            | OpcodeId::CREATE2
+            | OpcodeId::CALL
+            | OpcodeId::SELFDESTRUCT
+    ) || opcode.is_log()
+    {
+        Ok(None)
+    } else {
+        Err(Error::TracingError(format!(
+            "error of {:?} at pc {} can't be recovered",
+            opcode, op.pc
+        )))
+    }
+}
+
+/// Return whether the SSTORE of `value` is known not to change the refund
+/// counter (EIP-2200 and EIP-3529), from the `original` and `current` values
+/// of the slot, when they're known, and whether the slot is known to be clean,
+/// i.e. not written yet by the transaction.
+fn is_sstore_refund_unchanged(
+    original: Option<Word>,
+    current: Option<Word>,
+    is_clean: bool,
+    value: Word,
+) -> bool {
+    if current == Some(value) {
+        return true;
+    }
+    if is_clean {
+        // Only the clearing of a clean slot is refunded.
+        return !value.is_zero()
+            || original
+                .or(current)
+                .map_or(false, |original| original.is_zero());
+    }
+    match (original, current) {
+        (Some(original), Some(current)) if original == current => {
+            !value.is_zero() || original.is_zero()
+        }
+        // A dirty slot changes the refund when it's cleared, when it was
+        // cleared, or when its original value is restored.
+        (Some(original), Some(current)) => {
+            (original.is_zero() || (!current.is_zero() && !value.is_zero())) && value != original
+        }
+        _ => false,
+    }
+}
+
+/// Return whether `opcode` pushes one more item than it pops, which fails when
+/// the stack is full.
+fn is_stack_growing(opcode: OpcodeId) -> bool {
+    opcode.is_push()
+        || opcode.is_dup()
+        || matches!(
+            opcode,
+            OpcodeId::ADDRESS
+                | OpcodeId::ORIGIN
+                | OpcodeId::CALLER
+                | OpcodeId::CALLVALUE
+                | OpcodeId::CALLDATASIZE
+                | OpcodeId::CODESIZE
+                | OpcodeId::GASPRICE
+                | OpcodeId::RETURNDATASIZE
+                | OpcodeId::COINBASE
+                | OpcodeId::TIMESTAMP
+                | OpcodeId::NUMBER
+                | OpcodeId::DIFFICULTY
+                | OpcodeId::GASLIMIT
+                | OpcodeId::CHAINID
+                | OpcodeId::SELFBALANCE
+                | OpcodeId::BASEFEE
+                | OpcodeId::BLOBBASEFEE
+                | OpcodeId::PC
+                | OpcodeId::MSIZE
+                | OpcodeId::GAS
+        )
+}
+
+/// Return the number of items popped from the stack by `opcode`.  For DUPn
+/// and SWAPn, the items that are moved are popped, since the `vmTrace` format
+/// reports them as pushed.
+fn stack_inputs(opcode: OpcodeId) -> usize {
+    if opcode.is_push() {
+        0
+    } else if opcode.is_dup() {
+        (opcode.as_u8() - OpcodeId::DUP1.as_u8() + 1) as usize
+    } else if opcode.is_swap() {
+        (opcode.as_u8() - OpcodeId::SWAP1.as_u8() + 2) as usize
+    } else if opcode.is_log() {
+        (opcode.as_u8() - OpcodeId::LOG0.as_u8() + 2) as usize
+    } else {
+        match opcode {
+            OpcodeId::CALL | OpcodeId::CALLCODE => 7,
+            OpcodeId::DELEGATECALL | OpcodeId::STATICCALL => 6,
+            OpcodeId::EXTCODECOPY | OpcodeId::CREATE2 => 4,
+            OpcodeId::ADDMOD
+            | OpcodeId::MULMOD
+            | OpcodeId::CALLDATACOPY
+            | OpcodeId::CODECOPY
+            | OpcodeId::RETURNDATACOPY
+            | OpcodeId::CREATE => 3,
+            OpcodeId::ADD
+            | OpcodeId::MUL
+            | OpcodeId::SUB
+            | OpcodeId::DIV
+            | OpcodeId::SDIV
+            | OpcodeId::MOD
+            | OpcodeId::SMOD
+            | OpcodeId::EXP
+            | OpcodeId::SIGNEXTEND
+            | OpcodeId::LT
+            | OpcodeId::GT
+            | OpcodeId::SLT
+            | OpcodeId::SGT
+            | OpcodeId::EQ
+            | OpcodeId::AND
+            | OpcodeId::OR
+            | OpcodeId::XOR
+            | OpcodeId::BYTE
+            | OpcodeId::SHL
+            | OpcodeId::SHR
+            | OpcodeId::SAR
+            | OpcodeId::SHA3
+            | OpcodeId::MSTORE
+            | OpcodeId::MSTORE8
+            | OpcodeId::SSTORE
+            | OpcodeId::JUMPI
+            | OpcodeId::RETURN
+            | OpcodeId::REVERT => 2,
+            OpcodeId::ISZERO
+            | OpcodeId::NOT
+            | OpcodeId::BALANCE
+            | OpcodeId::CALLDATALOAD
+            | OpcodeId::EXTCODESIZE
+            | OpcodeId::EXTCODEHASH
+            | OpcodeId::BLOCKHASH
+            | OpcodeId::BLOBHASH
+            | OpcodeId::POP
+            | OpcodeId::MLOAD
+            | OpcodeId::SLOAD
+            | OpcodeId::JUMP
+            | OpcodeId::SELFDESTRUCT => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// Return the (offset, length) of the memory ranges accessed by `opcode`,
+/// which expand the memory, given the `stack` before it.
+fn memory_ranges(opcode: OpcodeId, stack: &[Word]) -> Vec<(Word, Word)> {
+    let nth_last = |nth: usize| {
+        stack
+            .len()
+            .checked_sub(nth + 1)
+            .map(|idx| stack[idx])
+            .unwrap_or_default()
+    };
+    match opcode {
+        OpcodeId::MLOAD | OpcodeId::MSTORE => vec![(nth_last(0), Word::from(32))],
+        OpcodeId::MSTORE8 => vec![(nth_last(0), Word::one())],
+        OpcodeId::CALLDATACOPY | OpcodeId::CODECOPY | OpcodeId::RETURNDATACOPY => {
+            vec![(nth_last(0), nth_last(2))]
+        }
+        OpcodeId::EXTCODECOPY => vec![(nth_last(1), nth_last(3))],
+        OpcodeId::CREATE | OpcodeId::CREATE2 => vec![(nth_last(1), nth_last(2))],
+        OpcodeId::CALL | OpcodeId::CALLCODE => {
+            vec![(nth_last(3), nth_last(4)), (nth_last(5), nth_last(6))]
+        }
+        OpcodeId::DELEGATECALL | OpcodeId::STATICCALL => {
+            vec![(nth_last(2), nth_last(3)), (nth_last(4), nth_last(5))]
+        }
+        opcode if opcode.is_log() => vec![(nth_last(0), nth_last(1))],
+        OpcodeId::SHA3 | OpcodeId::RETURN | OpcodeId::REVERT => {
+            vec![(nth_last(0), nth_last(1))]
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod vm_trace_tests {
+    use super::*;
+
+    /// Call with a gas limit of 30000 above the intrinsic gas.
+    fn tx() -> Transaction {
+        Transaction {
+            to: Some(Default::default()),
+            gas: 51000.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn trace_replay_into_geth_exec_trace() {
+        // PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x00 SLOAD PUSH1 0x01 SWAP1 SSTORE STOP
+        let replay_json = r#"
+  {
+    "output": "0x",
+    "trace": [
+      {
+        "action": {},
+        "result": { "gasUsed": "0x5655", "output": "0x" },
+        "subtraces": 0,
+        "traceAddress": [],
+        "type": "call"
+      }
+    ],
+    "vmTrace": {
+      "code": "0x602a6000526000546001905500",
+      "ops": [
+        { "cost": 3, "ex": { "mem": null, "push": ["0x2a"], "store": null, "used": 29997 }, "pc": 0, "sub": null },
+        { "cost": 3, "ex": { "mem": null, "push": ["0x0"], "store": null, "used": 29994 }, "pc": 2, "sub": null },
+        {
+          "cost": 6,
+          "ex": {
+            "mem": {
+              "data": "0x000000000000000000000000000000000000000000000000000000000000002a",
+              "off": 0
+            },
+            "push": [],
+            "store": null,
+            "used": 29988
+          },
+          "pc": 4,
+          "sub": null
+        },
+        { "cost": 3, "ex": { "mem": null, "push": ["0x0"], "store": null, "used": 29985 }, "pc": 5, "sub": null },
+        { "cost": 2100, "ex": { "mem": null, "push": ["0x7"], "store": null, "used": 27885 }, "pc": 7, "sub": null },
+        { "cost": 3, "ex": { "mem": null, "push": ["0x1"], "store": null, "used": 27882 }, "pc": 8, "sub": null },
+        { "cost": 3, "ex": { "mem": null, "push": ["0x1", "0x7"], "store": null, "used": 27879 }, "pc": 10, "sub": null },
+        {
+          "cost": 2900,
+          "ex": { "mem": null, "push": [], "store": { "key": "0x7", "val": "0x1" }, "used": 24979 },
+          "pc": 11,
+          "sub": null
+        },
+        { "cost": 0, "ex": { "mem": null, "push": [], "store": null, "used": 24979 }, "pc": 12, "sub": null }
+      ]
+    }
+  }
+        "#;
+        let replay: TraceReplay = serde_json::from_str(replay_json).unwrap();
+        let trace = GethExecTrace::try_from((replay, &tx())).unwrap();
+
+        // The intrinsic gas and the gas used by the instructions.
+        assert_eq!(trace.gas, Gas(21000 + 30000 - 24979));
+        assert!(!trace.failed);
+        assert_eq!(trace.return_value, "");
+        assert_eq!(
+            trace
+                .struct_logs
+                .iter()
+                .map(|step| (step.pc.0, step.op, step.gas.0, step.depth))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, OpcodeId::PUSH1, 30000, 1),
+                (2, OpcodeId::PUSH1, 29997, 1),
+                (4, OpcodeId::MSTORE, 29994, 1),
+                (5, OpcodeId::PUSH1, 29988, 1),
+                (7, OpcodeId::SLOAD, 29985, 1),
+                (8, OpcodeId::PUSH1, 27885, 1),
+                (10, OpcodeId::SWAP1, 27882, 1),
+                (11, OpcodeId::SSTORE, 27879, 1),
+                (12, OpcodeId::STOP, 24979, 1),
+            ]
+        );
+
+        let sload = &trace.struct_logs[4];
+        assert_eq!(sload.stack, Stack(vec![Word::zero()]));
+        assert_eq!(sload.memory.0.len(), 32);
+        assert_eq!(sload.memory.0[31], 0x2a);
+        assert_eq!(sload.storage.get(&Word::zero()), Some(&Word::from(7)));
+
+        let sstore = &trace.struct_logs[7];
+        assert_eq!(sstore.stack, Stack(vec![Word::one(), Word::from(7)]));
+        assert_eq!(sstore.storage.get(&Word::from(7)), Some(&Word::one()));
+
+        assert_eq!(trace.struct_logs[8].stack, Stack(vec![]));
+        assert_eq!(trace.struct_logs[8].storage, Storage::empty());
+    }
+
+    #[test]
+    fn trace_replay_sub_call() {
+        // The caller stack is restored after the call, with its result.
+        let replay_json = r#"
+  {
+    "output": "0x",
+    "trace": [{ "error": "Reverted" }],
+    "vmTrace": {
+      "code": "0x6000600060006000600060016000f1fd",
+      "ops": [
+        { "cost": 3, "ex": { "mem": null, "push": ["0x0"], "store": null, "used": 29997 }, "pc": 0, "sub": null },
+        { "cost": 3, "ex": { "mem": null, "push": ["0x0"], "store": null, "used": 29994 }, "pc": 2, "sub": null },
+        { "cost": 3, "ex": { "mem": null, "push": ["0x0"], "store": null, "used": 29991 }, "pc": 4, "sub": null },
+        { "cost": 3, "ex": { "mem": null, "push": ["0x0"], "store": null, "used": 29988 }, "pc": 6, "sub": null },
+        { "cost": 3, "ex": { "mem": null, "push": ["0x0"], "store": null, "used": 29985 }, "pc": 8, "sub": null },
+        { "cost": 3, "ex": { "mem": null, "push": ["0x1"], "store": null, "used": 29982 }, "pc": 10, "sub": null },
+        { "cost": 3, "ex": { "mem": null, "push": ["0x0"], "store": null, "used": 29979 }, "pc": 12, "sub": null },
+        {
+          "cost": 2600,
+          "ex": { "mem": null, "push": ["0x1"], "store": null, "used": 27379 },
+          "pc": 14,
+          "sub": {
+            "code": "0x00",
+            "ops": [
+              { "cost": 0, "ex": { "mem": null, "push": [], "store": null, "used": 0 }, "pc": 0, "sub": null }
+            ]
+          }
+        },
+        { "cost": 0, "ex": null, "pc": 15, "sub": null }
+      ]
+    }
+  }
+        "#;
+        let replay: TraceReplay = serde_json::from_str(replay_json).unwrap();
+        let trace = GethExecTrace::try_from((replay, &tx())).unwrap();
+
+        // The REVERT fails with a stack underflow, using all the gas.
+        assert!(trace.failed);
+        assert_eq!(trace.gas, Gas(51000));
+        assert_eq!(
+            trace
+                .struct_logs
+                .iter()
+                .map(|step| (step.op, step.depth))
+                .collect::<Vec<_>>()[7..],
+            [
+                (OpcodeId::CALL, 1),
+                (OpcodeId::STOP, 2),
+                (OpcodeId::REVERT, 1)
+            ]
+        );
+        assert_eq!(trace.struct_logs[7].stack.0.len(), 7);
+        assert_eq!(trace.struct_logs[8].stack, Stack(vec![]));
+        assert_eq!(trace.struct_logs[9].stack, Stack(vec![Word::one()]));
+        assert_eq!(trace.struct_logs[9].gas, Gas(27379));
+        assert_eq!(
+            trace.struct_logs[9].error,
+            Some("stack underflow (1 <=> 2)".to_string())
+        );
+    }
+
+    #[test]
+    fn trace_replay_unknown_refund() {
+        // PUSH1 0x00 PUSH1 0x00 SSTORE STOP, which may clear the slot 0.
+        let replay_json = r#"
+  {
+    "output": "0x",
+    "trace": [{ "result": { "gasUsed": "0x1a2c", "output": "0x" } }],
+    "vmTrace": {
+      "code": "0x600060005500",
+      "ops": [
+        { "cost": 3, "ex": { "mem": null, "push": ["0x0"], "store": null, "used": 29997 }, "pc": 0, "sub": null },
+        { "cost": 3, "ex": { "mem": null, "push": ["0x0"], "store": null, "used": 29994 }, "pc": 2, "sub": null },
+        {
+          "cost": 2200,
+          "ex": { "mem": null, "push": [], "store": { "key": "0x0", "val": "0x0" }, "used": 27794 },
+          "pc": 4,
+          "sub": null
+        },
+        { "cost": 0, "ex": { "mem": null, "push": [], "store": null, "used": 27794 }, "pc": 5, "sub": null }
+      ]
+    }
+  }
+        "#;
+        let replay: TraceReplay = serde_json::from_str(replay_json).unwrap();
+        assert!(matches!(
+            GethExecTrace::try_from((replay, &tx())),
+            Err(Error::TracingError(_))
+        ));
+    }
+
+    #[test]
+    fn sstore_refund_unchanged() {
+        let (zero, one, two) = (Word::zero(), Word::one(), Word::from(2));
+        // Writing a clean slot is only refunded when clearing it.
+        assert!(is_sstore_refund_unchanged(None, None, true, one));
+        assert!(!is_sstore_refund_unchanged(None, None, true, zero));
+        assert!(is_sstore_refund_unchanged(None, Some(zero), true, zero));
+        // Writing a dirty slot is refunded when restoring its original value.
+        assert!(is_sstore_refund_unchanged(Some(one), Some(two), false, two));
+        assert!(!is_sstore_refund_unchanged(
+            Some(one),
+            Some(two),
+            false,
+            one
+        ));
+        assert!(is_sstore_refund_unchanged(
+            Some(zero),
+            Some(one),
+            false,
+            two
+        ));
+        assert!(!is_sstore_refund_unchanged(None, Some(one), false, two));
+    }
+}
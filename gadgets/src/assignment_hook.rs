@@ -0,0 +1,369 @@
+//! Wrapper of an assigned circuit which passes the values assigned in its
+//! advice and fixed cells through a hook, so that the circuit can be observed
+//! or altered by the tests without changing its assignment code.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{layouter::RegionLayouter, Cell, Layouter, Region, Table},
+    plonk::{
+        Advice, Assigned, Circuit, Column, ConstraintSystem, Error, Fixed, Instance, Selector,
+    },
+};
+use std::rc::Rc;
+
+/// Hook through which the values of the assigned cells are passed.
+pub(crate) trait AssignmentHook<F> {
+    /// Returns the value assigned at `offset` of the advice `column` of
+    /// `region`, given the value assigned by the circuit.
+    fn advice(
+        &self,
+        region: &str,
+        annotation: &dyn Fn() -> String,
+        column: Column<Advice>,
+        offset: usize,
+        value: Assigned<F>,
+    ) -> Assigned<F>;
+
+    /// Returns the value assigned at `offset` of the fixed `column` of
+    /// `region`, given the value assigned by the circuit.
+    fn fixed(
+        &self,
+        _region: &str,
+        _annotation: &dyn Fn() -> String,
+        _column: Column<Fixed>,
+        _offset: usize,
+        value: Assigned<F>,
+    ) -> Assigned<F> {
+        value
+    }
+}
+
+/// Region which assigns the values returned by the hook in the cells of the
+/// wrapped region.
+#[derive(Debug)]
+struct HookedRegion<'a, 'r, F: FieldExt, H> {
+    region: &'a mut Region<'r, F>,
+    name: String,
+    hook: &'a H,
+}
+
+impl<'a, 'r, F: FieldExt, H: AssignmentHook<F>> RegionLayouter<F> for HookedRegion<'a, 'r, F, H> {
+    fn enable_selector<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        selector: &Selector,
+        offset: usize,
+    ) -> Result<(), Error> {
+        self.region.enable_selector(annotation, selector, offset)
+    }
+
+    fn assign_advice<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        column: Column<Advice>,
+        offset: usize,
+        to: &'v mut (dyn FnMut() -> Result<Assigned<F>, Error> + 'v),
+    ) -> Result<Cell, Error> {
+        let (name, hook) = (&self.name, self.hook);
+        self.region
+            .assign_advice(annotation, column, offset, || {
+                to().map(|value| hook.advice(name, annotation, column, offset, value))
+            })
+            .map(|cell| cell.cell())
+    }
+
+    fn assign_advice_from_constant<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        column: Column<Advice>,
+        offset: usize,
+        constant: Assigned<F>,
+    ) -> Result<Cell, Error> {
+        self.region
+            .assign_advice_from_constant(annotation, column, offset, constant)
+            .map(|cell| cell.cell())
+    }
+
+    fn assign_advice_from_instance<'v>(
+        &mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        instance: Column<Instance>,
+        row: usize,
+        advice: Column<Advice>,
+        offset: usize,
+    ) -> Result<(Cell, Option<F>), Error> {
+        self.region
+            .assign_advice_from_instance(annotation, instance, row, advice, offset)
+            .map(|cell| (cell.cell(), cell.value().cloned()))
+    }
+
+    fn assign_fixed<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        column: Column<Fixed>,
+        offset: usize,
+        to: &'v mut (dyn FnMut() -> Result<Assigned<F>, Error> + 'v),
+    ) -> Result<Cell, Error> {
+        let (name, hook) = (&self.name, self.hook);
+        self.region
+            .assign_fixed(annotation, column, offset, || {
+                to().map(|value| hook.fixed(name, annotation, column, offset, value))
+            })
+            .map(|cell| cell.cell())
+    }
+
+    fn constrain_constant(&mut self, cell: Cell, constant: Assigned<F>) -> Result<(), Error> {
+        self.region.constrain_constant(cell, constant)
+    }
+
+    fn constrain_equal(&mut self, left: Cell, right: Cell) -> Result<(), Error> {
+        self.region.constrain_equal(left, right)
+    }
+}
+
+/// Layouter whose regions are wrapped in a [`HookedRegion`].
+struct HookedLayouter<'a, F: FieldExt, L: Layouter<F>, H> {
+    layouter: L,
+    hook: &'a H,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<'a, F: FieldExt, L: Layouter<F>, H: AssignmentHook<F>> Layouter<F>
+    for HookedLayouter<'a, F, L, H>
+{
+    type Root = Self;
+
+    fn assign_region<A, AR, N, NR>(&mut self, name: N, mut assignment: A) -> Result<AR, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        let region_name: String = name().into();
+        let hook = self.hook;
+        self.layouter.assign_region(
+            || region_name.clone(),
+            |mut region| {
+                let mut hooked = HookedRegion {
+                    region: &mut region,
+                    name: region_name.clone(),
+                    hook,
+                };
+                assignment(Region::from(&mut hooked as &mut dyn RegionLayouter<F>))
+            },
+        )
+    }
+
+    fn assign_table<A, N, NR>(&mut self, name: N, assignment: A) -> Result<(), Error>
+    where
+        A: FnMut(Table<'_, F>) -> Result<(), Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.layouter.assign_table(name, assignment)
+    }
+
+    fn constrain_instance(
+        &mut self,
+        cell: Cell,
+        column: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error> {
+        self.layouter.constrain_instance(cell, column, row)
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.layouter.get_root().push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.layouter.get_root().pop_namespace(gadget_name)
+    }
+}
+
+/// Circuit wrapped by a [`HookedCircuit`]: the circuit borrowed from the test,
+/// or its copy without witnesses owned by the wrapper.
+enum WrappedCircuit<'a, C> {
+    Borrowed(&'a C),
+    Owned(C),
+}
+
+impl<'a, C> std::ops::Deref for WrappedCircuit<'a, C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        match self {
+            Self::Borrowed(circuit) => circuit,
+            Self::Owned(circuit) => circuit,
+        }
+    }
+}
+
+/// Circuit which assigns the witness of the wrapped circuit through a hook.
+/// The hook is shared with the wrapper of the circuit without witnesses, so
+/// that it also observes the cells assigned during the keygen.
+pub(crate) struct HookedCircuit<'a, C, H> {
+    circuit: WrappedCircuit<'a, C>,
+    hook: Rc<H>,
+}
+
+impl<'a, C, H> HookedCircuit<'a, C, H> {
+    /// Wraps `circuit` with `hook`.
+    pub(crate) fn new(circuit: &'a C, hook: H) -> Self {
+        Self {
+            circuit: WrappedCircuit::Borrowed(circuit),
+            hook: Rc::new(hook),
+        }
+    }
+
+    /// Returns the hook, to read what it observed during the synthesis.
+    pub(crate) fn hook(&self) -> &H {
+        &self.hook
+    }
+}
+
+impl<'a, F: FieldExt, C: Circuit<F>, H: AssignmentHook<F>> Circuit<F> for HookedCircuit<'a, C, H> {
+    type Config = C::Config;
+    type FloorPlanner = C::FloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            circuit: WrappedCircuit::Owned(self.circuit.without_witnesses()),
+            hook: self.hook.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.circuit.synthesize(
+            config,
+            HookedLayouter {
+                layouter,
+                hook: &*self.hook,
+                _marker: std::marker::PhantomData,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AssignmentHook, HookedCircuit};
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, Layouter},
+        dev::MockProver,
+        pairing::bn256::Fr,
+        plonk::{Advice, Assigned, Circuit, Column, ConstraintSystem, Error, Expression, Fixed},
+        poly::Rotation,
+    };
+    use std::cell::RefCell;
+
+    #[derive(Clone)]
+    struct TestConfig {
+        q_enable: Column<Fixed>,
+        value: Column<Advice>,
+    }
+
+    /// Circuit constraining `value` to be 7.
+    #[derive(Default)]
+    struct TestCircuit {
+        value: Option<Fr>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let q_enable = meta.fixed_column();
+            let value = meta.advice_column();
+            meta.create_gate("value == 7", |meta| {
+                let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+                let value = meta.query_advice(value, Rotation::cur());
+                vec![q_enable * (value - Expression::Constant(Fr::from(7)))]
+            });
+            TestConfig { q_enable, value }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "value",
+                |mut region| {
+                    region.assign_fixed(|| "q_enable", config.q_enable, 0, || Ok(Fr::one()))?;
+                    region.assign_advice(
+                        || "value",
+                        config.value,
+                        0,
+                        || self.value.ok_or(Error::Synthesis),
+                    )?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    /// Hook counting the assigned fixed cells.
+    #[derive(Default)]
+    struct CountHook {
+        fixed: RefCell<usize>,
+    }
+
+    impl AssignmentHook<Fr> for CountHook {
+        fn advice(
+            &self,
+            _region: &str,
+            _annotation: &dyn Fn() -> String,
+            _column: Column<Advice>,
+            _offset: usize,
+            value: Assigned<Fr>,
+        ) -> Assigned<Fr> {
+            value
+        }
+
+        fn fixed(
+            &self,
+            _region: &str,
+            _annotation: &dyn Fn() -> String,
+            _column: Column<Fixed>,
+            _offset: usize,
+            value: Assigned<Fr>,
+        ) -> Assigned<Fr> {
+            *self.fixed.borrow_mut() += 1;
+            value
+        }
+    }
+
+    #[test]
+    fn hooked_circuit_without_witnesses() {
+        // The V1 floor planner measures the regions of the circuit without
+        // witnesses before assigning them.
+        let circuit = TestCircuit {
+            value: Some(Fr::from(7)),
+        };
+        let hooked = HookedCircuit::new(&circuit, CountHook::default());
+        assert_eq!(
+            MockProver::run(4, &hooked, vec![]).unwrap().verify(),
+            Ok(())
+        );
+        assert!(*hooked.hook().fixed.borrow() > 0);
+    }
+}
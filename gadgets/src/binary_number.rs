@@ -180,3 +180,133 @@ pub fn from_bits(bits: &[bool]) -> usize {
     bits.iter()
         .fold(0, |result, &bit| bit as usize + 2 * result)
 }
+
+#[cfg(test)]
+mod test {
+    use super::{AsBits, BinaryNumberChip, BinaryNumberConfig};
+    use eth_types::Field;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        pairing::bn256::Fr as Fp,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed},
+        poly::Rotation,
+    };
+    use std::marker::PhantomData;
+    use strum_macros::EnumIter;
+
+    #[derive(Clone, Copy, Debug, EnumIter)]
+    enum Tag {
+        A,
+        B,
+        C,
+    }
+
+    impl From<Tag> for usize {
+        fn from(tag: Tag) -> usize {
+            tag as usize
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct TestCircuitConfig {
+        q_enable: Column<Fixed>,
+        check: Column<Advice>,
+        tag: BinaryNumberConfig<Tag, 2>,
+    }
+
+    #[derive(Default)]
+    struct TestCircuit<F> {
+        // Values assigned to the bits, which can be out of the range of Tag.
+        values: Vec<usize>,
+        // checks[i] = values[i] == Tag::B
+        checks: Vec<bool>,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: Field> Circuit<F> for TestCircuit<F> {
+        type Config = TestCircuitConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let q_enable = meta.fixed_column();
+            let check = meta.advice_column();
+            let tag = BinaryNumberChip::<F, Tag, 2>::configure(meta, q_enable);
+
+            meta.create_gate("check value_equals", |meta| {
+                let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+                let check = meta.query_advice(check, Rotation::cur());
+                vec![q_enable * (tag.value_equals(Tag::B, Rotation::cur())(meta) - check)]
+            });
+
+            TestCircuitConfig {
+                q_enable,
+                check,
+                tag,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "witness",
+                |mut region| {
+                    for (offset, (value, check)) in self.values.iter().zip(&self.checks).enumerate()
+                    {
+                        region.assign_fixed(
+                            || "q_enable",
+                            config.q_enable,
+                            offset,
+                            || Ok(F::one()),
+                        )?;
+                        region.assign_advice(
+                            || "check",
+                            config.check,
+                            offset,
+                            || Ok(F::from(*check as u64)),
+                        )?;
+                        let bits: [bool; 2] = value.as_bits();
+                        for (bit, column) in bits.iter().zip(config.tag.bits) {
+                            region.assign_advice(
+                                || "bit",
+                                column,
+                                offset,
+                                || Ok(F::from(*bit as u64)),
+                            )?;
+                        }
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn verify(values: Vec<usize>, checks: Vec<bool>) -> bool {
+        let circuit = TestCircuit::<Fp> {
+            values,
+            checks,
+            _marker: PhantomData,
+        };
+        let prover = MockProver::<Fp>::run(4, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn binary_number_value_equals() {
+        assert!(verify(vec![0, 1, 2], vec![false, true, false]));
+        assert!(!verify(vec![0, 1, 2], vec![true, false, false]));
+    }
+
+    #[test]
+    fn binary_number_out_of_range() {
+        // 3 isn't the value of any Tag variant.
+        assert!(!verify(vec![0, 3], vec![false, false]));
+    }
+}
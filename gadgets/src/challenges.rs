@@ -0,0 +1,146 @@
+//! The challenges with which the circuits compute random linear combinations,
+//! so that all of them use the same randomness for the same kind of data.
+//!
+//! The circuits still take the randomness of their random linear combinations
+//! as a witness, whose powers the super circuit exposes in instance columns.
+//! The prover of the block proofs derives it with `verifier::block_randomness`
+//! from the verifying key and the public data of the block, which the verifier
+//! recomputes, but it's known before the commitment of the advice columns.
+//! For the random linear combinations to be sound, the challenges must be
+//! squeezed from the transcript after the commitment of the advice columns
+//! they combine, which requires the multi-phase API of halo2
+//! (`ConstraintSystem::challenge_usable_after`, the second phase advice
+//! columns and `Layouter::get_challenge`).  The pinned halo2 (`v2022_06_03`)
+//! predates it, so the migration waits for the bump of halo2:
+//!
+//! - `configure` of each circuit takes a `Challenges<Expression<F>>`, built
+//!   from `meta.challenge_usable_after(FirstPhase)` by the super circuit, in
+//!   place of its powers of randomness,
+//! - the advice columns holding random linear combinations (the rw table
+//!   values, the word and keccak input RLCs, the copy and bytecode RLCs, the
+//!   RLCs of the lookups) move to `meta.advice_column_in(SecondPhase)`, and
+//!   are assigned from the values of `layouter.get_challenge`,
+//! - the instance columns of the powers of randomness are removed from the
+//!   super circuit and from the `block_instance` of the verifier, and with
+//!   them `block_randomness`.
+
+use crate::util::{rlc, Expr};
+use eth_types::Field;
+use halo2_proofs::plonk::Expression;
+
+/// The challenges used by the circuits, given as expressions when configuring
+/// and as values when assigning.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Challenges<T> {
+    /// Challenge for the random linear combination of the bytes of EVM words.
+    evm_word: T,
+    /// Challenge for the random linear combination of the keccak inputs.
+    keccak_input: T,
+    /// Challenge for the random linear combination of the columns of a lookup.
+    lookup_input: T,
+}
+
+impl<T: Clone> Challenges<T> {
+    /// Construct the challenges from their expressions or values.
+    pub fn new(evm_word: T, keccak_input: T, lookup_input: T) -> Self {
+        Self {
+            evm_word,
+            keccak_input,
+            lookup_input,
+        }
+    }
+
+    /// Construct the challenges from a single randomness, used for all the
+    /// kinds of data.
+    pub fn from_randomness(randomness: T) -> Self {
+        Self::new(randomness.clone(), randomness.clone(), randomness)
+    }
+
+    /// Returns the challenge of the EVM words.
+    pub fn evm_word(&self) -> T {
+        self.evm_word.clone()
+    }
+
+    /// Returns the challenge of the keccak inputs.
+    pub fn keccak_input(&self) -> T {
+        self.keccak_input.clone()
+    }
+
+    /// Returns the challenge of the lookup inputs.
+    pub fn lookup_input(&self) -> T {
+        self.lookup_input.clone()
+    }
+
+    /// Returns the challenges mapped by `f`, as done to get their powers.
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> Challenges<U> {
+        Challenges {
+            evm_word: f(&self.evm_word),
+            keccak_input: f(&self.keccak_input),
+            lookup_input: f(&self.lookup_input),
+        }
+    }
+}
+
+impl<F: Field> Challenges<Expression<F>> {
+    fn powers_of<const S: usize>(base: Expression<F>) -> [Expression<F>; S] {
+        std::iter::successors(Some(base.clone()), |power| {
+            Some(base.clone() * power.clone())
+        })
+        .take(S)
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap()
+    }
+
+    /// Returns the powers `r, r^2, ..., r^S` of the challenge of the EVM words.
+    pub fn evm_word_powers_of_randomness<const S: usize>(&self) -> [Expression<F>; S] {
+        Self::powers_of(self.evm_word.clone())
+    }
+
+    /// Returns the powers `r, r^2, ..., r^S` of the challenge of the keccak
+    /// inputs.
+    pub fn keccak_powers_of_randomness<const S: usize>(&self) -> [Expression<F>; S] {
+        Self::powers_of(self.keccak_input.clone())
+    }
+
+    /// Returns the random linear combination of the bytes of an EVM word.
+    pub fn evm_word_rlc<E: Expr<F>>(&self, bytes: &[E]) -> Expression<F> {
+        rlc::expr(bytes, self.evm_word.clone())
+    }
+
+    /// Returns the random linear combination of the columns of a lookup.
+    pub fn lookup_input_rlc<E: Expr<F>>(&self, columns: &[E]) -> Expression<F> {
+        rlc::expr(columns, self.lookup_input.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Challenges;
+    use crate::util::rlc;
+    use eth_types::Rlc;
+    use halo2_proofs::pairing::bn256::Fr as Fp;
+
+    #[test]
+    fn challenges_rlc() {
+        let challenges = Challenges::new(Fp::from(3), Fp::from(5), Fp::from(7));
+        let bytes = [1u8, 2, 3, 4];
+        assert_eq!(
+            rlc::value(
+                &bytes.map(|byte| Fp::from(byte as u64)),
+                challenges.evm_word()
+            ),
+            bytes.rlc(challenges.evm_word())
+        );
+        assert_eq!(
+            rlc::value(&[Fp::from(1), Fp::from(2)], challenges.lookup_input()),
+            Fp::from(1 + 2 * 7)
+        );
+        assert_eq!(
+            challenges
+                .map(|challenge| challenge * challenge)
+                .keccak_input(),
+            Fp::from(25)
+        );
+    }
+}
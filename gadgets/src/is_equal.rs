@@ -0,0 +1,294 @@
+//! IsEqual chip can be used to check equality of two expressions LHS and RHS,
+//! with an [`IsZeroChip`] checking whether LHS - RHS is zero.
+
+use halo2_proofs::{
+    circuit::{Chip, Region},
+    pairing::arithmetic::FieldExt,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, VirtualCells},
+};
+
+use crate::is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction};
+
+/// Instruction that the IsEqual chip needs to implement.
+pub trait IsEqualInstruction<F: FieldExt> {
+    /// Assign the lhs and rhs witnesses to the IsEqual chip's region.
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        lhs: Option<F>,
+        rhs: Option<F>,
+    ) -> Result<(), Error>;
+}
+
+/// Config for the IsEqual chip.
+#[derive(Clone, Debug)]
+pub struct IsEqualConfig<F> {
+    /// Config of the IsZero chip checking whether lhs - rhs is zero.
+    pub is_zero: IsZeroConfig<F>,
+}
+
+impl<F: FieldExt> IsEqualConfig<F> {
+    /// Returns the is_equal expression, which is 1 if lhs == rhs, and 0
+    /// otherwise.
+    pub fn expr(&self) -> Expression<F> {
+        self.is_zero.expr()
+    }
+}
+
+/// Chip that checks whether lhs == rhs.
+#[derive(Clone, Debug)]
+pub struct IsEqualChip<F> {
+    config: IsEqualConfig<F>,
+}
+
+impl<F: FieldExt> IsEqualChip<F> {
+    /// Configures the IsEqual chip, with `diff_inv` witnessing the inverse of
+    /// lhs - rhs.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        lhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        rhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        diff_inv: Column<Advice>,
+    ) -> IsEqualConfig<F> {
+        let is_zero = IsZeroChip::configure(meta, q_enable, |meta| lhs(meta) - rhs(meta), diff_inv);
+
+        IsEqualConfig { is_zero }
+    }
+
+    /// Constructs an IsEqual chip given a config.
+    pub fn construct(config: IsEqualConfig<F>) -> Self {
+        IsEqualChip { config }
+    }
+}
+
+impl<F: FieldExt> IsEqualInstruction<F> for IsEqualChip<F> {
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        lhs: Option<F>,
+        rhs: Option<F>,
+    ) -> Result<(), Error> {
+        IsZeroChip::construct(self.config.is_zero.clone()).assign(
+            region,
+            offset,
+            lhs.zip(rhs).map(|(lhs, rhs)| lhs - rhs),
+        )
+    }
+}
+
+impl<F: FieldExt> Chip<F> for IsEqualChip<F> {
+    type Config = IsEqualConfig<F>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{IsEqualChip, IsEqualConfig, IsEqualInstruction};
+    use crate::mutation::{assert_mutations_rejected, CellTarget, Mutate, Mutation};
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        pairing::bn256::Fr as Fp,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
+        poly::Rotation,
+    };
+    use std::marker::PhantomData;
+
+    macro_rules! try_test_circuit {
+        ($values:expr, $checks:expr, $result:expr) => {{
+            // TODO: remove zk blinding factors in halo2 to restore the
+            // correct k (without the extra + 2).
+            let k = usize::BITS - $values.len().leading_zeros() + 2;
+            let circuit = TestCircuit::<Fp> {
+                values: Some($values),
+                checks: Some($checks),
+                _marker: PhantomData,
+            };
+            let prover = MockProver::<Fp>::run(k, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify(), $result);
+        }};
+    }
+
+    macro_rules! try_test_circuit_error {
+        ($values:expr, $checks:expr) => {{
+            // TODO: remove zk blinding factors in halo2 to restore the
+            // correct k (without the extra + 2).
+            let k = usize::BITS - $values.len().leading_zeros() + 2;
+            let circuit = TestCircuit::<Fp> {
+                values: Some($values),
+                checks: Some($checks),
+                _marker: PhantomData,
+            };
+            let prover = MockProver::<Fp>::run(k, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }};
+    }
+
+    #[test]
+    fn column_is_equal() {
+        #[derive(Clone, Debug)]
+        struct TestCircuitConfig<F> {
+            q_enable: Selector,
+            value_a: Column<Advice>,
+            value_b: Column<Advice>,
+            check: Column<Advice>,
+            is_equal: IsEqualConfig<F>,
+        }
+
+        #[derive(Default)]
+        struct TestCircuit<F: FieldExt> {
+            values: Option<Vec<(u64, u64)>>,
+            // checks[i] = values[i].0 == values[i].1
+            checks: Option<Vec<bool>>,
+            _marker: PhantomData<F>,
+        }
+
+        impl<F: FieldExt> Circuit<F> for TestCircuit<F> {
+            type Config = TestCircuitConfig<F>;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                Self::default()
+            }
+
+            fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+                let q_enable = meta.complex_selector();
+                let (value_a, value_b) = (meta.advice_column(), meta.advice_column());
+                let check = meta.advice_column();
+
+                let is_equal = IsEqualChip::configure(
+                    meta,
+                    |meta| meta.query_selector(q_enable),
+                    |meta| meta.query_advice(value_a, Rotation::cur()),
+                    |meta| meta.query_advice(value_b, Rotation::cur()),
+                    meta.advice_column(),
+                );
+
+                let config = Self::Config {
+                    q_enable,
+                    value_a,
+                    value_b,
+                    check,
+                    is_equal,
+                };
+
+                meta.create_gate("check is_equal", |meta| {
+                    let q_enable = meta.query_selector(q_enable);
+
+                    // This verifies is_equal is calculated correctly
+                    let check = meta.query_advice(config.check, Rotation::cur());
+
+                    vec![q_enable * (config.is_equal.expr() - check)]
+                });
+
+                config
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<F>,
+            ) -> Result<(), Error> {
+                let chip = IsEqualChip::construct(config.is_equal.clone());
+
+                let values: Vec<_> = self
+                    .values
+                    .as_ref()
+                    .map(|values| {
+                        values
+                            .iter()
+                            .map(|(value_a, value_b)| (F::from(*value_a), F::from(*value_b)))
+                            .collect()
+                    })
+                    .ok_or(Error::Synthesis)?;
+                let checks = self.checks.as_ref().ok_or(Error::Synthesis)?;
+
+                layouter.assign_region(
+                    || "witness",
+                    |mut region| {
+                        for (idx, ((value_a, value_b), check)) in
+                            values.iter().zip(checks).enumerate()
+                        {
+                            region.assign_advice(
+                                || "check",
+                                config.check,
+                                idx + 1,
+                                || Ok(F::from(*check as u64)),
+                            )?;
+                            region.assign_advice(
+                                || "value_a",
+                                config.value_a,
+                                idx + 1,
+                                || Ok(*value_a),
+                            )?;
+                            region.assign_advice(
+                                || "value_b",
+                                config.value_b,
+                                idx + 1,
+                                || Ok(*value_b),
+                            )?;
+
+                            config.q_enable.enable(&mut region, idx + 1)?;
+                            chip.assign(&mut region, idx + 1, Some(*value_a), Some(*value_b))?;
+                        }
+
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        // ok
+        try_test_circuit!(
+            vec![(1, 2), (3, 4), (5, 6)],
+            vec![false, false, false],
+            Ok(())
+        );
+        try_test_circuit!(
+            vec![(1, 1), (3, 4), (6, 6)],
+            vec![true, false, true],
+            Ok(())
+        );
+        // error
+        try_test_circuit_error!(vec![(1, 2), (3, 4), (5, 6)], vec![true, true, true]);
+        try_test_circuit_error!(vec![(1, 1), (3, 4), (6, 6)], vec![false, true, false]);
+        // sound: the valid witness with one mutated cell is rejected
+        let circuit = TestCircuit::<Fp> {
+            values: Some(vec![(1, 1), (3, 4)]),
+            checks: Some(vec![true, false]),
+            _marker: PhantomData,
+        };
+        assert_mutations_rejected(
+            4,
+            &circuit,
+            vec![],
+            vec![
+                // lhs != rhs with the inverse witnessed for lhs == rhs
+                Mutation::new(
+                    CellTarget::Annotation("value_a"),
+                    1,
+                    Mutate::Add(Fp::from(1)),
+                ),
+                // lhs == rhs with the inverse witnessed for lhs != rhs
+                Mutation::new(
+                    CellTarget::Annotation("value_b"),
+                    2,
+                    Mutate::Set(Fp::from(3)),
+                ),
+                Mutation::new(CellTarget::Annotation("check"), 2, Mutate::Set(Fp::from(1))),
+            ],
+        );
+    }
+}
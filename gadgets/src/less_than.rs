@@ -5,8 +5,8 @@ use std::array;
 use eth_types::Field;
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::{Chip, Region},
-    plonk::{Advice, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    circuit::{Chip, Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
 
@@ -33,8 +33,10 @@ pub struct LtConfig<F, const N_BYTES: usize> {
     /// Denotes the lt outcome. If lhs < rhs then lt == 1, otherwise lt == 0.
     pub lt: Column<Advice>,
     /// Denotes the bytes representation of the difference between lhs and rhs.
-    /// Note that the range of each byte is not checked by this config.
     pub diff: [Column<Advice>; N_BYTES],
+    /// Fixed table of the values 0..256 in which each byte of the difference
+    /// is looked up.
+    pub u8_table: Column<Fixed>,
     /// Denotes the range within which both lhs and rhs lie.
     pub range: F,
 }
@@ -53,12 +55,15 @@ pub struct LtChip<F, const N_BYTES: usize> {
 }
 
 impl<F: Field, const N_BYTES: usize> LtChip<F, N_BYTES> {
-    /// Configures the Lt chip.
+    /// Configures the Lt chip.  The bytes of the difference are range checked
+    /// with lookups to `u8_table`, which can be shared by several chips and
+    /// must be loaded once with [`LtChip::load`].
     pub fn configure(
         meta: &mut ConstraintSystem<F>,
-        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        q_enable: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F>,
         lhs: impl FnOnce(&mut VirtualCells<F>) -> Expression<F>,
         rhs: impl FnOnce(&mut VirtualCells<F>) -> Expression<F>,
+        u8_table: Column<Fixed>,
     ) -> LtConfig<F, N_BYTES> {
         let lt = meta.advice_column();
         let diff = [(); N_BYTES].map(|_| meta.advice_column());
@@ -81,13 +86,45 @@ impl<F: Field, const N_BYTES: usize> LtChip<F, N_BYTES> {
             array::IntoIter::new([check_a, check_b]).map(move |poly| q_enable.clone() * poly)
         });
 
-        LtConfig { lt, diff, range }
+        for diff_byte in diff {
+            meta.lookup_any("lt chip: diff byte range", |meta| {
+                vec![(
+                    q_enable(meta) * meta.query_advice(diff_byte, Rotation::cur()),
+                    meta.query_fixed(u8_table, Rotation::cur()),
+                )]
+            });
+        }
+
+        LtConfig {
+            lt,
+            diff,
+            u8_table,
+            range,
+        }
     }
 
     /// Constructs a Lt chip given a config.
     pub fn construct(config: LtConfig<F, N_BYTES>) -> LtChip<F, N_BYTES> {
         LtChip { config }
     }
+
+    /// Loads the u8 table used to range check the bytes of the difference.
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "lt chip: u8 table",
+            |mut region| {
+                for value in 0..256 {
+                    region.assign_fixed(
+                        || "u8 table",
+                        self.config.u8_table,
+                        value,
+                        || Ok(F::from(value as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
 }
 
 impl<F: Field, const N_BYTES: usize> LtInstruction<F> for LtChip<F, N_BYTES> {
@@ -157,7 +194,8 @@ mod test {
 
             // TODO: remove zk blinding factors in halo2 to restore the
             // correct k (without the extra + 2).
-            let k = usize::BITS - $values.len().leading_zeros() + 2;
+            // The u8 table used by the range checks needs at least 256 rows.
+            let k = std::cmp::max(usize::BITS - $values.len().leading_zeros() + 2, 9);
             let circuit = TestCircuit::<Fp> {
                 values: Some($values),
                 checks: Some($checks),
@@ -174,7 +212,8 @@ mod test {
 
             // TODO: remove zk blinding factors in halo2 to restore the
             // correct k (without the extra + 2).
-            let k = usize::BITS - $values.len().leading_zeros() + 2;
+            // The u8 table used by the range checks needs at least 256 rows.
+            let k = std::cmp::max(usize::BITS - $values.len().leading_zeros() + 2, 9);
             let circuit = TestCircuit::<Fp> {
                 values: Some($values),
                 checks: Some($checks),
@@ -215,12 +254,14 @@ mod test {
                 let q_enable = meta.complex_selector();
                 let value = meta.advice_column();
                 let check = meta.advice_column();
+                let u8_table = meta.fixed_column();
 
                 let lt = LtChip::configure(
                     meta,
                     |meta| meta.query_selector(q_enable),
                     |meta| meta.query_advice(value, Rotation::prev()),
                     |meta| meta.query_advice(value, Rotation::cur()),
+                    u8_table,
                 );
 
                 let config = Self::Config {
@@ -248,6 +289,7 @@ mod test {
                 mut layouter: impl Layouter<F>,
             ) -> Result<(), Error> {
                 let chip = LtChip::construct(config.lt);
+                chip.load(&mut layouter)?;
 
                 let values: Vec<_> = self
                     .values
@@ -333,12 +375,14 @@ mod test {
                 let q_enable = meta.complex_selector();
                 let (value_a, value_b) = (meta.advice_column(), meta.advice_column());
                 let check = meta.advice_column();
+                let u8_table = meta.fixed_column();
 
                 let lt = LtChip::configure(
                     meta,
                     |meta| meta.query_selector(q_enable),
                     |meta| meta.query_advice(value_a, Rotation::cur()),
                     |meta| meta.query_advice(value_b, Rotation::cur()),
+                    u8_table,
                 );
 
                 let config = Self::Config {
@@ -367,6 +411,7 @@ mod test {
                 mut layouter: impl Layouter<F>,
             ) -> Result<(), Error> {
                 let chip = LtChip::construct(config.lt);
+                chip.load(&mut layouter)?;
 
                 let values: Vec<_> = self
                     .values
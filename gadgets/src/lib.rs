@@ -11,11 +11,19 @@
 #![deny(unsafe_code)]
 #![deny(clippy::debug_assert_with_mut_call)]
 
+mod assignment_hook;
 pub mod binary_number;
+pub mod challenges;
 pub mod evm_word;
+pub mod is_equal;
 pub mod is_zero;
 pub mod less_than;
 pub mod monotone;
+pub mod mul_add;
+pub mod mul_add_512;
+pub mod mutation;
+pub mod range_check;
+pub mod snapshot;
 pub mod util;
 
 use eth_types::Field;
@@ -0,0 +1,409 @@
+//! MulAdd chip can be used to check `a * b + c == d (mod 2^256)` for 256-bit
+//! words `a`, `b`, `c` and `d`.
+//!
+//! The words `a` and `b` are witnessed as little-endian bytes, which are
+//! combined into 64-bit limbs `a0..a3` and `b0..b3`, while `c` and `d` are
+//! witnessed as their low and high 128-bit halves. The product is split into
+//! the partial sums
+//!   t0 = a0 * b0
+//!   t1 = a0 * b1 + a1 * b0
+//!   t2 = a0 * b2 + a1 * b1 + a2 * b0
+//!   t3 = a0 * b3 + a1 * b2 + a2 * b1 + a3 * b0
+//! and the chip constrains
+//!   t0 + t1 * 2^64 + c_lo = d_lo + carry_lo * 2^128
+//!   t2 + t3 * 2^64 + c_hi + carry_lo = d_hi + carry_hi * 2^128
+//!
+//! The parts of the product that are higher than 256 bits are summed into
+//!   overflow = carry_hi + a1 * b3 + a2 * b2 + a3 * b1 + a2 * b3 + a3 * b2
+//!              + a3 * b3
+//! which has to be constrained to zero outside of the chip when the
+//! multiplication must not wrap around.
+
+use eth_types::{Field, ToLittleEndian, Word};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::util::{expr_from_bytes, pow_of_two, Expr};
+
+/// Instruction that the MulAdd chip needs to implement.
+pub trait MulAddInstruction<F: FieldExt> {
+    /// Assign the witnesses of `a * b + c == d (mod 2^256)` to the MulAdd
+    /// chip's region, where `words` is `[a, b, c, d]`.
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        words: [Word; 4],
+    ) -> Result<(), Error>;
+}
+
+/// Config for the MulAdd chip.  Note that the range of the bytes of `a`, `b`,
+/// `carry_lo` and `carry_hi` is not checked by this config.
+#[derive(Clone, Debug)]
+pub struct MulAddConfig<F> {
+    /// Little-endian bytes of the multiplicand `a`.
+    pub a: [Column<Advice>; 32],
+    /// Little-endian bytes of the multiplier `b`.
+    pub b: [Column<Advice>; 32],
+    /// Low 128 bits of the addend `c`.
+    pub c_lo: Column<Advice>,
+    /// High 128 bits of the addend `c`.
+    pub c_hi: Column<Advice>,
+    /// Low 128 bits of the result `d`.
+    pub d_lo: Column<Advice>,
+    /// High 128 bits of the result `d`.
+    pub d_hi: Column<Advice>,
+    /// Little-endian bytes of the carry from the low 128 bits.
+    pub carry_lo: [Column<Advice>; 9],
+    /// Little-endian bytes of the carry from the high 128 bits.
+    pub carry_hi: [Column<Advice>; 9],
+    /// Sum of the parts of `a * b` that are higher than 256 bits, which is
+    /// zero if and only if `a * b + c` does not overflow.
+    pub overflow: Expression<F>,
+}
+
+impl<F: Field> MulAddConfig<F> {
+    /// Returns the low and high 128-bit expressions of `a`.
+    pub fn a_lo_hi(&self, meta: &mut VirtualCells<F>) -> (Expression<F>, Expression<F>) {
+        Self::lo_hi(meta, &self.a)
+    }
+
+    /// Returns the low and high 128-bit expressions of `b`.
+    pub fn b_lo_hi(&self, meta: &mut VirtualCells<F>) -> (Expression<F>, Expression<F>) {
+        Self::lo_hi(meta, &self.b)
+    }
+
+    fn lo_hi(
+        meta: &mut VirtualCells<F>,
+        bytes: &[Column<Advice>; 32],
+    ) -> (Expression<F>, Expression<F>) {
+        let bytes = bytes
+            .iter()
+            .map(|column| meta.query_advice(*column, Rotation::cur()))
+            .collect::<Vec<_>>();
+        (expr_from_bytes(&bytes[..16]), expr_from_bytes(&bytes[16..]))
+    }
+}
+
+/// Chip that checks `a * b + c == d (mod 2^256)`.
+#[derive(Clone, Debug)]
+pub struct MulAddChip<F> {
+    config: MulAddConfig<F>,
+}
+
+impl<F: Field> MulAddChip<F> {
+    /// Configures the MulAdd chip.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+    ) -> MulAddConfig<F> {
+        let a = [(); 32].map(|_| meta.advice_column());
+        let b = [(); 32].map(|_| meta.advice_column());
+        let c_lo = meta.advice_column();
+        let c_hi = meta.advice_column();
+        let d_lo = meta.advice_column();
+        let d_hi = meta.advice_column();
+        let carry_lo = [(); 9].map(|_| meta.advice_column());
+        let carry_hi = [(); 9].map(|_| meta.advice_column());
+
+        // dummy initialization
+        let mut overflow = 0.expr();
+
+        meta.create_gate("mul add gate", |meta| {
+            let q_enable = q_enable(meta);
+
+            let a_limbs = query_limbs(meta, &a);
+            let b_limbs = query_limbs(meta, &b);
+            let c_lo = meta.query_advice(c_lo, Rotation::cur());
+            let c_hi = meta.query_advice(c_hi, Rotation::cur());
+            let d_lo = meta.query_advice(d_lo, Rotation::cur());
+            let d_hi = meta.query_advice(d_hi, Rotation::cur());
+            let carry_lo = expr_from_bytes(
+                &carry_lo
+                    .iter()
+                    .map(|column| meta.query_advice(*column, Rotation::cur()))
+                    .collect::<Vec<_>>(),
+            );
+            let carry_hi = expr_from_bytes(
+                &carry_hi
+                    .iter()
+                    .map(|column| meta.query_advice(*column, Rotation::cur()))
+                    .collect::<Vec<_>>(),
+            );
+
+            let t0 = a_limbs[0].clone() * b_limbs[0].clone();
+            let t1 =
+                a_limbs[0].clone() * b_limbs[1].clone() + a_limbs[1].clone() * b_limbs[0].clone();
+            let t2 = a_limbs[0].clone() * b_limbs[2].clone()
+                + a_limbs[1].clone() * b_limbs[1].clone()
+                + a_limbs[2].clone() * b_limbs[0].clone();
+            let t3 = a_limbs[0].clone() * b_limbs[3].clone()
+                + a_limbs[1].clone() * b_limbs[2].clone()
+                + a_limbs[2].clone() * b_limbs[1].clone()
+                + a_limbs[3].clone() * b_limbs[0].clone();
+            overflow = carry_hi.clone()
+                + a_limbs[1].clone() * b_limbs[3].clone()
+                + a_limbs[2].clone() * b_limbs[2].clone()
+                + a_limbs[3].clone() * b_limbs[1].clone()
+                + a_limbs[2].clone() * b_limbs[3].clone()
+                + a_limbs[3].clone() * b_limbs[2].clone()
+                + a_limbs[3].clone() * b_limbs[3].clone();
+
+            let two_64 = Expression::Constant(pow_of_two::<F>(64));
+            let two_128 = Expression::Constant(pow_of_two::<F>(128));
+
+            // (a * b)_lo + c_lo == d_lo + carry_lo ⋅ 2^128
+            let check_lo =
+                t0 + t1 * two_64.clone() + c_lo - d_lo - carry_lo.clone() * two_128.clone();
+            // (a * b)_hi + c_hi + carry_lo == d_hi + carry_hi ⋅ 2^128
+            let check_hi = t2 + t3 * two_64 + c_hi + carry_lo - d_hi - carry_hi * two_128;
+
+            [check_lo, check_hi].map(move |poly| q_enable.clone() * poly)
+        });
+
+        MulAddConfig {
+            a,
+            b,
+            c_lo,
+            c_hi,
+            d_lo,
+            d_hi,
+            carry_lo,
+            carry_hi,
+            overflow,
+        }
+    }
+
+    /// Constructs a MulAdd chip given a config.
+    pub fn construct(config: MulAddConfig<F>) -> MulAddChip<F> {
+        MulAddChip { config }
+    }
+}
+
+impl<F: Field> MulAddInstruction<F> for MulAddChip<F> {
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        words: [Word; 4],
+    ) -> Result<(), Error> {
+        let config = self.config();
+        let [a, b, c, d] = words;
+
+        for (name, columns, word) in [("a", &config.a, a), ("b", &config.b, b)] {
+            for (idx, (column, byte)) in columns.iter().zip(word.to_le_bytes()).enumerate() {
+                region.assign_advice(
+                    || format!("mul add chip: {} byte {}", name, idx),
+                    *column,
+                    offset,
+                    || Ok(F::from(byte as u64)),
+                )?;
+            }
+        }
+
+        let (c_lo, c_hi) = split_u256(&c);
+        let (d_lo, d_hi) = split_u256(&d);
+        for (name, column, value) in [
+            ("c_lo", config.c_lo, c_lo),
+            ("c_hi", config.c_hi, c_hi),
+            ("d_lo", config.d_lo, d_lo),
+            ("d_hi", config.d_hi, d_hi),
+        ] {
+            region.assign_advice(
+                || format!("mul add chip: {}", name),
+                column,
+                offset,
+                || Ok(word_to_field(value)),
+            )?;
+        }
+
+        let a_limbs = split_u256_limb64(&a);
+        let b_limbs = split_u256_limb64(&b);
+        let t0 = a_limbs[0] * b_limbs[0];
+        let t1 = a_limbs[0] * b_limbs[1] + a_limbs[1] * b_limbs[0];
+        let t2 = a_limbs[0] * b_limbs[2] + a_limbs[1] * b_limbs[1] + a_limbs[2] * b_limbs[0];
+        let t3 = a_limbs[0] * b_limbs[3]
+            + a_limbs[1] * b_limbs[2]
+            + a_limbs[2] * b_limbs[1]
+            + a_limbs[3] * b_limbs[0];
+        let carry_lo = (t0 + (t1 << 64) + c_lo).saturating_sub(d_lo) >> 128;
+        let carry_hi = (t2 + (t3 << 64) + c_hi + carry_lo).saturating_sub(d_hi) >> 128;
+
+        for (name, columns, carry) in [
+            ("carry_lo", &config.carry_lo, carry_lo),
+            ("carry_hi", &config.carry_hi, carry_hi),
+        ] {
+            for (idx, (column, byte)) in columns.iter().zip(carry.to_le_bytes()).enumerate() {
+                region.assign_advice(
+                    || format!("mul add chip: {} byte {}", name, idx),
+                    *column,
+                    offset,
+                    || Ok(F::from(byte as u64)),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<F: Field> Chip<F> for MulAddChip<F> {
+    type Config = MulAddConfig<F>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+/// Returns the expressions of the 4 64-bit limbs of a word given its bytes.
+pub(crate) fn query_limbs<F: Field>(
+    meta: &mut VirtualCells<F>,
+    bytes: &[Column<Advice>; 32],
+) -> Vec<Expression<F>> {
+    bytes
+        .chunks(8)
+        .map(|chunk| {
+            let chunk = chunk
+                .iter()
+                .map(|column| meta.query_advice(*column, Rotation::cur()))
+                .collect::<Vec<_>>();
+            expr_from_bytes(&chunk)
+        })
+        .collect()
+}
+
+/// Returns the low and high 128 bits of a word.
+pub(crate) fn split_u256(value: &Word) -> (Word, Word) {
+    (
+        Word([value.0[0], value.0[1], 0, 0]),
+        Word([value.0[2], value.0[3], 0, 0]),
+    )
+}
+
+/// Splits a word into its 4 64-bit limbs.
+pub(crate) fn split_u256_limb64(value: &Word) -> [Word; 4] {
+    [
+        Word([value.0[0], 0, 0, 0]),
+        Word([value.0[1], 0, 0, 0]),
+        Word([value.0[2], 0, 0, 0]),
+        Word([value.0[3], 0, 0, 0]),
+    ]
+}
+
+/// Converts a word of at most 128 bits into a field element.
+pub(crate) fn word_to_field<F: FieldExt>(value: Word) -> F {
+    F::from_u128(value.low_u128())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MulAddChip, MulAddConfig, MulAddInstruction};
+    use eth_types::{Field, Word};
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        pairing::bn256::Fr as Fp,
+        plonk::{Circuit, ConstraintSystem, Error, Selector},
+    };
+    use std::marker::PhantomData;
+
+    macro_rules! try_test_circuit {
+        ($values:expr, $result:expr) => {{
+            let k = 6;
+            let circuit = TestCircuit::<Fp> {
+                values: $values,
+                _marker: PhantomData,
+            };
+            let prover = MockProver::<Fp>::run(k, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify().is_ok(), $result);
+        }};
+    }
+
+    #[derive(Clone, Debug)]
+    struct TestCircuitConfig<F> {
+        q_enable: Selector,
+        mul_add: MulAddConfig<F>,
+    }
+
+    #[derive(Default)]
+    struct TestCircuit<F: FieldExt> {
+        values: Vec<[Word; 4]>,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: Field> Circuit<F> for TestCircuit<F> {
+        type Config = TestCircuitConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let q_enable = meta.selector();
+            let mul_add = MulAddChip::configure(meta, |meta| meta.query_selector(q_enable));
+
+            Self::Config { q_enable, mul_add }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = MulAddChip::construct(config.mul_add);
+
+            layouter.assign_region(
+                || "witness",
+                |mut region| {
+                    for (offset, words) in self.values.iter().enumerate() {
+                        config.q_enable.enable(&mut region, offset)?;
+                        chip.assign(&mut region, offset, *words)?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn mul_add(a: Word, b: Word, c: Word) -> [Word; 4] {
+        let d = a.overflowing_mul(b).0.overflowing_add(c).0;
+        [a, b, c, d]
+    }
+
+    #[test]
+    fn mul_add_valid() {
+        try_test_circuit!(
+            vec![
+                mul_add(Word::from(0), Word::from(0), Word::from(0)),
+                mul_add(Word::from(3), Word::from(5), Word::from(7)),
+                mul_add(Word::from(u64::MAX), Word::from(u128::MAX), Word::from(1)),
+                mul_add(Word::MAX, Word::MAX, Word::MAX),
+                mul_add(
+                    Word::from(2).pow(Word::from(200)),
+                    Word::from(2).pow(Word::from(100)),
+                    Word::from(0)
+                ),
+            ],
+            true
+        );
+    }
+
+    #[test]
+    fn mul_add_invalid() {
+        let [a, b, c, d] = mul_add(Word::from(3), Word::from(5), Word::from(7));
+        try_test_circuit!(vec![[a, b, c, d + 1]], false);
+        let [a, b, c, d] = mul_add(Word::MAX, Word::from(2), Word::from(0));
+        try_test_circuit!(vec![[a, b, c, d - 1]], false);
+    }
+}
@@ -0,0 +1,351 @@
+//! MulAdd512 chip can be used to check `a * b + c == d * 2^256 + e` for 256-bit
+//! words `a`, `b`, `c`, `d` and `e`, where `d` is the overflow word of the
+//! 512-bit result, which is needed by MULMOD and ADDMOD.
+//!
+//! The words `a` and `b` are witnessed as little-endian bytes, which are
+//! combined into 64-bit limbs `a0..a3` and `b0..b3`, while `c`, `d` and `e`
+//! are witnessed as their low and high 128-bit halves. The product is split
+//! into the partial sums
+//!   t0 = a0 * b0
+//!   t1 = a0 * b1 + a1 * b0
+//!   t2 = a0 * b2 + a1 * b1 + a2 * b0
+//!   t3 = a0 * b3 + a1 * b2 + a2 * b1 + a3 * b0
+//!   t4 = a1 * b3 + a2 * b2 + a3 * b1
+//!   t5 = a2 * b3 + a3 * b2
+//!   t6 = a3 * b3
+//! and the chip constrains
+//!   t0 + t1 * 2^64 + c_lo = e_lo + carry_0 * 2^128
+//!   t2 + t3 * 2^64 + c_hi + carry_0 = e_hi + carry_1 * 2^128
+//!   t4 + t5 * 2^64 + carry_1 = d_lo + carry_2 * 2^128
+//!   t6 + carry_2 = d_hi
+
+use eth_types::{Field, ToLittleEndian, Word};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::{
+    mul_add::{query_limbs, split_u256, split_u256_limb64, word_to_field},
+    util::{expr_from_bytes, pow_of_two},
+};
+
+/// Instruction that the MulAdd512 chip needs to implement.
+pub trait MulAdd512Instruction<F: FieldExt> {
+    /// Assign the witnesses of `a * b + c == d * 2^256 + e` to the MulAdd512
+    /// chip's region, where `words` is `[a, b, c, d, e]`.
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        words: [Word; 5],
+    ) -> Result<(), Error>;
+}
+
+/// Config for the MulAdd512 chip.  Note that the range of the bytes of `a`,
+/// `b` and the carries is not checked by this config.
+#[derive(Clone, Debug)]
+pub struct MulAdd512Config {
+    /// Little-endian bytes of the multiplicand `a`.
+    pub a: [Column<Advice>; 32],
+    /// Little-endian bytes of the multiplier `b`.
+    pub b: [Column<Advice>; 32],
+    /// Low and high 128 bits of the addend `c`.
+    pub c: [Column<Advice>; 2],
+    /// Low and high 128 bits of the high word `d` of the result.
+    pub d: [Column<Advice>; 2],
+    /// Low and high 128 bits of the low word `e` of the result.
+    pub e: [Column<Advice>; 2],
+    /// Little-endian bytes of the carries of the 128-bit parts of the result.
+    pub carries: [[Column<Advice>; 9]; 3],
+}
+
+/// Chip that checks `a * b + c == d * 2^256 + e`.
+#[derive(Clone, Debug)]
+pub struct MulAdd512Chip<F> {
+    config: MulAdd512Config,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: Field> MulAdd512Chip<F> {
+    /// Configures the MulAdd512 chip.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+    ) -> MulAdd512Config {
+        let a = [(); 32].map(|_| meta.advice_column());
+        let b = [(); 32].map(|_| meta.advice_column());
+        let c = [(); 2].map(|_| meta.advice_column());
+        let d = [(); 2].map(|_| meta.advice_column());
+        let e = [(); 2].map(|_| meta.advice_column());
+        let carries = [(); 3].map(|_| [(); 9].map(|_| meta.advice_column()));
+
+        meta.create_gate("mul add 512 gate", |meta| {
+            let q_enable = q_enable(meta);
+
+            let a_limbs = query_limbs(meta, &a);
+            let b_limbs = query_limbs(meta, &b);
+            let [c_lo, c_hi] = c.map(|column| meta.query_advice(column, Rotation::cur()));
+            let [d_lo, d_hi] = d.map(|column| meta.query_advice(column, Rotation::cur()));
+            let [e_lo, e_hi] = e.map(|column| meta.query_advice(column, Rotation::cur()));
+            let [carry_0, carry_1, carry_2] = carries.map(|carry| {
+                expr_from_bytes(
+                    &carry
+                        .iter()
+                        .map(|column| meta.query_advice(*column, Rotation::cur()))
+                        .collect::<Vec<_>>(),
+                )
+            });
+
+            let t0 = a_limbs[0].clone() * b_limbs[0].clone();
+            let t1 =
+                a_limbs[0].clone() * b_limbs[1].clone() + a_limbs[1].clone() * b_limbs[0].clone();
+            let t2 = a_limbs[0].clone() * b_limbs[2].clone()
+                + a_limbs[1].clone() * b_limbs[1].clone()
+                + a_limbs[2].clone() * b_limbs[0].clone();
+            let t3 = a_limbs[0].clone() * b_limbs[3].clone()
+                + a_limbs[1].clone() * b_limbs[2].clone()
+                + a_limbs[2].clone() * b_limbs[1].clone()
+                + a_limbs[3].clone() * b_limbs[0].clone();
+            let t4 = a_limbs[1].clone() * b_limbs[3].clone()
+                + a_limbs[2].clone() * b_limbs[2].clone()
+                + a_limbs[3].clone() * b_limbs[1].clone();
+            let t5 =
+                a_limbs[2].clone() * b_limbs[3].clone() + a_limbs[3].clone() * b_limbs[2].clone();
+            let t6 = a_limbs[3].clone() * b_limbs[3].clone();
+
+            let two_64 = Expression::Constant(pow_of_two::<F>(64));
+            let two_128 = Expression::Constant(pow_of_two::<F>(128));
+
+            // t0 + t1 ⋅ 2^64 + c_lo == e_lo + carry_0 ⋅ 2^128
+            let check_0 =
+                t0 + t1 * two_64.clone() + c_lo - e_lo - carry_0.clone() * two_128.clone();
+            // t2 + t3 ⋅ 2^64 + c_hi + carry_0 == e_hi + carry_1 ⋅ 2^128
+            let check_1 = t2 + t3 * two_64.clone() + c_hi + carry_0
+                - e_hi
+                - carry_1.clone() * two_128.clone();
+            // t4 + t5 ⋅ 2^64 + carry_1 == d_lo + carry_2 ⋅ 2^128
+            let check_2 = t4 + t5 * two_64 + carry_1 - d_lo - carry_2.clone() * two_128;
+            // t6 + carry_2 == d_hi
+            let check_3 = t6 + carry_2 - d_hi;
+
+            [check_0, check_1, check_2, check_3].map(move |poly| q_enable.clone() * poly)
+        });
+
+        MulAdd512Config {
+            a,
+            b,
+            c,
+            d,
+            e,
+            carries,
+        }
+    }
+
+    /// Constructs a MulAdd512 chip given a config.
+    pub fn construct(config: MulAdd512Config) -> MulAdd512Chip<F> {
+        MulAdd512Chip {
+            config,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<F: Field> MulAdd512Instruction<F> for MulAdd512Chip<F> {
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        words: [Word; 5],
+    ) -> Result<(), Error> {
+        let config = self.config();
+        let [a, b, c, d, e] = words;
+
+        for (name, columns, word) in [("a", &config.a, a), ("b", &config.b, b)] {
+            for (idx, (column, byte)) in columns.iter().zip(word.to_le_bytes()).enumerate() {
+                region.assign_advice(
+                    || format!("mul add 512 chip: {} byte {}", name, idx),
+                    *column,
+                    offset,
+                    || Ok(F::from(byte as u64)),
+                )?;
+            }
+        }
+
+        let (c_lo, c_hi) = split_u256(&c);
+        let (d_lo, d_hi) = split_u256(&d);
+        let (e_lo, e_hi) = split_u256(&e);
+        for (name, column, value) in [
+            ("c_lo", config.c[0], c_lo),
+            ("c_hi", config.c[1], c_hi),
+            ("d_lo", config.d[0], d_lo),
+            ("d_hi", config.d[1], d_hi),
+            ("e_lo", config.e[0], e_lo),
+            ("e_hi", config.e[1], e_hi),
+        ] {
+            region.assign_advice(
+                || format!("mul add 512 chip: {}", name),
+                column,
+                offset,
+                || Ok(word_to_field(value)),
+            )?;
+        }
+
+        let a_limbs = split_u256_limb64(&a);
+        let b_limbs = split_u256_limb64(&b);
+        let t0 = a_limbs[0] * b_limbs[0];
+        let t1 = a_limbs[0] * b_limbs[1] + a_limbs[1] * b_limbs[0];
+        let t2 = a_limbs[0] * b_limbs[2] + a_limbs[1] * b_limbs[1] + a_limbs[2] * b_limbs[0];
+        let t3 = a_limbs[0] * b_limbs[3]
+            + a_limbs[1] * b_limbs[2]
+            + a_limbs[2] * b_limbs[1]
+            + a_limbs[3] * b_limbs[0];
+        let t4 = a_limbs[1] * b_limbs[3] + a_limbs[2] * b_limbs[2] + a_limbs[3] * b_limbs[1];
+        let t5 = a_limbs[2] * b_limbs[3] + a_limbs[3] * b_limbs[2];
+        let carry_0 = (t0 + (t1 << 64) + c_lo).saturating_sub(e_lo) >> 128;
+        let carry_1 = (t2 + (t3 << 64) + c_hi + carry_0).saturating_sub(e_hi) >> 128;
+        let carry_2 = (t4 + (t5 << 64) + carry_1).saturating_sub(d_lo) >> 128;
+
+        for (idx, (columns, carry)) in config
+            .carries
+            .iter()
+            .zip([carry_0, carry_1, carry_2])
+            .enumerate()
+        {
+            for (byte_idx, (column, byte)) in columns.iter().zip(carry.to_le_bytes()).enumerate() {
+                region.assign_advice(
+                    || format!("mul add 512 chip: carry_{} byte {}", idx, byte_idx),
+                    *column,
+                    offset,
+                    || Ok(F::from(byte as u64)),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<F: Field> Chip<F> for MulAdd512Chip<F> {
+    type Config = MulAdd512Config;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MulAdd512Chip, MulAdd512Config, MulAdd512Instruction};
+    use eth_types::{Field, Word};
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        pairing::bn256::Fr as Fp,
+        plonk::{Circuit, ConstraintSystem, Error, Selector},
+    };
+    use std::marker::PhantomData;
+
+    macro_rules! try_test_circuit {
+        ($values:expr, $result:expr) => {{
+            let k = 6;
+            let circuit = TestCircuit::<Fp> {
+                values: $values,
+                _marker: PhantomData,
+            };
+            let prover = MockProver::<Fp>::run(k, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify().is_ok(), $result);
+        }};
+    }
+
+    #[derive(Clone, Debug)]
+    struct TestCircuitConfig {
+        q_enable: Selector,
+        mul_add: MulAdd512Config,
+    }
+
+    #[derive(Default)]
+    struct TestCircuit<F: FieldExt> {
+        values: Vec<[Word; 5]>,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: Field> Circuit<F> for TestCircuit<F> {
+        type Config = TestCircuitConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let q_enable = meta.selector();
+            let mul_add = MulAdd512Chip::configure(meta, |meta| meta.query_selector(q_enable));
+
+            Self::Config { q_enable, mul_add }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = MulAdd512Chip::construct(config.mul_add);
+
+            layouter.assign_region(
+                || "witness",
+                |mut region| {
+                    for (offset, words) in self.values.iter().enumerate() {
+                        config.q_enable.enable(&mut region, offset)?;
+                        chip.assign(&mut region, offset, *words)?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn mul_add(a: Word, b: Word, c: Word) -> [Word; 5] {
+        let (product, _) = a.full_mul(b).overflowing_add(c.into());
+        let d = Word([product.0[4], product.0[5], product.0[6], product.0[7]]);
+        let e = Word([product.0[0], product.0[1], product.0[2], product.0[3]]);
+        [a, b, c, d, e]
+    }
+
+    #[test]
+    fn mul_add_512_valid() {
+        try_test_circuit!(
+            vec![
+                mul_add(Word::from(0), Word::from(0), Word::from(0)),
+                mul_add(Word::from(3), Word::from(5), Word::from(7)),
+                mul_add(Word::from(u64::MAX), Word::from(u128::MAX), Word::from(1)),
+                mul_add(Word::MAX, Word::MAX, Word::MAX),
+                mul_add(
+                    Word::from(2).pow(Word::from(200)),
+                    Word::from(2).pow(Word::from(100)),
+                    Word::from(0)
+                ),
+            ],
+            true
+        );
+    }
+
+    #[test]
+    fn mul_add_512_invalid() {
+        let [a, b, c, d, e] = mul_add(Word::from(3), Word::from(5), Word::from(7));
+        try_test_circuit!(vec![[a, b, c, d, e + 1]], false);
+        let [a, b, c, d, e] = mul_add(Word::MAX, Word::from(2), Word::from(0));
+        try_test_circuit!(vec![[a, b, c, d - 1, e]], false);
+        // The overflow word can't be dropped.
+        let [a, b, c, _, e] = mul_add(Word::MAX, Word::MAX, Word::MAX);
+        try_test_circuit!(vec![[a, b, c, Word::zero(), e]], false);
+    }
+}
@@ -0,0 +1,162 @@
+//! Mutation framework for soundness tests: it wraps an assigned circuit and
+//! mutates targeted advice cells, so that a test can assert that the
+//! constraints of a circuit reject the witnesses which differ from a valid
+//! one in the targeted cells, and not only accept the valid one.
+//!
+//! The cells are targeted by region, column or annotation, and offset in the
+//! region, so that any circuit can be mutated without changing its
+//! assignment code:
+//!
+//! ```ignore
+//! let mutations = vec![
+//!     Mutation::new(CellTarget::Column(config.diff_inv), 1, Mutate::Add(Fp::one())),
+//!     Mutation::new(CellTarget::Annotation("value_a"), 2, Mutate::Set(Fp::zero()))
+//!         .in_region("witness"),
+//! ];
+//! assert_mutations_rejected(k, circuit, vec![], mutations);
+//! ```
+
+use crate::assignment_hook::{AssignmentHook, HookedCircuit};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    dev::MockProver,
+    plonk::{Advice, Assigned, Circuit, Column},
+};
+use std::cell::RefCell;
+
+/// The cells of a region targeted by a mutation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CellTarget {
+    /// The cells of an advice column.
+    Column(Column<Advice>),
+    /// The cells assigned with an annotation containing this string, which
+    /// targets them by their meaning rather than by their column.
+    Annotation(&'static str),
+}
+
+/// How the value of a targeted cell is mutated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mutate<F> {
+    /// Replace the value by this one.
+    Set(F),
+    /// Add this value to the assigned one.
+    Add(F),
+}
+
+/// A mutation of the advice cell at `offset` of the regions whose name
+/// contains `region`, or of all the regions if `region` is `None`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mutation<F> {
+    target: CellTarget,
+    offset: usize,
+    mutate: Mutate<F>,
+    region: Option<&'static str>,
+}
+
+impl<F: FieldExt> Mutation<F> {
+    /// Mutation of the `target` cells at `offset` in every region.
+    pub fn new(target: CellTarget, offset: usize, mutate: Mutate<F>) -> Self {
+        Self {
+            target,
+            offset,
+            mutate,
+            region: None,
+        }
+    }
+
+    /// Restricts the mutation to the regions whose name contains `region`.
+    pub fn in_region(mut self, region: &'static str) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    fn matches(
+        &self,
+        region: &str,
+        annotation: &dyn Fn() -> String,
+        column: Column<Advice>,
+        offset: usize,
+    ) -> bool {
+        self.offset == offset
+            && self.region.map_or(true, |name| region.contains(name))
+            && match &self.target {
+                CellTarget::Column(target) => *target == column,
+                CellTarget::Annotation(target) => annotation().contains(target),
+            }
+    }
+
+    fn apply(&self, value: Assigned<F>) -> Assigned<F> {
+        match self.mutate {
+            Mutate::Set(value) => Assigned::from(value),
+            Mutate::Add(delta) => value + Assigned::from(delta),
+        }
+    }
+}
+
+/// Hook which applies a mutation, and counts the mutated cells.
+struct MutationHook<F> {
+    mutation: Mutation<F>,
+    applied: RefCell<usize>,
+}
+
+impl<F: FieldExt> AssignmentHook<F> for MutationHook<F> {
+    fn advice(
+        &self,
+        region: &str,
+        annotation: &dyn Fn() -> String,
+        column: Column<Advice>,
+        offset: usize,
+        value: Assigned<F>,
+    ) -> Assigned<F> {
+        if !self.mutation.matches(region, annotation, column, offset) {
+            return value;
+        }
+        *self.applied.borrow_mut() += 1;
+        self.mutation.apply(value)
+    }
+}
+
+/// Returns the result of the MockProver verification of `circuit` with the
+/// cells targeted by `mutation` mutated.  Panics if no cell is targeted, as
+/// the verification would then trivially check the valid witness.
+pub fn verify_mutated<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instance: Vec<Vec<F>>,
+    mutation: Mutation<F>,
+) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+    let mutated = HookedCircuit::new(
+        circuit,
+        MutationHook {
+            mutation: mutation.clone(),
+            applied: RefCell::new(0),
+        },
+    );
+    let prover = MockProver::<F>::run(k, &mutated, instance).unwrap();
+    assert!(
+        *mutated.hook().applied.borrow() > 0,
+        "mutation {:?} targets no assigned cell",
+        mutation
+    );
+    prover.verify()
+}
+
+/// Asserts that the valid `circuit` is accepted, and that each of the
+/// `mutations` of its witness, applied one at a time, is rejected.
+pub fn assert_mutations_rejected<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instance: Vec<Vec<F>>,
+    mutations: Vec<Mutation<F>>,
+) {
+    let prover = MockProver::<F>::run(k, circuit, instance.clone()).unwrap();
+    assert_eq!(prover.verify(), Ok(()), "valid witness rejected");
+
+    for mutation in mutations {
+        assert!(
+            verify_mutated(k, circuit, instance.clone(), mutation.clone()).is_err(),
+            "witness mutated by {:?} accepted",
+            mutation
+        );
+    }
+}
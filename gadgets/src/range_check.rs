@@ -0,0 +1,248 @@
+//! RangeCheck chip owns the fixed tables of the values 0..2^8 and 0..2^16, with
+//! which any number of cells per row can be range checked by lookups, so that
+//! the range checks of a circuit share the same fixed columns.
+
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{Chip, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// Config for the RangeCheck chip.
+#[derive(Clone, Copy, Debug)]
+pub struct RangeCheckConfig {
+    /// Fixed table of the values 0..2^8.
+    pub u8: Column<Fixed>,
+    /// Fixed table of the values 0..2^16, which is only configured for the
+    /// circuits doing 16-bit range checks, as it needs 2^16 rows.
+    pub u16: Option<Column<Fixed>>,
+}
+
+impl RangeCheckConfig {
+    /// Range checks the cells of `columns` at the current row to be in
+    /// 0..2^8 when `q_enable` is 1.
+    pub fn range_check_u8<F: Field>(
+        &self,
+        meta: &mut ConstraintSystem<F>,
+        name: &'static str,
+        q_enable: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F>,
+        columns: impl IntoIterator<Item = Column<Advice>>,
+    ) {
+        Self::range_check(meta, name, q_enable, columns, self.u8);
+    }
+
+    /// Range checks the cells of `columns` at the current row to be in
+    /// 0..2^16 when `q_enable` is 1.  Panics if the chip is configured without
+    /// the u16 table.
+    pub fn range_check_u16<F: Field>(
+        &self,
+        meta: &mut ConstraintSystem<F>,
+        name: &'static str,
+        q_enable: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F>,
+        columns: impl IntoIterator<Item = Column<Advice>>,
+    ) {
+        let u16 = self
+            .u16
+            .expect("range check chip configured without u16 table");
+        Self::range_check(meta, name, q_enable, columns, u16);
+    }
+
+    fn range_check<F: Field>(
+        meta: &mut ConstraintSystem<F>,
+        name: &'static str,
+        q_enable: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F>,
+        columns: impl IntoIterator<Item = Column<Advice>>,
+        table: Column<Fixed>,
+    ) {
+        for column in columns {
+            meta.lookup_any(name, |meta| {
+                vec![(
+                    q_enable(meta) * meta.query_advice(column, Rotation::cur()),
+                    meta.query_fixed(table, Rotation::cur()),
+                )]
+            });
+        }
+    }
+}
+
+/// Chip that loads the tables of the range checks.
+#[derive(Clone, Debug)]
+pub struct RangeCheckChip<F> {
+    config: RangeCheckConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> RangeCheckChip<F> {
+    /// Configures the RangeCheck chip, with the u16 table only if `with_u16`
+    /// is true.
+    pub fn configure(meta: &mut ConstraintSystem<F>, with_u16: bool) -> RangeCheckConfig {
+        RangeCheckConfig {
+            u8: meta.fixed_column(),
+            u16: with_u16.then(|| meta.fixed_column()),
+        }
+    }
+
+    /// Constructs a RangeCheck chip given a config.
+    pub fn construct(config: RangeCheckConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Loads the fixed tables of the chip.
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        for (column, bits) in [(Some(self.config.u8), 8), (self.config.u16, 16)] {
+            if let Some(column) = column {
+                layouter.assign_region(
+                    || format!("range check chip: u{} table", bits),
+                    |mut region| {
+                        for value in 0..(1 << bits) {
+                            region.assign_fixed(
+                                || format!("u{} table", bits),
+                                column,
+                                value,
+                                || Ok(F::from(value as u64)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: Field> Chip<F> for RangeCheckChip<F> {
+    type Config = RangeCheckConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RangeCheckChip, RangeCheckConfig};
+    use eth_types::Field;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        pairing::bn256::Fr as Fp,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
+    };
+    use std::marker::PhantomData;
+
+    #[derive(Clone, Debug)]
+    struct TestCircuitConfig {
+        q_enable: Selector,
+        // Range checked with the u8 table.
+        bytes: [Column<Advice>; 2],
+        // Range checked with the u16 table.
+        halfword: Column<Advice>,
+        range_check: RangeCheckConfig,
+    }
+
+    #[derive(Default)]
+    struct TestCircuit<F> {
+        values: Vec<([u64; 2], u64)>,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: Field> Circuit<F> for TestCircuit<F> {
+        type Config = TestCircuitConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let q_enable = meta.complex_selector();
+            let bytes = [(); 2].map(|_| meta.advice_column());
+            let halfword = meta.advice_column();
+            let range_check = RangeCheckChip::configure(meta, true);
+
+            range_check.range_check_u8(
+                meta,
+                "bytes in u8 range",
+                |meta| meta.query_selector(q_enable),
+                bytes,
+            );
+            range_check.range_check_u16(
+                meta,
+                "halfword in u16 range",
+                |meta| meta.query_selector(q_enable),
+                [halfword],
+            );
+
+            Self::Config {
+                q_enable,
+                bytes,
+                halfword,
+                range_check,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            RangeCheckChip::construct(config.range_check).load(&mut layouter)?;
+
+            layouter.assign_region(
+                || "witness",
+                |mut region| {
+                    for (offset, (bytes, halfword)) in self.values.iter().enumerate() {
+                        config.q_enable.enable(&mut region, offset)?;
+                        for (column, byte) in config.bytes.iter().zip(bytes) {
+                            region.assign_advice(
+                                || "byte",
+                                *column,
+                                offset,
+                                || Ok(F::from(*byte)),
+                            )?;
+                        }
+                        region.assign_advice(
+                            || "halfword",
+                            config.halfword,
+                            offset,
+                            || Ok(F::from(*halfword)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn verify(values: Vec<([u64; 2], u64)>) -> bool {
+        let circuit = TestCircuit::<Fp> {
+            values,
+            _marker: PhantomData,
+        };
+        // The u16 table needs 2^16 rows, plus the blinding rows.
+        let prover = MockProver::<Fp>::run(17, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    fn range_check_valid() {
+        assert!(verify(vec![([0, 255], 0), ([17, 3], 65535)]));
+    }
+
+    #[test]
+    fn range_check_invalid() {
+        assert!(!verify(vec![([0, 256], 0)]));
+        assert!(!verify(vec![([0, 0], 65536)]));
+    }
+}
@@ -0,0 +1,279 @@
+//! Witness snapshots for golden tests: the non-zero values assigned in the
+//! advice and fixed cells of the selected regions of a circuit, serialized one
+//! cell per line and sorted by region, offset and column, so that a change of
+//! the row layout or of the content of a table shows up as a diff of a
+//! checked-in golden file.
+//!
+//! ```ignore
+//! let snapshot = witness_snapshot(k, &circuit, instance, |region| region == "rw table");
+//! assert_snapshot(golden_path, &snapshot);
+//! ```
+//!
+//! A missing golden file is written by [`assert_snapshot`], and all of them
+//! are rewritten when the environment variable `UPDATE_SNAPSHOTS` is set.
+
+use crate::assignment_hook::{AssignmentHook, HookedCircuit};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    dev::MockProver,
+    pairing::group::ff::PrimeField,
+    plonk::{Advice, Any, Assigned, Circuit, Column, Fixed},
+};
+use std::{cell::RefCell, fmt::Write, fs, path::Path};
+
+/// Environment variable which makes [`assert_snapshot`] rewrite the golden
+/// files instead of comparing with them.
+pub const UPDATE_SNAPSHOTS: &str = "UPDATE_SNAPSHOTS";
+
+/// A cell assigned in a region.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct SnapshotCell {
+    region: String,
+    offset: usize,
+    column: (bool, usize),
+    annotation: String,
+    value: String,
+}
+
+/// Hook which records the cells assigned in the selected regions.
+struct SnapshotHook<R> {
+    regions: R,
+    cells: RefCell<Vec<SnapshotCell>>,
+}
+
+impl<R: Fn(&str) -> bool> SnapshotHook<R> {
+    fn record<F: FieldExt>(
+        &self,
+        region: &str,
+        annotation: &dyn Fn() -> String,
+        column: Column<Any>,
+        offset: usize,
+        value: Assigned<F>,
+    ) {
+        let value = value.evaluate();
+        if (self.regions)(region) && value != F::zero() {
+            self.cells.borrow_mut().push(SnapshotCell {
+                region: region.to_string(),
+                offset,
+                column: (*column.column_type() == Any::Fixed, column.index()),
+                annotation: annotation(),
+                value: format_value(value),
+            });
+        }
+    }
+}
+
+impl<F: FieldExt, R: Fn(&str) -> bool> AssignmentHook<F> for SnapshotHook<R> {
+    fn advice(
+        &self,
+        region: &str,
+        annotation: &dyn Fn() -> String,
+        column: Column<Advice>,
+        offset: usize,
+        value: Assigned<F>,
+    ) -> Assigned<F> {
+        self.record(region, annotation, column.into(), offset, value);
+        value
+    }
+
+    fn fixed(
+        &self,
+        region: &str,
+        annotation: &dyn Fn() -> String,
+        column: Column<Fixed>,
+        offset: usize,
+        value: Assigned<F>,
+    ) -> Assigned<F> {
+        self.record(region, annotation, column.into(), offset, value);
+        value
+    }
+}
+
+/// Returns the big endian hex of `value`, or of its negation prefixed by `-`
+/// if it is shorter, so that the small negative values stay readable.
+fn format_value<F: FieldExt>(value: F) -> String {
+    let hex = |value: F| {
+        let repr = value.to_repr();
+        let hex = repr
+            .as_ref()
+            .iter()
+            .rev()
+            .fold(String::new(), |mut hex, byte| {
+                write!(hex, "{:02x}", byte).unwrap();
+                hex
+            });
+        match hex.trim_start_matches('0') {
+            "" => "0".to_string(),
+            hex => hex.to_string(),
+        }
+    };
+    let (positive, negative) = (hex(value), hex(-value));
+    if negative.len() < positive.len() {
+        format!("-0x{}", negative)
+    } else {
+        format!("0x{}", positive)
+    }
+}
+
+/// Returns the snapshot of the non-zero cells assigned by `circuit` in the
+/// regions whose name is selected by `regions`.
+pub fn witness_snapshot<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    instance: Vec<Vec<F>>,
+    regions: impl Fn(&str) -> bool,
+) -> String {
+    let hooked = HookedCircuit::new(
+        circuit,
+        SnapshotHook {
+            regions,
+            cells: RefCell::new(vec![]),
+        },
+    );
+    MockProver::<F>::run(k, &hooked, instance).unwrap();
+
+    let mut cells = hooked.hook().cells.borrow().clone();
+    cells.sort();
+    cells.dedup();
+    cells.into_iter().fold(String::new(), |mut snapshot, cell| {
+        writeln!(
+            snapshot,
+            "{} {} {}[{}] '{}' {}",
+            cell.region,
+            cell.offset,
+            if cell.column.0 { "fixed" } else { "advice" },
+            cell.column.1,
+            cell.annotation,
+            cell.value
+        )
+        .unwrap();
+        snapshot
+    })
+}
+
+/// Asserts that `snapshot` is the content of the golden file at `path`, which
+/// is written instead if it is missing or if `UPDATE_SNAPSHOTS` is set.
+pub fn assert_snapshot(path: impl AsRef<Path>, snapshot: &str) {
+    let path = path.as_ref();
+    if std::env::var_os(UPDATE_SNAPSHOTS).is_some() || !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, snapshot).unwrap();
+        log::info!("snapshot {} written", path.display());
+        return;
+    }
+
+    let golden = fs::read_to_string(path).unwrap();
+    if golden == snapshot {
+        return;
+    }
+    let (golden_lines, lines) = (golden.lines().count(), snapshot.lines().count());
+    let (line, (expected, actual)) = golden
+        .lines()
+        .map(Some)
+        .chain(std::iter::repeat(None))
+        .zip(snapshot.lines().map(Some).chain(std::iter::repeat(None)))
+        .enumerate()
+        .find(|(_, (expected, actual))| expected != actual)
+        .unwrap();
+    panic!(
+        "witness differs from the snapshot {} ({} lines, {} expected) at line {}:\n\
+         expected: {}\n  actual: {}\n\
+         rerun with {}=1 to update the snapshot if the change is intended",
+        path.display(),
+        lines,
+        golden_lines,
+        line + 1,
+        expected.unwrap_or("<end>"),
+        actual.unwrap_or("<end>"),
+        UPDATE_SNAPSHOTS,
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::{format_value, witness_snapshot};
+    use halo2_proofs::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner},
+        pairing::bn256::Fr as Fp,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed},
+    };
+
+    #[derive(Clone, Debug)]
+    struct TestCircuitConfig {
+        advice: Column<Advice>,
+        fixed: Column<Fixed>,
+    }
+
+    #[derive(Default)]
+    struct TestCircuit {
+        values: Vec<u64>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for TestCircuit {
+        type Config = TestCircuitConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            TestCircuitConfig {
+                advice: meta.advice_column(),
+                fixed: meta.fixed_column(),
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "table",
+                |mut region| {
+                    for (offset, value) in self.values.iter().enumerate() {
+                        region.assign_fixed(
+                            || "index",
+                            config.fixed,
+                            offset,
+                            || Ok(F::from(offset as u64)),
+                        )?;
+                        region.assign_advice(
+                            || "value",
+                            config.advice,
+                            offset,
+                            || Ok(-F::from(*value)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+            layouter.assign_region(
+                || "ignored",
+                |mut region| {
+                    region.assign_advice(|| "value", config.advice, 0, || Ok(F::one()))?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn snapshot_selected_regions() {
+        let circuit = TestCircuit {
+            values: vec![0, 1, 0x1234],
+        };
+        assert_eq!(
+            witness_snapshot::<Fp, _>(4, &circuit, vec![], |region| region == "table"),
+            "table 1 advice[0] 'value' -0x1\n\
+             table 1 fixed[0] 'index' 0x1\n\
+             table 2 advice[0] 'value' -0x1234\n\
+             table 2 fixed[0] 'index' 0x2\n"
+        );
+        assert_eq!(format_value(Fp::from(0xabcd)), "0xabcd");
+    }
+}
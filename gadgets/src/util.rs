@@ -1,5 +1,6 @@
 //! Utility traits, functions used in the crate.
 use eth_types::evm_types::{GasCost, OpcodeId};
+use eth_types::public_data::{BlockContextFieldTag, TxContextFieldTag};
 use halo2_proofs::{arithmetic::FieldExt, plonk::Expression};
 
 /// Returns the sum of the passed in cells
@@ -115,6 +116,32 @@ pub mod select {
     }
 }
 
+/// Returns the random linear combination of the inputs, with the first input
+/// multiplied by the lowest power of the randomness: `v_0 + v_1 * r + ...`.
+pub mod rlc {
+    use crate::util::Expr;
+    use halo2_proofs::{arithmetic::FieldExt, plonk::Expression};
+
+    /// Returns an expression for the random linear combination of the
+    /// expressions.
+    pub fn expr<F: FieldExt, E: Expr<F>>(
+        expressions: &[E],
+        randomness: Expression<F>,
+    ) -> Expression<F> {
+        expressions.iter().rev().fold(0.expr(), |acc, expression| {
+            acc * randomness.clone() + expression.expr()
+        })
+    }
+
+    /// Returns the random linear combination of the values.
+    pub fn value<F: FieldExt>(values: &[F], randomness: F) -> F {
+        values
+            .iter()
+            .rev()
+            .fold(F::zero(), |acc, value| acc * randomness + value)
+    }
+}
+
 /// Trait that implements functionality to get a constant expression from
 /// commonly used types.
 pub trait Expr<F: FieldExt> {
@@ -149,6 +176,8 @@ impl_expr!(u64);
 impl_expr!(usize);
 impl_expr!(OpcodeId, OpcodeId::as_u8);
 impl_expr!(GasCost, GasCost::as_u64);
+impl_expr!(TxContextFieldTag);
+impl_expr!(BlockContextFieldTag);
 
 impl<F: FieldExt> Expr<F> for Expression<F> {
     #[inline]
@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.4;
+
+import "../vendor/openzeppelin-contracts/contracts/token/ERC20/IERC20.sol";
+
+/**
+ * @title SwapPair
+ * @dev Minimal Uniswap V2 style pair of two ERC20 tokens, which swaps them at
+ * the constant product price with a 0.3% fee.
+ */
+contract SwapPair {
+    IERC20 public immutable token0;
+    IERC20 public immutable token1;
+
+    uint256 public reserve0;
+    uint256 public reserve1;
+
+    event Swap(address indexed sender, uint256 amountIn, uint256 amountOut, bool zeroForOne, address indexed to);
+
+    constructor(IERC20 _token0, IERC20 _token1) {
+        token0 = _token0;
+        token1 = _token1;
+    }
+
+    function addLiquidity(uint256 amount0, uint256 amount1) external {
+        require(token0.transferFrom(msg.sender, address(this), amount0), "transfer0");
+        require(token1.transferFrom(msg.sender, address(this), amount1), "transfer1");
+        reserve0 += amount0;
+        reserve1 += amount1;
+    }
+
+    function getAmountOut(uint256 amountIn, uint256 reserveIn, uint256 reserveOut) public pure returns (uint256) {
+        uint256 amountInWithFee = amountIn * 997;
+        return (amountInWithFee * reserveOut) / (reserveIn * 1000 + amountInWithFee);
+    }
+
+    function swap(uint256 amountIn, bool zeroForOne, address to) external returns (uint256 amountOut) {
+        (IERC20 tokenIn, IERC20 tokenOut, uint256 reserveIn, uint256 reserveOut) = zeroForOne
+            ? (token0, token1, reserve0, reserve1)
+            : (token1, token0, reserve1, reserve0);
+        amountOut = getAmountOut(amountIn, reserveIn, reserveOut);
+        require(amountOut > 0, "insufficient output");
+
+        require(tokenIn.transferFrom(msg.sender, address(this), amountIn), "transfer in");
+        require(tokenOut.transfer(to, amountOut), "transfer out");
+        if (zeroForOne) {
+            reserve0 += amountIn;
+            reserve1 -= amountOut;
+        } else {
+            reserve1 += amountIn;
+            reserve0 -= amountOut;
+        }
+        emit Swap(msg.sender, amountIn, amountOut, zeroForOne, to);
+    }
+}
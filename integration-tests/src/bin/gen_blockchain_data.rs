@@ -1,5 +1,5 @@
 use ethers::{
-    abi::{self, Tokenize},
+    abi::{self, Detokenize, Tokenize},
     contract::{builders::ContractCall, Contract, ContractFactory},
     core::types::{
         transaction::eip2718::TypedTransaction, Address, TransactionReceipt, TransactionRequest,
@@ -39,26 +39,49 @@ where
         .expect("cannot confirm deploy")
 }
 
-fn erc20_transfer<M>(
+fn contract_call<M, T, D>(
     prov: Arc<M>,
     contract_address: Address,
     contract_abi: &abi::Contract,
-    to: Address,
-    amount: U256,
+    method: &str,
+    args: T,
+    gas: u64,
 ) -> TypedTransaction
 where
     M: Middleware,
+    T: Tokenize,
+    D: Detokenize,
 {
     let contract = Contract::new(contract_address, contract_abi.clone(), prov);
-    let call: ContractCall<M, _> = contract
-        .method::<_, bool>("transfer", (to, amount))
-        .expect("cannot construct ERC20 transfer call");
+    let call: ContractCall<M, D> = contract
+        .method::<_, D>(method, args)
+        .unwrap_or_else(|_| panic!("cannot construct {} call", method));
     // Set gas to avoid `eth_estimateGas` call
     let call = call.legacy();
-    let call = call.gas(100_000);
+    let call = call.gas(gas);
     call.tx
 }
 
+fn erc20_transfer<M>(
+    prov: Arc<M>,
+    contract_address: Address,
+    contract_abi: &abi::Contract,
+    to: Address,
+    amount: U256,
+) -> TypedTransaction
+where
+    M: Middleware,
+{
+    contract_call::<_, _, bool>(
+        prov,
+        contract_address,
+        contract_abi,
+        "transfer",
+        (to, amount),
+        100_000,
+    )
+}
+
 async fn send_confirm_tx<M>(prov: &Arc<M>, tx: TypedTransaction) -> TransactionReceipt
 where
     M: Middleware,
@@ -356,6 +379,94 @@ async fn main() {
         block_num.as_u64(),
     );
 
+    //
+    // Uniswap V2 style swap between two ERC20 tokens
+    //
+
+    info!("Generating swap pair calls...");
+
+    // Second token of the pair, whose whole supply is owned by wallet0 like
+    // the first one.
+    let contract = deploy(
+        prov_wallet0.clone(),
+        contracts
+            .get("OpenZeppelinERC20TestToken")
+            .expect("contract not found"),
+        prov_wallet0.address(),
+    )
+    .await;
+    let block_num = prov.get_block_number().await.expect("cannot get block_num");
+    deployments.insert(
+        "OpenZeppelinERC20TestToken2".to_string(),
+        (block_num.as_u64(), contract.address()),
+    );
+    let token1_address = contract.address();
+
+    let contract = deploy(
+        prov_wallet0.clone(),
+        contracts.get("SwapPair").expect("contract not found"),
+        (contract_address, token1_address),
+    )
+    .await;
+    let block_num = prov.get_block_number().await.expect("cannot get block_num");
+    blocks.insert("Deploy SwapPair".to_string(), block_num.as_u64());
+    deployments.insert(
+        "SwapPair".to_string(),
+        (block_num.as_u64(), contract.address()),
+    );
+    let pair_address = contract.address();
+    let pair_abi = &contracts.get("SwapPair").expect("contract not found").abi;
+
+    // wallet0 approves the pair to spend both of its tokens, and provides the
+    // liquidity of the pair.
+    info!("Adding liquidity to the swap pair...");
+    let amount = U256::from_dec_str("1000000000000000000000").unwrap();
+    for token_address in [contract_address, token1_address] {
+        let tx = contract_call::<_, _, bool>(
+            wallets[0].clone(),
+            token_address,
+            contract_abi,
+            "approve",
+            (pair_address, U256::MAX),
+            100_000,
+        );
+        let receipt = send_confirm_tx(&wallets[0], tx).await;
+        assert_eq!(receipt.status, Some(U64::from(1u64)));
+    }
+    let tx = contract_call::<_, _, ()>(
+        wallets[0].clone(),
+        pair_address,
+        pair_abi,
+        "addLiquidity",
+        (amount, amount),
+        300_000,
+    );
+    let receipt = send_confirm_tx(&wallets[0], tx).await;
+    assert_eq!(receipt.status, Some(U64::from(1u64)));
+    blocks.insert(
+        "SwapPair add liquidity".to_string(),
+        receipt.block_number.unwrap().as_u64(),
+    );
+
+    // wallet0 swaps 12.3 tokens of the first token for the second one, sent
+    // to wallet1.
+    info!("Doing swap pair swap...");
+    let amount = U256::from_dec_str("12300000000000000000").unwrap();
+    let tx = contract_call::<_, _, U256>(
+        wallets[0].clone(),
+        pair_address,
+        pair_abi,
+        "swap",
+        (amount, true, wallets[1].address()),
+        300_000,
+    );
+    let receipt = send_confirm_tx(&wallets[0], tx).await;
+    assert_eq!(receipt.status, Some(U64::from(1u64)));
+    blocks.insert(
+        "SwapPair swap".to_string(),
+        receipt.block_number.unwrap().as_u64(),
+    );
+
     let gen_data = GenDataOutput {
         coinbase: accounts[0],
         wallets: wallets.iter().map(|w| w.address()).collect(),
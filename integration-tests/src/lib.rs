@@ -3,7 +3,7 @@
 #![deny(rustdoc::broken_intra_doc_links)]
 #![deny(missing_docs)]
 
-use bus_mapping::rpc::GethClient;
+use bus_mapping::rpc::{GethClient, HttpBatchProvider};
 use env_logger::Env;
 use eth_types::Address;
 use ethers::{
@@ -33,6 +33,7 @@ pub const CONTRACTS: &[(&str, &str)] = &[
         "OpenZeppelinERC20TestToken",
         "ERC20/OpenZeppelinERC20TestToken.sol",
     ),
+    ("SwapPair", "swap/SwapPair.sol"),
 ];
 /// Path to gen_blockchain_data output file
 pub const GENDATA_OUTPUT_PATH: &str = "gendata_output.json";
@@ -59,8 +60,8 @@ pub fn log_init() {
 }
 
 /// Get the integration test [`GethClient`]
-pub fn get_client() -> GethClient<Http> {
-    let transport = Http::new(Url::parse(&GETH0_URL).expect("invalid url"));
+pub fn get_client() -> GethClient<HttpBatchProvider> {
+    let transport = HttpBatchProvider::new(Url::parse(&GETH0_URL).expect("invalid url"));
     GethClient::new(transport)
 }
 
@@ -1,6 +1,6 @@
 #![cfg(feature = "circuit_input_builder")]
 
-use bus_mapping::circuit_input_builder::BuilderClient;
+use bus_mapping::circuit_input_builder::{BuilderClient, CircuitsParams};
 use integration_tests::{get_client, log_init, GenDataOutput};
 use lazy_static::lazy_static;
 use log::trace;
@@ -11,26 +11,42 @@ lazy_static! {
 
 async fn test_circuit_input_builder_block(block_num: u64) {
     let cli = get_client();
-    let cli = BuilderClient::new(cli).await.unwrap();
+    let cli = BuilderClient::new(cli, CircuitsParams::default())
+        .await
+        .unwrap();
 
     // 1. Query geth for Block, Txs and TxExecTraces
     let (eth_block, geth_trace) = cli.get_block(block_num).await.unwrap();
+    let block = cli.get_block_context(&eth_block).await.unwrap();
 
-    // 2. Get State Accesses from TxExecTraces
-    let access_set = cli.get_state_accesses(&eth_block, &geth_trace).unwrap();
+    // 2. Get State Accesses from TxExecTraces and withdrawals
+    let access_set = cli
+        .get_state_accesses(&eth_block, &block.withdrawals, &geth_trace)
+        .unwrap();
     trace!("AccessSet: {:#?}", access_set);
 
     // 3. Query geth for all accounts, storage keys, and codes from Accesses
+    let code_addresses = access_set.code.clone();
     let (proofs, codes) = cli.get_state(block_num, access_set).await.unwrap();
 
     // 4. Build a partial StateDB from step 3
     let (state_db, code_db) = cli.build_state_code_db(proofs, codes);
     trace!("StateDB: {:#?}", state_db);
+    // The code of every account whose code is accessed is found by its code hash.
+    for address in code_addresses {
+        let (found, account) = state_db.get_account(&address);
+        assert!(found, "account {:?} not in the StateDB", address);
+        assert!(
+            code_db.0.contains_key(&account.code_hash),
+            "code of account {:?} not in the CodeDB",
+            address
+        );
+    }
 
     // 5. For each step in TxExecTraces, gen the associated ops and state
     // circuit inputs
     let builder = cli
-        .gen_inputs_from_state(state_db, code_db, &eth_block, &geth_trace)
+        .gen_inputs_from_state(state_db, code_db, block, &eth_block, &geth_trace)
         .unwrap();
 
     trace!("CircuitInputBuilder: {:#?}", builder);
@@ -77,3 +93,18 @@ declare_tests!(
     test_circuit_input_builder_multiple_erc20_openzeppelin_transfers,
     "Multiple ERC20 OpenZeppelin transfers"
 );
+// This test builds the complete circuit inputs for the block where the
+// SwapPair contract is deployed.
+declare_tests!(
+    test_circuit_input_builder_deploy_swap_pair,
+    "Deploy SwapPair"
+);
+// This test builds the complete circuit inputs for the block where liquidity
+// is added to the SwapPair, transferring both of its tokens.
+declare_tests!(
+    test_circuit_input_builder_swap_pair_add_liquidity,
+    "SwapPair add liquidity"
+);
+// This test builds the complete circuit inputs for the block with a swap of
+// the SwapPair tokens.
+declare_tests!(test_circuit_input_builder_swap_pair_swap, "SwapPair swap");
@@ -1,6 +1,6 @@
 #![cfg(feature = "circuits")]
 
-use bus_mapping::circuit_input_builder::BuilderClient;
+use bus_mapping::circuit_input_builder::{BuilderClient, CircuitsParams};
 use bus_mapping::operation::OperationContainer;
 use halo2_proofs::dev::MockProver;
 use integration_tests::{get_client, log_init, GenDataOutput};
@@ -19,7 +19,9 @@ lazy_static! {
 async fn test_evm_circuit_block(block_num: u64) {
     log::info!("test evm circuit, block number: {}", block_num);
     let cli = get_client();
-    let cli = BuilderClient::new(cli).await.unwrap();
+    let cli = BuilderClient::new(cli, CircuitsParams::default())
+        .await
+        .unwrap();
     let builder = cli.gen_inputs(block_num).await.unwrap();
 
     let block = block_convert(&builder.block, &builder.code_db);
@@ -32,7 +34,9 @@ async fn test_state_circuit_block(block_num: u64) {
 
     log::info!("test state circuit, block number: {}", block_num);
     let cli = get_client();
-    let cli = BuilderClient::new(cli).await.unwrap();
+    let cli = BuilderClient::new(cli, CircuitsParams::default())
+        .await
+        .unwrap();
     let builder = cli.gen_inputs(block_num).await.unwrap();
 
     // Generate state proof
@@ -53,7 +57,7 @@ async fn test_state_circuit_block(block_num: u64) {
     });
 
     let randomness = Fr::rand();
-    let circuit = StateCircuit::<Fr, { 1 << 16 }>::new(randomness, rw_map);
+    let circuit = StateCircuit::<Fr>::new(randomness, rw_map, 1 << 16);
     let power_of_randomness = circuit.instance();
 
     use halo2_proofs::pairing::bn256::Fr as Fp;
@@ -79,7 +83,6 @@ macro_rules! declare_tests {
     };
 }
 
-/*
 declare_tests!(
     test_evm_circuit_block_transfer_0,
     test_state_circuit_block_transfer_0,
@@ -95,7 +98,6 @@ declare_tests!(
     test_state_circuit_multiple_transfers_0,
     "Multiple transfers 0"
 );
-*/
 declare_tests!(
     test_evm_circuit_erc20_openzeppelin_transfer_fail,
     test_state_circuit_erc20_openzeppelin_transfer_fail,
@@ -111,3 +113,18 @@ declare_tests!(
     test_state_circuit_multiple_erc20_openzeppelin_transfers,
     "Multiple ERC20 OpenZeppelin transfers"
 );
+declare_tests!(
+    test_evm_circuit_deploy_swap_pair,
+    test_state_circuit_deploy_swap_pair,
+    "Deploy SwapPair"
+);
+declare_tests!(
+    test_evm_circuit_swap_pair_add_liquidity,
+    test_state_circuit_swap_pair_add_liquidity,
+    "SwapPair add liquidity"
+);
+declare_tests!(
+    test_evm_circuit_swap_pair_swap,
+    test_state_circuit_swap_pair_swap,
+    "SwapPair swap"
+);
@@ -1,6 +1,7 @@
 #![cfg(feature = "rpc")]
 
-use eth_types::{StorageProof, Word};
+use bus_mapping::rpc::GethLoggerConfig;
+use eth_types::{evm_types::Storage, StorageProof, Word};
 use integration_tests::{get_client, CompiledContract, GenDataOutput, CHAIN_ID, CONTRACTS_PATH};
 use lazy_static::lazy_static;
 use pretty_assertions::assert_eq;
@@ -18,6 +19,13 @@ async fn test_get_chain_id() {
     assert_eq!(CHAIN_ID, chain_id);
 }
 
+#[tokio::test]
+async fn test_get_chain_config() {
+    let cli = get_client();
+    let chain_config = cli.get_chain_config().await.unwrap();
+    assert_eq!(CHAIN_ID, chain_config.chain_id);
+}
+
 #[tokio::test]
 async fn test_get_coinbase() {
     let cli = get_client();
@@ -40,18 +48,57 @@ async fn test_get_block_by_number_by_hash() {
     assert_eq!(block_by_num.transactions[0].to, Some(GEN_DATA.wallets[0]));
 }
 
+#[tokio::test]
+async fn test_get_history() {
+    let cli = get_client();
+    let block = cli.get_block_by_number(3.into()).await.unwrap();
+    let history = cli.get_history(3, 2).await.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].number, Some(1.into()));
+    assert_eq!(history[1].hash, Some(block.parent_hash));
+    assert_eq!(history[1].parent_hash, history[0].hash.unwrap());
+}
+
 #[tokio::test]
 async fn test_trace_block_by_number_by_hash() {
     let block_num = GEN_DATA.deployments.get("Greeter").unwrap().0;
 
     let cli = get_client();
     let block = cli.get_block_by_number(block_num.into()).await.unwrap();
-    let trace_by_number = cli.trace_block_by_number(block_num.into()).await.unwrap();
-    let trace_by_hash = cli.trace_block_by_hash(block.hash.unwrap()).await.unwrap();
+    let cfg = GethLoggerConfig::default();
+    let trace_by_number = cli
+        .trace_block_by_number(block_num.into(), &cfg)
+        .await
+        .unwrap();
+    let trace_by_hash = cli
+        .trace_block_by_hash(block.hash.unwrap(), &cfg)
+        .await
+        .unwrap();
     assert_eq!(trace_by_number, trace_by_hash);
     assert!(!trace_by_number[0].struct_logs.is_empty())
 }
 
+#[tokio::test]
+async fn test_trace_block_by_number_without_stack_and_storage() {
+    let block_num = GEN_DATA.deployments.get("Greeter").unwrap().0;
+
+    let cli = get_client();
+    let cfg = GethLoggerConfig {
+        disable_stack: true,
+        disable_storage: true,
+        ..GethLoggerConfig::default()
+    };
+    let trace = cli
+        .trace_block_by_number(block_num.into(), &cfg)
+        .await
+        .unwrap();
+    assert!(!trace[0].struct_logs.is_empty());
+    assert!(trace[0]
+        .struct_logs
+        .iter()
+        .all(|step| step.stack.0.is_empty() && step.storage == Storage::empty()));
+}
+
 #[tokio::test]
 async fn test_get_contract_code() {
     let contract_name = "Greeter";
@@ -1,3 +1,10 @@
+//! # Keccak256
+//!
+//! The keccak-f permutation circuit, with the plain and arithmetic models
+//! from which its tests are built.  The gates are generic over the `Field` of
+//! eth-types on the halo2_proofs backend of the workspace, so that they can be
+//! configured in the same constraint system as the other circuits over bn256.
+
 // Leave here until #105 uses all the functions that now are
 // just used in tests
 
@@ -183,7 +183,6 @@ mod tests {
     use halo2_proofs::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
     use itertools::Itertools;
     use pairing::bn256::Fr as Fp;
-    use pairing::group::ff::PrimeField;
     use pretty_assertions::assert_eq;
     use std::convert::TryInto;
     use std::marker::PhantomData;
@@ -198,10 +197,7 @@ mod tests {
             is_mixing: bool,
             _marker: PhantomData<F>,
         }
-        impl<F: Field> Circuit<F> for MyCircuit<F>
-        where
-            F: PrimeField<Repr = [u8; 32]>,
-        {
+        impl<F: Field> Circuit<F> for MyCircuit<F> {
             type Config = AbsorbConfig<F>;
             type FloorPlanner = SimpleFloorPlanner;
 
@@ -178,8 +178,8 @@ impl<F: Field> BaseConversionConfig<F> {
 mod tests {
     use super::*;
     use crate::arith_helpers::{convert_b2_to_b13, convert_b9_lane_to_b13};
-    use crate::gate_helpers::biguint_to_f;
     use crate::permutation::tables::{FromBase9TableConfig, FromBinaryTableConfig};
+    use eth_types::ToScalar;
     use halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner},
         dev::MockProver,
@@ -288,7 +288,7 @@ mod tests {
         let input = 12345678u64;
         let circuit = MyCircuit::<Fp> {
             input_b2_lane: Fp::from(input),
-            output_b13_lane: biguint_to_f::<Fp>(&convert_b2_to_b13(input)),
+            output_b13_lane: convert_b2_to_b13(input).to_scalar().unwrap(),
         };
         let k = 17;
 
@@ -406,8 +406,8 @@ mod tests {
         let input = BigUint::parse_bytes(b"02939a42ef593e37757abe328e9e409e75dcd76cf1b3427bc3", 16)
             .unwrap();
         let circuit = MyCircuit::<Fp> {
-            input_lane: biguint_to_f::<Fp>(&input),
-            output_lane: biguint_to_f::<Fp>(&convert_b9_lane_to_b13(input)),
+            input_lane: input.to_scalar().unwrap(),
+            output_lane: convert_b9_lane_to_b13(input).to_scalar().unwrap(),
         };
         let k = 16;
         let prover = MockProver::<Fp>::run(k, &circuit, vec![]).unwrap();
@@ -551,7 +551,7 @@ mod tests {
             .unwrap();
         let out_state: [Fp; 25] = in_state_flat
             .iter()
-            .map(|&x| biguint_to_f::<Fp>(&convert_b2_to_b13(*x)))
+            .map(|&x| ToScalar::<Fp>::to_scalar(&convert_b2_to_b13(*x)).unwrap())
             .collect::<Vec<_>>()
             .try_into()
             .unwrap();
@@ -285,7 +285,7 @@ impl<F: Field> KeccakFConfig<F> {
 mod tests {
     use super::*;
     use crate::common::{State, NEXT_INPUTS_LANES};
-    use crate::gate_helpers::biguint_to_f;
+    use eth_types::ToScalar;
     use halo2_proofs::circuit::Layouter;
     use halo2_proofs::pairing::bn256::Fr as Fp;
     use halo2_proofs::plonk::{ConstraintSystem, Error};
@@ -381,7 +381,7 @@ mod tests {
         // Generate in_state as `[Fp;25]`
         let mut in_state_fp: [Fp; 25] = [Fp::zero(); 25];
         for (x, y) in (0..5).cartesian_product(0..5) {
-            in_state_fp[5 * x + y] = biguint_to_f(&convert_b2_to_b13(in_state[x][y]));
+            in_state_fp[5 * x + y] = convert_b2_to_b13(in_state[x][y]).to_scalar().unwrap();
             in_state_biguint[(x, y)] = convert_b2_to_b13(in_state[x][y]);
         }
 
@@ -1,7 +1,6 @@
 use crate::arith_helpers::{convert_b2_to_b13, convert_b2_to_b9, A4};
 use crate::common::{PERMUTATION, ROUND_CONSTANTS};
-use crate::gate_helpers::biguint_to_f;
-use eth_types::Field;
+use eth_types::{Field, ToScalar};
 use itertools::Itertools;
 use std::convert::TryInto;
 
@@ -13,14 +12,15 @@ pub struct IotaConstants<F> {
 
 impl<F: Field> Default for IotaConstants<F> {
     fn default() -> Self {
-        let round_constant_b13 =
-            biguint_to_f::<F>(&convert_b2_to_b13(ROUND_CONSTANTS[PERMUTATION - 1]));
+        let round_constant_b13: F = convert_b2_to_b13(ROUND_CONSTANTS[PERMUTATION - 1])
+            .to_scalar()
+            .unwrap();
 
         let a4_times_round_constants_b9: [F; 24] = ROUND_CONSTANTS
             .iter()
             .map(|&x| {
                 let constant = A4 * convert_b2_to_b9(x);
-                biguint_to_f::<F>(&constant)
+                ToScalar::<F>::to_scalar(&constant).unwrap()
             })
             .collect_vec()
             .try_into()
@@ -96,8 +96,8 @@ mod tests {
     use super::*;
     use crate::arith_helpers::*;
     use crate::common::*;
-    use crate::gate_helpers::biguint_to_f;
     use crate::keccak_arith::*;
+    use eth_types::ToScalar;
     use halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner},
         dev::MockProver,
@@ -255,12 +255,12 @@ mod tests {
         }
         let s0_arith = KeccakFArith::theta(&in_biguint);
         for (x, y) in (0..5).cartesian_product(0..5) {
-            in_state[5 * x + y] = biguint_to_f(&s0_arith[(x, y)]);
+            in_state[5 * x + y] = s0_arith[(x, y)].to_scalar().unwrap();
         }
         let s1_arith = KeccakFArith::rho(&s0_arith);
         let mut out_state: [Fp; 25] = [Fp::zero(); 25];
         for (x, y) in (0..5).cartesian_product(0..5) {
-            out_state[5 * x + y] = biguint_to_f(&s1_arith[(x, y)]);
+            out_state[5 * x + y] = s1_arith[(x, y)].to_scalar().unwrap();
         }
         let circuit = MyCircuit::<Fp> {
             in_state,
@@ -106,13 +106,13 @@
 //! detector 170 and fail the final sum check.
 use crate::arith_helpers::*;
 use crate::common::ROTATION_CONSTANTS;
-use crate::gate_helpers::{biguint_to_f, f_to_biguint};
+use crate::gate_helpers::f_to_biguint;
 use crate::permutation::{
     generic::GenericConfig,
     rho_helpers::*,
     tables::{Base13toBase9TableConfig, StackableTable},
 };
-use eth_types::Field;
+use eth_types::{Field, ToScalar};
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter},
     plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Selector},
@@ -209,18 +209,18 @@ impl<F: Field> LaneRotateConversionConfig<F> {
                             || format!("Input Coef {}", chunk_idx),
                             self.input_coef,
                             offset,
-                            || Ok(biguint_to_f::<F>(&conv.input.coef)),
+                            || Ok(ToScalar::<F>::to_scalar(&conv.input.coef).unwrap()),
                         )?;
                         input_coefs.push(input_coef);
-                        input_pobs.push(biguint_to_f::<F>(&conv.input.power_of_base));
+                        input_pobs.push(conv.input.power_of_base.to_scalar().unwrap());
                         let output_coef = region.assign_advice(
                             || "Output Coef",
                             self.output_coef,
                             offset,
-                            || Ok(biguint_to_f::<F>(&conv.output.coef)),
+                            || Ok(ToScalar::<F>::to_scalar(&conv.output.coef).unwrap()),
                         )?;
                         output_coefs.push(output_coef);
-                        output_pobs.push(biguint_to_f::<F>(&conv.output.power_of_base));
+                        output_pobs.push(conv.output.power_of_base.to_scalar().unwrap());
 
                         let od = region.assign_advice(
                             || "Overflow detector",
@@ -100,9 +100,8 @@ impl<F: Field> ThetaConfig<F> {
 mod tests {
     use super::*;
     use crate::common::*;
-    use crate::gate_helpers::biguint_to_f;
     use crate::keccak_arith::*;
-    use eth_types::Field;
+    use eth_types::{Field, ToScalar};
     use halo2_proofs::pairing::bn256::Fr as Fp;
     use halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner},
@@ -190,12 +189,12 @@ mod tests {
 
         for (x, y) in (0..5).cartesian_product(0..5) {
             in_biguint[(x, y)] = convert_b2_to_b13(input1[x][y]);
-            in_state[5 * x + y] = biguint_to_f(&in_biguint[(x, y)]);
+            in_state[5 * x + y] = in_biguint[(x, y)].to_scalar().unwrap();
         }
         let s1_arith = KeccakFArith::theta(&in_biguint);
         let mut out_state: [Fp; 25] = [Fp::zero(); 25];
         for (x, y) in (0..5).cartesian_product(0..5) {
-            out_state[5 * x + y] = biguint_to_f(&s1_arith[(x, y)]);
+            out_state[5 * x + y] = s1_arith[(x, y)].to_scalar().unwrap();
         }
 
         let circuit = MyCircuit::<Fp> {
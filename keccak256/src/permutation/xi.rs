@@ -103,8 +103,8 @@ mod tests {
     use super::*;
     use crate::arith_helpers::*;
     use crate::common::*;
-    use crate::gate_helpers::biguint_to_f;
     use crate::keccak_arith::*;
+    use eth_types::ToScalar;
     use halo2_proofs::circuit::Layouter;
     use halo2_proofs::pairing::bn256::Fr as Fp;
     use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error};
@@ -190,12 +190,12 @@ mod tests {
 
         for (x, y) in (0..5).cartesian_product(0..5) {
             in_biguint[(x, y)] = convert_b2_to_b9(input1[x][y]);
-            in_state[5 * x + y] = biguint_to_f(&in_biguint[(x, y)]);
+            in_state[5 * x + y] = in_biguint[(x, y)].to_scalar().unwrap();
         }
         let s1_arith = KeccakFArith::xi(&in_biguint);
         let mut out_state: [Fp; 25] = [Fp::zero(); 25];
         for (x, y) in (0..5).cartesian_product(0..5) {
-            out_state[5 * x + y] = biguint_to_f(&s1_arith[(x, y)]);
+            out_state[5 * x + y] = s1_arith[(x, y)].to_scalar().unwrap();
         }
         let circuit = MyCircuit::<Fp> {
             in_state,
@@ -6,7 +6,7 @@ use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Method, Request, Response, Server, StatusCode};
 use std::env::var;
 
-use prover::shared_state::SharedState;
+use prover::shared_state::{SharedState, TaskLimits};
 use prover::structs::*;
 
 /// sets default headers for CORS requests
@@ -162,6 +162,45 @@ async fn handle_method(
                 }
             }
         }
+        // enqueues a task for computing the proofs of a block and returns its
+        // identifier.
+        "prove_block" => {
+            let options = params.get(0).ok_or("expected struct ProofRequestOptions")?;
+            let options: ProofRequestOptions =
+                serde_json::from_value(options.to_owned()).map_err(|e| e.to_string())?;
+            let id = shared_state.enqueue(&options).await?;
+
+            Ok(serde_json::json!({ "id": id }))
+        }
+        // returns the status of a task, with its proofs if it's completed.
+        "get_proof_status" => {
+            let id = task_id(params)?;
+            let status = shared_state
+                .status(id)
+                .await
+                .ok_or_else(|| format!("unknown task {}", id))?;
+
+            Ok(serde_json::to_value(status).unwrap())
+        }
+        // returns the verifying keys of the proofs of a completed task, by
+        // circuit.
+        "get_vk" => {
+            let id = task_id(params)?;
+            let status = shared_state
+                .status(id)
+                .await
+                .ok_or_else(|| format!("unknown task {}", id))?;
+            let proofs = status
+                .result
+                .ok_or_else(|| format!("task {} is {:?}", id, status.status))?;
+            let vks: serde_json::Map<String, serde_json::Value> = proofs
+                .vks
+                .into_iter()
+                .map(|(circuit, vk)| (circuit, serde_json::to_value(vk).unwrap()))
+                .collect();
+
+            Ok(vks.into())
+        }
         // TODO/TBD: add method to only return the witnesses for a block.
         //  block table, tx table, etc...
         //
@@ -195,11 +234,35 @@ async fn handle_method(
     }
 }
 
+fn task_id(params: &[serde_json::Value]) -> Result<u64, String> {
+    params
+        .get(0)
+        .and_then(|id| id.as_u64())
+        .ok_or_else(|| "expected a task id".to_string())
+}
+
+/// Returns the value of the env var `name` or `default` if it's not set.
+fn var_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    var(name).map_or(default, |value| {
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("Cannot parse {} env var", name))
+    })
+}
+
 /// This command starts a http/json-rpc server and serves proof oriented
 /// methods. Required environment variables:
 /// - BIND - the interface address + port combination to accept connections on
 ///   `[::]:1234`
-/// - PARAMS_PATH - a path to a file generated with the gen_params tool
+///
+/// Optional environment variables:
+/// - MAX_RUNNING_TASKS - the number of proofs computed at the same time, 1 by
+///   default
+/// - MAX_QUEUED_TASKS - the number of tasks waiting to be computed above which
+///   new tasks are rejected, 64 by default
+///
+/// The parameter file of each proof is given in its request, and generated
+/// with the gen_params tool.
 #[tokio::main]
 async fn main() {
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
@@ -208,7 +271,11 @@ async fn main() {
         .expect("BIND env var")
         .parse::<std::net::SocketAddr>()
         .expect("valid socket address");
-    let shared_state = SharedState::new();
+    let default_limits = TaskLimits::default();
+    let shared_state = SharedState::with_limits(TaskLimits {
+        max_running: var_or("MAX_RUNNING_TASKS", default_limits.max_running),
+        max_queued: var_or("MAX_QUEUED_TASKS", default_limits.max_queued),
+    });
 
     {
         // start the http server
@@ -0,0 +1,125 @@
+use clap::Parser;
+use env_logger::Env;
+use halo2_proofs::pairing::bn256::G1Affine;
+use halo2_proofs::poly::commitment::Params;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use prover::compute_proof::{circuits_params, fit_circuits_params, gen_inputs, prove_block};
+use prover::structs::ProofMode;
+
+/// Proves a block: builds the inputs of the circuits from the traces of a
+/// geth node, runs the circuits in the chosen mode and writes, for each
+/// circuit, its proof with its public inputs in the versioned format, to
+/// `<out-dir>/block_<block>_<circuit>.proof` in binary and to
+/// `<out-dir>/block_<block>_<circuit>.proof.json` in JSON.
+#[derive(Parser, Debug)]
+#[clap(name = "zkevm-prover")]
+struct Args {
+    /// A geth http rpc that supports the debug namespace
+    #[clap(long)]
+    rpc_url: String,
+
+    /// The number of the block to prove
+    #[clap(long)]
+    block: u64,
+
+    /// A path to a file generated with the gen_params tool, not needed in the
+    /// mock mode
+    #[clap(long)]
+    params_path: Option<PathBuf>,
+
+    /// How the circuits are run: mock, real or aggregate
+    #[clap(long, default_value = "real")]
+    mode: ProofMode,
+
+    /// The directory of the proofs and public inputs
+    #[clap(long, default_value = ".")]
+    out_dir: PathBuf,
+
+    /// A directory in which the verifying keys are cached, so that they're
+    /// only generated by the first run for a circuit shape
+    #[clap(long)]
+    keys_dir: Option<PathBuf>,
+
+    /// Reduce the capacities of the circuits to the content of the block, so
+    /// that the super circuit takes its minimum degree, with a verifying key
+    /// specific to the block
+    #[clap(long)]
+    fit_params: bool,
+
+    /// A path to record the spans of the keygen, the synthesis and the proving
+    /// in the chrome trace format
+    #[cfg(feature = "chrome-trace")]
+    #[clap(long)]
+    trace_path: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+    let args = Args::parse();
+    #[cfg(feature = "chrome-trace")]
+    let _trace_guard = args
+        .trace_path
+        .as_deref()
+        .map(prover::trace::init_chrome_trace);
+    let time_started = Instant::now();
+
+    // load polynomial commitment parameters
+    let params: Option<Params<G1Affine>> = args.params_path.as_ref().map(|params_path| {
+        let time_started = Instant::now();
+        let params_fs = File::open(params_path).expect("couldn't open params");
+        let params =
+            Params::read::<_>(&mut BufReader::new(params_fs)).expect("Failed to read params");
+        log::info!(
+            "params of degree {} loaded in {:?}",
+            params.k,
+            time_started.elapsed()
+        );
+        params
+    });
+
+    let mut builder = gen_inputs(args.block, &args.rpc_url, circuits_params())
+        .await
+        .expect("gen_inputs");
+    if args.fit_params {
+        fit_circuits_params(&mut builder).expect("fit the circuits params");
+    }
+    let proofs = prove_block(
+        params.as_ref(),
+        args.mode,
+        &builder,
+        args.keys_dir.as_deref(),
+    )
+    .expect("prove_block");
+
+    fs::create_dir_all(&args.out_dir).expect("create the output directory");
+    for proof in proofs.iter() {
+        if args.mode != ProofMode::Mock {
+            let versioned = proof.versioned(&builder.block.circuits_params);
+            for extension in ["proof", "proof.json"] {
+                let path = args.out_dir.join(format!(
+                    "block_{}_{}.{}",
+                    args.block, proof.circuit, extension
+                ));
+                versioned.write(&path).expect("write the proof");
+            }
+        }
+        log::info!(
+            "{} circuit of degree {} done in {}ms",
+            proof.circuit,
+            proof.k,
+            proof.duration
+        );
+    }
+
+    log::info!(
+        "block {} proven in the {} mode in {:?}",
+        args.block,
+        args.mode,
+        time_started.elapsed()
+    );
+}
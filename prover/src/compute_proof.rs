@@ -1,7 +1,8 @@
-use bus_mapping::circuit_input_builder::BuilderClient;
-use bus_mapping::rpc::GethClient;
-use ethers_providers::Http;
+use bus_mapping::circuit_input_builder::{BuilderClient, CircuitInputBuilder, CircuitsParams};
+use bus_mapping::rpc::{GethClient, HttpBatchProvider};
+use eth_types::evm_types::{Hardfork, HardforkSpec, LondonSpec};
 use halo2_proofs::{
+    dev::MockProver,
     pairing::bn256::{Fr, G1Affine},
     plonk::*,
     poly::commitment::Params,
@@ -10,16 +11,338 @@ use halo2_proofs::{
 use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
 
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 use strum::IntoEnumIterator;
+use tracing::info_span;
+use verifier::{
+    block_randomness,
+    key_format::{read_vk, write_vk, KeyHeader},
+    BLOCK_CIRCUIT_NAME, MAX_WITHDRAWALS,
+};
 use zkevm_circuits::evm_circuit::{
-    table::FixedTableTag, test::TestCircuit, witness::block_convert,
+    table::FixedTableTag,
+    test::{test_circuit_instance, test_circuit_k, TestCircuit},
+    witness::block_convert,
 };
 use zkevm_circuits::state_circuit::StateCircuit;
+use zkevm_circuits::super_circuit::{min_k, SuperCircuit};
+
+use crate::structs::{BlockProofs, CircuitProof, ProofMode, ProofRequestOptions, Proofs};
+
+/// The circuit of the block proofs, for the London blocks.
+pub type BlockCircuit = SuperCircuit<Fr, LondonSpec, MAX_WITHDRAWALS>;
+
+lazy_static! {
+    /// The proving key of the last proof of each circuit, with the bytes of its
+    /// verifying key.  The proving keys can't be serialized with the halo2
+    /// version of the workspace, so they're only reused within the process.
+    static ref PROVING_KEYS: Mutex<HashMap<String, (Vec<u8>, Arc<ProvingKey<G1Affine>>)>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Returns the capacities of the circuits proven for a block.
+pub fn circuits_params() -> CircuitsParams {
+    CircuitsParams {
+        max_rws: 1 << 16,
+        ..CircuitsParams::default()
+    }
+}
+
+/// Reduces the capacities of the circuits of the block built by `builder` to
+/// its content, with which the super circuit takes its minimum degree, at the
+/// cost of a verifying key specific to the block.
+pub fn fit_circuits_params(
+    builder: &mut CircuitInputBuilder,
+) -> Result<(), Box<dyn std::error::Error>> {
+    builder.block.circuits_params =
+        BlockCircuit::params_for_block(&builder.block, &builder.code_db)?;
+    log::info!(
+        "circuits params fitted to the block: {:?}",
+        builder.block.circuits_params
+    );
+    Ok(())
+}
+
+/// Gathers debug trace(s) from `rpc_url` for block `block_num` and builds the
+/// inputs of the circuits with `circuits_params`.
+/// Expects a go-ethereum node with debug & archive capabilities on `rpc_url`.
+pub async fn gen_inputs(
+    block_num: u64,
+    rpc_url: &str,
+    circuits_params: CircuitsParams,
+) -> Result<CircuitInputBuilder, Box<dyn std::error::Error>> {
+    let time_started = Instant::now();
+    let url = HttpBatchProvider::from_str(rpc_url)?;
+    let geth_client = GethClient::new(url);
+    let builder = BuilderClient::new(geth_client, circuits_params).await?;
+    let builder = builder.gen_inputs(block_num).await?;
+    log::info!(
+        "block {}: inputs built in {:?}",
+        block_num,
+        time_started.elapsed()
+    );
+
+    Ok(builder)
+}
+
+/// Runs `circuit` with `instance` in the MockProver with `2^k` rows.
+fn mock_circuit<C: Circuit<Fr>>(
+    name: &str,
+    k: u32,
+    circuit: C,
+    instance: Vec<Vec<Fr>>,
+) -> Result<CircuitProof, Box<dyn std::error::Error>> {
+    let _span = info_span!("mock", circuit = name, k).entered();
+    let time_started = Instant::now();
+    let prover = MockProver::<Fr>::run(k, &circuit, instance.clone())?;
+    prover.verify().map_err(|failures| {
+        for failure in failures.iter() {
+            log::error!("{} circuit: {}", name, failure);
+        }
+        format!("{} circuit: {} failures", name, failures.len())
+    })?;
+    log::info!("{} circuit: verified in {:?}", name, time_started.elapsed());
+
+    Ok(CircuitProof {
+        circuit: name.to_string(),
+        k,
+        proof: vec![].into(),
+        vk: vec![].into(),
+        instance,
+        duration: time_started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Returns the verifying key of `circuit` with `params`, proving the blocks of
+/// `hardfork`, read from `<keys_dir>/<name>_<k>.vk` if it was written there
+/// for the same circuit shape, and generated and written there otherwise.
+fn load_or_keygen_vk<C: Circuit<Fr>>(
+    params: &Params<G1Affine>,
+    name: &str,
+    circuit: &C,
+    circuits_params: &CircuitsParams,
+    hardfork: Hardfork,
+    keys_dir: Option<&Path>,
+) -> Result<VerifyingKey<G1Affine>, Box<dyn std::error::Error>> {
+    let keygen = || info_span!("keygen_vk", circuit = name).in_scope(|| keygen_vk(params, circuit));
+    let keys_dir = match keys_dir {
+        Some(keys_dir) => keys_dir,
+        None => return Ok(keygen()?),
+    };
+    let header = KeyHeader::new(name, params.k, circuits_params, hardfork);
+    let path = keys_dir.join(format!("{}_{}.vk", name, params.k));
+    if path.exists() {
+        let time_started = Instant::now();
+        match read_vk::<_, C>(&mut BufReader::new(File::open(&path)?), &header, params) {
+            Ok(vk) => {
+                log::info!(
+                    "{} circuit: verifying key read from {} in {:?}",
+                    name,
+                    path.display(),
+                    time_started.elapsed()
+                );
+                return Ok(vk);
+            }
+            Err(err) => log::warn!("{} circuit: invalid {}: {}", name, path.display(), err),
+        }
+    }
+
+    let vk = keygen()?;
+    fs::create_dir_all(keys_dir)?;
+    write_vk(&mut BufWriter::new(File::create(&path)?), &header, &vk)?;
+    Ok(vk)
+}
+
+/// Creates the proof of `circuit` with `instance` and `params`, proving the
+/// blocks of `hardfork`, with the verifying key cached in `keys_dir`.
+fn prove_circuit<C: Circuit<Fr>>(
+    params: &Params<G1Affine>,
+    name: &str,
+    circuit: C,
+    instance: Vec<Vec<Fr>>,
+    circuits_params: &CircuitsParams,
+    hardfork: Hardfork,
+    keys_dir: Option<&Path>,
+) -> Result<CircuitProof, Box<dyn std::error::Error>> {
+    let vk = load_or_keygen_vk(params, name, &circuit, circuits_params, hardfork, keys_dir)?;
+    prove_circuit_with_vk(params, name, vk, circuit, instance)
+}
+
+/// Returns the proving key of `circuit` with `params` and its verifying key
+/// `vk` of bytes `vk_bytes`, which is the one of the previous proof of the
+/// circuit `name` in this process if it had the same verifying key.
+fn proving_key<C: Circuit<Fr>>(
+    params: &Params<G1Affine>,
+    name: &str,
+    vk: VerifyingKey<G1Affine>,
+    vk_bytes: &[u8],
+    circuit: &C,
+) -> Result<Arc<ProvingKey<G1Affine>>, Error> {
+    if let Some((cached_vk_bytes, pk)) = PROVING_KEYS.lock().unwrap().get(name) {
+        if cached_vk_bytes == vk_bytes {
+            log::info!("{} circuit: proving key reused", name);
+            return Ok(pk.clone());
+        }
+    }
+    let pk = Arc::new(info_span!("keygen_pk").in_scope(|| keygen_pk(params, vk, circuit))?);
+    PROVING_KEYS
+        .lock()
+        .unwrap()
+        .insert(name.to_string(), (vk_bytes.to_vec(), pk.clone()));
+    Ok(pk)
+}
+
+/// Creates the proof of `circuit` with `instance`, `params` and its verifying
+/// key `vk`.
+fn prove_circuit_with_vk<C: Circuit<Fr>>(
+    params: &Params<G1Affine>,
+    name: &str,
+    vk: VerifyingKey<G1Affine>,
+    circuit: C,
+    instance: Vec<Vec<Fr>>,
+) -> Result<CircuitProof, Box<dyn std::error::Error>> {
+    let _span = info_span!("prove", circuit = name, k = params.k).entered();
+    let time_started = Instant::now();
+
+    let mut vk_bytes = vec![];
+    vk.write(&mut vk_bytes)?;
+    let pk = proving_key(params, name, vk, &vk_bytes, &circuit)?;
+    log::info!("{} circuit: keys in {:?}", name, time_started.elapsed());
+
+    // Create randomness
+    let rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
 
-use crate::structs::Proofs;
+    // create a proof
+    let time_proof = Instant::now();
+    let instance_slices = instance.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    info_span!("create_proof").in_scope(|| {
+        create_proof(
+            params,
+            &*pk,
+            &[circuit],
+            &[&instance_slices],
+            rng,
+            &mut transcript,
+        )
+    })?;
+    let proof = transcript.finalize();
+    log::info!("{} circuit: proof in {:?}", name, time_proof.elapsed());
+
+    Ok(CircuitProof {
+        circuit: name.to_string(),
+        k: params.k,
+        proof: proof.into(),
+        vk: vk_bytes.into(),
+        instance,
+        duration: time_started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Runs the circuits of the block built by `builder` in `mode`, with `params`
+/// created via the `gen_params` tool.  The mock mode doesn't need `params`,
+/// and runs each circuit with its minimum number of rows if they are missing.
+/// The verifying keys are cached in `keys_dir`, if any.
+pub fn prove_block(
+    params: Option<&Params<G1Affine>>,
+    mode: ProofMode,
+    builder: &CircuitInputBuilder,
+    keys_dir: Option<&Path>,
+) -> Result<Vec<CircuitProof>, Box<dyn std::error::Error>> {
+    if mode != ProofMode::Mock && params.is_none() {
+        return Err(format!("the {} mode requires params", mode).into());
+    }
+
+    if let (ProofMode::Aggregate, Some(params)) = (mode, params) {
+        let min_k = BlockCircuit::min_k(&builder.block.circuits_params);
+        if params.k < min_k {
+            return Err(format!(
+                "the super circuit requires params of degree {}, got {}",
+                min_k, params.k
+            )
+            .into());
+        }
+        // The verifying key doesn't depend on the randomness, which is then
+        // derived from it and the public data as by the verifier.
+        let circuit = info_span!("witness")
+            .in_scope(|| BlockCircuit::build(&builder.block, &builder.code_db, Fr::from(1)));
+        let vk = load_or_keygen_vk(
+            params,
+            BLOCK_CIRCUIT_NAME,
+            &circuit,
+            &builder.block.circuits_params,
+            LondonSpec::HARDFORK,
+            keys_dir,
+        )?;
+        let randomness = block_randomness(
+            &vk,
+            &circuit.pi_circuit.public_data,
+            &builder.block.circuits_params,
+        );
+        let circuit = info_span!("witness")
+            .in_scope(|| BlockCircuit::build(&builder.block, &builder.code_db, randomness));
+        let instance = circuit.instance(params.k);
+        return Ok(vec![prove_circuit_with_vk(
+            params,
+            BLOCK_CIRCUIT_NAME,
+            vk,
+            circuit,
+            instance,
+        )?]);
+    }
+
+    let block = info_span!("witness").in_scope(|| block_convert(&builder.block, &builder.code_db));
+    let fixed_table_tags = FixedTableTag::iter().collect::<Vec<_>>();
+    let evm_k = params.map_or_else(|| test_circuit_k(&block, &fixed_table_tags), |p| p.k);
+    let evm_instance = test_circuit_instance(&block, evm_k);
+    let evm_circuit = TestCircuit::<Fr>::new(block.clone(), fixed_table_tags);
+    let state_circuit = StateCircuit::<Fr>::new_from_block(&block);
+    let state_instance = state_circuit.instance();
+
+    match (mode, params) {
+        (ProofMode::Real, Some(params)) => Ok(vec![
+            prove_circuit(
+                params,
+                "evm",
+                evm_circuit,
+                evm_instance,
+                &block.circuits_params,
+                block.hardfork,
+                keys_dir,
+            )?,
+            prove_circuit(
+                params,
+                "state",
+                state_circuit,
+                state_instance,
+                &block.circuits_params,
+                block.hardfork,
+                keys_dir,
+            )?,
+        ]),
+        _ => {
+            let state_k = params.map_or_else(
+                || min_k(StateCircuit::<Fr>::min_num_rows(&block.circuits_params)),
+                |p| p.k,
+            );
+            Ok(vec![
+                mock_circuit("evm", evm_k, evm_circuit, evm_instance)?,
+                mock_circuit("state", state_k, state_circuit, state_instance)?,
+            ])
+        }
+    }
+}
 
 /// Gathers debug trace(s) from `rpc_url` for block `block_num` with `params`
 /// created via the `gen_params` tool.
@@ -31,64 +354,72 @@ pub async fn compute_proof(
 ) -> Result<Proofs, Box<dyn std::error::Error>> {
     // request & build the inputs for the circuits
     let time_started = Instant::now();
-    let url = Http::from_str(rpc_url)?;
-    let geth_client = GethClient::new(url);
-    let builder = BuilderClient::new(geth_client).await?;
-    let builder = builder.gen_inputs(*block_num).await?;
+    let builder = gen_inputs(*block_num, rpc_url, circuits_params()).await?;
 
     // TODO: only {evm,state}_proof are implemented right now
-    let evm_proof;
-    let state_proof;
-    let block = block_convert(&builder.block, &builder.code_db);
-    {
-        // generate evm_circuit proof
-        let circuit = TestCircuit::<Fr>::new(block.clone(), FixedTableTag::iter().collect());
-
-        // TODO: can this be pre-generated to a file?
-        // related
-        // https://github.com/zcash/halo2/issues/443
-        // https://github.com/zcash/halo2/issues/449
-        let vk = keygen_vk(params, &circuit)?;
-        let pk = keygen_pk(params, vk, &circuit)?;
-
-        // Create randomness
-        let rng = XorShiftRng::from_seed([
-            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
-            0xbc, 0xe5,
-        ]);
-
-        // create a proof
-        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
-        create_proof(params, &pk, &[circuit], &[], rng, &mut transcript)?;
-        evm_proof = transcript.finalize();
-    }
-
-    {
-        // generate state_circuit proof
-        const N_ROWS: usize = 1 << 16;
-        let circuit = StateCircuit::<Fr, N_ROWS>::new(block.randomness, block.rws);
-
-        // TODO: same quest like in the first scope
-        let vk = keygen_vk(params, &circuit)?;
-        let pk = keygen_pk(params, vk, &circuit)?;
-
-        // Create randomness
-        let rng = XorShiftRng::from_seed([
-            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
-            0xbc, 0xe5,
-        ]);
-
-        // create a proof
-        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
-        create_proof(params, &pk, &[circuit], &[], rng, &mut transcript)?;
-        state_proof = transcript.finalize();
-    }
+    let mut proofs = prove_block(Some(params), ProofMode::Real, &builder, None)?.into_iter();
+    let (evm_proof, state_proof) = (proofs.next().unwrap(), proofs.next().unwrap());
 
     let ret = Proofs {
-        evm_proof: evm_proof.into(),
-        state_proof: state_proof.into(),
+        evm_proof: evm_proof.proof,
+        state_proof: state_proof.proof,
         duration: Instant::now().duration_since(time_started).as_millis() as u64,
     };
 
     Ok(ret)
 }
+
+/// Gathers debug trace(s) from `options.rpc` for block `options.block` and
+/// runs its circuits in `options.mode` with `params`, loaded from
+/// `options.param`.
+pub async fn compute_block_proofs(
+    params: Option<&Params<G1Affine>>,
+    options: &ProofRequestOptions,
+) -> Result<BlockProofs, Box<dyn std::error::Error>> {
+    let time_started = Instant::now();
+    let builder = gen_inputs(options.block, &options.rpc, circuits_params()).await?;
+    let proofs = prove_block(params, options.mode, &builder, None)?;
+
+    Ok(BlockProofs {
+        block: options.block,
+        mode: options.mode,
+        proofs: proofs
+            .iter()
+            .map(|proof| proof.versioned(&builder.block.circuits_params))
+            .collect(),
+        vks: proofs
+            .into_iter()
+            .map(|proof| (proof.circuit, proof.vk))
+            .collect(),
+        duration: time_started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::BlockCircuit;
+    use eth_types::{
+        public_data::{BlockPublicData, PublicData},
+        CircuitsParams,
+    };
+    use halo2_proofs::pairing::bn256::Fr;
+    use verifier::{block_instance, NUM_RANDOMNESS_POWERS};
+    use zkevm_circuits::tx_circuit::POW_RAND_SIZE;
+
+    #[test]
+    fn verifier_block_instance() {
+        assert_eq!(NUM_RANDOMNESS_POWERS, POW_RAND_SIZE);
+        let public_data = PublicData {
+            blocks: vec![BlockPublicData {
+                state_root: [1; 32].into(),
+                ..Default::default()
+            }],
+        };
+        let circuits_params = CircuitsParams::default();
+        let randomness = Fr::from(0x100);
+        assert_eq!(
+            block_instance(7, randomness, &public_data, &circuits_params),
+            BlockCircuit::public_instance(7, randomness, &public_data, &circuits_params)
+        );
+    }
+}
@@ -1,3 +1,6 @@
 pub mod compute_proof;
+pub mod proof_format;
 pub mod shared_state;
 pub mod structs;
+#[cfg(feature = "chrome-trace")]
+pub mod trace;
@@ -0,0 +1,3 @@
+//! The versioned serialization of the proofs, defined by the verifier crate.
+
+pub use verifier::proof_format::*;
@@ -7,19 +7,58 @@ use std::sync::Arc;
 
 use tokio::sync::Mutex;
 
-use crate::compute_proof::compute_proof;
-use crate::structs::{ProofRequestOptions, Proofs};
+use crate::compute_proof::compute_block_proofs;
+use crate::structs::{
+    BlockProofs, ProofMode, ProofRequestOptions, Proofs, TaskStatus, TaskStatusResponse,
+};
 
 #[derive(Debug, Clone)]
 pub struct ProofRequest {
+    /// the task identifier, returned by `enqueue`
+    pub id: u64,
     pub options: ProofRequestOptions,
-    pub result: Option<Result<Proofs, String>>,
+    /// whether the task is being computed
+    pub running: bool,
+    pub result: Option<Result<BlockProofs, String>>,
+}
+
+impl ProofRequest {
+    fn status(&self) -> TaskStatus {
+        match (&self.result, self.running) {
+            (Some(Ok(_)), _) => TaskStatus::Done,
+            (Some(Err(_)), _) => TaskStatus::Failed,
+            (None, true) => TaskStatus::Running,
+            (None, false) => TaskStatus::Queued,
+        }
+    }
+}
+
+/// Limits of the number of tasks.
+#[derive(Debug, Clone, Copy)]
+pub struct TaskLimits {
+    /// maximum number of tasks computed at the same time
+    pub max_running: u32,
+    /// maximum number of tasks waiting to be computed, above which new tasks
+    /// are rejected
+    pub max_queued: usize,
+}
+
+impl Default for TaskLimits {
+    fn default() -> Self {
+        Self {
+            max_running: 1,
+            max_queued: 64,
+        }
+    }
 }
 
 pub struct RwState {
     pub tasks: Vec<ProofRequest>,
     pub pending_tasks: u32,
     pub params_cache: HashMap<String, Arc<Params<G1Affine>>>,
+    pub limits: TaskLimits,
+    /// the identifier of the next task
+    pub next_id: u64,
 }
 
 #[derive(Clone)]
@@ -29,86 +68,127 @@ pub struct SharedState {
 
 impl SharedState {
     pub fn new() -> SharedState {
+        Self::with_limits(TaskLimits::default())
+    }
+
+    pub fn with_limits(limits: TaskLimits) -> SharedState {
         Self {
             rw: Arc::new(Mutex::new(RwState {
                 tasks: Vec::new(),
                 pending_tasks: 0,
                 params_cache: HashMap::new(),
+                limits,
+                next_id: 1,
             })),
         }
     }
 
-    /// Will return the result or error of the task if it's completed.
-    /// Otherwise enqueues the task and returns `None`.
+    /// Enqueues the task of `options`, unless it is already pending or
+    /// completed, and returns its identifier.
     /// `retry_if_error` enqueues the task again if it returned with an error
     /// before.
-    pub async fn get_or_enqueue(
-        &self,
-        options: &ProofRequestOptions,
-    ) -> Option<Result<Proofs, String>> {
+    /// Returns an error if the queue is full.
+    pub async fn enqueue(&self, options: &ProofRequestOptions) -> Result<u64, String> {
         let mut rw = self.rw.lock().await;
 
         // task already pending or completed?
-        let task = rw.tasks.iter_mut().find(|e| e.options == *options);
-
-        if task.is_some() {
-            let mut task = task.unwrap();
-
-            if task.result.is_some() {
-                if options.retry && task.result.as_ref().unwrap().is_err() {
-                    log::debug!("retrying: {:#?}", task);
-                    // will be a candidate in `duty_cycle` again
-                    task.result = None;
-                } else {
-                    log::debug!("completed: {:#?}", task);
-                    return task.result.clone();
-                }
-            } else {
-                log::debug!("pending: {:#?}", task);
-                return None;
+        if let Some(task) = rw.tasks.iter_mut().find(|e| e.options == *options) {
+            if options.retry && matches!(task.result, Some(Err(_))) {
+                log::debug!("retrying: {:#?}", task);
+                // will be a candidate in `duty_cycle` again
+                task.result = None;
             }
-        } else {
-            // enqueue the task
-            let task = ProofRequest {
-                options: options.clone(),
-                result: None,
-            };
-            log::debug!("enqueue: {:#?}", task);
-            rw.tasks.push(task);
+            return Ok(task.id);
+        }
+
+        let queued = rw
+            .tasks
+            .iter()
+            .filter(|e| e.status() == TaskStatus::Queued)
+            .count();
+        if queued >= rw.limits.max_queued {
+            return Err(format!("the queue is full with {} tasks", queued));
         }
 
-        None
+        // enqueue the task
+        let task = ProofRequest {
+            id: rw.next_id,
+            options: options.clone(),
+            running: false,
+            result: None,
+        };
+        log::debug!("enqueue: {:#?}", task);
+        rw.next_id += 1;
+        rw.tasks.push(task);
+
+        Ok(rw.next_id - 1)
+    }
+
+    /// Will return the result or error of the task if it's completed.
+    /// Otherwise enqueues the task and returns `None`.
+    pub async fn get_or_enqueue(
+        &self,
+        options: &ProofRequestOptions,
+    ) -> Option<Result<Proofs, String>> {
+        let id = match self.enqueue(options).await {
+            Ok(id) => id,
+            Err(err) => return Some(Err(err)),
+        };
+
+        self.status(id)
+            .await
+            .and_then(|status| match status.status {
+                TaskStatus::Done => status.result.as_ref().map(|proofs| Ok(proofs.into())),
+                TaskStatus::Failed => status.error.map(Err),
+                _ => None,
+            })
+    }
+
+    /// Returns the status of the task `id`, or `None` if it is unknown.
+    pub async fn status(&self, id: u64) -> Option<TaskStatusResponse> {
+        let rw = self.rw.lock().await;
+        let task = rw.tasks.iter().find(|e| e.id == id)?;
+
+        Some(TaskStatusResponse {
+            id,
+            status: task.status(),
+            result: task.result.clone().and_then(Result::ok),
+            error: task.result.clone().and_then(Result::err),
+        })
     }
 
     /// Checks if there is anything to do like:
     /// - records if a task completed
-    /// - starting a new task
-    /// Blocks until completion but releases the lock of `self.rw` in between.
+    /// - starting new tasks, up to `TaskLimits::max_running` at the same time
+    /// Doesn't wait for the completion of the started tasks.
     pub async fn duty_cycle(&self) {
         let mut rw = self.rw.lock().await;
 
-        if rw.pending_tasks > 0 {
-            // already computing
-            // TODO: can spawn multiple tasks if ever required
-            return;
-        }
-
-        // find a pending task
-        let pending_task = rw.tasks.iter().find(|&e| e.result.is_none());
-        if pending_task.is_none() {
-            // nothing to do
-            return;
-        }
+        while rw.pending_tasks < rw.limits.max_running {
+            // find a pending task
+            let pending_task = rw
+                .tasks
+                .iter_mut()
+                .find(|e| e.status() == TaskStatus::Queued);
+            let pending_task = match pending_task {
+                Some(pending_task) => pending_task,
+                // nothing to do
+                None => return,
+            };
 
-        // needs to be cloned because of long running tasks and
-        // the possibility that the task gets removed in the meantime
-        let mut pending_task = pending_task.unwrap().clone();
-        {
+            // needs to be cloned because of long running tasks and
+            // the possibility that the task gets removed in the meantime
+            pending_task.running = true;
+            let pending_task = pending_task.clone();
             rw.pending_tasks += 1;
             log::info!("compute_proof: {:#?}", pending_task);
-            drop(rw);
+
+            let self_copy = self.clone();
+            tokio::spawn(async move { self_copy.run_task(pending_task).await });
         }
+    }
 
+    async fn run_task(&self, mut pending_task: ProofRequest) {
         // Note: this catches any panics for the task itself but will not help in the
         // situation when the process get itself OOM killed, stack overflows etc.
         // This could be avoided by spawning a subprocess for the proof computation
@@ -116,28 +196,23 @@ impl SharedState {
 
         let pending_task_copy = pending_task.clone();
         let self_copy = self.clone();
-        let task_result: Result<Result<Proofs, String>, tokio::task::JoinError> =
+        let task_result: Result<Result<BlockProofs, String>, tokio::task::JoinError> =
             tokio::spawn(async move {
+                let options = &pending_task_copy.options;
                 // lazily load the file and cache it
-                let param = self_copy.load_param(&pending_task_copy.options.param).await;
-                let res = compute_proof(
-                    param.as_ref(),
-                    &pending_task_copy.options.block,
-                    &pending_task_copy.options.rpc,
-                )
-                .await;
-
-                if let Err(err) = res {
-                    // cast Error to string
-                    return Err(err.to_string());
-                }
-
-                Ok(res.unwrap())
+                let param = match options.mode {
+                    ProofMode::Mock if options.param.is_empty() => None,
+                    _ => Some(self_copy.load_param(&options.param).await),
+                };
+                let res = compute_block_proofs(param.as_deref(), options).await;
+
+                // cast Error to string
+                res.map_err(|err| err.to_string())
             })
             .await;
 
         // convert the JoinError to string - if applicable
-        let task_result: Result<Proofs, String> = match task_result {
+        let task_result: Result<BlockProofs, String> = match task_result {
             Err(err) => match err.is_panic() {
                 true => {
                     let panic = err.into_panic();
@@ -162,15 +237,14 @@ impl SharedState {
             let mut rw = self.rw.lock().await;
             rw.pending_tasks -= 1;
 
-            let task = rw
-                .tasks
-                .iter_mut()
-                .find(|e| e.options == pending_task.options);
+            let task = rw.tasks.iter_mut().find(|e| e.id == pending_task.id);
             if let Some(task) = task {
                 // found our task, update result
+                task.running = false;
                 task.result = Some(task_result);
             } else {
                 // task was already removed in the meantime, insert it again
+                pending_task.running = false;
                 pending_task.result = Some(task_result);
                 rw.tasks.push(pending_task);
             }
@@ -5,6 +5,83 @@ pub struct Proofs {
     pub duration: u64,
 }
 
+/// How the circuits of a block are run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProofMode {
+    /// Verify the witnesses of the evm and state circuits with the MockProver,
+    /// without creating proofs.
+    Mock,
+    /// Create a proof of the evm circuit and one of the state circuit.
+    Real,
+    /// Create a single proof of the super circuit, which gathers all the
+    /// circuits with their shared tables.
+    Aggregate,
+}
+
+impl std::str::FromStr for ProofMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mock" => Ok(Self::Mock),
+            "real" => Ok(Self::Real),
+            "aggregate" => Ok(Self::Aggregate),
+            _ => Err(format!("unknown proof mode {}", s)),
+        }
+    }
+}
+
+impl Default for ProofMode {
+    fn default() -> Self {
+        Self::Real
+    }
+}
+
+impl std::fmt::Display for ProofMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mock => write!(f, "mock"),
+            Self::Real => write!(f, "real"),
+            Self::Aggregate => write!(f, "aggregate"),
+        }
+    }
+}
+
+/// The proof of a circuit with its public inputs.
+#[derive(Debug, Clone)]
+pub struct CircuitProof {
+    /// name of the circuit
+    pub circuit: String,
+    /// the circuit has `2^k` rows
+    pub k: u32,
+    /// the proof, empty in the mock mode
+    pub proof: eth_types::Bytes,
+    /// the verifying key of the proof, empty in the mock mode
+    pub vk: eth_types::Bytes,
+    /// the instance columns
+    pub instance: Vec<Vec<halo2_proofs::pairing::bn256::Fr>>,
+    /// milliseconds spent on the circuit
+    pub duration: u64,
+}
+
+impl CircuitProof {
+    /// Returns the proof in the versioned serialization format, created with
+    /// the capacities `circuits_params`.
+    pub fn versioned(
+        &self,
+        circuits_params: &bus_mapping::circuit_input_builder::CircuitsParams,
+    ) -> crate::proof_format::VersionedProof {
+        crate::proof_format::VersionedProof::new(
+            &self.circuit,
+            self.k,
+            circuits_params,
+            self.proof.to_vec(),
+            self.instance.clone(),
+        )
+    }
+}
+
 #[derive(Debug, serde::Serialize)]
 pub struct JsonRpcError {
     pub code: i32,
@@ -40,12 +117,84 @@ pub struct ProofRequestOptions {
     pub rpc: String,
     /// retry proof computation if error
     pub retry: bool,
-    /// parameter file to use
+    /// parameter file to use, not needed in the mock mode
     pub param: String,
+    /// how the circuits are run, real by default
+    #[serde(default)]
+    pub mode: ProofMode,
 }
 
 impl PartialEq for ProofRequestOptions {
     fn eq(&self, other: &Self) -> bool {
-        self.block == other.block && self.rpc == other.rpc && self.param == other.param
+        self.block == other.block
+            && self.rpc == other.rpc
+            && self.param == other.param
+            && self.mode == other.mode
     }
 }
+
+/// The proofs of the circuits of a block.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlockProofs {
+    /// the block number
+    pub block: u64,
+    /// how the circuits were run
+    pub mode: ProofMode,
+    /// the proofs in the versioned format, one per circuit
+    pub proofs: Vec<crate::proof_format::VersionedProof>,
+    /// the verifying keys of the proofs, one per circuit
+    #[serde(skip)]
+    pub vks: Vec<(String, eth_types::Bytes)>,
+    /// milliseconds spent on the block
+    pub duration: u64,
+}
+
+impl BlockProofs {
+    /// Returns the proof of `circuit`, empty if it wasn't proven.
+    fn proof(&self, circuit: &str) -> eth_types::Bytes {
+        self.proofs
+            .iter()
+            .find(|proof| proof.circuit == circuit)
+            .map(|proof| proof.proof.clone().into())
+            .unwrap_or_default()
+    }
+}
+
+impl From<&BlockProofs> for Proofs {
+    fn from(block_proofs: &BlockProofs) -> Self {
+        Self {
+            state_proof: block_proofs.proof("state"),
+            evm_proof: block_proofs.proof("evm"),
+            duration: block_proofs.duration,
+        }
+    }
+}
+
+/// The state of a proof task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    /// waiting for a free slot
+    Queued,
+    /// being computed
+    Running,
+    /// completed with proofs
+    Done,
+    /// completed with an error
+    Failed,
+}
+
+/// The status of a proof task, returned by `get_proof_status`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TaskStatusResponse {
+    /// the task identifier
+    pub id: u64,
+    /// the state of the task
+    pub status: TaskStatus,
+    /// the proofs if the task is done
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<BlockProofs>,
+    /// the error if the task failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
@@ -0,0 +1,20 @@
+//! Export of the tracing spans of the prover in the chrome trace format.
+
+use std::path::Path;
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+use tracing_subscriber::prelude::*;
+
+/// Records the spans of the keygen, of the synthesis of each sub-circuit and
+/// of the proving to `path` in the chrome trace format, viewed in
+/// `chrome://tracing` or Perfetto.  The FFTs and the MSMs are recorded within
+/// the span of their proving step only, since the pinned halo2 doesn't emit
+/// spans of its own.  The trace is written when the returned guard is
+/// dropped.
+pub fn init_chrome_trace(path: &Path) -> FlushGuard {
+    let (chrome_layer, guard) = ChromeLayerBuilder::new()
+        .file(path)
+        .include_args(true)
+        .build();
+    tracing_subscriber::registry().with(chrome_layer).init();
+    guard
+}
@@ -0,0 +1,146 @@
+//! Configuration of the testool, read from a TOML file, which selects the
+//! circuits to verify and lists the tests to run or to skip.
+
+use crate::statetest::StateTest;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Circuits through which the witnesses of the tests are verified.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CircuitsConfig {
+    #[serde(default = "enabled")]
+    pub evm: bool,
+    #[serde(default = "enabled")]
+    pub state: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Default for CircuitsConfig {
+    fn default() -> Self {
+        Self {
+            evm: true,
+            state: true,
+        }
+    }
+}
+
+/// Tests skipped because their path contains one of `paths`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkipPaths {
+    pub desc: String,
+    pub paths: Vec<String>,
+}
+
+/// Tests skipped because their id starts with one of `ids`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkipTests {
+    pub desc: String,
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub circuits: CircuitsConfig,
+    /// If not empty, only the tests whose id starts with one of these are
+    /// run.
+    #[serde(default)]
+    pub allow_tests: Vec<String>,
+    #[serde(default)]
+    pub skip_paths: Vec<SkipPaths>,
+    #[serde(default)]
+    pub skip_tests: Vec<SkipTests>,
+}
+
+impl Config {
+    /// Reads the configuration from the TOML file at `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("reading config {}", path.display()))?;
+        toml::from_str(&source).with_context(|| format!("parsing config {}", path.display()))
+    }
+
+    /// Returns the reason for which the test is not run, if any.
+    pub fn skip_reason(&self, st: &StateTest) -> Option<&str> {
+        if !self.allow_tests.is_empty() && !self.allow_tests.iter().any(|id| st.id.starts_with(id))
+        {
+            return Some("not in allow_tests");
+        }
+        self.skip_paths
+            .iter()
+            .find(|skip| skip.paths.iter().any(|path| st.path.contains(path)))
+            .map(|skip| skip.desc.as_str())
+            .or_else(|| {
+                self.skip_tests
+                    .iter()
+                    .find(|skip| skip.ids.iter().any(|id| st.id.starts_with(id)))
+                    .map(|skip| skip.desc.as_str())
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::statetest::StateTest;
+    use eth_types::{Address, Bytes, Word};
+    use std::collections::HashMap;
+
+    fn state_test(path: &str, id: &str) -> StateTest {
+        StateTest {
+            path: path.to_string(),
+            id: id.to_string(),
+            env: Default::default(),
+            from: Address::zero(),
+            to: None,
+            gas_limit: 0,
+            gas_price: Word::zero(),
+            nonce: Word::zero(),
+            value: Word::zero(),
+            data: Bytes::default(),
+            pre: HashMap::new(),
+            result: HashMap::new(),
+            exception: false,
+        }
+    }
+
+    #[test]
+    fn config_skip_reason() -> Result<()> {
+        let config: Config = toml::from_str(
+            r#"
+            [circuits]
+            state = false
+
+            [[skip_paths]]
+            desc = "precompiles"
+            paths = ["stPreCompiledContracts"]
+
+            [[skip_tests]]
+            desc = "too big"
+            ids = ["loopExp"]
+            "#,
+        )?;
+        assert!(config.circuits.evm);
+        assert!(!config.circuits.state);
+
+        let test = state_test("stPreCompiledContracts/identity.json", "identity_d0_g0_v0");
+        assert_eq!(config.skip_reason(&test), Some("precompiles"));
+        let test = state_test("stExample/loopExp.json", "loopExp_d1_g0_v0");
+        assert_eq!(config.skip_reason(&test), Some("too big"));
+        let test = state_test("stExample/add11.json", "add11_d0_g0_v0");
+        assert_eq!(config.skip_reason(&test), None);
+
+        let config = Config {
+            allow_tests: vec!["add".to_string()],
+            ..config
+        };
+        assert_eq!(config.skip_reason(&test), None);
+        let test = state_test("stExample/mul.json", "mul_d0_g0_v0");
+        assert_eq!(config.skip_reason(&test), Some("not in allow_tests"));
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+//! Differential fuzzer, which runs random but structured programs through
+//! geth and through bus-mapping and the EVM circuit, and reports their
+//! divergences with a minimized reproducer.
+
+mod compare;
+mod generator;
+
+use crate::panic_message;
+use anyhow::{Context, Result};
+use compare::{compare, trace, Divergence};
+use eth_types::Word;
+use generator::{generate, Program};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use std::mem::discriminant;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+pub struct FuzzConfig {
+    /// Number of programs generated.
+    pub iterations: usize,
+    /// Seed of the generator, so that a run can be reproduced.
+    pub seed: u64,
+    /// Maximum number of snippets of a program.
+    pub max_snippets: usize,
+    /// Directory where the reproducers of the divergences are written.
+    pub out: PathBuf,
+}
+
+/// Runs the program, returning `None` if geth rejected it.
+fn run(program: &Program) -> Option<Result<(), Divergence>> {
+    let geth_data = trace(program)?;
+    Some(
+        catch_unwind(AssertUnwindSafe(|| compare(geth_data)))
+            .unwrap_or_else(|panic| Err(Divergence::Panic(panic_message(panic)))),
+    )
+}
+
+/// Returns the smallest program found by removing snippets and inputs of
+/// `program`, which still diverges in the same way.
+fn minimize(mut program: Program, mut divergence: Divergence) -> (Program, Divergence) {
+    let reproduces = |candidate: &Program, divergence: &mut Divergence| match run(candidate) {
+        Some(Err(found)) if discriminant(&found) == discriminant(divergence) => {
+            *divergence = found;
+            true
+        }
+        _ => false,
+    };
+
+    // Remove the snippets from the last one, as the latter ones depend on
+    // the results of the former ones left on the stack.
+    let mut idx = program.snippets.len();
+    while idx > 0 {
+        idx -= 1;
+        let mut candidate = program.clone();
+        candidate.snippets.remove(idx);
+        if reproduces(&candidate, &mut divergence) {
+            program = candidate;
+        }
+    }
+
+    for simplify in [
+        (|candidate: &mut Program| candidate.calldata.clear()) as fn(&mut Program),
+        |candidate| candidate.value = Word::zero(),
+    ] {
+        let mut candidate = program.clone();
+        simplify(&mut candidate);
+        if candidate != program && reproduces(&candidate, &mut divergence) {
+            program = candidate;
+        }
+    }
+
+    (program, divergence)
+}
+
+/// Runs the fuzzer, and returns the number of divergences found.
+pub fn fuzz(config: &FuzzConfig) -> Result<usize> {
+    std::fs::create_dir_all(&config.out)
+        .with_context(|| format!("creating {}", config.out.display()))?;
+
+    let mut rng = ChaCha20Rng::seed_from_u64(config.seed);
+    let mut divergences = 0;
+    for iteration in 0..config.iterations {
+        let program = generate(&mut rng, config.max_snippets);
+        let divergence = match run(&program) {
+            Some(Err(divergence)) => divergence,
+            // Programs rejected by geth, or on which both agree.
+            _ => continue,
+        };
+        divergences += 1;
+
+        let (program, divergence) = minimize(program, divergence);
+        log::error!(
+            "iteration {}: {:?} with the program\n{}",
+            iteration,
+            divergence,
+            program
+        );
+        let reproducer = serde_json::json!({
+            "seed": config.seed,
+            "iteration": iteration,
+            "divergence": divergence,
+            "bytecode": format!("0x{}", hex::encode(program.bytecode().to_vec())),
+            "program": program,
+        });
+        let path = config
+            .out
+            .join(format!("{}_{}.json", config.seed, iteration));
+        std::fs::write(&path, serde_json::to_string_pretty(&reproducer)?)
+            .with_context(|| format!("writing {}", path.display()))?;
+    }
+    log::info!(
+        "{} divergences in {} programs",
+        divergences,
+        config.iterations
+    );
+
+    Ok(divergences)
+}
@@ -0,0 +1,159 @@
+//! Comparison of the execution of a program by geth and by bus-mapping and
+//! the EVM circuit.
+
+use super::generator::Program;
+use bus_mapping::{
+    circuit_input_builder::{CircuitInputBuilder, ExecState},
+    mock::BlockData,
+    operation::{Target, TxReceiptField, RW},
+};
+use eth_types::{evm_types::OpcodeId, geth_types::GethData, GethExecTrace, Word};
+use mock::{
+    test_ctx::{helpers::account_0_code_account_1_no_code, TestContext},
+    MOCK_ACCOUNTS,
+};
+use serde::Serialize;
+use zkevm_circuits::evm_circuit::{test::run_test_circuit_complete_fixed_table, witness};
+
+/// A divergence between geth and bus-mapping or the EVM circuit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum Divergence {
+    /// The circuit input builder failed to handle the trace of geth.
+    CircuitInputBuilder(String),
+    /// The gas used by the transaction differs.
+    Gas { geth: u64, bus_mapping: u64 },
+    /// A stack write of a step differs from the stack of geth after it.
+    Stack {
+        step: usize,
+        opcode: OpcodeId,
+        address: usize,
+        geth: Option<Word>,
+        bus_mapping: Word,
+    },
+    /// A storage slot of the called contract differs after the transaction.
+    Storage {
+        key: Word,
+        geth: Word,
+        bus_mapping: Word,
+    },
+    /// The MockProver of the EVM circuit reported failures.
+    EvmCircuit(String),
+    /// bus-mapping or the EVM circuit panicked.
+    Panic(String),
+}
+
+/// Returns the trace of the program by geth, or `None` if geth rejected it.
+pub fn trace(program: &Program) -> Option<GethData> {
+    let ctx = TestContext::<2, 1>::new(
+        None,
+        account_0_code_account_1_no_code(program.bytecode()),
+        |mut txs, accs| {
+            txs[0]
+                .from(accs[1].address)
+                .to(accs[0].address)
+                .input(program.calldata.clone().into())
+                .value(program.value);
+        },
+        |block, _txs| block,
+    )
+    .ok()?;
+    Some(ctx.into())
+}
+
+fn check_gas(builder: &CircuitInputBuilder, trace: &GethExecTrace) -> Result<(), Divergence> {
+    let gas_used = builder
+        .block
+        .container
+        .tx_receipt
+        .iter()
+        .find(|op| op.op().field == TxReceiptField::CumulativeGasUsed)
+        .map(|op| op.op().value)
+        .unwrap_or_default();
+    if gas_used != trace.gas.0 {
+        return Err(Divergence::Gas {
+            geth: trace.gas.0,
+            bus_mapping: gas_used,
+        });
+    }
+    Ok(())
+}
+
+fn check_stack(builder: &CircuitInputBuilder, trace: &GethExecTrace) -> Result<(), Divergence> {
+    let steps = builder.block.txs()[0]
+        .steps()
+        .iter()
+        .filter(|step| matches!(step.exec_state, ExecState::Op(_)));
+    for (idx, step) in steps.enumerate() {
+        // The stack after the last step, or after a failing one, is not
+        // traced.
+        let geth_stack = match trace.struct_logs.get(idx + 1) {
+            Some(next) if step.error.is_none() => &next.stack,
+            _ => continue,
+        };
+        for op_ref in &step.bus_mapping_instance {
+            if op_ref.target() != Target::Stack {
+                continue;
+            }
+            let op = &builder.block.container.stack[op_ref.as_usize()];
+            if op.rw() != RW::WRITE {
+                continue;
+            }
+            // The stack grows down from the address 1023.
+            let address = op.op().address.0;
+            let geth = geth_stack.0.get(1023 - address).cloned();
+            if geth != Some(op.op().value) {
+                return Err(Divergence::Stack {
+                    step: idx,
+                    opcode: trace.struct_logs[idx].op,
+                    address,
+                    geth,
+                    bus_mapping: op.op().value,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_storage(builder: &CircuitInputBuilder, trace: &GethExecTrace) -> Result<(), Divergence> {
+    // The storage traced by geth accumulates the slots accessed by the
+    // contract, so that of the last step accessing one is complete.
+    let geth_storage = match trace
+        .struct_logs
+        .iter()
+        .rev()
+        .find(|step| matches!(step.op, OpcodeId::SLOAD | OpcodeId::SSTORE))
+    {
+        Some(step) => &step.storage,
+        None => return Ok(()),
+    };
+    for (key, geth) in geth_storage.iter() {
+        let (_, bus_mapping) = builder.sdb.get_storage(&MOCK_ACCOUNTS[0], key);
+        if bus_mapping != geth {
+            return Err(Divergence::Storage {
+                key: *key,
+                geth: *geth,
+                bus_mapping: *bus_mapping,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Runs the traced program through bus-mapping and the EVM circuit, and
+/// returns the first divergence found.
+pub fn compare(geth_data: GethData) -> Result<(), Divergence> {
+    let trace = geth_data.geth_traces[0].clone();
+    let mut builder = BlockData::new_from_geth_data(geth_data.clone()).new_circuit_input_builder();
+    builder
+        .handle_block(&geth_data.eth_block, &geth_data.geth_traces)
+        .map_err(|err| Divergence::CircuitInputBuilder(format!("{:?}", err)))?;
+
+    check_gas(&builder, &trace)?;
+    check_stack(&builder, &trace)?;
+    check_storage(&builder, &trace)?;
+
+    let block = witness::block_convert(&builder.block, &builder.code_db);
+    run_test_circuit_complete_fixed_table(block)
+        .map_err(|failures| Divergence::EvmCircuit(format!("{:?}", failures)))
+}
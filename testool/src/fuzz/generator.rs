@@ -0,0 +1,176 @@
+//! Generator of random but structured programs: sequences of snippets which
+//! push the arguments of an opcode and execute it, with the memory offsets
+//! and storage keys kept small so that most programs don't run out of gas.
+
+use eth_types::{evm_types::OpcodeId, Bytecode, Word};
+use rand::{seq::SliceRandom, Rng};
+use serde::Serialize;
+
+/// Maximum memory offset pushed as argument of the memory opcodes.
+const MAX_MEMORY_OFFSET: u64 = 1024;
+/// Maximum storage key pushed as argument of the storage opcodes.
+const MAX_STORAGE_KEY: u64 = 16;
+
+/// Opcodes of the generated programs, with their number of stack inputs.
+const OPCODES: [(OpcodeId, usize); 39] = [
+    (OpcodeId::ADD, 2),
+    (OpcodeId::MUL, 2),
+    (OpcodeId::SUB, 2),
+    (OpcodeId::DIV, 2),
+    (OpcodeId::SDIV, 2),
+    (OpcodeId::MOD, 2),
+    (OpcodeId::SMOD, 2),
+    (OpcodeId::ADDMOD, 3),
+    (OpcodeId::MULMOD, 3),
+    (OpcodeId::EXP, 2),
+    (OpcodeId::SIGNEXTEND, 2),
+    (OpcodeId::LT, 2),
+    (OpcodeId::GT, 2),
+    (OpcodeId::SLT, 2),
+    (OpcodeId::SGT, 2),
+    (OpcodeId::EQ, 2),
+    (OpcodeId::ISZERO, 1),
+    (OpcodeId::AND, 2),
+    (OpcodeId::OR, 2),
+    (OpcodeId::XOR, 2),
+    (OpcodeId::NOT, 1),
+    (OpcodeId::BYTE, 2),
+    (OpcodeId::SHL, 2),
+    (OpcodeId::SHR, 2),
+    (OpcodeId::SAR, 2),
+    (OpcodeId::CALLDATALOAD, 1),
+    (OpcodeId::CALLDATASIZE, 0),
+    (OpcodeId::CALLVALUE, 0),
+    (OpcodeId::CALLER, 0),
+    (OpcodeId::ADDRESS, 0),
+    (OpcodeId::PC, 0),
+    (OpcodeId::MSIZE, 0),
+    (OpcodeId::GAS, 0),
+    (OpcodeId::MLOAD, 1),
+    (OpcodeId::MSTORE, 2),
+    (OpcodeId::MSTORE8, 2),
+    (OpcodeId::SLOAD, 1),
+    (OpcodeId::SSTORE, 2),
+    (OpcodeId::POP, 1),
+];
+
+/// An opcode with the arguments pushed before it, the other arguments being
+/// the results of the previous snippets left on the stack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Snippet {
+    pub pushes: Vec<Word>,
+    pub opcode: OpcodeId,
+}
+
+/// A program and the inputs of the transaction calling it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Program {
+    pub snippets: Vec<Snippet>,
+    pub calldata: Vec<u8>,
+    pub value: Word,
+}
+
+impl Program {
+    /// Returns the bytecode of the program, which pushes the arguments of
+    /// each snippet in reverse order so that the first one is on top.
+    pub fn bytecode(&self) -> Bytecode {
+        let mut code = Bytecode::default();
+        for snippet in &self.snippets {
+            for value in snippet.pushes.iter().rev() {
+                code.push_value(*value);
+            }
+            code.write_op(snippet.opcode);
+        }
+        code.write_op(OpcodeId::STOP);
+        code
+    }
+}
+
+impl std::fmt::Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for snippet in &self.snippets {
+            for value in snippet.pushes.iter().rev() {
+                writeln!(f, "PUSH({:#x})", value)?;
+            }
+            writeln!(f, "{:?}", snippet.opcode)?;
+        }
+        writeln!(f, "STOP")?;
+        writeln!(f, "calldata: 0x{}", hex::encode(&self.calldata))?;
+        write!(f, "value: {:#x}", self.value)
+    }
+}
+
+/// Returns a random word, biased towards the edge cases of the arithmetic.
+fn random_word(rng: &mut impl Rng) -> Word {
+    match rng.gen_range(0..6) {
+        0 => Word::zero(),
+        1 => Word::one(),
+        2 => Word::MAX,
+        3 => Word::one() << 255,
+        4 => Word::from(rng.gen::<u8>()),
+        _ => Word::from_big_endian(&rng.gen::<[u8; 32]>()),
+    }
+}
+
+/// Returns the pushed argument `idx` of `opcode`, where the offsets and keys
+/// are kept small.
+fn random_argument(rng: &mut impl Rng, opcode: OpcodeId, idx: usize) -> Word {
+    match (opcode, idx) {
+        (OpcodeId::MLOAD | OpcodeId::MSTORE | OpcodeId::MSTORE8, 0) => {
+            Word::from(rng.gen_range(0..MAX_MEMORY_OFFSET))
+        }
+        (OpcodeId::SLOAD | OpcodeId::SSTORE, 0) => Word::from(rng.gen_range(0..MAX_STORAGE_KEY)),
+        (OpcodeId::CALLDATALOAD, 0) => Word::from(rng.gen_range(0..64u64)),
+        _ => random_word(rng),
+    }
+}
+
+/// Returns true if the opcode pushes a result on the stack.
+fn has_output(opcode: OpcodeId) -> bool {
+    !matches!(
+        opcode,
+        OpcodeId::MSTORE | OpcodeId::MSTORE8 | OpcodeId::SSTORE | OpcodeId::POP
+    )
+}
+
+/// Generates a program of at most `max_snippets` snippets.
+pub fn generate(rng: &mut impl Rng, max_snippets: usize) -> Program {
+    let mut snippets = Vec::new();
+    // Number of results of the previous snippets on the stack.
+    let mut depth = 0;
+    for _ in 0..rng.gen_range(1..=max_snippets) {
+        let (opcode, inputs) = *OPCODES.choose(rng).unwrap();
+        // The arguments below the pushed ones are taken from the stack.
+        let from_stack = rng.gen_range(0..=inputs.min(depth));
+        let pushes = (0..inputs - from_stack)
+            .map(|idx| random_argument(rng, opcode, idx))
+            .collect();
+        depth = depth - from_stack + has_output(opcode) as usize;
+        snippets.push(Snippet { pushes, opcode });
+    }
+
+    let calldata_len = rng.gen_range(0..64);
+    Program {
+        snippets,
+        calldata: (0..calldata_len).map(|_| rng.gen()).collect(),
+        value: Word::from(rng.gen_range(0..1_000_000u64)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn generate_deterministic() {
+        let program = generate(&mut ChaCha20Rng::seed_from_u64(1), 32);
+        assert_eq!(program, generate(&mut ChaCha20Rng::seed_from_u64(1), 32));
+        assert!(!program.snippets.is_empty() && program.snippets.len() <= 32);
+        assert_eq!(
+            program.bytecode().to_vec().last(),
+            Some(&OpcodeId::STOP.as_u8())
+        );
+    }
+}
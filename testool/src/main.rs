@@ -0,0 +1,177 @@
+//! testool runs the state tests of ethereum/tests through the circuit input
+//! builder and the MockProver of the circuits, writing a conformance report of
+//! the results, and fuzzes bus-mapping and the EVM circuit against geth.
+
+mod config;
+mod fuzz;
+mod statetest;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use config::Config;
+use fuzz::{fuzz, FuzzConfig};
+use statetest::{load_statetests_suite, run_test, Fork, ResultInfo, ResultLevel, Results};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[clap(about = "Test the circuits against the ethereum/tests state tests and geth")]
+struct Args {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the ethereum/tests state tests through the circuits.
+    Statetests(StateTestsArgs),
+    /// Fuzz bus-mapping and the EVM circuit against geth with random programs.
+    Fuzz(FuzzArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct StateTestsArgs {
+    /// Path of the state tests to run: a filled test, a filler or a
+    /// directory of them.
+    #[clap(long, default_value = "tests/GeneralStateTests")]
+    suite: PathBuf,
+
+    /// Fork whose expectations are checked.
+    #[clap(long, default_value = "London")]
+    fork: Fork,
+
+    /// Path of the configuration listing the tests to run or to skip.
+    #[clap(long, default_value = "Config.toml")]
+    config: PathBuf,
+
+    /// Only run the tests whose id contains this string.
+    #[clap(long)]
+    test: Option<String>,
+
+    /// Path of the report, written in HTML if it ends with `.html` and in
+    /// Markdown otherwise.
+    #[clap(long, default_value = "report.md")]
+    report: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct FuzzArgs {
+    /// Number of programs generated.
+    #[clap(long, default_value = "100")]
+    iterations: usize,
+
+    /// Seed of the generator.
+    #[clap(long, default_value = "0")]
+    seed: u64,
+
+    /// Maximum number of snippets (opcodes with their arguments) of a
+    /// program.
+    #[clap(long, default_value = "32")]
+    max_snippets: usize,
+
+    /// Directory where the reproducers of the divergences are written.
+    #[clap(long, default_value = "divergences")]
+    out: PathBuf,
+}
+
+pub(crate) fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|msg| msg.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Runs the state tests and writes their report, returning true if none of
+/// them failed.
+fn run_statetests(args: StateTestsArgs) -> Result<bool> {
+    let config = Config::load(&args.config)?;
+    let (state_tests, errors) = load_statetests_suite(&args.suite, args.fork)?;
+    log::info!(
+        "{} tests loaded from {}, {} files not compiled",
+        state_tests.len(),
+        args.suite.display(),
+        errors.len()
+    );
+
+    let mut results = Results::default();
+    for (path, err) in errors {
+        results.insert(
+            path.clone(),
+            ResultInfo {
+                level: ResultLevel::Ignored,
+                details: err,
+                path,
+            },
+        );
+    }
+
+    for st in state_tests {
+        if let Some(test) = &args.test {
+            if !st.id.contains(test.as_str()) {
+                continue;
+            }
+        }
+        if let Some(reason) = config.skip_reason(&st) {
+            results.insert(
+                st.id.clone(),
+                ResultInfo {
+                    level: ResultLevel::Ignored,
+                    details: reason.to_string(),
+                    path: st.path.clone(),
+                },
+            );
+            continue;
+        }
+
+        let (level, details) =
+            match catch_unwind(AssertUnwindSafe(|| run_test(&st, &config.circuits))) {
+                Ok(Ok(())) => (ResultLevel::Success, String::new()),
+                Ok(Err(err)) => (ResultLevel::Fail, err.to_string()),
+                Err(panic) => (ResultLevel::Panic, panic_message(panic)),
+            };
+        results.insert(
+            st.id.clone(),
+            ResultInfo {
+                level,
+                details,
+                path: st.path.clone(),
+            },
+        );
+    }
+
+    let report = match args
+        .report
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("html") => results.html(),
+        _ => results.markdown(),
+    };
+    std::fs::write(&args.report, report)
+        .with_context(|| format!("writing report {}", args.report.display()))?;
+    log::info!("report written to {}", args.report.display());
+
+    Ok(results.is_success())
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let success = match Args::parse().command {
+        Command::Statetests(args) => run_statetests(args)?,
+        Command::Fuzz(args) => {
+            let config = FuzzConfig {
+                iterations: args.iterations,
+                seed: args.seed,
+                max_snippets: args.max_snippets,
+                out: args.out,
+            };
+            fuzz(&config)? == 0
+        }
+    };
+    if !success {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,64 @@
+//! The state tests of ethereum/tests, their compilers and their executor.
+
+mod executor;
+mod json;
+mod parse;
+mod results;
+mod spec;
+mod yaml;
+
+pub use executor::run_test;
+pub use results::{ResultInfo, ResultLevel, Results};
+pub use spec::{Fork, StateTest};
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Returns true if the file at `path` is a filler, which is compiled from
+/// its YAML (or JSON) expectations rather than from its filled post states.
+fn is_filler(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map_or(false, |stem| stem.ends_with("Filler"))
+}
+
+/// Loads the test cases of `fork` of the state tests under `path`, given as
+/// a single file or a directory of them.  The files which can't be compiled
+/// (e.g. fillers whose code needs a compiler) are returned with their error
+/// so that they are reported as ignored.
+pub fn load_statetests_suite(
+    path: &Path,
+    fork: Fork,
+) -> Result<(Vec<StateTest>, Vec<(String, String)>)> {
+    let mut state_tests = Vec::new();
+    let mut errors = Vec::new();
+
+    let mut paths = vec![path.to_path_buf()];
+    while let Some(path) = paths.pop() {
+        if path.is_dir() {
+            for entry in
+                std::fs::read_dir(&path).with_context(|| format!("reading {}", path.display()))?
+            {
+                paths.push(entry?.path());
+            }
+            continue;
+        }
+
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        let compile = match extension {
+            Some("yml" | "yaml") => yaml::compile,
+            Some("json") if is_filler(&path) => yaml::compile,
+            Some("json") => json::compile,
+            _ => continue,
+        };
+        let path = path.display().to_string();
+        let source = std::fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
+        match compile(&path, &source, fork) {
+            Ok(tests) => state_tests.extend(tests),
+            Err(err) => errors.push((path, format!("{:#}", err))),
+        }
+    }
+    state_tests.sort_by(|a, b| (&a.path, &a.id).cmp(&(&b.path, &b.id)));
+
+    Ok((state_tests, errors))
+}
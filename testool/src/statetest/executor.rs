@@ -0,0 +1,197 @@
+//! Execution of a [`StateTest`] through the external tracer, the circuit
+//! input builder and the MockProver of the circuits.
+
+use super::spec::StateTest;
+use crate::config::CircuitsConfig;
+use bus_mapping::{circuit_input_builder::CircuitInputBuilder, mock::BlockData};
+use eth_types::{
+    geth_types::{self, BlockConstants, GethData},
+    Address, Block, ToWord, Transaction, Word, U64,
+};
+use external_tracer::TraceConfig;
+use halo2_proofs::{dev::MockProver, pairing::bn256::Fr};
+use thiserror::Error;
+use zkevm_circuits::{evm_circuit, state_circuit::StateCircuit};
+
+/// Chain id of the state tests.
+const CHAIN_ID: u64 = 1;
+
+/// Reason of the failure of a state test.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum StateTestError {
+    /// The external tracer failed to execute the transaction.
+    #[error("ExternalTracer({0})")]
+    ExternalTracer(String),
+    /// The circuit input builder failed to handle the traces.
+    #[error("CircuitInputBuilder({0})")]
+    CircuitInputBuilder(String),
+    /// The MockProver of a circuit reported failures.
+    #[error("CircuitVerification({circuit}: {failures})")]
+    CircuitVerification {
+        circuit: &'static str,
+        failures: String,
+    },
+    /// The transaction was expected to be invalid, but was executed.
+    #[error("ExceptionExpected")]
+    ExceptionExpected,
+    /// A field of the post state does not match the expected one.
+    #[error("PostStateMismatch({address:?}.{field}: expected {expected}, found {found})")]
+    PostStateMismatch {
+        address: Address,
+        field: String,
+        expected: String,
+        found: String,
+    },
+}
+
+/// Returns the block of the test, whose single transaction is unsigned, as
+/// the signature is not checked by the tracer nor by the circuits run here.
+fn into_block(st: &StateTest) -> Block<Transaction> {
+    let tx = Transaction {
+        from: st.from,
+        to: st.to,
+        nonce: st.nonce,
+        gas: Word::from(st.gas_limit),
+        value: st.value,
+        gas_price: Some(st.gas_price),
+        input: st.data.clone(),
+        block_number: Some(U64::from(st.env.current_number)),
+        transaction_index: Some(U64::zero()),
+        transaction_type: Some(U64::zero()),
+        chain_id: Some(Word::from(CHAIN_ID)),
+        ..Default::default()
+    };
+
+    Block {
+        author: st.env.current_coinbase,
+        parent_hash: st.env.previous_hash,
+        number: Some(U64::from(st.env.current_number)),
+        timestamp: Word::from(st.env.current_timestamp),
+        difficulty: st.env.current_difficulty,
+        gas_limit: Word::from(st.env.current_gas_limit),
+        base_fee_per_gas: Some(st.env.current_base_fee),
+        transactions: vec![tx],
+        ..Default::default()
+    }
+}
+
+/// Checks the post state of the builder against the expected one of the
+/// test.
+fn check_post(builder: &CircuitInputBuilder, st: &StateTest) -> Result<(), StateTestError> {
+    for (address, expected) in &st.result {
+        let (_, actual) = builder.sdb.get_account(address);
+        let mismatch = |field: &str, expected: String, found: String| {
+            Err(StateTestError::PostStateMismatch {
+                address: *address,
+                field: field.to_string(),
+                expected,
+                found,
+            })
+        };
+
+        if let Some(balance) = expected.balance {
+            if balance != actual.balance {
+                return mismatch("balance", balance.to_string(), actual.balance.to_string());
+            }
+        }
+        if let Some(nonce) = expected.nonce {
+            if nonce != actual.nonce {
+                return mismatch("nonce", nonce.to_string(), actual.nonce.to_string());
+            }
+        }
+        if let Some(code) = &expected.code {
+            let actual_code = builder
+                .code_db
+                .0
+                .get(&actual.code_hash)
+                .cloned()
+                .unwrap_or_default();
+            if code.to_vec() != actual_code {
+                return mismatch("code", hex::encode(code), hex::encode(actual_code));
+            }
+        }
+        for (key, value) in &expected.storage {
+            let actual_value = actual.storage.get(key).cloned().unwrap_or_default();
+            if *value != actual_value {
+                return mismatch(
+                    &format!("storage[{:#x}]", key),
+                    value.to_string(),
+                    actual_value.to_string(),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs a state test through the external tracer and the circuit input
+/// builder, checks its post state and verifies the witness with the
+/// circuits enabled in `circuits`.
+pub fn run_test(st: &StateTest, circuits: &CircuitsConfig) -> Result<(), StateTestError> {
+    let eth_block = into_block(st);
+    let history_hashes = vec![st.env.previous_hash.to_word()];
+    let trace_config = TraceConfig {
+        chain_id: Word::from(CHAIN_ID),
+        history_hashes: history_hashes.clone(),
+        block_constants: BlockConstants::try_from(&eth_block)
+            .expect("block of the test has a number and a base fee"),
+        accounts: st.pre.clone(),
+        transactions: eth_block
+            .transactions
+            .iter()
+            .map(geth_types::Transaction::from_eth_tx)
+            .collect(),
+    };
+
+    let geth_traces = match external_tracer::trace(&trace_config) {
+        Ok(geth_traces) => geth_traces,
+        // An invalid transaction is rejected by the tracer.
+        Err(_) if st.exception => return Ok(()),
+        Err(err) => return Err(StateTestError::ExternalTracer(err.to_string())),
+    };
+    if st.exception {
+        return Err(StateTestError::ExceptionExpected);
+    }
+
+    let geth_data = GethData {
+        chain_id: Word::from(CHAIN_ID),
+        history_hashes,
+        eth_block: eth_block.clone(),
+        geth_traces: geth_traces.clone(),
+        accounts: st.pre.values().cloned().collect(),
+        withdrawals: Vec::new(),
+    };
+    let mut builder = BlockData::new_from_geth_data(geth_data).new_circuit_input_builder();
+    builder
+        .handle_block(&eth_block, &geth_traces)
+        .map_err(|err| StateTestError::CircuitInputBuilder(format!("{:?}", err)))?;
+
+    check_post(&builder, st)?;
+
+    let block = evm_circuit::witness::block_convert(&builder.block, &builder.code_db);
+
+    if circuits.evm {
+        evm_circuit::test::run_test_circuit_complete_fixed_table(block.clone()).map_err(
+            |failures| StateTestError::CircuitVerification {
+                circuit: "evm",
+                failures: format!("{:?}", failures),
+            },
+        )?;
+    }
+
+    if circuits.state {
+        const N_ROWS: usize = 1 << 16;
+        let num_rws = block.rws.0.values().map(|rws| rws.len()).sum::<usize>();
+        let state_circuit = StateCircuit::<Fr>::new(block.randomness, block.rws, N_ROWS);
+        let power_of_randomness = state_circuit.instance();
+        let prover = MockProver::<Fr>::run(18, &state_circuit, power_of_randomness).unwrap();
+        prover
+            .verify_at_rows(N_ROWS - num_rws..N_ROWS, N_ROWS - num_rws..N_ROWS)
+            .map_err(|failures| StateTestError::CircuitVerification {
+                circuit: "state",
+                failures: format!("{:?}", failures),
+            })?;
+    }
+
+    Ok(())
+}
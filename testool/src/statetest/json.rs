@@ -0,0 +1,278 @@
+//! Compiler of the filled state tests of ethereum/tests
+//! (`GeneralStateTests/**/*.json`), into one [`StateTest`] per post state
+//! expected for the fork.
+
+use super::{
+    parse::{parse_address, parse_bytes, parse_code, parse_hash, parse_u256, parse_u64, Value},
+    spec::{Env, Fork, StateTest},
+};
+use anyhow::{Context, Result};
+use eth_types::{geth_types::Account, Address, Bytes};
+use ethers_core::k256::ecdsa::SigningKey;
+use ethers_signers::{LocalWallet, Signer};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Environment of a test, which has the same format in the fillers.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TestEnv {
+    current_coinbase: String,
+    current_difficulty: Value,
+    current_gas_limit: Value,
+    current_number: Value,
+    current_timestamp: Value,
+    #[serde(default)]
+    current_base_fee: Option<Value>,
+    #[serde(default)]
+    previous_hash: Option<String>,
+}
+
+impl TestEnv {
+    pub(crate) fn to_env(&self) -> Result<Env> {
+        Ok(Env {
+            current_coinbase: parse_address(&self.current_coinbase)?,
+            current_difficulty: parse_u256(&self.current_difficulty)?,
+            current_gas_limit: parse_u64(&self.current_gas_limit)?,
+            current_number: parse_u64(&self.current_number)?,
+            current_timestamp: parse_u64(&self.current_timestamp)?,
+            // The tests of the forks before London have no base fee.
+            current_base_fee: self
+                .current_base_fee
+                .as_ref()
+                .map(parse_u256)
+                .transpose()?
+                .unwrap_or_default(),
+            previous_hash: self
+                .previous_hash
+                .as_deref()
+                .map(parse_hash)
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Account of the pre state of a test, which has the same format in the
+/// fillers.
+#[derive(Debug, Deserialize)]
+pub(crate) struct AccountPre {
+    balance: Value,
+    #[serde(default)]
+    code: String,
+    nonce: Value,
+    #[serde(default)]
+    storage: HashMap<Value, Value>,
+}
+
+/// Returns the accounts of the pre state of a test.
+pub(crate) fn to_pre(pre: &HashMap<String, AccountPre>) -> Result<HashMap<Address, Account>> {
+    pre.iter()
+        .map(|(address, account)| {
+            let address = parse_address(address)?;
+            let account = Account {
+                address,
+                nonce: parse_u256(&account.nonce)?,
+                balance: parse_u256(&account.balance)?,
+                code: parse_code(&account.code)?,
+                storage: account
+                    .storage
+                    .iter()
+                    .map(|(key, value)| Ok((parse_u256(key)?, parse_u256(value)?)))
+                    .collect::<Result<_>>()?,
+            };
+            Ok((address, account))
+        })
+        .collect()
+}
+
+/// Returns the address of the sender of the transactions signed with
+/// `secret_key`.
+pub(crate) fn sender(secret_key: &Bytes) -> Result<Address> {
+    let signing_key = SigningKey::from_bytes(secret_key).context("invalid secret key")?;
+    Ok(LocalWallet::from(signing_key).address())
+}
+
+/// Returns the recipient of a transaction, where an empty `to` creates a
+/// contract.
+pub(crate) fn to_recipient(to: &str) -> Result<Option<Address>> {
+    if to.trim().is_empty() {
+        Ok(None)
+    } else {
+        parse_address(to).map(Some)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestTransaction {
+    data: Vec<String>,
+    gas_limit: Vec<Value>,
+    #[serde(default)]
+    gas_price: Option<Value>,
+    #[serde(default)]
+    max_fee_per_gas: Option<Value>,
+    nonce: Value,
+    secret_key: String,
+    #[serde(default)]
+    to: String,
+    value: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Indexes {
+    data: usize,
+    gas: usize,
+    value: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestPost {
+    indexes: Indexes,
+    #[serde(default)]
+    expect_exception: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Test {
+    env: TestEnv,
+    pre: HashMap<String, AccountPre>,
+    #[serde(default)]
+    post: HashMap<String, Vec<TestPost>>,
+    transaction: TestTransaction,
+}
+
+/// Compiles the filled state tests of `source`, read from `path`, into the
+/// test cases of `fork`.
+pub fn compile(path: &str, source: &str, fork: Fork) -> Result<Vec<StateTest>> {
+    let tests: HashMap<String, Test> =
+        serde_json::from_str(source).with_context(|| format!("parsing {}", path))?;
+
+    let mut state_tests = Vec::new();
+    for (name, test) in tests {
+        let env = test.env.to_env()?;
+        let pre = to_pre(&test.pre)?;
+        let tx = &test.transaction;
+        let secret_key = parse_bytes(&tx.secret_key)?;
+        let from = sender(&secret_key)?;
+        let to = to_recipient(&tx.to)?;
+        let nonce = parse_u256(&tx.nonce)?;
+        let gas_price = tx
+            .gas_price
+            .as_ref()
+            .or(tx.max_fee_per_gas.as_ref())
+            .map(parse_u256)
+            .transpose()?
+            .unwrap_or_default();
+
+        let posts = match test
+            .post
+            .iter()
+            .find(|(network, _)| network.parse::<Fork>().ok() == Some(fork))
+        {
+            Some((_, posts)) => posts,
+            None => continue,
+        };
+        for post in posts {
+            let Indexes { data, gas, value } = post.indexes;
+            state_tests.push(StateTest {
+                path: path.to_string(),
+                id: format!("{}_d{}_g{}_v{}", name, data, gas, value),
+                env: env.clone(),
+                from,
+                to,
+                gas_limit: parse_u64(tx.gas_limit.get(gas).context("gas index")?)?,
+                gas_price,
+                nonce,
+                value: parse_u256(tx.value.get(value).context("value index")?)?,
+                data: parse_code(tx.data.get(data).context("data index")?)?,
+                pre: pre.clone(),
+                result: HashMap::new(),
+                exception: post.expect_exception.is_some(),
+            });
+        }
+    }
+    state_tests.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Ok(state_tests)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use eth_types::Word;
+    use pretty_assertions::assert_eq;
+
+    const FILLED: &str = r#"{
+        "add": {
+            "env": {
+                "currentCoinbase": "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+                "currentDifficulty": "0x020000",
+                "currentGasLimit": "0xff112233445566",
+                "currentNumber": "0x01",
+                "currentTimestamp": "0x03e8",
+                "currentBaseFee": "0x0a",
+                "previousHash": "0x5e20a0453cecd065ea59c37ac63e079ee08998b6045136a8ce6635c7912ec0b6"
+            },
+            "pre": {
+                "0xcccccccccccccccccccccccccccccccccccccccc": {
+                    "balance": "0x0ba1a9ce0ba1a9ce",
+                    "code": "0x600160010160005500",
+                    "nonce": "0x00",
+                    "storage": {}
+                },
+                "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b": {
+                    "balance": "0x0ba1a9ce0ba1a9ce",
+                    "code": "0x",
+                    "nonce": "0x00",
+                    "storage": {"0x00": "0x01"}
+                }
+            },
+            "post": {
+                "London": [
+                    {"indexes": {"data": 0, "gas": 0, "value": 0}},
+                    {"indexes": {"data": 0, "gas": 1, "value": 0}, "expectException": "TR_IntrinsicGas"}
+                ],
+                "Berlin": [
+                    {"indexes": {"data": 0, "gas": 0, "value": 0}}
+                ]
+            },
+            "transaction": {
+                "data": ["0x"],
+                "gasLimit": ["0x04c4b400", "0x01"],
+                "gasPrice": "0x0a",
+                "nonce": "0x00",
+                "secretKey": "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8",
+                "to": "0xcccccccccccccccccccccccccccccccccccccccc",
+                "value": ["0x01"]
+            }
+        }
+    }"#;
+
+    #[test]
+    fn compile_filled() -> Result<()> {
+        let tests = compile("add.json", FILLED, Fork::London)?;
+        assert_eq!(
+            tests
+                .iter()
+                .map(|test| test.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["add_d0_g0_v0", "add_d0_g1_v0"]
+        );
+        let test = &tests[0];
+        assert_eq!(
+            test.from,
+            parse_address("0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b")?
+        );
+        assert_eq!(test.gas_limit, 0x04c4b400);
+        assert_eq!(test.value, Word::one());
+        assert_eq!(test.pre.len(), 2);
+        assert!(!test.exception);
+        assert!(tests[1].exception);
+
+        assert_eq!(compile("add.json", FILLED, Fork::Berlin)?.len(), 1);
+        assert!(compile("add.json", FILLED, Fork::Shanghai)?.is_empty());
+        Ok(())
+    }
+}
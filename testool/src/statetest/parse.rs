@@ -0,0 +1,131 @@
+//! Parsing of the values of the state tests, which are given as hex or
+//! decimal strings (or numbers in the YAML fillers).
+
+use anyhow::{bail, Context, Result};
+use eth_types::{Address, Bytes, Word, H256};
+use serde::Deserialize;
+
+/// A value of a state test, given as a string or a number.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Str(String),
+    Num(u64),
+}
+
+impl Default for Value {
+    fn default() -> Self {
+        Value::Str(String::new())
+    }
+}
+
+impl Value {
+    fn as_str(&self) -> std::borrow::Cow<'_, str> {
+        match self {
+            Value::Str(value) => value.trim().into(),
+            Value::Num(value) => value.to_string().into(),
+        }
+    }
+}
+
+/// Parses an hex (prefixed by `0x`) or decimal word, where `_` can be used as
+/// a digit separator.
+pub fn parse_u256(value: &Value) -> Result<Word> {
+    let value = value.as_str().replace('_', "");
+    if value == "0x" || value.is_empty() {
+        Ok(Word::zero())
+    } else if let Some(hex) = value.strip_prefix("0x") {
+        Word::from_str_radix(hex, 16).with_context(|| format!("invalid hex word {}", value))
+    } else {
+        Word::from_dec_str(&value).with_context(|| format!("invalid decimal word {}", value))
+    }
+}
+
+/// Parses an hex or decimal u64.
+pub fn parse_u64(value: &Value) -> Result<u64> {
+    let word = parse_u256(value)?;
+    if word > Word::from(u64::MAX) {
+        bail!("value {} overflows u64", word);
+    }
+    Ok(word.as_u64())
+}
+
+/// Parses bytes given in hex, with or without the `0x` prefix.
+pub fn parse_bytes(value: &str) -> Result<Bytes> {
+    let value = value.trim();
+    let hex = value.strip_prefix("0x").unwrap_or(value);
+    Ok(Bytes::from(
+        hex::decode(hex).with_context(|| format!("invalid hex bytes {}", value))?,
+    ))
+}
+
+/// Parses an address given in hex, with or without the `0x` prefix.
+pub fn parse_address(value: &str) -> Result<Address> {
+    let bytes = parse_bytes(value)?;
+    if bytes.len() != 20 {
+        bail!("invalid address {}", value);
+    }
+    Ok(Address::from_slice(&bytes))
+}
+
+/// Parses a 32 bytes hash given in hex, with or without the `0x` prefix.
+pub fn parse_hash(value: &str) -> Result<H256> {
+    let word = parse_u256(&Value::Str(format!(
+        "0x{}",
+        value.trim().trim_start_matches("0x")
+    )))?;
+    let mut hash = [0u8; 32];
+    word.to_big_endian(&mut hash);
+    Ok(H256::from(hash))
+}
+
+/// Parses the code or the calldata of a state test, which in the fillers can
+/// also be prefixed by a `:label` and given as `:raw` bytes.  The sources
+/// which need a compiler (LLL, Yul, Solidity, `:abi`) are not supported.
+pub fn parse_code(value: &str) -> Result<Bytes> {
+    let mut value = value.trim();
+    if let Some(labelled) = value.strip_prefix(":label") {
+        // Skip the label name.
+        value = labelled
+            .trim_start()
+            .split_once(char::is_whitespace)
+            .map(|(_, rest)| rest.trim())
+            .unwrap_or_default();
+    }
+    if let Some(raw) = value.strip_prefix(":raw") {
+        value = raw.trim();
+    }
+    if value.is_empty() {
+        return Ok(Bytes::default());
+    }
+    if value.starts_with(':') || value.starts_with('{') || value.starts_with('(') {
+        bail!("unsupported code source {}", value);
+    }
+    parse_bytes(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_values() -> Result<()> {
+        assert_eq!(parse_u256(&Value::Str("0x10".into()))?, Word::from(16));
+        assert_eq!(parse_u256(&Value::Str("1_000".into()))?, Word::from(1000));
+        assert_eq!(parse_u256(&Value::Num(7))?, Word::from(7));
+        assert!(parse_u64(&Value::Str(format!("0x1{}", "0".repeat(16)))).is_err());
+        assert_eq!(
+            parse_address("cafe000000000000000000000000000000000001")?,
+            parse_address("0xcafe000000000000000000000000000000000001")?
+        );
+        assert_eq!(parse_code(":raw 0x6001")?, Bytes::from(vec![0x60, 0x01]));
+        assert_eq!(
+            parse_code(":label transfer :raw 0x00")?,
+            Bytes::from(vec![0x00])
+        );
+        assert_eq!(parse_code("")?, Bytes::default());
+        assert!(parse_code("{ [[0]] 1 }").is_err());
+        assert!(parse_code(":abi f(uint) 1").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+//! Results of a run of the state tests, and their conformance report in
+//! Markdown or HTML.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Outcome of a test, from the best to the worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ResultLevel {
+    Success,
+    Ignored,
+    Fail,
+    Panic,
+}
+
+impl ResultLevel {
+    const ALL: [ResultLevel; 4] = [
+        ResultLevel::Success,
+        ResultLevel::Ignored,
+        ResultLevel::Fail,
+        ResultLevel::Panic,
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultInfo {
+    pub level: ResultLevel,
+    pub details: String,
+    pub path: String,
+}
+
+/// Results of the tests, by test id.
+#[derive(Debug, Clone, Default)]
+pub struct Results {
+    pub tests: BTreeMap<String, ResultInfo>,
+}
+
+impl Results {
+    pub fn insert(&mut self, id: String, info: ResultInfo) {
+        log::info!("{:?} {}: {}", info.level, id, info.details);
+        self.tests.insert(id, info);
+    }
+
+    /// Returns true if no test failed or panicked.
+    pub fn is_success(&self) -> bool {
+        self.tests
+            .values()
+            .all(|info| info.level <= ResultLevel::Ignored)
+    }
+
+    /// Returns the number of tests of each level, for all the tests and for
+    /// the tests of each directory.
+    fn summary(&self) -> (Vec<usize>, BTreeMap<String, Vec<usize>>) {
+        let mut total = vec![0; ResultLevel::ALL.len()];
+        let mut by_dir = BTreeMap::<_, Vec<usize>>::new();
+        for info in self.tests.values() {
+            let dir = std::path::Path::new(&info.path)
+                .parent()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default();
+            total[info.level as usize] += 1;
+            by_dir
+                .entry(dir)
+                .or_insert_with(|| vec![0; ResultLevel::ALL.len()])[info.level as usize] += 1;
+        }
+        (total, by_dir)
+    }
+
+    fn failures(&self) -> impl Iterator<Item = (&String, &ResultInfo)> {
+        self.tests
+            .iter()
+            .filter(|(_, info)| info.level > ResultLevel::Ignored)
+    }
+
+    /// Returns the conformance report in Markdown.
+    pub fn markdown(&self) -> String {
+        let (total, by_dir) = self.summary();
+        let header = ResultLevel::ALL
+            .iter()
+            .map(|level| format!("{:?}", level))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        let row = |counts: &[usize]| {
+            counts
+                .iter()
+                .map(|count| count.to_string())
+                .collect::<Vec<_>>()
+                .join(" | ")
+        };
+
+        let mut md = String::new();
+        writeln!(md, "# State tests conformance report\n").unwrap();
+        writeln!(md, "| Path | {} |", header).unwrap();
+        writeln!(md, "|---{}|", "|---".repeat(ResultLevel::ALL.len())).unwrap();
+        writeln!(md, "| **Total** | {} |", row(&total)).unwrap();
+        for (dir, counts) in &by_dir {
+            writeln!(md, "| {} | {} |", dir, row(counts)).unwrap();
+        }
+
+        writeln!(md, "\n## Failures\n").unwrap();
+        for (id, info) in self.failures() {
+            writeln!(
+                md,
+                "- `{}` ({}) {:?}: {}",
+                id, info.path, info.level, info.details
+            )
+            .unwrap();
+        }
+        md
+    }
+
+    /// Returns the conformance report in HTML.
+    pub fn html(&self) -> String {
+        let escape = |s: &str| {
+            s.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+        };
+        let (total, by_dir) = self.summary();
+        let cells = |counts: &[usize]| {
+            counts
+                .iter()
+                .map(|count| format!("<td>{}</td>", count))
+                .collect::<String>()
+        };
+
+        let mut html = String::new();
+        writeln!(
+            html,
+            "<html><head><title>State tests conformance report</title></head><body>"
+        )
+        .unwrap();
+        writeln!(html, "<h1>State tests conformance report</h1>").unwrap();
+        writeln!(html, "<table><tr><th>Path</th>").unwrap();
+        for level in ResultLevel::ALL {
+            write!(html, "<th>{:?}</th>", level).unwrap();
+        }
+        writeln!(html, "</tr>").unwrap();
+        writeln!(html, "<tr><th>Total</th>{}</tr>", cells(&total)).unwrap();
+        for (dir, counts) in &by_dir {
+            writeln!(html, "<tr><td>{}</td>{}</tr>", escape(dir), cells(counts)).unwrap();
+        }
+        writeln!(html, "</table>").unwrap();
+
+        writeln!(html, "<h2>Failures</h2><ul>").unwrap();
+        for (id, info) in self.failures() {
+            writeln!(
+                html,
+                "<li><code>{}</code> ({}) {:?}: {}</li>",
+                escape(id),
+                escape(&info.path),
+                info.level,
+                escape(&info.details)
+            )
+            .unwrap();
+        }
+        writeln!(html, "</ul></body></html>").unwrap();
+        html
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn results_markdown() {
+        let mut results = Results::default();
+        for (id, level, path) in [
+            ("add_d0_g0_v0", ResultLevel::Success, "stExample/add.json"),
+            ("mul_d0_g0_v0", ResultLevel::Fail, "stExample/mul.json"),
+            ("call_d0_g0_v0", ResultLevel::Ignored, "stCall/call.json"),
+        ] {
+            results.insert(
+                id.to_string(),
+                ResultInfo {
+                    level,
+                    details: "details".to_string(),
+                    path: path.to_string(),
+                },
+            );
+        }
+        assert!(!results.is_success());
+
+        let md = results.markdown();
+        assert!(md.contains("| **Total** | 1 | 1 | 1 | 0 |"));
+        assert!(md.contains("| stExample | 1 | 0 | 1 | 0 |"));
+        assert!(md.contains("| stCall | 0 | 1 | 0 | 0 |"));
+        assert!(md.contains("- `mul_d0_g0_v0` (stExample/mul.json) Fail: details"));
+        assert!(!md.contains("add_d0_g0_v0"));
+    }
+}
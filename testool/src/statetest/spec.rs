@@ -0,0 +1,129 @@
+use eth_types::{geth_types::Account, Address, Bytes, Word, H256};
+use std::collections::HashMap;
+
+/// Block environment in which the transaction of a state test is executed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Env {
+    pub current_coinbase: Address,
+    pub current_difficulty: Word,
+    pub current_gas_limit: u64,
+    pub current_number: u64,
+    pub current_timestamp: u64,
+    pub current_base_fee: Word,
+    pub previous_hash: H256,
+}
+
+/// Expected state of an account after the execution of a state test, where
+/// the fields set to `None` are not checked.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccountMatch {
+    pub balance: Option<Word>,
+    pub code: Option<Bytes>,
+    pub nonce: Option<Word>,
+    pub storage: HashMap<Word, Word>,
+}
+
+/// A single case of a state test, which executes one transaction (one
+/// combination of the data, gas and value indexes of the test) on top of the
+/// `pre` state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateTest {
+    /// Path of the file the test was read from.
+    pub path: String,
+    /// Identifier of the test case, `<name>_d<data>_g<gas>_v<value>`.
+    pub id: String,
+    pub env: Env,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub gas_limit: u64,
+    pub gas_price: Word,
+    pub nonce: Word,
+    pub value: Word,
+    pub data: Bytes,
+    pub pre: HashMap<Address, Account>,
+    /// Expected post state of the accounts, empty for the filled tests which
+    /// only provide the state root.
+    pub result: HashMap<Address, AccountMatch>,
+    /// Whether the transaction is expected to be invalid.
+    pub exception: bool,
+}
+
+impl std::fmt::Display for StateTest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.id, self.path)
+    }
+}
+
+/// The hardforks for which the state tests provide expectations, in
+/// activation order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Fork {
+    Frontier,
+    Homestead,
+    EIP150,
+    EIP158,
+    Byzantium,
+    Constantinople,
+    ConstantinopleFix,
+    Istanbul,
+    Berlin,
+    London,
+    Merge,
+    Shanghai,
+}
+
+impl Fork {
+    const ALL: [Fork; 12] = [
+        Fork::Frontier,
+        Fork::Homestead,
+        Fork::EIP150,
+        Fork::EIP158,
+        Fork::Byzantium,
+        Fork::Constantinople,
+        Fork::ConstantinopleFix,
+        Fork::Istanbul,
+        Fork::Berlin,
+        Fork::London,
+        Fork::Merge,
+        Fork::Shanghai,
+    ];
+
+    /// Returns true if the fork matches a network of the expectations of a
+    /// filler, such as `London`, `>=Berlin` or `<Merge`.
+    pub fn matches(&self, network: &str) -> bool {
+        let network = network.trim();
+        let (op, name) = match network.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(idx) => network.split_at(idx),
+            None => return false,
+        };
+        let other = match name.parse::<Fork>() {
+            Ok(other) => other,
+            Err(_) => return false,
+        };
+        match op.trim() {
+            "" => *self == other,
+            ">=" => *self >= other,
+            ">" => *self > other,
+            "<=" => *self <= other,
+            "<" => *self < other,
+            _ => false,
+        }
+    }
+}
+
+impl std::str::FromStr for Fork {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|fork| format!("{:?}", fork).eq_ignore_ascii_case(s))
+            .ok_or_else(|| anyhow::anyhow!("unknown fork {}", s))
+    }
+}
+
+impl std::fmt::Display for Fork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
@@ -0,0 +1,290 @@
+//! Compiler of the state test fillers of ethereum/tests
+//! (`src/GeneralStateTestsFiller/**/*Filler.{yml,json}`), into one
+//! [`StateTest`] per combination of the transaction indexes, with the post
+//! state expected for the fork.  Only the fillers whose code is given as raw
+//! bytes (not LLL, Yul or Solidity) can be compiled.
+
+use super::{
+    json::{sender, to_pre, to_recipient, AccountPre, TestEnv},
+    parse::{parse_address, parse_bytes, parse_code, parse_u256, parse_u64, Value},
+    spec::{AccountMatch, Fork, StateTest},
+};
+use anyhow::{Context, Result};
+use eth_types::Address;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Index {
+    Int(i64),
+    Str(String),
+}
+
+impl Index {
+    /// Returns true if the index matches `idx`, where -1 matches any index
+    /// and `a-b` a range of them.
+    fn matches(&self, idx: usize) -> bool {
+        match self {
+            Index::Int(-1) => true,
+            Index::Int(index) => *index == idx as i64,
+            Index::Str(index) => match index.split_once('-') {
+                Some((start, end)) => match (start.trim().parse(), end.trim().parse()) {
+                    (Ok(start), Ok(end)) => (start..=end).contains(&idx),
+                    _ => false,
+                },
+                None => index.trim().parse::<usize>() == Ok(idx),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Indexes {
+    One(Index),
+    Many(Vec<Index>),
+}
+
+impl Default for Indexes {
+    fn default() -> Self {
+        Indexes::One(Index::Int(-1))
+    }
+}
+
+impl Indexes {
+    fn matches(&self, idx: usize) -> bool {
+        match self {
+            Indexes::One(index) => index.matches(idx),
+            Indexes::Many(indexes) => indexes.iter().any(|index| index.matches(idx)),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ExpectIndexes {
+    #[serde(default)]
+    data: Indexes,
+    #[serde(default)]
+    gas: Indexes,
+    #[serde(default)]
+    value: Indexes,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountResult {
+    #[serde(default)]
+    balance: Option<Value>,
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    nonce: Option<Value>,
+    #[serde(default)]
+    storage: HashMap<Value, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Expect {
+    #[serde(default)]
+    indexes: ExpectIndexes,
+    network: Vec<String>,
+    #[serde(default)]
+    result: HashMap<String, AccountResult>,
+    #[serde(default)]
+    expect_exception: Option<serde_yaml::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Data {
+    Code(String),
+    WithAccessList { data: String },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FillerTransaction {
+    data: Vec<Data>,
+    gas_limit: Vec<Value>,
+    #[serde(default)]
+    gas_price: Option<Value>,
+    #[serde(default)]
+    max_fee_per_gas: Option<Value>,
+    nonce: Value,
+    secret_key: String,
+    #[serde(default)]
+    to: String,
+    value: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Filler {
+    env: TestEnv,
+    pre: HashMap<String, AccountPre>,
+    transaction: FillerTransaction,
+    expect: Vec<Expect>,
+}
+
+/// Compiles the fillers of `source`, read from `path`, into the test cases of
+/// `fork`.  The JSON fillers are parsed as YAML, of which JSON is a subset.
+pub fn compile(path: &str, source: &str, fork: Fork) -> Result<Vec<StateTest>> {
+    let fillers: HashMap<String, Filler> =
+        serde_yaml::from_str(source).with_context(|| format!("parsing {}", path))?;
+
+    let mut state_tests = Vec::new();
+    for (name, filler) in fillers {
+        let env = filler.env.to_env()?;
+        let pre = to_pre(&filler.pre)?;
+        let tx = &filler.transaction;
+        let secret_key = parse_bytes(&tx.secret_key)?;
+        let from = sender(&secret_key)?;
+        let to = to_recipient(&tx.to)?;
+        let nonce = parse_u256(&tx.nonce)?;
+        let gas_price = tx
+            .gas_price
+            .as_ref()
+            .or(tx.max_fee_per_gas.as_ref())
+            .map(parse_u256)
+            .transpose()?
+            .unwrap_or_default();
+
+        for (data_idx, data) in tx.data.iter().enumerate() {
+            let data = match data {
+                Data::Code(data) | Data::WithAccessList { data } => parse_code(data)?,
+            };
+            for (gas_idx, gas_limit) in tx.gas_limit.iter().enumerate() {
+                for (value_idx, value) in tx.value.iter().enumerate() {
+                    // The first expectation matching the indexes and the fork
+                    // applies, the other combinations have no test case.
+                    let expect = match filler.expect.iter().find(|expect| {
+                        expect.indexes.data.matches(data_idx)
+                            && expect.indexes.gas.matches(gas_idx)
+                            && expect.indexes.value.matches(value_idx)
+                            && expect.network.iter().any(|network| fork.matches(network))
+                    }) {
+                        Some(expect) => expect,
+                        None => continue,
+                    };
+
+                    state_tests.push(StateTest {
+                        path: path.to_string(),
+                        id: format!("{}_d{}_g{}_v{}", name, data_idx, gas_idx, value_idx),
+                        env: env.clone(),
+                        from,
+                        to,
+                        gas_limit: parse_u64(gas_limit)?,
+                        gas_price,
+                        nonce,
+                        value: parse_u256(value)?,
+                        data: data.clone(),
+                        pre: pre.clone(),
+                        result: to_result(&expect.result)?,
+                        exception: expect.expect_exception.is_some(),
+                    });
+                }
+            }
+        }
+    }
+    state_tests.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Ok(state_tests)
+}
+
+fn to_result(result: &HashMap<String, AccountResult>) -> Result<HashMap<Address, AccountMatch>> {
+    result
+        .iter()
+        .map(|(address, account)| {
+            let address = parse_address(address)?;
+            let account = AccountMatch {
+                balance: account.balance.as_ref().map(parse_u256).transpose()?,
+                code: account.code.as_deref().map(parse_code).transpose()?,
+                nonce: account.nonce.as_ref().map(parse_u256).transpose()?,
+                storage: account
+                    .storage
+                    .iter()
+                    .map(|(key, value)| Ok((parse_u256(key)?, parse_u256(value)?)))
+                    .collect::<Result<_>>()?,
+            };
+            Ok((address, account))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use eth_types::Word;
+    use pretty_assertions::assert_eq;
+
+    const FILLER: &str = r#"
+addFiller:
+  env:
+    currentCoinbase: 2adc25665018aa1fe0e6bc666dac8fc2697ff9ba
+    currentDifficulty: 0x20000
+    currentGasLimit: 100000000
+    currentNumber: 1
+    currentTimestamp: 1000
+    currentBaseFee: 10
+  pre:
+    cccccccccccccccccccccccccccccccccccccccc:
+      balance: 1000000000000000000
+      code: :raw 0x600160010160005500
+      nonce: 0
+      storage: {}
+    a94f5374fce5edbc8e2a8697c15331677e6ebf0b:
+      balance: 1000000000000000000
+      code: ''
+      nonce: 0
+      storage: {}
+  transaction:
+    data:
+    - ''
+    - :label two :raw 0x02
+    gasLimit:
+    - 80000000
+    gasPrice: 10
+    nonce: 0
+    to: cccccccccccccccccccccccccccccccccccccccc
+    secretKey: 45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8
+    value:
+    - 1
+  expect:
+  - indexes:
+      data: 0
+      gas: -1
+      value: -1
+    network:
+    - '>=London'
+    result:
+      cccccccccccccccccccccccccccccccccccccccc:
+        storage:
+          0: 2
+  - indexes:
+      data: 1
+    network:
+    - 'Berlin'
+    result: {}
+"#;
+
+    #[test]
+    fn compile_filler() -> Result<()> {
+        let tests = compile("addFiller.yml", FILLER, Fork::London)?;
+        assert_eq!(tests.len(), 1);
+        let test = &tests[0];
+        assert_eq!(test.id, "addFiller_d0_g0_v0");
+        assert_eq!(test.gas_limit, 80000000);
+        let address = parse_address("cccccccccccccccccccccccccccccccccccccccc")?;
+        assert_eq!(test.pre[&address].code.len(), 9);
+        assert_eq!(
+            test.result[&address].storage,
+            HashMap::from([(Word::zero(), Word::from(2))])
+        );
+
+        let tests = compile("addFiller.yml", FILLER, Fork::Berlin)?;
+        assert_eq!(tests.len(), 1);
+        assert_eq!(tests[0].id, "addFiller_d1_g0_v0");
+        assert_eq!(tests[0].data.to_vec(), vec![0x02]);
+        Ok(())
+    }
+}
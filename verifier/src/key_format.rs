@@ -0,0 +1,173 @@
+//! Versioned serialization of the verifying keys, so that the provers can
+//! reuse the keys generated by a previous run instead of redoing the keygen at
+//! each start.
+//!
+//! A serialized key starts with a [`KeyHeader`] encoded with bincode, which
+//! identifies the shape of the circuit of the key: the [`KEY_FORMAT_VERSION`],
+//! the name of the circuit, its degree, the hash of the capacities of the
+//! circuits and the hardfork of the blocks it proves.  The header is checked
+//! before decoding the key written by `VerifyingKey::write`, so that the key
+//! of another shape is rejected instead of being misread.
+//!
+//! The proving keys can't be serialized with the halo2 version of the
+//! workspace, which has no `ProvingKey::write` and keeps the fields of the key
+//! private.  They are derived from the verifying keys by `keygen_pk`, and kept
+//! in memory by the prover for the next proofs of the same circuit.
+
+use crate::proof_format::{circuits_params_hash, FormatError};
+use eth_types::{evm_types::Hardfork, CircuitsParams};
+use halo2_proofs::{
+    pairing::bn256::{Fr, G1Affine},
+    plonk::{Circuit, VerifyingKey},
+    poly::commitment::Params,
+};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// Version of the serialization format of the keys, bumped independently of
+/// the one of the proofs.  The version 2 adds the hardfork to the header.
+pub const KEY_FORMAT_VERSION: u32 = 2;
+
+/// Header of a serialized verifying key.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyHeader {
+    /// Version of the format
+    pub version: u32,
+    /// Name of the circuit
+    pub circuit: String,
+    /// The circuit has `2^k` rows
+    pub k: u32,
+    /// Hash of the capacities of the circuits, see [`circuits_params_hash`]
+    pub params_hash: [u8; 32],
+    /// Hardfork of the blocks proven by the circuit
+    pub hardfork: Hardfork,
+}
+
+impl KeyHeader {
+    /// Returns the header of the current version of the format.
+    pub fn new(
+        circuit: &str,
+        k: u32,
+        circuits_params: &CircuitsParams,
+        hardfork: Hardfork,
+    ) -> Self {
+        Self {
+            version: KEY_FORMAT_VERSION,
+            circuit: circuit.to_string(),
+            k,
+            params_hash: circuits_params_hash(circuits_params),
+            hardfork,
+        }
+    }
+}
+
+/// Writes the verifying key `vk` of the circuit of `header`, preceded by the
+/// header.
+pub fn write_vk<W: Write>(
+    writer: &mut W,
+    header: &KeyHeader,
+    vk: &VerifyingKey<G1Affine>,
+) -> Result<(), FormatError> {
+    bincode::serialize_into(&mut *writer, header)?;
+    vk.write(writer)?;
+    Ok(())
+}
+
+/// Reads the verifying key of the circuit `C` written by [`write_vk`], checking
+/// that its header is `header`.
+pub fn read_vk<R: Read, C: Circuit<Fr>>(
+    reader: &mut R,
+    header: &KeyHeader,
+    params: &Params<G1Affine>,
+) -> Result<VerifyingKey<G1Affine>, FormatError> {
+    read_header(reader, header)?;
+    Ok(VerifyingKey::read::<_, C>(reader, params)?)
+}
+
+/// Reads the header of a serialized key, checking that it's `header`.
+fn read_header<R: Read>(reader: &mut R, header: &KeyHeader) -> Result<(), FormatError> {
+    // The version is the first field, decoded before the others.  The keys of
+    // the version 1 don't identify their hardfork, and are generated again.
+    let version: u32 = bincode::deserialize_from(&mut *reader)?;
+    if version != KEY_FORMAT_VERSION {
+        return Err(FormatError::UnsupportedVersion(version));
+    }
+    let (circuit, k, params_hash, hardfork) = bincode::deserialize_from(&mut *reader)?;
+    let read_header = KeyHeader {
+        version,
+        circuit,
+        k,
+        params_hash,
+        hardfork,
+    };
+    if read_header != *header {
+        return Err(FormatError::KeyMismatch);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_header, FormatError, KeyHeader, KEY_FORMAT_VERSION};
+    use eth_types::{evm_types::Hardfork, CircuitsParams};
+
+    fn header() -> KeyHeader {
+        KeyHeader {
+            version: KEY_FORMAT_VERSION,
+            circuit: "evm".to_string(),
+            k: 3,
+            params_hash: [0x11; 32],
+            hardfork: Hardfork::London,
+        }
+    }
+
+    // The encoding of the header of the version 2 of the format, which must
+    // stay readable.
+    const HEADER_V2: &str = concat!(
+        "02000000",
+        "0300000000000000",
+        "65766d",
+        "03000000",
+        "1111111111111111111111111111111111111111111111111111111111111111",
+        "01000000",
+    );
+
+    #[test]
+    fn header_compatibility() {
+        let bytes = hex::decode(HEADER_V2).unwrap();
+        assert_eq!(bincode::serialize(&header()).unwrap(), bytes);
+        assert!(read_header(&mut &bytes[..], &header()).is_ok());
+    }
+
+    #[test]
+    fn header_mismatch() {
+        let mut bytes = hex::decode(HEADER_V2).unwrap();
+        // The key of another degree, capacities or hardfork.
+        let other_header = KeyHeader { k: 4, ..header() };
+        assert!(matches!(
+            read_header(&mut &bytes[..], &other_header),
+            Err(FormatError::KeyMismatch)
+        ));
+        let other_header = KeyHeader::new("evm", 3, &CircuitsParams::default(), Hardfork::London);
+        assert!(matches!(
+            read_header(&mut &bytes[..], &other_header),
+            Err(FormatError::KeyMismatch)
+        ));
+        let other_header = KeyHeader {
+            hardfork: Hardfork::Shanghai,
+            ..header()
+        };
+        assert!(matches!(
+            read_header(&mut &bytes[..], &other_header),
+            Err(FormatError::KeyMismatch)
+        ));
+        // The version 1 of the format, without the hardfork, and a later one.
+        for version in [1, 3] {
+            bytes[0] = version;
+            assert!(matches!(
+                read_header(&mut &bytes[..], &header()),
+                Err(FormatError::UnsupportedVersion(v)) if v == version as u32
+            ));
+        }
+    }
+}
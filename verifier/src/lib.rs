@@ -0,0 +1,311 @@
+//! # ZKEVM-Verifier
+//!
+//! Verification of the block proofs created by the prover in the aggregate
+//! mode, which prove the super circuit: the deserialization of its verifying
+//! key, the reconstruction of the public inputs from the public data of the
+//! block, and the verification of the proof.
+//!
+//! The randomness of the random linear combinations of the circuits is derived
+//! by [`block_randomness`] from the verifying key, the public data of the block
+//! and the capacities of the circuits, instead of being read from the proof.
+//! It is still known to the prover before it commits to the witnesses, as it
+//! isn't a challenge of the transcript squeezed after the commitments of the
+//! advice columns: a prover which can pick the witnesses after the randomness,
+//! e.g. by grinding over the values of an RLC, isn't ruled out.  Deriving it
+//! from the transcript requires the challenge API of a later halo2 version.
+//!
+//! The crate only depends on eth-types and halo2, the public data of the
+//! blocks being defined in [`eth_types::public_data`], so that it builds for
+//! wasm32, which `make test_verifier_wasm` checks.  Decoding a verifying key
+//! takes the `configure` of its circuit, so [`read_vk`] is generic over the
+//! circuit, given by the caller.
+
+#![cfg_attr(docsrs, feature(doc_cfg))]
+// Catch documentation errors caused by code changes.
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+#![deny(clippy::debug_assert_with_mut_call)]
+
+pub mod key_format;
+pub mod proof_format;
+
+use eth_types::{public_data::PublicData, CircuitsParams};
+use halo2_proofs::{
+    arithmetic::{BaseExt, FieldExt},
+    pairing::bn256::{Bn256, Fr, G1Affine},
+    plonk::{verify_proof, Circuit, SingleVerifier, VerifyingKey},
+    poly::commitment::{Params, ParamsVerifier},
+    transcript::{Blake2bRead, Challenge255},
+};
+use proof_format::{circuits_params_hash, FormatError, VersionedProof};
+use sha3::{Digest, Keccak512};
+use std::fmt;
+
+/// Maximum number of withdrawals of the blocks proven by the super circuit.
+pub const MAX_WITHDRAWALS: usize = 1;
+
+/// Name of the circuit of the block proofs in a [`VersionedProof`].
+pub const BLOCK_CIRCUIT_NAME: &str = "super";
+
+/// Number of instance columns of the block circuit holding the powers of the
+/// randomness, the first one holding the randomness.
+pub const NUM_RANDOMNESS_POWERS: usize = 63;
+
+/// Number of rows at the end of the instance columns of the block circuit which
+/// aren't assigned, reserved for the blinding factors.
+pub const NUM_UNUSABLE_ROWS: usize = 64;
+
+/// Error of the verification of a block proof.
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The proof could not be decoded.
+    Format(FormatError),
+    /// The verifying key could not be decoded.
+    VerifyingKey(std::io::Error),
+    /// The proof is a proof of another circuit.
+    Circuit(String),
+    /// The proof was created with other capacities of the circuits.
+    ParamsHash,
+    /// The public inputs of the proof aren't the ones of the block.
+    Instance,
+    /// The proof is invalid.
+    Proof(halo2_proofs::plonk::Error),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Format(err) => write!(f, "invalid proof encoding: {}", err),
+            Self::VerifyingKey(err) => write!(f, "invalid verifying key: {}", err),
+            Self::Circuit(circuit) => write!(f, "proof of the {} circuit", circuit),
+            Self::ParamsHash => write!(f, "proof created with other circuits params"),
+            Self::Instance => write!(f, "public inputs of another block"),
+            Self::Proof(err) => write!(f, "invalid proof: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl From<FormatError> for VerifyError {
+    fn from(err: FormatError) -> Self {
+        Self::Format(err)
+    }
+}
+
+/// Decodes the verifying key of the block circuit `C`, written by
+/// `VerifyingKey::write`.
+pub fn read_vk<C: Circuit<Fr>>(
+    bytes: &[u8],
+    params: &Params<G1Affine>,
+) -> Result<VerifyingKey<G1Affine>, VerifyError> {
+    VerifyingKey::read::<_, C>(&mut &bytes[..], params).map_err(VerifyError::VerifyingKey)
+}
+
+/// Randomness with which the public data is encoded in the seed of the
+/// [`block_randomness`], which can't depend on the derived randomness.
+const SEED_RANDOMNESS: u64 = 0x100;
+
+/// Returns the randomness of the random linear combinations of the proof of
+/// the block of `public_data`, with the verifying key `vk` of the block circuit
+/// with the capacities `circuits_params`.  It's the keccak-512 hash of the
+/// verifying key, the raw public inputs of the block and the hash of the
+/// capacities, reduced into the field.
+pub fn block_randomness(
+    vk: &VerifyingKey<G1Affine>,
+    public_data: &PublicData,
+    circuits_params: &CircuitsParams,
+) -> Fr {
+    let mut vk_bytes = vec![];
+    vk.write(&mut vk_bytes)
+        .expect("write the verifying key into memory");
+    randomness_from_seed(&vk_bytes, public_data, circuits_params)
+}
+
+/// Returns the [`block_randomness`] of the verifying key of bytes `vk_bytes`.
+fn randomness_from_seed(
+    vk_bytes: &[u8],
+    public_data: &PublicData,
+    circuits_params: &CircuitsParams,
+) -> Fr {
+    let mut hasher = Keccak512::new();
+    hasher.update(vk_bytes);
+    hasher.update(public_data.rpi_bytes(
+        1,
+        circuits_params.max_txs,
+        circuits_params.max_calldata,
+        MAX_WITHDRAWALS,
+        Fr::from(SEED_RANDOMNESS),
+    ));
+    hasher.update(circuits_params_hash(circuits_params));
+    Fr::from_bytes_wide(&hasher.finalize().into())
+}
+
+/// Returns the public inputs of the proof of the block of `public_data` with
+/// `2^k` rows, `randomness` and the capacities `circuits_params`: the columns
+/// of the powers of the randomness, the empty column of the tx circuit and the
+/// digest of the raw public inputs.
+pub fn block_instance(
+    k: u32,
+    randomness: Fr,
+    public_data: &PublicData,
+    circuits_params: &CircuitsParams,
+) -> Vec<Vec<Fr>> {
+    let mut instance: Vec<Vec<Fr>> = (1..NUM_RANDOMNESS_POWERS + 1)
+        .map(|exp| vec![randomness.pow(&[exp as u64, 0, 0, 0]); (1 << k) - NUM_UNUSABLE_ROWS])
+        .collect();
+    instance.push(vec![]);
+    instance.push(
+        public_data
+            .digest(
+                1,
+                circuits_params.max_txs,
+                circuits_params.max_calldata,
+                MAX_WITHDRAWALS,
+                randomness,
+            )
+            .to_vec(),
+    );
+    instance
+}
+
+/// Checks that `proof` is a block proof created with `circuits_params`, whose
+/// public inputs are the ones of the block of `public_data` with `randomness`.
+fn check_block_proof(
+    proof: &VersionedProof,
+    randomness: Fr,
+    circuits_params: &CircuitsParams,
+    public_data: &PublicData,
+) -> Result<(), VerifyError> {
+    if proof.circuit != BLOCK_CIRCUIT_NAME {
+        return Err(VerifyError::Circuit(proof.circuit.clone()));
+    }
+    if proof.params_hash != circuits_params_hash(circuits_params) {
+        return Err(VerifyError::ParamsHash);
+    }
+    if proof.instance != block_instance(proof.k, randomness, public_data, circuits_params) {
+        return Err(VerifyError::Instance);
+    }
+    Ok(())
+}
+
+/// Verifies that `proof` proves the block of `public_data` with the verifying
+/// key `vk` of the block circuit with the capacities `circuits_params`.
+pub fn verify_block_proof(
+    params: &ParamsVerifier<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    circuits_params: &CircuitsParams,
+    public_data: &PublicData,
+    proof: &VersionedProof,
+) -> Result<(), VerifyError> {
+    let randomness = block_randomness(vk, public_data, circuits_params);
+    check_block_proof(proof, randomness, circuits_params, public_data)?;
+
+    let instance = proof.instance.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof.proof[..]);
+    verify_proof(
+        params,
+        vk,
+        SingleVerifier::new(params),
+        &[&instance],
+        &mut transcript,
+    )
+    .map_err(VerifyError::Proof)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        block_instance, check_block_proof, proof_format::VersionedProof, randomness_from_seed,
+        VerifyError, BLOCK_CIRCUIT_NAME,
+    };
+    use eth_types::public_data::{BlockPublicData, PublicData};
+    use eth_types::CircuitsParams;
+    use halo2_proofs::pairing::bn256::Fr;
+
+    fn public_data(state_root: u8) -> PublicData {
+        PublicData {
+            blocks: vec![BlockPublicData {
+                state_root: [state_root; 32].into(),
+                ..Default::default()
+            }],
+        }
+    }
+
+    fn block_proof(circuits_params: &CircuitsParams, randomness: Fr) -> VersionedProof {
+        let k = 7;
+        VersionedProof::new(
+            BLOCK_CIRCUIT_NAME,
+            k,
+            circuits_params,
+            vec![],
+            block_instance(k, randomness, &public_data(1), circuits_params),
+        )
+    }
+
+    #[test]
+    fn block_randomness_seed() {
+        let circuits_params = CircuitsParams::default();
+        let randomness = randomness_from_seed(&[1], &public_data(1), &circuits_params);
+        assert_eq!(
+            randomness,
+            randomness_from_seed(&[1], &public_data(1), &circuits_params)
+        );
+        // Another verifying key, block or capacities.
+        assert_ne!(
+            randomness,
+            randomness_from_seed(&[2], &public_data(1), &circuits_params)
+        );
+        assert_ne!(
+            randomness,
+            randomness_from_seed(&[1], &public_data(2), &circuits_params)
+        );
+        let other_params = CircuitsParams {
+            max_txs: 2,
+            ..circuits_params
+        };
+        assert_ne!(
+            randomness,
+            randomness_from_seed(&[1], &public_data(1), &other_params)
+        );
+    }
+
+    #[test]
+    fn check_block_proofs() {
+        let circuits_params = CircuitsParams::default();
+        let randomness = Fr::from(0x100);
+        let proof = block_proof(&circuits_params, randomness);
+        assert!(check_block_proof(&proof, randomness, &circuits_params, &public_data(1)).is_ok());
+
+        // The public inputs with another randomness.
+        assert!(matches!(
+            check_block_proof(&proof, Fr::from(0x101), &circuits_params, &public_data(1)),
+            Err(VerifyError::Instance)
+        ));
+
+        // The public inputs of another block.
+        assert!(matches!(
+            check_block_proof(&proof, randomness, &circuits_params, &public_data(2)),
+            Err(VerifyError::Instance)
+        ));
+        // Other capacities.
+        let other_params = CircuitsParams {
+            max_txs: 2,
+            ..circuits_params
+        };
+        assert!(matches!(
+            check_block_proof(&proof, randomness, &other_params, &public_data(1)),
+            Err(VerifyError::ParamsHash)
+        ));
+        // A proof of another circuit.
+        let evm_proof = VersionedProof {
+            circuit: "evm".to_string(),
+            ..proof
+        };
+        assert!(matches!(
+            check_block_proof(&evm_proof, randomness, &circuits_params, &public_data(1)),
+            Err(VerifyError::Circuit(_))
+        ));
+    }
+}
@@ -0,0 +1,362 @@
+//! Versioned serialization of the proofs with their public inputs, so that the
+//! proofs created by one version of the prover can be consumed by external
+//! verifier services and by the
+//! [`verify_block_proof`](crate::verify_block_proof) of this crate.
+//!
+//! A [`VersionedProof`] has two encodings of the same fields: a compact binary
+//! one with bincode, in which the bytes and the field elements are raw, and a
+//! JSON one in which they are `0x` prefixed hex strings, the field elements
+//! being big endian.  Both start with the [`FORMAT_VERSION`], which is bumped
+//! on any change of the layout and checked before decoding the other fields.
+
+use eth_types::CircuitsParams;
+use halo2_proofs::pairing::{bn256::Fr, group::ff::PrimeField};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::fmt;
+use std::{fs, path::Path};
+
+/// Version of the serialization format.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Error of the decoding of a [`VersionedProof`].
+#[derive(Debug)]
+pub enum FormatError {
+    /// The file could not be read or written.
+    Io(std::io::Error),
+    /// The binary encoding is invalid.
+    Bincode(bincode::Error),
+    /// The JSON encoding is invalid.
+    Json(serde_json::Error),
+    /// The proof or the key was encoded with another version of the format.
+    UnsupportedVersion(u32),
+    /// The key was written for another circuit, degree, capacities or
+    /// hardfork.
+    KeyMismatch,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io: {}", err),
+            Self::Bincode(err) => write!(f, "bincode: {}", err),
+            Self::Json(err) => write!(f, "json: {}", err),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {}", version)
+            }
+            Self::KeyMismatch => write!(f, "key of another circuit shape"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<std::io::Error> for FormatError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<bincode::Error> for FormatError {
+    fn from(err: bincode::Error) -> Self {
+        Self::Bincode(err)
+    }
+}
+
+impl From<serde_json::Error> for FormatError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+/// Returns the keccak hash of the capacities of the circuits, which fix the
+/// shape of the proof, so that a verifier can check that a proof was created
+/// with the capacities of its verifying key.
+pub fn circuits_params_hash(circuits_params: &CircuitsParams) -> [u8; 32] {
+    let encoded = bincode::serialize(circuits_params).expect("serialize the circuits params");
+    Keccak256::digest(&encoded).into()
+}
+
+/// A proof of a circuit with its public inputs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionedProof {
+    /// Version of the format
+    pub version: u32,
+    /// Name of the circuit
+    pub circuit: String,
+    /// The circuit has `2^k` rows
+    pub k: u32,
+    /// Hash of the capacities of the circuits, see [`circuits_params_hash`]
+    #[serde(with = "hash")]
+    pub params_hash: [u8; 32],
+    /// The proof
+    #[serde(with = "bytes")]
+    pub proof: Vec<u8>,
+    /// The instance columns
+    #[serde(with = "instance")]
+    pub instance: Vec<Vec<Fr>>,
+}
+
+/// The version, decoded before the other fields.
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+impl VersionedProof {
+    /// Returns the proof of the current version of the format.
+    pub fn new(
+        circuit: &str,
+        k: u32,
+        circuits_params: &CircuitsParams,
+        proof: Vec<u8>,
+        instance: Vec<Vec<Fr>>,
+    ) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            circuit: circuit.to_string(),
+            k,
+            params_hash: circuits_params_hash(circuits_params),
+            proof,
+            instance,
+        }
+    }
+
+    /// Returns the binary encoding of the proof.
+    pub fn to_bincode(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("serialize the proof")
+    }
+
+    /// Decodes a proof from its binary encoding.
+    pub fn from_bincode(bytes: &[u8]) -> Result<Self, FormatError> {
+        // The version is the first field, encoded in 4 little endian bytes.
+        let version: u32 = bincode::deserialize(bytes)?;
+        if version != FORMAT_VERSION {
+            return Err(FormatError::UnsupportedVersion(version));
+        }
+        Ok(bincode::deserialize(bytes)?)
+    }
+
+    /// Returns the JSON encoding of the proof.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("serialize the proof")
+    }
+
+    /// Decodes a proof from its JSON encoding.
+    pub fn from_json(json: &str) -> Result<Self, FormatError> {
+        let Version { version } = serde_json::from_str(json)?;
+        if version != FORMAT_VERSION {
+            return Err(FormatError::UnsupportedVersion(version));
+        }
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Writes the proof to `path`, in JSON if its extension is `json` and in
+    /// binary otherwise.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), FormatError> {
+        let path = path.as_ref();
+        if is_json(path) {
+            fs::write(path, self.to_json())?;
+        } else {
+            fs::write(path, self.to_bincode())?;
+        }
+        Ok(())
+    }
+
+    /// Reads a proof from `path`, in JSON if its extension is `json` and in
+    /// binary otherwise.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, FormatError> {
+        let path = path.as_ref();
+        if is_json(path) {
+            Self::from_json(&fs::read_to_string(path)?)
+        } else {
+            Self::from_bincode(&fs::read(path)?)
+        }
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension == "json")
+}
+
+/// Serialization of bytes, as a hex string in the human readable formats.
+mod bytes {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&format!("0x{}", hex::encode(bytes)))
+        } else {
+            serializer.serialize_bytes(bytes)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        if deserializer.is_human_readable() {
+            let hex = String::deserialize(deserializer)?;
+            let hex = hex
+                .strip_prefix("0x")
+                .ok_or_else(|| D::Error::custom("missing 0x prefix"))?;
+            hex::decode(hex).map_err(D::Error::custom)
+        } else {
+            Vec::<u8>::deserialize(deserializer)
+        }
+    }
+}
+
+/// Serialization of a hash: 32 raw bytes, or a hex string in the human
+/// readable formats.
+mod hash {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(hash: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            super::bytes::serialize(hash, serializer)
+        } else {
+            hash.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        if deserializer.is_human_readable() {
+            super::bytes::deserialize(deserializer)?
+                .try_into()
+                .map_err(|_| D::Error::custom("expected 32 bytes"))
+        } else {
+            <[u8; 32]>::deserialize(deserializer)
+        }
+    }
+}
+
+/// Serialization of the instance columns: the little endian representations of
+/// their field elements, or their big endian hex strings in the human readable
+/// formats.
+mod instance {
+    use super::{Fr, PrimeField};
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Field element of an instance column.
+    struct Value(Fr);
+
+    impl Serialize for Value {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut repr = self.0.to_repr();
+            if serializer.is_human_readable() {
+                repr.reverse();
+            }
+            super::hash::serialize(&repr, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Value {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let human_readable = deserializer.is_human_readable();
+            let mut repr = super::hash::deserialize(deserializer)?;
+            if human_readable {
+                repr.reverse();
+            }
+            Option::from(Fr::from_repr(repr))
+                .map(Value)
+                .ok_or_else(|| D::Error::custom("non canonical field element"))
+        }
+    }
+
+    pub fn serialize<S: Serializer>(
+        instance: &[Vec<Fr>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            instance
+                .iter()
+                .map(|column| column.iter().map(|value| Value(*value)).collect::<Vec<_>>()),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<Fr>>, D::Error> {
+        let instance = Vec::<Vec<Value>>::deserialize(deserializer)?;
+        Ok(instance
+            .into_iter()
+            .map(|column| column.into_iter().map(|value| value.0).collect())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FormatError, VersionedProof, FORMAT_VERSION};
+    use halo2_proofs::pairing::bn256::Fr;
+
+    fn proof() -> VersionedProof {
+        VersionedProof {
+            version: FORMAT_VERSION,
+            circuit: "evm".to_string(),
+            k: 3,
+            params_hash: [0x11; 32],
+            proof: vec![1, 2, 3],
+            instance: vec![vec![Fr::from(5)], vec![-Fr::one()]],
+        }
+    }
+
+    // The encodings of the version 1 of the format, which must stay readable.
+    const BINCODE_V1: &str = concat!(
+        "01000000",
+        "0300000000000000",
+        "65766d",
+        "03000000",
+        "1111111111111111111111111111111111111111111111111111111111111111",
+        "0300000000000000",
+        "010203",
+        "0200000000000000",
+        "0100000000000000",
+        "0500000000000000000000000000000000000000000000000000000000000000",
+        "0100000000000000",
+        "000000f093f5e1439170b97948e833285d588181b64550b829a031e1724e6430",
+    );
+    const JSON_V1: &str = concat!(
+        r#"{"version":1,"circuit":"evm","k":3,"#,
+        r#""params_hash":"0x1111111111111111111111111111111111111111111111111111111111111111","#,
+        r#""proof":"0x010203","#,
+        r#""instance":[["0x0000000000000000000000000000000000000000000000000000000000000005"],"#,
+        r#"["0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000"]]}"#,
+    );
+
+    #[test]
+    fn bincode_compatibility() {
+        let bytes = hex::decode(BINCODE_V1).unwrap();
+        assert_eq!(proof().to_bincode(), bytes);
+        assert_eq!(VersionedProof::from_bincode(&bytes).unwrap(), proof());
+    }
+
+    #[test]
+    fn json_compatibility() {
+        assert_eq!(proof().to_json(), JSON_V1);
+        assert_eq!(VersionedProof::from_json(JSON_V1).unwrap(), proof());
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut bytes = hex::decode(BINCODE_V1).unwrap();
+        bytes[0] = 2;
+        assert!(matches!(
+            VersionedProof::from_bincode(&bytes),
+            Err(FormatError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            VersionedProof::from_json(&JSON_V1.replace(r#""version":1"#, r#""version":2"#)),
+            Err(FormatError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn non_canonical_field_element() {
+        // The modulus is not a canonical field element.
+        let json = JSON_V1.replace("f0000000", "f0000001");
+        assert!(matches!(
+            VersionedProof::from_json(&json),
+            Err(FormatError::Json(_))
+        ));
+    }
+}
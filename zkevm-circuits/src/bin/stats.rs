@@ -0,0 +1,11 @@
+use bus_mapping::circuit_input_builder::CircuitsParams;
+use zkevm_circuits::stats::circuit_stats;
+
+/// This utility prints the columns, lookups and degrees of each circuit, and
+/// the minimum `k` with the default capacities.
+/// Can be invoked with: stats
+fn main() {
+    for stats in circuit_stats(&CircuitsParams::default()) {
+        println!("{}", stats);
+    }
+}
@@ -1,33 +1,32 @@
 use crate::{
     evm_circuit::{
-        table::BytecodeFieldTag,
+        param::N_BYTES_WORD,
+        table::{BytecodeFieldTag, LookupTable},
         util::{
-            and, constraint_builder::BaseConstraintBuilder, not, or, select,
+            and, constraint_builder::BaseConstraintBuilder, not, or, rlc, select,
             RandomLinearCombination,
         },
     },
+    table::KeccakTable,
     util::Expr,
 };
 use bus_mapping::evm::OpcodeId;
 use eth_types::Field;
-use gadgets::{
-    evm_word::encode,
-    is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction},
-};
+use gadgets::is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction};
 use halo2_proofs::{
     circuit::{Layouter, Region},
     plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, VirtualCells},
     poly::Rotation,
 };
 use keccak256::plain::Keccak;
-use std::{convert::TryInto, vec};
+use std::{convert::TryInto, marker::PhantomData, vec};
 
-use super::param::{KECCAK_WIDTH, PUSH_TABLE_WIDTH};
+use super::param::PUSH_TABLE_WIDTH;
 
 /// Public data for the bytecode
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct BytecodeRow<F: Field> {
-    hash: F,
+    pub(crate) hash: F,
     tag: F,
     index: F,
     is_code: F,
@@ -37,13 +36,12 @@ pub(crate) struct BytecodeRow<F: Field> {
 /// Unrolled bytecode
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct UnrolledBytecode<F: Field> {
-    bytes: Vec<u8>,
-    rows: Vec<BytecodeRow<F>>,
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) rows: Vec<BytecodeRow<F>>,
 }
 
 #[derive(Clone, Debug)]
 pub struct Config<F> {
-    r: F,
     minimum_rows: usize,
     q_enable: Column<Fixed>,
     q_first: Column<Fixed>,
@@ -64,19 +62,25 @@ pub struct Config<F> {
     length_inv: Column<Advice>,
     length_is_zero: IsZeroConfig<F>,
     push_table: [Column<Fixed>; PUSH_TABLE_WIDTH],
-    keccak_table: [Column<Advice>; KECCAK_WIDTH],
+    keccak_table: KeccakTable,
+    _marker: PhantomData<F>,
 }
 
 impl<F: Field> Config<F> {
-    pub(crate) fn configure(meta: &mut ConstraintSystem<F>, r: F) -> Self {
+    /// Configure the bytecode circuit assigning the `bytecode_table` made of
+    /// `[hash, tag, index, is_code, value]`, and looking up the hash of each
+    /// bytecode in the `keccak_table`.
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        power_of_randomness: [Expression<F>; N_BYTES_WORD - 1],
+        bytecode_table: [Column<Advice>; 5],
+        keccak_table: KeccakTable,
+    ) -> Self {
+        let r = power_of_randomness[0].clone();
         let q_enable = meta.fixed_column();
         let q_first = meta.fixed_column();
         let q_last = meta.selector();
-        let hash = meta.advice_column();
-        let tag = meta.advice_column();
-        let index = meta.advice_column();
-        let is_code = meta.advice_column();
-        let value = meta.advice_column();
+        let [hash, tag, index, is_code, value] = bytecode_table;
         let push_rindex = meta.advice_column();
         let hash_rlc = meta.advice_column();
         let hash_length = meta.advice_column();
@@ -86,7 +90,6 @@ impl<F: Field> Config<F> {
         let push_rindex_inv = meta.advice_column();
         let length_inv = meta.advice_column();
         let push_table = array_init::array_init(|_| meta.fixed_column());
-        let keccak_table = array_init::array_init(|_| meta.advice_column());
 
         // A byte is an opcode when `push_rindex == 0` on the previous row,
         // else it's push data.
@@ -169,7 +172,7 @@ impl<F: Field> Config<F> {
             cb.require_equal(
                 "hash_rlc := hash_rlc_prev * r + byte",
                 meta.query_advice(hash_rlc, Rotation::cur()),
-                meta.query_advice(hash_rlc, Rotation::prev()) * r
+                meta.query_advice(hash_rlc, Rotation::prev()) * r.clone()
                     + meta.query_advice(value, Rotation::cur()),
             );
             cb.require_equal(
@@ -220,7 +223,7 @@ impl<F: Field> Config<F> {
                 cb.require_equal(
                     "if length == 0: hash == RLC(EMPTY_HASH, randomness)",
                     meta.query_advice(hash, Rotation::cur()),
-                    Expression::Constant(keccak(&[], r)),
+                    rlc::expr(&empty_hash(), &power_of_randomness),
                 );
             });
             // Conditions:
@@ -354,19 +357,17 @@ impl<F: Field> Config<F> {
                 meta.query_advice(is_final, Rotation::cur()),
                 not::expr(meta.query_advice(padding, Rotation::cur())),
             ]);
-            let lookup_columns = vec![hash_rlc, hash_length, hash];
-            let mut constraints = vec![];
-            for i in 0..KECCAK_WIDTH {
-                constraints.push((
-                    enable.clone() * meta.query_advice(lookup_columns[i], Rotation::cur()),
-                    meta.query_advice(keccak_table[i], Rotation::cur()),
-                ))
+            let mut constraints = vec![enable.clone()];
+            for column in [hash_rlc, hash_length, hash] {
+                constraints.push(enable.clone() * meta.query_advice(column, Rotation::cur()));
             }
             constraints
+                .into_iter()
+                .zip(keccak_table.table_exprs(meta))
+                .collect()
         });
 
         Config {
-            r,
             minimum_rows: meta.minimum_rows(),
             q_enable,
             q_first,
@@ -388,6 +389,7 @@ impl<F: Field> Config<F> {
             length_is_zero,
             push_table,
             keccak_table,
+            _marker: PhantomData,
         }
     }
 
@@ -396,6 +398,7 @@ impl<F: Field> Config<F> {
         mut layouter: impl Layouter<F>,
         size: usize,
         witness: &[UnrolledBytecode<F>],
+        randomness: F,
     ) -> Result<(), Error> {
         let push_rindex_is_zero_chip = IsZeroChip::construct(self.push_rindex_is_zero.clone());
         let length_is_zero_chip = IsZeroChip::construct(self.length_is_zero.clone());
@@ -426,7 +429,7 @@ impl<F: Field> Config<F> {
                             } else {
                                 push_rindex - 1
                             };
-                            hash_rlc = hash_rlc * self.r + row.value;
+                            hash_rlc = hash_rlc * randomness + row.value;
                         }
 
                         // Set the data for this row
@@ -560,11 +563,7 @@ impl<F: Field> Config<F> {
         Ok(())
     }
 
-    pub(crate) fn load(
-        &self,
-        layouter: &mut impl Layouter<F>,
-        bytecodes: &[UnrolledBytecode<F>],
-    ) -> Result<(), Error> {
+    pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
         // push table: BYTE -> NUM_PUSHED:
         // [0, OpcodeId::PUSH1[ -> 0
         // [OpcodeId::PUSH1, OpcodeId::PUSH32] -> [1..32]
@@ -588,37 +587,11 @@ impl<F: Field> Config<F> {
                 }
                 Ok(())
             },
-        )?;
-
-        // keccak table
-        layouter.assign_region(
-            || "keccak table",
-            |mut region| {
-                for (offset, bytecode) in bytecodes.iter().map(|v| v.bytes.clone()).enumerate() {
-                    let hash: F = keccak(&bytecode[..], self.r);
-                    let rlc: F = linear_combine(bytecode.clone(), self.r);
-                    let size = F::from(bytecode.len() as u64);
-                    for (name, column, value) in &[
-                        ("rlc", self.keccak_table[0], rlc),
-                        ("size", self.keccak_table[1], size),
-                        ("hash", self.keccak_table[2], hash),
-                    ] {
-                        region.assign_advice(
-                            || format!("Keccak table assign {} {}", name, offset),
-                            *column,
-                            offset,
-                            || Ok(*value),
-                        )?;
-                    }
-                }
-                Ok(())
-            },
-        )?;
-        Ok(())
+        )
     }
 }
 
-fn unroll<F: Field>(bytes: Vec<u8>, r: F) -> UnrolledBytecode<F> {
+pub(crate) fn unroll<F: Field>(bytes: Vec<u8>, r: F) -> UnrolledBytecode<F> {
     let hash = keccak(&bytes[..], r);
     let mut rows = vec![BytecodeRow::<F> {
         hash,
@@ -661,10 +634,26 @@ fn get_push_size(byte: u8) -> u64 {
     }
 }
 
+/// Returns the RLC of the digest of `msg` as a little endian word, as in the
+/// bytecode table of the EVM circuit.
 fn keccak<F: Field>(msg: &[u8], r: F) -> F {
     let mut keccak = Keccak::default();
     keccak.update(msg);
-    RandomLinearCombination::<F, 32>::random_linear_combine(keccak.digest().try_into().unwrap(), r)
+    let mut digest: [u8; 32] = keccak.digest().try_into().unwrap();
+    digest.reverse();
+    RandomLinearCombination::<F, 32>::random_linear_combine(digest, r)
+}
+
+/// Returns the bytes of the digest of the empty bytecode in little endian.
+fn empty_hash<F: Field>() -> Vec<Expression<F>> {
+    let mut keccak = Keccak::default();
+    keccak.update(&[]);
+    keccak
+        .digest()
+        .into_iter()
+        .rev()
+        .map(|byte| Expression::Constant(F::from(byte as u64)))
+        .collect()
 }
 
 fn into_words(message: &[u8]) -> Vec<u64> {
@@ -680,12 +669,8 @@ fn into_words(message: &[u8]) -> Vec<u64> {
     words
 }
 
-fn linear_combine<F: Field>(bytes: Vec<u8>, r: F) -> F {
-    encode(bytes.into_iter(), r)
-}
-
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use eth_types::{Bytecode, Word};
     use halo2_proofs::{
@@ -696,13 +681,13 @@ mod tests {
     };
 
     #[derive(Default)]
-    struct MyCircuit<F: Field> {
+    pub(crate) struct MyCircuit<F: Field> {
         bytecodes: Vec<UnrolledBytecode<F>>,
         size: usize,
     }
 
     impl<F: Field> MyCircuit<F> {
-        fn r() -> F {
+        pub(crate) fn r() -> F {
             F::from(123456)
         }
     }
@@ -716,7 +701,12 @@ mod tests {
         }
 
         fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            Config::configure(meta, MyCircuit::r())
+            let power_of_randomness = array_init::array_init(|exp| {
+                Expression::Constant(MyCircuit::r().pow(&[exp as u64 + 1, 0, 0, 0]))
+            });
+            let bytecode_table = [(); 5].map(|_| meta.advice_column());
+            let keccak_table = KeccakTable::construct(meta);
+            Config::configure(meta, power_of_randomness, bytecode_table, keccak_table)
         }
 
         fn synthesize(
@@ -724,13 +714,22 @@ mod tests {
             config: Self::Config,
             mut layouter: impl Layouter<F>,
         ) -> Result<(), Error> {
-            config.load(&mut layouter, &self.bytecodes)?;
-            config.assign(layouter, self.size, &self.bytecodes)?;
+            config.load(&mut layouter)?;
+            config.keccak_table.dev_load(
+                &mut layouter,
+                &self
+                    .bytecodes
+                    .iter()
+                    .map(|bytecode| bytecode.bytes.clone())
+                    .collect::<Vec<_>>(),
+                MyCircuit::r(),
+            )?;
+            config.assign(layouter, self.size, &self.bytecodes, MyCircuit::r())?;
             Ok(())
         }
     }
 
-    fn verify<F: Field>(k: u32, bytecodes: Vec<UnrolledBytecode<F>>, success: bool) {
+    pub(crate) fn verify<F: Field>(k: u32, bytecodes: Vec<UnrolledBytecode<F>>, success: bool) {
         let circuit = MyCircuit::<F> {
             bytecodes,
             size: 2usize.pow(k),
@@ -1,50 +1,40 @@
 //! The Copy circuit implements constraints and lookups for read-write steps for
-//! copied bytes while execution opcodes such as CALLDATACOPY, CODECOPY, LOGS,
-//! etc.
+//! copied bytes while execution opcodes such as CALLDATACOPY, CODECOPY,
+//! EXTCODECOPY, RETURNDATACOPY, LOGS, RETURN/REVERT and CREATE/CREATE2, where
+//! the source and destination of each copy event are one of memory, bytecode,
+//! tx calldata or tx log.
 
 use bus_mapping::circuit_input_builder::{CopyDataType, CopyEvent, CopyStep, NumberOrHash};
 use eth_types::{Field, ToAddress, ToScalar, U256};
 use gadgets::{
-    binary_number::{BinaryNumberChip, BinaryNumberConfig},
+    binary_number::BinaryNumberChip,
     less_than::{LtChip, LtConfig, LtInstruction},
     util::{and, not, or, Expr},
 };
 use halo2_proofs::{
     circuit::{Layouter, Region},
-    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, VirtualCells},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
     poly::Rotation,
 };
 
-use crate::evm_circuit::{
-    table::{BytecodeFieldTag, LookupTable, RwTableTag, TxContextFieldTag, TxLogFieldTag},
-    util::{constraint_builder::BaseConstraintBuilder, RandomLinearCombination},
-    witness::Block,
+use crate::{
+    evm_circuit::{
+        table::{BytecodeFieldTag, LookupTable, RwTableTag, TxContextFieldTag, TxLogFieldTag},
+        util::{constraint_builder::BaseConstraintBuilder, RandomLinearCombination},
+        witness::Block,
+    },
+    table::CopyTable,
 };
 
-/// The rw table shared between evm circuit and state circuit
+/// The copy circuit, which constrains the copy table to contain the
+/// read-write steps of every copy event.
 #[derive(Clone, Copy, Debug)]
 pub struct CopyCircuit<F> {
-    /// Whether the row is enabled or not.
-    pub q_enable: Column<Fixed>,
     /// Whether this row denotes a step. A read row is a step and a write row is
     /// not.
     pub q_step: Selector,
-    /// Whether the row is the first read-write pair for a copy event.
-    pub is_first: Column<Advice>,
     /// Whether the row is the last read-write pair for a copy event.
     pub is_last: Column<Advice>,
-    /// The relevant ID for the read-write row, represented as a random linear
-    /// combination. The ID may be one of the below:
-    /// 1. Call ID/Caller ID for CopyDataType::Memory
-    /// 2. RLC encoding of bytecode hash for CopyDataType::Bytecode
-    /// 3. Transaction ID for CopyDataType::TxCalldata, CopyDataType::TxLog
-    pub id: Column<Advice>,
-    /// The source/destination address for this copy step.
-    pub addr: Column<Advice>,
-    /// The end of the source buffer for the copy event.
-    pub src_addr_end: Column<Advice>,
-    /// The number of bytes left to be copied.
-    pub bytes_left: Column<Advice>,
     /// The value copied in this copy step.
     pub value: Column<Advice>,
     /// In case of a bytecode tag, this denotes whether or not the copied byte
@@ -52,68 +42,54 @@ pub struct CopyCircuit<F> {
     pub is_code: Column<Advice>,
     /// Whether the row is padding.
     pub is_pad: Column<Advice>,
-    /// The associated read-write counter for this row.
-    pub rw_counter: Column<Advice>,
-    /// Decrementing counter denoting reverse read-write counter.
-    pub rwc_inc_left: Column<Advice>,
-    /// Binary chip to constrain the copy table conditionally depending on the
-    /// current row's tag, whether it is Bytecode, Memory, TxCalldata or
-    /// TxLog.
-    pub tag: BinaryNumberConfig<CopyDataType, 3>,
+    /// The random linear combination of the values read up to this step.
+    pub value_acc: Column<Advice>,
     /// Lt chip to check: src_addr < src_addr_end.
     /// Since `src_addr` and `src_addr_end` are u64, 8 bytes are sufficient for
     /// the Lt chip.
     pub addr_lt_addr_end: LtConfig<F, 8>,
-}
-
-impl<F: Field> LookupTable<F> for CopyCircuit<F> {
-    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
-        vec![
-            meta.query_advice(self.is_first, Rotation::cur()),
-            meta.query_advice(self.id, Rotation::cur()), // src_id
-            self.tag.value(Rotation::cur())(meta),       // src_tag
-            meta.query_advice(self.id, Rotation::next()), // dst_id
-            self.tag.value(Rotation::next())(meta),      // dst_tag
-            meta.query_advice(self.addr, Rotation::cur()), // src_addr
-            meta.query_advice(self.src_addr_end, Rotation::cur()), // src_addr_end
-            meta.query_advice(self.addr, Rotation::next()), // dst_addr
-            meta.query_advice(self.bytes_left, Rotation::cur()), // length
-            meta.query_advice(self.rw_counter, Rotation::cur()), // rw_counter
-            meta.query_advice(self.rwc_inc_left, Rotation::cur()), // rwc_inc_left
-        ]
-    }
+    /// The copy table, looked up by the EVM circuit.
+    pub copy_table: CopyTable,
 }
 
 impl<F: Field> CopyCircuit<F> {
-    /// Configure the Copy Circuit constraining read-write steps and doing
-    /// appropriate lookups to the Tx Table, RW Table and Bytecode Table.
+    /// Configure the Copy Circuit constraining read-write steps of the copy
+    /// table and doing appropriate lookups to the Tx Table, RW Table and
+    /// Bytecode Table.
     pub fn configure(
         meta: &mut ConstraintSystem<F>,
         tx_table: &dyn LookupTable<F>,
         rw_table: &dyn LookupTable<F>,
         bytecode_table: &dyn LookupTable<F>,
+        copy_table: CopyTable,
+        randomness: Expression<F>,
     ) -> Self {
-        let q_enable = meta.fixed_column();
         let q_step = meta.complex_selector();
-        let is_first = meta.advice_column();
         let is_last = meta.advice_column();
-        let id = meta.advice_column();
-        let addr = meta.advice_column();
-        let src_addr_end = meta.advice_column();
-        let bytes_left = meta.advice_column();
         let value = meta.advice_column();
         let is_code = meta.advice_column();
         let is_pad = meta.advice_column();
-        let rw_counter = meta.advice_column();
-        let rwc_inc_left = meta.advice_column();
-
-        let tag = BinaryNumberChip::configure(meta, q_enable);
+        let value_acc = meta.advice_column();
+        let u8_table = meta.fixed_column();
+        let CopyTable {
+            q_enable,
+            is_first,
+            id,
+            tag,
+            addr,
+            src_addr_end,
+            bytes_left,
+            rw_counter,
+            rwc_inc_left,
+            rlc_acc,
+        } = copy_table;
 
         let addr_lt_addr_end = LtChip::configure(
             meta,
             |meta| meta.query_selector(q_step),
             |meta| meta.query_advice(addr, Rotation::cur()),
             |meta| meta.query_advice(src_addr_end, Rotation::cur()),
+            u8_table,
         );
 
         meta.create_gate("verify row", |meta| {
@@ -166,6 +142,11 @@ impl<F: Field> CopyCircuit<F> {
                     meta.query_advice(src_addr_end, Rotation::cur()),
                     meta.query_advice(src_addr_end, Rotation(2)),
                 );
+                cb.require_equal(
+                    "rows[0].rlc_acc == rows[2].rlc_acc",
+                    meta.query_advice(rlc_acc, Rotation::cur()),
+                    meta.query_advice(rlc_acc, Rotation(2)),
+                );
             });
 
             let rw_diff = and::expr([
@@ -243,6 +224,35 @@ impl<F: Field> CopyCircuit<F> {
                 meta.query_advice(is_pad, Rotation::next()),
             );
 
+            // Accumulate the random linear combination of the read values, which
+            // is exposed in the copy table when the destination is RlcAcc.
+            cb.condition(meta.query_advice(is_first, Rotation::cur()), |cb| {
+                cb.require_equal(
+                    "value_acc == value for first step",
+                    meta.query_advice(value_acc, Rotation::cur()),
+                    meta.query_advice(value, Rotation::cur()),
+                );
+            });
+            cb.condition(
+                not::expr(meta.query_advice(is_last, Rotation::next())),
+                |cb| {
+                    cb.require_equal(
+                        "value_acc_next == value_acc * r + value_next for non-last step",
+                        meta.query_advice(value_acc, Rotation(2)),
+                        meta.query_advice(value_acc, Rotation::cur()) * randomness
+                            + meta.query_advice(value, Rotation(2)),
+                    );
+                },
+            );
+            cb.condition(meta.query_advice(is_last, Rotation::next()), |cb| {
+                cb.require_equal(
+                    "rlc_acc == value_acc if dst is RlcAcc else 0 for last step",
+                    meta.query_advice(rlc_acc, Rotation::cur()),
+                    tag.value_equals(CopyDataType::RlcAcc, Rotation::next())(meta)
+                        * meta.query_advice(value_acc, Rotation::cur()),
+                );
+            });
+
             cb.gate(meta.query_selector(q_step))
         });
 
@@ -325,21 +335,14 @@ impl<F: Field> CopyCircuit<F> {
         });
 
         Self {
-            q_enable,
             q_step,
-            is_first,
             is_last,
-            id,
-            addr,
-            src_addr_end,
-            bytes_left,
             value,
             is_code,
             is_pad,
-            rw_counter,
-            rwc_inc_left,
-            tag,
+            value_acc,
             addr_lt_addr_end,
+            copy_table,
         }
     }
 
@@ -349,14 +352,30 @@ impl<F: Field> CopyCircuit<F> {
         layouter: &mut impl Layouter<F>,
         block: &Block<F>,
     ) -> Result<(), Error> {
-        let tag_chip = BinaryNumberChip::construct(self.tag);
+        let tag_chip = BinaryNumberChip::construct(self.copy_table.tag);
         let lt_chip = LtChip::construct(self.addr_lt_addr_end);
+        lt_chip.load(layouter)?;
 
         layouter.assign_region(
             || "assign copy table",
             |mut region| {
                 let mut offset = 0;
                 for copy_event in block.copy_events.values() {
+                    // accumulated rlc of the read values, one per step
+                    let value_accs: Vec<F> = copy_event
+                        .steps
+                        .iter()
+                        .filter(|step| step.rw.is_read())
+                        .scan(F::zero(), |acc, step| {
+                            *acc = *acc * block.randomness + F::from(step.value as u64);
+                            Some(*acc)
+                        })
+                        .collect();
+                    let rlc_acc = if copy_event.dst_type == CopyDataType::RlcAcc {
+                        value_accs.last().cloned().unwrap_or_else(F::zero)
+                    } else {
+                        F::zero()
+                    };
                     for (step_idx, copy_step) in copy_event.steps.iter().enumerate() {
                         self.assign_step(
                             &mut region,
@@ -365,6 +384,8 @@ impl<F: Field> CopyCircuit<F> {
                             copy_event,
                             step_idx,
                             copy_step,
+                            value_accs[step_idx / 2],
+                            rlc_acc,
                             &tag_chip,
                             &lt_chip,
                         )?;
@@ -390,11 +411,18 @@ impl<F: Field> CopyCircuit<F> {
         copy_event: &CopyEvent,
         step_idx: usize,
         copy_step: &CopyStep,
+        value_acc: F,
+        rlc_acc: F,
         tag_chip: &BinaryNumberChip<F, CopyDataType, 3>,
         lt_chip: &LtChip<F, 8>,
     ) -> Result<(), Error> {
         // q_enable
-        region.assign_fixed(|| "q_enable", self.q_enable, offset, || Ok(F::one()))?;
+        region.assign_fixed(
+            || "q_enable",
+            self.copy_table.q_enable,
+            offset,
+            || Ok(F::one()),
+        )?;
         // enable q_step on the Read step
         if copy_step.rw.is_read() {
             self.q_step.enable(region, offset)?;
@@ -410,7 +438,7 @@ impl<F: Field> CopyCircuit<F> {
         // is_first
         region.assign_advice(
             || format!("assign is_first {}", offset),
-            self.is_first,
+            self.copy_table.is_first,
             offset,
             || Ok(if step_idx == 0 { F::one() } else { F::zero() }),
         )?;
@@ -430,7 +458,7 @@ impl<F: Field> CopyCircuit<F> {
         // id
         region.assign_advice(
             || format!("assign id {}", offset),
-            self.id,
+            self.copy_table.id,
             offset,
             || {
                 Ok(match id {
@@ -452,7 +480,7 @@ impl<F: Field> CopyCircuit<F> {
         // addr
         region.assign_advice(
             || format!("assign addr {}", offset),
-            self.addr,
+            self.copy_table.addr,
             offset,
             || {
                 Ok(match copy_step.tag {
@@ -491,17 +519,31 @@ impl<F: Field> CopyCircuit<F> {
         // rw_counter
         region.assign_advice(
             || format!("assign rw_counter {}", offset),
-            self.rw_counter,
+            self.copy_table.rw_counter,
             offset,
             || Ok(F::from(copy_step.rwc.0 as u64)),
         )?;
         // rwc_inc_left
         region.assign_advice(
             || format!("assign rwc_inc_left {}", offset),
-            self.rwc_inc_left,
+            self.copy_table.rwc_inc_left,
             offset,
             || Ok(F::from(copy_step.rwc_inc_left)),
         )?;
+        // value_acc
+        region.assign_advice(
+            || format!("assign value_acc {}", offset),
+            self.value_acc,
+            offset,
+            || Ok(value_acc),
+        )?;
+        // rlc_acc
+        region.assign_advice(
+            || format!("assign rlc_acc {}", offset),
+            self.copy_table.rlc_acc,
+            offset,
+            || Ok(rlc_acc),
+        )?;
         // tag binary number chip
         tag_chip.assign(region, offset, &copy_step.tag)?;
         // assignment for read steps
@@ -509,14 +551,14 @@ impl<F: Field> CopyCircuit<F> {
             // src_addr_end
             region.assign_advice(
                 || format!("assign src_addr_end {}", offset),
-                self.src_addr_end,
+                self.copy_table.src_addr_end,
                 offset,
                 || Ok(F::from(copy_event.src_addr_end)),
             )?;
             // bytes_left
             region.assign_advice(
                 || format!("assign bytes_left {}", offset),
-                self.bytes_left,
+                self.copy_table.bytes_left,
                 offset,
                 || Ok(F::from(bytes_left)),
             )?;
@@ -538,11 +580,16 @@ impl<F: Field> CopyCircuit<F> {
         tag_chip: &BinaryNumberChip<F, CopyDataType, 3>,
     ) -> Result<(), Error> {
         // q_enable
-        region.assign_fixed(|| "q_enable", self.q_enable, offset, || Ok(F::zero()))?;
+        region.assign_fixed(
+            || "q_enable",
+            self.copy_table.q_enable,
+            offset,
+            || Ok(F::zero()),
+        )?;
         // is_first
         region.assign_advice(
             || format!("assign is_first {}", offset),
-            self.is_first,
+            self.copy_table.is_first,
             offset,
             || Ok(F::zero()),
         )?;
@@ -556,28 +603,28 @@ impl<F: Field> CopyCircuit<F> {
         // id
         region.assign_advice(
             || format!("assign id {}", offset),
-            self.id,
+            self.copy_table.id,
             offset,
             || Ok(F::zero()),
         )?;
         // addr
         region.assign_advice(
             || format!("assign addr {}", offset),
-            self.addr,
+            self.copy_table.addr,
             offset,
             || Ok(F::zero()),
         )?;
         // src_addr_end
         region.assign_advice(
             || format!("assign src_addr_end {}", offset),
-            self.src_addr_end,
+            self.copy_table.src_addr_end,
             offset,
             || Ok(F::zero()),
         )?;
         // bytes_left
         region.assign_advice(
             || format!("assign bytes_left {}", offset),
-            self.bytes_left,
+            self.copy_table.bytes_left,
             offset,
             || Ok(F::zero()),
         )?;
@@ -605,14 +652,28 @@ impl<F: Field> CopyCircuit<F> {
         // rw_counter
         region.assign_advice(
             || format!("assign rw_counter {}", offset),
-            self.rw_counter,
+            self.copy_table.rw_counter,
             offset,
             || Ok(F::zero()),
         )?;
         // rwc_inc_left
         region.assign_advice(
             || format!("assign rwc_inc_left {}", offset),
-            self.rwc_inc_left,
+            self.copy_table.rwc_inc_left,
+            offset,
+            || Ok(F::zero()),
+        )?;
+        // value_acc
+        region.assign_advice(
+            || format!("assign value_acc {}", offset),
+            self.value_acc,
+            offset,
+            || Ok(F::zero()),
+        )?;
+        // rlc_acc
+        region.assign_advice(
+            || format!("assign rlc_acc {}", offset),
+            self.copy_table.rlc_acc,
             offset,
             || Ok(F::zero()),
         )?;
@@ -630,69 +691,34 @@ mod tests {
         operation::RWCounter,
     };
     use eth_types::{bytecode, geth_types::GethData, Field, Word};
+    use gadgets::util::Expr;
     use halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner},
         dev::{MockProver, VerifyFailure},
         plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+        poly::Rotation,
     };
     use itertools::Itertools;
     use mock::TestContext;
     use rand::{prelude::SliceRandom, Rng};
 
     use crate::{
-        evm_circuit::witness::{block_convert, Block, Bytecode, RwMap, Transaction},
+        evm_circuit::witness::{block_convert, Block, Bytecode, RwMap},
         rw_table::RwTable,
+        table::{CopyTable, TxTable},
     };
 
     use super::CopyCircuit;
 
     #[derive(Clone)]
     struct MyConfig<F> {
-        tx_table: [Column<Advice>; 4],
+        tx_table: TxTable,
         rw_table: RwTable,
         bytecode_table: [Column<Advice>; 5],
-        copy_table: CopyCircuit<F>,
+        copy_circuit: CopyCircuit<F>,
     }
 
     impl<F: Field> MyConfig<F> {
-        fn load_txs(
-            &self,
-            layouter: &mut impl Layouter<F>,
-            txs: &[Transaction],
-            randomness: F,
-        ) -> Result<(), Error> {
-            layouter.assign_region(
-                || "tx table",
-                |mut region| {
-                    let mut offset = 0;
-                    for column in self.tx_table {
-                        region.assign_advice(
-                            || "tx table all-zero row",
-                            column,
-                            offset,
-                            || Ok(F::zero()),
-                        )?;
-                    }
-                    offset += 1;
-
-                    for tx in txs.iter() {
-                        for row in tx.table_assignments(randomness) {
-                            for (column, value) in self.tx_table.iter().zip_eq(row) {
-                                region.assign_advice(
-                                    || format!("tx table row {}", offset),
-                                    *column,
-                                    offset,
-                                    || Ok(value),
-                                )?;
-                            }
-                            offset += 1;
-                        }
-                    }
-                    Ok(())
-                },
-            )
-        }
-
         fn load_rws(
             &self,
             layouter: &mut impl Layouter<F>,
@@ -790,16 +816,38 @@ mod tests {
         }
 
         fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let tx_table = [(); 4].map(|_| meta.advice_column());
+            let tx_table = TxTable::construct(meta);
             let rw_table = RwTable::construct(meta);
             let bytecode_table = [(); 5].map(|_| meta.advice_column());
-            let copy_table = CopyCircuit::configure(meta, &tx_table, &rw_table, &bytecode_table);
+            let copy_table = CopyTable::construct(meta);
+
+            let randomness = {
+                let column = meta.instance_column();
+                let mut randomness = None;
+
+                meta.create_gate("", |meta| {
+                    randomness = Some(meta.query_instance(column, Rotation::cur()));
+
+                    [0.expr()]
+                });
+
+                randomness.unwrap()
+            };
+
+            let copy_circuit = CopyCircuit::configure(
+                meta,
+                &tx_table,
+                &rw_table,
+                &bytecode_table,
+                copy_table,
+                randomness,
+            );
 
             MyConfig {
                 tx_table,
                 rw_table,
                 bytecode_table,
-                copy_table,
+                copy_circuit,
             }
         }
 
@@ -808,20 +856,27 @@ mod tests {
             config: Self::Config,
             mut layouter: impl Layouter<F>,
         ) -> Result<(), halo2_proofs::plonk::Error> {
-            config.load_txs(&mut layouter, &self.block.txs, self.block.randomness)?;
+            config.tx_table.load(
+                &mut layouter,
+                &self.block.txs,
+                self.block.txs.len(),
+                self.block.txs.iter().map(|tx| tx.call_data.len()).sum(),
+                self.block.randomness,
+            )?;
             config.load_rws(&mut layouter, &self.block.rws, self.block.randomness)?;
             config.load_bytecodes(
                 &mut layouter,
                 self.block.bytecodes.values(),
                 self.block.randomness,
             )?;
-            config.copy_table.assign_block(&mut layouter, &self.block)
+            config.copy_circuit.assign_block(&mut layouter, &self.block)
         }
     }
 
     fn run_circuit<F: Field>(k: u32, block: Block<F>) -> Result<(), Vec<VerifyFailure>> {
+        let randomness = vec![block.randomness; (1 << k) - 64];
         let circuit = MyCircuit::<F>::new(block);
-        let prover = MockProver::<F>::run(k, &circuit, vec![]).unwrap();
+        let prover = MockProver::<F>::run(k, &circuit, vec![randomness]).unwrap();
         prover.verify()
     }
 
@@ -859,6 +914,25 @@ mod tests {
         builder
     }
 
+    fn gen_log_data() -> CircuitInputBuilder {
+        let code = bytecode! {
+            PUSH32(Word::MAX)
+            PUSH32(Word::from(0x00))
+            MSTORE
+            PUSH32(Word::from(0x28))
+            PUSH32(Word::from(0x10))
+            LOG0
+            STOP
+        };
+        let test_ctx = TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap();
+        let block: GethData = test_ctx.into();
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        builder
+    }
+
     #[test]
     fn copy_circuit_valid_calldatacopy() {
         let builder = gen_calldatacopy_data();
@@ -873,6 +947,13 @@ mod tests {
         assert!(run_circuit(10, block).is_ok());
     }
 
+    #[test]
+    fn copy_circuit_valid_log() {
+        let builder = gen_log_data();
+        let block = block_convert(&builder.block, &builder.code_db);
+        assert!(run_circuit(10, block).is_ok());
+    }
+
     fn perturb_tag(block: &mut bus_mapping::circuit_input_builder::Block, tag: CopyDataType) {
         debug_assert!(!block.copy_events.is_empty());
         debug_assert!(!block.copy_events[0].steps.is_empty());
@@ -915,4 +996,15 @@ mod tests {
         let block = block_convert(&builder.block, &builder.code_db);
         assert!(run_circuit(10, block).is_err());
     }
+
+    #[test]
+    fn copy_circuit_invalid_log() {
+        let mut builder = gen_log_data();
+        match rand::thread_rng().gen_bool(0.5) {
+            true => perturb_tag(&mut builder.block, CopyDataType::Memory),
+            false => perturb_tag(&mut builder.block, CopyDataType::TxLog),
+        }
+        let block = block_convert(&builder.block, &builder.code_db);
+        assert!(run_circuit(10, block).is_err());
+    }
 }
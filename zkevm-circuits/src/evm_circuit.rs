@@ -4,15 +4,18 @@
 use halo2_proofs::{circuit::Layouter, plonk::*};
 
 mod execution;
+pub mod failure;
 pub mod param;
-mod step;
+pub(crate) mod step;
 pub(crate) mod util;
 
 pub mod table;
 pub mod witness;
 
-use eth_types::Field;
+use bus_mapping::circuit_input_builder::CircuitsParams;
+use eth_types::{evm_types::Hardfork, Field};
 use execution::ExecutionConfig;
+use failure::StepMap;
 use itertools::Itertools;
 use table::{FixedTableTag, LookupTable};
 use witness::Block;
@@ -26,20 +29,24 @@ pub struct EvmCircuit<F> {
 }
 
 impl<F: Field> EvmCircuit<F> {
-    /// Configure EvmCircuit
+    /// Configure EvmCircuit for the blocks of the given hardfork
+    #[allow(clippy::too_many_arguments)]
     pub fn configure(
         meta: &mut ConstraintSystem<F>,
+        hardfork: Hardfork,
         power_of_randomness: [Expression<F>; 31],
         tx_table: &dyn LookupTable<F>,
         rw_table: &dyn LookupTable<F>,
         bytecode_table: &dyn LookupTable<F>,
         block_table: &dyn LookupTable<F>,
         copy_table: &dyn LookupTable<F>,
+        keccak_table: &dyn LookupTable<F>,
     ) -> Self {
         let fixed_table = [(); 4].map(|_| meta.fixed_column());
         let byte_table = [(); 1].map(|_| meta.fixed_column());
         let execution = Box::new(ExecutionConfig::configure(
             meta,
+            hardfork,
             power_of_randomness,
             &fixed_table,
             &byte_table,
@@ -48,6 +55,7 @@ impl<F: Field> EvmCircuit<F> {
             bytecode_table,
             block_table,
             copy_table,
+            keccak_table,
         ));
 
         Self {
@@ -140,6 +148,26 @@ impl<F: Field> EvmCircuit<F> {
         }
         num_rows
     }
+
+    /// Returns the locations of the steps of `block` in the execution step
+    /// region, with which the MockProver failures are translated back to the
+    /// steps.
+    pub fn step_map(&self, block: &Block<F>) -> StepMap {
+        StepMap::new(block, |execution_state| {
+            self.execution.get_step_height(execution_state)
+        })
+    }
+
+    /// Return the minimum number of rows required to load the fixed tables
+    /// with `fixed_table_tags`, the byte table and assign `params.max_evm_rows`
+    /// rows of execution steps.
+    pub fn min_num_rows(params: &CircuitsParams, fixed_table_tags: &[FixedTableTag]) -> usize {
+        let fixed_table_rows = 1 + fixed_table_tags
+            .iter()
+            .map(|tag| tag.build::<F>().count())
+            .sum::<usize>();
+        fixed_table_rows.max(256).max(params.max_evm_rows)
+    }
 }
 
 #[cfg(any(feature = "test", test))]
@@ -147,14 +175,16 @@ pub mod test {
     use crate::{
         copy_circuit::CopyCircuit,
         evm_circuit::{
+            failure::{verify_at_rows_pretty, StepMap},
             table::FixedTableTag,
-            witness::{Block, BlockContext, Bytecode, RwMap, Transaction},
+            witness::{Block, Bytecode, RwMap},
             EvmCircuit,
         },
         rw_table::RwTable,
+        table::{BlockTable, CopyTable, KeccakTable, TxTable},
         util::Expr,
     };
-    use eth_types::{Field, Word};
+    use eth_types::{evm_types::Hardfork, Field, Word};
     use halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner},
         dev::{MockProver, VerifyFailure},
@@ -190,53 +220,16 @@ pub mod test {
 
     #[derive(Clone)]
     pub struct TestCircuitConfig<F> {
-        tx_table: [Column<Advice>; 4],
+        tx_table: TxTable,
         rw_table: RwTable,
         bytecode_table: [Column<Advice>; 5],
-        block_table: [Column<Advice>; 3],
-        copy_table: CopyCircuit<F>,
+        block_table: BlockTable,
+        keccak_table: KeccakTable,
+        copy_circuit: CopyCircuit<F>,
         evm_circuit: EvmCircuit<F>,
     }
 
     impl<F: Field> TestCircuitConfig<F> {
-        fn load_txs(
-            &self,
-            layouter: &mut impl Layouter<F>,
-            txs: &[Transaction],
-            randomness: F,
-        ) -> Result<(), Error> {
-            layouter.assign_region(
-                || "tx table",
-                |mut region| {
-                    let mut offset = 0;
-                    for column in self.tx_table {
-                        region.assign_advice(
-                            || "tx table all-zero row",
-                            column,
-                            offset,
-                            || Ok(F::zero()),
-                        )?;
-                    }
-                    offset += 1;
-
-                    for tx in txs.iter() {
-                        for row in tx.table_assignments(randomness) {
-                            for (column, value) in self.tx_table.iter().zip_eq(row) {
-                                region.assign_advice(
-                                    || format!("tx table row {}", offset),
-                                    *column,
-                                    offset,
-                                    || Ok(value),
-                                )?;
-                            }
-                            offset += 1;
-                        }
-                    }
-                    Ok(())
-                },
-            )
-        }
-
         fn load_rws(
             &self,
             layouter: &mut impl Layouter<F>,
@@ -312,43 +305,6 @@ pub mod test {
                 },
             )
         }
-
-        fn load_block(
-            &self,
-            layouter: &mut impl Layouter<F>,
-            block: &BlockContext,
-            randomness: F,
-        ) -> Result<(), Error> {
-            layouter.assign_region(
-                || "block table",
-                |mut region| {
-                    let mut offset = 0;
-                    for column in self.block_table {
-                        region.assign_advice(
-                            || "block table all-zero row",
-                            column,
-                            offset,
-                            || Ok(F::zero()),
-                        )?;
-                    }
-                    offset += 1;
-
-                    for row in block.table_assignments(randomness) {
-                        for (column, value) in self.block_table.iter().zip_eq(row) {
-                            region.assign_advice(
-                                || format!("block table row {}", offset),
-                                *column,
-                                offset,
-                                || Ok(value),
-                            )?;
-                        }
-                        offset += 1;
-                    }
-
-                    Ok(())
-                },
-            )
-        }
     }
 
     #[derive(Default)]
@@ -375,11 +331,12 @@ pub mod test {
         }
 
         fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let tx_table = [(); 4].map(|_| meta.advice_column());
+            let tx_table = TxTable::construct(meta);
             let rw_table = RwTable::construct(meta);
             let bytecode_table = [(); 5].map(|_| meta.advice_column());
-            let block_table = [(); 3].map(|_| meta.advice_column());
-            let copy_table = CopyCircuit::configure(meta, &tx_table, &rw_table, &bytecode_table);
+            let block_table = BlockTable::construct(meta);
+            let copy_table = CopyTable::construct(meta);
+            let keccak_table = KeccakTable::construct(meta);
 
             // This gate is used just to get the array of expressions from the power of
             // randomness instance column, so that later on we don't need to query
@@ -399,20 +356,32 @@ pub mod test {
                 power_of_randomness.unwrap()
             };
 
+            let copy_circuit = CopyCircuit::configure(
+                meta,
+                &tx_table,
+                &rw_table,
+                &bytecode_table,
+                copy_table,
+                power_of_randomness[0].clone(),
+            );
+
             Self::Config {
                 tx_table,
                 rw_table,
                 bytecode_table,
                 block_table,
-                copy_table,
+                keccak_table,
+                copy_circuit,
                 evm_circuit: EvmCircuit::configure(
                     meta,
+                    Hardfork::default(),
                     power_of_randomness,
                     &tx_table,
                     &rw_table,
                     &bytecode_table,
                     &block_table,
                     &copy_table,
+                    &keccak_table,
                 ),
             }
         }
@@ -426,15 +395,30 @@ pub mod test {
                 .evm_circuit
                 .load_fixed_table(&mut layouter, self.fixed_table_tags.clone())?;
             config.evm_circuit.load_byte_table(&mut layouter)?;
-            config.load_txs(&mut layouter, &self.block.txs, self.block.randomness)?;
+            config.tx_table.load(
+                &mut layouter,
+                &self.block.txs,
+                self.block.txs.len(),
+                self.block.txs.iter().map(|tx| tx.call_data.len()).sum(),
+                self.block.randomness,
+            )?;
             config.load_rws(&mut layouter, &self.block.rws, self.block.randomness)?;
             config.load_bytecodes(
                 &mut layouter,
                 self.block.bytecodes.values(),
                 self.block.randomness,
             )?;
-            config.load_block(&mut layouter, &self.block.context, self.block.randomness)?;
-            config.copy_table.assign_block(&mut layouter, &self.block)?;
+            config
+                .block_table
+                .load(&mut layouter, &self.block.context, self.block.randomness)?;
+            config.keccak_table.dev_load(
+                &mut layouter,
+                &self.block.sha3_inputs,
+                self.block.randomness,
+            )?;
+            config
+                .copy_circuit
+                .assign_block(&mut layouter, &self.block)?;
             config
                 .evm_circuit
                 .assign_block_exact(&mut layouter, &self.block)
@@ -453,15 +437,20 @@ pub mod test {
             let config = TestCircuit::configure(&mut cs);
             config.evm_circuit.get_active_rows(block)
         }
+
+        pub fn step_map(block: &Block<F>) -> StepMap {
+            let mut cs = ConstraintSystem::default();
+            let config = TestCircuit::configure(&mut cs);
+            config.evm_circuit.step_map(block)
+        }
     }
 
-    pub fn run_test_circuit<F: Field>(
-        block: Block<F>,
-        fixed_table_tags: Vec<FixedTableTag>,
-    ) -> Result<(), Vec<VerifyFailure>> {
+    /// Returns the `k` of the test circuit assigning `block` with the fixed
+    /// tables of `fixed_table_tags`.
+    pub fn test_circuit_k<F: Field>(block: &Block<F>, fixed_table_tags: &[FixedTableTag]) -> u32 {
         let log2_ceil = |n| u32::BITS - (n as u32).leading_zeros() - (n & (n - 1) == 0) as u32;
 
-        let num_rows_required_for_steps = TestCircuit::get_num_rows_required(&block);
+        let num_rows_required_for_steps = TestCircuit::get_num_rows_required(block);
 
         let k = log2_ceil(
             64 + fixed_table_tags
@@ -476,16 +465,45 @@ pub mod test {
                 .map(|bytecode| bytecode.bytes.len())
                 .sum::<usize>(),
         ));
-        let k = k.max(log2_ceil(64 + num_rows_required_for_steps));
-        log::debug!("evm circuit uses k = {}", k);
+        let k = k.max(log2_ceil(64 + block.sha3_inputs.len()));
+        k.max(log2_ceil(64 + num_rows_required_for_steps))
+    }
 
-        let power_of_randomness = (1..32)
+    /// Returns the powers of randomness of `block`, given as instance columns
+    /// to the test circuit with `2^k` rows.
+    pub fn test_circuit_instance<F: Field>(block: &Block<F>, k: u32) -> Vec<Vec<F>> {
+        (1..32)
             .map(|exp| vec![block.randomness.pow(&[exp, 0, 0, 0]); (1 << k) - 64])
-            .collect();
+            .collect()
+    }
+
+    pub fn run_test_circuit<F: Field>(
+        block: Block<F>,
+        fixed_table_tags: Vec<FixedTableTag>,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let k = test_circuit_k(&block, &fixed_table_tags);
+        log::debug!("evm circuit uses k = {}", k);
+
+        let power_of_randomness = test_circuit_instance(&block, k);
         let (active_gate_rows, active_lookup_rows) = TestCircuit::get_active_rows(&block);
+        let steps = TestCircuit::step_map(&block);
         let circuit = TestCircuit::<F>::new(block, fixed_table_tags);
         let prover = MockProver::<F>::run(k, &circuit, power_of_randomness).unwrap();
-        prover.verify_at_rows(active_gate_rows.into_iter(), active_lookup_rows.into_iter())
+        verify_at_rows_pretty(
+            &prover,
+            &steps,
+            active_gate_rows.into_iter(),
+            active_lookup_rows.into_iter(),
+        )
+        .map_err(|failures| {
+            failures
+                .into_iter()
+                .map(|failure| {
+                    log::error!("{}", failure);
+                    failure.failure
+                })
+                .collect()
+        })
     }
 
     pub fn run_test_circuit_incomplete_fixed_table<F: Field>(
@@ -1,4 +1,4 @@
-use super::util::{CachedRegion, CellManager, StoredExpression};
+use super::util::{CachedRegion, CellAssignments, CellManager, StoredExpression};
 use crate::{
     evm_circuit::{
         param::{MAX_STEP_HEIGHT, STEP_WIDTH},
@@ -12,13 +12,14 @@ use crate::{
     },
     util::Expr,
 };
-use eth_types::Field;
+use eth_types::{evm_types::Hardfork, Field};
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::{Layouter, Region},
+    circuit::Layouter,
     plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector, VirtualCells},
     poly::Rotation,
 };
+use rayon::prelude::*;
 use std::{collections::HashMap, convert::TryInto, iter};
 use strum::IntoEnumIterator;
 
@@ -43,6 +44,7 @@ mod dup;
 mod end_block;
 mod end_tx;
 mod error_oog_static_memory;
+mod error_unsupported_opcode;
 mod extcodehash;
 mod gas;
 mod gasprice;
@@ -92,6 +94,7 @@ use dup::DupGadget;
 use end_block::EndBlockGadget;
 use end_tx::EndTxGadget;
 use error_oog_static_memory::ErrorOOGStaticMemoryGadget;
+use error_unsupported_opcode::ErrorUnsupportedOpcodeGadget;
 use extcodehash::ExtcodehashGadget;
 use gas::GasGadget;
 use gasprice::GasPriceGadget;
@@ -129,7 +132,7 @@ pub(crate) trait ExecutionGadget<F: FieldExt> {
 
     fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         transaction: &Transaction,
@@ -147,6 +150,7 @@ pub(crate) struct ExecutionConfig<F> {
     q_step_first: Selector,
     q_step_last: Selector,
     advices: [Column<Advice>; STEP_WIDTH],
+    hardfork: Hardfork,
     step: Step<F>,
     height_map: HashMap<ExecutionState, usize>,
     stored_expressions_map: HashMap<ExecutionState, Vec<StoredExpression<F>>>,
@@ -219,12 +223,14 @@ pub(crate) struct ExecutionConfig<F> {
     block_ctx_u256_gadget: BlockCtxU256Gadget<F>,
     // error gadgets
     error_oog_static_memory_gadget: ErrorOOGStaticMemoryGadget<F>,
+    error_unsupported_opcode_gadget: ErrorUnsupportedOpcodeGadget<F>,
 }
 
 impl<F: Field> ExecutionConfig<F> {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn configure(
         meta: &mut ConstraintSystem<F>,
+        hardfork: Hardfork,
         power_of_randomness: [Expression<F>; 31],
         fixed_table: &dyn LookupTable<F>,
         byte_table: &dyn LookupTable<F>,
@@ -233,6 +239,7 @@ impl<F: Field> ExecutionConfig<F> {
         bytecode_table: &dyn LookupTable<F>,
         block_table: &dyn LookupTable<F>,
         copy_table: &dyn LookupTable<F>,
+        keccak_table: &dyn LookupTable<F>,
     ) -> Self {
         let q_usable = meta.complex_selector();
         let q_step = meta.advice_column();
@@ -343,6 +350,7 @@ impl<F: Field> ExecutionConfig<F> {
                     num_rows_until_next_step,
                     q_step_first,
                     q_step_last,
+                    hardfork,
                     &power_of_randomness,
                     &step_curr,
                     &step_next,
@@ -361,6 +369,7 @@ impl<F: Field> ExecutionConfig<F> {
             q_step_first,
             q_step_last,
             advices,
+            hardfork,
             // internal states
             begin_tx_gadget: configure_gadget!(),
             end_block_gadget: configure_gadget!(),
@@ -430,6 +439,7 @@ impl<F: Field> ExecutionConfig<F> {
             block_ctx_u256_gadget: configure_gadget!(),
             // error gadgets
             error_oog_static_memory_gadget: configure_gadget!(),
+            error_unsupported_opcode_gadget: configure_gadget!(),
             // step and presets
             step: step_curr,
             height_map,
@@ -445,6 +455,7 @@ impl<F: Field> ExecutionConfig<F> {
             bytecode_table,
             block_table,
             copy_table,
+            keccak_table,
             &power_of_randomness,
             &cell_manager,
         );
@@ -468,6 +479,7 @@ impl<F: Field> ExecutionConfig<F> {
         num_rows_until_next_step: Column<Advice>,
         q_step_first: Selector,
         q_step_last: Selector,
+        hardfork: Hardfork,
         power_of_randomness: &[Expression<F>; 31],
         step_curr: &Step<F>,
         step_next: &Step<F>,
@@ -480,6 +492,7 @@ impl<F: Field> ExecutionConfig<F> {
             let mut cb = ConstraintBuilder::new(
                 step_curr.clone(),
                 step_next.clone(),
+                hardfork,
                 power_of_randomness,
                 G::EXECUTION_STATE,
             );
@@ -493,6 +506,7 @@ impl<F: Field> ExecutionConfig<F> {
         let mut cb = ConstraintBuilder::new(
             step_curr.clone(),
             step_next.clone(),
+            hardfork,
             power_of_randomness,
             G::EXECUTION_STATE,
         );
@@ -617,6 +631,7 @@ impl<F: Field> ExecutionConfig<F> {
         bytecode_table: &dyn LookupTable<F>,
         block_table: &dyn LookupTable<F>,
         copy_table: &dyn LookupTable<F>,
+        keccak_table: &dyn LookupTable<F>,
         power_of_randomness: &[Expression<F>; 31],
         cell_manager: &CellManager<F>,
     ) {
@@ -632,6 +647,7 @@ impl<F: Field> ExecutionConfig<F> {
                         Table::Block => block_table,
                         Table::Byte => byte_table,
                         Table::Copy => copy_table,
+                        Table::Keccak => keccak_table,
                     }
                     .table_exprs(meta);
                     vec![(
@@ -652,12 +668,56 @@ impl<F: Field> ExecutionConfig<F> {
         block: &Block<F>,
         _exact: bool,
     ) -> Result<(), Error> {
+        if block.hardfork != self.hardfork {
+            log::error!(
+                "block of {:?} assigned to the EVM circuit configured for {:?}",
+                block.hardfork,
+                self.hardfork
+            );
+            return Err(Error::Synthesis);
+        }
+
         let power_of_randomness = (1..32)
             .map(|exp| block.randomness.pow(&[exp, 0, 0, 0]))
             .collect::<Vec<F>>()
             .try_into()
             .unwrap();
 
+        // The witnesses of the steps don't depend on each other, so they're
+        // computed in parallel before being assigned to the region.
+        let steps: Vec<_> = block
+            .txs
+            .iter()
+            .flat_map(|tx| tx.steps.iter().map(move |step| (tx, step)))
+            .collect();
+        let offsets: Vec<_> = steps
+            .iter()
+            .scan(0, |offset, (_, step)| {
+                let step_offset = *offset;
+                *offset += self.get_step_height(step.execution_state);
+                Some(step_offset)
+            })
+            .collect();
+        let step_assignments = steps
+            .par_iter()
+            .zip(offsets.par_iter())
+            .enumerate()
+            .map(|(idx, ((transaction, step), offset))| {
+                let next = steps.get(idx + 1).map(|(transaction, step)| {
+                    (*transaction, &transaction.calls[step.call_index], *step)
+                });
+                self.assign_exec_step(
+                    *offset,
+                    block,
+                    transaction,
+                    &transaction.calls[step.call_index],
+                    step,
+                    next,
+                    power_of_randomness,
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
         layouter.assign_region(
             || "Execution step",
             |mut region| {
@@ -665,32 +725,12 @@ impl<F: Field> ExecutionConfig<F> {
 
                 self.q_step_first.enable(&mut region, offset)?;
 
-                // Collect all steps
-                let mut steps = block
-                    .txs
-                    .iter()
-                    .flat_map(|tx| tx.steps.iter().map(move |step| (tx, step)))
-                    .peekable();
-
                 let mut last_height = 0;
-                while let Some((transaction, step)) = steps.next() {
-                    let call = &transaction.calls[step.call_index];
+                for ((_, step), assignments) in steps.iter().zip(step_assignments.iter()) {
                     let height = self.get_step_height(step.execution_state);
 
                     // Assign the step witness
-                    self.assign_exec_step(
-                        &mut region,
-                        offset,
-                        block,
-                        transaction,
-                        call,
-                        step,
-                        height,
-                        steps.peek().map(|&(transaction, step)| {
-                            (transaction, &transaction.calls[step.call_index], step)
-                        }),
-                        power_of_randomness,
-                    )?;
+                    assignments.assign(&mut region)?;
 
                     // q_step logic
                     for idx in 0..height {
@@ -748,24 +788,22 @@ impl<F: Field> ExecutionConfig<F> {
         )
     }
 
+    /// Returns the assignments of the witness of `step` at `offset`.
     #[allow(clippy::too_many_arguments)]
     fn assign_exec_step(
         &self,
-        region: &mut Region<'_, F>,
         offset: usize,
         block: &Block<F>,
         transaction: &Transaction,
         call: &Call,
         step: &ExecStep,
-        height: usize,
         next: Option<(&Transaction, &Call, &ExecStep)>,
         power_of_randomness: [F; 31],
-    ) -> Result<(), Error> {
+    ) -> Result<CellAssignments<F>, Error> {
         // Make the region large enough for the current step and the next step.
         // The next step's next step may also be accessed, so make the region large
         // enough for 3 steps.
-        let region = &mut CachedRegion::<'_, '_, F>::new(
-            region,
+        let mut region = CachedRegion::<F>::new(
             power_of_randomness,
             STEP_WIDTH,
             MAX_STEP_HEIGHT * 3,
@@ -779,8 +817,8 @@ impl<F: Field> ExecutionConfig<F> {
         // to correctly calculate the intermediate value.
         if let Some((transaction_next, call_next, step_next)) = next {
             self.assign_exec_step_int(
-                region,
-                offset + height,
+                &mut region,
+                offset + self.get_step_height(step.execution_state),
                 block,
                 transaction_next,
                 call_next,
@@ -788,12 +826,13 @@ impl<F: Field> ExecutionConfig<F> {
             )?;
         }
 
-        self.assign_exec_step_int(region, offset, block, transaction, call, step)
+        self.assign_exec_step_int(&mut region, offset, block, transaction, call, step)?;
+        Ok(region.into_assignments())
     }
 
     fn assign_exec_step_int(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         transaction: &Transaction,
@@ -884,6 +923,9 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::ErrorOutOfGasStaticMemoryExpansion => {
                 assign_exec_step!(self.error_oog_static_memory_gadget)
             }
+            ExecutionState::ErrorUnsupportedOpcode => {
+                assign_exec_step!(self.error_unsupported_opcode_gadget)
+            }
             _ => unimplemented!("unimplemented ExecutionState: {:?}", step.execution_state),
         }
 
@@ -137,7 +137,7 @@ impl<F: Field> ExecutionGadget<F> for AddModGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
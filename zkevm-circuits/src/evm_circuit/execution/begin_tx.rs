@@ -1,23 +1,33 @@
 use crate::{
     evm_circuit::{
         execution::ExecutionGadget,
-        param::N_BYTES_GAS,
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_GAS, N_BYTES_U64},
         step::ExecutionState,
-        table::{AccountFieldTag, CallContextFieldTag, TxContextFieldTag},
+        table::{AccountFieldTag, BlockContextFieldTag, CallContextFieldTag, TxContextFieldTag},
         util::{
             common_gadget::TransferWithGasFeeGadget,
             constraint_builder::{
                 ConstraintBuilder, ReversionInfo, StepStateTransition,
                 Transition::{Delta, To},
             },
-            math_gadget::{MulWordByU64Gadget, RangeCheckGadget},
-            select, CachedRegion, Cell, RandomLinearCombination, Word,
+            from_bytes,
+            math_gadget::{
+                AddWordsGadget, IsEqualGadget, IsZeroGadget, LtGadget, LtWordGadget,
+                MulWordByU64Gadget, RangeCheckGadget,
+            },
+            not, or, rlc, select, sum, CachedRegion, Cell, RandomLinearCombination, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
+    tx_circuit::DYNAMIC_FEE_TX_TYPE,
     util::Expr,
 };
-use eth_types::{evm_types::GasCost, Field, ToLittleEndian, ToScalar};
+use array_init::array_init;
+use bus_mapping::circuit_input_builder::CopyDataType;
+use eth_types::{
+    evm_types::GasCost, geth_types::create_address_rlp, Field, Rlc, ToLittleEndian, ToScalar,
+};
+use ethers_core::utils::keccak256;
 use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
@@ -26,10 +36,26 @@ pub(crate) struct BeginTxGadget<F> {
     tx_nonce: Cell<F>,
     tx_gas: Cell<F>,
     tx_gas_price: Word<F>,
+    tx_type: Cell<F>,
+    tx_max_fee_per_gas: Word<F>,
+    is_dynamic_fee_tx: IsEqualGadget<F>,
+    add_priority_fee_to_base_fee: AddWordsGadget<F, 2, true>,
+    max_fee_lt_fee_sum: LtWordGadget<F>,
+    max_fee_lt_priority_fee: LtWordGadget<F>,
+    max_fee_lt_base_fee: LtWordGadget<F>,
     mul_gas_fee_by_gas: MulWordByU64Gadget<F>,
     tx_caller_address: Cell<F>,
     tx_callee_address: Cell<F>,
     tx_is_create: Cell<F>,
+    callee_address: Cell<F>,
+    caller_address_bytes: [Cell<F>; N_BYTES_ACCOUNT_ADDRESS],
+    nonce_bytes: [Cell<F>; N_BYTES_U64],
+    is_nonce_suffix_zero: [IsZeroGadget<F>; N_BYTES_U64],
+    is_nonce_lt_0x80: LtGadget<F, 1>,
+    create_address_hash: Word<F>,
+    is_callee_zero: IsZeroGadget<F>,
+    is_callee_lt_num_precompiles: LtGadget<F, N_BYTES_ACCOUNT_ADDRESS>,
+    is_callee_caller: IsEqualGadget<F>,
     tx_value: Word<F>,
     tx_call_data_length: Cell<F>,
     tx_call_data_gas_cost: Cell<F>,
@@ -37,6 +63,8 @@ pub(crate) struct BeginTxGadget<F> {
     sufficient_gas_left: RangeCheckGadget<F, N_BYTES_GAS>,
     transfer_with_gas_fee: TransferWithGasFeeGadget<F>,
     code_hash: Cell<F>,
+    is_call_data_empty: IsZeroGadget<F>,
+    init_code_rlc: Cell<F>,
 }
 
 impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
@@ -82,8 +110,47 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             None,
         );
 
-        // TODO: Implement EIP 1559 (currently it only supports legacy
-        // transaction format)
+        // Check the gas price of EIP-1559 transactions, which must be the effective
+        // gas price min(max_fee_per_gas, base_fee + max_priority_fee_per_gas), with
+        // max_priority_fee_per_gas <= max_fee_per_gas and base_fee <= max_fee_per_gas.
+        let tx_type = cb.tx_context(tx_id.expr(), TxContextFieldTag::TxType, None);
+        let [tx_max_fee_per_gas, tx_max_priority_fee_per_gas] = [
+            TxContextFieldTag::MaxFeePerGas,
+            TxContextFieldTag::MaxPriorityFeePerGas,
+        ]
+        .map(|field_tag| cb.tx_context_as_word(tx_id.expr(), field_tag, None));
+        let base_fee = cb.query_word();
+        cb.block_lookup(BlockContextFieldTag::BaseFee.expr(), None, base_fee.expr());
+        let is_dynamic_fee_tx =
+            IsEqualGadget::construct(cb, tx_type.expr(), DYNAMIC_FEE_TX_TYPE.expr());
+        let priority_fee_plus_base_fee = cb.query_word();
+        let add_priority_fee_to_base_fee = AddWordsGadget::construct(
+            cb,
+            [base_fee.clone(), tx_max_priority_fee_per_gas.clone()],
+            priority_fee_plus_base_fee.clone(),
+        );
+        let max_fee_lt_fee_sum =
+            LtWordGadget::construct(cb, &tx_max_fee_per_gas, &priority_fee_plus_base_fee);
+        let max_fee_lt_priority_fee =
+            LtWordGadget::construct(cb, &tx_max_fee_per_gas, &tx_max_priority_fee_per_gas);
+        let max_fee_lt_base_fee = LtWordGadget::construct(cb, &tx_max_fee_per_gas, &base_fee);
+        cb.condition(is_dynamic_fee_tx.expr(), |cb| {
+            cb.require_equal(
+                "gas_price == min(max_fee_per_gas, base_fee + max_priority_fee_per_gas)",
+                tx_gas_price.expr(),
+                select::expr(
+                    max_fee_lt_fee_sum.expr(),
+                    tx_max_fee_per_gas.expr(),
+                    priority_fee_plus_base_fee.expr(),
+                ),
+            );
+            cb.require_zero(
+                "max_priority_fee_per_gas <= max_fee_per_gas",
+                max_fee_lt_priority_fee.expr(),
+            );
+            cb.require_zero("base_fee <= max_fee_per_gas", max_fee_lt_base_fee.expr());
+        });
+
         // Calculate transaction gas fee
         let mul_gas_fee_by_gas =
             MulWordByU64Gadget::construct(cb, tx_gas_price.clone(), tx_gas.expr());
@@ -100,7 +167,119 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         let gas_left = tx_gas.expr() - intrinsic_gas_cost;
         let sufficient_gas_left = RangeCheckGadget::construct(cb, gas_left.clone());
 
-        // Prepare access list of caller and callee
+        // The callee of a creation transaction is the created account, whose
+        // address is empty in the tx table.
+        let callee_address = cb.query_cell();
+        cb.condition(not::expr(tx_is_create.expr()), |cb| {
+            cb.require_equal(
+                "callee_address == tx_callee_address",
+                callee_address.expr(),
+                tx_callee_address.expr(),
+            );
+        });
+
+        // The created address is keccak(rlp([caller, nonce]))[12..], where
+        // the nonce is encoded with its bytes up to the most significant
+        // non-zero one, whose number is the number of non-zero suffixes of
+        // its little endian bytes.  A nonce below 0x80 other than 0 is
+        // encoded as a single byte.
+        let caller_address_bytes = cb.query_bytes();
+        let nonce_bytes = cb.query_bytes();
+        let is_nonce_suffix_zero =
+            array_init(|idx| IsZeroGadget::construct(cb, sum::expr(&nonce_bytes[idx..])));
+        let is_nonce_lt_0x80 = LtGadget::construct(cb, nonce_bytes[0].expr(), 0x80.expr());
+        let create_address_hash = cb.query_word();
+        let power_of_randomness = cb.power_of_randomness().to_vec();
+        let randomness_pow = |exponent: usize| {
+            if exponent == 0 {
+                1.expr()
+            } else {
+                power_of_randomness[exponent - 1].clone()
+            }
+        };
+        let is_suffix_zero = |idx: usize| {
+            if idx == N_BYTES_U64 {
+                1.expr()
+            } else {
+                is_nonce_suffix_zero[idx].expr()
+            }
+        };
+        let nonce_len = sum::expr(
+            is_nonce_suffix_zero
+                .iter()
+                .map(|is_zero| 1.expr() - is_zero.expr()),
+        );
+        let randomness_pow_nonce_len = (1..=N_BYTES_U64).fold(is_suffix_zero(0), |acc, len| {
+            acc + (is_suffix_zero(len) - is_suffix_zero(len - 1)) * randomness_pow(len)
+        });
+        let is_nonce_single_byte =
+            not::expr(is_suffix_zero(0)) * is_suffix_zero(1) * is_nonce_lt_0x80.expr();
+        let nonce_rlp_rlc = select::expr(
+            is_nonce_single_byte.clone(),
+            nonce_bytes[0].expr(),
+            (0x80.expr() + nonce_len.clone()) * randomness_pow_nonce_len.clone()
+                + rlc::expr(
+                    &nonce_bytes.iter().map(Expr::expr).collect::<Vec<_>>(),
+                    &power_of_randomness,
+                ),
+        );
+        let nonce_rlp_len =
+            select::expr(is_nonce_single_byte.clone(), 1.expr(), 1.expr() + nonce_len);
+        let randomness_pow_nonce_rlp_len = select::expr(
+            is_nonce_single_byte,
+            randomness_pow(1),
+            randomness_pow(1) * randomness_pow_nonce_len,
+        );
+        let caller_address_rlp_rlc = 0x94.expr() * randomness_pow(N_BYTES_ACCOUNT_ADDRESS)
+            + rlc::expr(
+                &caller_address_bytes
+                    .iter()
+                    .map(Expr::expr)
+                    .collect::<Vec<_>>(),
+                &power_of_randomness,
+            );
+        cb.condition(tx_is_create.expr(), |cb| {
+            cb.require_equal(
+                "caller_address_bytes == tx_caller_address",
+                from_bytes::expr(&caller_address_bytes),
+                tx_caller_address.expr(),
+            );
+            cb.require_equal(
+                "nonce_bytes == tx_nonce",
+                from_bytes::expr(&nonce_bytes),
+                tx_nonce.expr(),
+            );
+            cb.require_equal(
+                "callee_address == keccak(rlp([caller, nonce]))[12..]",
+                callee_address.expr(),
+                from_bytes::expr(&create_address_hash.cells[..N_BYTES_ACCOUNT_ADDRESS]),
+            );
+            cb.keccak_table_lookup(
+                (((0xc0 + 1 + N_BYTES_ACCOUNT_ADDRESS).expr() + nonce_rlp_len.clone())
+                    * randomness_pow(1 + N_BYTES_ACCOUNT_ADDRESS)
+                    + caller_address_rlp_rlc)
+                    * randomness_pow_nonce_rlp_len
+                    + nonce_rlp_rlc,
+                (2 + N_BYTES_ACCOUNT_ADDRESS).expr() + nonce_rlp_len,
+                create_address_hash.expr(),
+            );
+        });
+
+        // Prepare access list of the precompiled contracts of the hardfork,
+        // which are written first into the empty access list of the
+        // transaction.
+        let num_precompiles = cb.hardfork().num_precompiles();
+        for address in 1..=num_precompiles {
+            cb.account_access_list_write(tx_id.expr(), address.expr(), 1.expr(), 0.expr(), None);
+        }
+
+        // Prepare access list of caller and callee, the callee being already in
+        // it if it's a precompiled contract or the caller.
+        let is_callee_zero = IsZeroGadget::construct(cb, callee_address.expr());
+        let is_callee_lt_num_precompiles =
+            LtGadget::construct(cb, callee_address.expr(), (num_precompiles + 1).expr());
+        let is_callee_caller =
+            IsEqualGadget::construct(cb, callee_address.expr(), tx_caller_address.expr());
         cb.account_access_list_write(
             tx_id.expr(),
             tx_caller_address.expr(),
@@ -110,42 +289,57 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         );
         cb.account_access_list_write(
             tx_id.expr(),
-            tx_callee_address.expr(),
+            callee_address.expr(),
             1.expr(),
-            0.expr(),
+            or::expr([
+                is_callee_lt_num_precompiles.expr() * not::expr(is_callee_zero.expr()),
+                is_callee_caller.expr(),
+            ]),
             None,
         );
 
+        // Increase the nonce of the created account from 0 to 1 (EIP-161),
+        // which is reverted with the transfer if the creation fails.
+        cb.condition(tx_is_create.expr(), |cb| {
+            cb.account_write(
+                callee_address.expr(),
+                AccountFieldTag::Nonce,
+                1.expr(),
+                0.expr(),
+                Some(&mut reversion_info),
+            );
+        });
+
         // Transfer value from caller to callee
         let transfer_with_gas_fee = TransferWithGasFeeGadget::construct(
             cb,
             tx_caller_address.expr(),
-            tx_callee_address.expr(),
+            callee_address.expr(),
             tx_value.clone(),
             mul_gas_fee_by_gas.product().clone(),
             &mut reversion_info,
         );
 
-        // TODO: Handle creation transaction
-        // TODO: Handle precompiled
-
-        // Read code_hash of callee
+        // Read code_hash of callee, or use the hash of the init code in the tx
+        // call data for creation transactions.
         let code_hash = cb.query_cell();
-        cb.account_read(
-            tx_callee_address.expr(),
-            AccountFieldTag::CodeHash,
-            code_hash.expr(),
-        );
+        cb.condition(not::expr(tx_is_create.expr()), |cb| {
+            cb.account_read(
+                callee_address.expr(),
+                AccountFieldTag::CodeHash,
+                code_hash.expr(),
+            );
+        });
 
         // Setup next call's context.
         for (field_tag, value) in [
             (CallContextFieldTag::Depth, 1.expr()),
             (CallContextFieldTag::CallerAddress, tx_caller_address.expr()),
-            (CallContextFieldTag::CalleeAddress, tx_callee_address.expr()),
+            (CallContextFieldTag::CalleeAddress, callee_address.expr()),
             (CallContextFieldTag::CallDataOffset, 0.expr()),
             (
                 CallContextFieldTag::CallDataLength,
-                tx_call_data_length.expr(),
+                tx_call_data_length.expr() * not::expr(tx_is_create.expr()),
             ),
             (CallContextFieldTag::Value, tx_value.expr()),
             (CallContextFieldTag::IsStatic, 0.expr()),
@@ -153,23 +347,55 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             (CallContextFieldTag::LastCalleeReturnDataOffset, 0.expr()),
             (CallContextFieldTag::LastCalleeReturnDataLength, 0.expr()),
             (CallContextFieldTag::IsRoot, 1.expr()),
-            (CallContextFieldTag::IsCreate, 0.expr()),
+            (CallContextFieldTag::IsCreate, tx_is_create.expr()),
             (CallContextFieldTag::CodeHash, code_hash.expr()),
         ] {
             cb.call_context_lookup(false.expr(), Some(call_id.expr()), field_tag, value);
         }
 
+        // The init code of a creation transaction is its call data, copied
+        // into an accumulated RLC whose keccak hash is the code hash.
+        let is_call_data_empty = IsZeroGadget::construct(cb, tx_call_data_length.expr());
+        let init_code_rlc = cb.query_cell();
+        cb.condition(
+            tx_is_create.expr() * not::expr(is_call_data_empty.expr()),
+            |cb| {
+                cb.copy_table_lookup_with_rlc_acc(
+                    tx_id.expr(),
+                    CopyDataType::TxCalldata.expr(),
+                    0.expr(),
+                    CopyDataType::RlcAcc.expr(),
+                    0.expr(),
+                    tx_call_data_length.expr(),
+                    0.expr(),
+                    tx_call_data_length.expr(),
+                    init_code_rlc.expr(),
+                    cb.curr.state.rw_counter.expr() + cb.rw_counter_offset(),
+                    0.expr(),
+                );
+            },
+        );
+        cb.condition(tx_is_create.expr(), |cb| {
+            cb.keccak_table_lookup(
+                init_code_rlc.expr(),
+                tx_call_data_length.expr(),
+                code_hash.expr(),
+            );
+        });
+
         cb.require_step_state_transition(StepStateTransition {
-            // 19 read/write including:
+            // 22 read/write plus one per precompiled contract including:
             //   - Read CallContext TxId
             //   - Read CallContext RwCounterEndOfReversion
             //   - Read CallContext IsPersistent
             //   - Write Account Nonce
-            //   - Write TxAccessListAccount
-            //   - Write TxAccessListAccount
+            //   - Write TxAccessListAccount (precompiled contracts)
+            //   - Write TxAccessListAccount (caller)
+            //   - Write TxAccessListAccount (callee)
+            //   - Write Account Nonce (only for creation transactions)
             //   - Write Account Balance
             //   - Write Account Balance
-            //   - Read Account CodeHash
+            //   - Read Account CodeHash (only for non-creation transactions)
             //   - Read CallContext Depth
             //   - Read CallContext CallerAddress
             //   - Read CallContext CalleeAddress
@@ -180,13 +406,16 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             //   - Read CallContext LastCalleeId
             //   - Read CallContext LastCalleeReturnDataOffset
             //   - Read CallContext LastCalleeReturnDataLength
-            rw_counter: Delta(22.expr()),
+            //   - Read CallContext IsRoot
+            //   - Read CallContext IsCreate
+            //   - Read CallContext CodeHash
+            rw_counter: Delta(22.expr() + num_precompiles.expr()),
             call_id: To(call_id.expr()),
             is_root: To(true.expr()),
-            is_create: To(false.expr()),
+            is_create: To(tx_is_create.expr()),
             code_hash: To(code_hash.expr()),
             gas_left: To(gas_left),
-            reversible_write_counter: To(2.expr()),
+            reversible_write_counter: To(2.expr() + tx_is_create.expr()),
             log_id: To(0.expr()),
             ..StepStateTransition::new_context()
         });
@@ -196,10 +425,26 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             tx_nonce,
             tx_gas,
             tx_gas_price,
+            tx_type,
+            tx_max_fee_per_gas,
+            is_dynamic_fee_tx,
+            add_priority_fee_to_base_fee,
+            max_fee_lt_fee_sum,
+            max_fee_lt_priority_fee,
+            max_fee_lt_base_fee,
             mul_gas_fee_by_gas,
             tx_caller_address,
             tx_callee_address,
             tx_is_create,
+            callee_address,
+            caller_address_bytes,
+            nonce_bytes,
+            is_nonce_suffix_zero,
+            is_nonce_lt_0x80,
+            create_address_hash,
+            is_callee_zero,
+            is_callee_lt_num_precompiles,
+            is_callee_caller,
             tx_value,
             tx_call_data_length,
             tx_call_data_gas_cost,
@@ -207,12 +452,14 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             sufficient_gas_left,
             transfer_with_gas_fee,
             code_hash,
+            is_call_data_empty,
+            init_code_rlc,
         }
     }
 
     fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         tx: &Transaction,
@@ -220,9 +467,14 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         step: &ExecStep,
     ) -> Result<(), Error> {
         let gas_fee = tx.gas_price * tx.gas;
-        let [caller_balance_pair, callee_balance_pair, (callee_code_hash, _)] =
-            [step.rw_indices[6], step.rw_indices[7], step.rw_indices[8]]
-                .map(|idx| block.rws[idx].account_value_pair());
+        // The balances are written after the access list of the precompiled
+        // contracts, the caller and the callee, and the nonce of the created
+        // account.
+        let num_precompiles = block.hardfork.num_precompiles() as usize;
+        let [caller_balance_pair, callee_balance_pair] = [6, 7].map(|idx| {
+            block.rws[step.rw_indices[idx + num_precompiles + tx.is_create as usize]]
+                .account_value_pair()
+        });
 
         self.tx_id
             .assign(region, offset, Some(F::from(tx.id as u64)))?;
@@ -231,6 +483,31 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         self.tx_gas.assign(region, offset, Some(F::from(tx.gas)))?;
         self.tx_gas_price
             .assign(region, offset, Some(tx.gas_price.to_le_bytes()))?;
+        self.tx_type
+            .assign(region, offset, Some(F::from(tx.tx_type)))?;
+        self.tx_max_fee_per_gas
+            .assign(region, offset, Some(tx.max_fee_per_gas.to_le_bytes()))?;
+        self.is_dynamic_fee_tx.assign(
+            region,
+            offset,
+            F::from(tx.tx_type),
+            F::from(DYNAMIC_FEE_TX_TYPE),
+        )?;
+        let base_fee = block.context.base_fee;
+        let priority_fee_plus_base_fee = base_fee + tx.max_priority_fee_per_gas;
+        self.add_priority_fee_to_base_fee.assign(
+            region,
+            offset,
+            [base_fee, tx.max_priority_fee_per_gas],
+            priority_fee_plus_base_fee,
+        )?;
+        for (gadget, rhs) in [
+            (&self.max_fee_lt_fee_sum, priority_fee_plus_base_fee),
+            (&self.max_fee_lt_priority_fee, tx.max_priority_fee_per_gas),
+            (&self.max_fee_lt_base_fee, base_fee),
+        ] {
+            gadget.assign(region, offset, tx.max_fee_per_gas, rhs)?;
+        }
         self.mul_gas_fee_by_gas
             .assign(region, offset, tx.gas_price, tx.gas, gas_fee)?;
         self.tx_caller_address
@@ -239,6 +516,54 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             .assign(region, offset, tx.callee_address.to_scalar())?;
         self.tx_is_create
             .assign(region, offset, Some(F::from(tx.is_create as u64)))?;
+        self.callee_address
+            .assign(region, offset, call.callee_address.to_scalar())?;
+        for (cell, byte) in self
+            .caller_address_bytes
+            .iter()
+            .zip(tx.caller_address.to_fixed_bytes().iter().rev())
+        {
+            cell.assign(region, offset, Some(F::from(*byte as u64)))?;
+        }
+        let nonce_bytes = tx.nonce.to_le_bytes();
+        for (cell, byte) in self.nonce_bytes.iter().zip(nonce_bytes.iter()) {
+            cell.assign(region, offset, Some(F::from(*byte as u64)))?;
+        }
+        for (idx, is_zero) in self.is_nonce_suffix_zero.iter().enumerate() {
+            let suffix_sum = nonce_bytes[idx..]
+                .iter()
+                .map(|byte| *byte as u64)
+                .sum::<u64>();
+            is_zero.assign(region, offset, F::from(suffix_sum))?;
+        }
+        self.is_nonce_lt_0x80.assign(
+            region,
+            offset,
+            F::from(nonce_bytes[0] as u64),
+            F::from(0x80),
+        )?;
+        let mut create_address_hash = if tx.is_create {
+            keccak256(&create_address_rlp(&tx.caller_address, tx.nonce))
+        } else {
+            [0; 32]
+        };
+        create_address_hash.reverse();
+        self.create_address_hash
+            .assign(region, offset, Some(create_address_hash))?;
+        let callee_address = call.callee_address.to_scalar().unwrap();
+        self.is_callee_zero.assign(region, offset, callee_address)?;
+        self.is_callee_lt_num_precompiles.assign(
+            region,
+            offset,
+            callee_address,
+            F::from(block.hardfork.num_precompiles() + 1),
+        )?;
+        self.is_callee_caller.assign(
+            region,
+            offset,
+            callee_address,
+            tx.caller_address.to_scalar().unwrap(),
+        )?;
         self.tx_call_data_length.assign(
             region,
             offset,
@@ -266,10 +591,19 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             region,
             offset,
             Some(RandomLinearCombination::random_linear_combine(
-                callee_code_hash.to_le_bytes(),
+                call.code_hash.to_le_bytes(),
                 block.randomness,
             )),
         )?;
+        self.is_call_data_empty
+            .assign(region, offset, F::from(tx.call_data_length as u64))?;
+        let init_code_rlc = if tx.is_create {
+            tx.call_data.rlc_stream(block.randomness)
+        } else {
+            F::zero()
+        };
+        self.init_code_rlc
+            .assign(region, offset, Some(init_code_rlc))?;
         Ok(())
     }
 }
@@ -282,6 +616,7 @@ mod test {
     };
     use bus_mapping::{evm::OpcodeId, mock::BlockData};
     use eth_types::{self, bytecode, evm_types::GasCost, geth_types::GethData, Word};
+    use halo2_proofs::dev::VerifyFailure;
     use mock::{
         eth, gwei, test_ctx::helpers::account_0_code_account_1_no_code, TestContext, MOCK_ACCOUNTS,
     };
@@ -368,6 +703,52 @@ mod test {
         );
     }
 
+    fn test_dynamic_fee(
+        gas_price: Word,
+        max_fee_per_gas: Word,
+        max_priority_fee_per_gas: Word,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let code = bytecode! {
+            STOP
+        };
+
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(code),
+            |mut txs, accs| {
+                txs[0]
+                    .to(accs[0].address)
+                    .from(accs[1].address)
+                    .transaction_type(2)
+                    .gas_price(gas_price)
+                    .max_fee_per_gas(max_fee_per_gas)
+                    .max_priority_fee_per_gas(max_priority_fee_per_gas);
+            },
+            |block, _tx| block.base_fee_per_gas(gwei(1)),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        let block = block_convert(&builder.block, &builder.code_db);
+        run_test_circuit_incomplete_fixed_table(block)
+    }
+
+    #[test]
+    fn begin_tx_dynamic_fee() {
+        // Effective gas price capped by the max priority fee
+        assert_eq!(test_dynamic_fee(gwei(2), gwei(3), gwei(1)), Ok(()));
+        // Effective gas price capped by the max fee
+        assert_eq!(test_dynamic_fee(gwei(3), gwei(3), gwei(3)), Ok(()));
+        // Gas price isn't the effective gas price
+        assert!(test_dynamic_fee(gwei(3), gwei(3), gwei(1)).is_err());
+        // Max priority fee greater than max fee
+        assert!(test_dynamic_fee(gwei(3), gwei(3), gwei(4)).is_err());
+    }
+
     #[test]
     fn begin_tx_large_nonce() {
         // This test checks that the rw table assignment and evm circuit are consistent
@@ -405,6 +786,37 @@ mod test {
         assert_eq!(run_test_circuit_incomplete_fixed_table(block), Ok(()));
     }
 
+    #[test]
+    fn begin_tx_gadget_create() {
+        let init_code = bytecode! {
+            STOP
+        };
+
+        let block: GethData = TestContext::<1, 1>::new(
+            None,
+            |accs| {
+                accs[0].address(MOCK_ACCOUNTS[0]).balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[0].address)
+                    .value(eth(1))
+                    .input(init_code.to_vec().into());
+            },
+            |block, _| block,
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        let block = block_convert(&builder.block, &builder.code_db);
+
+        assert_eq!(run_test_circuit_incomplete_fixed_table(block), Ok(()));
+    }
+
     #[test]
     fn begin_tx_gadget_rand() {
         let random_amount = Word::from_little_endian(&rand_bytes(32)) % eth(1);
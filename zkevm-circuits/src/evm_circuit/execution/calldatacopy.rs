@@ -165,7 +165,7 @@ impl<F: Field> ExecutionGadget<F> for CallDataCopyGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         tx: &Transaction,
@@ -169,7 +169,7 @@ impl<F: Field> ExecutionGadget<F> for CallDataLoadGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         tx: &Transaction,
@@ -1,25 +1,27 @@
 use crate::{
     evm_circuit::{
         execution::ExecutionGadget,
+        param::N_BYTES_U64,
         step::ExecutionState,
         table::BlockContextFieldTag,
         util::{
             common_gadget::SameContextGadget,
             constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
-            CachedRegion, Cell, Word,
+            from_bytes, CachedRegion, RandomLinearCombination,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
 use bus_mapping::evm::OpcodeId;
-use eth_types::{Field, ToLittleEndian};
+use eth_types::Field;
 use halo2_proofs::plonk::Error;
+use std::convert::TryFrom;
 
 #[derive(Clone, Debug)]
 pub(crate) struct ChainIdGadget<F> {
     same_context: SameContextGadget<F>,
-    chain_id: Cell<F>,
+    chain_id: RandomLinearCombination<F, N_BYTES_U64>,
 }
 
 impl<F: Field> ExecutionGadget<F> for ChainIdGadget<F> {
@@ -28,13 +30,17 @@ impl<F: Field> ExecutionGadget<F> for ChainIdGadget<F> {
     const EXECUTION_STATE: ExecutionState = ExecutionState::CHAINID;
 
     fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
-        let chain_id = cb.query_cell();
+        let chain_id = cb.query_rlc();
 
         // Push the value to the stack
         cb.stack_push(chain_id.expr());
 
         // Lookup block table with chain_id
-        cb.block_lookup(BlockContextFieldTag::ChainId.expr(), None, chain_id.expr());
+        cb.block_lookup(
+            BlockContextFieldTag::ChainId.expr(),
+            None,
+            from_bytes::expr(&chain_id.cells),
+        );
 
         // State transition
         let opcode = cb.query_cell();
@@ -55,7 +61,7 @@ impl<F: Field> ExecutionGadget<F> for ChainIdGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -68,10 +74,7 @@ impl<F: Field> ExecutionGadget<F> for ChainIdGadget<F> {
         self.chain_id.assign(
             region,
             offset,
-            Some(Word::random_linear_combine(
-                chain_id.to_le_bytes(),
-                block.randomness,
-            )),
+            Some(u64::try_from(chain_id).unwrap().to_le_bytes()),
         )?;
         Ok(())
     }
@@ -63,7 +63,7 @@ impl<F: Field> ExecutionGadget<F> for CoinbaseGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -33,7 +33,7 @@ impl<F: Field> ExecutionGadget<F> for EndBlockGadget<F> {
 
     fn assign_exec_step(
         &self,
-        _region: &mut CachedRegion<'_, '_, F>,
+        _region: &mut CachedRegion<F>,
         _offset: usize,
         _: &Block<F>,
         _: &Transaction,
@@ -20,7 +20,7 @@ use crate::{
     },
     util::Expr,
 };
-use eth_types::{evm_types::MAX_REFUND_QUOTIENT_OF_GAS_USED, Field, ToScalar};
+use eth_types::{Field, ToScalar};
 use halo2_proofs::plonk::Error;
 use strum::EnumCount;
 
@@ -57,16 +57,17 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
                 .map(|field_tag| cb.tx_context(tx_id.expr(), field_tag, None));
         let tx_gas_price = cb.tx_context_as_word(tx_id.expr(), TxContextFieldTag::GasPrice, None);
 
-        // Calculate effective gas to refund
+        // Calculate effective gas to refund, bounded by a fraction of the gas
+        // used depending on the hardfork.
         let gas_used = tx_gas.expr() - cb.curr.state.gas_left.expr();
-        let max_refund = ConstantDivisionGadget::construct(
-            cb,
-            gas_used.clone(),
-            MAX_REFUND_QUOTIENT_OF_GAS_USED as u64,
-        );
+        let max_refund_quotient = cb.hardfork().max_refund_quotient();
+        let max_refund =
+            ConstantDivisionGadget::construct(cb, gas_used.clone(), max_refund_quotient);
         let refund = cb.query_cell();
         cb.tx_refund_read(tx_id.expr(), refund.expr());
         let effective_refund = MinMaxGadget::construct(cb, max_refund.quotient(), refund.expr());
+        // The gas used reported in the receipt is net of the refund.
+        let gas_used_after_refund = gas_used.clone() - effective_refund.min();
 
         // Add effective_refund * tx_gas_price back to caller's balance
         let mul_gas_price_by_refund = MulWordByU64Gadget::construct(
@@ -81,7 +82,7 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
             None,
         );
 
-        // Add gas_used * effective_tip to coinbase's balance
+        // Add gas_used_after_refund * effective_tip to coinbase's balance
         let coinbase = cb.query_cell();
         let base_fee = cb.query_word();
         for (tag, value) in [
@@ -94,7 +95,7 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
         let sub_gas_price_by_base_fee =
             AddWordsGadget::construct(cb, [effective_tip.clone(), base_fee], tx_gas_price);
         let mul_effective_tip_by_gas_used =
-            MulWordByU64Gadget::construct(cb, effective_tip, gas_used.clone());
+            MulWordByU64Gadget::construct(cb, effective_tip, gas_used_after_refund.clone());
         let coinbase_reward = UpdateBalanceGadget::construct(
             cb,
             coinbase.expr(),
@@ -139,7 +140,7 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
             1.expr(),
             tx_id.expr(),
             TxReceiptFieldTag::CumulativeGasUsed,
-            gas_used + current_cumulative_gas_used.expr(),
+            gas_used_after_refund + current_cumulative_gas_used.expr(),
         );
 
         cb.condition(
@@ -190,7 +191,7 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         tx: &Transaction,
@@ -214,6 +215,7 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
             F::from(refund),
         )?;
         let effective_refund = refund.min(max_refund as u64);
+        let gas_used_after_refund = gas_used - effective_refund;
         let gas_fee_refund = tx.gas_price * (effective_refund + step.gas_left);
         self.mul_gas_price_by_refund.assign(
             region,
@@ -242,8 +244,8 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
             region,
             offset,
             effective_tip,
-            gas_used,
-            effective_tip * gas_used,
+            gas_used_after_refund,
+            effective_tip * gas_used_after_refund,
         )?;
         self.coinbase
             .assign(region, offset, block.context.coinbase.to_scalar())?;
@@ -251,7 +253,7 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
             region,
             offset,
             coinbase_balance_prev,
-            vec![effective_tip * gas_used],
+            vec![effective_tip * gas_used_after_refund],
             coinbase_balance,
         )?;
 
@@ -286,8 +288,12 @@ mod test {
     use crate::evm_circuit::{
         test::run_test_circuit_incomplete_fixed_table, witness::block_convert,
     };
-    use eth_types::{self, bytecode, geth_types::GethData};
-    use mock::{eth, test_ctx::helpers::account_0_code_account_1_no_code, TestContext};
+    use eth_types::{self, bytecode, geth_types::GethData, Word};
+    use mock::{
+        eth,
+        test_ctx::helpers::{account_0_code_account_1_no_code, tx_from_1_to_0},
+        TestContext, MOCK_ACCOUNTS,
+    };
 
     fn test_ok(block: GethData) {
         let block_data = bus_mapping::mock::BlockData::new_from_geth_data(block);
@@ -302,20 +308,6 @@ mod test {
 
     #[test]
     fn end_tx_gadget_simple() {
-        // TODO: Enable this with respective code when SSTORE is implemented.
-        // Tx with non-capped refund
-        // test_ok(vec![mock_tx(
-        //     address!("0x00000000000000000000000000000000000000fe"),
-        //     Some(27000),
-        //     None,
-        // )]);
-        // Tx with capped refund
-        // test_ok(vec![mock_tx(
-        //     address!("0x00000000000000000000000000000000000000fe"),
-        //     Some(65000),
-        //     None,
-        // )]);
-
         // Multiple txs
         test_ok(
             // Get the execution steps from the external tracer
@@ -342,4 +334,32 @@ mod test {
             .into(),
         );
     }
+
+    #[test]
+    fn end_tx_gadget_with_refund() {
+        // Clearing a storage slot gives a refund, which is deducted from the
+        // gas used of the tx.
+        test_ok(
+            TestContext::<2, 1>::new(
+                None,
+                |accs| {
+                    accs[0]
+                        .address(MOCK_ACCOUNTS[0])
+                        .balance(eth(10))
+                        .code(bytecode! {
+                            PUSH1(0x00) // value
+                            PUSH1(0x00) // key
+                            SSTORE
+                            STOP
+                        })
+                        .storage(vec![(Word::zero(), Word::one())].into_iter());
+                    accs[1].address(MOCK_ACCOUNTS[1]).balance(eth(10));
+                },
+                tx_from_1_to_0,
+                |block, _tx| block.number(0xcafeu64),
+            )
+            .unwrap()
+            .into(),
+        );
+    }
 }
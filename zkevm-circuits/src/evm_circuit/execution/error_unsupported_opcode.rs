@@ -0,0 +1,55 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{constraint_builder::ConstraintBuilder, CachedRegion, Cell},
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::Field;
+use halo2_proofs::plonk::Error;
+
+/// Gadget of the opcodes known to the EVM but not supported by the circuit
+/// yet: BLOBHASH and BLOBBASEFEE of Cancun.  The steps executing them can't be
+/// proven, so their assignment fails with an error naming the opcode, and
+/// their constraint can't be satisfied.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorUnsupportedOpcodeGadget<F> {
+    opcode: Cell<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorUnsupportedOpcodeGadget<F> {
+    const NAME: &'static str = "ErrorUnsupportedOpcode";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorUnsupportedOpcode;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+
+        // TODO: Replace by the gadgets of BLOBHASH and BLOBBASEFEE once the
+        // blob versioned hashes and the blob base fee are in the tx table and
+        // the block table.
+        cb.require_zero("opcode is supported by the circuit", 1.expr());
+
+        Self { opcode }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<F>,
+        offset: usize,
+        _: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        log::error!("{:?} is not supported by the EVM circuit yet", opcode);
+        Err(Error::Synthesis)
+    }
+}
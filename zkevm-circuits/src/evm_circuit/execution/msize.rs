@@ -59,7 +59,7 @@ impl<F: Field> ExecutionGadget<F> for MsizeGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         _: &Block<F>,
         _: &Transaction,
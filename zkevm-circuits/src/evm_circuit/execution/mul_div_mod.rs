@@ -110,7 +110,7 @@ impl<F: Field> ExecutionGadget<F> for MulDivModGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
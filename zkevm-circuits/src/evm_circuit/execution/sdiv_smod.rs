@@ -13,7 +13,7 @@ use crate::{
     util::Expr,
 };
 use bus_mapping::evm::OpcodeId;
-use eth_types::{Field, ToLittleEndian, U256};
+use eth_types::{Field, ToLittleEndian, I256};
 use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
@@ -139,7 +139,7 @@ impl<F: Field> ExecutionGadget<F> for SignedDivModGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -149,40 +149,27 @@ impl<F: Field> ExecutionGadget<F> for SignedDivModGadget<F> {
         self.same_context.assign_exec_step(region, offset, step)?;
         let indices = [step.rw_indices[0], step.rw_indices[1], step.rw_indices[2]];
         let [pop1, pop2, push] = indices.map(|idx| block.rws[idx].stack_value());
-        let pop1_abs = get_abs(pop1);
-        let pop2_abs = get_abs(pop2);
-        let push_abs = get_abs(push);
-        let is_pop1_neg = is_neg(pop1);
-        let is_pop2_neg = is_neg(pop2);
         let (quotient, divisor, remainder, dividend) = match step.opcode.unwrap() {
             OpcodeId::SDIV => (
                 push,
                 pop2,
-                if is_pop1_neg {
-                    get_neg(pop1_abs - push_abs * pop2_abs)
+                if pop2.is_zero() {
+                    pop1
                 } else {
-                    pop1_abs - push_abs * pop2_abs
+                    (I256::from_raw(pop1) % I256::from_raw(pop2)).into_raw()
                 },
                 pop1,
             ),
             OpcodeId::SMOD => (
-                if pop2.is_zero() {
-                    U256::from(0)
-                } else if is_pop1_neg == is_pop2_neg {
-                    pop1_abs / pop2_abs
-                } else {
-                    get_neg(pop1_abs / pop2_abs)
-                },
+                (I256::from_raw(pop1) / I256::from_raw(pop2)).into_raw(),
                 pop2,
                 if pop2.is_zero() { pop1 } else { push },
                 pop1,
             ),
             _ => unreachable!(),
         };
-        let quotient_abs = get_abs(quotient);
-        let divisor_abs = get_abs(divisor);
-        let remainder_abs = get_abs(remainder);
-        let dividend_abs = get_abs(dividend);
+        let [quotient_abs, divisor_abs, remainder_abs, dividend_abs] =
+            [quotient, divisor, remainder, dividend].map(|x| I256::from_raw(x).unsigned_abs());
         self.quotient_abs_word
             .assign(region, offset, quotient, quotient_abs)?;
         self.divisor_abs_word
@@ -217,29 +204,6 @@ impl<F: Field> ExecutionGadget<F> for SignedDivModGadget<F> {
     }
 }
 
-#[inline]
-fn get_abs(x: U256) -> U256 {
-    if is_neg(x) {
-        get_neg(x)
-    } else {
-        x
-    }
-}
-
-#[inline]
-fn get_neg(x: U256) -> U256 {
-    if x.is_zero() {
-        x
-    } else {
-        U256::from_big_endian(&[255u8; 32]) - x + U256::from(1)
-    }
-}
-
-#[inline]
-fn is_neg(x: U256) -> bool {
-    127 < x.to_le_bytes()[31]
-}
-
 #[cfg(test)]
 mod test {
     use crate::{evm_circuit::test::rand_word, test_util::run_test_circuits};
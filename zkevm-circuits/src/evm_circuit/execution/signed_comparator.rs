@@ -144,7 +144,7 @@ impl<F: Field> ExecutionGadget<F> for SignedComparatorGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         _transaction: &Transaction,
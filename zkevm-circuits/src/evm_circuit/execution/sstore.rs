@@ -135,7 +135,7 @@ impl<F: Field> ExecutionGadget<F> for SstoreGadget<F> {
 
     fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         tx: &Transaction,
@@ -290,7 +290,7 @@ impl<F: Field> SstoreGasGadget<F> {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         gas_cost: u64,
         value: eth_types::Word,
@@ -360,6 +360,7 @@ pub(crate) struct SstoreTxRefundGadget<F> {
     original_eq_value_gadget: IsEqualGadget<F>,
     prev_eq_value_gadget: IsEqualGadget<F>,
     original_eq_prev_gadget: IsEqualGadget<F>,
+    sstore_clears_schedule: GasCost,
 }
 
 impl<F: Field> SstoreTxRefundGadget<F> {
@@ -406,11 +407,12 @@ impl<F: Field> SstoreTxRefundGadget<F> {
         let recreate_slot =
             not::expr(prev_eq_value) * not::expr(original_eq_prev) * (value_prev_is_zero);
 
+        let sstore_clears_schedule = cb.hardfork().sstore_clears_schedule();
         let tx_refund_new = tx_refund_old.expr()
-            + delete_slot * GasCost::SSTORE_CLEARS_SCHEDULE.expr()
+            + delete_slot * sstore_clears_schedule.expr()
             + reset_existing * (GasCost::SSTORE_RESET.expr() - GasCost::WARM_ACCESS.expr())
             + reset_inexistent * (GasCost::SSTORE_SET.expr() - GasCost::WARM_ACCESS.expr())
-            - recreate_slot * (GasCost::SSTORE_CLEARS_SCHEDULE.expr());
+            - recreate_slot * (sstore_clears_schedule.expr());
 
         Self {
             value,
@@ -424,6 +426,7 @@ impl<F: Field> SstoreTxRefundGadget<F> {
             original_eq_value_gadget,
             prev_eq_value_gadget,
             original_eq_prev_gadget,
+            sstore_clears_schedule,
         }
     }
 
@@ -435,7 +438,7 @@ impl<F: Field> SstoreTxRefundGadget<F> {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         tx_refund: u64,
         tx_refund_old: u64,
@@ -501,7 +504,13 @@ impl<F: Field> SstoreTxRefundGadget<F> {
             Word::random_linear_combine(value_prev.to_le_bytes(), randomness),
         )?;
         debug_assert_eq!(
-            calc_expected_tx_refund(tx_refund_old, value, value_prev, original_value),
+            calc_expected_tx_refund(
+                tx_refund_old,
+                value,
+                value_prev,
+                original_value,
+                self.sstore_clears_schedule
+            ),
             tx_refund
         );
         Ok(())
@@ -537,6 +546,7 @@ fn calc_expected_tx_refund(
     value: eth_types::Word,
     value_prev: eth_types::Word,
     original_value: eth_types::Word,
+    sstore_clears_schedule: GasCost,
 ) -> u64 {
     // Same clause tags(like "delete slot (2.1.2b)") used as [`makeGasSStoreFunc` in go-ethereum](https://github.com/ethereum/go-ethereum/blob/9fd8825d5a196edde6d8ef81382979875145b346/core/vm/operations_acl.go#L27)
     // Control flow of this function try to follow `makeGasSStoreFunc` for better
@@ -563,11 +573,11 @@ fn calc_expected_tx_refund(
         if !original_value.is_zero() {
             if value_prev.is_zero() {
                 // recreate slot (2.2.1.1)
-                tx_refund_new -= GasCost::SSTORE_CLEARS_SCHEDULE.as_u64()
+                tx_refund_new -= sstore_clears_schedule.as_u64()
             }
             if value.is_zero() {
                 // delete slot (2.2.1.2)
-                tx_refund_new += GasCost::SSTORE_CLEARS_SCHEDULE.as_u64()
+                tx_refund_new += sstore_clears_schedule.as_u64()
             }
         }
 
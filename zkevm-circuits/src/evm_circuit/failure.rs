@@ -0,0 +1,241 @@
+//! Translation of the MockProver failures of the EVM circuit back to the
+//! execution steps of the block, so that a failure reads as "tx 1, call 1,
+//! step 3 (ADD)" rather than as "constraint 37 in gate 113 at offset 180211".
+
+use super::{step::ExecutionState, witness::Block};
+use eth_types::evm_types::OpcodeId;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    dev::{metadata, FailureLocation, MockProver, VerifyFailure},
+};
+use std::fmt;
+
+/// Name of the region in which the execution steps are assigned.
+const EXECUTION_STEP_REGION: &str = "Execution step";
+
+/// The execution step assigned at some rows of the EVM circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepLocation {
+    /// The transaction identifier in the block
+    pub tx_id: usize,
+    /// The identifier of the call of the step
+    pub call_id: usize,
+    /// The index of the step in the transaction
+    pub step_index: usize,
+    /// The opcode of the step, if any
+    pub opcode: Option<OpcodeId>,
+    /// The execution state of the step, which names its gadget
+    pub execution_state: ExecutionState,
+    /// The first offset of the step in the execution step region
+    pub offset: usize,
+    /// The number of rows of the step
+    pub height: usize,
+}
+
+impl fmt::Display for StepLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "tx {}, call {}, step {}",
+            self.tx_id, self.call_id, self.step_index
+        )?;
+        if let Some(opcode) = self.opcode {
+            write!(f, " ({:?})", opcode)?;
+        }
+        write!(f, ", gadget {:?}", self.execution_state)
+    }
+}
+
+/// The locations of the execution steps of a block, in the order in which
+/// they are assigned.
+#[derive(Clone, Debug, Default)]
+pub struct StepMap {
+    steps: Vec<StepLocation>,
+}
+
+impl StepMap {
+    /// Returns the locations of the steps of `block`, assigned with the
+    /// heights given by `step_height`.
+    pub fn new<F>(block: &Block<F>, step_height: impl Fn(ExecutionState) -> usize) -> Self {
+        let mut offset = 0;
+        let steps = block
+            .txs
+            .iter()
+            .flat_map(|tx| {
+                tx.steps
+                    .iter()
+                    .enumerate()
+                    .map(move |(step_index, step)| (tx, step_index, step))
+            })
+            .map(|(tx, step_index, step)| {
+                let height = step_height(step.execution_state);
+                let location = StepLocation {
+                    tx_id: tx.id,
+                    call_id: tx.calls[step.call_index].id,
+                    step_index,
+                    opcode: step.opcode,
+                    execution_state: step.execution_state,
+                    offset,
+                    height,
+                };
+                offset += height;
+                location
+            })
+            .collect();
+
+        Self { steps }
+    }
+
+    /// Returns the step assigned at `offset` of the execution step region.
+    pub fn locate(&self, offset: usize) -> Option<&StepLocation> {
+        let index = self
+            .steps
+            .partition_point(|step| step.offset + step.height <= offset);
+        self.steps.get(index).filter(|step| step.offset <= offset)
+    }
+
+    /// Returns the failure with the step at which it happened, when it
+    /// happened in the execution step region.
+    pub fn prettify(&self, failure: VerifyFailure) -> PrettyFailure {
+        let step = execution_step_offset(&failure)
+            .and_then(|offset| self.locate(offset))
+            .cloned();
+        PrettyFailure { step, failure }
+    }
+}
+
+/// A MockProver failure of the EVM circuit, with the step at which it
+/// happened.
+#[derive(Debug, PartialEq)]
+pub struct PrettyFailure {
+    /// The step at which the failure happened, or `None` if it happened
+    /// outside of the execution step region
+    pub step: Option<StepLocation>,
+    /// The failure reported by the MockProver
+    pub failure: VerifyFailure,
+}
+
+impl fmt::Display for PrettyFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.step {
+            Some(step) => write!(f, "{}: {}", step, self.failure),
+            None => write!(f, "outside of the execution steps: {}", self.failure),
+        }
+    }
+}
+
+fn region_offset(region: &metadata::Region, offset: usize) -> Option<usize> {
+    // The fields of the region metadata aren't public, so we have to match off
+    // of its format string.
+    region
+        .to_string()
+        .contains(&format!("'{}'", EXECUTION_STEP_REGION))
+        .then(|| offset)
+}
+
+fn execution_step_offset(failure: &VerifyFailure) -> Option<usize> {
+    match failure {
+        VerifyFailure::ConstraintNotSatisfied {
+            location: FailureLocation::InRegion { region, offset },
+            ..
+        }
+        | VerifyFailure::Lookup {
+            location: FailureLocation::InRegion { region, offset },
+            ..
+        } => region_offset(region, *offset),
+        VerifyFailure::CellNotAssigned {
+            region,
+            gate_offset,
+            ..
+        } => region_offset(region, *gate_offset),
+        _ => None,
+    }
+}
+
+/// Wraps [`MockProver::verify_at_rows`] so that the failures come with the
+/// steps of `block` at which they happened.
+pub fn verify_at_rows_pretty<F: FieldExt>(
+    prover: &MockProver<F>,
+    steps: &StepMap,
+    gate_row_ids: impl Iterator<Item = usize>,
+    lookup_input_row_ids: impl Iterator<Item = usize>,
+) -> Result<(), Vec<PrettyFailure>> {
+    prover
+        .verify_at_rows(gate_row_ids, lookup_input_row_ids)
+        .map_err(|failures| {
+            failures
+                .into_iter()
+                .map(|failure| steps.prettify(failure))
+                .collect()
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{StepMap, EXECUTION_STEP_REGION};
+    use crate::evm_circuit::{
+        step::ExecutionState,
+        witness::{Block, Call, ExecStep, Transaction},
+    };
+    use eth_types::evm_types::OpcodeId;
+    use halo2_proofs::{
+        dev::{metadata::Region, FailureLocation, VerifyFailure},
+        pairing::bn256::Fr,
+    };
+
+    fn lookup_failure(region: &'static str, offset: usize) -> VerifyFailure {
+        VerifyFailure::Lookup {
+            name: "rw lookup",
+            lookup_index: 0,
+            location: FailureLocation::InRegion {
+                region: Region::from((1, region)),
+                offset,
+            },
+        }
+    }
+
+    #[test]
+    fn prettify_failures() {
+        let step = |execution_state, opcode| ExecStep {
+            execution_state,
+            opcode,
+            ..Default::default()
+        };
+        let block = Block::<Fr> {
+            txs: vec![Transaction {
+                id: 1,
+                calls: vec![Call {
+                    id: 1,
+                    ..Default::default()
+                }],
+                steps: vec![
+                    step(ExecutionState::BeginTx, None),
+                    step(ExecutionState::PUSH, Some(OpcodeId::PUSH1)),
+                    step(ExecutionState::STOP, Some(OpcodeId::STOP)),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        // BeginTx spans the offsets 0..4, PUSH 4..6 and STOP 6..8.
+        let steps = StepMap::new(&block, |state| match state {
+            ExecutionState::BeginTx => 4,
+            _ => 2,
+        });
+
+        let failure = steps.prettify(lookup_failure(EXECUTION_STEP_REGION, 5));
+        let step = failure.step.as_ref().unwrap();
+        assert_eq!(
+            (step.tx_id, step.call_id, step.step_index, step.opcode),
+            (1, 1, 1, Some(OpcodeId::PUSH1))
+        );
+        assert!(failure
+            .to_string()
+            .starts_with("tx 1, call 1, step 1 (PUSH1)"));
+
+        assert_eq!(steps.locate(0).unwrap().step_index, 0);
+        assert_eq!(steps.locate(6).unwrap().step_index, 2);
+        assert_eq!(steps.locate(8), None);
+        assert_eq!(steps.prettify(lookup_failure("fixed table", 5)).step, None);
+    }
+}
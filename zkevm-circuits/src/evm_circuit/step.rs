@@ -117,6 +117,8 @@ pub enum ExecutionState {
     ErrorOutOfGasCREATE2,
     ErrorOutOfGasSTATICCALL,
     ErrorOutOfGasSELFDESTRUCT,
+    // Opcodes not supported by the circuit yet
+    ErrorUnsupportedOpcode, // BLOBHASH, BLOBBASEFEE
 }
 
 impl Default for ExecutionState {
@@ -317,6 +319,7 @@ impl ExecutionState {
             Self::STATICCALL => vec![OpcodeId::STATICCALL],
             Self::REVERT => vec![OpcodeId::REVERT],
             Self::SELFDESTRUCT => vec![OpcodeId::SELFDESTRUCT],
+            Self::ErrorUnsupportedOpcode => vec![OpcodeId::BLOBHASH, OpcodeId::BLOBBASEFEE],
             _ => vec![],
         }
     }
@@ -411,7 +414,7 @@ impl<F: FieldExt> Step<F> {
 
     pub(crate) fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
@@ -1,4 +1,5 @@
-use crate::{evm_circuit::step::ExecutionState, impl_expr};
+use crate::{evm_circuit::step::ExecutionState, impl_expr, util::Expr};
+pub use eth_types::public_data::{BlockContextFieldTag, TxContextFieldTag};
 use halo2_proofs::{
     arithmetic::FieldExt,
     plonk::{Advice, Column, Expression, Fixed, VirtualCells},
@@ -115,33 +116,6 @@ impl FixedTableTag {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-pub enum TxContextFieldTag {
-    Nonce = 1,
-    Gas,
-    GasPrice,
-    CallerAddress,
-    CalleeAddress,
-    IsCreate,
-    Value,
-    CallDataLength,
-    CallDataGasCost,
-    CallData,
-}
-
-// Keep the sequence consistent with OpcodeId for scalar
-#[derive(Clone, Copy, Debug)]
-pub enum BlockContextFieldTag {
-    Coinbase = 1,
-    Timestamp,
-    Number,
-    Difficulty,
-    GasLimit,
-    BaseFee = 8,
-    BlockHash,
-    ChainId,
-}
-
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter)]
 pub enum RwTableTag {
     Start = 1,
@@ -238,12 +212,10 @@ pub enum CallContextFieldTag {
 }
 
 impl_expr!(FixedTableTag);
-impl_expr!(TxContextFieldTag);
 impl_expr!(RwTableTag);
 impl_expr!(AccountFieldTag);
 impl_expr!(BytecodeFieldTag);
 impl_expr!(CallContextFieldTag);
-impl_expr!(BlockContextFieldTag);
 impl_expr!(TxLogFieldTag);
 impl_expr!(TxReceiptFieldTag);
 
@@ -256,6 +228,7 @@ pub(crate) enum Table {
     Block,
     Byte,
     Copy,
+    Keccak,
 }
 
 #[derive(Clone, Debug)]
@@ -352,6 +325,19 @@ pub(crate) enum Lookup<F> {
         /// The RW counter that is incremented by the time all bytes have been
         /// copied specific to this copy event.
         rwc_inc: Expression<F>,
+        /// The random linear combination of the copied bytes, which is only
+        /// accumulated when the destination is CopyDataType::RlcAcc.
+        rlc_acc: Expression<F>,
+    },
+    /// Lookup to keccak table.
+    KeccakTable {
+        /// Random linear combination of the hashed bytes, the first byte
+        /// having the highest power of randomness.
+        input_rlc: Expression<F>,
+        /// Number of hashed bytes.
+        input_len: Expression<F>,
+        /// Random linear combination of the digest as a little endian word.
+        output_rlc: Expression<F>,
     },
     /// Conditional lookup enabled by the first element.
     Conditional(Expression<F>, Box<Lookup<F>>),
@@ -371,6 +357,7 @@ impl<F: FieldExt> Lookup<F> {
             Self::Block { .. } => Table::Block,
             Self::Byte { .. } => Table::Byte,
             Self::CopyTable { .. } => Table::Copy,
+            Self::KeccakTable { .. } => Table::Keccak,
             Self::Conditional(_, lookup) => lookup.table(),
         }
     }
@@ -431,6 +418,7 @@ impl<F: FieldExt> Lookup<F> {
                 length,
                 rw_counter,
                 rwc_inc,
+                rlc_acc,
             } => vec![
                 is_first.clone(),
                 src_id.clone(),
@@ -443,6 +431,17 @@ impl<F: FieldExt> Lookup<F> {
                 length.clone(),
                 rw_counter.clone(),
                 rwc_inc.clone(),
+                rlc_acc.clone(),
+            ],
+            Self::KeccakTable {
+                input_rlc,
+                input_len,
+                output_rlc,
+            } => vec![
+                1.expr(), // is_enabled
+                input_rlc.clone(),
+                input_len.clone(),
+                output_rlc.clone(),
             ],
             Self::Conditional(condition, lookup) => lookup
                 .input_exprs()
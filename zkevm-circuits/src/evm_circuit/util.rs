@@ -5,11 +5,11 @@ use crate::{
     },
     util::Expr,
 };
-use eth_types::U256;
+use eth_types::{Rlc, U256};
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::{AssignedCell, Region},
-    plonk::{Advice, Assigned, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    circuit::Region,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, VirtualCells},
     poly::Rotation,
 };
 use std::collections::BTreeMap;
@@ -41,21 +41,12 @@ impl<F: FieldExt> Cell<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         value: Option<F>,
-    ) -> Result<AssignedCell<F, F>, Error> {
-        region.assign_advice(
-            || {
-                format!(
-                    "Cell column: {:?} and rotation: {}",
-                    self.column, self.rotation
-                )
-            },
-            self.column,
-            offset + self.rotation,
-            || value.ok_or(Error::Synthesis),
-        )
+    ) -> Result<(), Error> {
+        region.assign_advice(self.column, offset + self.rotation, value);
+        Ok(())
     }
 }
 
@@ -71,18 +62,22 @@ impl<F: FieldExt> Expr<F> for &Cell<F> {
     }
 }
 
-pub struct CachedRegion<'r, 'b, F: FieldExt> {
-    region: &'r mut Region<'b, F>,
+/// Region in which the witness of an execution step is assigned, caching the
+/// values of its cells, which are read to assign the stored expressions, and
+/// recording the assignments until they're copied into the halo2 region.  It
+/// doesn't borrow the halo2 region, so that the steps can be assigned in
+/// parallel.
+pub struct CachedRegion<F: FieldExt> {
     advice: Vec<Vec<F>>,
+    assignments: Vec<(Column<Advice>, usize, Option<F>)>,
     power_of_randomness: [F; 31],
     width_start: usize,
     height_start: usize,
 }
 
-impl<'r, 'b, F: FieldExt> CachedRegion<'r, 'b, F> {
+impl<F: FieldExt> CachedRegion<F> {
     /// New cached region
     pub(crate) fn new(
-        region: &'r mut Region<'b, F>,
         power_of_randomness: [F; 31],
         width: usize,
         height: usize,
@@ -90,39 +85,26 @@ impl<'r, 'b, F: FieldExt> CachedRegion<'r, 'b, F> {
         height_start: usize,
     ) -> Self {
         Self {
-            region,
             advice: vec![vec![F::zero(); height]; width],
+            assignments: Vec::new(),
             power_of_randomness,
             width_start,
             height_start,
         }
     }
 
-    /// Assign an advice column value (witness).
-    pub fn assign_advice<'v, V, VR, A, AR>(
-        &'v mut self,
-        annotation: A,
-        column: Column<Advice>,
-        offset: usize,
-        to: V,
-    ) -> Result<AssignedCell<VR, F>, Error>
-    where
-        V: FnMut() -> Result<VR, Error> + 'v,
-        for<'vr> Assigned<F>: From<&'vr VR>,
-        A: Fn() -> AR,
-        AR: Into<String>,
-    {
-        // Actually set the value
-        let res = self.region.assign_advice(annotation, column, offset, to);
-        // Cache the value
-        if let Result::Ok(cell) = &res {
-            let call_value = cell.value_field();
-            if let Some(value) = call_value {
-                self.advice[column.index() - self.width_start][offset - self.height_start] =
-                    value.evaluate();
-            }
+    /// Assign an advice column value (witness).  A missing value fails the
+    /// synthesis when it's copied into the halo2 region.
+    pub fn assign_advice(&mut self, column: Column<Advice>, offset: usize, value: Option<F>) {
+        if let Some(value) = value {
+            self.advice[column.index() - self.width_start][offset - self.height_start] = value;
         }
-        res
+        self.assignments.push((column, offset, value));
+    }
+
+    /// Returns the assignments of the region, without the cached values.
+    pub(crate) fn into_assignments(self) -> CellAssignments<F> {
+        CellAssignments(self.assignments)
     }
 
     pub fn get_fixed(&self, _row_index: usize, _column_index: usize, _rotation: Rotation) -> F {
@@ -139,6 +121,25 @@ impl<'r, 'b, F: FieldExt> CachedRegion<'r, 'b, F> {
     }
 }
 
+/// Values assigned to the cells of the advice columns by a [`CachedRegion`], in
+/// the order of their assignments.
+pub(crate) struct CellAssignments<F>(Vec<(Column<Advice>, usize, Option<F>)>);
+
+impl<F: FieldExt> CellAssignments<F> {
+    /// Copy the assignments into `region`.
+    pub(crate) fn assign(&self, region: &mut Region<'_, F>) -> Result<(), Error> {
+        for (column, offset, value) in self.0.iter() {
+            region.assign_advice(
+                || format!("Cell column: {:?} and offset: {}", column, offset),
+                *column,
+                *offset,
+                || value.ok_or(Error::Synthesis),
+            )?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct StoredExpression<F> {
     name: String,
@@ -149,11 +150,7 @@ pub struct StoredExpression<F> {
 }
 
 impl<F: FieldExt> StoredExpression<F> {
-    pub fn assign(
-        &self,
-        region: &mut CachedRegion<'_, '_, F>,
-        offset: usize,
-    ) -> Result<AssignedCell<F, F>, Error> {
+    pub fn assign(&self, region: &mut CachedRegion<F>, offset: usize) -> Result<(), Error> {
         let value = self.expr.evaluate(
             &|scalar| scalar,
             &|_| unimplemented!("selector column"),
@@ -309,7 +306,7 @@ impl<F: FieldExt, const N: usize> RandomLinearCombination<F, N> {
 
     // TODO: replace `bytes` type by a reference
     pub(crate) fn random_linear_combine(bytes: [u8; N], randomness: F) -> F {
-        rlc::value(&bytes, randomness)
+        bytes.rlc(randomness)
     }
 
     pub(crate) fn random_linear_combine_expr(
@@ -331,10 +328,10 @@ impl<F: FieldExt, const N: usize> RandomLinearCombination<F, N> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         bytes: Option<[u8; N]>,
-    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    ) -> Result<(), Error> {
         bytes.map_or(Err(Error::Synthesis), |bytes| {
             self.cells
                 .iter()
@@ -406,12 +403,6 @@ pub(crate) mod rlc {
         }
         rlc
     }
-
-    pub(crate) fn value<F: FieldExt>(values: &[u8], randomness: F) -> F {
-        values.iter().rev().fold(F::zero(), |acc, value| {
-            acc * randomness + F::from(*value as u64)
-        })
-    }
 }
 
 /// Returns 2**by as FieldExt
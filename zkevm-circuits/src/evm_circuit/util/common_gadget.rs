@@ -61,7 +61,7 @@ impl<F: Field> SameContextGadget<F> {
 
     pub(crate) fn assign_exec_step(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         step: &ExecStep,
     ) -> Result<(), Error> {
@@ -180,7 +180,7 @@ impl<F: Field> RestoreContextGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         block: &Block<F>,
         call: &Call,
@@ -297,7 +297,7 @@ impl<F: Field, const N_ADDENDS: usize, const INCREASE: bool>
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         value_prev: U256,
         updates: Vec<U256>,
@@ -346,7 +346,7 @@ impl<F: Field> TransferWithGasFeeGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         (sender_balance, sender_balance_prev): (U256, U256),
         (receiver_balance, receiver_balance_prev): (U256, U256),
@@ -403,7 +403,7 @@ impl<F: Field> TransferGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         (sender_balance, sender_balance_prev): (U256, U256),
         (receiver_balance, receiver_balance_prev): (U256, U256),
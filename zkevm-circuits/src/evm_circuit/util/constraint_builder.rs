@@ -10,6 +10,7 @@ use crate::{
     },
     util::Expr,
 };
+use eth_types::evm_types::Hardfork;
 use halo2_proofs::{
     arithmetic::FieldExt,
     plonk::{
@@ -118,15 +119,25 @@ impl<F: FieldExt> ReversionInfo<F> {
     /// Returns `rw_counter_end_of_reversion - reversible_write_counter` and
     /// increases `reversible_write_counter` by `1`.
     pub(crate) fn rw_counter_of_reversion(&mut self) -> Expression<F> {
+        self.rw_counter_of_reversion_with_increment(1.expr())
+    }
+
+    /// Returns `rw_counter_end_of_reversion - reversible_write_counter` and
+    /// increases `reversible_write_counter` by `increment`, which is the
+    /// condition of a conditional reversible write.
+    fn rw_counter_of_reversion_with_increment(
+        &mut self,
+        increment: Expression<F>,
+    ) -> Expression<F> {
         let rw_counter_of_reversion =
             self.rw_counter_end_of_reversion.expr() - self.reversible_write_counter.expr();
-        self.reversible_write_counter = self.reversible_write_counter.clone() + 1.expr();
+        self.reversible_write_counter = self.reversible_write_counter.clone() + increment;
         rw_counter_of_reversion
     }
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         rw_counter_end_of_reversion: usize,
         is_persistent: bool,
@@ -243,6 +254,7 @@ pub(crate) struct ConstraintBuilder<'a, F> {
     pub max_degree: usize,
     pub(crate) curr: Step<F>,
     pub(crate) next: Step<F>,
+    hardfork: Hardfork,
     power_of_randomness: &'a [Expression<F>; 31],
     execution_state: ExecutionState,
     constraints: Vec<(&'static str, Expression<F>)>,
@@ -260,6 +272,7 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
     pub(crate) fn new(
         curr: Step<F>,
         next: Step<F>,
+        hardfork: Hardfork,
         power_of_randomness: &'a [Expression<F>; 31],
         execution_state: ExecutionState,
     ) -> Self {
@@ -267,6 +280,7 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
             max_degree: MAX_DEGREE,
             curr,
             next,
+            hardfork,
             power_of_randomness,
             execution_state,
             constraints: Vec::new(),
@@ -313,6 +327,12 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         self.execution_state
     }
 
+    /// Hardfork of the blocks proven by the circuit, on which the constraints
+    /// of some gadgets depend.
+    pub(crate) fn hardfork(&self) -> Hardfork {
+        self.hardfork
+    }
+
     pub(crate) fn rw_counter_offset(&self) -> Expression<F> {
         self.rw_counter_offset.clone()
     }
@@ -682,19 +702,39 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         self.rw_lookup(name, true.expr(), tag, values.clone());
 
         if let Some(reversion_info) = reversion_info {
-            // Revert if is_persistent is 0
-            self.condition(1.expr() - reversion_info.is_persistent(), |cb| {
-                // Swap value and value_prev
-                values.swap(4, 5);
-
-                cb.rw_lookup_with_counter(
-                    name,
-                    reversion_info.rw_counter_of_reversion(),
-                    true.expr(),
-                    tag,
-                    values,
-                )
-            });
+            // Swap value and value_prev
+            values.swap(4, 5);
+
+            match self.condition.take() {
+                // Revert if is_persistent is 0
+                None => self.condition(1.expr() - reversion_info.is_persistent(), |cb| {
+                    cb.rw_lookup_with_counter(
+                        name,
+                        reversion_info.rw_counter_of_reversion(),
+                        true.expr(),
+                        tag,
+                        values,
+                    )
+                }),
+                // Revert if is_persistent is 0 and the write is done, which
+                // only counts as a reversible write if it's done.
+                Some(condition) => {
+                    self.condition(
+                        condition.clone() * (1.expr() - reversion_info.is_persistent()),
+                        |cb| {
+                            cb.rw_lookup_with_counter(
+                                name,
+                                reversion_info
+                                    .rw_counter_of_reversion_with_increment(condition.clone()),
+                                true.expr(),
+                                tag,
+                                values,
+                            )
+                        },
+                    );
+                    self.condition = Some(condition);
+                }
+            }
         }
     }
 
@@ -1080,6 +1120,38 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         length: Expression<F>,
         rw_counter: Expression<F>,
         rwc_inc: Expression<F>,
+    ) {
+        self.copy_table_lookup_with_rlc_acc(
+            src_id,
+            src_tag,
+            dst_id,
+            dst_tag,
+            src_addr,
+            src_addr_end,
+            dst_addr,
+            length,
+            0.expr(),
+            rw_counter,
+            rwc_inc,
+        );
+    }
+
+    /// Look up a copy event whose destination is CopyDataType::RlcAcc, with
+    /// `rlc_acc` being the random linear combination of the copied bytes.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn copy_table_lookup_with_rlc_acc(
+        &mut self,
+        src_id: Expression<F>,
+        src_tag: Expression<F>,
+        dst_id: Expression<F>,
+        dst_tag: Expression<F>,
+        src_addr: Expression<F>,
+        src_addr_end: Expression<F>,
+        dst_addr: Expression<F>,
+        length: Expression<F>,
+        rlc_acc: Expression<F>,
+        rw_counter: Expression<F>,
+        rwc_inc: Expression<F>,
     ) {
         self.add_lookup(
             "copy lookup",
@@ -1095,6 +1167,25 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
                 length,
                 rw_counter,
                 rwc_inc,
+                rlc_acc,
+            },
+        );
+    }
+
+    // Keccak Table
+
+    pub(crate) fn keccak_table_lookup(
+        &mut self,
+        input_rlc: Expression<F>,
+        input_len: Expression<F>,
+        output_rlc: Expression<F>,
+    ) {
+        self.add_lookup(
+            "keccak lookup",
+            Lookup::KeccakTable {
+                input_rlc,
+                input_len,
+                output_rlc,
             },
         );
     }
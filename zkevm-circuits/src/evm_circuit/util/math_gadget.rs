@@ -46,7 +46,7 @@ impl<F: Field> IsZeroGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         value: F,
     ) -> Result<F, Error> {
@@ -83,7 +83,7 @@ impl<F: Field> IsEqualGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         lhs: F,
         rhs: F,
@@ -129,7 +129,7 @@ impl<F: Field, const N: usize> BatchedIsZeroGadget<F, N> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         values: [F; N],
     ) -> Result<F, Error> {
@@ -224,7 +224,7 @@ impl<F: Field, const N_ADDENDS: usize, const CHECK_OVREFLOW: bool>
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         addends: [Word; N_ADDENDS],
         sum: Word,
@@ -317,7 +317,7 @@ impl<F: Field> MulWordByU64Gadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         multiplicand: Word,
         multiplier: u64,
@@ -373,7 +373,7 @@ impl<F: Field, const N_BYTES: usize> RangeCheckGadget<F, N_BYTES> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         value: F,
     ) -> Result<(), Error> {
@@ -428,7 +428,7 @@ impl<F: Field, const N_BYTES: usize> LtGadget<F, N_BYTES> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         lhs: F,
         rhs: F,
@@ -490,7 +490,7 @@ impl<F: Field> LtWordGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         lhs: Word,
         rhs: Word,
@@ -542,7 +542,7 @@ impl<F: Field, const N_BYTES: usize> ComparisonGadget<F, N_BYTES> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         lhs: F,
         rhs: F,
@@ -594,7 +594,7 @@ impl<F: Field> PairSelectGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         value: F,
         a: F,
@@ -662,7 +662,7 @@ impl<F: Field, const N_BYTES: usize> ConstantDivisionGadget<F, N_BYTES> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         numerator: u128,
     ) -> Result<(u128, u128), Error> {
@@ -715,7 +715,7 @@ impl<F: Field, const N_BYTES: usize> MinMaxGadget<F, N_BYTES> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         lhs: F,
         rhs: F,
@@ -845,7 +845,7 @@ impl<F: Field> MulAddWordsGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         words: [Word; 4],
     ) -> Result<(), Error> {
@@ -1051,7 +1051,7 @@ impl<F: Field> ShrWordsGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         a: Word,
         shift: Word,
@@ -1071,7 +1071,7 @@ impl<F: Field> ShrWordsGadget<F> {
 
     fn assign_witness(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         a: &Word,
         shift: &Word,
@@ -1191,7 +1191,7 @@ impl<F: Field> CmpWordsGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         a: Word,
         b: Word,
@@ -1344,7 +1344,7 @@ impl<F: Field> MulAddWords512Gadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         words: [Word; 4],
         addend: Option<Word>,
@@ -1455,7 +1455,7 @@ impl<F: Field> ModGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         a: Word,
         n: Word,
@@ -1540,7 +1540,7 @@ impl<F: Field> AbsWordGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         x: Word,
         x_abs: Word,
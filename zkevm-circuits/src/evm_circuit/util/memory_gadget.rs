@@ -93,7 +93,7 @@ impl<F: Field> MemoryAddressGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         memory_offset: U256,
         memory_length: U256,
@@ -174,7 +174,7 @@ impl<F: Field> MemoryWordSizeGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         address: u64,
     ) -> Result<u64, Error> {
@@ -278,7 +278,7 @@ impl<F: Field, const N: usize, const N_BYTES_MEMORY_WORD_SIZE: usize>
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         curr_memory_word_size: u64,
         addresses: [u64; N],
@@ -375,7 +375,7 @@ impl<F: Field> MemoryCopierGasGadget<F> {
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         num_bytes: u64,
         memory_expansion_gas_cost: u64,
@@ -488,7 +488,7 @@ impl<F: Field, const MAX_BYTES: usize, const ADDR_SIZE_IN_BYTES: usize>
 
     pub(crate) fn assign(
         &self,
-        region: &mut CachedRegion<'_, '_, F>,
+        region: &mut CachedRegion<F>,
         offset: usize,
         addr_start: u64,
         addr_end: u64,
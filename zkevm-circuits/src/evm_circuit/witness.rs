@@ -3,20 +3,29 @@ use crate::evm_circuit::{
     param::{N_BYTES_WORD, STACK_CAPACITY},
     step::ExecutionState,
     table::{
-        AccountFieldTag, BlockContextFieldTag, BytecodeFieldTag, CallContextFieldTag, RwTableTag,
-        TxContextFieldTag, TxLogFieldTag, TxReceiptFieldTag,
+        AccountFieldTag, BytecodeFieldTag, CallContextFieldTag, RwTableTag, TxLogFieldTag,
+        TxReceiptFieldTag,
     },
     util::RandomLinearCombination,
 };
 
 use bus_mapping::{
-    circuit_input_builder::{self, CopyEvent},
+    circuit_input_builder::{
+        self, CircuitsParams, CopyDataType, CopyEvent, ExpEvent, NumberOrHash,
+    },
     error::{ExecError, OogError},
     operation::{self, AccountField, CallContextField, TxLogField, TxReceiptField},
 };
 
-use eth_types::{evm_types::OpcodeId, ToWord};
-use eth_types::{Address, Field, ToLittleEndian, ToScalar, Word};
+pub use eth_types::public_data::BlockContext;
+use eth_types::public_data::{BlockPublicData, TxPublicData};
+use eth_types::{
+    evm_types::{Hardfork, OpcodeId},
+    ToWord,
+};
+use eth_types::{
+    Address, EIP1186ProofResponse, Field, ToLittleEndian, ToScalar, Withdrawal, Word, H256,
+};
 use eth_types::{ToAddress, U256};
 use halo2_proofs::arithmetic::{BaseExt, FieldExt};
 use halo2_proofs::pairing::bn256::Fr;
@@ -39,93 +48,45 @@ pub struct Block<F> {
     /// Copy events for the EVM circuit's Copy Table, a mapping from (tx_id ||
     /// call_id || pc) to the corresponding copy event.
     pub copy_events: HashMap<(usize, usize, usize), CopyEvent>,
+    /// Exponentiation events for the Exp Table.
+    pub exp_events: Vec<ExpEvent>,
+    /// Inputs of the keccak hashes for the Keccak Table.
+    pub sha3_inputs: Vec<Vec<u8>>,
+    /// State root before the block.
+    pub prev_state_root: H256,
+    /// State root after the block.
+    pub state_root: H256,
+    /// Hash of the block.
+    pub hash: H256,
+    /// Proofs of the accounts and the storage slots accessed by the block, at
+    /// the state root before the block.
+    pub state_proofs: Vec<EIP1186ProofResponse>,
+    /// Withdrawals of the block, credited after its transactions.
+    pub withdrawals: Vec<Withdrawal>,
+    /// Blob gas used by the transactions of the block (EIP-4844).
+    pub blob_gas_used: u64,
+    /// Blob gas in excess of the target accumulated before the block
+    /// (EIP-4844).
+    pub excess_blob_gas: u64,
+    /// Hardfork of the EVM rules followed by the block.
+    pub hardfork: Hardfork,
+    /// Capacities of the circuits proving the block.
+    pub circuits_params: CircuitsParams,
 }
 
-#[derive(Debug, Default, Clone)]
-pub struct BlockContext {
-    /// The address of the miner for the block
-    pub coinbase: Address,
-    /// The gas limit of the block
-    pub gas_limit: u64,
-    /// The number of the block
-    pub number: Word,
-    /// The timestamp of the block
-    pub timestamp: Word,
-    /// The difficulty of the blcok
-    pub difficulty: Word,
-    /// The base fee, the minimum amount of gas fee for a transaction
-    pub base_fee: Word,
-    /// The hash of previous blocks
-    pub history_hashes: Vec<Word>,
-    /// The chain id
-    pub chain_id: Word,
-}
-
-impl BlockContext {
-    pub fn table_assignments<F: Field>(&self, randomness: F) -> Vec<[F; 3]> {
-        [
-            vec![
-                [
-                    F::from(BlockContextFieldTag::Coinbase as u64),
-                    F::zero(),
-                    self.coinbase.to_scalar().unwrap(),
-                ],
-                [
-                    F::from(BlockContextFieldTag::Timestamp as u64),
-                    F::zero(),
-                    self.timestamp.to_scalar().unwrap(),
-                ],
-                [
-                    F::from(BlockContextFieldTag::Number as u64),
-                    F::zero(),
-                    self.number.to_scalar().unwrap(),
-                ],
-                [
-                    F::from(BlockContextFieldTag::Difficulty as u64),
-                    F::zero(),
-                    RandomLinearCombination::random_linear_combine(
-                        self.difficulty.to_le_bytes(),
-                        randomness,
-                    ),
-                ],
-                [
-                    F::from(BlockContextFieldTag::GasLimit as u64),
-                    F::zero(),
-                    F::from(self.gas_limit),
-                ],
-                [
-                    F::from(BlockContextFieldTag::BaseFee as u64),
-                    F::zero(),
-                    RandomLinearCombination::random_linear_combine(
-                        self.base_fee.to_le_bytes(),
-                        randomness,
-                    ),
-                ],
-                [
-                    F::from(BlockContextFieldTag::ChainId as u64),
-                    F::zero(),
-                    RandomLinearCombination::random_linear_combine(
-                        self.chain_id.to_le_bytes(),
-                        randomness,
-                    ),
-                ],
-            ],
-            self.history_hashes
-                .iter()
-                .enumerate()
-                .map(|(idx, hash)| {
-                    [
-                        F::from(BlockContextFieldTag::BlockHash as u64),
-                        (self.number - idx - 1).to_scalar().unwrap(),
-                        RandomLinearCombination::random_linear_combine(
-                            hash.to_le_bytes(),
-                            randomness,
-                        ),
-                    ]
-                })
-                .collect(),
-        ]
-        .concat()
+impl<F> Block<F> {
+    /// Returns the public data of the block committed by the PI circuit.
+    pub fn public_data(&self) -> BlockPublicData {
+        BlockPublicData {
+            context: self.context.clone(),
+            prev_state_root: self.prev_state_root,
+            state_root: self.state_root,
+            hash: self.hash,
+            txs: self.txs.iter().map(Transaction::public_data).collect(),
+            withdrawals: self.withdrawals.clone(),
+            blob_gas_used: self.blob_gas_used,
+            excess_blob_gas: self.excess_blob_gas,
+        }
     }
 }
 
@@ -139,6 +100,12 @@ pub struct Transaction {
     pub gas: u64,
     /// The gas price
     pub gas_price: Word,
+    /// The transaction type (EIP-2718)
+    pub tx_type: u64,
+    /// The max fee per gas (EIP-1559)
+    pub max_fee_per_gas: Word,
+    /// The max priority fee per gas (EIP-1559)
+    pub max_priority_fee_per_gas: Word,
     /// The caller address
     pub caller_address: Address,
     /// The callee address
@@ -160,84 +127,28 @@ pub struct Transaction {
 }
 
 impl Transaction {
+    /// Returns the fields of the transaction committed in the tx table.
+    pub fn public_data(&self) -> TxPublicData {
+        TxPublicData {
+            id: self.id,
+            nonce: self.nonce,
+            gas: self.gas,
+            gas_price: self.gas_price,
+            tx_type: self.tx_type,
+            max_fee_per_gas: self.max_fee_per_gas,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+            caller_address: self.caller_address,
+            callee_address: self.callee_address,
+            is_create: self.is_create,
+            value: self.value,
+            call_data: self.call_data.clone(),
+            call_data_length: self.call_data_length,
+            call_data_gas_cost: self.call_data_gas_cost,
+        }
+    }
+
     pub fn table_assignments<F: Field>(&self, randomness: F) -> Vec<[F; 4]> {
-        [
-            vec![
-                [
-                    F::from(self.id as u64),
-                    F::from(TxContextFieldTag::Nonce as u64),
-                    F::zero(),
-                    F::from(self.nonce),
-                ],
-                [
-                    F::from(self.id as u64),
-                    F::from(TxContextFieldTag::Gas as u64),
-                    F::zero(),
-                    F::from(self.gas),
-                ],
-                [
-                    F::from(self.id as u64),
-                    F::from(TxContextFieldTag::GasPrice as u64),
-                    F::zero(),
-                    RandomLinearCombination::random_linear_combine(
-                        self.gas_price.to_le_bytes(),
-                        randomness,
-                    ),
-                ],
-                [
-                    F::from(self.id as u64),
-                    F::from(TxContextFieldTag::CallerAddress as u64),
-                    F::zero(),
-                    self.caller_address.to_scalar().unwrap(),
-                ],
-                [
-                    F::from(self.id as u64),
-                    F::from(TxContextFieldTag::CalleeAddress as u64),
-                    F::zero(),
-                    self.callee_address.to_scalar().unwrap(),
-                ],
-                [
-                    F::from(self.id as u64),
-                    F::from(TxContextFieldTag::IsCreate as u64),
-                    F::zero(),
-                    F::from(self.is_create as u64),
-                ],
-                [
-                    F::from(self.id as u64),
-                    F::from(TxContextFieldTag::Value as u64),
-                    F::zero(),
-                    RandomLinearCombination::random_linear_combine(
-                        self.value.to_le_bytes(),
-                        randomness,
-                    ),
-                ],
-                [
-                    F::from(self.id as u64),
-                    F::from(TxContextFieldTag::CallDataLength as u64),
-                    F::zero(),
-                    F::from(self.call_data_length as u64),
-                ],
-                [
-                    F::from(self.id as u64),
-                    F::from(TxContextFieldTag::CallDataGasCost as u64),
-                    F::zero(),
-                    F::from(self.call_data_gas_cost),
-                ],
-            ],
-            self.call_data
-                .iter()
-                .enumerate()
-                .map(|(idx, byte)| {
-                    [
-                        F::from(self.id as u64),
-                        F::from(TxContextFieldTag::CallData as u64),
-                        F::from(idx as u64),
-                        F::from(*byte as u64),
-                    ]
-                })
-                .collect(),
-        ]
-        .concat()
+        self.public_data().table_assignments(randomness)
     }
 }
 
@@ -1218,6 +1129,9 @@ impl From<&circuit_input_builder::ExecStep> for ExecutionState {
                     OpcodeId::CALLDATALOAD => ExecutionState::CALLDATALOAD,
                     OpcodeId::CODESIZE => ExecutionState::CODESIZE,
                     OpcodeId::RETURN | OpcodeId::REVERT => ExecutionState::RETURN,
+                    OpcodeId::BLOBHASH | OpcodeId::BLOBBASEFEE => {
+                        ExecutionState::ErrorUnsupportedOpcode
+                    }
                     // dummy ops
                     OpcodeId::SHA3 => dummy!(ExecutionState::SHA3),
                     OpcodeId::ADDRESS => dummy!(ExecutionState::ADDRESS),
@@ -1298,6 +1212,9 @@ fn tx_convert(tx: &circuit_input_builder::Transaction, id: usize, is_last_tx: bo
         nonce: tx.nonce,
         gas: tx.gas,
         gas_price: tx.gas_price,
+        tx_type: tx.transaction_type as u64,
+        max_fee_per_gas: tx.max_fee_per_gas,
+        max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
         caller_address: tx.from,
         callee_address: tx.to,
         is_create: tx.is_create(),
@@ -1370,16 +1287,25 @@ pub fn block_convert(
         bytecodes: block
             .txs()
             .iter()
-            .flat_map(|tx| {
-                tx.calls()
-                    .iter()
-                    .map(|call| call.code_hash)
-                    .unique()
-                    .into_iter()
-                    .map(|code_hash| {
-                        let bytecode = Bytecode::new(code_db.0.get(&code_hash).unwrap().to_vec());
-                        (bytecode.hash, bytecode)
-                    })
+            .flat_map(|tx| tx.calls().iter().map(|call| call.code_hash))
+            .chain(block.copy_events.iter().flat_map(|copy_event| {
+                // The bytecodes copied from by EXTCODECOPY or copied to by
+                // RETURN of a create, which are not necessarily executed.
+                [
+                    (copy_event.src_type, &copy_event.src_id),
+                    (copy_event.dst_type, &copy_event.dst_id),
+                ]
+                .into_iter()
+                .filter_map(|(tag, id)| match (tag, id) {
+                    (CopyDataType::Bytecode, NumberOrHash::Hash(code_hash)) => Some(*code_hash),
+                    _ => None,
+                })
+            }))
+            .unique()
+            .map(|code_hash| {
+                let bytecode =
+                    Bytecode::new(code_db.0.get(&code_hash).cloned().unwrap_or_default());
+                (bytecode.hash, bytecode)
             })
             .collect(),
         copy_events: block
@@ -1392,5 +1318,16 @@ pub fn block_convert(
                 )
             })
             .collect(),
+        exp_events: block.exp_events.clone(),
+        sha3_inputs: block.sha3_inputs.clone(),
+        prev_state_root: block.prev_state_root,
+        state_root: block.state_root,
+        hash: block.hash,
+        state_proofs: block.state_proofs.clone(),
+        withdrawals: block.withdrawals.clone(),
+        blob_gas_used: block.blob_gas_used,
+        excess_blob_gas: block.excess_blob_gas,
+        hardfork: block.hardfork(),
+        circuits_params: block.circuits_params,
     }
 }
@@ -0,0 +1,592 @@
+//! The Exp circuit implements constraints for the exponentiation of 256-bit
+//! words by square-and-multiply, where each exponentiation event is laid out
+//! as one row per step, from the full exponent down to the exponent 1:
+//!   - when the exponent is odd, exponentiation = exponentiation_next * base
+//!     and exponent_next = exponent - 1,
+//!   - when the exponent is even, exponentiation = exponentiation_next^2 and
+//!     exponent_next = exponent / 2,
+//!   - at the last step, exponent = 1 and exponentiation = base.
+
+use bus_mapping::circuit_input_builder::{ExpEvent, ExpStep};
+use eth_types::{Field, Word};
+use gadgets::{
+    mul_add::{MulAddChip, MulAddConfig, MulAddInstruction},
+    range_check::{RangeCheckChip, RangeCheckConfig},
+    util::{not, select, Expr},
+};
+use halo2_proofs::{
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error},
+    poly::Rotation,
+};
+use log::error;
+
+use crate::{
+    evm_circuit::{
+        util::{constraint_builder::BaseConstraintBuilder, split_u256},
+        witness::Block,
+    },
+    table::ExpTable,
+};
+
+/// The exp circuit, which constrains the exp table to contain the steps of
+/// every exponentiation event.
+#[derive(Clone, Debug)]
+pub struct ExpCircuit<F> {
+    /// The exp table, holding the base, exponent and exponentiation of each
+    /// step.
+    pub exp_table: ExpTable,
+    /// Whether the exponent at this step is odd.
+    pub parity: Column<Advice>,
+    /// MulAdd chip to check exponentiation == a * b (mod 2^256), where `a` is
+    /// the exponentiation of the next step and `b` is either the base or the
+    /// exponentiation of the next step depending on the parity.
+    pub mul_gadget: MulAddConfig<F>,
+    /// MulAdd chip to check exponent == 2 * quotient + parity, where the
+    /// quotient is the exponent of the next step when the exponent is even.
+    pub parity_check: MulAddConfig<F>,
+    /// Range check chip for the bytes of the MulAdd chips.
+    pub range_check: RangeCheckConfig,
+}
+
+impl<F: Field> ExpCircuit<F> {
+    /// Configure the Exp circuit constraining the steps of the exp table.
+    pub fn configure(meta: &mut ConstraintSystem<F>, exp_table: ExpTable) -> Self {
+        let parity = meta.advice_column();
+        let range_check = RangeCheckChip::configure(meta, false);
+        let ExpTable {
+            q_enable,
+            identifier,
+            is_first,
+            is_last,
+            base_lo,
+            base_hi,
+            exponent_lo,
+            exponent_hi,
+            exponentiation_lo,
+            exponentiation_hi,
+        } = exp_table;
+
+        let mul_gadget =
+            MulAddChip::configure(meta, |meta| meta.query_fixed(q_enable, Rotation::cur()));
+        let parity_check =
+            MulAddChip::configure(meta, |meta| meta.query_fixed(q_enable, Rotation::cur()));
+
+        meta.create_gate("verify exponentiation step", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            cb.require_boolean(
+                "is_first is boolean",
+                meta.query_advice(is_first, Rotation::cur()),
+            );
+            cb.require_boolean(
+                "is_last is boolean",
+                meta.query_advice(is_last, Rotation::cur()),
+            );
+            cb.require_boolean(
+                "parity is boolean",
+                meta.query_advice(parity, Rotation::cur()),
+            );
+
+            // exponentiation == a * b (mod 2^256)
+            cb.require_zero(
+                "mul_gadget: c_lo == 0",
+                meta.query_advice(mul_gadget.c_lo, Rotation::cur()),
+            );
+            cb.require_zero(
+                "mul_gadget: c_hi == 0",
+                meta.query_advice(mul_gadget.c_hi, Rotation::cur()),
+            );
+            cb.require_equal(
+                "mul_gadget: d_lo == exponentiation_lo",
+                meta.query_advice(mul_gadget.d_lo, Rotation::cur()),
+                meta.query_advice(exponentiation_lo, Rotation::cur()),
+            );
+            cb.require_equal(
+                "mul_gadget: d_hi == exponentiation_hi",
+                meta.query_advice(mul_gadget.d_hi, Rotation::cur()),
+                meta.query_advice(exponentiation_hi, Rotation::cur()),
+            );
+
+            // exponent == 2 * quotient + parity
+            let (two_lo, two_hi) = parity_check.a_lo_hi(meta);
+            cb.require_equal("parity_check: a_lo == 2", two_lo, 2.expr());
+            cb.require_zero("parity_check: a_hi == 0", two_hi);
+            cb.require_equal(
+                "parity_check: c_lo == parity",
+                meta.query_advice(parity_check.c_lo, Rotation::cur()),
+                meta.query_advice(parity, Rotation::cur()),
+            );
+            cb.require_zero(
+                "parity_check: c_hi == 0",
+                meta.query_advice(parity_check.c_hi, Rotation::cur()),
+            );
+            cb.require_equal(
+                "parity_check: d_lo == exponent_lo",
+                meta.query_advice(parity_check.d_lo, Rotation::cur()),
+                meta.query_advice(exponent_lo, Rotation::cur()),
+            );
+            cb.require_equal(
+                "parity_check: d_hi == exponent_hi",
+                meta.query_advice(parity_check.d_hi, Rotation::cur()),
+                meta.query_advice(exponent_hi, Rotation::cur()),
+            );
+            cb.require_zero(
+                "parity_check: 2 * quotient + parity does not overflow",
+                parity_check.overflow.clone(),
+            );
+
+            let (a_lo, a_hi) = mul_gadget.a_lo_hi(meta);
+            let (b_lo, b_hi) = mul_gadget.b_lo_hi(meta);
+            let (quotient_lo, quotient_hi) = parity_check.b_lo_hi(meta);
+            let parity = meta.query_advice(parity, Rotation::cur());
+
+            cb.condition(
+                not::expr(meta.query_advice(is_last, Rotation::cur())),
+                |cb| {
+                    cb.require_equal(
+                        "identifier_next == identifier",
+                        meta.query_advice(identifier, Rotation::next()),
+                        meta.query_advice(identifier, Rotation::cur()),
+                    );
+                    cb.require_equal(
+                        "base_lo_next == base_lo",
+                        meta.query_advice(base_lo, Rotation::next()),
+                        meta.query_advice(base_lo, Rotation::cur()),
+                    );
+                    cb.require_equal(
+                        "base_hi_next == base_hi",
+                        meta.query_advice(base_hi, Rotation::next()),
+                        meta.query_advice(base_hi, Rotation::cur()),
+                    );
+                    cb.require_equal(
+                        "a_lo == exponentiation_lo_next",
+                        a_lo.clone(),
+                        meta.query_advice(exponentiation_lo, Rotation::next()),
+                    );
+                    cb.require_equal(
+                        "a_hi == exponentiation_hi_next",
+                        a_hi.clone(),
+                        meta.query_advice(exponentiation_hi, Rotation::next()),
+                    );
+                    cb.require_equal(
+                        "b_lo == parity ? base_lo : exponentiation_lo_next",
+                        b_lo.clone(),
+                        select::expr(
+                            parity.clone(),
+                            meta.query_advice(base_lo, Rotation::cur()),
+                            meta.query_advice(exponentiation_lo, Rotation::next()),
+                        ),
+                    );
+                    cb.require_equal(
+                        "b_hi == parity ? base_hi : exponentiation_hi_next",
+                        b_hi.clone(),
+                        select::expr(
+                            parity.clone(),
+                            meta.query_advice(base_hi, Rotation::cur()),
+                            meta.query_advice(exponentiation_hi, Rotation::next()),
+                        ),
+                    );
+                    cb.require_equal(
+                        "exponent_lo_next == parity ? exponent_lo - 1 : quotient_lo",
+                        meta.query_advice(exponent_lo, Rotation::next()),
+                        select::expr(
+                            parity.clone(),
+                            meta.query_advice(exponent_lo, Rotation::cur()) - 1.expr(),
+                            quotient_lo,
+                        ),
+                    );
+                    cb.require_equal(
+                        "exponent_hi_next == parity ? exponent_hi : quotient_hi",
+                        meta.query_advice(exponent_hi, Rotation::next()),
+                        select::expr(
+                            parity,
+                            meta.query_advice(exponent_hi, Rotation::cur()),
+                            quotient_hi,
+                        ),
+                    );
+                },
+            );
+
+            // A new exponentiation starts right after the last step of the
+            // previous one, and only there.
+            cb.condition(meta.query_fixed(q_enable, Rotation::next()), |cb| {
+                cb.require_equal(
+                    "is_first_next == is_last",
+                    meta.query_advice(is_first, Rotation::next()),
+                    meta.query_advice(is_last, Rotation::cur()),
+                );
+            });
+
+            cb.condition(meta.query_advice(is_last, Rotation::cur()), |cb| {
+                cb.require_equal(
+                    "exponent_lo == 1 for last step",
+                    meta.query_advice(exponent_lo, Rotation::cur()),
+                    1.expr(),
+                );
+                cb.require_zero(
+                    "exponent_hi == 0 for last step",
+                    meta.query_advice(exponent_hi, Rotation::cur()),
+                );
+                cb.require_equal(
+                    "a_lo == base_lo for last step",
+                    a_lo,
+                    meta.query_advice(base_lo, Rotation::cur()),
+                );
+                cb.require_equal(
+                    "a_hi == base_hi for last step",
+                    a_hi,
+                    meta.query_advice(base_hi, Rotation::cur()),
+                );
+                cb.require_equal("b_lo == 1 for last step", b_lo, 1.expr());
+                cb.require_zero("b_hi == 0 for last step", b_hi);
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        range_check.range_check_u8(
+            meta,
+            "mul add byte fits into u8",
+            |meta| meta.query_fixed(q_enable, Rotation::cur()),
+            mul_gadget
+                .a
+                .iter()
+                .chain(mul_gadget.b.iter())
+                .chain(mul_gadget.carry_lo.iter())
+                .chain(mul_gadget.carry_hi.iter())
+                .chain(parity_check.a.iter())
+                .chain(parity_check.b.iter())
+                .chain(parity_check.carry_lo.iter())
+                .chain(parity_check.carry_hi.iter())
+                .copied(),
+        );
+
+        Self {
+            exp_table,
+            parity,
+            mul_gadget,
+            parity_check,
+            range_check,
+        }
+    }
+
+    /// Load the fixed table of the values 0..256.
+    pub fn load_u8_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        RangeCheckChip::construct(self.range_check).load(layouter)
+    }
+
+    /// Return the number of rows of the steps of `exp_events`, without the
+    /// padding row.
+    pub fn num_rows(exp_events: &[ExpEvent]) -> usize {
+        exp_events
+            .iter()
+            .map(|exp_event| exp_event.steps.len())
+            .sum()
+    }
+
+    /// Assign the steps of every exponentiation event in the block, or return
+    /// an error if they exceed the `max_exp_rows` of the block.
+    pub fn assign_block(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        block: &Block<F>,
+    ) -> Result<(), Error> {
+        let num_rows = Self::num_rows(&block.exp_events);
+        if num_rows > block.circuits_params.max_exp_rows {
+            error!(
+                "exp rows {} exceed the capacity of {}",
+                num_rows, block.circuits_params.max_exp_rows
+            );
+            return Err(Error::Synthesis);
+        }
+        let mul_chip = MulAddChip::construct(self.mul_gadget.clone());
+        let parity_check_chip = MulAddChip::construct(self.parity_check.clone());
+
+        layouter.assign_region(
+            || "assign exp table",
+            |mut region| {
+                let mut offset = 0;
+                for exp_event in block.exp_events.iter() {
+                    for (step_idx, exp_step) in exp_event.steps.iter().enumerate() {
+                        self.assign_step(
+                            &mut region,
+                            offset,
+                            exp_event,
+                            step_idx,
+                            exp_step,
+                            &mul_chip,
+                            &parity_check_chip,
+                        )?;
+                        offset += 1;
+                    }
+                }
+                // pad a row in the end to satisfy Halo2 cell assignment check
+                self.assign_padding_row(&mut region, offset, &mul_chip, &parity_check_chip)
+            },
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn assign_step(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        exp_event: &ExpEvent,
+        step_idx: usize,
+        exp_step: &ExpStep,
+        mul_chip: &MulAddChip<F>,
+        parity_check_chip: &MulAddChip<F>,
+    ) -> Result<(), Error> {
+        let parity = exp_step.exponent.low_u64() & 1;
+        let (base_lo, base_hi) = split_u256(&exp_event.base);
+        let (exponent_lo, exponent_hi) = split_u256(&exp_step.exponent);
+        let (exponentiation_lo, exponentiation_hi) = split_u256(&exp_step.d);
+
+        region.assign_fixed(
+            || "q_enable",
+            self.exp_table.q_enable,
+            offset,
+            || Ok(F::one()),
+        )?;
+        for (name, column, value) in [
+            (
+                "identifier",
+                self.exp_table.identifier,
+                F::from(exp_event.identifier as u64),
+            ),
+            ("is_first", self.exp_table.is_first, F::from(step_idx == 0)),
+            (
+                "is_last",
+                self.exp_table.is_last,
+                F::from(step_idx == exp_event.steps.len() - 1),
+            ),
+            ("base_lo", self.exp_table.base_lo, word_to_field(base_lo)),
+            ("base_hi", self.exp_table.base_hi, word_to_field(base_hi)),
+            (
+                "exponent_lo",
+                self.exp_table.exponent_lo,
+                word_to_field(exponent_lo),
+            ),
+            (
+                "exponent_hi",
+                self.exp_table.exponent_hi,
+                word_to_field(exponent_hi),
+            ),
+            (
+                "exponentiation_lo",
+                self.exp_table.exponentiation_lo,
+                word_to_field(exponentiation_lo),
+            ),
+            (
+                "exponentiation_hi",
+                self.exp_table.exponentiation_hi,
+                word_to_field(exponentiation_hi),
+            ),
+            ("parity", self.parity, F::from(parity)),
+        ] {
+            region.assign_advice(
+                || format!("assign {} {}", name, offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+
+        mul_chip.assign(
+            region,
+            offset,
+            [exp_step.a, exp_step.b, Word::zero(), exp_step.d],
+        )?;
+        parity_check_chip.assign(
+            region,
+            offset,
+            [
+                Word::from(2),
+                exp_step.exponent >> 1,
+                Word::from(parity),
+                exp_step.exponent,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn assign_padding_row(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        mul_chip: &MulAddChip<F>,
+        parity_check_chip: &MulAddChip<F>,
+    ) -> Result<(), Error> {
+        region.assign_fixed(
+            || "q_enable",
+            self.exp_table.q_enable,
+            offset,
+            || Ok(F::zero()),
+        )?;
+        for column in [
+            self.exp_table.identifier,
+            self.exp_table.is_first,
+            self.exp_table.is_last,
+            self.exp_table.base_lo,
+            self.exp_table.base_hi,
+            self.exp_table.exponent_lo,
+            self.exp_table.exponent_hi,
+            self.exp_table.exponentiation_lo,
+            self.exp_table.exponentiation_hi,
+            self.parity,
+        ] {
+            region.assign_advice(
+                || format!("assign padding row {}", offset),
+                column,
+                offset,
+                || Ok(F::zero()),
+            )?;
+        }
+        mul_chip.assign(region, offset, [Word::zero(); 4])?;
+        parity_check_chip.assign(region, offset, [Word::zero(); 4])?;
+
+        Ok(())
+    }
+}
+
+/// Converts a word of at most 128 bits into a field element.
+fn word_to_field<F: Field>(value: Word) -> F {
+    F::from_u128(value.low_u128())
+}
+
+#[cfg(test)]
+mod exp_circuit_tests {
+    use bus_mapping::circuit_input_builder::{CircuitsParams, ExpEvent};
+    use eth_types::{Field, Word};
+    use gadgets::mutation::{verify_mutated, CellTarget, Mutate, Mutation};
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::{MockProver, VerifyFailure},
+        pairing::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use rand::Rng;
+
+    use crate::{evm_circuit::witness::Block, table::ExpTable};
+
+    use super::ExpCircuit;
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        block: Block<F>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = ExpCircuit<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let exp_table = ExpTable::construct(meta);
+            ExpCircuit::configure(meta, exp_table)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.load_u8_table(&mut layouter)?;
+            config.assign_block(&mut layouter, &self.block)
+        }
+    }
+
+    fn run_circuit(exp_events: Vec<ExpEvent>) -> Result<(), Vec<VerifyFailure>> {
+        let block = Block::<Fr> {
+            exp_events,
+            ..Default::default()
+        };
+        let circuit = MyCircuit::<Fr> { block };
+        let prover = MockProver::<Fr>::run(10, &circuit, vec![]).unwrap();
+        prover.verify()
+    }
+
+    fn exp_event(identifier: usize, base: Word, exponent: Word) -> ExpEvent {
+        let exp_event = ExpEvent::new(identifier, base, exponent);
+        assert_eq!(exp_event.exponentiation, base.overflowing_pow(exponent).0);
+        exp_event
+    }
+
+    #[test]
+    fn exp_circuit_valid() {
+        let mut rng = rand::thread_rng();
+        let exp_events = vec![
+            exp_event(1, Word::from(2), Word::from(2)),
+            exp_event(2, Word::from(3), Word::from(7)),
+            exp_event(3, Word::from(5), Word::one()),
+            exp_event(4, Word::MAX, Word::from(255)),
+            exp_event(
+                5,
+                Word::from(rng.gen::<u128>()),
+                Word::from(rng.gen::<u128>()),
+            ),
+            exp_event(6, Word::from(2), Word::from(256)),
+        ];
+        assert_eq!(run_circuit(exp_events), Ok(()));
+    }
+
+    #[test]
+    fn exp_circuit_capacity_exceeded() {
+        let exp_events = vec![exp_event(1, Word::from(3), Word::from(7))];
+        let block = Block::<Fr> {
+            circuits_params: CircuitsParams {
+                max_exp_rows: exp_events[0].steps.len() - 1,
+                ..Default::default()
+            },
+            exp_events,
+            ..Default::default()
+        };
+        let circuit = MyCircuit::<Fr> { block };
+        assert!(MockProver::<Fr>::run(10, &circuit, vec![]).is_err());
+    }
+
+    #[test]
+    fn exp_circuit_invalid_exponentiation() {
+        let mut exp_event = exp_event(1, Word::from(3), Word::from(7));
+        exp_event.steps[0].d = exp_event.steps[0].d + 1;
+        assert!(run_circuit(vec![exp_event]).is_err());
+    }
+
+    #[test]
+    fn exp_circuit_invalid_exponent() {
+        let mut exp_event = exp_event(1, Word::from(3), Word::from(7));
+        exp_event.steps[1].exponent = exp_event.steps[1].exponent + 1;
+        assert!(run_circuit(vec![exp_event]).is_err());
+    }
+
+    #[test]
+    fn exp_circuit_invalid_last_step() {
+        let mut exp_event = exp_event(1, Word::from(3), Word::from(2));
+        let last = exp_event.steps.len() - 1;
+        exp_event.steps[last].a = Word::from(4);
+        exp_event.steps[last].d = Word::from(4);
+        assert!(run_circuit(vec![exp_event]).is_err());
+    }
+
+    #[test]
+    fn exp_circuit_invalid_first_step() {
+        // A new exponentiation can't start in the middle of another one.
+        let block = Block::<Fr> {
+            exp_events: vec![exp_event(1, Word::from(3), Word::from(7))],
+            ..Default::default()
+        };
+        let circuit = MyCircuit::<Fr> { block };
+        let mutation = Mutation::new(
+            CellTarget::Annotation("assign is_first"),
+            2,
+            Mutate::Set(Fr::one()),
+        )
+        .in_region("assign exp table");
+        assert!(verify_mutated(10, &circuit, vec![], mutation).is_err());
+    }
+}
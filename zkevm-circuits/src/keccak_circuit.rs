@@ -0,0 +1,685 @@
+//! The keccak circuit implements the keccak-256 hashes of the keccak table,
+//! with the state of the sponge laid out bit by bit, one row per step of each
+//! keccak-f permutation:
+//!   - the input row holds the 136 bytes of the absorbed block, whether each of
+//!     them is padding, the RLC of the data bytes absorbed since the first
+//!     block of the input and their number,
+//!   - each of the 24 rounds takes a state row, holding the state and the
+//!     parities of its columns, and a theta row, holding the state after the
+//!     theta step, from which the rho, pi, chi and iota steps give the state of
+//!     the next round,
+//!   - the output row holds the state after the permutation, and the keccak
+//!     table row of the digest when the block is the last one of its input.
+//!
+//! The state row of the first round is the xor of the absorbed block into the
+//! output state of the previous permutation, unless the block is the first
+//! one of its input.
+
+use crate::{evm_circuit::util::constraint_builder::BaseConstraintBuilder, table::KeccakTable};
+use eth_types::Field;
+use gadgets::util::{not, sum, Expr};
+use halo2_proofs::{
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use itertools::Itertools;
+use keccak256::{
+    common::{State, PERMUTATION, ROTATION_CONSTANTS, ROUND_CONSTANTS},
+    plain::KeccakF,
+};
+use log::error;
+use rayon::prelude::*;
+use std::marker::PhantomData;
+
+/// Number of bytes absorbed by a permutation.
+const RATE: usize = 136;
+/// Number of bits absorbed by a permutation.
+const RATE_IN_BITS: usize = RATE * 8;
+/// Number of bits of the state.
+const NUM_BITS: usize = 1600;
+/// Number of bits of the column parities of the state.
+const NUM_PARITIES: usize = 320;
+/// Bit positions of the round constants which may be set.
+const ROUND_CONSTANT_BITS: [usize; 7] = [0, 1, 3, 7, 15, 31, 63];
+
+/// Number of rows of a keccak-f permutation: the input row, a state row and a
+/// theta row for each round, and the output row.
+pub const ROWS_PER_PERMUTATION: usize = 2 * PERMUTATION + 2;
+/// Rotation from a row to the same row of the previous permutation.
+const PREV_PERMUTATION: i32 = -(ROWS_PER_PERMUTATION as i32);
+/// Rotation from the output row to the input row of a permutation.
+const INPUT_ROW: i32 = 1 - ROWS_PER_PERMUTATION as i32;
+
+/// Index of the bit `z` of the lane `(x, y)` of the state, which is the
+/// little endian bit order of the absorbed bytes.
+fn bit_index(x: usize, y: usize, z: usize) -> usize {
+    (x + 5 * y) * 64 + z
+}
+
+fn xor<F: Field>(a: Expression<F>, b: Expression<F>) -> Expression<F> {
+    a.clone() + b.clone() - 2.expr() * a * b
+}
+
+/// Return the byte of the little endian `bits`.
+fn byte_expr<F: Field>(bits: &[Expression<F>]) -> Expression<F> {
+    bits.iter().enumerate().fold(0.expr(), |acc, (idx, bit)| {
+        acc + bit.clone() * (1u64 << idx).expr()
+    })
+}
+
+fn query_row<F: Field>(
+    meta: &mut VirtualCells<F>,
+    columns: &[Column<Advice>],
+    rotation: i32,
+) -> Vec<Expression<F>> {
+    columns
+        .iter()
+        .map(|column| meta.query_advice(*column, Rotation(rotation)))
+        .collect()
+}
+
+/// The keccak circuit, which constrains the keccak table to hold the digest of
+/// the inputs absorbed by its permutations.
+#[derive(Clone, Debug)]
+pub struct KeccakCircuit<F> {
+    /// The keccak table, whose `q_enable` selects the output rows.
+    pub keccak_table: KeccakTable,
+    /// Bits of the state.  On the input row, the first 1088 bits are the bits
+    /// of the absorbed block and the next 136 are the padding flags of its
+    /// bytes.
+    pub bits: Vec<Column<Advice>>,
+    /// Parities of the columns of the state on the state rows.  On the input
+    /// row, the first 136 are the RLC of the data bytes absorbed up to each
+    /// byte of the block.
+    pub parities: Vec<Column<Advice>>,
+    /// Whether the block of the input row is the first one of its input.
+    pub is_first: Column<Advice>,
+    /// Number of data bytes absorbed up to the block of the input row.
+    pub length: Column<Advice>,
+    /// Whether the row is the input row of the first permutation.
+    pub q_first: Column<Fixed>,
+    /// Whether the row is an input row.
+    pub q_input: Column<Fixed>,
+    /// Whether the row is a state row.
+    pub q_state: Column<Fixed>,
+    /// Whether the row is a theta row.
+    pub q_theta: Column<Fixed>,
+    /// Bits of the round constant of the round of a theta row, at the
+    /// positions of `ROUND_CONSTANT_BITS`.
+    pub round_constant_bits: [Column<Fixed>; 7],
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> KeccakCircuit<F> {
+    /// Configure the keccak circuit constraining the rows of the
+    /// `keccak_table`, where `power_of_randomness` holds the first 31 powers
+    /// of the randomness of the RLCs.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        keccak_table: KeccakTable,
+        power_of_randomness: [Expression<F>; 31],
+    ) -> Self {
+        let bits = (0..NUM_BITS).map(|_| meta.advice_column()).collect_vec();
+        let parities = (0..NUM_PARITIES)
+            .map(|_| meta.advice_column())
+            .collect_vec();
+        let is_first = meta.advice_column();
+        let length = meta.advice_column();
+        let q_first = meta.fixed_column();
+        let q_input = meta.fixed_column();
+        let q_state = meta.fixed_column();
+        let q_theta = meta.fixed_column();
+        let round_constant_bits = [(); 7].map(|_| meta.fixed_column());
+
+        let randomness = power_of_randomness[0].clone();
+
+        meta.create_gate("keccak input row", |meta| {
+            let mut cb = BaseConstraintBuilder::new(4);
+            let q_input = meta.query_fixed(q_input, Rotation::cur());
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let is_first = meta.query_advice(is_first, Rotation::cur());
+            let bits_cur = query_row(meta, &bits, 0);
+            let is_padding = &bits_cur[RATE_IN_BITS..RATE_IN_BITS + RATE];
+            let rlcs = query_row(meta, &parities, 0);
+            let prev_is_final =
+                meta.query_advice(bits[RATE_IN_BITS + RATE - 1], Rotation(PREV_PERMUTATION));
+
+            // The previous permutation finalized its input, unless this is the
+            // first permutation.
+            cb.require_equal(
+                "is_first = q_first + (1 - q_first) * previous is_final",
+                is_first.clone(),
+                q_first.clone() + not::expr(q_first) * prev_is_final,
+            );
+            for bit in bits_cur[..RATE_IN_BITS + RATE].iter() {
+                cb.require_boolean("block bits and padding flags are boolean", bit.clone());
+            }
+
+            // The padding starts at some byte and lasts until the end of the
+            // block, with the bytes 0x01 0x00 ... 0x00 0x80, or 0x81 when it
+            // is a single byte.
+            let mut prev_rlc = not::expr(is_first.clone())
+                * meta.query_advice(parities[RATE - 1], Rotation(PREV_PERMUTATION));
+            for (idx, padding) in is_padding.iter().enumerate() {
+                let byte = byte_expr(&bits_cur[idx * 8..(idx + 1) * 8]);
+                let prev_is_padding = if idx == 0 {
+                    0.expr()
+                } else {
+                    is_padding[idx - 1].clone()
+                };
+                cb.require_zero(
+                    "padding lasts until the end of the block",
+                    prev_is_padding.clone() * not::expr(padding.clone()),
+                );
+                let first_padding = padding.clone() - prev_is_padding;
+                let last_padding = if idx == RATE - 1 {
+                    0x80.expr()
+                } else {
+                    0.expr()
+                };
+                cb.require_zero(
+                    "padding bytes",
+                    padding.clone() * (byte.clone() - first_padding - last_padding),
+                );
+                // rlc = rlc_prev * r + byte for a data byte, rlc_prev otherwise.
+                cb.require_equal(
+                    "RLC of the data bytes",
+                    rlcs[idx].clone(),
+                    prev_rlc.clone()
+                        + not::expr(padding.clone())
+                            * (prev_rlc.clone() * (randomness.clone() - 1.expr()) + byte),
+                );
+                prev_rlc = rlcs[idx].clone();
+            }
+            cb.require_equal(
+                "length = previous length + number of data bytes",
+                meta.query_advice(length, Rotation::cur()),
+                not::expr(is_first.clone()) * meta.query_advice(length, Rotation(PREV_PERMUTATION))
+                    + sum::expr(
+                        is_padding
+                            .iter()
+                            .map(|is_padding| not::expr(is_padding.clone())),
+                    ),
+            );
+
+            // Absorb the block into the output state of the previous permutation
+            let bits_prev = query_row(meta, &bits, -1);
+            let bits_next = query_row(meta, &bits, 1);
+            for (idx, (bit_prev, bit_next)) in bits_prev.into_iter().zip(bits_next).enumerate() {
+                let bit_prev = not::expr(is_first.clone()) * bit_prev;
+                let absorbed = if idx < RATE_IN_BITS {
+                    xor(bit_prev, bits_cur[idx].clone())
+                } else {
+                    bit_prev
+                };
+                cb.require_equal("absorb the block", bit_next, absorbed);
+            }
+
+            cb.gate(q_input)
+        });
+
+        meta.create_gate("keccak state row", |meta| {
+            let mut cb = BaseConstraintBuilder::new(4);
+            let q_state = meta.query_fixed(q_state, Rotation::cur());
+            let bits_cur = query_row(meta, &bits, 0);
+            let bits_next = query_row(meta, &bits, 1);
+            let parities = query_row(meta, &parities, 0);
+
+            // parity = xor of the 5 bits of the column, from their sum being
+            // parity, parity + 2 or parity + 4.
+            for (x, z) in (0..5).cartesian_product(0..64) {
+                let parity = parities[x * 64 + z].clone();
+                let column_sum = sum::expr((0..5).map(|y| bits_cur[bit_index(x, y, z)].clone()));
+                cb.require_boolean("parity is boolean", parity.clone());
+                cb.require_in_set(
+                    "column sum = parity + 0, 2 or 4",
+                    column_sum - parity,
+                    vec![0.expr(), 2.expr(), 4.expr()],
+                );
+            }
+            // theta: a[x][y][z] ^= c[x - 1][z] ^ c[x + 1][z - 1]
+            for (x, y, z) in (0..5)
+                .cartesian_product(0..5)
+                .cartesian_product(0..64)
+                .map(|((x, y), z)| (x, y, z))
+            {
+                let theta = xor(
+                    xor(
+                        bits_cur[bit_index(x, y, z)].clone(),
+                        parities[((x + 4) % 5) * 64 + z].clone(),
+                    ),
+                    parities[((x + 1) % 5) * 64 + (z + 63) % 64].clone(),
+                );
+                cb.require_equal("theta", bits_next[bit_index(x, y, z)].clone(), theta);
+            }
+
+            cb.gate(q_state)
+        });
+
+        meta.create_gate("keccak theta row", |meta| {
+            let mut cb = BaseConstraintBuilder::new(4);
+            let q_theta = meta.query_fixed(q_theta, Rotation::cur());
+            let bits_cur = query_row(meta, &bits, 0);
+            let bits_next = query_row(meta, &bits, 1);
+            let round_constant_bits =
+                round_constant_bits.map(|column| meta.query_fixed(column, Rotation::cur()));
+
+            // rho and pi: b[y][2x + 3y] = rotate_left(a[x][y], r[x][y])
+            let mut b = vec![vec![vec![0.expr(); 64]; 5]; 5];
+            for (x, y) in (0..5).cartesian_product(0..5) {
+                let rotation = ROTATION_CONSTANTS[x][y] as usize;
+                for z in 0..64 {
+                    b[y][(2 * x + 3 * y) % 5][(z + rotation) % 64] =
+                        bits_cur[bit_index(x, y, z)].clone();
+                }
+            }
+            // chi: a[x][y] = b[x][y] ^ (!b[x + 1][y] & b[x + 2][y]), and iota:
+            // a[0][0] ^= round constant
+            for (x, y, z) in (0..5)
+                .cartesian_product(0..5)
+                .cartesian_product(0..64)
+                .map(|((x, y), z)| (x, y, z))
+            {
+                let mut chi = xor(
+                    b[x][y][z].clone(),
+                    not::expr(b[(x + 1) % 5][y][z].clone()) * b[(x + 2) % 5][y][z].clone(),
+                );
+                if (x, y) == (0, 0) {
+                    if let Some(idx) = ROUND_CONSTANT_BITS.iter().position(|bit| *bit == z) {
+                        chi = xor(chi, round_constant_bits[idx].clone());
+                    }
+                }
+                cb.require_equal("chi and iota", bits_next[bit_index(x, y, z)].clone(), chi);
+            }
+
+            cb.gate(q_theta)
+        });
+
+        meta.create_gate("keccak output row", |meta| {
+            let mut cb = BaseConstraintBuilder::new(4);
+            let q_output = meta.query_fixed(keccak_table.q_enable, Rotation::cur());
+            let is_enabled = meta.query_advice(keccak_table.is_enabled, Rotation::cur());
+            let bits_cur = query_row(meta, &bits, 0);
+
+            // The digest is held by the output row of the last block of the
+            // input, which ends with padding.
+            cb.require_equal(
+                "is_enabled = is_final",
+                is_enabled.clone(),
+                meta.query_advice(bits[RATE_IN_BITS + RATE - 1], Rotation(INPUT_ROW)),
+            );
+            cb.condition(is_enabled, |cb| {
+                cb.require_equal(
+                    "input_rlc = RLC of the data bytes",
+                    meta.query_advice(keccak_table.input_rlc, Rotation::cur()),
+                    meta.query_advice(parities[RATE - 1], Rotation(INPUT_ROW)),
+                );
+                cb.require_equal(
+                    "input_len = number of data bytes",
+                    meta.query_advice(keccak_table.input_len, Rotation::cur()),
+                    meta.query_advice(length, Rotation(INPUT_ROW)),
+                );
+                // The digest is made of the first 4 lanes, and its RLC as a
+                // little endian word gives its first byte the highest power.
+                let output_rlc = (0..32).fold(0.expr(), |acc, idx| {
+                    let byte = byte_expr(&bits_cur[idx * 8..(idx + 1) * 8]);
+                    if idx == 31 {
+                        acc + byte
+                    } else {
+                        acc + byte * power_of_randomness[30 - idx].clone()
+                    }
+                });
+                cb.require_equal(
+                    "output_rlc = RLC of the digest",
+                    meta.query_advice(keccak_table.output_rlc, Rotation::cur()),
+                    output_rlc,
+                );
+            });
+
+            cb.gate(q_output)
+        });
+
+        Self {
+            keccak_table,
+            bits,
+            parities,
+            is_first,
+            length,
+            q_first,
+            q_input,
+            q_state,
+            q_theta,
+            round_constant_bits,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Return the number of rows of the keccak circuit hashing `inputs`, each
+    /// of which takes a permutation per block of 136 bytes, including a
+    /// padding of at least one byte.
+    pub fn num_rows(inputs: &[Vec<u8>]) -> usize {
+        inputs
+            .iter()
+            .map(|input| (input.len() / RATE + 1) * ROWS_PER_PERMUTATION)
+            .sum()
+    }
+
+    /// Assign the rows of the permutations hashing `inputs` with the RLCs of
+    /// `randomness`, followed by permutations hashing the empty input to fill
+    /// the `num_rows` of the circuit, so that the fixed columns don't depend
+    /// on the inputs.
+    pub fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        num_rows: usize,
+        inputs: &[Vec<u8>],
+        randomness: F,
+    ) -> Result<(), Error> {
+        let required_rows = Self::num_rows(inputs);
+        if required_rows > num_rows {
+            error!(
+                "keccak rows {} exceed the capacity of {}",
+                required_rows, num_rows
+            );
+            return Err(Error::Synthesis);
+        }
+        let num_padding_inputs = (num_rows - required_rows) / ROWS_PER_PERMUTATION;
+        let rows: Vec<KeccakRow<F>> = inputs
+            .par_iter()
+            .chain(vec![vec![]; num_padding_inputs].par_iter())
+            .flat_map(|input| input_rows(input, randomness))
+            .collect();
+        self.assign_rows(layouter, &rows)
+    }
+
+    fn assign_rows(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        rows: &[KeccakRow<F>],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "keccak circuit",
+            |mut region| {
+                for (offset, row) in rows.iter().enumerate() {
+                    self.assign_fixed_row(&mut region, offset)?;
+                    for (column, bit) in self.bits.iter().zip(row.bits.iter()) {
+                        region.assign_advice(
+                            || format!("keccak bit {}", offset),
+                            *column,
+                            offset,
+                            || Ok(F::from(*bit as u64)),
+                        )?;
+                    }
+                    for (column, value) in self.parities.iter().zip(row.parities.iter()) {
+                        region.assign_advice(
+                            || format!("keccak parity {}", offset),
+                            *column,
+                            offset,
+                            || Ok(*value),
+                        )?;
+                    }
+                    for (name, column, value) in [
+                        ("is_first", self.is_first, F::from(row.is_first as u64)),
+                        ("length", self.length, F::from(row.length)),
+                    ]
+                    .into_iter()
+                    .chain(
+                        ["is_enabled", "input_rlc", "input_len", "output_rlc"]
+                            .into_iter()
+                            .zip(self.keccak_table.columns())
+                            .zip(row.table)
+                            .map(|((name, column), value)| (name, column, value)),
+                    ) {
+                        region.assign_advice(
+                            || format!("keccak {} {}", name, offset),
+                            column,
+                            offset,
+                            || Ok(value),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the selectors and the round constant bits of the row at
+    /// `offset`, which only depend on its position in its permutation.
+    fn assign_fixed_row(&self, region: &mut Region<'_, F>, offset: usize) -> Result<(), Error> {
+        let row = offset % ROWS_PER_PERMUTATION;
+        let (round, is_theta) = ((row.max(1) - 1) / 2, row > 0 && row % 2 == 0);
+        for (name, column, value) in [
+            ("q_first", self.q_first, offset == 0),
+            ("q_input", self.q_input, row == 0),
+            (
+                "q_state",
+                self.q_state,
+                row % 2 == 1 && row < ROWS_PER_PERMUTATION - 1,
+            ),
+            (
+                "q_theta",
+                self.q_theta,
+                is_theta && row < ROWS_PER_PERMUTATION - 1,
+            ),
+            (
+                "q_output",
+                self.keccak_table.q_enable,
+                row == ROWS_PER_PERMUTATION - 1,
+            ),
+        ] {
+            region.assign_fixed(
+                || format!("keccak {} {}", name, offset),
+                column,
+                offset,
+                || Ok(F::from(value as u64)),
+            )?;
+        }
+        for (column, bit) in self.round_constant_bits.iter().zip(ROUND_CONSTANT_BITS) {
+            let value = is_theta
+                && row < ROWS_PER_PERMUTATION - 1
+                && (ROUND_CONSTANTS[round] >> bit) & 1 == 1;
+            region.assign_fixed(
+                || format!("keccak round constant bit {}", offset),
+                *column,
+                offset,
+                || Ok(F::from(value as u64)),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Values of a row of the keccak circuit.
+#[derive(Clone, Debug)]
+struct KeccakRow<F> {
+    bits: Vec<bool>,
+    parities: Vec<F>,
+    is_first: bool,
+    length: u64,
+    table: [F; 4],
+}
+
+impl<F: Field> KeccakRow<F> {
+    fn new(state: &State) -> Self {
+        Self {
+            bits: (0..NUM_BITS)
+                .map(|idx| {
+                    let lane = idx / 64;
+                    (state[lane % 5][lane / 5] >> (idx % 64)) & 1 == 1
+                })
+                .collect(),
+            parities: vec![F::zero(); NUM_PARITIES],
+            is_first: false,
+            length: 0,
+            table: [F::zero(); 4],
+        }
+    }
+}
+
+/// Return the rows of the permutations absorbing the padded `input`.
+fn input_rows<F: Field>(input: &[u8], randomness: F) -> Vec<KeccakRow<F>> {
+    let num_blocks = input.len() / RATE + 1;
+    let mut padded = input.to_vec();
+    padded.resize(num_blocks * RATE, 0);
+    padded[input.len()] |= 0x01;
+    padded[num_blocks * RATE - 1] |= 0x80;
+
+    let mut state: State = [[0; 5]; 5];
+    let mut rlc = F::zero();
+    let mut rows = Vec::with_capacity(num_blocks * ROWS_PER_PERMUTATION);
+    for (block_idx, block) in padded.chunks(RATE).enumerate() {
+        let mut input_row = KeccakRow::new(&[[0; 5]; 5]);
+        input_row.is_first = block_idx == 0;
+        for (idx, byte) in block.iter().enumerate() {
+            let is_padding = block_idx * RATE + idx >= input.len();
+            for bit in 0..8 {
+                input_row.bits[idx * 8 + bit] = (byte >> bit) & 1 == 1;
+            }
+            input_row.bits[RATE_IN_BITS + idx] = is_padding;
+            if !is_padding {
+                rlc = rlc * randomness + F::from(*byte as u64);
+            }
+            input_row.parities[idx] = rlc;
+        }
+        input_row.length = input.len().min((block_idx + 1) * RATE) as u64;
+        rows.push(input_row);
+
+        for (idx, word) in block.chunks(8).enumerate() {
+            state[idx % 5][idx / 5] ^= u64::from_le_bytes(word.try_into().unwrap());
+        }
+        for round_constant in ROUND_CONSTANTS.iter() {
+            let mut state_row = KeccakRow::new(&state);
+            for (x, lanes) in state.iter().enumerate() {
+                let parity = lanes.iter().fold(0, |acc, lane| acc ^ lane);
+                for z in 0..64 {
+                    state_row.parities[x * 64 + z] = F::from((parity >> z) & 1);
+                }
+            }
+            rows.push(state_row);
+            let theta = KeccakF::theta(state);
+            rows.push(KeccakRow::new(&theta));
+            state = KeccakF::iota(
+                KeccakF::xi(KeccakF::pi(KeccakF::rho(theta))),
+                *round_constant,
+            );
+        }
+        let mut output_row = KeccakRow::new(&state);
+        if block_idx == num_blocks - 1 {
+            output_row.table = KeccakTable::assignments(input, randomness);
+        }
+        rows.push(output_row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod keccak_circuit_tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::{MockProver, VerifyFailure},
+        pairing::bn256::Fr,
+        plonk::Circuit,
+    };
+
+    const NUM_ROWS: usize = 8 * ROWS_PER_PERMUTATION;
+
+    fn randomness() -> Fr {
+        Fr::from(0x100)
+    }
+
+    struct MyCircuit {
+        inputs: Vec<Vec<u8>>,
+        corrupt_output: bool,
+    }
+
+    impl Circuit<Fr> for MyCircuit {
+        type Config = KeccakCircuit<Fr>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                inputs: vec![],
+                corrupt_output: false,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let keccak_table = KeccakTable::construct(meta);
+            let power_of_randomness = (1..32)
+                .map(|exp| Expression::Constant(randomness().pow(&[exp, 0, 0, 0])))
+                .collect_vec()
+                .try_into()
+                .unwrap();
+            KeccakCircuit::configure(meta, keccak_table, power_of_randomness)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            if !self.corrupt_output {
+                return config.assign(&mut layouter, NUM_ROWS, &self.inputs, randomness());
+            }
+            let mut rows = self
+                .inputs
+                .iter()
+                .flat_map(|input| input_rows(input, randomness()))
+                .collect_vec();
+            rows.last_mut().unwrap().table[3] += Fr::one();
+            config.assign_rows(&mut layouter, &rows)
+        }
+    }
+
+    fn run_circuit(inputs: Vec<Vec<u8>>, corrupt_output: bool) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = MyCircuit {
+            inputs,
+            corrupt_output,
+        };
+        let prover = MockProver::<Fr>::run(9, &circuit, vec![]).unwrap();
+        prover.verify()
+    }
+
+    #[test]
+    fn keccak_circuit_valid() {
+        let inputs = vec![
+            vec![],
+            vec![0xff; 31],
+            vec![0x42; 135],
+            vec![0x01; 136],
+            (0..300).map(|idx| idx as u8).collect(),
+        ];
+        assert_eq!(
+            KeccakCircuit::<Fr>::num_rows(&inputs),
+            7 * ROWS_PER_PERMUTATION
+        );
+        assert_eq!(run_circuit(inputs, false), Ok(()));
+    }
+
+    #[test]
+    fn keccak_circuit_table_rows() {
+        let input = (0..300).map(|idx| idx as u8).collect_vec();
+        let rows = input_rows(&input, randomness());
+        assert_eq!(rows.len(), 3 * ROWS_PER_PERMUTATION);
+        assert_eq!(
+            rows.last().unwrap().table,
+            KeccakTable::assignments(&input, randomness())
+        );
+    }
+
+    #[test]
+    fn keccak_circuit_capacity_exceeded() {
+        let circuit = MyCircuit {
+            inputs: vec![vec![0x01; 136 * 8]],
+            corrupt_output: false,
+        };
+        assert!(MockProver::<Fr>::run(9, &circuit, vec![]).is_err());
+    }
+
+    #[test]
+    fn keccak_circuit_invalid_digest() {
+        assert!(run_circuit(vec![vec![0x01; 10]], true).is_err());
+    }
+}
@@ -19,11 +19,25 @@
 pub mod bytecode_circuit;
 pub mod copy_circuit;
 pub mod evm_circuit;
+pub mod exp_circuit;
+pub mod keccak_circuit;
+pub mod mpt_circuit;
+pub mod pi_circuit;
+pub mod poseidon_circuit;
+pub mod rlp_circuit;
 pub mod rw_table;
+#[cfg(test)]
+mod soundness_canaries;
 pub mod state_circuit;
+pub mod stats;
+pub mod super_circuit;
+pub mod table;
 #[cfg(test)]
 pub mod test_util;
 pub mod tx_circuit;
 pub mod util;
+#[cfg(test)]
+mod witness_snapshots;
+pub mod zktrie_circuit;
 
 pub use gadgets::impl_expr;
@@ -0,0 +1,2019 @@
+//! The MPT circuit implements constraints for the updates of the Merkle
+//! Patricia Tries of the state, proving that each update of an account field
+//! or a storage slot changes the root of its trie from the old root to the new
+//! root.
+//!
+//! Each update is laid out as the path of its key in the storage trie (for a
+//! storage update) followed by its path in the account trie, where every path
+//! takes one row per nibble of the key:
+//!   - a branch node takes the row of the nibble selecting its child, and holds
+//!     the references to its 16 children,
+//!   - an extension node takes one row per nibble of its path, and holds the
+//!     reference to its child,
+//!   - a leaf node takes the rows of the remaining nibbles of the key.
+//!
+//! Every row holds the references to its node before and after the update.  A
+//! branch node only changes its child selected by the nibble, which is the node
+//! of the next row, and the node of the first row is the root.  A missing leaf
+//! (for an insertion or a deletion) has the reference 0 of an empty child.
+//!
+//! The storage roots of a storage path are the values of the account path
+//! following it, and the new state root of each update is the old state root of
+//! the next one.
+//!
+//! The columns of the [`MptTable`] hold the values of the paths, and each
+//! update is enabled in the table at the first row of its first path, where it
+//! is looked up by the state circuit.
+//!
+//! The nodes and the leaf values are given by their RLP encodings, decoded by
+//! the [`RlpDecoderConfig`]: the children of a branch, the path and the child
+//! of an extension, and the path and the value of a leaf are looked up as the
+//! items of the encoding of their node, identified by its RLC and its length.
+//! The value of a leaf is itself decoded into the storage value or into the
+//! fields of the account, the fields not modified by the update being the same
+//! before and after the update.
+//!
+//! Every node is referenced by the RLC of the hash of its encoding, looked up
+//! in the keccak table, except for the nodes shorter than 32 bytes inlined into
+//! their parent, which are referenced by the RLC of their encoding.  The
+//! references are the items of the encodings of the parents, which links every
+//! node on a path to the root of the trie.
+//!
+//! The tries are secure tries, whose keys are the hashes of the addresses and
+//! of the storage keys.  The key of every path is looked up in the keccak
+//! table at its last row, from the storage key or from the address, whose
+//! nibbles are laid out on the first rows of the path.
+//!
+//! The updates of a block are generated by [`mpt_updates`], which replays
+//! them on the [`MptState`] known from the proofs of `eth_getProof` at the
+//! state root before the block.
+
+mod rlp;
+mod state;
+mod trie;
+mod witness;
+
+pub use rlp::{RlpDecoderConfig, RlpDecoderRow, RlpItemQueries, RlpPrefixType};
+#[cfg(test)]
+pub(crate) use state::{dev_state_proofs, replay_updates};
+pub use state::{mpt_updates, MptState};
+pub use trie::Trie;
+pub(crate) use witness::hash_rlc;
+pub use witness::{
+    account_trie_key, storage_trie_key, MptNodeType, MptPath, MptProof, MptProofType, MptRow,
+    MptUpdate, MptWitnessError, ADDRESS_NIBBLES, KEY_NIBBLES,
+};
+
+use bus_mapping::circuit_input_builder::CircuitsParams;
+use eth_types::Field;
+use ethers_core::utils::keccak256;
+use gadgets::{
+    binary_number::{BinaryNumberChip, BinaryNumberConfig},
+    util::{not, sum, Expr},
+};
+use halo2_proofs::{
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{
+    evm_circuit::{
+        table::LookupTable,
+        util::{constraint_builder::BaseConstraintBuilder, rlc},
+    },
+    table::{KeccakTable, MptTable},
+    tx_circuit::sign_verify::{
+        KECCAK_INPUT_LEN, KECCAK_INPUT_RLC, KECCAK_IS_ENABLED, KECCAK_OUTPUT_RLC,
+    },
+};
+pub(crate) use witness::ACCOUNT_FIELDS;
+
+/// The MPT circuit, which constrains the paths of every trie update.
+#[derive(Clone, Debug)]
+pub struct MptCircuit<F> {
+    /// Whether the row belongs to a path.
+    pub q_enable: Column<Fixed>,
+    /// Whether the row is the first one of a path, at the root.
+    pub q_first: Column<Fixed>,
+    /// Whether the row is the last one of a path.
+    pub q_last: Column<Fixed>,
+    /// Whether the row is at an odd depth, being the low nibble of a key byte.
+    pub q_odd: Column<Fixed>,
+    /// Whether the row is at a depth below the number of nibbles of an
+    /// address.
+    pub q_address: Column<Fixed>,
+    /// The table of the updates, holding the address, the storage key, the
+    /// state roots and the values of the path on every row.
+    pub mpt_table: MptTable,
+    /// The keccak table, holding the hashes of the nodes referenced by their
+    /// hash.
+    pub keccak_table: KeccakTable,
+    /// The type of the update the path belongs to.
+    pub proof_type: BinaryNumberConfig<MptProofType, 3>,
+    /// Whether the path is in a storage trie.
+    pub is_storage: Column<Advice>,
+    /// The RLC of the root of the trie before the update.
+    pub old_root: Column<Advice>,
+    /// The RLC of the root of the trie after the update.
+    pub new_root: Column<Advice>,
+    /// The RLC of the key of the path, as a little endian word like the hash
+    /// of the keccak table.
+    pub key_rlc: Column<Advice>,
+    /// Whether the key has no value before the update.
+    pub is_old_empty: Column<Advice>,
+    /// Whether the key has no value after the update.
+    pub is_new_empty: Column<Advice>,
+    /// The RLC of the encoding of the leaf value before the update.
+    pub old_leaf_rlc: Column<Advice>,
+    /// The length of the encoding of the leaf value before the update.
+    pub old_leaf_len: Column<Advice>,
+    /// The RLC of the encoding of the leaf value after the update.
+    pub new_leaf_rlc: Column<Advice>,
+    /// The length of the encoding of the leaf value after the update.
+    pub new_leaf_len: Column<Advice>,
+    /// Whether the path is an account path modifying each field of the
+    /// account, in the order of the encoding of the account.
+    pub is_account_field: [Column<Advice>; 4],
+    /// The RLCs of the fields of the account, which are the same before and
+    /// after the update except for the modified field.
+    pub account_fields: [Column<Advice>; 4],
+    /// The lengths of the fields of the account.
+    pub account_field_lens: [Column<Advice>; 4],
+    /// The type of the node of the row.
+    pub node_type: BinaryNumberConfig<MptNodeType, 2>,
+    /// The nibble of the key at the depth of the row.
+    pub nibble: Column<Advice>,
+    /// The RLC of the key nibbles up to the depth of the row.
+    pub key_rlc_acc: Column<Advice>,
+    /// The multiplier of the nibble in the RLC of the key, which is
+    /// 16 * r^(31 - i) for the high nibble and r^(31 - i) for the low nibble
+    /// of the i-th key byte.
+    pub key_rlc_mult: Column<Advice>,
+    /// The nibble of the address at the depth of the row, or 0 below the
+    /// nibbles of the address.
+    pub address_nibble: Column<Advice>,
+    /// The address nibbles up to the depth of the row.
+    pub address_acc: Column<Advice>,
+    /// The RLC of the address bytes up to the depth of the row, being the
+    /// input of the keccak table for the key of an account.
+    pub address_rlc_acc: Column<Advice>,
+    /// The reference to the node before the update.
+    pub old_ref: Column<Advice>,
+    /// The reference to the node after the update.
+    pub new_ref: Column<Advice>,
+    /// The RLC of the encoding of the node before the update.
+    pub old_node_rlc: Column<Advice>,
+    /// The length of the encoding of the node before the update.
+    pub old_node_len: Column<Advice>,
+    /// The RLC of the encoding of the node after the update.
+    pub new_node_rlc: Column<Advice>,
+    /// The length of the encoding of the node after the update.
+    pub new_node_len: Column<Advice>,
+    /// Whether the node before the update is inlined into its parent.
+    pub is_old_inline: Column<Advice>,
+    /// Whether the node after the update is inlined into its parent.
+    pub is_new_inline: Column<Advice>,
+    /// The RLC of the nibbles of the extension or leaf node up to the row.
+    pub node_path_rlc: Column<Advice>,
+    /// The number of nibbles of the extension or leaf node up to the row.
+    pub node_path_len: Column<Advice>,
+    /// The reference to the child of the extension node before the update.
+    pub old_ext_child: Column<Advice>,
+    /// The reference to the child of the extension node after the update.
+    pub new_ext_child: Column<Advice>,
+    /// Whether the child of the branch node is selected by the nibble.
+    pub is_child: [Column<Advice>; 16],
+    /// The references to the children of the branch node before the update.
+    pub old_children: [Column<Advice>; 16],
+    /// The references to the children of the branch node after the update.
+    pub new_children: [Column<Advice>; 16],
+    /// Fixed table of the values 0..16 to range check the nibbles.
+    pub u4_table: Column<Fixed>,
+    /// The decoder of the encodings of the nodes and the leaf values.
+    pub rlp_decoder: RlpDecoderConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> MptCircuit<F> {
+    /// Configure the MPT circuit constraining the paths of the trie updates.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        mpt_table: MptTable,
+        keccak_table: KeccakTable,
+        power_of_randomness: [Expression<F>; 31],
+    ) -> Self {
+        let MptTable {
+            address,
+            storage_key,
+            old_root: old_state_root,
+            new_root: new_state_root,
+            old_value,
+            new_value,
+            ..
+        } = mpt_table;
+
+        let q_enable = meta.fixed_column();
+        let q_first = meta.fixed_column();
+        let q_last = meta.fixed_column();
+        let q_odd = meta.fixed_column();
+        let q_address = meta.fixed_column();
+        let proof_type = BinaryNumberChip::configure(meta, q_enable);
+        let is_storage = meta.advice_column();
+        let old_root = meta.advice_column();
+        let new_root = meta.advice_column();
+        let key_rlc = meta.advice_column();
+        let is_old_empty = meta.advice_column();
+        let is_new_empty = meta.advice_column();
+        let old_leaf_rlc = meta.advice_column();
+        let old_leaf_len = meta.advice_column();
+        let new_leaf_rlc = meta.advice_column();
+        let new_leaf_len = meta.advice_column();
+        let is_account_field = [(); 4].map(|_| meta.advice_column());
+        let account_fields = [(); 4].map(|_| meta.advice_column());
+        let account_field_lens = [(); 4].map(|_| meta.advice_column());
+        let node_type = BinaryNumberChip::configure(meta, q_enable);
+        let nibble = meta.advice_column();
+        let key_rlc_acc = meta.advice_column();
+        let key_rlc_mult = meta.advice_column();
+        let address_nibble = meta.advice_column();
+        let address_acc = meta.advice_column();
+        let address_rlc_acc = meta.advice_column();
+        let old_ref = meta.advice_column();
+        let new_ref = meta.advice_column();
+        let old_node_rlc = meta.advice_column();
+        let old_node_len = meta.advice_column();
+        let new_node_rlc = meta.advice_column();
+        let new_node_len = meta.advice_column();
+        let is_old_inline = meta.advice_column();
+        let is_new_inline = meta.advice_column();
+        let node_path_rlc = meta.advice_column();
+        let node_path_len = meta.advice_column();
+        let old_ext_child = meta.advice_column();
+        let new_ext_child = meta.advice_column();
+        let is_child = [(); 16].map(|_| meta.advice_column());
+        let old_children = [(); 16].map(|_| meta.advice_column());
+        let new_children = [(); 16].map(|_| meta.advice_column());
+        let u4_table = meta.fixed_column();
+        let rlp_decoder =
+            RlpDecoderConfig::configure(meta, u4_table, power_of_randomness[0].clone());
+
+        // The root of the empty trie is the hash of the empty string, as a
+        // little endian word.
+        let mut empty_root = keccak256(&[0x80]);
+        empty_root.reverse();
+        let empty_root_rlc = rlc::expr(&empty_root.map(|byte| byte.expr()), &power_of_randomness);
+
+        meta.create_gate("mpt path values and key", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+            let q_odd = meta.query_fixed(q_odd, Rotation::cur());
+            let is_storage_expr = meta.query_advice(is_storage, Rotation::cur());
+            let is_old_empty_expr = meta.query_advice(is_old_empty, Rotation::cur());
+            let is_new_empty_expr = meta.query_advice(is_new_empty, Rotation::cur());
+            let is_leaf = node_type.value_equals(MptNodeType::Leaf, Rotation::cur())(meta);
+            let is_storage_mod =
+                proof_type.value_equals(MptProofType::StorageMod, Rotation::cur())(meta);
+
+            cb.require_boolean("is_storage is boolean", is_storage_expr.clone());
+            cb.require_boolean("is_old_empty is boolean", is_old_empty_expr.clone());
+            cb.require_boolean("is_new_empty is boolean", is_new_empty_expr.clone());
+            cb.require_zero(
+                "key has a value before or after the update",
+                is_old_empty_expr.clone() * is_new_empty_expr.clone(),
+            );
+
+            cb.require_equal(
+                "proof_type in the mpt table is the proof type of the path",
+                meta.query_advice(mpt_table.proof_type, Rotation::cur()),
+                proof_type.value(Rotation::cur())(meta),
+            );
+            cb.require_zero(
+                "storage_key is 0 for an account update",
+                not::expr(is_storage_mod.clone()) * meta.query_advice(storage_key, Rotation::cur()),
+            );
+
+            // The update is in the table at the first row of its first path,
+            // which is its storage path for a storage update.
+            cb.require_equal(
+                "update is enabled in the mpt table at the first row of its first path",
+                meta.query_fixed(mpt_table.q_enable, Rotation::cur()),
+                q_first.clone() * (1.expr() - not::expr(is_storage_expr.clone()) * is_storage_mod),
+            );
+
+            for (column, field) in is_account_field.iter().zip(ACCOUNT_FIELDS) {
+                cb.require_equal(
+                    "is_account_field is the field modified by an account path",
+                    meta.query_advice(*column, Rotation::cur()),
+                    not::expr(is_storage_expr.clone())
+                        * proof_type.value_equals(field, Rotation::cur())(meta),
+                );
+            }
+
+            cb.condition(is_storage_expr.clone(), |cb| {
+                cb.require_equal(
+                    "storage path belongs to a storage update",
+                    proof_type.value(Rotation::cur())(meta),
+                    MptProofType::StorageMod.expr(),
+                );
+            });
+            cb.condition(not::expr(is_storage_expr), |cb| {
+                cb.require_equal(
+                    "old_state_root == old_root for an account path",
+                    meta.query_advice(old_state_root, Rotation::cur()),
+                    meta.query_advice(old_root, Rotation::cur()),
+                );
+                cb.require_equal(
+                    "new_state_root == new_root for an account path",
+                    meta.query_advice(new_state_root, Rotation::cur()),
+                    meta.query_advice(new_root, Rotation::cur()),
+                );
+            });
+
+            cb.condition(not::expr(q_last.clone()), |cb| {
+                cb.require_equal(
+                    "proof_type_next == proof_type",
+                    proof_type.value(Rotation::next())(meta),
+                    proof_type.value(Rotation::cur())(meta),
+                );
+                for column in [
+                    is_storage,
+                    address,
+                    storage_key,
+                    old_root,
+                    new_root,
+                    old_state_root,
+                    new_state_root,
+                    key_rlc,
+                    old_value,
+                    new_value,
+                    is_old_empty,
+                    is_new_empty,
+                ] {
+                    cb.require_equal(
+                        "path value is the same for all rows of the path",
+                        meta.query_advice(column, Rotation::next()),
+                        meta.query_advice(column, Rotation::cur()),
+                    );
+                }
+            });
+
+            // key_rlc_acc accumulates the RLC of the key bytes from the highest
+            // power of randomness, each byte made of the nibble at an even
+            // depth followed by the nibble at an odd depth.
+            let nibble = meta.query_advice(nibble, Rotation::cur());
+            let key_rlc_acc_expr = meta.query_advice(key_rlc_acc, Rotation::cur());
+            let key_rlc_mult_expr = meta.query_advice(key_rlc_mult, Rotation::cur());
+            let key_rlc_mult_prev = meta.query_advice(key_rlc_mult, Rotation::prev());
+            cb.condition(q_first.clone(), |cb| {
+                cb.require_equal(
+                    "key_rlc_mult == 16 * r^31 at the root",
+                    key_rlc_mult_expr.clone(),
+                    16.expr() * power_of_randomness[30].clone(),
+                );
+                cb.require_equal(
+                    "key_rlc_acc == nibble * key_rlc_mult at the root",
+                    key_rlc_acc_expr.clone(),
+                    nibble.clone() * key_rlc_mult_expr.clone(),
+                );
+            });
+            cb.condition(not::expr(q_first.clone()), |cb| {
+                cb.require_equal(
+                    "key_rlc_acc == key_rlc_acc_prev + nibble * key_rlc_mult",
+                    key_rlc_acc_expr.clone(),
+                    meta.query_advice(key_rlc_acc, Rotation::prev())
+                        + nibble * key_rlc_mult_expr.clone(),
+                );
+            });
+            cb.condition(q_odd.clone(), |cb| {
+                cb.require_equal(
+                    "key_rlc_mult_prev == 16 * key_rlc_mult at an odd depth",
+                    key_rlc_mult_prev.clone(),
+                    16.expr() * key_rlc_mult_expr.clone(),
+                );
+            });
+            cb.condition(not::expr(q_first.clone()) * not::expr(q_odd), |cb| {
+                cb.require_equal(
+                    "key_rlc_mult * r == key_rlc_mult_prev * 16 at an even depth",
+                    key_rlc_mult_expr * power_of_randomness[0].clone(),
+                    key_rlc_mult_prev * 16.expr(),
+                );
+            });
+            cb.condition(q_last, |cb| {
+                cb.require_equal(
+                    "key_rlc == key_rlc_acc at the last nibble",
+                    meta.query_advice(key_rlc, Rotation::cur()),
+                    key_rlc_acc_expr,
+                );
+            });
+
+            // The root of an empty trie is not the reference 0 of its missing
+            // leaf.
+            cb.condition(q_first, |cb| {
+                cb.require_equal(
+                    "old_root is the reference to the root node before the update",
+                    meta.query_advice(old_root, Rotation::cur()),
+                    meta.query_advice(old_ref, Rotation::cur())
+                        + is_old_empty_expr * is_leaf.clone() * empty_root_rlc.clone(),
+                );
+                cb.require_equal(
+                    "new_root is the reference to the root node after the update",
+                    meta.query_advice(new_root, Rotation::cur()),
+                    meta.query_advice(new_ref, Rotation::cur())
+                        + is_new_empty_expr * is_leaf * empty_root_rlc.clone(),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("mpt extension and leaf nodes", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+            let is_extension =
+                node_type.value_equals(MptNodeType::Extension, Rotation::cur())(meta);
+            let is_leaf = node_type.value_equals(MptNodeType::Leaf, Rotation::cur())(meta);
+            let is_branch_next =
+                node_type.value_equals(MptNodeType::Branch, Rotation::next())(meta);
+            let is_extension_next =
+                node_type.value_equals(MptNodeType::Extension, Rotation::next())(meta);
+            let is_leaf_next = node_type.value_equals(MptNodeType::Leaf, Rotation::next())(meta);
+
+            cb.condition(q_last.clone(), |cb| {
+                cb.require_equal("last row is a leaf", is_leaf.clone(), 1.expr());
+            });
+
+            // A missing leaf has the reference and the value of an empty child.
+            for (is_empty, node_ref, value) in [
+                (is_old_empty, old_ref, old_value),
+                (is_new_empty, new_ref, new_value),
+            ] {
+                cb.condition(
+                    is_leaf.clone() * meta.query_advice(is_empty, Rotation::cur()),
+                    |cb| {
+                        cb.require_zero(
+                            "reference to a missing leaf is 0",
+                            meta.query_advice(node_ref, Rotation::cur()),
+                        );
+                        cb.require_zero(
+                            "value of a missing leaf is 0",
+                            meta.query_advice(value, Rotation::cur()),
+                        );
+                    },
+                );
+            }
+
+            cb.condition(is_leaf * not::expr(q_last), |cb| {
+                cb.require_equal(
+                    "leaf is followed by the same leaf",
+                    is_leaf_next.clone(),
+                    1.expr(),
+                );
+                for column in [
+                    old_ref,
+                    new_ref,
+                    old_node_rlc,
+                    old_node_len,
+                    new_node_rlc,
+                    new_node_len,
+                ] {
+                    cb.require_equal(
+                        "leaf node is the same for all its rows",
+                        meta.query_advice(column, Rotation::next()),
+                        meta.query_advice(column, Rotation::cur()),
+                    );
+                }
+            });
+
+            cb.condition(is_extension.clone(), |cb| {
+                cb.require_zero(
+                    "extension is followed by a branch or the same extension",
+                    is_leaf_next,
+                );
+            });
+            cb.condition(is_extension.clone() * is_extension_next, |cb| {
+                for column in [
+                    old_ref,
+                    new_ref,
+                    old_node_rlc,
+                    old_node_len,
+                    new_node_rlc,
+                    new_node_len,
+                    old_ext_child,
+                    new_ext_child,
+                ] {
+                    cb.require_equal(
+                        "extension node is the same for all its rows",
+                        meta.query_advice(column, Rotation::next()),
+                        meta.query_advice(column, Rotation::cur()),
+                    );
+                }
+            });
+            cb.condition(is_extension * is_branch_next, |cb| {
+                for (ext_child, node_ref) in [(old_ext_child, old_ref), (new_ext_child, new_ref)] {
+                    cb.require_equal(
+                        "extension child is the branch of the next row",
+                        meta.query_advice(ext_child, Rotation::cur()),
+                        meta.query_advice(node_ref, Rotation::next()),
+                    );
+                }
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("mpt extension and leaf paths", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let is_branch = node_type.value_equals(MptNodeType::Branch, Rotation::cur())(meta);
+            let is_branch_prev =
+                node_type.value_equals(MptNodeType::Branch, Rotation::prev())(meta);
+            let nibble = meta.query_advice(nibble, Rotation::cur());
+            let node_path_rlc_expr = meta.query_advice(node_path_rlc, Rotation::cur());
+            let node_path_len_expr = meta.query_advice(node_path_len, Rotation::cur());
+
+            // The nibbles of an extension or a leaf start at its first row,
+            // which is the root or follows a branch.
+            let is_node_start = q_first.clone() + not::expr(q_first) * is_branch_prev;
+            cb.condition(not::expr(is_branch.clone()) * is_node_start.clone(), |cb| {
+                cb.require_equal(
+                    "node_path_rlc == nibble at the first row of the node",
+                    node_path_rlc_expr.clone(),
+                    nibble.clone(),
+                );
+                cb.require_equal(
+                    "node_path_len == 1 at the first row of the node",
+                    node_path_len_expr.clone(),
+                    1.expr(),
+                );
+            });
+            cb.condition(not::expr(is_branch) * not::expr(is_node_start), |cb| {
+                cb.require_equal(
+                    "node_path_rlc == node_path_rlc_prev * r + nibble",
+                    node_path_rlc_expr,
+                    meta.query_advice(node_path_rlc, Rotation::prev())
+                        * power_of_randomness[0].clone()
+                        + nibble,
+                );
+                cb.require_equal(
+                    "node_path_len == node_path_len_prev + 1",
+                    node_path_len_expr,
+                    meta.query_advice(node_path_len, Rotation::prev()) + 1.expr(),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("mpt node references", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let is_leaf = node_type.value_equals(MptNodeType::Leaf, Rotation::cur())(meta);
+
+            for (is_empty, is_inline, node_ref, node_rlc) in [
+                (is_old_empty, is_old_inline, old_ref, old_node_rlc),
+                (is_new_empty, is_new_inline, new_ref, new_node_rlc),
+            ] {
+                let is_inline = meta.query_advice(is_inline, Rotation::cur());
+                cb.require_boolean("is_inline is boolean", is_inline.clone());
+                cb.require_zero(
+                    "root node is not inlined",
+                    q_first.clone() * is_inline.clone(),
+                );
+                cb.require_zero(
+                    "missing leaf is not inlined",
+                    is_leaf.clone()
+                        * meta.query_advice(is_empty, Rotation::cur())
+                        * is_inline.clone(),
+                );
+                cb.condition(is_inline, |cb| {
+                    cb.require_equal(
+                        "reference to an inlined node is the RLC of its encoding",
+                        meta.query_advice(node_ref, Rotation::cur()),
+                        meta.query_advice(node_rlc, Rotation::cur()),
+                    );
+                });
+            }
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        // Every row of a node looks up its hash, since all of them hold the
+        // same node.
+        for (is_empty, is_inline, node_ref, node_rlc, node_len) in [
+            (
+                is_old_empty,
+                is_old_inline,
+                old_ref,
+                old_node_rlc,
+                old_node_len,
+            ),
+            (
+                is_new_empty,
+                is_new_inline,
+                new_ref,
+                new_node_rlc,
+                new_node_len,
+            ),
+        ] {
+            meta.lookup_any("mpt node reference is the hash of its encoding", |meta| {
+                let condition = meta.query_fixed(q_enable, Rotation::cur())
+                    * not::expr(
+                        node_type.value_equals(MptNodeType::Leaf, Rotation::cur())(meta)
+                            * meta.query_advice(is_empty, Rotation::cur()),
+                    )
+                    * not::expr(meta.query_advice(is_inline, Rotation::cur()));
+                let table = keccak_table.table_exprs(meta);
+                vec![
+                    (condition.clone(), KECCAK_IS_ENABLED),
+                    (
+                        condition.clone() * meta.query_advice(node_rlc, Rotation::cur()),
+                        KECCAK_INPUT_RLC,
+                    ),
+                    (
+                        condition.clone() * meta.query_advice(node_len, Rotation::cur()),
+                        KECCAK_INPUT_LEN,
+                    ),
+                    (
+                        condition * meta.query_advice(node_ref, Rotation::cur()),
+                        KECCAK_OUTPUT_RLC,
+                    ),
+                ]
+                .into_iter()
+                .map(|(expr, column)| (expr, table[column].clone()))
+                .collect()
+            });
+        }
+
+        meta.create_gate("mpt branch node", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let is_branch = node_type.value_equals(MptNodeType::Branch, Rotation::cur())(meta);
+            let is_child = is_child.map(|column| meta.query_advice(column, Rotation::cur()));
+            let old_children =
+                old_children.map(|column| meta.query_advice(column, Rotation::cur()));
+            let new_children =
+                new_children.map(|column| meta.query_advice(column, Rotation::cur()));
+
+            cb.condition(is_branch, |cb| {
+                for ((is_child, old_child), new_child) in is_child
+                    .iter()
+                    .zip(old_children.iter())
+                    .zip(new_children.iter())
+                {
+                    cb.require_boolean("is_child is boolean", is_child.clone());
+                    cb.require_zero(
+                        "children not selected by the nibble are not updated",
+                        not::expr(is_child.clone()) * (old_child.clone() - new_child.clone()),
+                    );
+                }
+                cb.require_equal(
+                    "a single child is selected",
+                    sum::expr(is_child.iter()),
+                    1.expr(),
+                );
+                cb.require_equal(
+                    "selected child is the nibble",
+                    sum::expr(
+                        is_child
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, is_child)| is_child.clone() * idx.expr()),
+                    ),
+                    meta.query_advice(nibble, Rotation::cur()),
+                );
+                for (children, node_ref) in [(&old_children, old_ref), (&new_children, new_ref)] {
+                    cb.require_equal(
+                        "selected child is the node of the next row",
+                        sum::expr(
+                            is_child
+                                .iter()
+                                .zip(children.iter())
+                                .map(|(is_child, child)| is_child.clone() * child.clone()),
+                        ),
+                        meta.query_advice(node_ref, Rotation::next()),
+                    );
+                }
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("mpt update chaining", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+            let is_storage_expr = meta.query_advice(is_storage, Rotation::cur());
+            let is_storage_mod =
+                proof_type.value_equals(MptProofType::StorageMod, Rotation::cur())(meta);
+
+            // The storage path is followed by the path of its account, holding
+            // the storage roots as values.
+            cb.condition(q_last.clone() * is_storage_expr.clone(), |cb| {
+                cb.require_equal(
+                    "storage path is followed by a path",
+                    meta.query_fixed(q_enable, Rotation::next()),
+                    1.expr(),
+                );
+                cb.require_zero(
+                    "storage path is followed by an account path",
+                    meta.query_advice(is_storage, Rotation::next()),
+                );
+                cb.require_equal(
+                    "account path of a storage path is a storage update",
+                    proof_type.value(Rotation::next())(meta),
+                    MptProofType::StorageMod.expr(),
+                );
+                for (value, root) in [(old_value, old_root), (new_value, new_root)] {
+                    cb.require_equal(
+                        "account value is the storage root",
+                        meta.query_advice(value, Rotation::next()),
+                        meta.query_advice(root, Rotation::cur()),
+                    );
+                }
+                for column in [address, storage_key, old_state_root, new_state_root] {
+                    cb.require_equal(
+                        "account path has the keys and the state roots of the storage path",
+                        meta.query_advice(column, Rotation::next()),
+                        meta.query_advice(column, Rotation::cur()),
+                    );
+                }
+            });
+            cb.condition(
+                q_first * not::expr(is_storage_expr.clone()) * is_storage_mod,
+                |cb| {
+                    cb.require_equal(
+                        "account path of a storage update follows a path",
+                        meta.query_fixed(q_enable, Rotation::prev()),
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "account path of a storage update follows a storage path",
+                        meta.query_advice(is_storage, Rotation::prev()),
+                        1.expr(),
+                    );
+                },
+            );
+
+            // The new state root of an update is the old one of the next update.
+            cb.condition(
+                q_last * not::expr(is_storage_expr) * meta.query_fixed(q_enable, Rotation::next()),
+                |cb| {
+                    cb.require_equal(
+                        "old_state_root_next == new_state_root",
+                        meta.query_advice(old_state_root, Rotation::next()),
+                        meta.query_advice(new_state_root, Rotation::cur()),
+                    );
+                },
+            );
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("mpt key address", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let q_odd = meta.query_fixed(q_odd, Rotation::cur());
+            let q_address = meta.query_fixed(q_address, Rotation::cur());
+            let nibble = meta.query_advice(address_nibble, Rotation::cur());
+            let address_acc_expr = meta.query_advice(address_acc, Rotation::cur());
+            let address_rlc_acc_expr = meta.query_advice(address_rlc_acc, Rotation::cur());
+            let address_acc_prev = meta.query_advice(address_acc, Rotation::prev());
+            let address_rlc_acc_prev = meta.query_advice(address_rlc_acc, Rotation::prev());
+
+            // address_acc accumulates the nibbles of the address, and
+            // address_rlc_acc the RLC of its bytes from the highest power of
+            // randomness, as the input of the keccak table.
+            cb.condition(not::expr(q_address.clone()), |cb| {
+                cb.require_zero(
+                    "address_nibble is 0 below the nibbles of the address",
+                    nibble.clone(),
+                );
+            });
+            cb.condition(q_first.clone(), |cb| {
+                cb.require_equal(
+                    "address_acc == address_nibble at the root",
+                    address_acc_expr.clone(),
+                    nibble.clone(),
+                );
+                cb.require_equal(
+                    "address_rlc_acc == 16 * address_nibble at the root",
+                    address_rlc_acc_expr.clone(),
+                    16.expr() * nibble.clone(),
+                );
+            });
+            cb.condition(not::expr(q_first) * q_address.clone(), |cb| {
+                cb.require_equal(
+                    "address_acc == address_acc_prev * 16 + address_nibble",
+                    address_acc_expr.clone(),
+                    address_acc_prev.clone() * 16.expr() + nibble.clone(),
+                );
+                cb.require_equal(
+                    "address_rlc_acc accumulates the address nibble",
+                    address_rlc_acc_expr.clone(),
+                    q_odd.clone() * (address_rlc_acc_prev.clone() + nibble.clone())
+                        + not::expr(q_odd)
+                            * (address_rlc_acc_prev.clone() * power_of_randomness[0].clone()
+                                + 16.expr() * nibble),
+                );
+            });
+            cb.condition(not::expr(q_address), |cb| {
+                cb.require_equal(
+                    "address_acc == address_acc_prev below the nibbles of the address",
+                    address_acc_expr.clone(),
+                    address_acc_prev,
+                );
+                cb.require_equal(
+                    "address_rlc_acc == address_rlc_acc_prev below the nibbles of the address",
+                    address_rlc_acc_expr,
+                    address_rlc_acc_prev,
+                );
+            });
+            cb.condition(meta.query_fixed(q_last, Rotation::cur()), |cb| {
+                cb.require_equal(
+                    "address_acc == address at the last nibble",
+                    address_acc_expr,
+                    meta.query_advice(address, Rotation::cur()),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        // The key of a storage path is the hash of the storage key, whose RLC
+        // as a little endian word is the RLC of its big endian bytes from the
+        // highest power of randomness, and the key of an account path is the
+        // hash of the 20 bytes of the address.
+        meta.lookup_any(
+            "mpt key is the hash of the storage key or the address",
+            |meta| {
+                let condition = meta.query_fixed(q_last, Rotation::cur());
+                let is_storage = meta.query_advice(is_storage, Rotation::cur());
+                let table = keccak_table.table_exprs(meta);
+                vec![
+                    (condition.clone(), KECCAK_IS_ENABLED),
+                    (
+                        condition.clone()
+                            * (is_storage.clone()
+                                * meta.query_advice(storage_key, Rotation::cur())
+                                + not::expr(is_storage.clone())
+                                    * meta.query_advice(address_rlc_acc, Rotation::cur())),
+                        KECCAK_INPUT_RLC,
+                    ),
+                    (
+                        condition.clone()
+                            * (is_storage.clone() * 32.expr() + not::expr(is_storage) * 20.expr()),
+                        KECCAK_INPUT_LEN,
+                    ),
+                    (
+                        condition * meta.query_advice(key_rlc, Rotation::cur()),
+                        KECCAK_OUTPUT_RLC,
+                    ),
+                ]
+                .into_iter()
+                .map(|(expr, column)| (expr, table[column].clone()))
+                .collect()
+            },
+        );
+
+        for nibble in [nibble, address_nibble] {
+            meta.lookup_any("nibble fits into u4", |meta| {
+                let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+                let nibble = meta.query_advice(nibble, Rotation::cur());
+                let u4_table = meta.query_fixed(u4_table, Rotation::cur());
+                vec![(q_enable * nibble, u4_table)]
+            });
+        }
+
+        // The inputs matching the stream and the index of an item with the
+        // encoding of a node.
+        let node_item = |meta: &mut VirtualCells<F>,
+                         (node_rlc, node_len): (Column<Advice>, Column<Advice>),
+                         index: usize,
+                         item: &RlpItemQueries<F>| {
+            vec![
+                (
+                    meta.query_advice(node_rlc, Rotation::cur()),
+                    item.stream_rlc.clone(),
+                ),
+                (
+                    meta.query_advice(node_len, Rotation::cur()),
+                    item.stream_len.clone(),
+                ),
+                (index.expr(), item.index.clone()),
+            ]
+        };
+
+        for (node, children) in [
+            ((old_node_rlc, old_node_len), old_children),
+            ((new_node_rlc, new_node_len), new_children),
+        ] {
+            for (idx, child) in children.into_iter().enumerate() {
+                rlp_decoder.lookup_item(
+                    meta,
+                    "mpt branch child is an item of the branch node",
+                    |meta, item| {
+                        let mut inputs = node_item(meta, node, idx, &item);
+                        inputs.push((meta.query_advice(child, Rotation::cur()), item.horner));
+                        (
+                            meta.query_fixed(q_enable, Rotation::cur())
+                                * node_type.value_equals(MptNodeType::Branch, Rotation::cur())(
+                                    meta,
+                                ),
+                            inputs,
+                        )
+                    },
+                );
+            }
+            rlp_decoder.lookup_item(
+                meta,
+                "mpt branch node ends with an empty value",
+                |meta, item| {
+                    let mut inputs = node_item(meta, node, 16, &item);
+                    inputs.extend([(1.expr(), item.is_last), (0.expr(), item.len)]);
+                    (
+                        meta.query_fixed(q_enable, Rotation::cur())
+                            * node_type.value_equals(MptNodeType::Branch, Rotation::cur())(meta),
+                        inputs,
+                    )
+                },
+            );
+        }
+
+        // The rows out of the paths are the padding leaf or unassigned rows of
+        // the type of a branch, so that the lookups of the extensions don't
+        // need q_enable.
+        for (node, ext_child) in [
+            ((old_node_rlc, old_node_len), old_ext_child),
+            ((new_node_rlc, new_node_len), new_ext_child),
+        ] {
+            rlp_decoder.lookup_item(
+                meta,
+                "mpt extension path is the first item of the extension node",
+                |meta, item| {
+                    let mut inputs = node_item(meta, node, 0, &item);
+                    inputs.extend([
+                        (1.expr(), item.is_hp),
+                        (0.expr(), item.hp_is_leaf),
+                        (
+                            meta.query_advice(node_path_rlc, Rotation::cur()),
+                            item.nibbles_rlc,
+                        ),
+                        (
+                            meta.query_advice(node_path_len, Rotation::cur()),
+                            item.nibbles_len,
+                        ),
+                    ]);
+                    (
+                        node_type.value_equals(MptNodeType::Extension, Rotation::cur())(meta)
+                            * node_type.value_equals(MptNodeType::Branch, Rotation::next())(meta),
+                        inputs,
+                    )
+                },
+            );
+            rlp_decoder.lookup_item(
+                meta,
+                "mpt extension child is the last item of the extension node",
+                |meta, item| {
+                    let mut inputs = node_item(meta, node, 1, &item);
+                    inputs.extend([
+                        (1.expr(), item.is_last),
+                        (meta.query_advice(ext_child, Rotation::cur()), item.horner),
+                    ]);
+                    (
+                        node_type.value_equals(MptNodeType::Extension, Rotation::cur())(meta),
+                        inputs,
+                    )
+                },
+            );
+        }
+
+        for (is_empty, node, (leaf_rlc, leaf_len), value) in [
+            (
+                is_old_empty,
+                (old_node_rlc, old_node_len),
+                (old_leaf_rlc, old_leaf_len),
+                old_value,
+            ),
+            (
+                is_new_empty,
+                (new_node_rlc, new_node_len),
+                (new_leaf_rlc, new_leaf_len),
+                new_value,
+            ),
+        ] {
+            let is_leaf = move |meta: &mut VirtualCells<F>| {
+                meta.query_fixed(q_last, Rotation::cur())
+                    * not::expr(meta.query_advice(is_empty, Rotation::cur()))
+            };
+            rlp_decoder.lookup_item(
+                meta,
+                "mpt leaf path is the first item of the leaf node",
+                |meta, item| {
+                    let mut inputs = node_item(meta, node, 0, &item);
+                    inputs.extend([
+                        (1.expr(), item.is_hp),
+                        (1.expr(), item.hp_is_leaf),
+                        (
+                            meta.query_advice(node_path_rlc, Rotation::cur()),
+                            item.nibbles_rlc,
+                        ),
+                        (
+                            meta.query_advice(node_path_len, Rotation::cur()),
+                            item.nibbles_len,
+                        ),
+                    ]);
+                    (is_leaf(meta), inputs)
+                },
+            );
+            rlp_decoder.lookup_item(
+                meta,
+                "mpt leaf value is the last item of the leaf node",
+                |meta, item| {
+                    let mut inputs = node_item(meta, node, 1, &item);
+                    inputs.extend([
+                        (1.expr(), item.is_last),
+                        (meta.query_advice(leaf_rlc, Rotation::cur()), item.horner),
+                        (meta.query_advice(leaf_len, Rotation::cur()), item.len),
+                    ]);
+                    (is_leaf(meta), inputs)
+                },
+            );
+
+            // The storage value is the RLC of the big-endian bytes of the
+            // word.
+            rlp_decoder.lookup_item(
+                meta,
+                "mpt storage value is the string of the leaf value",
+                |meta, item| {
+                    let mut inputs = node_item(meta, (leaf_rlc, leaf_len), 0, &item);
+                    inputs.extend([
+                        (1.expr(), item.is_last),
+                        (meta.query_advice(value, Rotation::cur()), item.horner),
+                    ]);
+                    (
+                        is_leaf(meta) * meta.query_advice(is_storage, Rotation::cur()),
+                        inputs,
+                    )
+                },
+            );
+
+            // The nonce is a number, and the balance, the storage root and the
+            // code hash are the RLC of a word.
+            for (idx, field) in ACCOUNT_FIELDS.into_iter().enumerate() {
+                let is_last_field = idx == ACCOUNT_FIELDS.len() - 1;
+                rlp_decoder.lookup_item(
+                    meta,
+                    "mpt modified account field is an item of the leaf value",
+                    |meta, item| {
+                        let mut inputs = node_item(meta, (leaf_rlc, leaf_len), idx, &item);
+                        let field_value = if field == MptProofType::NonceMod {
+                            item.value
+                        } else {
+                            item.horner
+                        };
+                        inputs.extend([
+                            (is_last_field.expr(), item.is_last),
+                            (meta.query_advice(value, Rotation::cur()), field_value),
+                        ]);
+                        (
+                            is_leaf(meta)
+                                * meta.query_advice(is_account_field[idx], Rotation::cur()),
+                            inputs,
+                        )
+                    },
+                );
+                rlp_decoder.lookup_item(
+                    meta,
+                    "mpt account field not modified by the update is an item of the leaf value",
+                    |meta, item| {
+                        let mut inputs = node_item(meta, (leaf_rlc, leaf_len), idx, &item);
+                        inputs.extend([
+                            (is_last_field.expr(), item.is_last),
+                            (
+                                meta.query_advice(account_fields[idx], Rotation::cur()),
+                                item.rlc,
+                            ),
+                            (
+                                meta.query_advice(account_field_lens[idx], Rotation::cur()),
+                                item.len,
+                            ),
+                        ]);
+                        (
+                            is_leaf(meta)
+                                * not::expr(meta.query_advice(is_storage, Rotation::cur()))
+                                * not::expr(
+                                    meta.query_advice(is_account_field[idx], Rotation::cur()),
+                                ),
+                            inputs,
+                        )
+                    },
+                );
+            }
+        }
+
+        Self {
+            q_enable,
+            q_first,
+            q_last,
+            q_odd,
+            q_address,
+            mpt_table,
+            keccak_table,
+            proof_type,
+            is_storage,
+            old_root,
+            new_root,
+            key_rlc,
+            is_old_empty,
+            is_new_empty,
+            old_leaf_rlc,
+            old_leaf_len,
+            new_leaf_rlc,
+            new_leaf_len,
+            is_account_field,
+            account_fields,
+            account_field_lens,
+            node_type,
+            nibble,
+            key_rlc_acc,
+            key_rlc_mult,
+            address_nibble,
+            address_acc,
+            address_rlc_acc,
+            old_ref,
+            new_ref,
+            old_node_rlc,
+            old_node_len,
+            new_node_rlc,
+            new_node_len,
+            is_old_inline,
+            is_new_inline,
+            node_path_rlc,
+            node_path_len,
+            old_ext_child,
+            new_ext_child,
+            is_child,
+            old_children,
+            new_children,
+            u4_table,
+            rlp_decoder,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Load the fixed tables of the values 0..16 and of the RLP prefixes.
+    pub fn load_tables(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "mpt circuit u4 table",
+            |mut region| {
+                for value in 0..16 {
+                    region.assign_fixed(
+                        || "u4 table",
+                        self.u4_table,
+                        value,
+                        || Ok(F::from(value as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+        self.rlp_decoder.load_prefix_table(layouter)
+    }
+
+    /// Return the number of rows of the paths, which are assigned next to the
+    /// rows of the RLP decoder of their nodes.
+    pub fn num_rows(paths: &[MptPath<F>]) -> usize {
+        let num_path_rows = paths.iter().map(|path| path.rows.len()).sum::<usize>();
+        let num_rlp_rows = paths.iter().map(|path| path.rlp_rows.len()).sum::<usize>();
+        num_path_rows.max(num_rlp_rows)
+    }
+
+    /// Return the minimum number of rows required to assign the paths and the
+    /// fixed tables of the circuit with the capacities of `params`.
+    pub fn min_num_rows(params: &CircuitsParams) -> usize {
+        // The paths and the RLP decoder are padded with a row, and the prefix
+        // table holds a row per prefix byte after an empty row.
+        (params.max_mpt_rows + 1).max(1 + 256)
+    }
+
+    /// Assign the rows of every path of the trie updates.
+    pub fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        paths: &[MptPath<F>],
+    ) -> Result<(), Error> {
+        let proof_type_chip = BinaryNumberChip::construct(self.proof_type);
+        let node_type_chip = BinaryNumberChip::construct(self.node_type);
+
+        layouter.assign_region(
+            || "assign mpt paths",
+            |mut region| {
+                let mut offset = 0;
+                for path in paths.iter() {
+                    for (depth, row) in path.rows.iter().enumerate() {
+                        self.assign_row(
+                            &mut region,
+                            offset,
+                            path,
+                            depth,
+                            row,
+                            &proof_type_chip,
+                            &node_type_chip,
+                        )?;
+                        offset += 1;
+                    }
+                }
+                // pad a row in the end to satisfy Halo2 cell assignment check
+                self.assign_padding_row(&mut region, offset, &proof_type_chip, &node_type_chip)
+            },
+        )?;
+
+        self.rlp_decoder
+            .assign(layouter, paths.iter().flat_map(|path| path.rlp_rows.iter()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn assign_row(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        path: &MptPath<F>,
+        depth: usize,
+        row: &MptRow<F>,
+        proof_type_chip: &BinaryNumberChip<F, MptProofType, 3>,
+        node_type_chip: &BinaryNumberChip<F, MptNodeType, 2>,
+    ) -> Result<(), Error> {
+        for (name, column, value) in [
+            ("q_enable", self.q_enable, true),
+            ("q_first", self.q_first, depth == 0),
+            ("q_last", self.q_last, depth == KEY_NIBBLES - 1),
+            ("q_odd", self.q_odd, depth % 2 == 1),
+            ("q_address", self.q_address, depth < ADDRESS_NIBBLES),
+        ] {
+            region.assign_fixed(
+                || format!("assign {} {}", name, offset),
+                column,
+                offset,
+                || Ok(F::from(value)),
+            )?;
+        }
+        proof_type_chip.assign(region, offset, &path.proof_type)?;
+        node_type_chip.assign(region, offset, &row.node_type)?;
+        self.mpt_table.assign_row(
+            region,
+            offset,
+            depth == 0 && path.is_first_of_update(),
+            [
+                path.address,
+                path.storage_key,
+                F::from(path.proof_type as u64),
+                path.old_state_root,
+                path.new_state_root,
+                path.old_value,
+                path.new_value,
+            ],
+        )?;
+
+        let is_branch = row.node_type == MptNodeType::Branch;
+        for (name, column, value) in [
+            ("is_storage", self.is_storage, F::from(path.is_storage)),
+            ("old_root", self.old_root, path.old_root),
+            ("new_root", self.new_root, path.new_root),
+            ("key_rlc", self.key_rlc, path.key_rlc),
+            (
+                "is_old_empty",
+                self.is_old_empty,
+                F::from(path.is_old_empty),
+            ),
+            (
+                "is_new_empty",
+                self.is_new_empty,
+                F::from(path.is_new_empty),
+            ),
+            ("old_leaf_rlc", self.old_leaf_rlc, path.old_leaf_rlc),
+            (
+                "old_leaf_len",
+                self.old_leaf_len,
+                F::from(path.old_leaf_len as u64),
+            ),
+            ("new_leaf_rlc", self.new_leaf_rlc, path.new_leaf_rlc),
+            (
+                "new_leaf_len",
+                self.new_leaf_len,
+                F::from(path.new_leaf_len as u64),
+            ),
+            ("nibble", self.nibble, F::from(row.nibble as u64)),
+            ("key_rlc_acc", self.key_rlc_acc, row.key_rlc_acc),
+            ("key_rlc_mult", self.key_rlc_mult, row.key_rlc_mult),
+            (
+                "address_nibble",
+                self.address_nibble,
+                F::from(row.address_nibble as u64),
+            ),
+            ("address_acc", self.address_acc, row.address_acc),
+            ("address_rlc_acc", self.address_rlc_acc, row.address_rlc_acc),
+            ("old_ref", self.old_ref, row.old_ref),
+            ("new_ref", self.new_ref, row.new_ref),
+            ("old_node_rlc", self.old_node_rlc, row.old_node_rlc),
+            (
+                "old_node_len",
+                self.old_node_len,
+                F::from(row.old_node_len as u64),
+            ),
+            ("new_node_rlc", self.new_node_rlc, row.new_node_rlc),
+            (
+                "new_node_len",
+                self.new_node_len,
+                F::from(row.new_node_len as u64),
+            ),
+            (
+                "is_old_inline",
+                self.is_old_inline,
+                F::from(row.is_old_inline),
+            ),
+            (
+                "is_new_inline",
+                self.is_new_inline,
+                F::from(row.is_new_inline),
+            ),
+            ("node_path_rlc", self.node_path_rlc, row.node_path_rlc),
+            (
+                "node_path_len",
+                self.node_path_len,
+                F::from(row.node_path_len as u64),
+            ),
+            ("old_ext_child", self.old_ext_child, row.old_ext_child),
+            ("new_ext_child", self.new_ext_child, row.new_ext_child),
+        ]
+        .into_iter()
+        .chain((0..16).flat_map(|idx| {
+            [
+                (
+                    "is_child",
+                    self.is_child[idx],
+                    F::from(is_branch && idx == row.nibble as usize),
+                ),
+                (
+                    "old_children",
+                    self.old_children[idx],
+                    row.old_children[idx],
+                ),
+                (
+                    "new_children",
+                    self.new_children[idx],
+                    row.new_children[idx],
+                ),
+            ]
+        }))
+        .chain((0..4).flat_map(|idx| {
+            [
+                (
+                    "is_account_field",
+                    self.is_account_field[idx],
+                    F::from(!path.is_storage && path.proof_type == ACCOUNT_FIELDS[idx]),
+                ),
+                (
+                    "account_fields",
+                    self.account_fields[idx],
+                    path.account_fields[idx],
+                ),
+                (
+                    "account_field_lens",
+                    self.account_field_lens[idx],
+                    F::from(path.account_field_lens[idx] as u64),
+                ),
+            ]
+        })) {
+            region.assign_advice(
+                || format!("assign {} {}", name, offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn assign_padding_row(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        proof_type_chip: &BinaryNumberChip<F, MptProofType, 3>,
+        node_type_chip: &BinaryNumberChip<F, MptNodeType, 2>,
+    ) -> Result<(), Error> {
+        for column in [
+            self.q_enable,
+            self.q_first,
+            self.q_last,
+            self.q_odd,
+            self.q_address,
+        ] {
+            region.assign_fixed(
+                || format!("assign padding row {}", offset),
+                column,
+                offset,
+                || Ok(F::zero()),
+            )?;
+        }
+        proof_type_chip.assign(region, offset, &MptProofType::default())?;
+        node_type_chip.assign(region, offset, &MptNodeType::default())?;
+        self.mpt_table
+            .assign_row(region, offset, false, [F::zero(); 7])?;
+        for column in [
+            self.is_storage,
+            self.old_root,
+            self.new_root,
+            self.key_rlc,
+            self.is_old_empty,
+            self.is_new_empty,
+            self.old_leaf_rlc,
+            self.old_leaf_len,
+            self.new_leaf_rlc,
+            self.new_leaf_len,
+            self.nibble,
+            self.key_rlc_acc,
+            self.key_rlc_mult,
+            self.address_nibble,
+            self.address_acc,
+            self.address_rlc_acc,
+            self.old_ref,
+            self.new_ref,
+            self.old_node_rlc,
+            self.old_node_len,
+            self.new_node_rlc,
+            self.new_node_len,
+            self.is_old_inline,
+            self.is_new_inline,
+            self.node_path_rlc,
+            self.node_path_len,
+            self.old_ext_child,
+            self.new_ext_child,
+        ]
+        .into_iter()
+        .chain(self.is_child)
+        .chain(self.old_children)
+        .chain(self.new_children)
+        .chain(self.is_account_field)
+        .chain(self.account_fields)
+        .chain(self.account_field_lens)
+        {
+            region.assign_advice(
+                || format!("assign padding row {}", offset),
+                column,
+                offset,
+                || Ok(F::zero()),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod mpt_circuit_tests {
+    use eth_types::{Address, Field, ToBigEndian, Word, H256};
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::{MockProver, VerifyFailure},
+        pairing::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+        poly::Rotation,
+    };
+    use rlp::RlpStream;
+    use std::collections::BTreeMap;
+
+    use super::{
+        account_trie_key, storage_trie_key, MptCircuit, MptNodeType, MptPath, MptProof,
+        MptProofType, MptRow, MptUpdate, MptWitnessError, Trie, ADDRESS_NIBBLES, KEY_NIBBLES,
+    };
+    use crate::{
+        table::{KeccakTable, MptTable},
+        util::Expr,
+    };
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        paths: Vec<MptPath<F>>,
+        randomness: F,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = MptCircuit<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let power_of_randomness = {
+                let columns = [(); 31].map(|_| meta.instance_column());
+                let mut power_of_randomness = None;
+
+                meta.create_gate("", |meta| {
+                    power_of_randomness =
+                        Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
+
+                    [0.expr()]
+                });
+
+                power_of_randomness.unwrap()
+            };
+
+            let mpt_table = MptTable::construct(meta);
+            let keccak_table = KeccakTable::construct(meta);
+            MptCircuit::configure(meta, mpt_table, keccak_table, power_of_randomness)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.load_tables(&mut layouter)?;
+            config.keccak_table.dev_load(
+                &mut layouter,
+                &self
+                    .paths
+                    .iter()
+                    .flat_map(|path| path.keccak_inputs.iter().cloned())
+                    .collect::<Vec<_>>(),
+                self.randomness,
+            )?;
+            config.assign(&mut layouter, &self.paths)
+        }
+    }
+
+    fn randomness() -> Fr {
+        Fr::from(0xcafeu64)
+    }
+
+    fn paths(updates: &[MptUpdate]) -> Vec<MptPath<Fr>> {
+        updates
+            .iter()
+            .flat_map(|update| update.paths(randomness()).unwrap())
+            .collect()
+    }
+
+    fn run_circuit<F: Field>(
+        paths: Vec<MptPath<F>>,
+        randomness: F,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let k = 14;
+        let power_of_randomness = (1..32)
+            .map(|exp| vec![randomness.pow(&[exp, 0, 0, 0]); (1 << k) - 64])
+            .collect();
+        let circuit = MyCircuit::<F> { paths, randomness };
+        let prover = MockProver::<F>::run(k, &circuit, power_of_randomness).unwrap();
+        prover.verify()
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct Account {
+        nonce: Word,
+        balance: Word,
+        code_hash: H256,
+        storage: Trie,
+        slots: BTreeMap<Word, Word>,
+    }
+
+    impl Account {
+        fn encode(&self) -> Vec<u8> {
+            let mut stream = RlpStream::new_list(4);
+            stream
+                .append(&self.nonce)
+                .append(&self.balance)
+                .append(&self.storage.root())
+                .append(&self.code_hash);
+            stream.out().to_vec()
+        }
+    }
+
+    /// State trie keeping the accounts along the trie, to generate the proofs
+    /// of the updates.
+    #[derive(Default)]
+    struct State {
+        trie: Trie,
+        accounts: BTreeMap<Address, Account>,
+    }
+
+    impl State {
+        fn update(
+            &mut self,
+            address: Address,
+            proof_type: MptProofType,
+            storage_key: Word,
+            new_value: Word,
+        ) -> MptUpdate {
+            let key = account_trie_key(&address);
+            let old_root = self.trie.root();
+            let old_nodes = self.trie.prove(&key).unwrap();
+
+            let account = self.accounts.entry(address).or_default();
+            let (old_value, storage_proof) = match proof_type {
+                MptProofType::NonceMod => (std::mem::replace(&mut account.nonce, new_value), None),
+                MptProofType::BalanceMod => {
+                    (std::mem::replace(&mut account.balance, new_value), None)
+                }
+                MptProofType::CodeHashMod => (
+                    Word::from_big_endian(
+                        std::mem::replace(&mut account.code_hash, H256(new_value.to_be_bytes()))
+                            .as_bytes(),
+                    ),
+                    None,
+                ),
+                MptProofType::StorageMod => {
+                    let key = storage_trie_key(&storage_key);
+                    let old_root = account.storage.root();
+                    let old_nodes = account.storage.prove(&key).unwrap();
+                    let old_value = account
+                        .slots
+                        .insert(storage_key, new_value)
+                        .unwrap_or_default();
+                    account
+                        .storage
+                        .insert(
+                            &key,
+                            if new_value.is_zero() {
+                                vec![]
+                            } else {
+                                rlp::encode(&new_value).to_vec()
+                            },
+                        )
+                        .unwrap();
+                    let storage_proof = MptProof {
+                        key,
+                        old_root,
+                        new_root: account.storage.root(),
+                        old_nodes,
+                        new_nodes: account.storage.prove(&key).unwrap(),
+                    };
+                    (old_value, Some(storage_proof))
+                }
+            };
+            let encoding = account.encode();
+            self.trie.insert(&key, encoding).unwrap();
+
+            MptUpdate {
+                address,
+                storage_key,
+                proof_type,
+                old_value,
+                new_value,
+                storage_proof,
+                account_proof: MptProof {
+                    key,
+                    old_root,
+                    new_root: self.trie.root(),
+                    old_nodes,
+                    new_nodes: self.trie.prove(&key).unwrap(),
+                },
+            }
+        }
+    }
+
+    /// Returns a state with a few accounts, the first one having a few
+    /// storage slots.
+    fn state() -> (State, [Address; 3]) {
+        let addresses = [0x10u64, 0x20, 0x2000].map(Address::from_low_u64_be);
+        let mut state = State::default();
+        for (idx, address) in addresses.iter().enumerate() {
+            state.update(
+                *address,
+                MptProofType::BalanceMod,
+                Word::zero(),
+                Word::from(1000 + idx),
+            );
+        }
+        for slot in [0x10u64, 0x20, 0x1234] {
+            state.update(
+                addresses[0],
+                MptProofType::StorageMod,
+                Word::from(slot),
+                Word::from(slot + 1),
+            );
+        }
+        (state, addresses)
+    }
+
+    #[test]
+    fn mpt_circuit_valid_updates() {
+        let (mut state, [a, b, c]) = state();
+        let updates = vec![
+            state.update(a, MptProofType::NonceMod, Word::zero(), Word::from(1)),
+            state.update(b, MptProofType::BalanceMod, Word::zero(), Word::from(2000)),
+            state.update(a, MptProofType::StorageMod, Word::from(0x20), Word::MAX),
+            // insertion into an empty child of a branch
+            state.update(a, MptProofType::StorageMod, Word::from(0x30), Word::from(3)),
+            // deletion from a branch keeping other children
+            state.update(a, MptProofType::StorageMod, Word::from(0x10), Word::zero()),
+            state.update(
+                c,
+                MptProofType::CodeHashMod,
+                Word::zero(),
+                Word::from_big_endian(&[0xab; 32]),
+            ),
+        ];
+        assert_eq!(run_circuit(paths(&updates), randomness()), Ok(()));
+    }
+
+    #[test]
+    fn mpt_circuit_empty_storage_trie() {
+        let (mut state, [_, b, _]) = state();
+        let updates = vec![
+            // insertion into the empty storage trie
+            state.update(b, MptProofType::StorageMod, Word::from(1), Word::from(5)),
+            state.update(b, MptProofType::StorageMod, Word::from(1), Word::from(6)),
+            // deletion of the only slot
+            state.update(b, MptProofType::StorageMod, Word::from(1), Word::zero()),
+        ];
+        assert_eq!(run_circuit(paths(&updates), randomness()), Ok(()));
+    }
+
+    #[test]
+    fn mpt_circuit_new_account() {
+        let (mut state, _) = state();
+        let address = Address::from_low_u64_be(0x30);
+        let updates =
+            vec![state.update(address, MptProofType::NonceMod, Word::zero(), Word::from(1))];
+        assert_eq!(run_circuit(paths(&updates), randomness()), Ok(()));
+    }
+
+    #[test]
+    fn mpt_circuit_unsupported_update() {
+        let (mut state, [a, _, _]) = state();
+        // inserting next to the leaf of slot 0x20 splits it
+        let update = state.update(a, MptProofType::StorageMod, Word::from(0x1238), Word::one());
+        assert_eq!(
+            update.paths(randomness()).unwrap_err(),
+            MptWitnessError::UnsupportedUpdate
+        );
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_value() {
+        let (mut state, [a, _, _]) = state();
+        let mut update = state.update(a, MptProofType::StorageMod, Word::from(0x20), Word::one());
+        update.old_value = Word::from(7);
+        assert_eq!(
+            update.paths(randomness()).unwrap_err(),
+            MptWitnessError::ValueMismatch
+        );
+    }
+
+    #[test]
+    fn mpt_circuit_unhashed_key() {
+        let (mut state, [a, _, _]) = state();
+        let mut update = state.update(a, MptProofType::NonceMod, Word::zero(), Word::one());
+        update.account_proof.key = H256::from(a);
+        assert_eq!(
+            update.paths(randomness()).unwrap_err(),
+            MptWitnessError::InvalidProof
+        );
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_key_preimage() {
+        let (mut state, [a, b, _]) = state();
+        let updates =
+            vec![state.update(a, MptProofType::StorageMod, Word::from(0x20), Word::one())];
+        let mut paths = paths(&updates);
+        // the key of the account path isn't the hash of another address
+        paths[1].keccak_inputs[0] = b.as_bytes().to_vec();
+        assert!(run_circuit(paths.clone(), randomness()).is_err());
+        // nor the key of the storage path the hash of another storage key
+        paths[0].keccak_inputs[0] = Word::from(0x10).to_be_bytes().to_vec();
+        paths[1].keccak_inputs[0] = a.as_bytes().to_vec();
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_sibling() {
+        let (mut state, [a, _, _]) = state();
+        let updates =
+            vec![state.update(a, MptProofType::StorageMod, Word::from(0x20), Word::one())];
+        let mut paths = paths(&updates);
+        let row = paths[0]
+            .rows
+            .iter_mut()
+            .find(|row| row.node_type == MptNodeType::Branch)
+            .unwrap();
+        let sibling = (row.nibble as usize + 1) % 16;
+        row.new_children[sibling] += Fr::from(1);
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_branch_child() {
+        let (mut state, [a, _, _]) = state();
+        let updates =
+            vec![state.update(a, MptProofType::StorageMod, Word::from(0x20), Word::one())];
+        let mut paths = paths(&updates);
+        let row = paths[0]
+            .rows
+            .iter_mut()
+            .find(|row| row.node_type == MptNodeType::Branch)
+            .unwrap();
+        // an unmodified sibling which isn't the child in the encoding of the
+        // branch
+        let sibling = (row.nibble as usize + 1) % 16;
+        row.old_children[sibling] += Fr::from(1);
+        row.new_children[sibling] += Fr::from(1);
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_leaf_value() {
+        let (mut state, [a, _, _]) = state();
+        let updates =
+            vec![state.update(a, MptProofType::StorageMod, Word::from(0x20), Word::one())];
+        let mut paths = paths(&updates);
+        paths[0].new_value += Fr::from(1);
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+
+    #[test]
+    fn mpt_circuit_missing_node_hash() {
+        let (mut state, [a, _, _]) = state();
+        let updates =
+            vec![state.update(a, MptProofType::StorageMod, Word::from(0x20), Word::one())];
+        let mut paths = paths(&updates);
+        paths[1].keccak_inputs.clear();
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_storage_root() {
+        let (mut state, [a, _, _]) = state();
+        let updates =
+            vec![state.update(a, MptProofType::StorageMod, Word::from(0x20), Word::one())];
+        let mut paths = paths(&updates);
+        paths[1].new_value += Fr::from(1);
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_account_address() {
+        let (mut state, [a, _, _]) = state();
+        let updates =
+            vec![state.update(a, MptProofType::StorageMod, Word::from(0x20), Word::one())];
+        let mut paths = paths(&updates);
+        paths[1].address += Fr::from(1);
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_state_root_chain() {
+        let (mut state, [a, b, _]) = state();
+        let updates = vec![
+            state.update(a, MptProofType::NonceMod, Word::zero(), Word::from(1)),
+            state.update(b, MptProofType::NonceMod, Word::zero(), Word::from(1)),
+        ];
+        // skip the second update
+        let mut paths = paths(&updates[..1]);
+        paths.extend(paths.clone());
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+
+    /// Asserts that the circuit rejects the paths of `updates` after each of
+    /// the `mutations`, which corrupt a single value of one of the paths.
+    fn assert_mutations_rejected(
+        updates: &[MptUpdate],
+        mutations: &[(&str, fn(&mut MptPath<Fr>))],
+    ) {
+        let valid = paths(updates);
+        assert_eq!(run_circuit(valid.clone(), randomness()), Ok(()));
+        for idx in 0..valid.len() {
+            for (name, mutation) in mutations {
+                let mut paths = valid.clone();
+                mutation(&mut paths[idx]);
+                assert!(
+                    run_circuit(paths, randomness()).is_err(),
+                    "corrupted {} of path {} is accepted",
+                    name,
+                    idx
+                );
+            }
+        }
+    }
+
+    /// Returns the updates of a storage slot and of an account field, whose
+    /// paths are the storage path and the account path of the first update
+    /// followed by the account path of the second one.
+    fn mutated_updates() -> Vec<MptUpdate> {
+        let (mut state, [a, b, _]) = state();
+        vec![
+            state.update(a, MptProofType::StorageMod, Word::from(0x20), Word::from(5)),
+            state.update(b, MptProofType::BalanceMod, Word::zero(), Word::from(7)),
+        ]
+    }
+
+    fn shift_nibble(nibble: &mut u8) {
+        *nibble = (*nibble + 1) % 16;
+    }
+
+    fn first_branch(path: &mut MptPath<Fr>) -> &mut MptRow<Fr> {
+        path.rows
+            .iter_mut()
+            .find(|row| row.node_type == MptNodeType::Branch)
+            .unwrap()
+    }
+
+    #[test]
+    fn mpt_circuit_corrupted_nibbles() {
+        assert_mutations_rejected(
+            &mutated_updates(),
+            &[
+                ("nibble at the root", |path| {
+                    shift_nibble(&mut path.rows[0].nibble)
+                }),
+                ("nibble below the root", |path| {
+                    shift_nibble(&mut path.rows[1].nibble)
+                }),
+                ("last nibble", |path| {
+                    shift_nibble(&mut path.rows[KEY_NIBBLES - 1].nibble)
+                }),
+                ("first address nibble", |path| {
+                    shift_nibble(&mut path.rows[0].address_nibble)
+                }),
+                ("last address nibble", |path| {
+                    shift_nibble(&mut path.rows[ADDRESS_NIBBLES - 1].address_nibble)
+                }),
+            ],
+        );
+    }
+
+    #[test]
+    fn mpt_circuit_corrupted_node_hashes() {
+        assert_mutations_rejected(
+            &mutated_updates(),
+            &[
+                ("old root", |path| path.old_root += Fr::one()),
+                ("new root", |path| path.new_root += Fr::one()),
+                ("reference to the old root node", |path| {
+                    path.rows[0].old_ref += Fr::one()
+                }),
+                ("reference to the new root node", |path| {
+                    path.rows[0].new_ref += Fr::one()
+                }),
+                ("reference to the new leaf", |path| {
+                    path.rows[KEY_NIBBLES - 1].new_ref += Fr::one()
+                }),
+                ("selected old child of a branch", |path| {
+                    let row = first_branch(path);
+                    row.old_children[row.nibble as usize] += Fr::one();
+                }),
+                ("selected new child of a branch", |path| {
+                    let row = first_branch(path);
+                    row.new_children[row.nibble as usize] += Fr::one();
+                }),
+                ("encoding of the new root node", |path| {
+                    path.rows[0].new_node_rlc += Fr::one()
+                }),
+                ("hashed encoding of the new leaf", |path| {
+                    let idx = path.keccak_inputs.len() - 1;
+                    path.keccak_inputs[idx][1] ^= 1;
+                }),
+            ],
+        );
+    }
+
+    #[test]
+    fn mpt_circuit_corrupted_values() {
+        assert_mutations_rejected(
+            &mutated_updates(),
+            &[
+                ("old value", |path| path.old_value += Fr::one()),
+                ("new value", |path| path.new_value += Fr::one()),
+                ("old leaf value", |path| path.old_leaf_rlc += Fr::one()),
+                ("new leaf value", |path| path.new_leaf_rlc += Fr::one()),
+                ("key", |path| path.key_rlc += Fr::one()),
+            ],
+        );
+    }
+
+    #[test]
+    fn mpt_circuit_corrupted_account_fields() {
+        let updates = mutated_updates();
+        let mut paths = paths(&updates);
+        // the nonce isn't modified by both updates, and the balance by the
+        // storage update
+        for (idx, field) in [(1, 0), (1, 1), (2, 0)] {
+            let mut paths = paths.clone();
+            paths[idx].account_fields[field] += Fr::one();
+            assert!(run_circuit(paths, randomness()).is_err());
+        }
+        paths[2].account_field_lens[0] += 1;
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+}
@@ -0,0 +1,1209 @@
+//! RLP decoder of the trie nodes and of the leaf values of the MPT circuit.
+//!
+//! Every encoding is laid out as a stream taking one row per byte, which is
+//! either a list, made of its header followed by its items, or a single
+//! string.  An item is its prefix byte, followed by the bytes of its length
+//! for a long string and by its content bytes.  A byte below 0x80 is an item
+//! by itself, and a list item (a node inlined into its parent) is kept as an
+//! opaque item whose content is its whole encoding.
+//!
+//! The lengths given by the prefixes are constrained to add up to the length
+//! of the stream, and every item is exposed at its last row, indexed by the
+//! RLC and the length of its stream and by its position in the list, along
+//! with the accumulations of its content:
+//!   - `item_rlc`, the RLC with the first byte at the lowest power, which is
+//!     the reference to a child node,
+//!   - `item_horner`, the RLC with the first byte at the highest power, which
+//!     is the RLC of a word given by its big-endian bytes, and the RLC of an
+//!     encoding as a stream,
+//!   - `item_value`, the big-endian number of the content,
+//!   - the nibbles of a hex-prefix encoded path.
+
+use eth_types::Field;
+use gadgets::util::{not, sum, Expr};
+use halo2_proofs::{
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use super::witness::MptWitnessError;
+use crate::evm_circuit::util::constraint_builder::BaseConstraintBuilder;
+
+/// Type of an RLP prefix byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RlpPrefixType {
+    /// A byte below 0x80, which is its own content.
+    SingleByte = 1,
+    /// A string of less than 56 bytes.
+    ShortString,
+    /// A string whose length takes the next byte.
+    LongString,
+    /// A list of less than 56 bytes.
+    ShortList,
+    /// A list whose length takes the next 1 or 2 bytes.
+    LongList,
+}
+
+impl RlpPrefixType {
+    /// Returns the type of a prefix byte along with the length of the content
+    /// for a short string or list, or the number of bytes of the length for a
+    /// long one.  The strings longer than 255 bytes and the lists longer than
+    /// 65535 bytes aren't supported.
+    pub fn decode(byte: u8) -> Option<(Self, usize)> {
+        match byte {
+            0x00..=0x7f => Some((Self::SingleByte, 0)),
+            0x80..=0xb7 => Some((Self::ShortString, (byte - 0x80) as usize)),
+            0xb8 => Some((Self::LongString, 1)),
+            0xc0..=0xf7 => Some((Self::ShortList, (byte - 0xc0) as usize)),
+            0xf8..=0xf9 => Some((Self::LongList, (byte - 0xf7) as usize)),
+            _ => None,
+        }
+    }
+}
+
+/// A row of a stream of the RLP decoder.
+#[derive(Clone, Debug, Default)]
+pub struct RlpDecoderRow<F> {
+    /// Whether the row is the first byte of the stream.
+    pub is_first: bool,
+    /// Whether the row is the last byte of the stream.
+    pub is_last: bool,
+    /// Whether the stream is a list.
+    pub is_list: bool,
+    /// RLC of the stream, with the first byte at the highest power.
+    pub stream_rlc: F,
+    /// Number of bytes of the stream.
+    pub stream_len: usize,
+    /// RLC of the stream up to the row.
+    pub stream_rlc_acc: F,
+    /// Number of bytes of the stream after the row.
+    pub stream_bytes_left: usize,
+    /// The byte of the row.
+    pub byte: u8,
+    /// Whether the row belongs to the header of the list.
+    pub is_header: bool,
+    /// Whether the byte is a prefix.
+    pub is_prefix: bool,
+    /// Whether the byte belongs to the length of a long string or list.
+    pub is_len: bool,
+    /// Whether the byte belongs to the content of an item.
+    pub is_content: bool,
+    /// The type of the prefix of the row.
+    pub prefix_type: Option<RlpPrefixType>,
+    /// The length of a short prefix, or the number of bytes of the length of a
+    /// long one.
+    pub prefix_len: usize,
+    /// Number of bytes of the length of a long prefix after the row.
+    pub len_bytes_left: usize,
+    /// The length of a long prefix up to the row.
+    pub len_acc: usize,
+    /// Index of the item in the list.
+    pub item_index: usize,
+    /// Whether the row is the last one of the item.
+    pub is_item_end: bool,
+    /// Number of content bytes of the item after the row.
+    pub item_bytes_left: usize,
+    /// RLC of the content of the item up to the row, with the first byte at
+    /// the lowest power.
+    pub item_rlc: F,
+    /// Multiplier of the next content byte in `item_rlc`.
+    pub item_mult: F,
+    /// RLC of the content of the item up to the row, with the first byte at
+    /// the highest power.
+    pub item_horner: F,
+    /// Big-endian number of the content of the item up to the row.
+    pub item_value: F,
+    /// Number of content bytes of the item up to the row.
+    pub item_len: usize,
+    /// Whether the item is a hex-prefix encoded path.
+    pub is_hp: bool,
+    /// Whether the hex-prefix encoded path is the path of a leaf.
+    pub hp_is_leaf: bool,
+    /// Whether the hex-prefix encoded path has an odd number of nibbles.
+    pub hp_is_odd: bool,
+    /// RLC of the nibbles of the path up to the row, with the first nibble at
+    /// the highest power.
+    pub nibbles_rlc: F,
+    /// Number of nibbles of the path up to the row.
+    pub nibbles_len: usize,
+}
+
+/// The expressions of an item of the decoder, at its last row.
+#[derive(Clone, Debug)]
+pub struct RlpItemQueries<F> {
+    /// Whether the row is the last one of an item.
+    pub enabled: Expression<F>,
+    /// RLC of the stream of the item.
+    pub stream_rlc: Expression<F>,
+    /// Number of bytes of the stream of the item.
+    pub stream_len: Expression<F>,
+    /// Index of the item in the list.
+    pub index: Expression<F>,
+    /// Whether the item is the last one of the stream.
+    pub is_last: Expression<F>,
+    /// RLC of the content, with the first byte at the lowest power.
+    pub rlc: Expression<F>,
+    /// RLC of the content, with the first byte at the highest power.
+    pub horner: Expression<F>,
+    /// Big-endian number of the content.
+    pub value: Expression<F>,
+    /// Number of content bytes.
+    pub len: Expression<F>,
+    /// Whether the item is a hex-prefix encoded path.
+    pub is_hp: Expression<F>,
+    /// Whether the path is the path of a leaf.
+    pub hp_is_leaf: Expression<F>,
+    /// RLC of the nibbles of the path.
+    pub nibbles_rlc: Expression<F>,
+    /// Number of nibbles of the path.
+    pub nibbles_len: Expression<F>,
+}
+
+/// Config of the RLP decoder of the MPT circuit.
+#[derive(Clone, Copy, Debug)]
+pub struct RlpDecoderConfig<F> {
+    /// Whether the row belongs to a stream.
+    pub q_enable: Column<Fixed>,
+    /// Whether the row is the first byte of a stream.
+    pub is_first: Column<Advice>,
+    /// Whether the row is the last byte of a stream.
+    pub is_last: Column<Advice>,
+    /// Whether the stream is a list.
+    pub is_list: Column<Advice>,
+    /// RLC of the stream.
+    pub stream_rlc: Column<Advice>,
+    /// Number of bytes of the stream.
+    pub stream_len: Column<Advice>,
+    /// RLC of the stream up to the row.
+    pub stream_rlc_acc: Column<Advice>,
+    /// Number of bytes of the stream after the row.
+    pub stream_bytes_left: Column<Advice>,
+    /// The byte of the row.
+    pub byte: Column<Advice>,
+    /// The high nibble of the byte.
+    pub byte_hi: Column<Advice>,
+    /// The low nibble of the byte.
+    pub byte_lo: Column<Advice>,
+    /// Whether the row belongs to the header of the list.
+    pub is_header: Column<Advice>,
+    /// Whether the byte is a prefix.
+    pub is_prefix: Column<Advice>,
+    /// Whether the byte belongs to the length of a long string or list.
+    pub is_len: Column<Advice>,
+    /// Whether the byte belongs to the content of an item.
+    pub is_content: Column<Advice>,
+    /// Whether the prefix is of each [`RlpPrefixType`].
+    pub prefix_types: [Column<Advice>; 5],
+    /// The length of a short prefix, or the number of bytes of the length of a
+    /// long one.
+    pub prefix_len: Column<Advice>,
+    /// Number of bytes of the length of a long prefix after the row.
+    pub len_bytes_left: Column<Advice>,
+    /// The length of a long prefix up to the row.
+    pub len_acc: Column<Advice>,
+    /// Index of the item in the list.
+    pub item_index: Column<Advice>,
+    /// Whether the row is the last one of the item.
+    pub is_item_end: Column<Advice>,
+    /// Number of content bytes of the item after the row.
+    pub item_bytes_left: Column<Advice>,
+    /// RLC of the content of the item up to the row, with the first byte at
+    /// the lowest power.
+    pub item_rlc: Column<Advice>,
+    /// Multiplier of the next content byte in `item_rlc`.
+    pub item_mult: Column<Advice>,
+    /// RLC of the content of the item up to the row, with the first byte at
+    /// the highest power.
+    pub item_horner: Column<Advice>,
+    /// Big-endian number of the content of the item up to the row.
+    pub item_value: Column<Advice>,
+    /// Number of content bytes of the item up to the row.
+    pub item_len: Column<Advice>,
+    /// Whether the item is a hex-prefix encoded path.
+    pub is_hp: Column<Advice>,
+    /// Whether the hex-prefix encoded path is the path of a leaf.
+    pub hp_is_leaf: Column<Advice>,
+    /// Whether the hex-prefix encoded path has an odd number of nibbles.
+    pub hp_is_odd: Column<Advice>,
+    /// RLC of the nibbles of the path up to the row.
+    pub nibbles_rlc: Column<Advice>,
+    /// Number of nibbles of the path up to the row.
+    pub nibbles_len: Column<Advice>,
+    /// Fixed table of the valid prefix bytes, holding the byte, its type and
+    /// its length, along with a row of zeros.
+    pub prefix_table: [Column<Fixed>; 3],
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> RlpDecoderConfig<F> {
+    /// Configure the RLP decoder, range checking the nibbles of the bytes with
+    /// `u4_table`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        u4_table: Column<Fixed>,
+        randomness: Expression<F>,
+    ) -> Self {
+        let q_enable = meta.fixed_column();
+        let is_first = meta.advice_column();
+        let is_last = meta.advice_column();
+        let is_list = meta.advice_column();
+        let stream_rlc = meta.advice_column();
+        let stream_len = meta.advice_column();
+        let stream_rlc_acc = meta.advice_column();
+        let stream_bytes_left = meta.advice_column();
+        let byte = meta.advice_column();
+        let byte_hi = meta.advice_column();
+        let byte_lo = meta.advice_column();
+        let is_header = meta.advice_column();
+        let is_prefix = meta.advice_column();
+        let is_len = meta.advice_column();
+        let is_content = meta.advice_column();
+        let prefix_types = [(); 5].map(|_| meta.advice_column());
+        let prefix_len = meta.advice_column();
+        let len_bytes_left = meta.advice_column();
+        let len_acc = meta.advice_column();
+        let item_index = meta.advice_column();
+        let is_item_end = meta.advice_column();
+        let item_bytes_left = meta.advice_column();
+        let item_rlc = meta.advice_column();
+        let item_mult = meta.advice_column();
+        let item_horner = meta.advice_column();
+        let item_value = meta.advice_column();
+        let item_len = meta.advice_column();
+        let is_hp = meta.advice_column();
+        let hp_is_leaf = meta.advice_column();
+        let hp_is_odd = meta.advice_column();
+        let nibbles_rlc = meta.advice_column();
+        let nibbles_len = meta.advice_column();
+        let prefix_table = [(); 3].map(|_| meta.fixed_column());
+
+        let cur = |meta: &mut VirtualCells<F>, column| meta.query_advice(column, Rotation::cur());
+        let prev = |meta: &mut VirtualCells<F>, column| meta.query_advice(column, Rotation::prev());
+        let next = |meta: &mut VirtualCells<F>, column| meta.query_advice(column, Rotation::next());
+
+        meta.create_gate("rlp decoder stream", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let is_first_expr = cur(meta, is_first);
+            let is_last_expr = cur(meta, is_last);
+            let byte_expr = cur(meta, byte);
+            let stream_rlc_acc_expr = cur(meta, stream_rlc_acc);
+            let stream_bytes_left_expr = cur(meta, stream_bytes_left);
+            let is_header_expr = cur(meta, is_header);
+            let is_prefix_expr = cur(meta, is_prefix);
+
+            for column in [
+                is_first,
+                is_last,
+                is_list,
+                is_header,
+                is_prefix,
+                is_len,
+                is_content,
+                is_item_end,
+                is_hp,
+                hp_is_leaf,
+                hp_is_odd,
+            ]
+            .into_iter()
+            .chain(prefix_types)
+            {
+                cb.require_boolean("rlp decoder flag is boolean", cur(meta, column));
+            }
+            cb.require_equal(
+                "byte is a prefix, a length or a content byte",
+                is_prefix_expr.clone() + cur(meta, is_len) + cur(meta, is_content),
+                1.expr(),
+            );
+            cb.require_equal(
+                "prefix has a single type",
+                sum::expr(prefix_types.map(|column| cur(meta, column))),
+                is_prefix_expr.clone(),
+            );
+            cb.require_equal(
+                "byte == 16 * byte_hi + byte_lo",
+                byte_expr.clone(),
+                16.expr() * cur(meta, byte_hi) + cur(meta, byte_lo),
+            );
+
+            cb.condition(is_first_expr.clone(), |cb| {
+                cb.require_equal(
+                    "stream_rlc_acc == byte at the first byte",
+                    stream_rlc_acc_expr.clone(),
+                    byte_expr.clone(),
+                );
+                cb.require_equal(
+                    "stream_bytes_left == stream_len - 1 at the first byte",
+                    stream_bytes_left_expr.clone(),
+                    cur(meta, stream_len) - 1.expr(),
+                );
+                cb.require_equal(
+                    "stream starts with a prefix",
+                    is_prefix_expr.clone(),
+                    1.expr(),
+                );
+                cb.require_equal(
+                    "stream starts with the header of a list or with a string",
+                    is_header_expr.clone(),
+                    cur(meta, is_list),
+                );
+            });
+            cb.condition(not::expr(is_first_expr), |cb| {
+                for column in [is_list, stream_rlc, stream_len] {
+                    cb.require_equal(
+                        "stream value is the same for all its bytes",
+                        cur(meta, column),
+                        prev(meta, column),
+                    );
+                }
+                cb.require_equal(
+                    "stream_rlc_acc == stream_rlc_acc_prev * r + byte",
+                    stream_rlc_acc_expr.clone(),
+                    prev(meta, stream_rlc_acc) * randomness.clone() + byte_expr,
+                );
+                cb.require_equal(
+                    "stream_bytes_left == stream_bytes_left_prev - 1",
+                    stream_bytes_left_expr.clone(),
+                    prev(meta, stream_bytes_left) - 1.expr(),
+                );
+                cb.require_zero(
+                    "header prefix is the first byte of the stream",
+                    is_header_expr * is_prefix_expr,
+                );
+            });
+            cb.condition(
+                not::expr(meta.query_fixed(q_enable, Rotation::prev())),
+                |cb| {
+                    cb.require_equal(
+                        "byte following no stream is the first byte of a stream",
+                        cur(meta, is_first),
+                        1.expr(),
+                    );
+                },
+            );
+
+            let q_next = meta.query_fixed(q_enable, Rotation::next());
+            let is_first_next = next(meta, is_first);
+            cb.condition(is_last_expr.clone(), |cb| {
+                cb.require_zero("stream ends after stream_len bytes", stream_bytes_left_expr);
+                cb.require_equal(
+                    "stream_rlc is the RLC of all the bytes",
+                    cur(meta, stream_rlc),
+                    stream_rlc_acc_expr,
+                );
+                cb.require_equal(
+                    "stream ends at the end of an item",
+                    cur(meta, is_item_end),
+                    1.expr(),
+                );
+                cb.require_zero(
+                    "stream is followed by another stream or by no stream",
+                    q_next.clone() * not::expr(is_first_next.clone()),
+                );
+            });
+            cb.condition(not::expr(is_last_expr), |cb| {
+                cb.require_equal("stream continues on the next row", q_next, 1.expr());
+                cb.require_zero("stream continues on the next row", is_first_next);
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("rlp decoder lengths", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let is_header_expr = cur(meta, is_header);
+            let is_prefix_expr = cur(meta, is_prefix);
+            let is_len_expr = cur(meta, is_len);
+            let is_item_end_expr = cur(meta, is_item_end);
+            let [_, _, is_long_string, is_short_list, is_long_list] =
+                prefix_types.map(|column| cur(meta, column));
+            let prefix_len_expr = cur(meta, prefix_len);
+            let len_bytes_left_expr = cur(meta, len_bytes_left);
+            let len_acc_expr = cur(meta, len_acc);
+            let is_len_end = is_len_expr.clone() * not::expr(len_bytes_left_expr.clone());
+
+            cb.condition(is_header_expr.clone() * is_prefix_expr.clone(), |cb| {
+                cb.require_equal(
+                    "header is the prefix of a list",
+                    is_short_list.clone() + is_long_list.clone(),
+                    1.expr(),
+                );
+            });
+            cb.condition(not::expr(is_header_expr.clone()), |cb| {
+                cb.require_zero("item isn't a long list", is_long_list.clone());
+            });
+            cb.condition(is_header_expr.clone() * is_short_list.clone(), |cb| {
+                cb.require_equal(
+                    "stream_bytes_left is the length of a short list",
+                    cur(meta, stream_bytes_left),
+                    prefix_len_expr.clone(),
+                );
+            });
+
+            // The length of a long string or list takes the prefix_len bytes
+            // following the prefix, with the most significant byte first.
+            cb.condition(is_long_string.clone() + is_long_list.clone(), |cb| {
+                cb.require_equal(
+                    "len_bytes_left == prefix_len at a long prefix",
+                    len_bytes_left_expr.clone(),
+                    prefix_len_expr,
+                );
+                cb.require_zero("len_acc == 0 at a long prefix", len_acc_expr.clone());
+            });
+            cb.condition(is_len_expr.clone(), |cb| {
+                cb.require_boolean("length takes at most 2 bytes", len_bytes_left_expr.clone());
+                cb.require_equal(
+                    "len_bytes_left == len_bytes_left_prev - 1",
+                    len_bytes_left_expr.clone(),
+                    prev(meta, len_bytes_left) - 1.expr(),
+                );
+                cb.require_equal(
+                    "len_acc == len_acc_prev * 256 + byte",
+                    len_acc_expr.clone(),
+                    prev(meta, len_acc) * 256.expr() + cur(meta, byte),
+                );
+            });
+            cb.condition(is_header_expr.clone() * is_len_end.clone(), |cb| {
+                cb.require_equal(
+                    "stream_bytes_left is the length of a long list",
+                    cur(meta, stream_bytes_left),
+                    len_acc_expr,
+                );
+            });
+
+            // The kind of the next byte of the stream.
+            let is_header_next = next(meta, is_header);
+            let is_prefix_next = next(meta, is_prefix);
+            let is_len_next = next(meta, is_len);
+            let is_content_next = next(meta, is_content);
+            cb.condition(not::expr(cur(meta, is_last)), |cb| {
+                cb.condition(
+                    is_long_string.clone() + is_long_list + is_len_expr * len_bytes_left_expr,
+                    |cb| {
+                        cb.require_equal(
+                            "long prefix is followed by its length",
+                            is_len_next,
+                            1.expr(),
+                        );
+                        cb.require_equal(
+                            "length is in the header or the item of its prefix",
+                            is_header_next.clone(),
+                            is_header_expr.clone(),
+                        );
+                    },
+                );
+                cb.condition(
+                    is_header_expr.clone() * (is_short_list + is_len_end.clone()),
+                    |cb| {
+                        cb.require_equal(
+                            "header is followed by the first item",
+                            is_prefix_next.clone(),
+                            1.expr(),
+                        );
+                        cb.require_zero(
+                            "header is followed by the first item",
+                            is_header_next.clone(),
+                        );
+                    },
+                );
+                cb.condition(not::expr(is_header_expr.clone()) * is_len_end, |cb| {
+                    cb.require_equal(
+                        "length of a long string is followed by its content",
+                        is_content_next.clone(),
+                        1.expr(),
+                    );
+                });
+                cb.condition(is_item_end_expr.clone(), |cb| {
+                    cb.require_equal(
+                        "item is followed by the next item",
+                        is_prefix_next,
+                        1.expr(),
+                    );
+                    cb.require_zero("item is followed by the next item", is_header_next);
+                });
+                cb.condition(
+                    not::expr(is_header_expr)
+                        * (is_prefix_expr * not::expr(is_long_string) + cur(meta, is_content))
+                        * not::expr(is_item_end_expr),
+                    |cb| {
+                        cb.require_equal(
+                            "item continues with its content",
+                            is_content_next,
+                            1.expr(),
+                        );
+                    },
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("rlp decoder items", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let byte_expr = cur(meta, byte);
+            let is_header_expr = cur(meta, is_header);
+            let is_len_expr = cur(meta, is_len);
+            let is_content_expr = cur(meta, is_content);
+            let is_content_prev = prev(meta, is_content);
+            let is_item_prefix = not::expr(is_header_expr.clone()) * cur(meta, is_prefix);
+            let is_item_len = not::expr(is_header_expr.clone()) * is_len_expr.clone();
+            let is_item_end_expr = cur(meta, is_item_end);
+            let [is_single_byte, is_short_string, is_long_string, is_short_list, _] =
+                prefix_types.map(|column| cur(meta, column));
+            let item_bytes_left_expr = cur(meta, item_bytes_left);
+            let is_hp_expr = cur(meta, is_hp);
+
+            cb.condition(is_item_end_expr.clone(), |cb| {
+                cb.require_zero("item ends after its content", item_bytes_left_expr.clone());
+            });
+            cb.require_zero(
+                "item doesn't end at a header or a length byte",
+                (is_header_expr.clone() + is_len_expr.clone()) * is_item_end_expr.clone(),
+            );
+            cb.condition(is_header_expr.clone(), |cb| {
+                cb.require_zero("item_index is 0 in the header", cur(meta, item_index));
+            });
+
+            // The content of a single byte or of a list item starts with its
+            // prefix.
+            cb.condition(is_item_prefix.clone(), |cb| {
+                let is_first_item =
+                    cur(meta, is_first) + not::expr(cur(meta, is_first)) * prev(meta, is_header);
+                cb.require_equal(
+                    "item_index counts the items of the list",
+                    cur(meta, item_index),
+                    not::expr(is_first_item) * (prev(meta, item_index) + 1.expr()),
+                );
+                cb.require_equal(
+                    "item_bytes_left is the length of a short string or list",
+                    item_bytes_left_expr.clone(),
+                    (is_short_string.clone() + is_short_list.clone()) * cur(meta, prefix_len),
+                );
+                let starts_content = is_single_byte.clone() + is_short_list.clone();
+                for column in [item_rlc, item_horner, item_value] {
+                    cb.require_equal(
+                        "item accumulation starts with the prefix of a single byte or a list",
+                        cur(meta, column),
+                        starts_content.clone() * byte_expr.clone(),
+                    );
+                }
+                cb.require_equal(
+                    "item_len starts with the prefix of a single byte or a list",
+                    cur(meta, item_len),
+                    starts_content,
+                );
+                cb.require_equal(
+                    "item_mult == r after the prefix of a list and 1 otherwise",
+                    cur(meta, item_mult),
+                    1.expr() + is_short_list.clone() * (randomness.clone() - 1.expr()),
+                );
+                cb.condition(is_single_byte.clone(), |cb| {
+                    cb.require_equal(
+                        "single byte is an item by itself",
+                        is_item_end_expr.clone(),
+                        1.expr(),
+                    );
+                });
+                cb.condition(is_long_string.clone(), |cb| {
+                    cb.require_zero("long string has a length", is_item_end_expr.clone());
+                });
+            });
+            cb.condition(
+                not::expr(is_header_expr) * (is_len_expr + is_content_expr.clone()),
+                |cb| {
+                    for column in [item_index, is_hp, hp_is_leaf, hp_is_odd] {
+                        cb.require_equal(
+                            "item value is the same for all its bytes",
+                            cur(meta, column),
+                            prev(meta, column),
+                        );
+                    }
+                },
+            );
+            cb.condition(is_item_len, |cb| {
+                for column in [item_rlc, item_mult, item_horner, item_value, item_len] {
+                    cb.require_equal(
+                        "length isn't accumulated into the content",
+                        cur(meta, column),
+                        prev(meta, column),
+                    );
+                }
+                cb.require_equal(
+                    "item_bytes_left is the length of a long string",
+                    item_bytes_left_expr.clone(),
+                    cur(meta, len_acc),
+                );
+            });
+            cb.condition(is_content_expr.clone(), |cb| {
+                let item_mult_prev = prev(meta, item_mult);
+                cb.require_equal(
+                    "item_bytes_left == item_bytes_left_prev - 1",
+                    item_bytes_left_expr,
+                    prev(meta, item_bytes_left) - 1.expr(),
+                );
+                cb.require_equal(
+                    "item_rlc == item_rlc_prev + byte * item_mult_prev",
+                    cur(meta, item_rlc),
+                    prev(meta, item_rlc) + byte_expr.clone() * item_mult_prev.clone(),
+                );
+                cb.require_equal(
+                    "item_mult == item_mult_prev * r",
+                    cur(meta, item_mult),
+                    item_mult_prev * randomness.clone(),
+                );
+                cb.require_equal(
+                    "item_horner == item_horner_prev * r + byte",
+                    cur(meta, item_horner),
+                    prev(meta, item_horner) * randomness.clone() + byte_expr.clone(),
+                );
+                cb.require_equal(
+                    "item_value == item_value_prev * 256 + byte",
+                    cur(meta, item_value),
+                    prev(meta, item_value) * 256.expr() + byte_expr,
+                );
+                cb.require_equal(
+                    "item_len == item_len_prev + 1",
+                    cur(meta, item_len),
+                    prev(meta, item_len) + 1.expr(),
+                );
+            });
+
+            // The first byte of a hex-prefix encoded path holds its flags in
+            // the high nibble, and its first nibble in the low nibble for an
+            // odd number of nibbles.
+            let byte_hi_expr = cur(meta, byte_hi);
+            let byte_lo_expr = cur(meta, byte_lo);
+            let hp_is_odd_expr = cur(meta, hp_is_odd);
+            cb.condition(is_item_prefix * is_hp_expr.clone(), |cb| {
+                cb.require_zero(
+                    "hex-prefix path is a short string",
+                    is_long_string + is_short_list,
+                );
+                cb.require_zero(
+                    "hex-prefix path isn't empty",
+                    is_short_string * is_item_end_expr,
+                );
+            });
+            let is_first_content =
+                is_single_byte + is_content_expr.clone() * not::expr(is_content_prev.clone());
+            cb.condition(is_hp_expr * is_first_content, |cb| {
+                cb.require_equal(
+                    "high nibble of the first byte is the hex-prefix flag",
+                    byte_hi_expr.clone(),
+                    2.expr() * cur(meta, hp_is_leaf) + hp_is_odd_expr.clone(),
+                );
+                cb.require_zero(
+                    "low nibble of the first byte is 0 for an even path",
+                    not::expr(hp_is_odd_expr.clone()) * byte_lo_expr.clone(),
+                );
+                cb.require_equal(
+                    "nibbles_rlc starts with the low nibble for an odd path",
+                    cur(meta, nibbles_rlc),
+                    hp_is_odd_expr.clone() * byte_lo_expr.clone(),
+                );
+                cb.require_equal(
+                    "nibbles_len starts with 1 for an odd path",
+                    cur(meta, nibbles_len),
+                    hp_is_odd_expr,
+                );
+            });
+            cb.condition(is_content_expr * is_content_prev, |cb| {
+                cb.require_equal(
+                    "nibbles_rlc == nibbles_rlc_prev * r^2 + byte_hi * r + byte_lo",
+                    cur(meta, nibbles_rlc),
+                    (prev(meta, nibbles_rlc) * randomness.clone() + byte_hi_expr)
+                        * randomness.clone()
+                        + byte_lo_expr,
+                );
+                cb.require_equal(
+                    "nibbles_len == nibbles_len_prev + 2",
+                    cur(meta, nibbles_len),
+                    prev(meta, nibbles_len) + 2.expr(),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        for (name, column) in [
+            ("rlp decoder byte_hi fits into u4", byte_hi),
+            ("rlp decoder byte_lo fits into u4", byte_lo),
+        ] {
+            meta.lookup_any(name, |meta| {
+                let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+                let nibble = meta.query_advice(column, Rotation::cur());
+                let u4_table = meta.query_fixed(u4_table, Rotation::cur());
+                vec![(q_enable * nibble, u4_table)]
+            });
+        }
+        meta.lookup_any("rlp decoder prefix is valid", |meta| {
+            let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+            let is_prefix = meta.query_advice(is_prefix, Rotation::cur());
+            let prefix_type = sum::expr(prefix_types.iter().enumerate().map(|(idx, column)| {
+                (idx + 1).expr() * meta.query_advice(*column, Rotation::cur())
+            }));
+            let input = [
+                is_prefix.clone() * meta.query_advice(byte, Rotation::cur()),
+                prefix_type,
+                is_prefix * meta.query_advice(prefix_len, Rotation::cur()),
+            ];
+            input
+                .into_iter()
+                .zip(prefix_table)
+                .map(|(input, column)| {
+                    (
+                        q_enable.clone() * input,
+                        meta.query_fixed(column, Rotation::cur()),
+                    )
+                })
+                .collect()
+        });
+
+        Self {
+            q_enable,
+            is_first,
+            is_last,
+            is_list,
+            stream_rlc,
+            stream_len,
+            stream_rlc_acc,
+            stream_bytes_left,
+            byte,
+            byte_hi,
+            byte_lo,
+            is_header,
+            is_prefix,
+            is_len,
+            is_content,
+            prefix_types,
+            prefix_len,
+            len_bytes_left,
+            len_acc,
+            item_index,
+            is_item_end,
+            item_bytes_left,
+            item_rlc,
+            item_mult,
+            item_horner,
+            item_value,
+            item_len,
+            is_hp,
+            hp_is_leaf,
+            hp_is_odd,
+            nibbles_rlc,
+            nibbles_len,
+            prefix_table,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the expressions of the item ending at the current row.
+    pub fn item_queries(&self, meta: &mut VirtualCells<F>) -> RlpItemQueries<F> {
+        let cur = |meta: &mut VirtualCells<F>, column| meta.query_advice(column, Rotation::cur());
+        RlpItemQueries {
+            enabled: meta.query_fixed(self.q_enable, Rotation::cur()) * cur(meta, self.is_item_end),
+            stream_rlc: cur(meta, self.stream_rlc),
+            stream_len: cur(meta, self.stream_len),
+            index: cur(meta, self.item_index),
+            is_last: cur(meta, self.is_last),
+            rlc: cur(meta, self.item_rlc),
+            horner: cur(meta, self.item_horner),
+            value: cur(meta, self.item_value),
+            len: cur(meta, self.item_len),
+            is_hp: cur(meta, self.is_hp),
+            hp_is_leaf: cur(meta, self.hp_is_leaf),
+            nibbles_rlc: cur(meta, self.nibbles_rlc),
+            nibbles_len: cur(meta, self.nibbles_len),
+        }
+    }
+
+    /// Adds a lookup of an item of the decoder, where `lookup` returns the
+    /// condition of the lookup and the inputs matched with the expressions of
+    /// the item.
+    pub fn lookup_item(
+        &self,
+        meta: &mut ConstraintSystem<F>,
+        name: &'static str,
+        lookup: impl FnOnce(
+            &mut VirtualCells<F>,
+            RlpItemQueries<F>,
+        ) -> (Expression<F>, Vec<(Expression<F>, Expression<F>)>),
+    ) {
+        let config = *self;
+        meta.lookup_any(name, |meta| {
+            let item = config.item_queries(meta);
+            let enabled = item.enabled.clone();
+            let (condition, inputs) = lookup(meta, item);
+            std::iter::once((condition.clone(), enabled))
+                .chain(
+                    inputs
+                        .into_iter()
+                        .map(|(input, table)| (condition.clone() * input, table)),
+                )
+                .collect()
+        });
+    }
+
+    /// Load the fixed table of the valid prefix bytes.
+    pub fn load_prefix_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "rlp decoder prefix table",
+            |mut region| {
+                let rows = std::iter::once([F::zero(); 3]).chain((0..=255u8).filter_map(|byte| {
+                    RlpPrefixType::decode(byte).map(|(prefix_type, len)| {
+                        [
+                            F::from(byte as u64),
+                            F::from(prefix_type as u64),
+                            F::from(len as u64),
+                        ]
+                    })
+                }));
+                for (offset, row) in rows.enumerate() {
+                    for (column, value) in self.prefix_table.iter().zip(row) {
+                        region.assign_fixed(
+                            || "rlp decoder prefix table",
+                            *column,
+                            offset,
+                            || Ok(value),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the rows of the streams.
+    pub fn assign<'a>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        rows: impl Iterator<Item = &'a RlpDecoderRow<F>> + Clone,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "assign rlp decoder streams",
+            |mut region| {
+                let mut offset = 0;
+                for row in rows.clone() {
+                    self.assign_row(&mut region, offset, true, row)?;
+                    offset += 1;
+                }
+                // pad a row in the end to satisfy Halo2 cell assignment check
+                self.assign_row(&mut region, offset, false, &RlpDecoderRow::default())
+            },
+        )
+    }
+
+    fn assign_row(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        q_enable: bool,
+        row: &RlpDecoderRow<F>,
+    ) -> Result<(), Error> {
+        region.assign_fixed(
+            || format!("assign rlp decoder q_enable {}", offset),
+            self.q_enable,
+            offset,
+            || Ok(F::from(q_enable)),
+        )?;
+
+        let prefix_types = [
+            RlpPrefixType::SingleByte,
+            RlpPrefixType::ShortString,
+            RlpPrefixType::LongString,
+            RlpPrefixType::ShortList,
+            RlpPrefixType::LongList,
+        ]
+        .map(|prefix_type| row.prefix_type == Some(prefix_type));
+        for (name, column, value) in [
+            ("is_first", self.is_first, F::from(row.is_first)),
+            ("is_last", self.is_last, F::from(row.is_last)),
+            ("is_list", self.is_list, F::from(row.is_list)),
+            ("stream_rlc", self.stream_rlc, row.stream_rlc),
+            (
+                "stream_len",
+                self.stream_len,
+                F::from(row.stream_len as u64),
+            ),
+            ("stream_rlc_acc", self.stream_rlc_acc, row.stream_rlc_acc),
+            (
+                "stream_bytes_left",
+                self.stream_bytes_left,
+                F::from(row.stream_bytes_left as u64),
+            ),
+            ("byte", self.byte, F::from(row.byte as u64)),
+            ("byte_hi", self.byte_hi, F::from((row.byte >> 4) as u64)),
+            ("byte_lo", self.byte_lo, F::from((row.byte & 0xf) as u64)),
+            ("is_header", self.is_header, F::from(row.is_header)),
+            ("is_prefix", self.is_prefix, F::from(row.is_prefix)),
+            ("is_len", self.is_len, F::from(row.is_len)),
+            ("is_content", self.is_content, F::from(row.is_content)),
+            (
+                "prefix_len",
+                self.prefix_len,
+                F::from(row.prefix_len as u64),
+            ),
+            (
+                "len_bytes_left",
+                self.len_bytes_left,
+                F::from(row.len_bytes_left as u64),
+            ),
+            ("len_acc", self.len_acc, F::from(row.len_acc as u64)),
+            (
+                "item_index",
+                self.item_index,
+                F::from(row.item_index as u64),
+            ),
+            ("is_item_end", self.is_item_end, F::from(row.is_item_end)),
+            (
+                "item_bytes_left",
+                self.item_bytes_left,
+                F::from(row.item_bytes_left as u64),
+            ),
+            ("item_rlc", self.item_rlc, row.item_rlc),
+            ("item_mult", self.item_mult, row.item_mult),
+            ("item_horner", self.item_horner, row.item_horner),
+            ("item_value", self.item_value, row.item_value),
+            ("item_len", self.item_len, F::from(row.item_len as u64)),
+            ("is_hp", self.is_hp, F::from(row.is_hp)),
+            ("hp_is_leaf", self.hp_is_leaf, F::from(row.hp_is_leaf)),
+            ("hp_is_odd", self.hp_is_odd, F::from(row.hp_is_odd)),
+            ("nibbles_rlc", self.nibbles_rlc, row.nibbles_rlc),
+            (
+                "nibbles_len",
+                self.nibbles_len,
+                F::from(row.nibbles_len as u64),
+            ),
+        ]
+        .into_iter()
+        .chain(
+            self.prefix_types
+                .into_iter()
+                .zip(prefix_types)
+                .map(|(column, value)| ("prefix_types", column, F::from(value))),
+        ) {
+            region.assign_advice(
+                || format!("assign rlp decoder {} {}", name, offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A decoded prefix: its type, its length given by the prefix byte, the number
+/// of bytes of the prefix and of its length, and the number of content bytes.
+struct Prefix {
+    prefix_type: RlpPrefixType,
+    prefix_len: usize,
+    header_len: usize,
+    content_len: usize,
+}
+
+fn decode_prefix(bytes: &[u8]) -> Result<Prefix, MptWitnessError> {
+    let (&byte, rest) = bytes.split_first().ok_or(MptWitnessError::InvalidNode)?;
+    let (prefix_type, prefix_len) =
+        RlpPrefixType::decode(byte).ok_or(MptWitnessError::InvalidNode)?;
+    let (header_len, content_len) = match prefix_type {
+        RlpPrefixType::SingleByte => (0, 1),
+        RlpPrefixType::ShortString | RlpPrefixType::ShortList => (1, prefix_len),
+        RlpPrefixType::LongString | RlpPrefixType::LongList => {
+            let len = rest
+                .get(..prefix_len)
+                .ok_or(MptWitnessError::InvalidNode)?
+                .iter()
+                .fold(0, |len, byte| len * 256 + *byte as usize);
+            (1 + prefix_len, len)
+        }
+    };
+    Ok(Prefix {
+        prefix_type,
+        prefix_len,
+        header_len,
+        content_len,
+    })
+}
+
+/// Returns the rows of the RLP decoder for an encoding, which is either a list,
+/// whose first item is a hex-prefix encoded path when it has 2 items, or a
+/// single string.
+pub(crate) fn rlp_decoder_rows<F: Field>(
+    encoding: &[u8],
+    randomness: F,
+) -> Result<Vec<RlpDecoderRow<F>>, MptWitnessError> {
+    let is_list = matches!(encoding.first(), Some(byte) if *byte >= 0xc0);
+    let mut rows: Vec<RlpDecoderRow<F>> = Vec::with_capacity(encoding.len());
+
+    let mut offset = 0;
+    if is_list {
+        let header = decode_prefix(encoding)?;
+        if header.header_len + header.content_len != encoding.len() {
+            return Err(MptWitnessError::InvalidNode);
+        }
+        let mut len_acc = 0;
+        for (idx, &byte) in encoding[..header.header_len].iter().enumerate() {
+            let is_prefix = idx == 0;
+            let is_long = header.prefix_type == RlpPrefixType::LongList;
+            if !is_prefix {
+                len_acc = len_acc * 256 + byte as usize;
+            }
+            rows.push(RlpDecoderRow {
+                byte,
+                is_header: true,
+                is_prefix,
+                is_len: !is_prefix,
+                prefix_type: Some(header.prefix_type).filter(|_| is_prefix),
+                prefix_len: if is_prefix { header.prefix_len } else { 0 },
+                len_bytes_left: if is_long {
+                    header.header_len - 1 - idx
+                } else {
+                    0
+                },
+                len_acc,
+                ..Default::default()
+            });
+        }
+        offset = header.header_len;
+    }
+
+    let mut items = Vec::new();
+    while offset < encoding.len() {
+        let item = decode_prefix(&encoding[offset..])?;
+        if item.prefix_type == RlpPrefixType::LongList {
+            return Err(MptWitnessError::InvalidNode);
+        }
+        let end = offset + item.header_len + item.content_len;
+        if end > encoding.len() {
+            return Err(MptWitnessError::InvalidNode);
+        }
+        items.push((offset, end, item));
+        offset = end;
+    }
+    if !is_list && items.len() != 1 {
+        return Err(MptWitnessError::InvalidNode);
+    }
+
+    let is_path_list = is_list && items.len() == 2;
+    for (item_index, (start, end, item)) in items.into_iter().enumerate() {
+        let bytes = &encoding[start..end];
+        let is_hp = is_path_list && item_index == 0;
+        let (hp_is_leaf, hp_is_odd) = if is_hp {
+            if !matches!(
+                item.prefix_type,
+                RlpPrefixType::SingleByte | RlpPrefixType::ShortString
+            ) {
+                return Err(MptWitnessError::InvalidNode);
+            }
+            let first = *bytes
+                .get(item.header_len)
+                .ok_or(MptWitnessError::InvalidNode)?;
+            let flag = first >> 4;
+            if flag > 3 || (flag & 1 == 0 && first & 0xf != 0) {
+                return Err(MptWitnessError::InvalidNode);
+            }
+            (flag & 2 == 2, flag & 1 == 1)
+        } else {
+            (false, false)
+        };
+
+        for (idx, &byte) in bytes.iter().enumerate() {
+            let mut row = RlpDecoderRow {
+                byte,
+                item_index,
+                is_item_end: start + idx == end - 1,
+                is_hp,
+                hp_is_leaf,
+                hp_is_odd,
+                ..Default::default()
+            };
+            let (hi, lo) = (F::from((byte >> 4) as u64), F::from((byte & 0xf) as u64));
+            let is_first_content = if idx == 0 {
+                let starts_content = matches!(
+                    item.prefix_type,
+                    RlpPrefixType::SingleByte | RlpPrefixType::ShortList
+                );
+                let is_long = item.prefix_type == RlpPrefixType::LongString;
+                row.is_prefix = true;
+                row.prefix_type = Some(item.prefix_type);
+                row.prefix_len = item.prefix_len;
+                row.len_bytes_left = if is_long { item.prefix_len } else { 0 };
+                row.item_bytes_left = if item.prefix_type == RlpPrefixType::SingleByte || is_long {
+                    0
+                } else {
+                    item.prefix_len
+                };
+                let start = if starts_content {
+                    F::from(byte as u64)
+                } else {
+                    F::zero()
+                };
+                row.item_rlc = start;
+                row.item_horner = start;
+                row.item_value = start;
+                row.item_len = starts_content as usize;
+                row.item_mult = if item.prefix_type == RlpPrefixType::ShortList {
+                    randomness
+                } else {
+                    F::one()
+                };
+                item.prefix_type == RlpPrefixType::SingleByte
+            } else {
+                let prev = rows.last().unwrap();
+                if idx < item.header_len {
+                    row.is_len = true;
+                    row.len_bytes_left = prev.len_bytes_left - 1;
+                    row.len_acc = prev.len_acc * 256 + byte as usize;
+                    row.item_bytes_left = row.len_acc;
+                    row.item_rlc = prev.item_rlc;
+                    row.item_mult = prev.item_mult;
+                    row.item_horner = prev.item_horner;
+                    row.item_value = prev.item_value;
+                    row.item_len = prev.item_len;
+                    false
+                } else {
+                    row.is_content = true;
+                    row.item_bytes_left = prev.item_bytes_left - 1;
+                    row.item_rlc = prev.item_rlc + F::from(byte as u64) * prev.item_mult;
+                    row.item_mult = prev.item_mult * randomness;
+                    row.item_horner = prev.item_horner * randomness + F::from(byte as u64);
+                    row.item_value = prev.item_value * F::from(256) + F::from(byte as u64);
+                    row.item_len = prev.item_len + 1;
+                    if prev.is_content {
+                        row.nibbles_rlc = (prev.nibbles_rlc * randomness + hi) * randomness + lo;
+                        row.nibbles_len = prev.nibbles_len + 2;
+                    }
+                    !prev.is_content
+                }
+            };
+            if is_hp && is_first_content && hp_is_odd {
+                row.nibbles_rlc = lo;
+                row.nibbles_len = 1;
+            }
+            rows.push(row);
+        }
+    }
+
+    let stream_len = rows.len();
+    let mut stream_rlc_acc = F::zero();
+    for (idx, row) in rows.iter_mut().enumerate() {
+        stream_rlc_acc = stream_rlc_acc * randomness + F::from(row.byte as u64);
+        row.is_first = idx == 0;
+        row.is_last = idx == stream_len - 1;
+        row.is_list = is_list;
+        row.stream_len = stream_len;
+        row.stream_rlc_acc = stream_rlc_acc;
+        row.stream_bytes_left = stream_len - 1 - idx;
+    }
+    for row in rows.iter_mut() {
+        row.stream_rlc = stream_rlc_acc;
+    }
+
+    Ok(rows)
+}
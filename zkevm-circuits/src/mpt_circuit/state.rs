@@ -0,0 +1,577 @@
+//! The state trie partially known from the proofs of `eth_getProof`, on which
+//! the updates of a block are replayed to generate the witness of the MPT
+//! circuit.
+
+use eth_types::{Address, EIP1186ProofResponse, Field, ToBigEndian, Word, H256};
+use ethers_core::utils::keccak256;
+use rlp::RlpStream;
+use std::collections::HashMap;
+
+use super::{
+    trie::Trie,
+    witness::{
+        account_trie_key, storage_trie_key, MptProof, MptProofType, MptUpdate, MptWitnessError,
+    },
+};
+use crate::{
+    evm_circuit::{
+        table::{AccountFieldTag, RwTableTag},
+        witness::{Block, Rw},
+    },
+    state_circuit::{is_same_access_group, sort_key},
+};
+
+/// Account of the state trie, along with its storage trie.
+#[derive(Clone, Debug)]
+struct Account {
+    nonce: Word,
+    balance: Word,
+    code_hash: H256,
+    storage: Trie,
+}
+
+impl Default for Account {
+    fn default() -> Self {
+        Self {
+            nonce: Word::zero(),
+            balance: Word::zero(),
+            code_hash: H256(keccak256(&[])),
+            storage: Trie::default(),
+        }
+    }
+}
+
+impl Account {
+    fn encode(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(4);
+        stream
+            .append(&self.nonce)
+            .append(&self.balance)
+            .append(&self.storage.root())
+            .append(&self.code_hash);
+        stream.out().to_vec()
+    }
+}
+
+/// Encoding of a storage value in its trie, where the empty value removes
+/// the slot.
+fn encode_storage_value(value: &Word) -> Vec<u8> {
+    if value.is_zero() {
+        vec![]
+    } else {
+        rlp::encode(value).to_vec()
+    }
+}
+
+/// State trie known from the proofs of some of its accounts and storage
+/// slots, which generates the proofs of their updates.
+#[derive(Clone, Debug, Default)]
+pub struct MptState {
+    trie: Trie,
+    accounts: HashMap<Address, Account>,
+}
+
+impl MptState {
+    /// Returns the state of root `state_root` known from the `proofs`
+    /// returned by `eth_getProof` at this root, checking every proof against
+    /// the root and the values it proves.
+    pub fn from_proofs(
+        state_root: H256,
+        proofs: &[EIP1186ProofResponse],
+    ) -> Result<Self, MptWitnessError> {
+        let trie = Trie::from_proofs(
+            &state_root,
+            proofs
+                .iter()
+                .flat_map(|proof| proof.account_proof.iter().map(|node| node.as_ref())),
+        )?;
+
+        let mut accounts = HashMap::new();
+        for proof in proofs {
+            let storage = Trie::from_proofs(
+                &proof.storage_hash,
+                proof
+                    .storage_proof
+                    .iter()
+                    .flat_map(|slot| slot.proof.iter().map(|node| node.as_ref())),
+            )?;
+            let account = Account {
+                nonce: proof.nonce,
+                balance: proof.balance,
+                code_hash: proof.code_hash,
+                storage,
+            };
+            match trie.get(&account_trie_key(&proof.address))? {
+                Some(encoding) if encoding == account.encode() => {}
+                // A missing account is proven along with its empty fields.
+                None if account.nonce.is_zero()
+                    && account.balance.is_zero()
+                    && account.storage.root() == Trie::default().root() => {}
+                _ => return Err(MptWitnessError::ValueMismatch),
+            }
+            for slot in &proof.storage_proof {
+                let value = account.storage.get(&storage_trie_key(&slot.key))?;
+                if value.unwrap_or_default() != encode_storage_value(&slot.value) {
+                    return Err(MptWitnessError::ValueMismatch);
+                }
+            }
+            accounts.insert(proof.address, account);
+        }
+
+        Ok(Self { trie, accounts })
+    }
+
+    /// Returns the state root.
+    pub fn root(&self) -> H256 {
+        self.trie.root()
+    }
+
+    /// Sets the field of `proof_type` of the account at `address`, or its
+    /// storage slot at `storage_key` for [`MptProofType::StorageMod`], to
+    /// `new_value`, and returns the update along with its proofs.
+    pub fn update(
+        &mut self,
+        address: Address,
+        proof_type: MptProofType,
+        storage_key: Word,
+        new_value: Word,
+    ) -> Result<MptUpdate, MptWitnessError> {
+        let key = account_trie_key(&address);
+        let old_root = self.trie.root();
+        let old_nodes = self.trie.prove(&key)?;
+
+        // The accounts out of the proofs are checked to be missing, as their
+        // path in the trie may be unknown.
+        if !self.accounts.contains_key(&address) && self.trie.get(&key)?.is_some() {
+            return Err(MptWitnessError::InvalidProof);
+        }
+        let mut account = self.accounts.get(&address).cloned().unwrap_or_default();
+        let (old_value, storage_proof) = match proof_type {
+            MptProofType::NonceMod => (std::mem::replace(&mut account.nonce, new_value), None),
+            MptProofType::BalanceMod => (std::mem::replace(&mut account.balance, new_value), None),
+            MptProofType::CodeHashMod => (
+                Word::from_big_endian(
+                    std::mem::replace(&mut account.code_hash, H256(new_value.to_be_bytes()))
+                        .as_bytes(),
+                ),
+                None,
+            ),
+            MptProofType::StorageMod => {
+                let key = storage_trie_key(&storage_key);
+                let old_root = account.storage.root();
+                let old_nodes = account.storage.prove(&key)?;
+                let old_value = account
+                    .storage
+                    .get(&key)?
+                    .map(rlp::decode::<Word>)
+                    .transpose()
+                    .map_err(|_| MptWitnessError::ValueMismatch)?
+                    .unwrap_or_default();
+                account
+                    .storage
+                    .insert(&key, encode_storage_value(&new_value))?;
+                let storage_proof = MptProof {
+                    key,
+                    old_root,
+                    new_root: account.storage.root(),
+                    old_nodes,
+                    new_nodes: account.storage.prove(&key)?,
+                };
+                (old_value, Some(storage_proof))
+            }
+        };
+        let mut trie = self.trie.clone();
+        trie.insert(&key, account.encode())?;
+        let new_nodes = trie.prove(&key)?;
+
+        self.trie = trie;
+        self.accounts.insert(address, account);
+        Ok(MptUpdate {
+            address,
+            storage_key,
+            proof_type,
+            old_value,
+            new_value,
+            storage_proof,
+            account_proof: MptProof {
+                key,
+                old_root,
+                new_root: self.trie.root(),
+                old_nodes,
+                new_nodes,
+            },
+        })
+    }
+}
+
+/// Returns the updates of the MPT circuit for the accounts and the storage
+/// slots accessed by `block`, one for each access group of the state circuit
+/// in its order, from the value before the first access to the value after
+/// the last one, as looked up by the state circuit.  The updates are replayed
+/// on the state known from the proofs of the block, which must lead to the
+/// state root of the block.
+pub fn mpt_updates<F: Field>(block: &Block<F>) -> Result<Vec<MptUpdate>, MptWitnessError> {
+    let (updates, state_root) = replay_updates(block)?;
+    if state_root != block.state_root {
+        return Err(MptWitnessError::InvalidStateRoot);
+    }
+    Ok(updates)
+}
+
+/// Returns the updates of `block` replayed on the state known from its
+/// proofs, along with the state root they lead to.
+pub(crate) fn replay_updates<F: Field>(
+    block: &Block<F>,
+) -> Result<(Vec<MptUpdate>, H256), MptWitnessError> {
+    // The updates chain the state roots in the order of the last accesses of
+    // the state circuit, whose storage accesses are grouped by transaction.
+    let mut rws: Vec<&Rw> = [RwTableTag::Account, RwTableTag::AccountStorage]
+        .iter()
+        .filter_map(|tag| block.rws.0.get(tag))
+        .flatten()
+        .collect();
+    rws.sort_by_key(|rw| sort_key(rw));
+
+    // The values before and after every access group.
+    let mut values: Vec<((Address, MptProofType, Word), (Word, Word))> = Vec::new();
+    for (idx, rw) in rws.iter().enumerate() {
+        let (key, value, value_prev) = match rw {
+            Rw::Account {
+                account_address,
+                field_tag,
+                value,
+                value_prev,
+                ..
+            } => {
+                let proof_type = match field_tag {
+                    AccountFieldTag::Nonce => MptProofType::NonceMod,
+                    AccountFieldTag::Balance => MptProofType::BalanceMod,
+                    AccountFieldTag::CodeHash => MptProofType::CodeHashMod,
+                };
+                (
+                    (*account_address, proof_type, Word::zero()),
+                    value,
+                    value_prev,
+                )
+            }
+            Rw::AccountStorage {
+                account_address,
+                storage_key,
+                value,
+                value_prev,
+                ..
+            } => (
+                (*account_address, MptProofType::StorageMod, *storage_key),
+                value,
+                value_prev,
+            ),
+            _ => unreachable!(),
+        };
+        match values.last_mut() {
+            Some((_, (_, new_value))) if idx > 0 && is_same_access_group(rw, rws[idx - 1]) => {
+                *new_value = *value
+            }
+            _ => values.push((key, (*value_prev, *value))),
+        }
+    }
+
+    let mut state = MptState::from_proofs(block.prev_state_root, &block.state_proofs)?;
+    let updates = values
+        .into_iter()
+        .map(
+            |((address, proof_type, storage_key), (old_value, new_value))| {
+                let update = state.update(address, proof_type, storage_key, new_value)?;
+                if update.old_value != old_value {
+                    return Err(MptWitnessError::ValueMismatch);
+                }
+                Ok(update)
+            },
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((updates, state.root()))
+}
+
+/// Returns the state root of the `accounts` and their proofs, which hold all
+/// the nodes of the state trie, for the tests of the circuits proving blocks
+/// on mock accounts.
+#[cfg(test)]
+pub(crate) fn dev_state_proofs(
+    accounts: &[eth_types::geth_types::Account],
+) -> (H256, Vec<EIP1186ProofResponse>) {
+    let mut state = MptState::default();
+    for account in accounts {
+        for (proof_type, value) in [
+            (MptProofType::NonceMod, account.nonce),
+            (MptProofType::BalanceMod, account.balance),
+            (
+                MptProofType::CodeHashMod,
+                Word::from_big_endian(&keccak256(&account.code)),
+            ),
+        ] {
+            state
+                .update(account.address, proof_type, Word::zero(), value)
+                .unwrap();
+        }
+        for (storage_key, value) in &account.storage {
+            state
+                .update(
+                    account.address,
+                    MptProofType::StorageMod,
+                    *storage_key,
+                    *value,
+                )
+                .unwrap();
+        }
+    }
+
+    let to_bytes = |nodes: Vec<Vec<u8>>| -> Vec<eth_types::Bytes> {
+        nodes.into_iter().map(eth_types::Bytes::from).collect()
+    };
+    let proofs = accounts
+        .iter()
+        .map(|account| {
+            let storage = &state.accounts[&account.address].storage;
+            EIP1186ProofResponse {
+                address: account.address,
+                balance: account.balance,
+                code_hash: H256(keccak256(&account.code)),
+                nonce: account.nonce,
+                storage_hash: storage.root(),
+                account_proof: to_bytes(
+                    state
+                        .trie
+                        .prove(&account_trie_key(&account.address))
+                        .unwrap(),
+                ),
+                storage_proof: account
+                    .storage
+                    .iter()
+                    .map(|(storage_key, value)| eth_types::StorageProof {
+                        key: *storage_key,
+                        value: *value,
+                        proof: to_bytes(storage.prove(&storage_trie_key(storage_key)).unwrap()),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    (state.root(), proofs)
+}
+
+#[cfg(test)]
+mod mpt_state_tests {
+    use super::*;
+    use crate::evm_circuit::witness::RwMap;
+    use eth_types::{Bytes, StorageProof};
+    use halo2_proofs::pairing::bn256::Fr;
+
+    /// Returns the full state of a few accounts, the first one having a few
+    /// storage slots, along with its proofs of all the accounts and slots.
+    fn full_state() -> (MptState, Vec<EIP1186ProofResponse>) {
+        let addresses = [0x10u64, 0x20, 0x2000].map(Address::from_low_u64_be);
+        let mut state = MptState::default();
+        for (idx, address) in addresses.iter().enumerate() {
+            state
+                .update(
+                    *address,
+                    MptProofType::BalanceMod,
+                    Word::zero(),
+                    Word::from(1000 + idx),
+                )
+                .unwrap();
+        }
+        for slot in [0x10u64, 0x20, 0x1234] {
+            state
+                .update(
+                    addresses[0],
+                    MptProofType::StorageMod,
+                    Word::from(slot),
+                    Word::from(slot + 1),
+                )
+                .unwrap();
+        }
+
+        let proofs = addresses
+            .iter()
+            .map(|address| {
+                let account = &state.accounts[address];
+                let storage_proof = [0x10u64, 0x20, 0x30]
+                    .map(Word::from)
+                    .iter()
+                    .map(|key| StorageProof {
+                        key: *key,
+                        value: account
+                            .storage
+                            .get(&storage_trie_key(key))
+                            .unwrap()
+                            .map(|value| rlp::decode(value).unwrap())
+                            .unwrap_or_default(),
+                        proof: to_bytes(account.storage.prove(&storage_trie_key(key))),
+                    })
+                    .collect();
+                EIP1186ProofResponse {
+                    address: *address,
+                    balance: account.balance,
+                    code_hash: account.code_hash,
+                    nonce: account.nonce,
+                    storage_hash: account.storage.root(),
+                    account_proof: to_bytes(state.trie.prove(&account_trie_key(address))),
+                    storage_proof,
+                }
+            })
+            .collect();
+        (state, proofs)
+    }
+
+    fn to_bytes(nodes: Result<Vec<Vec<u8>>, MptWitnessError>) -> Vec<Bytes> {
+        nodes.unwrap().into_iter().map(Bytes::from).collect()
+    }
+
+    fn storage_rw(rw_counter: usize, key: u64, value: u64, value_prev: u64) -> Rw {
+        Rw::AccountStorage {
+            rw_counter,
+            is_write: true,
+            account_address: Address::from_low_u64_be(0x10),
+            storage_key: Word::from(key),
+            value: Word::from(value),
+            value_prev: Word::from(value_prev),
+            tx_id: 1,
+            committed_value: Word::from(value_prev),
+        }
+    }
+
+    fn block(state_root: H256) -> Block<Fr> {
+        let (state, proofs) = full_state();
+        let account_rws = vec![Rw::Account {
+            rw_counter: 1,
+            is_write: true,
+            account_address: Address::from_low_u64_be(0x20),
+            field_tag: AccountFieldTag::Nonce,
+            value: Word::from(1),
+            value_prev: Word::zero(),
+        }];
+        let storage_rws = vec![
+            storage_rw(2, 0x20, 5, 0x21),
+            storage_rw(3, 0x20, 6, 5),
+            storage_rw(4, 0x30, 7, 0),
+        ];
+        Block {
+            rws: RwMap(
+                [
+                    (RwTableTag::Account, account_rws),
+                    (RwTableTag::AccountStorage, storage_rws),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            prev_state_root: state.root(),
+            state_root,
+            state_proofs: proofs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn mpt_state_replays_updates() {
+        let (mut full, proofs) = full_state();
+        let mut state = MptState::from_proofs(full.root(), &proofs).unwrap();
+        for (address, proof_type, storage_key, value) in [
+            (0x10, MptProofType::NonceMod, 0, 1),
+            (0x20, MptProofType::BalanceMod, 0, 5),
+            // insertion into an empty child of a branch
+            (0x10, MptProofType::StorageMod, 0x30, 7),
+            // deletion from a branch keeping other children
+            (0x10, MptProofType::StorageMod, 0x20, 0),
+            // insertion of a missing account
+            (0x30, MptProofType::BalanceMod, 0, 9),
+        ] {
+            let (address, storage_key, value) = (
+                Address::from_low_u64_be(address),
+                Word::from(storage_key),
+                Word::from(value),
+            );
+            let expected = full
+                .update(address, proof_type, storage_key, value)
+                .unwrap();
+            let update = state
+                .update(address, proof_type, storage_key, value)
+                .unwrap();
+            assert_eq!(update, expected);
+            assert!(update.paths(Fr::from(0x100)).is_ok());
+        }
+    }
+
+    #[test]
+    fn mpt_state_invalid_proof_value() {
+        let (full, mut proofs) = full_state();
+        proofs[0].storage_proof[0].value = Word::from(1);
+        assert_eq!(
+            MptState::from_proofs(full.root(), &proofs).unwrap_err(),
+            MptWitnessError::ValueMismatch
+        );
+
+        let (full, mut proofs) = full_state();
+        proofs[1].balance = Word::from(1);
+        assert_eq!(
+            MptState::from_proofs(full.root(), &proofs).unwrap_err(),
+            MptWitnessError::ValueMismatch
+        );
+    }
+
+    #[test]
+    fn mpt_state_update_out_of_proofs() {
+        let (full, proofs) = full_state();
+        let mut state = MptState::from_proofs(full.root(), &proofs[..1]).unwrap();
+        assert_eq!(
+            state
+                .update(
+                    Address::from_low_u64_be(0x2000),
+                    MptProofType::NonceMod,
+                    Word::zero(),
+                    Word::from(1)
+                )
+                .unwrap_err(),
+            MptWitnessError::InvalidProof
+        );
+    }
+
+    #[test]
+    fn mpt_updates_of_block() {
+        let (mut full, _) = full_state();
+        let address = Address::from_low_u64_be(0x10);
+        full.update(
+            Address::from_low_u64_be(0x20),
+            MptProofType::NonceMod,
+            Word::zero(),
+            Word::from(1),
+        )
+        .unwrap();
+        full.update(
+            address,
+            MptProofType::StorageMod,
+            Word::from(0x20),
+            Word::from(6),
+        )
+        .unwrap();
+        full.update(
+            address,
+            MptProofType::StorageMod,
+            Word::from(0x30),
+            Word::from(7),
+        )
+        .unwrap();
+
+        let updates = mpt_updates(&block(full.root())).unwrap();
+        assert_eq!(updates.len(), 3);
+        assert_eq!(
+            (updates[0].old_value, updates[0].new_value),
+            (Word::from(0x21), Word::from(6))
+        );
+
+        assert_eq!(
+            mpt_updates(&block(H256::zero())).unwrap_err(),
+            MptWitnessError::InvalidStateRoot
+        );
+    }
+}
@@ -0,0 +1,403 @@
+//! A minimal in-memory Merkle Patricia Trie with 32-byte keys, used to compute
+//! the roots and the proofs of the trie updates witnessed by the MPT circuit.
+//! The trie may be partially known from the proofs of some of its keys, in
+//! which case the updates are limited to the nodes of the proofs.
+
+use eth_types::H256;
+use ethers_core::utils::keccak256;
+use rlp::{Rlp, RlpStream};
+use std::collections::HashMap;
+
+use super::witness::{data, decode_hex_prefix, item, key_nibbles, MptWitnessError};
+
+/// A node of the trie. Branch nodes never hold a value, since every key has
+/// the same length.
+#[derive(Clone, Debug)]
+enum Node {
+    Empty,
+    Leaf {
+        path: Vec<u8>,
+        value: Vec<u8>,
+    },
+    Extension {
+        path: Vec<u8>,
+        child: Box<Node>,
+    },
+    Branch {
+        children: Box<[Node; 16]>,
+    },
+    /// A node known only by its hash, being out of the proofs the trie is
+    /// built from.
+    Hash(H256),
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Self::Empty
+    }
+}
+
+impl Node {
+    /// Decodes the node of RLP encoding `node`, whose children referenced by
+    /// their hash are decoded from `nodes` when they are there.
+    fn decode(node: &Rlp, nodes: &HashMap<H256, &[u8]>) -> Result<Self, MptWitnessError> {
+        match node.item_count() {
+            Ok(17) => {
+                if !data(&item(node, 16)?)?.is_empty() {
+                    return Err(MptWitnessError::InvalidNode);
+                }
+                let mut children: Box<[Node; 16]> = Default::default();
+                for (idx, child) in children.iter_mut().enumerate() {
+                    *child = Self::decode_child(&item(node, idx)?, nodes)?;
+                }
+                Ok(Self::Branch { children })
+            }
+            Ok(2) => {
+                let (path, is_leaf) = decode_hex_prefix(data(&item(node, 0)?)?)?;
+                if is_leaf {
+                    Ok(Self::Leaf {
+                        path,
+                        value: data(&item(node, 1)?)?.to_vec(),
+                    })
+                } else {
+                    Ok(Self::Extension {
+                        path,
+                        child: Box::new(Self::decode_child(&item(node, 1)?, nodes)?),
+                    })
+                }
+            }
+            _ => Err(MptWitnessError::InvalidNode),
+        }
+    }
+
+    /// Decodes the child referenced by `child`, which is the empty string, the
+    /// inlined child or the hash of the child.
+    fn decode_child(child: &Rlp, nodes: &HashMap<H256, &[u8]>) -> Result<Self, MptWitnessError> {
+        if child.is_list() {
+            return Self::decode(child, nodes);
+        }
+        match data(child)? {
+            [] => Ok(Self::Empty),
+            hash if hash.len() == 32 => Self::from_hash(H256::from_slice(hash), nodes),
+            _ => Err(MptWitnessError::InvalidNode),
+        }
+    }
+
+    /// Returns the node of hash `hash`, decoded from `nodes` when it's there.
+    fn from_hash(hash: H256, nodes: &HashMap<H256, &[u8]>) -> Result<Self, MptWitnessError> {
+        match nodes.get(&hash) {
+            Some(encoding) => Self::decode(&Rlp::new(encoding), nodes),
+            None => Ok(Self::Hash(hash)),
+        }
+    }
+
+    /// Returns the RLP encoding of the node.
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Empty => vec![0x80],
+            Self::Leaf { path, value } => {
+                let mut stream = RlpStream::new_list(2);
+                stream.append(&hex_prefix(path, true)).append(value);
+                stream.out().to_vec()
+            }
+            Self::Extension { path, child } => {
+                let mut stream = RlpStream::new_list(2);
+                stream.append(&hex_prefix(path, false));
+                append_child(&mut stream, child);
+                stream.out().to_vec()
+            }
+            Self::Branch { children } => {
+                let mut stream = RlpStream::new_list(17);
+                for child in children.iter() {
+                    append_child(&mut stream, child);
+                }
+                stream.append_empty_data();
+                stream.out().to_vec()
+            }
+            Self::Hash(_) => unreachable!("a node known by its hash can't be encoded"),
+        }
+    }
+
+    /// Returns the node with `value` stored at the remaining `path`.
+    fn insert(self, path: &[u8], value: Vec<u8>) -> Result<Self, MptWitnessError> {
+        Ok(match self {
+            Self::Empty => Self::Leaf {
+                path: path.to_vec(),
+                value,
+            },
+            Self::Leaf {
+                path: leaf_path,
+                value: leaf_value,
+            } => {
+                if leaf_path == path {
+                    return Ok(Self::Leaf {
+                        path: leaf_path,
+                        value,
+                    });
+                }
+                let common = common_prefix(&leaf_path, path);
+                let branch = Self::Branch {
+                    children: Default::default(),
+                }
+                .insert(&leaf_path[common..], leaf_value)?
+                .insert(&path[common..], value)?;
+                with_extension(&path[..common], branch)
+            }
+            Self::Extension {
+                path: ext_path,
+                child,
+            } => {
+                let common = common_prefix(&ext_path, path);
+                if common == ext_path.len() {
+                    return Ok(Self::Extension {
+                        path: ext_path,
+                        child: Box::new(child.insert(&path[common..], value)?),
+                    });
+                }
+                let mut children: Box<[Node; 16]> = Default::default();
+                children[ext_path[common] as usize] =
+                    with_extension(&ext_path[common + 1..], *child);
+                let branch = Self::Branch { children }.insert(&path[common..], value)?;
+                with_extension(&path[..common], branch)
+            }
+            Self::Branch { mut children } => {
+                let nibble = path[0] as usize;
+                let child = std::mem::take(&mut children[nibble]);
+                children[nibble] = child.insert(&path[1..], value)?;
+                Self::Branch { children }
+            }
+            Self::Hash(_) => return Err(MptWitnessError::InvalidProof),
+        })
+    }
+
+    /// Returns the node without the value stored at the remaining `path`,
+    /// collapsing the branches left with a single child.
+    fn remove(self, path: &[u8]) -> Result<Self, MptWitnessError> {
+        Ok(match self {
+            Self::Empty => Self::Empty,
+            Self::Leaf {
+                path: leaf_path,
+                value,
+            } => {
+                if leaf_path == path {
+                    Self::Empty
+                } else {
+                    Self::Leaf {
+                        path: leaf_path,
+                        value,
+                    }
+                }
+            }
+            Self::Extension {
+                path: ext_path,
+                child,
+            } => {
+                if !path.starts_with(&ext_path) {
+                    return Ok(Self::Extension {
+                        path: ext_path,
+                        child,
+                    });
+                }
+                let child = child.remove(&path[ext_path.len()..])?;
+                with_extension(&ext_path, child)
+            }
+            Self::Branch { mut children } => {
+                let nibble = path[0] as usize;
+                let child = std::mem::take(&mut children[nibble]);
+                children[nibble] = child.remove(&path[1..])?;
+
+                let remaining: Vec<usize> = (0..16)
+                    .filter(|&nibble| !matches!(children[nibble], Self::Empty))
+                    .collect();
+                if let [nibble] = remaining[..] {
+                    // The remaining child is merged into the extension, which
+                    // needs to know whether it's a branch.
+                    if let Self::Hash(_) = children[nibble] {
+                        return Err(MptWitnessError::InvalidProof);
+                    }
+                    let child = std::mem::take(&mut children[nibble]);
+                    with_extension(&[nibble as u8], child)
+                } else {
+                    Self::Branch { children }
+                }
+            }
+            Self::Hash(_) => return Err(MptWitnessError::InvalidProof),
+        })
+    }
+
+    /// Appends the encodings of the nodes on the remaining `path` to `proof`.
+    fn prove(&self, path: &[u8], proof: &mut Vec<Vec<u8>>) -> Result<(), MptWitnessError> {
+        match self {
+            Self::Empty => {}
+            Self::Leaf { .. } => proof.push(self.encode()),
+            Self::Extension {
+                path: ext_path,
+                child,
+            } => {
+                proof.push(self.encode());
+                if path.starts_with(ext_path) {
+                    child.prove(&path[ext_path.len()..], proof)?;
+                }
+            }
+            Self::Branch { children } => {
+                proof.push(self.encode());
+                children[path[0] as usize].prove(&path[1..], proof)?;
+            }
+            Self::Hash(_) => return Err(MptWitnessError::InvalidProof),
+        }
+        Ok(())
+    }
+
+    /// Returns the value stored at the remaining `path`.
+    fn get(&self, path: &[u8]) -> Result<Option<&[u8]>, MptWitnessError> {
+        match self {
+            Self::Empty => Ok(None),
+            Self::Leaf {
+                path: leaf_path,
+                value,
+            } => Ok((leaf_path == path).then(|| &value[..])),
+            Self::Extension {
+                path: ext_path,
+                child,
+            } => {
+                if path.starts_with(ext_path) {
+                    child.get(&path[ext_path.len()..])
+                } else {
+                    Ok(None)
+                }
+            }
+            Self::Branch { children } => children[path[0] as usize].get(&path[1..]),
+            Self::Hash(_) => Err(MptWitnessError::InvalidProof),
+        }
+    }
+}
+
+/// Returns `node` behind an extension of `path`, merging the nibbles into the
+/// path of a leaf or an extension node.  A node known by its hash is taken for
+/// a branch, being the child of an extension.
+fn with_extension(path: &[u8], node: Node) -> Node {
+    if path.is_empty() {
+        return node;
+    }
+    match node {
+        Node::Empty => Node::Empty,
+        Node::Leaf {
+            path: leaf_path,
+            value,
+        } => Node::Leaf {
+            path: [path, &leaf_path].concat(),
+            value,
+        },
+        Node::Extension {
+            path: ext_path,
+            child,
+        } => Node::Extension {
+            path: [path, &ext_path].concat(),
+            child,
+        },
+        branch => Node::Extension {
+            path: path.to_vec(),
+            child: Box::new(branch),
+        },
+    }
+}
+
+/// Appends the reference to a child node, which is the node itself when its
+/// encoding is shorter than 32 bytes and the hash of its encoding otherwise.
+fn append_child(stream: &mut RlpStream, child: &Node) {
+    match child {
+        Node::Empty => {
+            stream.append_empty_data();
+            return;
+        }
+        Node::Hash(hash) => {
+            stream.append(hash);
+            return;
+        }
+        _ => {}
+    }
+    let encoding = child.encode();
+    if encoding.len() < 32 {
+        stream.append_raw(&encoding, 1);
+    } else {
+        stream.append(&H256(keccak256(&encoding)));
+    }
+}
+
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(a, b)| a == b).count()
+}
+
+/// Returns the hex-prefix encoding of a nibble path.
+fn hex_prefix(path: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 2 } else { 0 };
+    let (first, rest) = if path.len() % 2 == 1 {
+        (((flag + 1) << 4) | path[0], &path[1..])
+    } else {
+        (flag << 4, path)
+    };
+    std::iter::once(first)
+        .chain(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]))
+        .collect()
+}
+
+/// An in-memory Merkle Patricia Trie.
+#[derive(Clone, Debug, Default)]
+pub struct Trie {
+    root: Node,
+}
+
+impl Trie {
+    /// Returns the trie of root `root` known from the encodings of the
+    /// `nodes` of the proofs of some of its keys, the other nodes being known
+    /// by their hash only.
+    pub fn from_proofs<'a>(
+        root: &H256,
+        nodes: impl IntoIterator<Item = &'a [u8]>,
+    ) -> Result<Self, MptWitnessError> {
+        if *root == Self::default().root() {
+            return Ok(Self::default());
+        }
+        let nodes = nodes
+            .into_iter()
+            .map(|encoding| (H256(keccak256(encoding)), encoding))
+            .collect();
+        Ok(Self {
+            root: Node::from_hash(*root, &nodes)?,
+        })
+    }
+
+    /// Returns the root hash of the trie.
+    pub fn root(&self) -> H256 {
+        match &self.root {
+            Node::Hash(hash) => *hash,
+            root => H256(keccak256(&root.encode())),
+        }
+    }
+
+    /// Stores `value` at `key`, removing the key when `value` is empty.  The
+    /// trie is left unchanged when the update needs a node known by its hash
+    /// only.
+    pub fn insert(&mut self, key: &H256, value: Vec<u8>) -> Result<(), MptWitnessError> {
+        let path = key_nibbles(key);
+        self.root = if value.is_empty() {
+            self.root.clone().remove(&path)?
+        } else {
+            self.root.clone().insert(&path, value)?
+        };
+        Ok(())
+    }
+
+    /// Returns the value stored at `key`.
+    pub fn get(&self, key: &H256) -> Result<Option<&[u8]>, MptWitnessError> {
+        self.root.get(&key_nibbles(key))
+    }
+
+    /// Returns the encodings of the nodes from the root on the path of `key`,
+    /// including the nodes inlined into their parent.
+    pub fn prove(&self, key: &H256) -> Result<Vec<Vec<u8>>, MptWitnessError> {
+        let mut proof = Vec::new();
+        self.root.prove(&key_nibbles(key), &mut proof)?;
+        Ok(proof)
+    }
+}
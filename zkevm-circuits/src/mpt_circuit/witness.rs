@@ -0,0 +1,737 @@
+//! Witness of the MPT circuit: the trie updates, each proven by the trie nodes
+//! on the path of its key before and after the update, and the rows laid out
+//! for every path.
+
+use eth_types::{Address, Field, Rlc, ToBigEndian, ToLittleEndian, ToScalar, Word, H256};
+use ethers_core::utils::keccak256;
+use rlp::Rlp;
+use strum_macros::EnumIter;
+
+use super::rlp::{rlp_decoder_rows, RlpDecoderRow};
+use crate::{evm_circuit::util::RandomLinearCombination, impl_expr};
+
+/// Number of nibbles of the keys of the trie, which is the number of rows of
+/// every path.
+pub const KEY_NIBBLES: usize = 64;
+
+/// Number of nibbles of an address, laid out on the first rows of every path
+/// to derive the key of its account.
+pub const ADDRESS_NIBBLES: usize = 40;
+
+/// Type of the trie update, telling which value of the account is modified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, EnumIter)]
+pub enum MptProofType {
+    /// Modification of the nonce of an account.
+    NonceMod = 1,
+    /// Modification of the balance of an account.
+    BalanceMod,
+    /// Modification of the code hash of an account.
+    CodeHashMod,
+    /// Modification of a storage slot of an account, which also modifies the
+    /// storage root of the account.
+    StorageMod,
+}
+
+impl From<MptProofType> for usize {
+    fn from(t: MptProofType) -> Self {
+        t as usize
+    }
+}
+
+impl Default for MptProofType {
+    fn default() -> Self {
+        Self::NonceMod
+    }
+}
+
+impl_expr!(MptProofType);
+
+/// The proof types modifying the fields of an account, in the order of the
+/// fields in the encoding of the account.
+pub(crate) const ACCOUNT_FIELDS: [MptProofType; 4] = [
+    MptProofType::NonceMod,
+    MptProofType::BalanceMod,
+    MptProofType::StorageMod,
+    MptProofType::CodeHashMod,
+];
+
+/// Type of the trie node a row of a path belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter)]
+pub enum MptNodeType {
+    /// A branch node, which takes one row for the nibble of its child.
+    Branch,
+    /// An extension node, which takes one row for each nibble of its path.
+    Extension,
+    /// A leaf node, which takes the rows of the remaining nibbles of the key.
+    Leaf,
+}
+
+impl From<MptNodeType> for usize {
+    fn from(t: MptNodeType) -> Self {
+        t as usize
+    }
+}
+
+impl Default for MptNodeType {
+    fn default() -> Self {
+        Self::Leaf
+    }
+}
+
+/// Error returned when the witness of a trie update can't be laid out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MptWitnessError {
+    /// A proof node isn't the RLP encoding of a branch, extension or leaf
+    /// node.
+    InvalidNode,
+    /// A proof doesn't link the root to the leaf of the key (or to the empty
+    /// child in its place).
+    InvalidProof,
+    /// The value of the leaf doesn't match the value of the update.
+    ValueMismatch,
+    /// The update changes the nodes on the path of the key other than its
+    /// leaf, by splitting or collapsing a node, puts a leaf below a branch at
+    /// the last nibble of the key, or has no value in both tries.
+    UnsupportedUpdate,
+    /// The updates of a block don't lead to the state root of the block.
+    InvalidStateRoot,
+}
+
+/// Proof of the update of the value at `key` of a trie, given by the encodings
+/// of the nodes on the path of the key, starting from the root and including
+/// the nodes inlined into their parent.  A missing leaf is proven by the
+/// branch holding an empty child in its place, or by no nodes at all for the
+/// empty trie.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MptProof {
+    /// The key of the updated value in the trie, which is the hash of the
+    /// address or of the storage key.
+    pub key: H256,
+    /// The root of the trie before the update.
+    pub old_root: H256,
+    /// The root of the trie after the update.
+    pub new_root: H256,
+    /// The nodes on the path of the key before the update.
+    pub old_nodes: Vec<Vec<u8>>,
+    /// The nodes on the path of the key after the update.
+    pub new_nodes: Vec<Vec<u8>>,
+}
+
+/// An update of the state trie, modifying one value of an account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MptUpdate {
+    /// The address of the account.
+    pub address: Address,
+    /// The storage key of the slot, or zero for any other proof type.
+    pub storage_key: Word,
+    /// The type of the update.
+    pub proof_type: MptProofType,
+    /// The value before the update.
+    pub old_value: Word,
+    /// The value after the update.
+    pub new_value: Word,
+    /// The proof of the storage trie update for [`MptProofType::StorageMod`].
+    pub storage_proof: Option<MptProof>,
+    /// The proof of the account trie update.
+    pub account_proof: MptProof,
+}
+
+/// The path of a key in a trie, laid out as one row per nibble of the key.
+#[derive(Clone, Debug)]
+pub struct MptPath<F> {
+    /// The type of the update the path belongs to.
+    pub proof_type: MptProofType,
+    /// Whether the path is in a storage trie, in which case it's followed by
+    /// the path of its account in the account trie.
+    pub is_storage: bool,
+    /// The address of the account.
+    pub address: F,
+    /// The RLC of the storage key for a storage update, or 0 otherwise.
+    pub storage_key: F,
+    /// The RLC of the root of the trie before the update.
+    pub old_root: F,
+    /// The RLC of the root of the trie after the update.
+    pub new_root: F,
+    /// The RLC of the root of the state trie before the update.
+    pub old_state_root: F,
+    /// The RLC of the root of the state trie after the update.
+    pub new_state_root: F,
+    /// The RLC of the key, as a little endian word.
+    pub key_rlc: F,
+    /// The value at the key before the update: the storage value, the
+    /// modified account field, or the storage root of the account for a
+    /// storage update.
+    pub old_value: F,
+    /// The value at the key after the update.
+    pub new_value: F,
+    /// Whether the key has no value before the update.
+    pub is_old_empty: bool,
+    /// Whether the key has no value after the update.
+    pub is_new_empty: bool,
+    /// RLC of the encoding of the leaf value before the update.
+    pub old_leaf_rlc: F,
+    /// Length of the encoding of the leaf value before the update.
+    pub old_leaf_len: usize,
+    /// RLC of the encoding of the leaf value after the update.
+    pub new_leaf_rlc: F,
+    /// Length of the encoding of the leaf value after the update.
+    pub new_leaf_len: usize,
+    /// RLCs of the fields of the account for an account path, which are the
+    /// same before and after the update except for the modified field.
+    pub account_fields: [F; 4],
+    /// Lengths of the fields of the account for an account path.
+    pub account_field_lens: [usize; 4],
+    /// The rows of the path, one for each nibble of the key.
+    pub rows: Vec<MptRow<F>>,
+    /// The rows of the RLP decoder for the nodes and the leaf values of the
+    /// path.
+    pub rlp_rows: Vec<RlpDecoderRow<F>>,
+    /// The storage key or the address hashed into the key of the path,
+    /// followed by the encodings of the nodes of the path referenced by their
+    /// hash, which are the inputs of the keccak table.
+    pub keccak_inputs: Vec<Vec<u8>>,
+}
+
+/// A row of a path in the trie.
+#[derive(Clone, Debug)]
+pub struct MptRow<F> {
+    /// The type of the node of the row.
+    pub node_type: MptNodeType,
+    /// The nibble of the key at the depth of the row.
+    pub nibble: u8,
+    /// RLC of the key nibbles up to the depth of the row.
+    pub key_rlc_acc: F,
+    /// Multiplier of the nibble in the RLC of the key.
+    pub key_rlc_mult: F,
+    /// The nibble of the address at the depth of the row, or 0 below the
+    /// nibbles of the address.
+    pub address_nibble: u8,
+    /// The address nibbles up to the depth of the row.
+    pub address_acc: F,
+    /// RLC of the address bytes up to the depth of the row.
+    pub address_rlc_acc: F,
+    /// Reference to the node before the update.
+    pub old_ref: F,
+    /// Reference to the node after the update.
+    pub new_ref: F,
+    /// RLC of the encoding of the node before the update.
+    pub old_node_rlc: F,
+    /// Length of the encoding of the node before the update.
+    pub old_node_len: usize,
+    /// RLC of the encoding of the node after the update.
+    pub new_node_rlc: F,
+    /// Length of the encoding of the node after the update.
+    pub new_node_len: usize,
+    /// Whether the node before the update is inlined into its parent.
+    pub is_old_inline: bool,
+    /// Whether the node after the update is inlined into its parent.
+    pub is_new_inline: bool,
+    /// RLC of the nibbles of the extension or leaf node up to the row.
+    pub node_path_rlc: F,
+    /// Number of nibbles of the extension or leaf node up to the row.
+    pub node_path_len: usize,
+    /// Reference to the child of the extension node before the update.
+    pub old_ext_child: F,
+    /// Reference to the child of the extension node after the update.
+    pub new_ext_child: F,
+    /// References to the children of the branch node before the update.
+    pub old_children: [F; 16],
+    /// References to the children of the branch node after the update.
+    pub new_children: [F; 16],
+}
+
+impl<F> MptPath<F> {
+    /// Returns true if the path is the first one of its update, whose first
+    /// row holds the update in the mpt table.
+    pub fn is_first_of_update(&self) -> bool {
+        self.is_storage || self.proof_type != MptProofType::StorageMod
+    }
+}
+
+/// A node on the path of a key, starting at `depth`.
+struct Segment<F> {
+    node_type: MptNodeType,
+    depth: usize,
+    node_ref: F,
+    node_rlc: F,
+    node_len: usize,
+    is_inline: bool,
+    ext_child: F,
+    children: [F; 16],
+}
+
+impl MptUpdate {
+    /// Returns the paths laid out for the update, which are the path of the
+    /// storage trie followed by the path of the account trie for a storage
+    /// update, and the path of the account trie otherwise.
+    pub fn paths<F: Field>(&self, randomness: F) -> Result<Vec<MptPath<F>>, MptWitnessError> {
+        let state_roots = (self.account_proof.old_root, self.account_proof.new_root);
+        let keys = (&self.address, &self.storage_key);
+        let mut paths = Vec::with_capacity(2);
+        let account_values = match (self.proof_type, &self.storage_proof) {
+            (MptProofType::StorageMod, Some(storage_proof)) => {
+                paths.push(storage_proof.path(
+                    self.proof_type,
+                    true,
+                    keys,
+                    (self.old_value, self.new_value),
+                    state_roots,
+                    randomness,
+                )?);
+                (
+                    Word::from_big_endian(storage_proof.old_root.as_bytes()),
+                    Word::from_big_endian(storage_proof.new_root.as_bytes()),
+                )
+            }
+            (MptProofType::StorageMod, None) | (_, Some(_)) => {
+                return Err(MptWitnessError::InvalidProof)
+            }
+            _ => (self.old_value, self.new_value),
+        };
+        paths.push(self.account_proof.path(
+            self.proof_type,
+            false,
+            keys,
+            account_values,
+            state_roots,
+            randomness,
+        )?);
+        Ok(paths)
+    }
+}
+
+impl MptProof {
+    fn path<F: Field>(
+        &self,
+        proof_type: MptProofType,
+        is_storage: bool,
+        (address, storage_key): (&Address, &Word),
+        (old_value, new_value): (Word, Word),
+        (old_state_root, new_state_root): (H256, H256),
+        randomness: F,
+    ) -> Result<MptPath<F>, MptWitnessError> {
+        let preimage = if is_storage {
+            storage_key.to_be_bytes().to_vec()
+        } else {
+            address.as_bytes().to_vec()
+        };
+        if H256(keccak256(&preimage)) != self.key {
+            return Err(MptWitnessError::InvalidProof);
+        }
+        let key = key_nibbles(&self.key);
+        let address_nibbles = key_nibbles(&H256::from(*address));
+        let (old_segments, old_leaf) = segments(&self.old_root, &self.old_nodes, &key, randomness)?;
+        let (new_segments, new_leaf) = segments(&self.new_root, &self.new_nodes, &key, randomness)?;
+
+        if (old_leaf.is_none() && new_leaf.is_none())
+            || old_segments.len() != new_segments.len()
+            || old_segments
+                .iter()
+                .zip(new_segments.iter())
+                .any(|(old, new)| (old.node_type, old.depth) != (new.node_type, new.depth))
+        {
+            return Err(MptWitnessError::UnsupportedUpdate);
+        }
+        for (leaf, value) in [(&old_leaf, old_value), (&new_leaf, new_value)] {
+            let leaf_value = match leaf {
+                Some(leaf) => leaf_value(leaf, proof_type, is_storage)?,
+                None => Word::zero(),
+            };
+            if leaf_value != value {
+                return Err(MptWitnessError::ValueMismatch);
+            }
+        }
+
+        let value_field = |value: Word| match proof_type {
+            MptProofType::NonceMod => value.to_scalar().unwrap(),
+            _ => RandomLinearCombination::random_linear_combine(value.to_le_bytes(), randomness),
+        };
+
+        let (account_fields, account_field_lens) = if is_storage {
+            ([F::zero(); 4], [0; 4])
+        } else {
+            account_fields(&old_leaf, &new_leaf, proof_type, randomness)?
+        };
+        let leaf_rlc = |leaf: &Option<Vec<u8>>| {
+            leaf.as_ref().map_or((F::zero(), 0), |leaf| {
+                (leaf.rlc_stream(randomness), leaf.len())
+            })
+        };
+        let (old_leaf_rlc, old_leaf_len) = leaf_rlc(&old_leaf);
+        let (new_leaf_rlc, new_leaf_len) = leaf_rlc(&new_leaf);
+
+        let mut rlp_rows = Vec::new();
+        for encoding in self
+            .old_nodes
+            .iter()
+            .chain(self.new_nodes.iter())
+            .chain(old_leaf.iter())
+            .chain(new_leaf.iter())
+        {
+            rlp_rows.extend(rlp_decoder_rows(encoding, randomness)?);
+        }
+        // The root is referenced by its hash, and the other nodes when their
+        // encoding is at least 32 bytes long.
+        let keccak_inputs = std::iter::once(preimage)
+            .chain(
+                [&self.old_nodes, &self.new_nodes]
+                    .into_iter()
+                    .flat_map(|nodes| {
+                        nodes
+                            .iter()
+                            .enumerate()
+                            .filter(|(idx, encoding)| *idx == 0 || encoding.len() >= 32)
+                            .map(|(_, encoding)| encoding.clone())
+                    }),
+            )
+            .collect();
+
+        let mut key_rlc_acc = F::zero();
+        let (mut address_acc, mut address_rlc_acc) = (F::zero(), F::zero());
+        let mut node_path = (F::zero(), 0);
+        let rows = (0..KEY_NIBBLES)
+            .map(|depth| {
+                let idx = old_segments
+                    .iter()
+                    .rposition(|segment| segment.depth <= depth)
+                    .unwrap();
+                let (old, new) = (&old_segments[idx], &new_segments[idx]);
+                let nibble = key[depth];
+                let key_rlc_mult = randomness.pow(&[31 - depth as u64 / 2, 0, 0, 0])
+                    * if depth % 2 == 0 {
+                        F::from(16)
+                    } else {
+                        F::one()
+                    };
+                key_rlc_acc += F::from(nibble as u64) * key_rlc_mult;
+                // The nibbles of the address are the last 40 nibbles of the
+                // address as a word.
+                let address_nibble = address_nibbles
+                    .get(KEY_NIBBLES - ADDRESS_NIBBLES + depth)
+                    .copied()
+                    .unwrap_or_default();
+                if depth < ADDRESS_NIBBLES {
+                    address_acc = address_acc * F::from(16) + F::from(address_nibble as u64);
+                    address_rlc_acc = if depth % 2 == 0 {
+                        address_rlc_acc * randomness + F::from(16 * address_nibble as u64)
+                    } else {
+                        address_rlc_acc + F::from(address_nibble as u64)
+                    };
+                }
+                // The nibbles of an extension or a leaf start at its depth.
+                node_path = match old.node_type {
+                    MptNodeType::Branch => (F::zero(), 0),
+                    _ if depth == old.depth => (F::from(nibble as u64), 1),
+                    _ => (
+                        node_path.0 * randomness + F::from(nibble as u64),
+                        node_path.1 + 1,
+                    ),
+                };
+                MptRow {
+                    node_type: old.node_type,
+                    nibble,
+                    key_rlc_acc,
+                    key_rlc_mult,
+                    address_nibble,
+                    address_acc,
+                    address_rlc_acc,
+                    old_ref: old.node_ref,
+                    new_ref: new.node_ref,
+                    old_node_rlc: old.node_rlc,
+                    old_node_len: old.node_len,
+                    new_node_rlc: new.node_rlc,
+                    new_node_len: new.node_len,
+                    is_old_inline: old.is_inline,
+                    is_new_inline: new.is_inline,
+                    node_path_rlc: node_path.0,
+                    node_path_len: node_path.1,
+                    old_ext_child: old.ext_child,
+                    new_ext_child: new.ext_child,
+                    old_children: old.children,
+                    new_children: new.children,
+                }
+            })
+            .collect();
+
+        Ok(MptPath {
+            proof_type,
+            is_storage,
+            address: address.to_scalar().unwrap(),
+            storage_key: RandomLinearCombination::random_linear_combine(
+                storage_key.to_le_bytes(),
+                randomness,
+            ),
+            old_root: hash_rlc(&self.old_root, randomness),
+            new_root: hash_rlc(&self.new_root, randomness),
+            old_state_root: hash_rlc(&old_state_root, randomness),
+            new_state_root: hash_rlc(&new_state_root, randomness),
+            key_rlc: key_rlc_acc,
+            old_value: value_field(old_value),
+            new_value: value_field(new_value),
+            is_old_empty: old_leaf.is_none(),
+            is_new_empty: new_leaf.is_none(),
+            old_leaf_rlc,
+            old_leaf_len,
+            new_leaf_rlc,
+            new_leaf_len,
+            account_fields,
+            account_field_lens,
+            rows,
+            rlp_rows,
+            keccak_inputs,
+        })
+    }
+}
+
+/// Returns the nodes on the path of the key and the value of its leaf, if
+/// any.  A missing leaf is returned as an empty leaf taking the remaining
+/// nibbles of the key.
+fn segments<F: Field>(
+    root: &H256,
+    nodes: &[Vec<u8>],
+    key: &[u8; KEY_NIBBLES],
+    randomness: F,
+) -> Result<(Vec<Segment<F>>, Option<Vec<u8>>), MptWitnessError> {
+    if nodes.is_empty() && *root != H256(keccak256(&[0x80])) {
+        return Err(MptWitnessError::InvalidProof);
+    }
+
+    let mut segments = Vec::new();
+    let mut depth = 0;
+    // The reference to the next node on the path, or `None` once the leaf or
+    // the empty child in its place is reached.
+    let mut next_ref = Some(hash_rlc(root, randomness));
+    let mut leaf = None;
+    for (idx, encoding) in nodes.iter().enumerate() {
+        // The root is always referenced by its hash.
+        let is_inline = idx > 0 && encoding.len() < 32;
+        let node_ref = if is_inline {
+            encoding.rlc_stream(randomness)
+        } else {
+            hash_rlc(&H256(keccak256(encoding)), randomness)
+        };
+        if next_ref != Some(node_ref) {
+            return Err(MptWitnessError::InvalidProof);
+        }
+        let (node_rlc, node_len) = (encoding.rlc_stream(randomness), encoding.len());
+
+        let node = Rlp::new(encoding);
+        match node
+            .item_count()
+            .map_err(|_| MptWitnessError::InvalidNode)?
+        {
+            17 => {
+                // A branch at the last nibble would be followed by a leaf
+                // without any nibble left, which can't be laid out.
+                if depth >= KEY_NIBBLES - 1 {
+                    return Err(MptWitnessError::UnsupportedUpdate);
+                }
+                let mut children = [F::zero(); 16];
+                for (idx, child) in children.iter_mut().enumerate() {
+                    *child = child_ref(&item(&node, idx)?, randomness)?;
+                }
+                let child = children[key[depth] as usize];
+                next_ref = if child == F::zero() {
+                    None
+                } else {
+                    Some(child)
+                };
+                segments.push(Segment {
+                    node_type: MptNodeType::Branch,
+                    depth,
+                    node_ref,
+                    node_rlc,
+                    node_len,
+                    is_inline,
+                    ext_child: F::zero(),
+                    children,
+                });
+                depth += 1;
+            }
+            2 => {
+                let (path, is_leaf) = decode_hex_prefix(data(&item(&node, 0)?)?)?;
+                // A path diverging from the key proves its absence, but
+                // inserting it would split the node.
+                if !key[depth..].starts_with(&path) {
+                    return Err(MptWitnessError::UnsupportedUpdate);
+                }
+                let (node_type, ext_child) = if is_leaf {
+                    if depth + path.len() != KEY_NIBBLES {
+                        return Err(MptWitnessError::InvalidNode);
+                    }
+                    leaf = Some(data(&item(&node, 1)?)?.to_vec());
+                    next_ref = None;
+                    (MptNodeType::Leaf, F::zero())
+                } else {
+                    if path.is_empty() {
+                        return Err(MptWitnessError::InvalidNode);
+                    }
+                    let ext_child = child_ref(&item(&node, 1)?, randomness)?;
+                    next_ref = Some(ext_child);
+                    (MptNodeType::Extension, ext_child)
+                };
+                segments.push(Segment {
+                    node_type,
+                    depth,
+                    node_ref,
+                    node_rlc,
+                    node_len,
+                    is_inline,
+                    ext_child,
+                    children: [F::zero(); 16],
+                });
+                depth += path.len();
+            }
+            _ => return Err(MptWitnessError::InvalidNode),
+        }
+    }
+
+    match (&leaf, next_ref) {
+        (Some(_), _) => {}
+        (None, None) => segments.push(Segment {
+            node_type: MptNodeType::Leaf,
+            depth,
+            node_ref: F::zero(),
+            node_rlc: F::zero(),
+            node_len: 0,
+            is_inline: false,
+            ext_child: F::zero(),
+            children: [F::zero(); 16],
+        }),
+        // The empty trie has no nodes.
+        (None, Some(_)) if nodes.is_empty() => segments.push(Segment {
+            node_type: MptNodeType::Leaf,
+            depth: 0,
+            node_ref: F::zero(),
+            node_rlc: F::zero(),
+            node_len: 0,
+            is_inline: false,
+            ext_child: F::zero(),
+            children: [F::zero(); 16],
+        }),
+        (None, Some(_)) => return Err(MptWitnessError::InvalidProof),
+    }
+
+    Ok((segments, leaf))
+}
+
+/// Returns the value of the leaf checked by an update: the storage value for
+/// a storage trie, or the account field modified by the update.
+fn leaf_value(
+    leaf: &[u8],
+    proof_type: MptProofType,
+    is_storage: bool,
+) -> Result<Word, MptWitnessError> {
+    let value = if is_storage {
+        data(&Rlp::new(leaf))?.to_vec()
+    } else {
+        let field_idx = ACCOUNT_FIELDS
+            .iter()
+            .position(|field| *field == proof_type)
+            .unwrap();
+        data(&item(&Rlp::new(leaf), field_idx)?)?.to_vec()
+    };
+    if value.len() > 32 {
+        return Err(MptWitnessError::InvalidNode);
+    }
+    Ok(Word::from_big_endian(&value))
+}
+
+/// Returns the RLCs and the lengths of the fields of an account, checking that
+/// the fields other than the modified one are the same before and after the
+/// update.
+fn account_fields<F: Field>(
+    old_leaf: &Option<Vec<u8>>,
+    new_leaf: &Option<Vec<u8>>,
+    proof_type: MptProofType,
+    randomness: F,
+) -> Result<([F; 4], [usize; 4]), MptWitnessError> {
+    let mut fields = [F::zero(); 4];
+    let mut lens = [0; 4];
+    for (idx, field) in ACCOUNT_FIELDS.iter().enumerate() {
+        let values = [old_leaf, new_leaf]
+            .into_iter()
+            .flatten()
+            .map(|leaf| Ok(data(&item(&Rlp::new(leaf), idx)?)?.to_vec()))
+            .collect::<Result<Vec<_>, MptWitnessError>>()?;
+        if *field != proof_type && values.windows(2).any(|pair| pair[0] != pair[1]) {
+            return Err(MptWitnessError::ValueMismatch);
+        }
+        if let Some(value) = values.first() {
+            fields[idx] = value.rlc(randomness);
+            lens[idx] = value.len();
+        }
+    }
+    Ok((fields, lens))
+}
+
+/// Returns the RLC of a hash as a little endian word, which is the output of
+/// the keccak table.
+pub(crate) fn hash_rlc<F: Field>(hash: &H256, randomness: F) -> F {
+    RandomLinearCombination::random_linear_combine(
+        Word::from_big_endian(hash.as_bytes()).to_le_bytes(),
+        randomness,
+    )
+}
+
+/// Returns the reference to a child node, or zero for an empty child.  A child
+/// inlined into its parent is referenced by the RLC of its encoding, and any
+/// other child by the RLC of its hash.
+fn child_ref<F: Field>(child: &Rlp, randomness: F) -> Result<F, MptWitnessError> {
+    if child.is_list() {
+        return Ok(child.as_raw().rlc_stream(randomness));
+    }
+    let hash = data(child)?;
+    match hash.len() {
+        0 => Ok(F::zero()),
+        32 => Ok(hash_rlc(&H256::from_slice(hash), randomness)),
+        _ => Err(MptWitnessError::InvalidNode),
+    }
+}
+
+pub(crate) fn item<'a>(node: &Rlp<'a>, idx: usize) -> Result<Rlp<'a>, MptWitnessError> {
+    node.at(idx).map_err(|_| MptWitnessError::InvalidNode)
+}
+
+pub(crate) fn data<'a>(item: &Rlp<'a>) -> Result<&'a [u8], MptWitnessError> {
+    item.data().map_err(|_| MptWitnessError::InvalidNode)
+}
+
+/// Decodes a hex-prefix encoded path into its nibbles and leaf flag.
+pub(crate) fn decode_hex_prefix(bytes: &[u8]) -> Result<(Vec<u8>, bool), MptWitnessError> {
+    let (&first, rest) = bytes.split_first().ok_or(MptWitnessError::InvalidNode)?;
+    let flag = first >> 4;
+    if flag > 3 || (flag & 1 == 0 && first & 0xf != 0) {
+        return Err(MptWitnessError::InvalidNode);
+    }
+    let mut path = Vec::with_capacity(2 * bytes.len());
+    if flag & 1 == 1 {
+        path.push(first & 0xf);
+    }
+    for byte in rest {
+        path.extend([byte >> 4, byte & 0xf]);
+    }
+    Ok((path, flag & 2 == 2))
+}
+
+/// Returns the key of the account at `address` in the state trie.
+pub fn account_trie_key(address: &Address) -> H256 {
+    H256(keccak256(address))
+}
+
+/// Returns the key of the slot at `storage_key` in the storage trie of its
+/// account.
+pub fn storage_trie_key(storage_key: &Word) -> H256 {
+    H256(keccak256(storage_key.to_be_bytes()))
+}
+
+/// Returns the nibbles of a key, starting from the most significant nibble of
+/// the first byte.
+pub(crate) fn key_nibbles(key: &H256) -> [u8; KEY_NIBBLES] {
+    let mut nibbles = [0; KEY_NIBBLES];
+    for (idx, byte) in key.as_bytes().iter().enumerate() {
+        nibbles[2 * idx] = byte >> 4;
+        nibbles[2 * idx + 1] = byte & 0xf;
+    }
+    nibbles
+}
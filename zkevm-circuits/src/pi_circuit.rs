@@ -0,0 +1,817 @@
+//! The public input circuit implementation.
+//!
+//! The PI circuit proves the public data of a chunk of consecutive blocks.  It
+//! assigns the block table of each block and the tx table of their
+//! transactions, looked up by the other circuits, and the withdrawal table of
+//! the withdrawals (EIP-4895) credited after the transactions.  It copies every
+//! cell of these tables into the raw public inputs, along with the state roots
+//! before and after each block, the hash of each block and its blob gas fields
+//! (EIP-4844), and chains the
+//! blocks by constraining each of them to follow the previous one: its parent
+//! hash is the hash of the previous block, at the previous block number, and
+//! its state root before the block is the state root after the previous one.
+//!
+//! The raw public inputs are hashed with a keccak table lookup, each of them as
+//! the 32 bytes of its big endian representation, and only the digest is
+//! exposed as the instance, split into its high and low 128 bits.  The
+//! verifier knowing the public data of the blocks checks the content of the
+//! tables from these two instance values.
+//!
+//! The digest is bound to the raw public inputs by the keccak circuit, which
+//! proves the rows of the keccak table in the super circuit.  The standalone
+//! circuit loads the keccak table without proving it, so that its proofs only
+//! bind the digest for testing.
+
+use crate::evm_circuit::table::LookupTable;
+use crate::evm_circuit::util::constraint_builder::BaseConstraintBuilder;
+use crate::table::{BlockTable, KeccakTable, TxTable};
+use crate::tx_circuit::sign_verify::{
+    KECCAK_INPUT_LEN, KECCAK_INPUT_RLC, KECCAK_IS_ENABLED, KECCAK_OUTPUT_RLC,
+};
+use crate::util::Expr;
+use bus_mapping::circuit_input_builder::CircuitsParams;
+use eth_types::Field;
+use ethers_core::utils::keccak256;
+use gadgets::util::not;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance},
+    poly::Rotation,
+};
+use itertools::Itertools;
+use std::marker::PhantomData;
+
+pub use eth_types::public_data::{BlockPublicData, PublicData};
+
+/// Number of bytes of a raw public input in the hashed public data.
+const N_BYTES_RPI: usize = 32;
+
+/// Row of the block number in the block table.
+const BLOCK_NUMBER_ROW: usize = 3;
+/// Row of the hash of the parent block in the block table, the first of the
+/// history hashes.
+const PARENT_HASH_ROW: usize = 8;
+/// Number of raw public inputs of a block following the cells of its block
+/// table: the state roots before and after the block, the hash of the block,
+/// the blob gas used by the block and its excess blob gas.
+const N_BLOCK_EXTRA_RPIS: usize = 5;
+
+/// Config for PiCircuit
+#[derive(Clone, Debug)]
+pub struct PiCircuitConfig<F: Field> {
+    block_table: BlockTable,
+    tx_table: TxTable,
+    // [index, validator_index, address, amount]
+    withdrawal_table: [Column<Advice>; 4],
+    keccak_table: KeccakTable,
+    // Whether the row holds a byte of the raw public inputs or of the digest.
+    q_enable: Column<Fixed>,
+    // Whether the row holds the first byte of the hashed bytes or of the
+    // digest, where rpi_rlc_acc starts.
+    q_rlc_start: Column<Fixed>,
+    // Whether the row holds the first byte of a raw public input or of a half
+    // of the digest, where rpi_bytes_acc starts.
+    q_acc_start: Column<Fixed>,
+    // Whether the row holds the last byte of the digest, which looks up the
+    // digest in the keccak table.
+    q_keccak: Column<Fixed>,
+    // The number of hashed bytes at the q_keccak row.
+    rpi_len: Column<Fixed>,
+    rpi_bytes: Column<Advice>,
+    rpi_bytes_acc: Column<Advice>,
+    rpi_rlc_acc: Column<Advice>,
+    u8_table: Column<Fixed>,
+    // [digest_hi, digest_lo]
+    pi: Column<Instance>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> PiCircuitConfig<F> {
+    /// Configure the PI circuit assigning the `block_table`, the `tx_table`
+    /// and the `withdrawal_table`, and hashing the public inputs with
+    /// `keccak_table`.
+    pub fn new(
+        meta: &mut ConstraintSystem<F>,
+        block_table: BlockTable,
+        tx_table: TxTable,
+        withdrawal_table: [Column<Advice>; 4],
+        keccak_table: KeccakTable,
+        randomness: Expression<F>,
+    ) -> Self {
+        let q_enable = meta.fixed_column();
+        let q_rlc_start = meta.fixed_column();
+        let q_acc_start = meta.fixed_column();
+        let q_keccak = meta.fixed_column();
+        let rpi_len = meta.fixed_column();
+        let rpi_bytes = meta.advice_column();
+        let rpi_bytes_acc = meta.advice_column();
+        let rpi_rlc_acc = meta.advice_column();
+        let u8_table = meta.fixed_column();
+        let pi = meta.instance_column();
+
+        for column in [block_table.tag, block_table.index, block_table.value]
+            .into_iter()
+            .chain([tx_table.tx_id, tx_table.tag, tx_table.index, tx_table.value])
+            .chain(withdrawal_table)
+            .chain([rpi_bytes_acc])
+        {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(pi);
+
+        // rpi_bytes_acc accumulates the bytes of each raw public input, or of
+        // each half of the digest, in big endian, and rpi_rlc_acc the RLC of
+        // all the hashed bytes, or of the digest, from the highest power of
+        // randomness.
+        meta.create_gate("pi bytes", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let byte = meta.query_advice(rpi_bytes, Rotation::cur());
+            cb.require_equal(
+                "rpi_bytes_acc == rpi_bytes_acc_prev * 256 + byte",
+                meta.query_advice(rpi_bytes_acc, Rotation::cur()),
+                not::expr(meta.query_fixed(q_acc_start, Rotation::cur()))
+                    * meta.query_advice(rpi_bytes_acc, Rotation::prev())
+                    * 256.expr()
+                    + byte.clone(),
+            );
+            cb.require_equal(
+                "rpi_rlc_acc == rpi_rlc_acc_prev * r + byte",
+                meta.query_advice(rpi_rlc_acc, Rotation::cur()),
+                not::expr(meta.query_fixed(q_rlc_start, Rotation::cur()))
+                    * meta.query_advice(rpi_rlc_acc, Rotation::prev())
+                    * randomness
+                    + byte,
+            );
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.lookup_any("pi byte fits into u8", |meta| {
+            let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+            let byte = meta.query_advice(rpi_bytes, Rotation::cur());
+            vec![(q_enable * byte, meta.query_fixed(u8_table, Rotation::cur()))]
+        });
+
+        // The hashed bytes end right before the digest, whose RLC as a little
+        // endian word is the RLC of its big endian bytes from the highest
+        // power of randomness.
+        meta.lookup_any("pi digest in keccak table", |meta| {
+            let q_keccak = meta.query_fixed(q_keccak, Rotation::cur());
+            let table = keccak_table.table_exprs(meta);
+            vec![
+                (q_keccak.clone(), KECCAK_IS_ENABLED),
+                (
+                    q_keccak.clone()
+                        * meta.query_advice(rpi_rlc_acc, Rotation(-(N_BYTES_RPI as i32))),
+                    KECCAK_INPUT_RLC,
+                ),
+                (meta.query_fixed(rpi_len, Rotation::cur()), KECCAK_INPUT_LEN),
+                (
+                    q_keccak * meta.query_advice(rpi_rlc_acc, Rotation::cur()),
+                    KECCAK_OUTPUT_RLC,
+                ),
+            ]
+            .into_iter()
+            .map(|(expr, column)| (expr, table[column].clone()))
+            .collect()
+        });
+
+        Self {
+            block_table,
+            tx_table,
+            withdrawal_table,
+            keccak_table,
+            q_enable,
+            q_rlc_start,
+            q_acc_start,
+            q_keccak,
+            rpi_len,
+            rpi_bytes,
+            rpi_bytes_acc,
+            rpi_rlc_acc,
+            u8_table,
+            pi,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Load the fixed table of the values 0..256.
+    pub fn load_tables(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "pi circuit u8 table",
+            |mut region| {
+                for value in 0..256 {
+                    region.assign_fixed(
+                        || "u8 table",
+                        self.u8_table,
+                        value,
+                        || Ok(F::from(value as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assigns the rows of a table, returning the assigned cells.
+    fn assign_table<const N: usize>(
+        region: &mut Region<'_, F>,
+        name: &str,
+        columns: [Column<Advice>; N],
+        rows: &[[F; N]],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let mut cells = Vec::with_capacity(N * rows.len());
+        for (offset, row) in rows.iter().enumerate() {
+            for (column, value) in columns.iter().zip(row) {
+                cells.push(region.assign_advice(
+                    || format!("{} row {}", name, offset),
+                    *column,
+                    offset,
+                    || Ok(*value),
+                )?);
+            }
+        }
+        Ok(cells)
+    }
+
+    /// Assigns a row of hashed bytes, returning the cell of rpi_bytes_acc.
+    #[allow(clippy::too_many_arguments)]
+    fn assign_byte(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        byte: u8,
+        (rlc_start, acc_start): (bool, bool),
+        rpi_bytes_acc: &mut F,
+        rpi_rlc_acc: &mut F,
+        randomness: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let byte = F::from(byte as u64);
+        *rpi_bytes_acc = if acc_start {
+            byte
+        } else {
+            *rpi_bytes_acc * F::from(256) + byte
+        };
+        *rpi_rlc_acc = if rlc_start {
+            byte
+        } else {
+            *rpi_rlc_acc * randomness + byte
+        };
+        for (name, column, value) in [
+            ("q_enable", self.q_enable, true),
+            ("q_rlc_start", self.q_rlc_start, rlc_start),
+            ("q_acc_start", self.q_acc_start, acc_start),
+        ] {
+            region.assign_fixed(
+                || format!("assign {} {}", name, offset),
+                column,
+                offset,
+                || Ok(F::from(value as u64)),
+            )?;
+        }
+        region.assign_advice(
+            || format!("assign rpi_bytes {}", offset),
+            self.rpi_bytes,
+            offset,
+            || Ok(byte),
+        )?;
+        region.assign_advice(
+            || format!("assign rpi_rlc_acc {}", offset),
+            self.rpi_rlc_acc,
+            offset,
+            || Ok(*rpi_rlc_acc),
+        )?;
+        region.assign_advice(
+            || format!("assign rpi_bytes_acc {}", offset),
+            self.rpi_bytes_acc,
+            offset,
+            || Ok(*rpi_bytes_acc),
+        )
+    }
+
+    /// Assigns the block tables, the tx table and the withdrawal table with
+    /// the public data of the chunk padded up to `max_blocks` blocks, and the
+    /// bytes of the raw public inputs copied from them followed by the bytes
+    /// of their digest, constrained to the instance.
+    #[allow(clippy::too_many_arguments)]
+    pub fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        public_data: &PublicData,
+        max_blocks: usize,
+        max_txs: usize,
+        max_calldata: usize,
+        max_withdrawals: usize,
+        randomness: F,
+    ) -> Result<(), Error> {
+        let block_cells = public_data
+            .padded_blocks(max_blocks)
+            .iter()
+            .map(|block| self.block_table.load(layouter, &block.context, randomness))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let tx_table = self.tx_table;
+        let tx_cells = layouter.assign_region(
+            || "tx table",
+            |mut region| {
+                Self::assign_table(
+                    &mut region,
+                    "tx table",
+                    [tx_table.tx_id, tx_table.tag, tx_table.index, tx_table.value],
+                    &public_data.tx_table_rows(max_txs, max_calldata, randomness),
+                )
+            },
+        )?;
+        let withdrawal_cells = layouter.assign_region(
+            || "withdrawal table",
+            |mut region| {
+                Self::assign_table(
+                    &mut region,
+                    "withdrawal table",
+                    self.withdrawal_table,
+                    &public_data.withdrawal_table_rows(max_withdrawals),
+                )
+            },
+        )?;
+
+        // The state roots, the hash and the blob gas fields of each block
+        // follow the cells of its block table, and are only committed into the
+        // digest.
+        let table_cells = block_cells
+            .iter()
+            .flat_map(|cells| cells.iter().map(Some).chain([None; N_BLOCK_EXTRA_RPIS]))
+            .chain(tx_cells.iter().map(Some))
+            .chain(withdrawal_cells.iter().map(Some))
+            .collect::<Vec<_>>();
+        let rpi_bytes = public_data.rpi_bytes(
+            max_blocks,
+            max_txs,
+            max_calldata,
+            max_withdrawals,
+            randomness,
+        );
+        assert_eq!(table_cells.len() * N_BYTES_RPI, rpi_bytes.len());
+        let digest = keccak256(&rpi_bytes);
+
+        let digest_cells = layouter.assign_region(
+            || "pi bytes",
+            |mut region| {
+                let (mut rpi_bytes_acc, mut rpi_rlc_acc) = (F::zero(), F::zero());
+                let mut rpi_cells = Vec::with_capacity(table_cells.len());
+                for (offset, byte) in rpi_bytes.iter().enumerate() {
+                    let cell = self.assign_byte(
+                        &mut region,
+                        offset,
+                        *byte,
+                        (offset == 0, offset % N_BYTES_RPI == 0),
+                        &mut rpi_bytes_acc,
+                        &mut rpi_rlc_acc,
+                        randomness,
+                    )?;
+                    if offset % N_BYTES_RPI == N_BYTES_RPI - 1 {
+                        if let Some(table_cell) = table_cells[offset / N_BYTES_RPI] {
+                            region.constrain_equal(cell.cell(), table_cell.cell())?;
+                        }
+                        rpi_cells.push(cell);
+                    }
+                }
+
+                // Chain each block to the previous one, where the raw public
+                // inputs of a block are made of its block table cells followed
+                // by its state roots, its hash and its blob gas fields.
+                let block_len = block_cells[0].len() + N_BLOCK_EXTRA_RPIS;
+                // The cell of the state root before the block, of the state
+                // root after the block, or of the hash of the block.
+                let root_or_hash = |block: usize, idx: usize| {
+                    rpi_cells[block * block_len + block_cells[0].len() + idx].cell()
+                };
+                for (block, (prev, cur)) in block_cells.iter().tuple_windows().enumerate() {
+                    for (lhs, rhs) in [
+                        (
+                            cur[PARENT_HASH_ROW * 3 + 1].cell(),
+                            prev[BLOCK_NUMBER_ROW * 3 + 2].cell(),
+                        ),
+                        (cur[PARENT_HASH_ROW * 3 + 2].cell(), root_or_hash(block, 2)),
+                        (root_or_hash(block + 1, 0), root_or_hash(block, 1)),
+                    ] {
+                        region.constrain_equal(lhs, rhs)?;
+                    }
+                }
+
+                let mut digest_cells = Vec::with_capacity(2);
+                for (idx, byte) in digest.iter().enumerate() {
+                    let offset = rpi_bytes.len() + idx;
+                    let cell = self.assign_byte(
+                        &mut region,
+                        offset,
+                        *byte,
+                        (idx == 0, idx % 16 == 0),
+                        &mut rpi_bytes_acc,
+                        &mut rpi_rlc_acc,
+                        randomness,
+                    )?;
+                    if idx % 16 == 15 {
+                        digest_cells.push(cell);
+                    }
+                }
+                let offset = rpi_bytes.len() + digest.len() - 1;
+                for (name, column, value) in [
+                    ("q_keccak", self.q_keccak, F::one()),
+                    ("rpi_len", self.rpi_len, F::from(rpi_bytes.len() as u64)),
+                ] {
+                    region.assign_fixed(
+                        || format!("assign {} {}", name, offset),
+                        column,
+                        offset,
+                        || Ok(value),
+                    )?;
+                }
+                Ok(digest_cells)
+            },
+        )?;
+
+        for (row, cell) in digest_cells.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), self.pi, row)?;
+        }
+        Ok(())
+    }
+}
+
+/// Public input circuit committing the block tables, the tx table, the
+/// withdrawal table, the state roots and the hashes of a chunk of up to
+/// `MAX_BLOCKS` consecutive blocks into the digest of their public data.
+#[derive(Default)]
+pub struct PiCircuit<F: Field, const MAX_BLOCKS: usize, const MAX_WITHDRAWALS: usize> {
+    /// Randomness for RLC encoding
+    pub randomness: F,
+    /// Public data of the chunk of blocks
+    pub public_data: PublicData,
+    /// Max number of transactions of the chunk
+    pub max_txs: usize,
+    /// Max number of bytes of call data of the transactions of the chunk
+    pub max_calldata: usize,
+}
+
+impl<F: Field, const MAX_BLOCKS: usize, const MAX_WITHDRAWALS: usize>
+    PiCircuit<F, MAX_BLOCKS, MAX_WITHDRAWALS>
+{
+    /// Create a new PI circuit committing `public_data` with a capacity of
+    /// `max_txs` transactions and `max_calldata` bytes of call data.
+    pub fn new(
+        randomness: F,
+        public_data: PublicData,
+        max_txs: usize,
+        max_calldata: usize,
+    ) -> Self {
+        Self {
+            randomness,
+            public_data,
+            max_txs,
+            max_calldata,
+        }
+    }
+
+    /// Return the minimum number of rows required to commit a chunk of blocks
+    /// with the capacities of `params`: one row per byte of the raw public
+    /// inputs.
+    pub fn min_num_rows(params: &CircuitsParams) -> usize {
+        PublicData::default()
+            .rpi_bytes(
+                MAX_BLOCKS,
+                params.max_txs,
+                params.max_calldata,
+                MAX_WITHDRAWALS,
+                F::zero(),
+            )
+            .len()
+            + N_BYTES_RPI
+    }
+
+    /// Return the instance columns required by the circuit: the randomness,
+    /// followed by the high and low 128 bits of the digest of the public data.
+    pub fn instance(&self) -> Vec<Vec<F>> {
+        let rpi_bytes = self.public_data.rpi_bytes(
+            MAX_BLOCKS,
+            self.max_txs,
+            self.max_calldata,
+            MAX_WITHDRAWALS,
+            self.randomness,
+        );
+        vec![
+            vec![self.randomness; rpi_bytes.len() + N_BYTES_RPI],
+            self.public_data
+                .digest(
+                    MAX_BLOCKS,
+                    self.max_txs,
+                    self.max_calldata,
+                    MAX_WITHDRAWALS,
+                    self.randomness,
+                )
+                .to_vec(),
+        ]
+    }
+}
+
+impl<F: Field, const MAX_BLOCKS: usize, const MAX_WITHDRAWALS: usize> Circuit<F>
+    for PiCircuit<F, MAX_BLOCKS, MAX_WITHDRAWALS>
+{
+    type Config = PiCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        // The capacities fix the layout of the circuit, which must be the same
+        // in the verifying key.
+        Self {
+            max_txs: self.max_txs,
+            max_calldata: self.max_calldata,
+            ..Self::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let randomness = meta.instance_column();
+        let mut randomness_expr = None;
+        meta.create_gate("randomness", |meta| {
+            randomness_expr = Some(meta.query_instance(randomness, Rotation::cur()));
+            [0.expr()]
+        });
+
+        let block_table = BlockTable::construct(meta);
+        let tx_table = TxTable::construct(meta);
+        let withdrawal_table = [(); 4].map(|_| meta.advice_column());
+        // The keccak table is proven by the keccak circuit in the super
+        // circuit only.
+        let keccak_table = KeccakTable::construct(meta);
+        PiCircuitConfig::new(
+            meta,
+            block_table,
+            tx_table,
+            withdrawal_table,
+            keccak_table,
+            randomness_expr.unwrap(),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_tables(&mut layouter)?;
+        config.keccak_table.dev_load(
+            &mut layouter,
+            &[self.public_data.rpi_bytes(
+                MAX_BLOCKS,
+                self.max_txs,
+                self.max_calldata,
+                MAX_WITHDRAWALS,
+                self.randomness,
+            )],
+            self.randomness,
+        )?;
+        config.assign(
+            &mut layouter,
+            &self.public_data,
+            MAX_BLOCKS,
+            self.max_txs,
+            self.max_calldata,
+            MAX_WITHDRAWALS,
+            self.randomness,
+        )
+    }
+}
+
+#[cfg(test)]
+mod pi_circuit_tests {
+    use super::*;
+    use crate::table::MAX_HISTORY_HASHES;
+    use eth_types::public_data::{BlockContext, BlockContextFieldTag, TxPublicData};
+    use eth_types::{Address, ToScalar, Withdrawal, Word, H256};
+    use halo2_proofs::{dev::MockProver, pairing::bn256::Fr};
+    use pretty_assertions::assert_eq;
+
+    const K: u32 = 16;
+
+    fn run<
+        F: Field,
+        const MAX_BLOCKS: usize,
+        const MAX_TXS: usize,
+        const MAX_CALLDATA: usize,
+        const MAX_WITHDRAWALS: usize,
+    >(
+        k: u32,
+        public_data: PublicData,
+        instance: Option<Vec<Vec<F>>>,
+    ) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+        let circuit = PiCircuit::<F, MAX_BLOCKS, MAX_WITHDRAWALS>::new(
+            F::from(0xcafe),
+            public_data,
+            MAX_TXS,
+            MAX_CALLDATA,
+        );
+        let instance = instance.unwrap_or_else(|| circuit.instance());
+        let prover = MockProver::<F>::run(k, &circuit, instance).unwrap();
+        prover.verify()
+    }
+
+    fn tx(call_data: Vec<u8>) -> TxPublicData {
+        TxPublicData {
+            id: 1,
+            nonce: 3,
+            gas: 21000,
+            gas_price: Word::from(2_000_000_000u64),
+            caller_address: Address::from_low_u64_be(0xfe),
+            callee_address: Address::from_low_u64_be(0xff),
+            value: Word::from(1000),
+            call_data_length: call_data.len(),
+            call_data,
+            ..TxPublicData::default()
+        }
+    }
+
+    // A chunk of two consecutive blocks.
+    fn public_data() -> PublicData {
+        let block = BlockPublicData {
+            context: BlockContext {
+                coinbase: Address::from_low_u64_be(0xc014ba5e),
+                gas_limit: 30_000_000,
+                number: Word::from(0xcafe),
+                timestamp: Word::from(0x1234),
+                difficulty: Word::from(0x20000),
+                base_fee: Word::from(1_000_000_000),
+                history_hashes: (0..3).map(|idx| Word::from(0x100 + idx)).collect(),
+                chain_id: Word::from(1337),
+            },
+            prev_state_root: H256::repeat_byte(0x11),
+            state_root: H256::repeat_byte(0x22),
+            hash: H256::repeat_byte(0xaa),
+            txs: vec![tx(vec![1, 2, 3])],
+            withdrawals: vec![Withdrawal {
+                index: 0x1fu64.into(),
+                validator_index: 0x7au64.into(),
+                address: Address::from_low_u64_be(0xfe),
+                amount: 32_000_000_000u64.into(),
+            }],
+            blob_gas_used: 0x40000,
+            excess_blob_gas: 0x20000,
+        };
+        let mut next = BlockPublicData {
+            context: BlockContext {
+                number: block.context.number + 1,
+                timestamp: block.context.timestamp + 12,
+                ..block.context.clone()
+            },
+            prev_state_root: block.state_root,
+            state_root: H256::repeat_byte(0x33),
+            hash: H256::repeat_byte(0xbb),
+            txs: vec![tx(vec![4])],
+            withdrawals: Vec::new(),
+            blob_gas_used: 0,
+            excess_blob_gas: 0x40000,
+        };
+        next.context
+            .history_hashes
+            .push(Word::from_big_endian(block.hash.as_bytes()));
+        PublicData {
+            blocks: vec![block, next],
+        }
+    }
+
+    #[test]
+    fn pi_circuit_raw_public_inputs_len() {
+        let raw_public_inputs = public_data().raw_public_inputs(2, 2, 8, 2, Fr::from(0xcafe));
+        let tx_fields = TxPublicData::default().table_assignments(Fr::zero()).len();
+        assert_eq!(
+            raw_public_inputs.len(),
+            2 * (3 * (1 + 7 + MAX_HISTORY_HASHES) + 5) + 4 * (1 + 2 * tx_fields + 8) + 4 * 2
+        );
+    }
+
+    #[test]
+    fn pi_circuit_block_table_rows() {
+        let randomness = Fr::from(0xcafe);
+        let block = &public_data().blocks[1];
+        let rows = BlockTable::assignments(&block.context, randomness);
+        assert_eq!(
+            rows[BLOCK_NUMBER_ROW],
+            [
+                Fr::from(BlockContextFieldTag::Number as u64),
+                Fr::zero(),
+                Fr::from(0xcaff),
+            ]
+        );
+        assert_eq!(
+            rows[PARENT_HASH_ROW],
+            [
+                Fr::from(BlockContextFieldTag::BlockHash as u64),
+                Fr::from(0xcafe),
+                public_data().blocks[0].roots_and_hash(randomness)[2],
+            ]
+        );
+    }
+
+    #[test]
+    fn pi_circuit_padding_blocks() {
+        let mut public_data = public_data();
+        let last = public_data.blocks.pop().unwrap();
+        let blocks = public_data.padded_blocks(2);
+        assert_eq!(blocks[1].context.number, last.context.number);
+        assert_eq!(
+            blocks[1].context.history_hashes,
+            last.context.history_hashes
+        );
+        assert_eq!(blocks[1].prev_state_root, last.prev_state_root);
+        assert_eq!(blocks[1].state_root, last.prev_state_root);
+        assert!(blocks[1].txs.is_empty());
+
+        let blocks = PublicData::default().padded_blocks(2);
+        assert_eq!(blocks[1].context.number, Word::one());
+        assert_eq!(blocks[1].context.history_hashes, vec![Word::zero()]);
+    }
+
+    #[test]
+    fn pi_circuit_valid() {
+        assert_eq!(run::<Fr, 2, 2, 8, 2>(K, public_data(), None), Ok(()));
+
+        let mut public_data = public_data();
+        public_data.blocks.pop();
+        assert_eq!(run::<Fr, 2, 2, 8, 2>(K, public_data, None), Ok(()));
+
+        assert_eq!(
+            run::<Fr, 2, 2, 8, 2>(K, PublicData::default(), None),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn pi_circuit_broken_chain() {
+        let mutations: [(&str, fn(&mut BlockPublicData)); 3] = [
+            ("prev state root", |block| {
+                block.prev_state_root = H256::repeat_byte(0x44)
+            }),
+            ("number", |block| block.context.number += Word::one()),
+            ("parent hash", |block| {
+                *block.context.history_hashes.last_mut().unwrap() += Word::one()
+            }),
+        ];
+        for (name, mutate) in mutations {
+            let mut public_data = public_data();
+            mutate(&mut public_data.blocks[1]);
+            assert!(
+                run::<Fr, 2, 2, 8, 2>(K, public_data, None).is_err(),
+                "chain with a wrong {} is accepted",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn pi_circuit_wrong_public_data() {
+        let circuit = PiCircuit::<Fr, 2, 2>::new(Fr::from(0xcafe), public_data(), 2, 8);
+        let mutations: [fn(&mut PublicData); 4] = [
+            |public_data| public_data.blocks[1].state_root = H256::repeat_byte(0x44),
+            |public_data| public_data.blocks[1].excess_blob_gas = 0,
+            |public_data| public_data.blocks[0].txs[0].call_data[2] = 4,
+            |public_data| public_data.blocks[0].withdrawals[0].amount = 1u64.into(),
+        ];
+        for mutate in mutations {
+            let mut public_data = public_data();
+            mutate(&mut public_data);
+            assert!(run::<Fr, 2, 2, 8, 2>(K, public_data, Some(circuit.instance())).is_err());
+        }
+    }
+
+    #[test]
+    fn pi_circuit_digest() {
+        let public_data = public_data();
+        let rpi_bytes = public_data.rpi_bytes(2, 2, 8, 2, Fr::from(0xcafe));
+        assert_eq!(
+            rpi_bytes.len(),
+            N_BYTES_RPI
+                * public_data
+                    .raw_public_inputs(2, 2, 8, 2, Fr::from(0xcafe))
+                    .len()
+        );
+        // the last raw public input is the amount of the padding withdrawal
+        assert_eq!(rpi_bytes[rpi_bytes.len() - N_BYTES_RPI..], [0; N_BYTES_RPI]);
+
+        let [hi, lo] = public_data.digest(2, 2, 8, 2, Fr::from(0xcafe));
+        let digest = keccak256(&rpi_bytes);
+        assert_eq!(
+            hi,
+            Word::from_big_endian(&digest[..16]).to_scalar().unwrap()
+        );
+        assert_eq!(
+            lo,
+            Word::from_big_endian(&digest[16..]).to_scalar().unwrap()
+        );
+    }
+
+    #[test]
+    fn pi_circuit_wrong_digest() {
+        let circuit = PiCircuit::<Fr, 2, 2>::new(Fr::from(0xcafe), public_data(), 2, 8);
+        let mut instance = circuit.instance();
+        instance[1][1] += Fr::one();
+        assert!(run::<Fr, 2, 2, 8, 2>(K, public_data(), Some(instance)).is_err());
+    }
+}
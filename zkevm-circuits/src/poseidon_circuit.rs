@@ -0,0 +1,483 @@
+//! The Poseidon circuit implements constraints for the Poseidon permutation
+//! of a state of 3 field elements, used as the hash of pairs of field elements
+//! by the zkTrie.  Each hash is laid out as one row per round followed by the
+//! row of the final state:
+//!   - the state of the first row is made of the two inputs and a zero capacity
+//!     element,
+//!   - a full round adds the round constants to the state, raises every element
+//!     to the fifth power and multiplies the state by the MDS matrix,
+//!   - a partial round only raises the first element to the fifth power,
+//!   - the hash is the first element of the final state.
+//!
+//! The permutation has 8 full rounds, 4 before and 4 after the 57 partial
+//! rounds, which is the recommended number of rounds for a 254-bit field with
+//! the x^5 S-box.  The round constants are derived from the keccak hashes of
+//! their indices, and the MDS matrix is the Cauchy matrix 1 / (x_i + y_j) with
+//! x_i = i and y_j = 3 + j.
+
+use eth_types::Field;
+use ethers_core::utils::keccak256;
+use gadgets::util::{not, Expr};
+use halo2_proofs::{
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed},
+    poly::Rotation,
+};
+
+use crate::{evm_circuit::util::constraint_builder::BaseConstraintBuilder, table::PoseidonTable};
+
+/// Number of elements of the state of the permutation.
+pub const POSEIDON_WIDTH: usize = 3;
+/// Number of full rounds of the permutation.
+pub const POSEIDON_FULL_ROUNDS: usize = 8;
+/// Number of partial rounds of the permutation.
+pub const POSEIDON_PARTIAL_ROUNDS: usize = 57;
+/// Number of rounds of the permutation.
+pub const POSEIDON_ROUNDS: usize = POSEIDON_FULL_ROUNDS + POSEIDON_PARTIAL_ROUNDS;
+
+/// The round constants and the MDS matrix of the permutation.
+#[derive(Clone, Debug)]
+pub struct PoseidonParams<F> {
+    /// The constants added to the state at each round.
+    pub round_constants: Vec<[F; POSEIDON_WIDTH]>,
+    /// The MDS matrix multiplying the state at each round.
+    pub mds: [[F; POSEIDON_WIDTH]; POSEIDON_WIDTH],
+}
+
+impl<F: Field> Default for PoseidonParams<F> {
+    fn default() -> Self {
+        let round_constants = (0..POSEIDON_ROUNDS)
+            .map(|round| {
+                array_init::array_init(|idx| {
+                    let seed = keccak256(format!("poseidon round constant {} {}", round, idx));
+                    let mut bytes = [0; 64];
+                    bytes[..32].copy_from_slice(&seed);
+                    bytes[32..].copy_from_slice(&keccak256(&seed));
+                    F::from_bytes_wide(&bytes)
+                })
+            })
+            .collect();
+        let mds = array_init::array_init(|row| {
+            array_init::array_init(|col| {
+                F::from((row + POSEIDON_WIDTH + col) as u64)
+                    .invert()
+                    .unwrap()
+            })
+        });
+        Self {
+            round_constants,
+            mds,
+        }
+    }
+}
+
+impl<F: Field> PoseidonParams<F> {
+    /// Returns whether the round raises every element of the state to the
+    /// fifth power.
+    pub fn is_full_round(round: usize) -> bool {
+        round < POSEIDON_FULL_ROUNDS / 2
+            || round >= POSEIDON_FULL_ROUNDS / 2 + POSEIDON_PARTIAL_ROUNDS
+    }
+
+    /// Returns the state after the round.
+    pub fn round(&self, round: usize, state: [F; POSEIDON_WIDTH]) -> [F; POSEIDON_WIDTH] {
+        let mut state = state;
+        for (element, constant) in state.iter_mut().zip(self.round_constants[round]) {
+            *element += constant;
+        }
+        let sboxes = if Self::is_full_round(round) {
+            POSEIDON_WIDTH
+        } else {
+            1
+        };
+        for element in state.iter_mut().take(sboxes) {
+            *element = element.pow(&[5, 0, 0, 0]);
+        }
+        self.mds.map(|row| {
+            row.iter()
+                .zip(state)
+                .fold(F::zero(), |acc, (coefficient, element)| {
+                    acc + *coefficient * element
+                })
+        })
+    }
+
+    /// Returns the states before every round of the hash of `inputs`,
+    /// followed by the final state.
+    pub fn states(&self, inputs: [F; 2]) -> Vec<[F; POSEIDON_WIDTH]> {
+        let mut states = vec![[inputs[0], inputs[1], F::zero()]];
+        for round in 0..POSEIDON_ROUNDS {
+            states.push(self.round(round, states[round]));
+        }
+        states
+    }
+
+    /// Returns the hash of `inputs`.
+    pub fn hash(&self, inputs: [F; 2]) -> F {
+        self.states(inputs)[POSEIDON_ROUNDS][0]
+    }
+}
+
+/// Returns the Poseidon hash of a pair of field elements.
+pub fn poseidon_hash<F: Field>(inputs: [F; 2]) -> F {
+    PoseidonParams::default().hash(inputs)
+}
+
+/// The Poseidon circuit, which constrains the poseidon table to contain the
+/// hashes of its inputs.
+#[derive(Clone, Debug)]
+pub struct PoseidonCircuit<F> {
+    /// The poseidon table, holding the inputs and the hash at the first row of
+    /// each hash.
+    pub poseidon_table: PoseidonTable,
+    /// Whether the row belongs to a hash.
+    pub q_enable: Column<Fixed>,
+    /// Whether the row is the final state of a hash.
+    pub q_last: Column<Fixed>,
+    /// Whether the round of the row is a full round.
+    pub q_full: Column<Fixed>,
+    /// Whether the round of the row is a partial round.
+    pub q_partial: Column<Fixed>,
+    /// The constants of the round of the row.
+    pub round_constants: [Column<Fixed>; POSEIDON_WIDTH],
+    /// The state before the round of the row.
+    pub state: [Column<Advice>; POSEIDON_WIDTH],
+    /// The hash, which is the same for all rows of the hash.
+    pub output: Column<Advice>,
+    params: PoseidonParams<F>,
+}
+
+impl<F: Field> PoseidonCircuit<F> {
+    /// Configure the Poseidon circuit constraining the hashes of the poseidon
+    /// table.
+    pub fn configure(meta: &mut ConstraintSystem<F>, poseidon_table: PoseidonTable) -> Self {
+        let params = PoseidonParams::<F>::default();
+        let q_enable = meta.fixed_column();
+        let q_last = meta.fixed_column();
+        let q_full = meta.fixed_column();
+        let q_partial = meta.fixed_column();
+        let round_constants = [(); POSEIDON_WIDTH].map(|_| meta.fixed_column());
+        let state = [(); POSEIDON_WIDTH].map(|_| meta.advice_column());
+        let output = meta.advice_column();
+
+        for (name, q_round, is_full) in [
+            ("poseidon full round", q_full, true),
+            ("poseidon partial round", q_partial, false),
+        ] {
+            meta.create_gate(name, |meta| {
+                let mut cb = BaseConstraintBuilder::default();
+
+                let elements: Vec<_> = state
+                    .iter()
+                    .zip(round_constants)
+                    .enumerate()
+                    .map(|(idx, (element, constant))| {
+                        let element = meta.query_advice(*element, Rotation::cur())
+                            + meta.query_fixed(constant, Rotation::cur());
+                        if idx == 0 || is_full {
+                            element.clone()
+                                * element.clone()
+                                * element.clone()
+                                * element.clone()
+                                * element
+                        } else {
+                            element
+                        }
+                    })
+                    .collect();
+                for (row, element_next) in params.mds.iter().zip(state) {
+                    cb.require_equal(
+                        "state_next == mds * sbox(state + round_constants)",
+                        meta.query_advice(element_next, Rotation::next()),
+                        row.iter().zip(elements.iter()).fold(
+                            0.expr(),
+                            |acc, (coefficient, element)| {
+                                acc + Expression::Constant(*coefficient) * element.clone()
+                            },
+                        ),
+                    );
+                }
+
+                cb.gate(meta.query_fixed(q_round, Rotation::cur()))
+            });
+        }
+
+        meta.create_gate("poseidon hash", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_first = meta.query_fixed(poseidon_table.q_enable, Rotation::cur());
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+            let output_expr = meta.query_advice(output, Rotation::cur());
+
+            cb.condition(q_first, |cb| {
+                for (name, element, input) in [
+                    (
+                        "state[0] == left at the first row",
+                        state[0],
+                        meta.query_advice(poseidon_table.left, Rotation::cur()),
+                    ),
+                    (
+                        "state[1] == right at the first row",
+                        state[1],
+                        meta.query_advice(poseidon_table.right, Rotation::cur()),
+                    ),
+                    ("state[2] == 0 at the first row", state[2], 0.expr()),
+                ] {
+                    cb.require_equal(name, meta.query_advice(element, Rotation::cur()), input);
+                }
+                cb.require_equal(
+                    "hash == output at the first row",
+                    meta.query_advice(poseidon_table.hash, Rotation::cur()),
+                    output_expr.clone(),
+                );
+            });
+            cb.condition(not::expr(q_last.clone()), |cb| {
+                cb.require_equal(
+                    "output is the same for all rows of the hash",
+                    meta.query_advice(output, Rotation::next()),
+                    output_expr.clone(),
+                );
+            });
+            cb.condition(q_last, |cb| {
+                cb.require_equal(
+                    "output == state[0] at the last row",
+                    output_expr,
+                    meta.query_advice(state[0], Rotation::cur()),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        Self {
+            poseidon_table,
+            q_enable,
+            q_last,
+            q_full,
+            q_partial,
+            round_constants,
+            state,
+            output,
+            params,
+        }
+    }
+
+    /// Assign the rows of the hash of every pair of `inputs`.
+    pub fn assign(&self, layouter: &mut impl Layouter<F>, inputs: &[[F; 2]]) -> Result<(), Error> {
+        let states: Vec<_> = inputs
+            .iter()
+            .map(|inputs| self.params.states(*inputs))
+            .collect();
+        self.assign_states(layouter, &states)
+    }
+
+    fn assign_states(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        states: &[Vec<[F; POSEIDON_WIDTH]>],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "assign poseidon hashes",
+            |mut region| {
+                let mut offset = 0;
+                for states in states.iter() {
+                    for (round, state) in states.iter().enumerate() {
+                        self.assign_row(&mut region, offset, round, states, state)?;
+                        offset += 1;
+                    }
+                }
+                // pad a row in the end to satisfy Halo2 cell assignment check
+                self.assign_padding_row(&mut region, offset)
+            },
+        )
+    }
+
+    fn assign_row(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        round: usize,
+        states: &[[F; POSEIDON_WIDTH]],
+        state: &[F; POSEIDON_WIDTH],
+    ) -> Result<(), Error> {
+        let is_round = round < POSEIDON_ROUNDS;
+        let is_full = is_round && PoseidonParams::<F>::is_full_round(round);
+        let output = states[POSEIDON_ROUNDS][0];
+
+        self.poseidon_table.assign_row(
+            region,
+            offset,
+            round == 0,
+            if round == 0 {
+                [states[0][0], states[0][1], output]
+            } else {
+                [F::zero(); 3]
+            },
+        )?;
+        for (name, column, value) in [
+            ("q_enable", self.q_enable, F::one()),
+            ("q_last", self.q_last, F::from(!is_round)),
+            ("q_full", self.q_full, F::from(is_full)),
+            ("q_partial", self.q_partial, F::from(is_round && !is_full)),
+        ]
+        .into_iter()
+        .chain(
+            self.round_constants
+                .iter()
+                .enumerate()
+                .map(|(idx, column)| {
+                    (
+                        "round_constants",
+                        *column,
+                        if is_round {
+                            self.params.round_constants[round][idx]
+                        } else {
+                            F::zero()
+                        },
+                    )
+                }),
+        ) {
+            region.assign_fixed(
+                || format!("assign {} {}", name, offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        for (name, column, value) in [("output", self.output, output)].into_iter().chain(
+            self.state
+                .iter()
+                .zip(state)
+                .map(|(column, value)| ("state", *column, *value)),
+        ) {
+            region.assign_advice(
+                || format!("assign {} {}", name, offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn assign_padding_row(&self, region: &mut Region<F>, offset: usize) -> Result<(), Error> {
+        self.poseidon_table
+            .assign_row(region, offset, false, [F::zero(); 3])?;
+        for column in [self.q_enable, self.q_last, self.q_full, self.q_partial]
+            .into_iter()
+            .chain(self.round_constants)
+        {
+            region.assign_fixed(
+                || format!("assign padding row {}", offset),
+                column,
+                offset,
+                || Ok(F::zero()),
+            )?;
+        }
+        for column in std::iter::once(self.output).chain(self.state) {
+            region.assign_advice(
+                || format!("assign padding row {}", offset),
+                column,
+                offset,
+                || Ok(F::zero()),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod poseidon_circuit_tests {
+    use eth_types::Field;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::{MockProver, VerifyFailure},
+        pairing::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{poseidon_hash, PoseidonCircuit, PoseidonParams, POSEIDON_ROUNDS};
+    use crate::table::PoseidonTable;
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        states: Vec<Vec<[F; 3]>>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = PoseidonCircuit<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let poseidon_table = PoseidonTable::construct(meta);
+            PoseidonCircuit::configure(meta, poseidon_table)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.assign_states(&mut layouter, &self.states)
+        }
+    }
+
+    fn run_circuit(states: Vec<Vec<[Fr; 3]>>) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = MyCircuit::<Fr> { states };
+        let prover = MockProver::<Fr>::run(10, &circuit, vec![]).unwrap();
+        prover.verify()
+    }
+
+    fn states(inputs: &[[u64; 2]]) -> Vec<Vec<[Fr; 3]>> {
+        let params = PoseidonParams::default();
+        inputs
+            .iter()
+            .map(|inputs| params.states(inputs.map(Fr::from)))
+            .collect()
+    }
+
+    #[test]
+    fn poseidon_hash_of_inputs() {
+        let hash = poseidon_hash([Fr::from(1), Fr::from(2)]);
+        assert_ne!(hash, poseidon_hash([Fr::from(2), Fr::from(1)]));
+        assert_ne!(hash, poseidon_hash([Fr::from(1), Fr::from(3)]));
+        assert_eq!(
+            hash,
+            states(&[[1, 2]])[0][POSEIDON_ROUNDS][0],
+            "hash is the first element of the final state"
+        );
+    }
+
+    #[test]
+    fn poseidon_circuit_valid() {
+        assert_eq!(
+            run_circuit(states(&[[0, 0], [1, 2], [u64::MAX, 7], [2, 1]])),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn poseidon_circuit_invalid_round() {
+        let mut states = states(&[[1, 2]]);
+        states[0][30][1] += Fr::from(1);
+        assert!(run_circuit(states).is_err());
+    }
+
+    #[test]
+    fn poseidon_circuit_invalid_capacity() {
+        let params = PoseidonParams::<Fr>::default();
+        let mut state = [Fr::from(1), Fr::from(2), Fr::from(3)];
+        let mut states = vec![state];
+        for round in 0..POSEIDON_ROUNDS {
+            state = params.round(round, state);
+            states.push(state);
+        }
+        assert!(run_circuit(vec![states]).is_err());
+    }
+}
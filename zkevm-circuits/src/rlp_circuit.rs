@@ -0,0 +1,1368 @@
+//! The RLP circuit implements constraints to verify the byte-level RLP
+//! encoding of transactions, which is the input of the keccak hashes used for
+//! the signature (TxSign) and for the transaction hash (TxHash).
+//!
+//! Each row of the circuit contains one byte of the encoding.  The encoding of
+//! a transaction is the list header (tag `Null`) followed by the encoding of
+//! each field, where each field is made of a prefix row, optional length rows
+//! (long strings and long lists), and content rows.  The value of each field
+//! is accumulated in `value_acc` so that it can be looked up from the tx table.
+//!
+//! Typed transactions (EIP-2718) are prefixed by the transaction type byte
+//! (tag `TxType`) before the list header.  The access list of EIP-2930
+//! transactions is encoded as a list field whose payload is accumulated like
+//! the content of a string.
+
+use crate::{
+    evm_circuit::util::{constraint_builder::BaseConstraintBuilder, select},
+    impl_expr,
+    tx_circuit::{TxFieldTag, ACCESS_LIST_TX_TYPE, DYNAMIC_FEE_TX_TYPE, LEGACY_TX_TYPE},
+    util::Expr,
+};
+use eth_types::{
+    geth_types::{Transaction, TransactionType},
+    Field, ToBigEndian, Word,
+};
+use gadgets::{
+    less_than::{LtChip, LtConfig, LtInstruction},
+    range_check::{RangeCheckChip, RangeCheckConfig},
+    util::{and, not},
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+
+/// Data type of the RLP encoding of a transaction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RlpDataType {
+    /// Encoding of the transaction without signature, used for signing.
+    TxSign = 0,
+    /// Encoding of the signed transaction, used for the transaction hash.
+    TxHash,
+}
+impl_expr!(RlpDataType);
+
+/// Returns whether the value of a field is accumulated as the RLC of its
+/// bytes (interpreted as a little endian word), or as an integer.  The nonce
+/// and the gas are integers, as in the tx table of the EVM circuit.
+fn tag_is_rlc(tag: TxFieldTag) -> bool {
+    !matches!(
+        tag,
+        TxFieldTag::Null
+            | TxFieldTag::Nonce
+            | TxFieldTag::Gas
+            | TxFieldTag::TxType
+            | TxFieldTag::ChainID
+            | TxFieldTag::CalleeAddress
+            | TxFieldTag::SigV
+    )
+}
+
+/// Returns whether the field is encoded as an RLP list instead of an RLP
+/// string.
+fn tag_is_list(tag: TxFieldTag) -> bool {
+    matches!(tag, TxFieldTag::Null | TxFieldTag::AccessListRLC)
+}
+
+/// Transaction types supported by the RLP circuit.
+const TX_TYPES: [u64; 3] = [LEGACY_TX_TYPE, ACCESS_LIST_TX_TYPE, DYNAMIC_FEE_TX_TYPE];
+
+/// Return the sequence of tags in the RLP encoding of a transaction of type
+/// `tx_type`.  Typed transactions start with the transaction type byte,
+/// followed by the list header (tag `Null`).  Dynamic fee transactions encode
+/// the fee caps in place of the gas price.  Legacy transactions signed before
+/// EIP-155 don't encode the chain id.
+fn tag_sequence(tx_type: u64, data_type: RlpDataType, is_pre_eip155: bool) -> Vec<TxFieldTag> {
+    let mut tags = match tx_type {
+        LEGACY_TX_TYPE => vec![TxFieldTag::Null],
+        _ => vec![TxFieldTag::TxType, TxFieldTag::Null, TxFieldTag::ChainID],
+    };
+    tags.push(TxFieldTag::Nonce);
+    if tx_type == DYNAMIC_FEE_TX_TYPE {
+        tags.extend_from_slice(&[TxFieldTag::MaxPriorityFeePerGas, TxFieldTag::MaxFeePerGas]);
+    } else {
+        tags.push(TxFieldTag::GasPrice);
+    }
+    tags.extend_from_slice(&[
+        TxFieldTag::Gas,
+        TxFieldTag::CalleeAddress,
+        TxFieldTag::Value,
+        TxFieldTag::CallData,
+    ]);
+    if tx_type != LEGACY_TX_TYPE {
+        tags.push(TxFieldTag::AccessListRLC);
+    }
+    // Legacy transactions encode the chain id in place of the signature in the
+    // TxSign encoding (EIP-155), while typed transactions omit it.
+    match (tx_type, data_type) {
+        (_, RlpDataType::TxHash) => {
+            tags.extend_from_slice(&[TxFieldTag::SigV, TxFieldTag::SigR, TxFieldTag::SigS])
+        }
+        (LEGACY_TX_TYPE, RlpDataType::TxSign) if !is_pre_eip155 => {
+            tags.extend_from_slice(&[TxFieldTag::ChainID, TxFieldTag::SigR, TxFieldTag::SigS])
+        }
+        (_, RlpDataType::TxSign) => (),
+    }
+    tags
+}
+
+/// Valid transitions between the tags of an RLP encoding, as `(tx_type,
+/// data_type, tag, next_tag)`.  A `None` next tag denotes the end of the
+/// encoding.
+fn tag_transitions() -> Vec<(u64, RlpDataType, TxFieldTag, Option<TxFieldTag>)> {
+    let mut transitions = Vec::new();
+    for tx_type in TX_TYPES {
+        for data_type in [RlpDataType::TxSign, RlpDataType::TxHash] {
+            for is_pre_eip155 in [false, true] {
+                let tags = tag_sequence(tx_type, data_type, is_pre_eip155);
+                for (idx, tag) in tags.iter().enumerate() {
+                    let transition = (tx_type, data_type, *tag, tags.get(idx + 1).cloned());
+                    if !transitions.contains(&transition) {
+                        transitions.push(transition);
+                    }
+                }
+            }
+        }
+    }
+    transitions
+}
+
+/// Return the big endian bytes of the integer without leading zeroes, as
+/// required by the RLP encoding.
+fn word_to_rlp_bytes(value: Word) -> Vec<u8> {
+    value
+        .to_be_bytes()
+        .iter()
+        .skip_while(|byte| **byte == 0)
+        .cloned()
+        .collect()
+}
+
+/// Return the big endian bytes of `value` without leading zeroes.
+fn length_to_bytes(value: usize) -> Vec<u8> {
+    word_to_rlp_bytes(Word::from(value))
+}
+
+/// Return the fields of the transaction in the order they are RLP encoded,
+/// with their content as RLP strings, or as the RLP list payload for list
+/// fields.  The transaction type byte and the list header are not included.
+pub(crate) fn tx_rlp_fields(
+    tx: &Transaction,
+    data_type: RlpDataType,
+    chain_id: u64,
+) -> Vec<(TxFieldTag, Vec<u8>)> {
+    let (sig_r, sig_s) = match (tx.transaction_type, data_type) {
+        (TransactionType::Legacy, RlpDataType::TxSign) => (Word::zero(), Word::zero()),
+        _ => (tx.r, tx.s),
+    };
+    tag_sequence(tx.transaction_type as u64, data_type, tx.is_pre_eip155())
+        .into_iter()
+        .filter(|tag| !matches!(tag, TxFieldTag::TxType | TxFieldTag::Null))
+        .map(|tag| {
+            let bytes = match tag {
+                TxFieldTag::ChainID => word_to_rlp_bytes(Word::from(chain_id)),
+                TxFieldTag::Nonce => word_to_rlp_bytes(tx.nonce),
+                TxFieldTag::GasPrice => word_to_rlp_bytes(tx.gas_price),
+                TxFieldTag::MaxPriorityFeePerGas => word_to_rlp_bytes(tx.gas_tip_cap),
+                TxFieldTag::MaxFeePerGas => word_to_rlp_bytes(tx.gas_fee_cap),
+                TxFieldTag::Gas => word_to_rlp_bytes(tx.gas_limit),
+                TxFieldTag::CalleeAddress => {
+                    tx.to.map(|to| to.as_bytes().to_vec()).unwrap_or_default()
+                }
+                TxFieldTag::Value => word_to_rlp_bytes(tx.value),
+                TxFieldTag::CallData => tx.call_data.0.to_vec(),
+                TxFieldTag::AccessListRLC => tx.access_list_items_rlp().concat(),
+                TxFieldTag::SigV => word_to_rlp_bytes(Word::from(tx.v)),
+                TxFieldTag::SigR => word_to_rlp_bytes(sig_r),
+                TxFieldTag::SigS => word_to_rlp_bytes(sig_s),
+                _ => unreachable!("tag {:?} is not RLP encoded", tag),
+            };
+            (tag, bytes)
+        })
+        .collect()
+}
+
+/// Witness row of the RLP circuit.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RlpRow<F> {
+    tx_id: usize,
+    tx_type: u64,
+    data_type: u64,
+    tag: u64,
+    is_first: bool,
+    is_last: bool,
+    is_prefix: bool,
+    is_length: bool,
+    is_tag_end: bool,
+    tag_rindex: usize,
+    length_acc: usize,
+    value_is_rlc: bool,
+    is_list: bool,
+    is_header: bool,
+    value_acc: F,
+    pub(crate) byte: u8,
+    index: usize,
+    pub(crate) rlc_acc: F,
+    list_length: usize,
+    header_length: usize,
+}
+
+/// Generate the witness rows of the RLP encoding of a transaction of type
+/// `tx_type` made of a list of `fields`, where each field is encoded as an RLP
+/// string or list.
+pub(crate) fn rlp_rows<F: Field>(
+    tx_id: usize,
+    tx_type: u64,
+    data_type: RlpDataType,
+    fields: &[(TxFieldTag, Vec<u8>)],
+    randomness: F,
+) -> Vec<RlpRow<F>> {
+    let new_row = |tag: TxFieldTag, byte: u8| RlpRow {
+        tx_id,
+        tx_type,
+        data_type: data_type as u64,
+        tag: tag as u64,
+        value_is_rlc: tag_is_rlc(tag),
+        is_list: tag_is_list(tag),
+        is_header: matches!(tag, TxFieldTag::Null),
+        byte,
+        ..RlpRow::default()
+    };
+
+    let mut payload_rows = Vec::new();
+    for (tag, bytes) in fields {
+        let mult = if tag_is_rlc(*tag) {
+            randomness
+        } else {
+            F::from(256)
+        };
+        let (short_offset, long_offset) = if tag_is_list(*tag) {
+            (0xc0, 0xf7)
+        } else {
+            (0x80, 0xb7)
+        };
+        if !tag_is_list(*tag) && bytes.len() == 1 && bytes[0] < 0x80 {
+            payload_rows.push(RlpRow {
+                is_prefix: true,
+                is_tag_end: true,
+                value_acc: F::from(bytes[0] as u64),
+                ..new_row(*tag, bytes[0])
+            });
+            continue;
+        }
+        if bytes.len() <= 55 {
+            payload_rows.push(RlpRow {
+                is_prefix: true,
+                is_tag_end: bytes.is_empty(),
+                ..new_row(*tag, short_offset + bytes.len() as u8)
+            });
+        } else {
+            let length_bytes = length_to_bytes(bytes.len());
+            payload_rows.push(RlpRow {
+                is_prefix: true,
+                ..new_row(*tag, long_offset + length_bytes.len() as u8)
+            });
+            let mut length_acc = 0;
+            for (idx, byte) in length_bytes.iter().enumerate() {
+                length_acc = length_acc * 256 + *byte as usize;
+                payload_rows.push(RlpRow {
+                    is_length: true,
+                    tag_rindex: length_bytes.len() - idx,
+                    length_acc,
+                    ..new_row(*tag, *byte)
+                });
+            }
+        }
+        let mut value_acc = F::zero();
+        for (idx, byte) in bytes.iter().enumerate() {
+            value_acc = value_acc * mult + F::from(*byte as u64);
+            payload_rows.push(RlpRow {
+                is_tag_end: idx == bytes.len() - 1,
+                tag_rindex: bytes.len() - idx,
+                value_acc,
+                ..new_row(*tag, *byte)
+            });
+        }
+    }
+
+    let list_length = payload_rows.len();
+    let mut rows = Vec::new();
+    if tx_type != LEGACY_TX_TYPE {
+        rows.push(RlpRow {
+            is_prefix: true,
+            is_tag_end: true,
+            value_acc: F::from(tx_type),
+            ..new_row(TxFieldTag::TxType, tx_type as u8)
+        });
+    }
+    if list_length <= 55 {
+        rows.push(RlpRow {
+            is_prefix: true,
+            is_tag_end: true,
+            length_acc: list_length,
+            ..new_row(TxFieldTag::Null, 0xc0 + list_length as u8)
+        });
+    } else {
+        let length_bytes = length_to_bytes(list_length);
+        rows.push(RlpRow {
+            is_prefix: true,
+            ..new_row(TxFieldTag::Null, 0xf7 + length_bytes.len() as u8)
+        });
+        let mut length_acc = 0;
+        for (idx, byte) in length_bytes.iter().enumerate() {
+            length_acc = length_acc * 256 + *byte as usize;
+            rows.push(RlpRow {
+                is_length: true,
+                is_tag_end: idx == length_bytes.len() - 1,
+                tag_rindex: length_bytes.len() - idx,
+                length_acc,
+                ..new_row(TxFieldTag::Null, *byte)
+            });
+        }
+    }
+    let header_length = rows.len();
+    rows.extend(payload_rows);
+
+    let mut rlc_acc = F::zero();
+    let num_rows = rows.len();
+    for (idx, row) in rows.iter_mut().enumerate() {
+        rlc_acc = rlc_acc * randomness + F::from(row.byte as u64);
+        row.is_first = idx == 0;
+        row.is_last = idx == num_rows - 1;
+        row.index = idx + 1;
+        row.rlc_acc = rlc_acc;
+        row.list_length = list_length;
+        row.header_length = header_length;
+    }
+    rows
+}
+
+/// Config for the RLP circuit
+#[derive(Clone, Debug)]
+pub struct RlpCircuitConfig<F> {
+    /// Whether the row is enabled.
+    pub q_enable: Column<Fixed>,
+    /// Whether the row is the first one of an encoding.
+    pub is_first: Column<Advice>,
+    /// Whether the row is the last one of an encoding.
+    pub is_last: Column<Advice>,
+    /// Transaction id of the encoded transaction.
+    pub tx_id: Column<Advice>,
+    /// Type of the encoded transaction.
+    pub tx_type: Column<Advice>,
+    /// Data type of the encoding, see [`RlpDataType`].
+    pub data_type: Column<Advice>,
+    /// Field of the transaction encoded in this row, see [`TxFieldTag`].  The
+    /// list header uses the `Null` tag, and the transaction type byte of typed
+    /// transactions uses the `TxType` tag.
+    pub tag: Column<Advice>,
+    /// Whether the byte is the prefix of a string or list.
+    pub is_prefix: Column<Advice>,
+    /// Whether the byte is part of the length of a long string or list.
+    pub is_length: Column<Advice>,
+    /// Whether the row is the last one of the current tag.
+    pub is_tag_end: Column<Advice>,
+    /// Number of bytes left in the current length or content segment,
+    /// including the current byte.
+    pub tag_rindex: Column<Advice>,
+    /// Accumulated length of a long string or list.
+    pub length_acc: Column<Advice>,
+    /// Whether the value is accumulated as RLC or as integer.
+    pub value_is_rlc: Column<Advice>,
+    /// Whether the field is encoded as a list.
+    pub is_list: Column<Advice>,
+    /// Whether the row is part of the list header.
+    pub is_header: Column<Advice>,
+    /// Accumulated value of the current field.
+    pub value_acc: Column<Advice>,
+    /// Byte of the encoding.
+    pub byte: Column<Advice>,
+    /// Index of the byte in the encoding, starting at 1.
+    pub index: Column<Advice>,
+    /// Accumulated RLC of the bytes of the encoding.
+    pub rlc_acc: Column<Advice>,
+    /// Length of the list payload.
+    pub list_length: Column<Advice>,
+    /// Length of the list header.
+    pub header_length: Column<Advice>,
+    /// Lt chips used to classify the prefix bytes.
+    byte_lt_0x80: LtConfig<F, 1>,
+    byte_lt_0xb8: LtConfig<F, 1>,
+    byte_lt_0xc0: LtConfig<F, 1>,
+    byte_lt_0xf8: LtConfig<F, 1>,
+    /// Fixed table of the valid tag transitions: [enabled, tx_type, data_type,
+    /// tag, tag_next, is_last, value_is_rlc, is_list, is_header]
+    tag_transitions: [Column<Fixed>; 9],
+    /// Range check chip with the fixed table of all the values of a byte.
+    pub(crate) range_check: RangeCheckConfig,
+}
+
+impl<F: Field> RlpCircuitConfig<F> {
+    /// Configure the RLP circuit.
+    pub fn configure(meta: &mut ConstraintSystem<F>, randomness: Expression<F>) -> Self {
+        let q_enable = meta.fixed_column();
+        let is_first = meta.advice_column();
+        let is_last = meta.advice_column();
+        let tx_id = meta.advice_column();
+        let tx_type = meta.advice_column();
+        let data_type = meta.advice_column();
+        let tag = meta.advice_column();
+        let is_prefix = meta.advice_column();
+        let is_length = meta.advice_column();
+        let is_tag_end = meta.advice_column();
+        let tag_rindex = meta.advice_column();
+        let length_acc = meta.advice_column();
+        let value_is_rlc = meta.advice_column();
+        let is_list = meta.advice_column();
+        let is_header = meta.advice_column();
+        let value_acc = meta.advice_column();
+        let byte = meta.advice_column();
+        let index = meta.advice_column();
+        let rlc_acc = meta.advice_column();
+        let list_length = meta.advice_column();
+        let header_length = meta.advice_column();
+        let tag_transitions = [(); 9].map(|_| meta.fixed_column());
+        let range_check = RangeCheckChip::configure(meta, false);
+
+        let byte_lt = |meta: &mut ConstraintSystem<F>, value: u64| {
+            LtChip::configure(
+                meta,
+                |meta| meta.query_fixed(q_enable, Rotation::cur()),
+                |meta| meta.query_advice(byte, Rotation::cur()),
+                |_| value.expr(),
+                range_check.u8,
+            )
+        };
+        let byte_lt_0x80 = byte_lt(meta, 0x80);
+        let byte_lt_0xb8 = byte_lt(meta, 0xb8);
+        let byte_lt_0xc0 = byte_lt(meta, 0xc0);
+        let byte_lt_0xf8 = byte_lt(meta, 0xf8);
+
+        meta.create_gate("rlp row", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let cur =
+                |meta: &mut VirtualCells<F>, column| meta.query_advice(column, Rotation::cur());
+            let next =
+                |meta: &mut VirtualCells<F>, column| meta.query_advice(column, Rotation::next());
+
+            let is_first_cur = cur(meta, is_first);
+            let is_last_cur = cur(meta, is_last);
+            let is_prefix_cur = cur(meta, is_prefix);
+            let is_length_cur = cur(meta, is_length);
+            let is_content_cur = 1.expr() - is_prefix_cur.clone() - is_length_cur.clone();
+            let is_tag_end_cur = cur(meta, is_tag_end);
+            let value_is_rlc_cur = cur(meta, value_is_rlc);
+            let is_list_cur = cur(meta, is_list);
+            let is_header_cur = cur(meta, is_header);
+            let tag_cur = cur(meta, tag);
+            let byte_cur = cur(meta, byte);
+            let tag_rindex_cur = cur(meta, tag_rindex);
+            let length_acc_cur = cur(meta, length_acc);
+            let value_acc_cur = cur(meta, value_acc);
+            let index_cur = cur(meta, index);
+
+            let is_prefix_next = next(meta, is_prefix);
+            let is_length_next = next(meta, is_length);
+            let is_content_next = 1.expr() - is_prefix_next.clone() - is_length_next.clone();
+            let byte_next = next(meta, byte);
+            let tag_rindex_next = next(meta, tag_rindex);
+
+            let lt_0x80 = byte_lt_0x80.is_lt(meta, None);
+            let lt_0xb8 = byte_lt_0xb8.is_lt(meta, None);
+            let lt_0xc0 = byte_lt_0xc0.is_lt(meta, None);
+            let lt_0xf8 = byte_lt_0xf8.is_lt(meta, None);
+
+            for (name, column) in [
+                ("is_first is boolean", is_first),
+                ("is_last is boolean", is_last),
+                ("is_prefix is boolean", is_prefix),
+                ("is_length is boolean", is_length),
+                ("is_tag_end is boolean", is_tag_end),
+                ("value_is_rlc is boolean", value_is_rlc),
+                ("is_list is boolean", is_list),
+                ("is_header is boolean", is_header),
+            ] {
+                cb.require_boolean(name, cur(meta, column));
+            }
+            cb.require_zero(
+                "is_prefix and is_length are exclusive",
+                is_prefix_cur.clone() * is_length_cur.clone(),
+            );
+            cb.require_zero(
+                "is_last implies is_tag_end",
+                is_last_cur.clone() * not::expr(is_tag_end_cur.clone()),
+            );
+            cb.require_zero(
+                "is_header implies is_list",
+                is_header_cur.clone() * not::expr(is_list_cur.clone()),
+            );
+
+            // First row of an encoding
+            cb.condition(is_first_cur.clone(), |cb| {
+                cb.require_zero(
+                    "tag == Null or tag == TxType for the first row",
+                    tag_cur.clone() * (tag_cur.clone() - TxFieldTag::TxType.expr()),
+                );
+                cb.require_equal(
+                    "is_prefix == 1 for the first row",
+                    is_prefix_cur.clone(),
+                    1.expr(),
+                );
+                cb.require_equal("index == 1 for the first row", index_cur.clone(), 1.expr());
+                cb.require_equal(
+                    "rlc_acc == byte for the first row",
+                    cur(meta, rlc_acc),
+                    byte_cur.clone(),
+                );
+            });
+            cb.condition(
+                and::expr([is_first_cur.clone(), is_header_cur.clone()]),
+                |cb| {
+                    cb.require_zero(
+                        "tx_type == 0 for encodings starting with the list header",
+                        cur(meta, tx_type),
+                    );
+                },
+            );
+            cb.condition(
+                and::expr([is_first_cur, not::expr(is_header_cur.clone())]),
+                |cb| {
+                    cb.require_equal(
+                        "tx_type == byte for encodings starting with the type byte",
+                        cur(meta, tx_type),
+                        byte_cur.clone(),
+                    );
+                },
+            );
+
+            // Transition between rows of the same encoding
+            cb.condition(not::expr(is_last_cur.clone()), |cb| {
+                cb.require_zero("next.is_first == 0", next(meta, is_first));
+                cb.require_equal(
+                    "next.index == index + 1",
+                    next(meta, index),
+                    index_cur.clone() + 1.expr(),
+                );
+                cb.require_equal(
+                    "next.rlc_acc == rlc_acc * r + next.byte",
+                    next(meta, rlc_acc),
+                    cur(meta, rlc_acc) * randomness.clone() + byte_next.clone(),
+                );
+                for (name, column) in [
+                    ("next.tx_id == tx_id", tx_id),
+                    ("next.tx_type == tx_type", tx_type),
+                    ("next.data_type == data_type", data_type),
+                    ("next.list_length == list_length", list_length),
+                    ("next.header_length == header_length", header_length),
+                ] {
+                    cb.require_equal(name, next(meta, column), cur(meta, column));
+                }
+            });
+            cb.condition(is_last_cur.clone(), |cb| {
+                cb.require_equal(
+                    "index == header_length + list_length for the last row",
+                    index_cur.clone(),
+                    cur(meta, header_length) + cur(meta, list_length),
+                );
+            });
+            cb.condition(
+                is_last_cur.clone() * meta.query_fixed(q_enable, Rotation::next()),
+                |cb| {
+                    cb.require_equal(
+                        "next.is_first == 1 after the last row",
+                        next(meta, is_first),
+                        1.expr(),
+                    );
+                },
+            );
+
+            // Transition between rows of the same tag.  The properties of the tag are
+            // verified against the tag transitions table at the end of the tag.
+            cb.condition(not::expr(is_tag_end_cur.clone()), |cb| {
+                for (name, column) in [
+                    ("next.tag == tag", tag),
+                    ("next.value_is_rlc == value_is_rlc", value_is_rlc),
+                    ("next.is_list == is_list", is_list),
+                    ("next.is_header == is_header", is_header),
+                ] {
+                    cb.require_equal(name, next(meta, column), cur(meta, column));
+                }
+                cb.require_zero("next.is_prefix == 0", is_prefix_next.clone());
+            });
+            cb.condition(
+                and::expr([is_tag_end_cur.clone(), not::expr(is_last_cur.clone())]),
+                |cb| {
+                    cb.require_equal(
+                        "next.is_prefix == 1 after the end of a tag",
+                        is_prefix_next.clone(),
+                        1.expr(),
+                    );
+                },
+            );
+            cb.condition(
+                and::expr([not::expr(is_tag_end_cur.clone()), is_length_next.clone()]),
+                |cb| {
+                    cb.require_equal(
+                        "next.length_acc == length_acc * 256 + next.byte",
+                        next(meta, length_acc),
+                        length_acc_cur.clone() * 256.expr() + byte_next.clone(),
+                    );
+                },
+            );
+            cb.condition(
+                and::expr([not::expr(is_tag_end_cur.clone()), is_content_next.clone()]),
+                |cb| {
+                    cb.require_equal(
+                        "next.value_acc == value_acc * (r or 256) + next.byte",
+                        next(meta, value_acc),
+                        value_acc_cur.clone()
+                            * select::expr(
+                                value_is_rlc_cur.clone(),
+                                randomness.clone(),
+                                256.expr(),
+                            )
+                            + byte_next.clone(),
+                    );
+                },
+            );
+
+            // Prefix of the list header
+            let is_header_prefix = and::expr([is_prefix_cur.clone(), is_header_cur.clone()]);
+            cb.condition(is_header_prefix.clone(), |cb| {
+                cb.require_zero("list prefix >= 0xc0", lt_0xc0.clone());
+                cb.require_zero(
+                    "length_acc == 0 for long list",
+                    length_acc_cur.clone() * not::expr(lt_0xf8.clone()),
+                );
+            });
+            cb.condition(
+                and::expr([is_header_prefix.clone(), lt_0xf8.clone()]),
+                |cb| {
+                    cb.require_equal(
+                        "short list ends the header",
+                        is_tag_end_cur.clone(),
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "list_length == byte - 0xc0 for short list",
+                        cur(meta, list_length),
+                        byte_cur.clone() - 0xc0.expr(),
+                    );
+                    cb.require_equal(
+                        "header_length == index for short list",
+                        cur(meta, header_length),
+                        index_cur.clone(),
+                    );
+                },
+            );
+            cb.condition(
+                and::expr([is_header_prefix, not::expr(lt_0xf8.clone())]),
+                |cb| {
+                    cb.require_zero("long list doesn't end the header", is_tag_end_cur.clone());
+                    cb.require_equal(
+                        "next.is_length == 1 for long list",
+                        is_length_next.clone(),
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "next.tag_rindex == byte - 0xf7 for long list",
+                        tag_rindex_next.clone(),
+                        byte_cur.clone() - 0xf7.expr(),
+                    );
+                },
+            );
+
+            // Prefix of a field, which is either a string or a list
+            let is_field_prefix =
+                and::expr([is_prefix_cur.clone(), not::expr(is_header_cur.clone())]);
+            cb.condition(is_field_prefix.clone(), |cb| {
+                cb.require_equal(
+                    "string prefix < 0xc0 and list prefix >= 0xc0",
+                    lt_0xc0.clone(),
+                    not::expr(is_list_cur.clone()),
+                );
+                cb.require_zero("length_acc == 0 for field prefix", length_acc_cur.clone());
+            });
+            let is_string_prefix =
+                and::expr([is_field_prefix.clone(), not::expr(is_list_cur.clone())]);
+            let is_list_prefix = and::expr([is_field_prefix, is_list_cur.clone()]);
+            cb.condition(
+                and::expr([is_string_prefix.clone(), lt_0x80.clone()]),
+                |cb| {
+                    cb.require_equal(
+                        "single byte string ends the tag",
+                        is_tag_end_cur.clone(),
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "value_acc == byte for single byte string",
+                        value_acc_cur.clone(),
+                        byte_cur.clone(),
+                    );
+                },
+            );
+            for (is_short, is_long, short_offset, long_offset) in [
+                (
+                    and::expr([
+                        is_string_prefix.clone(),
+                        not::expr(lt_0x80),
+                        lt_0xb8.clone(),
+                    ]),
+                    and::expr([is_string_prefix, not::expr(lt_0xb8)]),
+                    0x80,
+                    0xb7,
+                ),
+                (
+                    and::expr([is_list_prefix.clone(), lt_0xf8.clone()]),
+                    and::expr([is_list_prefix, not::expr(lt_0xf8)]),
+                    0xc0,
+                    0xf7,
+                ),
+            ] {
+                cb.condition(is_short, |cb| {
+                    cb.require_zero("value_acc == 0 for short prefix", value_acc_cur.clone());
+                    cb.require_zero(
+                        "short field is empty when it ends the tag",
+                        is_tag_end_cur.clone() * (byte_cur.clone() - short_offset.expr()),
+                    );
+                    cb.require_zero(
+                        "next.is_content == 1 for non empty short field",
+                        not::expr(is_tag_end_cur.clone()) * not::expr(is_content_next.clone()),
+                    );
+                    cb.require_zero(
+                        "next.tag_rindex == byte - short_offset for non empty short field",
+                        not::expr(is_tag_end_cur.clone())
+                            * (tag_rindex_next.clone() - (byte_cur.clone() - short_offset.expr())),
+                    );
+                });
+                cb.condition(is_long, |cb| {
+                    cb.require_zero("value_acc == 0 for long prefix", value_acc_cur.clone());
+                    cb.require_zero("long prefix doesn't end the tag", is_tag_end_cur.clone());
+                    cb.require_equal(
+                        "next.is_length == 1 for long field",
+                        is_length_next.clone(),
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "next.tag_rindex == byte - long_offset for long field",
+                        tag_rindex_next.clone(),
+                        byte_cur.clone() - long_offset.expr(),
+                    );
+                });
+            }
+
+            // Length of a long list header
+            let is_header_length = and::expr([is_length_cur.clone(), is_header_cur.clone()]);
+            cb.condition(
+                and::expr([is_header_length.clone(), is_tag_end_cur.clone()]),
+                |cb| {
+                    cb.require_equal(
+                        "tag_rindex == 1 for the last length byte",
+                        tag_rindex_cur.clone(),
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "list_length == length_acc",
+                        cur(meta, list_length),
+                        length_acc_cur.clone(),
+                    );
+                    cb.require_equal(
+                        "header_length == index",
+                        cur(meta, header_length),
+                        index_cur.clone(),
+                    );
+                },
+            );
+            cb.condition(
+                and::expr([is_header_length, not::expr(is_tag_end_cur.clone())]),
+                |cb| {
+                    cb.require_equal(
+                        "next.is_length == 1 for list length",
+                        is_length_next.clone(),
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "next.tag_rindex == tag_rindex - 1 for list length",
+                        tag_rindex_next.clone(),
+                        tag_rindex_cur.clone() - 1.expr(),
+                    );
+                },
+            );
+
+            // Length of a long field
+            let is_field_length =
+                and::expr([is_length_cur.clone(), not::expr(is_header_cur.clone())]);
+            cb.condition(is_field_length.clone(), |cb| {
+                cb.require_zero("value_acc == 0 for field length", value_acc_cur.clone());
+                cb.require_zero("field length doesn't end the tag", is_tag_end_cur.clone());
+            });
+            cb.condition(
+                and::expr([is_field_length.clone(), is_length_next.clone()]),
+                |cb| {
+                    cb.require_equal(
+                        "next.tag_rindex == tag_rindex - 1 for field length",
+                        tag_rindex_next.clone(),
+                        tag_rindex_cur.clone() - 1.expr(),
+                    );
+                },
+            );
+            cb.condition(
+                and::expr([is_field_length, is_content_next.clone()]),
+                |cb| {
+                    cb.require_equal(
+                        "tag_rindex == 1 for the last length byte",
+                        tag_rindex_cur.clone(),
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "next.tag_rindex == length_acc",
+                        tag_rindex_next.clone(),
+                        length_acc_cur.clone(),
+                    );
+                },
+            );
+
+            // Content of a field
+            cb.condition(is_content_cur.clone(), |cb| {
+                cb.require_zero("content is not part of the list header", is_header_cur);
+            });
+            cb.condition(
+                and::expr([is_content_cur.clone(), is_tag_end_cur.clone()]),
+                |cb| {
+                    cb.require_equal(
+                        "tag_rindex == 1 for the last content byte",
+                        tag_rindex_cur.clone(),
+                        1.expr(),
+                    );
+                },
+            );
+            cb.condition(
+                and::expr([is_content_cur, not::expr(is_tag_end_cur)]),
+                |cb| {
+                    cb.require_equal(
+                        "next.is_content == 1 within content",
+                        is_content_next,
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "next.tag_rindex == tag_rindex - 1 within content",
+                        tag_rindex_next,
+                        tag_rindex_cur - 1.expr(),
+                    );
+                },
+            );
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.lookup_any("rlp tag transition", |meta| {
+            let cond = meta.query_fixed(q_enable, Rotation::cur())
+                * meta.query_advice(is_tag_end, Rotation::cur());
+            let is_last = meta.query_advice(is_last, Rotation::cur());
+            vec![
+                1.expr(),
+                meta.query_advice(tx_type, Rotation::cur()),
+                meta.query_advice(data_type, Rotation::cur()),
+                meta.query_advice(tag, Rotation::cur()),
+                not::expr(is_last.clone()) * meta.query_advice(tag, Rotation::next()),
+                is_last,
+                meta.query_advice(value_is_rlc, Rotation::cur()),
+                meta.query_advice(is_list, Rotation::cur()),
+                meta.query_advice(is_header, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(tag_transitions.into_iter())
+            .map(|(arg, table)| (cond.clone() * arg, meta.query_fixed(table, Rotation::cur())))
+            .collect()
+        });
+
+        range_check.range_check_u8(
+            meta,
+            "rlp byte range",
+            |meta| meta.query_fixed(q_enable, Rotation::cur()),
+            [byte],
+        );
+
+        Self {
+            q_enable,
+            is_first,
+            is_last,
+            tx_id,
+            tx_type,
+            data_type,
+            tag,
+            is_prefix,
+            is_length,
+            is_tag_end,
+            tag_rindex,
+            length_acc,
+            value_is_rlc,
+            is_list,
+            is_header,
+            value_acc,
+            byte,
+            index,
+            rlc_acc,
+            list_length,
+            header_length,
+            byte_lt_0x80,
+            byte_lt_0xb8,
+            byte_lt_0xc0,
+            byte_lt_0xf8,
+            tag_transitions,
+            range_check,
+        }
+    }
+
+    /// Expressions of the field values of the encodings, to be looked up with
+    /// `[1, tx_id, data_type, tag, value]`.
+    pub(crate) fn field_table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        let cond = meta.query_fixed(self.q_enable, Rotation::cur())
+            * meta.query_advice(self.is_tag_end, Rotation::cur());
+        vec![
+            cond.clone(),
+            cond.clone() * meta.query_advice(self.tx_id, Rotation::cur()),
+            cond.clone() * meta.query_advice(self.data_type, Rotation::cur()),
+            cond.clone() * meta.query_advice(self.tag, Rotation::cur()),
+            cond * meta.query_advice(self.value_acc, Rotation::cur()),
+        ]
+    }
+
+    /// Expressions of the full encodings, to be looked up with `[1, tx_id,
+    /// data_type, length, rlc]`.
+    pub(crate) fn encoding_table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        let cond = meta.query_fixed(self.q_enable, Rotation::cur())
+            * meta.query_advice(self.is_last, Rotation::cur());
+        vec![
+            cond.clone(),
+            cond.clone() * meta.query_advice(self.tx_id, Rotation::cur()),
+            cond.clone() * meta.query_advice(self.data_type, Rotation::cur()),
+            cond.clone() * meta.query_advice(self.index, Rotation::cur()),
+            cond * meta.query_advice(self.rlc_acc, Rotation::cur()),
+        ]
+    }
+
+    /// Load the fixed tables of the RLP circuit.
+    pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        RangeCheckChip::construct(self.range_check).load(layouter)?;
+        layouter.assign_region(
+            || "rlp tag transitions",
+            |mut region| {
+                // All zero row to allow simulating a disabled lookup.
+                for column in self.tag_transitions {
+                    region.assign_fixed(|| "tag transitions", column, 0, || Ok(F::zero()))?;
+                }
+                for (offset, (tx_type, data_type, tag, tag_next)) in
+                    tag_transitions().into_iter().enumerate()
+                {
+                    for (column, value) in self.tag_transitions.iter().zip([
+                        F::one(),
+                        F::from(tx_type),
+                        F::from(data_type as u64),
+                        F::from(tag as u64),
+                        F::from(tag_next.map(|tag| tag as u64).unwrap_or(0)),
+                        F::from(tag_next.is_none() as u64),
+                        F::from(tag_is_rlc(tag) as u64),
+                        F::from(tag_is_list(tag) as u64),
+                        F::from(matches!(tag, TxFieldTag::Null) as u64),
+                    ]) {
+                        region.assign_fixed(
+                            || "tag transitions",
+                            *column,
+                            offset + 1,
+                            || Ok(value),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn assign_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: &RlpRow<F>,
+        q_enable: bool,
+    ) -> Result<(), Error> {
+        region.assign_fixed(
+            || "q_enable",
+            self.q_enable,
+            offset,
+            || Ok(F::from(q_enable as u64)),
+        )?;
+        for (name, column, value) in [
+            ("is_first", self.is_first, F::from(row.is_first as u64)),
+            ("is_last", self.is_last, F::from(row.is_last as u64)),
+            ("tx_id", self.tx_id, F::from(row.tx_id as u64)),
+            ("tx_type", self.tx_type, F::from(row.tx_type)),
+            ("data_type", self.data_type, F::from(row.data_type)),
+            ("tag", self.tag, F::from(row.tag)),
+            ("is_prefix", self.is_prefix, F::from(row.is_prefix as u64)),
+            ("is_length", self.is_length, F::from(row.is_length as u64)),
+            (
+                "is_tag_end",
+                self.is_tag_end,
+                F::from(row.is_tag_end as u64),
+            ),
+            (
+                "tag_rindex",
+                self.tag_rindex,
+                F::from(row.tag_rindex as u64),
+            ),
+            (
+                "length_acc",
+                self.length_acc,
+                F::from(row.length_acc as u64),
+            ),
+            (
+                "value_is_rlc",
+                self.value_is_rlc,
+                F::from(row.value_is_rlc as u64),
+            ),
+            ("is_list", self.is_list, F::from(row.is_list as u64)),
+            ("is_header", self.is_header, F::from(row.is_header as u64)),
+            ("value_acc", self.value_acc, row.value_acc),
+            ("byte", self.byte, F::from(row.byte as u64)),
+            ("index", self.index, F::from(row.index as u64)),
+            ("rlc_acc", self.rlc_acc, row.rlc_acc),
+            (
+                "list_length",
+                self.list_length,
+                F::from(row.list_length as u64),
+            ),
+            (
+                "header_length",
+                self.header_length,
+                F::from(row.header_length as u64),
+            ),
+        ] {
+            region.assign_advice(
+                || format!("assign {} {}", name, offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+
+        for (config, value) in [
+            (self.byte_lt_0x80, 0x80),
+            (self.byte_lt_0xb8, 0xb8),
+            (self.byte_lt_0xc0, 0xc0),
+            (self.byte_lt_0xf8, 0xf8),
+        ] {
+            LtChip::construct(config).assign(
+                region,
+                offset,
+                F::from(row.byte as u64),
+                F::from(value),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Assign the witness rows of all the encodings.
+    pub(crate) fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        rows: &[RlpRow<F>],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "rlp circuit",
+            |mut region| {
+                let mut offset = 0;
+                for row in rows {
+                    self.assign_row(&mut region, offset, row, true)?;
+                    offset += 1;
+                }
+                // pad two rows in the end to satisfy Halo2 cell assignment check
+                for _ in 0..2 {
+                    self.assign_row(&mut region, offset, &RlpRow::default(), false)?;
+                    offset += 1;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod rlp_circuit_tests {
+    use super::*;
+    use eth_types::{address, word, AccessList, Bytes, H256};
+    use ethers_core::types::transaction::eip2930::AccessListItem;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::{MockProver, VerifyFailure},
+        pairing::bn256::Fr,
+        plonk::{Circuit, Instance},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[derive(Clone)]
+    struct TestRlpConfig<F> {
+        rlp: RlpCircuitConfig<F>,
+        _randomness: Column<Instance>,
+    }
+
+    #[derive(Default)]
+    struct TestRlpCircuit<F> {
+        rows: Vec<RlpRow<F>>,
+    }
+
+    impl<F: Field> Circuit<F> for TestRlpCircuit<F> {
+        type Config = TestRlpConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let randomness_column = meta.instance_column();
+            let mut randomness = None;
+            meta.create_gate("randomness", |meta| {
+                randomness = Some(meta.query_instance(randomness_column, Rotation::cur()));
+                [0.expr()]
+            });
+            TestRlpConfig {
+                rlp: RlpCircuitConfig::configure(meta, randomness.unwrap()),
+                _randomness: randomness_column,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.rlp.load(&mut layouter)?;
+            config.rlp.assign(&mut layouter, &self.rows)
+        }
+    }
+
+    fn tx(call_data: Vec<u8>) -> Transaction {
+        Transaction {
+            from: address!("0x5f9b7e36af4ff81688f712fb738bbbc1b7348aae"),
+            to: Some(address!("0x701653d7ae8ddaa5c8cee1ee056849f271827926")),
+            nonce: word!("0x3"),
+            gas_limit: word!("0x7a120"),
+            value: word!("0x0"),
+            gas_price: word!("0x4d2"),
+            call_data: Bytes::from(call_data),
+            v: 2710,
+            r: word!("0xaf180d27f90b2b20808bc7670ce0aca862bc2b5fa39c195ab7b1a96225ee14d7"),
+            s: word!("0x61159fa4664b698ea7d518526c96cd94cf4d8adf418000754be106a3a133f866"),
+            ..Transaction::default()
+        }
+    }
+
+    fn pre_eip155_tx(call_data: Vec<u8>) -> Transaction {
+        Transaction {
+            v: 28,
+            ..tx(call_data)
+        }
+    }
+
+    fn create_tx(call_data: Vec<u8>) -> Transaction {
+        Transaction {
+            to: None,
+            ..tx(call_data)
+        }
+    }
+
+    fn access_list_tx(call_data: Vec<u8>, num_storage_keys: u64) -> Transaction {
+        Transaction {
+            transaction_type: TransactionType::AccessList,
+            access_list: Some(AccessList(vec![
+                AccessListItem {
+                    address: address!("0x701653d7ae8ddaa5c8cee1ee056849f271827926"),
+                    storage_keys: (0..num_storage_keys).map(H256::from_low_u64_be).collect(),
+                },
+                AccessListItem {
+                    address: address!("0x5f9b7e36af4ff81688f712fb738bbbc1b7348aae"),
+                    storage_keys: vec![],
+                },
+            ])),
+            v: 1,
+            ..tx(call_data)
+        }
+    }
+
+    fn dynamic_fee_tx(call_data: Vec<u8>, num_storage_keys: u64) -> Transaction {
+        Transaction {
+            transaction_type: TransactionType::DynamicFee,
+            gas_fee_cap: word!("0x59682f00"),
+            gas_tip_cap: word!("0x3b9aca00"),
+            ..access_list_tx(call_data, num_storage_keys)
+        }
+    }
+
+    fn all_rows(txs: &[Transaction], randomness: Fr) -> Vec<RlpRow<Fr>> {
+        let mut rows = Vec::new();
+        for (tx_id, tx) in txs.iter().enumerate() {
+            for data_type in [RlpDataType::TxSign, RlpDataType::TxHash] {
+                rows.extend(rlp_rows(
+                    tx_id + 1,
+                    tx.transaction_type as u64,
+                    data_type,
+                    &tx_rlp_fields(tx, data_type, 1337),
+                    randomness,
+                ));
+            }
+        }
+        rows
+    }
+
+    fn run(rows: Vec<RlpRow<Fr>>, randomness: Fr) -> Result<(), Vec<VerifyFailure>> {
+        let k = 12;
+        // The randomness is required in all the rows, including the 2 padding rows.
+        let instance = vec![vec![randomness; rows.len() + 2]];
+        let circuit = TestRlpCircuit { rows };
+        let prover = MockProver::run(k, &circuit, instance).unwrap();
+        prover.verify()
+    }
+
+    #[test]
+    fn rlp_rows_match_rlp_encoding() {
+        let chain_id = 1337;
+        let randomness = Fr::from(0x100);
+        for call_data in [vec![], vec![0x7f], b"hello".to_vec(), vec![0xab; 300]] {
+            for tx in [
+                tx(call_data.clone()),
+                pre_eip155_tx(call_data.clone()),
+                create_tx(call_data.clone()),
+                access_list_tx(call_data.clone(), 0),
+                access_list_tx(call_data.clone(), 2),
+                dynamic_fee_tx(call_data, 1),
+            ] {
+                for (data_type, expected) in [
+                    (RlpDataType::TxSign, tx.sign_rlp(chain_id)),
+                    (RlpDataType::TxHash, tx.rlp(chain_id)),
+                ] {
+                    let rows = rlp_rows(
+                        1,
+                        tx.transaction_type as u64,
+                        data_type,
+                        &tx_rlp_fields(&tx, data_type, chain_id),
+                        randomness,
+                    );
+                    let bytes: Vec<u8> = rows.iter().map(|row| row.byte).collect();
+                    assert_eq!(bytes, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rlp_circuit_valid() {
+        let randomness = Fr::from(0xcafe);
+        let txs: Vec<Transaction> = [vec![], b"hello".to_vec(), vec![0xab; 300]]
+            .into_iter()
+            .map(tx)
+            .collect();
+        assert_eq!(run(all_rows(&txs, randomness), randomness), Ok(()));
+    }
+
+    #[test]
+    fn rlp_circuit_pre_eip155_valid() {
+        let randomness = Fr::from(0xcafe);
+        let txs = vec![
+            pre_eip155_tx(b"hello".to_vec()),
+            tx(vec![]),
+            pre_eip155_tx(vec![0xab; 300]),
+        ];
+        assert_eq!(run(all_rows(&txs, randomness), randomness), Ok(()));
+    }
+
+    #[test]
+    fn rlp_circuit_create_valid() {
+        let randomness = Fr::from(0xcafe);
+        let txs = vec![
+            create_tx(b"hello".to_vec()),
+            tx(vec![]),
+            Transaction {
+                to: None,
+                ..access_list_tx(vec![0xab; 300], 1)
+            },
+        ];
+        assert_eq!(run(all_rows(&txs, randomness), randomness), Ok(()));
+    }
+
+    #[test]
+    fn rlp_circuit_access_list_valid() {
+        let randomness = Fr::from(0xcafe);
+        // Short and long access lists
+        let txs = vec![
+            access_list_tx(b"hello".to_vec(), 0),
+            access_list_tx(vec![], 3),
+            tx(vec![0xab; 300]),
+        ];
+        assert_eq!(run(all_rows(&txs, randomness), randomness), Ok(()));
+    }
+
+    #[test]
+    fn rlp_circuit_dynamic_fee_valid() {
+        let randomness = Fr::from(0xcafe);
+        let txs = vec![
+            dynamic_fee_tx(b"hello".to_vec(), 0),
+            access_list_tx(vec![], 1),
+            dynamic_fee_tx(vec![0xab; 300], 2),
+        ];
+        assert_eq!(run(all_rows(&txs, randomness), randomness), Ok(()));
+    }
+
+    #[test]
+    fn rlp_circuit_invalid_tx_type() {
+        let randomness = Fr::from(0xcafe);
+        let tx = access_list_tx(b"hello".to_vec(), 1);
+        let mut rows = rlp_rows(
+            1,
+            tx.transaction_type as u64,
+            RlpDataType::TxSign,
+            &tx_rlp_fields(&tx, RlpDataType::TxSign, 1337),
+            randomness,
+        );
+        // Encode the access list transaction as a legacy one by dropping the
+        // type byte.
+        rows.remove(0);
+        for row in rows.iter_mut() {
+            row.tx_type = LEGACY_TX_TYPE;
+            row.index -= 1;
+            row.header_length -= 1;
+        }
+        rows[0].is_first = true;
+        let mut rlc_acc = Fr::zero();
+        for row in rows.iter_mut() {
+            rlc_acc = rlc_acc * randomness + Fr::from(row.byte as u64);
+            row.rlc_acc = rlc_acc;
+        }
+        assert!(run(rows, randomness).is_err());
+    }
+
+    #[test]
+    fn rlp_circuit_invalid_length() {
+        let randomness = Fr::from(0xcafe);
+        let tx = tx(b"hello".to_vec());
+        let mut rows = rlp_rows(
+            1,
+            tx.transaction_type as u64,
+            RlpDataType::TxSign,
+            &tx_rlp_fields(&tx, RlpDataType::TxSign, 1337),
+            randomness,
+        );
+        // Skip the last content byte of the call data while keeping its prefix.
+        let idx = rows
+            .iter()
+            .position(|row| row.tag == TxFieldTag::CallData as u64 && row.is_tag_end)
+            .unwrap();
+        rows.remove(idx);
+        rows[idx - 1].is_tag_end = true;
+        assert!(run(rows, randomness).is_err());
+    }
+}
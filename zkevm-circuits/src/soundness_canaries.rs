@@ -0,0 +1,252 @@
+//! Soundness canaries: adversarial witnesses of known attacks, each asserted
+//! to be rejected by the circuit constraining the forged data.
+//!
+//! Every canary starts from a valid witness, checks that it is accepted, and
+//! forges only the data of the attack, so that the canary fails as soon as a
+//! change of the constraints lets the attack through.  New audit findings get
+//! a canary here.
+
+use crate::{
+    bytecode_circuit::bytecode_unroller::{
+        tests::{verify as verify_bytecodes, MyCircuit as BytecodeTestCircuit},
+        unroll,
+    },
+    evm_circuit::{
+        step::ExecutionState,
+        table::CallContextFieldTag,
+        test::{run_test_circuit_incomplete_fixed_table, TestCircuit},
+        witness::{Block, Rw},
+    },
+    state_circuit::StateCircuit,
+    test_util::witness_block,
+};
+use eth_types::{bytecode, evm_types::OpcodeId, Address, ToWord, Word};
+use halo2_proofs::{dev::MockProver, pairing::bn256::Fr};
+use mock::TestContext;
+
+/// Asserts that the EVM circuit rejects `block`, with a failure at a step in
+/// `execution_state`.
+fn assert_evm_circuit_rejects_at(block: Block<Fr>, execution_state: ExecutionState) {
+    let steps = TestCircuit::step_map(&block);
+    let failures = run_test_circuit_incomplete_fixed_table(block)
+        .expect_err("forged witness accepted by the evm circuit");
+    assert!(
+        failures.into_iter().any(|failure| steps
+            .prettify(failure)
+            .step
+            .map_or(false, |step| step.execution_state == execution_state)),
+        "forged witness not rejected at a {:?} step",
+        execution_state
+    );
+}
+
+/// Returns the index of the first step of the first transaction of `block` in
+/// `execution_state`.
+fn step_index(block: &Block<Fr>, execution_state: ExecutionState) -> usize {
+    block.txs[0]
+        .steps
+        .iter()
+        .position(|step| step.execution_state == execution_state)
+        .unwrap()
+}
+
+#[test]
+fn jump_into_push_data() {
+    // The JUMPDEST at 4 is code, as the byte at 3 is a STOP.
+    let code = bytecode! {
+        PUSH1(4)
+        JUMP
+        STOP
+        JUMPDEST
+        STOP
+    };
+    let mut block = witness_block(TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap());
+    assert_eq!(
+        run_test_circuit_incomplete_fixed_table(block.clone()),
+        Ok(())
+    );
+
+    // Forge the byte at 3 into a PUSH1, so that the JUMPDEST at 4 becomes its
+    // push data, without changing the trace.
+    let bytecode = block
+        .bytecodes
+        .values_mut()
+        .find(|bytecode| bytecode.bytes.len() == 6)
+        .unwrap();
+    bytecode.bytes[3] = OpcodeId::PUSH1.as_u8();
+
+    assert_evm_circuit_rejects_at(block, ExecutionState::JUMP);
+}
+
+#[test]
+fn forged_keccak_output() {
+    let k = 9;
+    let r = BytecodeTestCircuit::<Fr>::r();
+    let code = bytecode! {
+        PUSH1(1)
+        PUSH1(2)
+        ADD
+        STOP
+    }
+    .to_vec();
+    let unrolled = unroll(code.clone(), r);
+    verify_bytecodes::<Fr>(k, vec![unrolled.clone()], true);
+
+    // Claim the hash of another bytecode for the same bytes.
+    let mut other_code = code;
+    other_code[1] = 3;
+    let forged_hash = unroll(other_code, r).rows[0].hash;
+    let mut forged = unrolled;
+    for row in forged.rows.iter_mut() {
+        row.hash = forged_hash;
+    }
+
+    verify_bytecodes::<Fr>(k, vec![forged], false);
+}
+
+#[test]
+fn unsorted_rw_table() {
+    let block = witness_block(
+        TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode! {
+            PUSH1(1)
+            PUSH1(2)
+            ADD
+            POP
+            STOP
+        })
+        .unwrap(),
+    );
+    const N_ROWS: usize = 1 << 16;
+    let verify = |circuit: StateCircuit<Fr>| {
+        let n_rows = circuit.rows.len();
+        let power_of_randomness = circuit.instance();
+        MockProver::<Fr>::run(17, &circuit, power_of_randomness)
+            .unwrap()
+            .verify_at_rows(N_ROWS - n_rows..N_ROWS, N_ROWS - n_rows..N_ROWS)
+    };
+    let circuit = StateCircuit::new(block.randomness, block.rws.clone(), N_ROWS);
+    assert_eq!(verify(circuit), Ok(()));
+
+    // Swap the last two rows, which are sorted by their keys.
+    let mut unsorted = StateCircuit::new(block.randomness, block.rws, N_ROWS);
+    let n_rows = unsorted.rows.len();
+    unsorted.rows.swap(n_rows - 2, n_rows - 1);
+
+    assert!(verify(unsorted).is_err());
+}
+
+#[test]
+fn gas_underflow() {
+    let mut block = witness_block(
+        TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode! {
+            PUSH1(1)
+            PUSH1(2)
+            ADD
+            STOP
+        })
+        .unwrap(),
+    );
+    assert_eq!(
+        run_test_circuit_incomplete_fixed_table(block.clone()),
+        Ok(())
+    );
+
+    // Lower the gas of the transaction, consistently up to the ADD step, which
+    // is left with less gas than its cost.  The gas cost of the ADD step is
+    // forged to the gas left for its witness to be assignable.
+    let add = step_index(&block, ExecutionState::ADD_SUB);
+    let gas_left = OpcodeId::ADD.constant_gas_cost().as_u64() - 1;
+    let delta = block.txs[0].steps[add].gas_left - gas_left;
+    let tx = &mut block.txs[0];
+    tx.gas -= delta;
+    for step in tx.steps[..=add].iter_mut() {
+        step.gas_left -= delta;
+    }
+    tx.steps[add].gas_cost = gas_left;
+    for step in tx.steps[add + 1..].iter_mut() {
+        step.gas_left = 0;
+        step.gas_cost = 0;
+    }
+
+    assert_evm_circuit_rejects_at(block, ExecutionState::ADD_SUB);
+}
+
+#[test]
+fn reentrancy_revert() {
+    // The contract calls itself with one byte of call data, and the reentered
+    // call writes to the storage before reverting.
+    let contract = Address::repeat_byte(0xfe);
+    let code = bytecode! {
+        CALLDATASIZE
+        PUSH1(40)
+        JUMPI
+
+        PUSH1(0)
+        PUSH1(0)
+        PUSH1(1)
+        PUSH1(0)
+        PUSH1(0)
+        PUSH20(contract.to_word())
+        PUSH2(10000)
+        CALL
+        STOP
+
+        JUMPDEST // 40
+        PUSH1(1)
+        PUSH1(0)
+        SSTORE
+        PUSH1(0)
+        PUSH1(0)
+        REVERT
+    };
+    let mut block = witness_block(
+        TestContext::<2, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(Address::repeat_byte(0xfd))
+                    .balance(Word::from(10u64.pow(19)));
+                accs[1].address(contract).code(code);
+            },
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[0].address)
+                    .to(accs[1].address)
+                    .gas(100000.into());
+            },
+            |block, _tx| block,
+        )
+        .unwrap(),
+    );
+    assert_eq!(
+        run_test_circuit_incomplete_fixed_table(block.clone()),
+        Ok(())
+    );
+
+    // Forge the reverted call into a persistent one, so that its storage
+    // write would not be reverted.
+    let reverted = block.txs[0]
+        .calls
+        .iter_mut()
+        .find(|call| !call.is_success)
+        .unwrap();
+    reverted.is_persistent = true;
+    let reverted_id = reverted.id;
+    for rws in block.rws.0.values_mut() {
+        for rw in rws.iter_mut() {
+            if let Rw::CallContext {
+                call_id,
+                field_tag: CallContextFieldTag::IsPersistent,
+                value,
+                ..
+            } = rw
+            {
+                if *call_id == reverted_id {
+                    *value = Word::one();
+                }
+            }
+        }
+    }
+
+    assert_evm_circuit_rejects_at(block, ExecutionState::CALL);
+}
@@ -7,13 +7,19 @@ mod random_linear_combination;
 #[cfg(test)]
 mod test;
 
-use crate::evm_circuit::{
-    param::N_BYTES_WORD,
-    table::RwTableTag,
-    witness::{Rw, RwMap},
+use crate::{
+    evm_circuit::{
+        param::N_BYTES_WORD,
+        table::{LookupTable, RwTableTag},
+        witness::{Block, Rw, RwMap},
+    },
+    mpt_circuit::MptProofType,
+    rw_table::RwTable,
+    table::MptTable,
 };
+use bus_mapping::circuit_input_builder::CircuitsParams;
 use constraint_builder::{ConstraintBuilder, Queries};
-use eth_types::{Address, Field};
+use eth_types::{Address, Field, Word};
 use gadgets::{
     binary_number::{BinaryNumberChip, BinaryNumberConfig},
     util::Expr,
@@ -29,6 +35,7 @@ use lexicographic_ordering::Config as LexicographicOrderingConfig;
 use lookups::{Chip as LookupsChip, Config as LookupsConfig, Queries as LookupsQueries};
 use multiple_precision_integer::{Chip as MpiChip, Config as MpiConfig, Queries as MpiQueries};
 use random_linear_combination::{Chip as RlcChip, Config as RlcConfig, Queries as RlcQueries};
+use rayon::prelude::*;
 #[cfg(test)]
 use std::collections::HashMap;
 use std::iter::once;
@@ -42,13 +49,19 @@ const N_LIMBS_ID: usize = 2;
 pub struct StateConfig {
     selector: Column<Fixed>, // Figure out why you get errors when this is Selector.
     // https://github.com/privacy-scaling-explorations/zkevm-circuits/issues/407
+    // The rw table shared with the EVM circuit, whose rw_counter, key and
+    // field tag columns are the values of the sort keys.
+    rw_table: RwTable,
     sort_keys: SortKeysConfig,
-    is_write: Column<Advice>,
-    value: Column<Advice>,
     initial_value: Column<Advice>, /* Assigned value at the start of the block. For Rw::Account
                                     * and Rw::AccountStorage rows this is the committed value in
                                     * the MPT, for others, it is 0. */
+    not_first_access: Column<Advice>,
+    // The state root after the updates of the mpt table looked up up to the
+    // row, chained from the root before the first update.
+    state_root: Column<Advice>,
     lexicographic_ordering: LexicographicOrderingConfig,
+    mpt_table: MptTable,
     lookups: LookupsConfig,
     power_of_randomness: [Column<Instance>; N_BYTES_WORD - 1],
 }
@@ -68,69 +81,153 @@ type Lookup<F> = (&'static str, Expression<F>, Expression<F>);
 
 /// State Circuit for proving RwTable is valid
 #[derive(Default)]
-pub struct StateCircuit<F: Field, const N_ROWS: usize> {
+pub struct StateCircuit<F: Field> {
     pub(crate) randomness: F,
     pub(crate) rows: Vec<Rw>,
+    /// Number of rows of the rw table, including the padding rows
+    pub(crate) n_rows: usize,
     #[cfg(test)]
     overrides: HashMap<(test::AdviceColumn, isize), F>,
 }
 
-impl<F: Field, const N_ROWS: usize> StateCircuit<F, N_ROWS> {
-    /// make a new state circuit from an RwMap
-    pub fn new(randomness: F, rw_map: RwMap) -> Self {
+impl<F: Field> StateCircuit<F> {
+    /// make a new state circuit from an RwMap, padded up to `n_rows` rows
+    pub fn new(randomness: F, rw_map: RwMap, n_rows: usize) -> Self {
         let mut rows: Vec<_> = rw_map.0.into_values().flatten().collect();
-        rows.sort_by_key(|row| {
-            (
-                row.tag() as u64,
-                row.id().unwrap_or_default(),
-                row.address().unwrap_or_default(),
-                row.field_tag().unwrap_or_default(),
-                row.storage_key().unwrap_or_default(),
-                row.rw_counter(),
-            )
-        });
+        rows.sort_by_key(sort_key);
         Self {
             randomness,
             rows,
+            n_rows,
             #[cfg(test)]
             overrides: HashMap::new(),
         }
     }
 
+    /// make a new state circuit from the rws of a witness block, padded up to
+    /// the `max_rws` rows of its circuits params
+    pub fn new_from_block(block: &Block<F>) -> Self {
+        Self::new(
+            block.randomness,
+            block.rws.clone(),
+            block.circuits_params.max_rws,
+        )
+    }
+
+    /// Return the minimum number of rows required to assign the rw table and
+    /// the fixed tables of the circuit with the capacities of `params`.
+    pub fn min_num_rows(params: &CircuitsParams) -> usize {
+        std::cmp::max(params.max_rws, 1 << 16)
+    }
+
     /// powers of randomness for instance columns
     pub fn instance(&self) -> Vec<Vec<F>> {
         (1..32)
-            .map(|exp| vec![self.randomness.pow(&[exp, 0, 0, 0]); N_ROWS])
+            .map(|exp| vec![self.randomness.pow(&[exp, 0, 0, 0]); self.n_rows])
             .collect()
     }
+
+    /// Return the `n_rows` rows of the mpt table made of the update from the
+    /// initial value to the final value of every account field and storage
+    /// slot accessed by the rows, padded with empty updates, along with the
+    /// state roots they chain.  They are proven by the MPT circuit in the
+    /// super circuit, and the roots are their indices here.
+    fn dev_mpt_updates(&self) -> (Vec<[F; 7]>, Vec<F>) {
+        let mut updates = Vec::new();
+        let mut initial_value = F::zero();
+        for (idx, row) in self.rows.iter().enumerate() {
+            let proof_type = match row.tag() {
+                RwTableTag::Account => row.field_tag().unwrap_or_default(),
+                RwTableTag::AccountStorage => MptProofType::StorageMod as u64,
+                _ => continue,
+            };
+            let table_row = row.table_assignment(self.randomness);
+            if idx == 0 || !is_same_access_group(row, &self.rows[idx - 1]) {
+                initial_value = table_row.value_prev;
+            }
+            if is_mpt_update(row, self.rows.get(idx + 1)) {
+                let root = F::from(updates.len() as u64);
+                updates.push([
+                    table_row.key2,
+                    table_row.key4,
+                    F::from(proof_type),
+                    root,
+                    root + F::one(),
+                    initial_value,
+                    table_row.value,
+                ]);
+            }
+        }
+        let state_roots = (0..=updates.len() as u64).map(F::from).collect();
+        updates.resize(self.n_rows, [F::zero(); 7]);
+        (updates, state_roots)
+    }
 }
 
-impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
+impl<F: Field> Circuit<F> for StateCircuit<F> {
     type Config = StateConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
-        Self::default()
+        // The number of rows fixes the selector column, which must be the same
+        // in the verifying key.
+        Self {
+            n_rows: self.n_rows,
+            ..Self::default()
+        }
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let power_of_randomness = [0; N_BYTES_WORD - 1].map(|_| meta.instance_column());
+        let rw_table = RwTable::construct(meta);
+        let mpt_table = MptTable::construct(meta);
+        StateConfig::configure(meta, power_of_randomness, rw_table, mpt_table)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let (mpt_updates, state_roots) = self.dev_mpt_updates();
+        config.mpt_table.dev_load(&mut layouter, &mpt_updates)?;
+        self.assign(&config, &mut layouter, &state_roots)
+    }
+}
+
+impl StateConfig {
+    /// Configure the state circuit assigning the `rw_table`, sorted by the
+    /// keys of the accessed values, and looking up the update of the last
+    /// access to each account field and storage slot in the `mpt_table`
+    /// assigned by the MPT circuit.
+    pub fn configure<F: Field>(
+        meta: &mut ConstraintSystem<F>,
+        power_of_randomness: [Column<Instance>; N_BYTES_WORD - 1],
+        rw_table: RwTable,
+        mpt_table: MptTable,
+    ) -> Self {
         let selector = meta.fixed_column();
         let lookups = LookupsChip::configure(meta);
-        let power_of_randomness = [0; N_BYTES_WORD - 1].map(|_| meta.instance_column());
 
-        let [is_write, field_tag, value, initial_value] = [0; 4].map(|_| meta.advice_column());
+        let [initial_value, not_first_access, state_root] = [0; 3].map(|_| meta.advice_column());
 
         let tag = BinaryNumberChip::configure(meta, selector);
 
-        let id = MpiChip::configure(meta, selector, lookups.u16);
-        let address = MpiChip::configure(meta, selector, lookups.u16);
-        let storage_key = RlcChip::configure(meta, selector, lookups.u8, power_of_randomness);
-        let rw_counter = MpiChip::configure(meta, selector, lookups.u16);
+        let id = MpiChip::configure(meta, selector, rw_table.key1, lookups.u16);
+        let address = MpiChip::configure(meta, selector, rw_table.key2, lookups.u16);
+        let storage_key = RlcChip::configure(
+            meta,
+            selector,
+            rw_table.key4,
+            lookups.u8,
+            power_of_randomness,
+        );
+        let rw_counter = MpiChip::configure(meta, selector, rw_table.rw_counter, lookups.u16);
 
         let sort_keys = SortKeysConfig {
             tag,
             id,
-            field_tag,
+            field_tag: rw_table.key3,
             address,
             storage_key,
             rw_counter,
@@ -143,13 +240,15 @@ impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
             power_of_randomness,
         );
 
-        let config = Self::Config {
+        let config = Self {
             selector,
+            rw_table,
             sort_keys,
-            is_write,
-            value,
             initial_value,
+            not_first_access,
+            state_root,
             lexicographic_ordering,
+            mpt_table,
             lookups,
             power_of_randomness,
         };
@@ -161,45 +260,72 @@ impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
             constraint_builder.gate(queries.selector)
         });
         for (name, expressions) in constraint_builder.lookups() {
-            meta.lookup_any(name, |_| vec![expressions]);
+            meta.lookup_any(name, |_| expressions);
         }
 
         config
     }
+}
 
-    fn synthesize(
+impl<F: Field> StateCircuit<F> {
+    /// Assign the rows of the rw table, padded at the front up to `n_rows`
+    /// rows, along with the `state_roots` chained by the updates of the mpt
+    /// table, which start with the root before the first update.
+    pub fn assign(
         &self,
-        config: Self::Config,
-        mut layouter: impl Layouter<F>,
+        config: &StateConfig,
+        layouter: &mut impl Layouter<F>,
+        state_roots: &[F],
     ) -> Result<(), Error> {
-        LookupsChip::construct(config.lookups).load(&mut layouter)?;
+        LookupsChip::construct(config.lookups).load(layouter)?;
 
         let tag_chip = BinaryNumberChip::construct(config.sort_keys.tag);
 
+        let padding_length = self.n_rows - self.rows.len();
+        let padding = (1..=padding_length).map(|rw_counter| Rw::Start { rw_counter });
+        let rows: Vec<Rw> = padding.chain(self.rows.iter().cloned()).collect();
+        // The values of the rows, which are random linear combinations for
+        // the words, are computed in parallel before being assigned in order.
+        let table_rows: Vec<_> = rows
+            .par_iter()
+            .map(|row| row.table_assignment(self.randomness))
+            .collect();
+
         layouter.assign_region(
             || "rw table",
             |mut region| {
-                let padding_length = N_ROWS - self.rows.len();
-                let padding = (1..=padding_length).map(|rw_counter| Rw::Start { rw_counter });
-
-                let rows = padding.chain(self.rows.iter().cloned());
-                let prev_rows = once(None).chain(rows.clone().map(Some));
+                let prev_rows = once(None).chain(rows.iter().map(Some));
 
                 let mut initial_value = F::zero();
+                let mut state_roots = state_roots.iter();
+                let mut state_root = *state_roots.next().ok_or(Error::Synthesis)?;
 
-                for (offset, (row, prev_row)) in rows.zip(prev_rows).enumerate() {
+                for (offset, ((row, table_row), prev_row)) in
+                    rows.iter().zip(&table_rows).zip(prev_rows).enumerate()
+                {
                     region.assign_fixed(|| "selector", config.selector, offset, || Ok(F::one()))?;
+                    // The columns of the sort keys are assigned along with
+                    // their limbs or bytes below.
+                    for (name, column, value) in [
+                        ("is_write", config.rw_table.is_write, table_row.is_write),
+                        ("tag", config.rw_table.tag, table_row.tag),
+                        ("field_tag", config.rw_table.key3, table_row.key3),
+                        ("value", config.rw_table.value, table_row.value),
+                        (
+                            "value_prev",
+                            config.rw_table.value_prev,
+                            table_row.value_prev,
+                        ),
+                        ("aux1", config.rw_table.aux1, table_row.aux1),
+                        ("aux2", config.rw_table.aux2, table_row.aux2),
+                    ] {
+                        region.assign_advice(|| name, column, offset, || Ok(value))?;
+                    }
                     config.sort_keys.rw_counter.assign(
                         &mut region,
                         offset,
                         row.rw_counter() as u32,
                     )?;
-                    region.assign_advice(
-                        || "is_write",
-                        config.is_write,
-                        offset,
-                        || Ok(if row.is_write() { F::one() } else { F::zero() }),
-                    )?;
                     tag_chip.assign(&mut region, offset, &row.tag())?;
                     if let Some(id) = row.id() {
                         config.sort_keys.id.assign(&mut region, offset, id as u32)?;
@@ -210,14 +336,6 @@ impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
                             .address
                             .assign(&mut region, offset, address)?;
                     }
-                    if let Some(field_tag) = row.field_tag() {
-                        region.assign_advice(
-                            || "field_tag",
-                            config.sort_keys.field_tag,
-                            offset,
-                            || Ok(F::from(field_tag as u64)),
-                        )?;
-                    }
                     if let Some(storage_key) = row.storage_key() {
                         config.sort_keys.storage_key.assign(
                             &mut region,
@@ -226,30 +344,25 @@ impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
                             storage_key,
                         )?;
                     }
-                    region.assign_advice(
-                        || "value",
-                        config.value,
-                        offset,
-                        || Ok(row.value_assignment(self.randomness)),
-                    )?;
 
+                    let mut not_first_access = false;
                     if let Some(prev_row) = prev_row {
                         let is_first_access = config.lexicographic_ordering.assign(
                             &mut region,
                             offset,
-                            &row,
-                            &prev_row,
+                            row,
+                            prev_row,
                         )?;
+                        not_first_access = !is_first_access;
 
                         // TODO: Get initial_values from MPT updates instead.
                         if is_first_access {
                             // TODO: Set initial values for Rw::CallContext to be 0 instead of
                             // special casing it.
                             initial_value = if matches!(row.tag(), RwTableTag::CallContext) {
-                                row.value_assignment(self.randomness)
+                                table_row.value
                             } else {
-                                row.value_prev_assignment(self.randomness)
-                                    .unwrap_or_default()
+                                table_row.value_prev
                             };
                         }
                     }
@@ -260,6 +373,22 @@ impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
                         offset,
                         || Ok(initial_value),
                     )?;
+                    region.assign_advice(
+                        || "not_first_access",
+                        config.not_first_access,
+                        offset,
+                        || Ok(F::from(not_first_access)),
+                    )?;
+
+                    if is_mpt_update(row, rows.get(offset + 1)) {
+                        state_root = *state_roots.next().ok_or(Error::Synthesis)?;
+                    }
+                    region.assign_advice(
+                        || "state_root",
+                        config.state_root,
+                        offset,
+                        || Ok(state_root),
+                    )?;
                 }
 
                 #[cfg(test)]
@@ -277,6 +406,32 @@ impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
     }
 }
 
+/// Returns the keys the rows are sorted by, which are the keys of the
+/// accessed value followed by the rw counter.
+pub(crate) fn sort_key(row: &Rw) -> (u64, usize, Address, u64, Word, usize) {
+    (
+        row.tag() as u64,
+        row.id().unwrap_or_default(),
+        row.address().unwrap_or_default(),
+        row.field_tag().unwrap_or_default(),
+        row.storage_key().unwrap_or_default(),
+        row.rw_counter(),
+    )
+}
+
+/// Returns true if the rows access the same value, having the same keys.
+pub(crate) fn is_same_access_group(a: &Rw, b: &Rw) -> bool {
+    (a.tag(), a.id(), a.address(), a.field_tag(), a.storage_key())
+        == (b.tag(), b.id(), b.address(), b.field_tag(), b.storage_key())
+}
+
+/// Returns true if the row is the last access to an account field or a
+/// storage slot, whose update is looked up in the mpt table.
+fn is_mpt_update(row: &Rw, next_row: Option<&Rw>) -> bool {
+    matches!(row.tag(), RwTableTag::Account | RwTableTag::AccountStorage)
+        && next_row.map_or(true, |next_row| !is_same_access_group(row, next_row))
+}
+
 fn queries<F: Field>(meta: &mut VirtualCells<'_, F>, c: &StateConfig) -> Queries<F> {
     let first_different_limb = c.lexicographic_ordering.first_different_limb;
     let final_bits_sum = meta.query_advice(first_different_limb.bits[3], Rotation::cur())
@@ -287,8 +442,9 @@ fn queries<F: Field>(meta: &mut VirtualCells<'_, F>, c: &StateConfig) -> Queries
         lexicographic_ordering_selector: meta
             .query_fixed(c.lexicographic_ordering.selector, Rotation::cur()),
         rw_counter: MpiQueries::new(meta, c.sort_keys.rw_counter),
-        is_write: meta.query_advice(c.is_write, Rotation::cur()),
+        is_write: meta.query_advice(c.rw_table.is_write, Rotation::cur()),
         tag: c.sort_keys.tag.value(Rotation::cur())(meta),
+        rw_table_tag: meta.query_advice(c.rw_table.tag, Rotation::cur()),
         tag_bits: c
             .sort_keys
             .tag
@@ -307,8 +463,8 @@ fn queries<F: Field>(meta: &mut VirtualCells<'_, F>, c: &StateConfig) -> Queries
         address: MpiQueries::new(meta, c.sort_keys.address),
         field_tag: meta.query_advice(c.sort_keys.field_tag, Rotation::cur()),
         storage_key: RlcQueries::new(meta, c.sort_keys.storage_key),
-        value: meta.query_advice(c.value, Rotation::cur()),
-        value_prev: meta.query_advice(c.value, Rotation::prev()),
+        value: meta.query_advice(c.rw_table.value, Rotation::cur()),
+        value_prev: meta.query_advice(c.rw_table.value, Rotation::prev()),
         initial_value: meta.query_advice(c.initial_value, Rotation::cur()),
         initial_value_prev: meta.query_advice(c.initial_value, Rotation::prev()),
         lookups: LookupsQueries::new(meta, c.lookups),
@@ -323,9 +479,16 @@ fn queries<F: Field>(meta: &mut VirtualCells<'_, F>, c: &StateConfig) -> Queries
             - meta.query_advice(first_different_limb.bits[3], Rotation::cur()),
         // 1 if first_different_limb is in the rw counter, 0 otherwise (i.e. any of the 4 most
         // significant bits are 0)
-        not_first_access: meta.query_advice(first_different_limb.bits[0], Rotation::cur())
+        first_different_limb_is_rw_counter: meta
+            .query_advice(first_different_limb.bits[0], Rotation::cur())
             * meta.query_advice(first_different_limb.bits[1], Rotation::cur())
             * meta.query_advice(first_different_limb.bits[2], Rotation::cur())
             * meta.query_advice(first_different_limb.bits[3], Rotation::cur()),
+        not_first_access: meta.query_advice(c.not_first_access, Rotation::cur()),
+        last_access: 1.expr() - meta.query_advice(c.not_first_access, Rotation::next()),
+        state_root: meta.query_advice(c.state_root, Rotation::cur()),
+        state_root_prev: meta.query_advice(c.state_root, Rotation::prev()),
+        selector_next: meta.query_fixed(c.selector, Rotation::next()),
+        mpt_table: c.mpt_table.table_exprs(meta).try_into().unwrap(),
     }
 }
@@ -8,6 +8,7 @@ use crate::evm_circuit::{
     table::{AccountFieldTag, RwTableTag},
     util::not,
 };
+use crate::mpt_circuit::MptProofType;
 use crate::util::Expr;
 use eth_types::Field;
 use gadgets::binary_number::BinaryNumberConfig;
@@ -21,6 +22,7 @@ pub struct Queries<F: Field> {
     pub rw_counter: MpiQueries<F, N_LIMBS_RW_COUNTER>,
     pub is_write: Expression<F>,
     pub tag: Expression<F>,
+    pub rw_table_tag: Expression<F>,
     pub tag_bits: [Expression<F>; 4],
     pub id: MpiQueries<F, N_LIMBS_ID>,
     pub is_tag_and_id_unchanged: Expression<F>,
@@ -34,11 +36,17 @@ pub struct Queries<F: Field> {
     pub lookups: LookupsQueries<F>,
     pub power_of_randomness: [Expression<F>; N_BYTES_WORD - 1],
     pub first_access: Expression<F>,
+    pub first_different_limb_is_rw_counter: Expression<F>,
     pub not_first_access: Expression<F>,
+    pub last_access: Expression<F>,
+    pub selector_next: Expression<F>,
+    pub state_root: Expression<F>,
+    pub state_root_prev: Expression<F>,
+    pub mpt_table: [Expression<F>; 8],
 }
 
 type Constraint<F> = (&'static str, Expression<F>);
-type Lookup<F> = (&'static str, (Expression<F>, Expression<F>));
+type Lookup<F> = (&'static str, Vec<(Expression<F>, Expression<F>)>);
 
 pub struct ConstraintBuilder<F: Field> {
     pub constraints: Vec<Constraint<F>>,
@@ -107,6 +115,11 @@ impl<F: Field> ConstraintBuilder<F> {
 
     fn build_general_constraints(&mut self, q: &Queries<F>) {
         // tag value in RwTableTag range is enforced in BinaryNumberChip
+        self.require_equal(
+            "tag in rw table matches tag bits",
+            q.rw_table_tag.clone(),
+            q.tag.clone(),
+        );
         self.require_boolean("is_write is boolean", q.is_write());
 
         // When at least one of the keys (tag, id, address, field_tag, or storage_key)
@@ -118,6 +131,16 @@ impl<F: Field> ConstraintBuilder<F> {
             );
         });
 
+        self.require_equal(
+            "not_first_access is 1 iff the first different limb is in the rw counter",
+            q.not_first_access.clone(),
+            q.first_different_limb_is_rw_counter.clone(),
+        );
+        self.require_zero(
+            "not_first_access is 0 after the last row",
+            (1.expr() - q.selector_next.clone()) * not::expr(q.last_access()),
+        );
+
         // When all the keys in the current row and previous row are equal.
         self.condition(q.not_first_access.clone(), |cb| {
             cb.require_zero(
@@ -129,6 +152,19 @@ impl<F: Field> ConstraintBuilder<F> {
                 q.initial_value.clone() - q.initial_value_prev(),
             );
         });
+
+        // The state root is only changed by the updates of the mpt table,
+        // which chain it from one update to the next.
+        self.condition(
+            q.lexicographic_ordering_selector.clone() * not::expr(q.is_mpt_update()),
+            |cb| {
+                cb.require_equal(
+                    "state_root is unchanged out of the mpt updates",
+                    q.state_root(),
+                    q.state_root_prev(),
+                )
+            },
+        );
     }
 
     fn build_start_constraints(&mut self, q: &Queries<F>) {
@@ -154,7 +190,7 @@ impl<F: Field> ConstraintBuilder<F> {
         }
         self.add_lookup(
             "memory value is a byte",
-            (q.value.clone(), q.lookups.u8.clone()),
+            vec![(q.value.clone(), q.lookups.u8.clone())],
         );
         self.require_zero("initial Memory value is 0", q.initial_value());
     }
@@ -168,7 +204,7 @@ impl<F: Field> ConstraintBuilder<F> {
         );
         self.add_lookup(
             "stack address fits into 10 bits",
-            (q.address.value.clone(), q.lookups.u10.clone()),
+            vec![(q.address.value.clone(), q.lookups.u10.clone())],
         );
         self.condition(q.is_tag_and_id_unchanged.clone(), |cb| {
             cb.require_boolean(
@@ -184,8 +220,11 @@ impl<F: Field> ConstraintBuilder<F> {
         // TODO: cold VS warm
         self.require_zero("field_tag is 0 for AccountStorage", q.field_tag());
 
-        // TODO: add mpt lookup for committed value and final value in an access
-        // group.
+        self.add_mpt_lookup(
+            "mpt update of the last AccountStorage access",
+            q,
+            MptProofType::StorageMod.expr(),
+        );
     }
     fn build_tx_access_list_account_constraints(&mut self, q: &Queries<F>) {
         self.require_zero("field_tag is 0 for TxAccessListAccount", q.field_tag());
@@ -234,8 +273,8 @@ impl<F: Field> ConstraintBuilder<F> {
             set::<F, AccountFieldTag>(),
         );
 
-        // TODO: add mpt lookup for committed value and final value in an access
-        // group.
+        // The proof types of the account fields are their field tags.
+        self.add_mpt_lookup("mpt update of the last Account access", q, q.field_tag());
     }
 
     fn build_account_destructed_constraints(&mut self, q: &Queries<F>) {
@@ -256,7 +295,7 @@ impl<F: Field> ConstraintBuilder<F> {
         );
         self.add_lookup(
             "field_tag in CallContextFieldTag range",
-            (q.field_tag(), q.lookups.call_context_field_tag.clone()),
+            vec![(q.field_tag(), q.lookups.call_context_field_tag.clone())],
         );
         // TODO: explain why call context doesn't need an initial value.
     }
@@ -361,12 +400,37 @@ impl<F: Field> ConstraintBuilder<F> {
         );
     }
 
-    fn add_lookup(&mut self, name: &'static str, lookup: (Expression<F>, Expression<F>)) {
-        let mut lookup = lookup;
-        lookup.0 = lookup.0 * self.condition.clone();
+    fn add_lookup(&mut self, name: &'static str, lookup: Vec<(Expression<F>, Expression<F>)>) {
+        let lookup = lookup
+            .into_iter()
+            .map(|(input, table)| (input * self.condition.clone(), table))
+            .collect();
         self.lookups.push((name, lookup));
     }
 
+    // Looks up the update from the initial value to the final value of an
+    // account field or a storage slot at the last access to it, from the state
+    // root of the previous row to the state root of the row.
+    fn add_mpt_lookup(&mut self, name: &'static str, q: &Queries<F>, proof_type: Expression<F>) {
+        let [q_enable, address, storage_key, mpt_proof_type, old_root, new_root, old_value, new_value] =
+            q.mpt_table.clone();
+        self.condition(q.last_access(), |cb| {
+            cb.add_lookup(
+                name,
+                vec![
+                    (1.expr(), q_enable),
+                    (q.address.value.clone(), address),
+                    (q.storage_key.encoded.clone(), storage_key),
+                    (proof_type, mpt_proof_type),
+                    (q.state_root_prev(), old_root),
+                    (q.state_root(), new_root),
+                    (q.initial_value(), old_value),
+                    (q.value(), new_value),
+                ],
+            )
+        });
+    }
+
     fn condition(&mut self, condition: Expression<F>, build: impl FnOnce(&mut Self)) {
         let original_condition = self.condition.clone();
         self.condition = self.condition.clone() * condition;
@@ -428,6 +492,25 @@ impl<F: Field> Queries<F> {
         self.first_access.clone()
     }
 
+    fn last_access(&self) -> Expression<F> {
+        self.last_access.clone()
+    }
+
+    // Whether the row is the last access to an account field or a storage
+    // slot, looked up in the mpt table.
+    fn is_mpt_update(&self) -> Expression<F> {
+        self.last_access()
+            * (self.tag_matches(RwTableTag::Account) + self.tag_matches(RwTableTag::AccountStorage))
+    }
+
+    fn state_root(&self) -> Expression<F> {
+        self.state_root.clone()
+    }
+
+    fn state_root_prev(&self) -> Expression<F> {
+        self.state_root_prev.clone()
+    }
+
     fn address_change(&self) -> Expression<F> {
         self.address.value.clone() - self.address.value_prev.clone()
     }
@@ -1,5 +1,5 @@
 use crate::evm_circuit::util::rlc;
-use eth_types::{Field, ToLittleEndian, U256};
+use eth_types::{Field, Rlc, ToLittleEndian, U256};
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter, Region},
     plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Instance, VirtualCells},
@@ -52,7 +52,7 @@ impl<const N: usize> Config<N> {
             || "encoded value in rlc",
             self.encoded,
             offset,
-            || Ok(rlc::value(&bytes, randomness)),
+            || Ok(bytes.rlc(randomness)),
         )
     }
 }
@@ -73,10 +73,10 @@ impl<F: Field, const N: usize> Chip<F, N> {
     pub fn configure(
         meta: &mut ConstraintSystem<F>,
         selector: Column<Fixed>,
+        encoded: Column<Advice>,
         u8_lookup: Column<Fixed>,
         power_of_randomness: [Column<Instance>; 31],
     ) -> Config<N> {
-        let encoded = meta.advice_column();
         let bytes = [0; N].map(|_| meta.advice_column());
 
         for &byte in &bytes {
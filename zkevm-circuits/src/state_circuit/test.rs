@@ -47,19 +47,20 @@ pub enum AdviceColumn {
     LimbIndexBit3,
     LimbIndexBit4, // least significant bit
     InitialValue,
+    StateRoot,
 }
 
 impl AdviceColumn {
     pub fn value(&self, config: &StateConfig) -> Column<Advice> {
         match self {
-            Self::IsWrite => config.is_write,
+            Self::IsWrite => config.rw_table.is_write,
             Self::Address => config.sort_keys.address.value,
             Self::AddressLimb0 => config.sort_keys.address.limbs[0],
             Self::AddressLimb1 => config.sort_keys.address.limbs[1],
             Self::StorageKey => config.sort_keys.storage_key.encoded,
             Self::StorageKeyByte0 => config.sort_keys.storage_key.bytes[0],
             Self::StorageKeyByte1 => config.sort_keys.storage_key.bytes[1],
-            Self::Value => config.value,
+            Self::Value => config.rw_table.value,
             Self::RwCounter => config.sort_keys.rw_counter.value,
             Self::RwCounterLimb0 => config.sort_keys.rw_counter.limbs[0],
             Self::RwCounterLimb1 => config.sort_keys.rw_counter.limbs[1],
@@ -73,6 +74,7 @@ impl AdviceColumn {
             Self::LimbIndexBit3 => config.lexicographic_ordering.first_different_limb.bits[3],
             Self::LimbIndexBit4 => config.lexicographic_ordering.first_different_limb.bits[4],
             Self::InitialValue => config.initial_value,
+            Self::StateRoot => config.state_root,
         }
     }
 }
@@ -90,7 +92,7 @@ fn test_state_circuit_ok(
     });
 
     let randomness = Fr::rand();
-    let circuit = StateCircuit::<Fr, N_ROWS>::new(randomness, rw_map);
+    let circuit = StateCircuit::<Fr>::new(randomness, rw_map, N_ROWS);
     let power_of_randomness = circuit.instance();
 
     let prover = MockProver::<Fr>::run(19, &circuit, power_of_randomness).unwrap();
@@ -101,7 +103,7 @@ fn test_state_circuit_ok(
 #[test]
 fn degree() {
     let mut meta = ConstraintSystem::<Fr>::default();
-    StateCircuit::<Fr, N_ROWS>::configure(&mut meta);
+    StateCircuit::<Fr>::configure(&mut meta);
     assert_eq!(meta.degree(), 9);
 }
 
@@ -111,8 +113,8 @@ fn verifying_key_independent_of_rw_length() {
     let degree = 17;
     let params = Params::<G1Affine>::unsafe_setup::<Bn256>(degree);
 
-    let no_rows = StateCircuit::<Fr, N_ROWS>::new(randomness, RwMap::default());
-    let one_row = StateCircuit::<Fr, N_ROWS>::new(
+    let no_rows = StateCircuit::<Fr>::new(randomness, RwMap::default(), N_ROWS);
+    let one_row = StateCircuit::<Fr>::new(
         randomness,
         RwMap::from(&OperationContainer {
             memory: vec![Operation::new(
@@ -122,6 +124,7 @@ fn verifying_key_independent_of_rw_length() {
             )],
             ..Default::default()
         }),
+        N_ROWS,
     );
 
     // halo2::plonk::VerifyingKey doesn't derive Eq, so we check for equality using
@@ -415,13 +418,13 @@ fn tx_log_bad() {
 
 #[test]
 fn address_limb_mismatch() {
-    let rows = vec![Rw::Account {
+    let rows = vec![Rw::TxAccessListAccount {
         rw_counter: 1,
         is_write: false,
+        tx_id: 1,
         account_address: address!("0x000000000000000000000000000000000cafe002"),
-        field_tag: AccountFieldTag::CodeHash,
-        value: U256::zero(),
-        value_prev: U256::zero(),
+        is_warm: false,
+        is_warm_prev: false,
     }];
     let overrides = HashMap::from([((AdviceColumn::Address, 0), Fr::from(10))]);
 
@@ -452,15 +455,14 @@ fn address_limb_out_of_range() {
 
 #[test]
 fn storage_key_mismatch() {
-    let rows = vec![Rw::AccountStorage {
+    let rows = vec![Rw::TxAccessListAccountStorage {
         rw_counter: 1,
         is_write: false,
+        tx_id: 4,
         account_address: Address::default(),
         storage_key: U256::from(6),
-        value: U256::from(34),
-        value_prev: U256::from(34),
-        tx_id: 4,
-        committed_value: U256::from(34),
+        is_warm: false,
+        is_warm_prev: false,
     }];
     let overrides = HashMap::from([((AdviceColumn::StorageKey, 0), Fr::from(10))]);
 
@@ -471,15 +473,14 @@ fn storage_key_mismatch() {
 
 #[test]
 fn storage_key_byte_out_of_range() {
-    let rows = vec![Rw::AccountStorage {
+    let rows = vec![Rw::TxAccessListAccountStorage {
         rw_counter: 1,
         is_write: false,
+        tx_id: 4,
         account_address: Address::default(),
         storage_key: U256::from(256),
-        value: U256::from(500),
-        value_prev: U256::from(500),
-        tx_id: 4,
-        committed_value: U256::from(500),
+        is_warm: false,
+        is_warm_prev: false,
     }];
     let overrides = HashMap::from([
         ((AdviceColumn::StorageKey, 0), Fr::from(256)),
@@ -727,6 +728,78 @@ fn read_inconsistency() {
     assert_error_matches(verify(rows), "non-first access reads don't change value");
 }
 
+#[test]
+fn invalid_account_final_value() {
+    let rows = vec![
+        Rw::Account {
+            rw_counter: 1,
+            is_write: true,
+            account_address: address!("0x000000000000000000000000000000000cafe002"),
+            field_tag: AccountFieldTag::Nonce,
+            value: U256::one(),
+            value_prev: U256::zero(),
+        },
+        Rw::Account {
+            rw_counter: 2,
+            is_write: true,
+            account_address: address!("0x000000000000000000000000000000000cafe002"),
+            field_tag: AccountFieldTag::Nonce,
+            value: U256::from(2),
+            value_prev: U256::one(),
+        },
+    ];
+    // The intermediate values of an access group aren't looked up in the MPT
+    // table.
+    let overrides = HashMap::from([((AdviceColumn::Value, 0), Fr::from(3))]);
+    assert_eq!(verify_with_overrides(rows.clone(), overrides), Ok(()));
+
+    let overrides = HashMap::from([((AdviceColumn::Value, 1), Fr::from(3))]);
+    let result = verify_with_overrides(rows, overrides);
+
+    assert_error_matches(result, "mpt update of the last Account access");
+}
+
+#[test]
+fn invalid_state_root_of_mpt_update() {
+    let rows = vec![Rw::Account {
+        rw_counter: 1,
+        is_write: true,
+        account_address: address!("0x000000000000000000000000000000000cafe002"),
+        field_tag: AccountFieldTag::Nonce,
+        value: U256::one(),
+        value_prev: U256::zero(),
+    }];
+    let overrides = HashMap::from([((AdviceColumn::StateRoot, 0), Fr::from(2))]);
+    let result = verify_with_overrides(rows, overrides);
+
+    assert_error_matches(result, "mpt update of the last Account access");
+}
+
+#[test]
+fn state_root_changed_out_of_mpt_updates() {
+    let rows = vec![
+        Rw::Account {
+            rw_counter: 1,
+            is_write: true,
+            account_address: address!("0x000000000000000000000000000000000cafe002"),
+            field_tag: AccountFieldTag::Nonce,
+            value: U256::one(),
+            value_prev: U256::zero(),
+        },
+        Rw::CallContext {
+            rw_counter: 2,
+            is_write: true,
+            call_id: 1,
+            field_tag: CallContextFieldTag::TxId,
+            value: U256::one(),
+        },
+    ];
+    let overrides = HashMap::from([((AdviceColumn::StateRoot, 1), Fr::from(2))]);
+    let result = verify_with_overrides(rows, overrides);
+
+    assert_error_matches(result, "state_root is unchanged out of the mpt updates");
+}
+
 #[test]
 fn all_padding() {
     assert_eq!(
@@ -978,9 +1051,10 @@ fn bad_initial_tx_receipt_value() {
 
 fn prover(rows: Vec<Rw>, overrides: HashMap<(AdviceColumn, isize), Fr>) -> MockProver<Fr> {
     let randomness = Fr::rand();
-    let circuit = StateCircuit::<Fr, N_ROWS> {
+    let circuit = StateCircuit::<Fr> {
         randomness,
         rows,
+        n_rows: N_ROWS,
         overrides,
     };
     let power_of_randomness = circuit.instance();
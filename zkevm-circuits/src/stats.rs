@@ -0,0 +1,297 @@
+//! Statistics of the constraint systems of the circuits: the numbers of
+//! columns and lookups, the degree of each gate and the minimum `k` with the
+//! capacities of some `CircuitsParams`, to guide optimization work.
+//!
+//! Each circuit is configured alone, with the tables it assigns or looks up,
+//! as in the super circuit.  The degrees are those of the expressions of the
+//! gates, before the compression of the selectors done by the key generation.
+
+use crate::bytecode_circuit::bytecode_unroller::Config as BytecodeConfig;
+use crate::copy_circuit::CopyCircuit;
+use crate::evm_circuit::EvmCircuit;
+use crate::exp_circuit::ExpCircuit;
+use crate::keccak_circuit::KeccakCircuit;
+use crate::mpt_circuit::MptCircuit;
+use crate::pi_circuit::PiCircuitConfig;
+use crate::rw_table::RwTable;
+use crate::state_circuit::StateCircuit;
+use crate::super_circuit::{min_k, power_of_randomness_from_instance, SuperCircuit};
+use crate::table::{BlockTable, CopyTable, ExpTable, KeccakTable, MptTable, TxTable};
+use crate::tx_circuit::TxCircuitConfig;
+use bus_mapping::circuit_input_builder::CircuitsParams;
+use eth_types::evm_types::{Hardfork, LondonSpec};
+use halo2_proofs::{
+    pairing::bn256::Fr,
+    plonk::{Advice, Circuit, Column, ConstraintSystem},
+};
+use std::fmt;
+
+/// Maximum number of withdrawals of the super circuit of the statistics.
+const MAX_WITHDRAWALS: usize = 1;
+
+/// Degree of a gate of a circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GateStats {
+    /// Name of the gate
+    pub name: &'static str,
+    /// Number of constraints of the gate
+    pub constraints: usize,
+    /// Maximum degree of the constraints of the gate
+    pub degree: usize,
+}
+
+/// Statistics of the constraint system of a circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitStats {
+    /// Name of the circuit
+    pub name: &'static str,
+    /// Number of advice columns
+    pub advice_columns: usize,
+    /// Number of fixed columns
+    pub fixed_columns: usize,
+    /// Number of instance columns
+    pub instance_columns: usize,
+    /// Number of selectors
+    pub selectors: usize,
+    /// Number of lookups
+    pub lookups: usize,
+    /// Degree of the constraint system
+    pub degree: usize,
+    /// Gates of the constraint system
+    pub gates: Vec<GateStats>,
+    /// Minimum `k` such that `2^k` rows fit the circuit
+    pub min_k: u32,
+}
+
+impl CircuitStats {
+    fn new(name: &'static str, meta: &ConstraintSystem<Fr>, min_k: u32) -> Self {
+        let gates = meta
+            .gates()
+            .iter()
+            .map(|gate| GateStats {
+                name: gate.name(),
+                constraints: gate.polynomials().len(),
+                degree: gate
+                    .polynomials()
+                    .iter()
+                    .map(|polynomial| polynomial.degree())
+                    .max()
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        Self {
+            name,
+            advice_columns: meta.num_advice_columns(),
+            fixed_columns: meta.num_fixed_columns(),
+            instance_columns: meta.num_instance_columns(),
+            selectors: meta.num_selectors(),
+            lookups: meta.lookups().len(),
+            degree: meta.degree(),
+            gates,
+            min_k,
+        }
+    }
+
+    /// Returns the gate of maximum degree.
+    pub fn max_degree_gate(&self) -> Option<&GateStats> {
+        self.gates.iter().max_by_key(|gate| gate.degree)
+    }
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} circuit", self.name)?;
+        writeln!(
+            f,
+            "  columns: {} advice, {} fixed, {} instance, {} selectors",
+            self.advice_columns, self.fixed_columns, self.instance_columns, self.selectors
+        )?;
+        writeln!(f, "  lookups: {}", self.lookups)?;
+        writeln!(f, "  degree: {}", self.degree)?;
+        writeln!(f, "  min k: {}", self.min_k)?;
+        writeln!(f, "  gates:")?;
+        let mut gates = self.gates.iter().collect::<Vec<_>>();
+        gates.sort_by(|a, b| b.degree.cmp(&a.degree).then(a.name.cmp(b.name)));
+        for gate in gates {
+            writeln!(
+                f,
+                "    {:>2} {} ({} constraints)",
+                gate.degree, gate.name, gate.constraints
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Configure the tables of the super circuit in `meta`.
+fn tables(
+    meta: &mut ConstraintSystem<Fr>,
+) -> (
+    TxTable,
+    RwTable,
+    [Column<Advice>; 5],
+    BlockTable,
+    CopyTable,
+    KeccakTable,
+) {
+    (
+        TxTable::construct(meta),
+        RwTable::construct(meta),
+        [(); 5].map(|_| meta.advice_column()),
+        BlockTable::construct(meta),
+        CopyTable::construct(meta),
+        KeccakTable::construct(meta),
+    )
+}
+
+/// Returns the statistics of each circuit, followed by the ones of the super
+/// circuit, with the capacities of `params`.
+pub fn circuit_stats(params: &CircuitsParams) -> Vec<CircuitStats> {
+    let num_rows =
+        SuperCircuit::<Fr, LondonSpec, MAX_WITHDRAWALS>::min_num_rows_per_circuit(params);
+    let num_rows = |name| {
+        num_rows
+            .iter()
+            .find(|(circuit, _)| *circuit == name)
+            .map(|(_, num_rows)| *num_rows)
+            .unwrap()
+    };
+    let configured = |name: &'static str, configure: &dyn Fn(&mut ConstraintSystem<Fr>)| {
+        let mut meta = ConstraintSystem::default();
+        configure(&mut meta);
+        CircuitStats::new(name, &meta, min_k(num_rows(name)))
+    };
+
+    let mut stats = vec![
+        configured("evm", &|meta| {
+            let (_, power_of_randomness) = power_of_randomness_from_instance(meta);
+            let (tx_table, rw_table, bytecode_table, block_table, copy_table, keccak_table) =
+                tables(meta);
+            EvmCircuit::configure(
+                meta,
+                Hardfork::default(),
+                array_init::array_init(|idx| power_of_randomness[idx].clone()),
+                &tx_table,
+                &rw_table,
+                &bytecode_table,
+                &block_table,
+                &copy_table,
+                &keccak_table,
+            );
+        }),
+        configured("state", &|meta| {
+            StateCircuit::<Fr>::configure(meta);
+        }),
+        configured("mpt", &|meta| {
+            let (_, power_of_randomness) = power_of_randomness_from_instance(meta);
+            let (_, _, _, _, _, keccak_table) = tables(meta);
+            MptCircuit::<Fr>::configure(
+                meta,
+                MptTable::construct(meta),
+                keccak_table,
+                array_init::array_init(|idx| power_of_randomness[idx].clone()),
+            );
+        }),
+        configured("tx", &|meta| {
+            let (_, power_of_randomness) = power_of_randomness_from_instance(meta);
+            let (tx_table, _, _, block_table, _, keccak_table) = tables(meta);
+            TxCircuitConfig::new(
+                meta,
+                power_of_randomness,
+                TxTable::construct(meta),
+                block_table,
+                keccak_table,
+                tx_table,
+            );
+        }),
+        configured("bytecode", &|meta| {
+            let (_, power_of_randomness) = power_of_randomness_from_instance(meta);
+            let (_, _, bytecode_table, _, _, keccak_table) = tables(meta);
+            BytecodeConfig::configure(
+                meta,
+                array_init::array_init(|idx| power_of_randomness[idx].clone()),
+                bytecode_table,
+                keccak_table,
+            );
+        }),
+        configured("copy", &|meta| {
+            let (_, power_of_randomness) = power_of_randomness_from_instance(meta);
+            let (tx_table, rw_table, bytecode_table, _, copy_table, _) = tables(meta);
+            CopyCircuit::configure(
+                meta,
+                &tx_table,
+                &rw_table,
+                &bytecode_table,
+                copy_table,
+                power_of_randomness[0].clone(),
+            );
+        }),
+        configured("exp", &|meta| {
+            ExpCircuit::configure(meta, ExpTable::construct(meta));
+        }),
+        configured("keccak", &|meta| {
+            let (_, power_of_randomness) = power_of_randomness_from_instance(meta);
+            let (_, _, _, _, _, keccak_table) = tables(meta);
+            KeccakCircuit::<Fr>::configure(
+                meta,
+                keccak_table,
+                array_init::array_init(|idx| power_of_randomness[idx].clone()),
+            );
+        }),
+        configured("pi", &|meta| {
+            let (_, power_of_randomness) = power_of_randomness_from_instance(meta);
+            let (tx_table, _, _, block_table, _, keccak_table) = tables(meta);
+            PiCircuitConfig::new(
+                meta,
+                block_table,
+                tx_table,
+                [(); 4].map(|_| meta.advice_column()),
+                keccak_table,
+                power_of_randomness[0].clone(),
+            );
+        }),
+    ];
+
+    let mut meta = ConstraintSystem::default();
+    SuperCircuit::<Fr, LondonSpec, MAX_WITHDRAWALS>::configure(&mut meta);
+    stats.push(CircuitStats::new(
+        "super",
+        &meta,
+        SuperCircuit::<Fr, LondonSpec, MAX_WITHDRAWALS>::min_k(params),
+    ));
+
+    stats
+}
+
+#[cfg(test)]
+mod test {
+    use super::circuit_stats;
+    use bus_mapping::circuit_input_builder::CircuitsParams;
+
+    #[test]
+    fn super_circuit_stats() {
+        let stats = circuit_stats(&CircuitsParams::default());
+        let (circuits, super_circuit) = stats.split_at(stats.len() - 1);
+        let super_circuit = &super_circuit[0];
+
+        assert_eq!(super_circuit.name, "super");
+        // The super circuit configures the lookups of all the circuits, and
+        // fits all of them.
+        assert_eq!(
+            super_circuit.lookups,
+            circuits
+                .iter()
+                .map(|circuit| circuit.lookups)
+                .sum::<usize>()
+        );
+        for circuit in circuits {
+            assert!(circuit.degree <= super_circuit.degree, "{}", circuit.name);
+            assert!(circuit.min_k <= super_circuit.min_k, "{}", circuit.name);
+            assert_eq!(
+                circuit.max_degree_gate().map(|gate| gate.degree),
+                circuit.gates.iter().map(|gate| gate.degree).max()
+            );
+        }
+    }
+}
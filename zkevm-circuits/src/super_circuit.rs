@@ -0,0 +1,877 @@
+//! The super circuit implementation.
+//!
+//! The super circuit configures the EVM, state, MPT, tx, bytecode, copy, exp,
+//! keccak and PI circuits in one constraint system, where each table is
+//! assigned by the circuit constraining its content and looked up by the
+//! others:
+//!
+//! - the rw table is assigned by the state circuit, and looked up by the EVM
+//!   circuit and the copy circuit,
+//! - the mpt table is assigned by the MPT circuit, and looked up by the state
+//!   circuit,
+//! - the bytecode table is assigned by the bytecode circuit, and looked up by
+//!   the EVM circuit and the copy circuit,
+//! - the block table and the tx table are assigned by the PI circuit, and
+//!   looked up by the EVM circuit, the copy circuit and the tx circuit, whose
+//!   own tx table holds the fields of the signed transactions,
+//! - the copy table and the exp table are assigned by the copy circuit and the
+//!   exp circuit,
+//! - the keccak table is assigned by the keccak circuit, and looked up by the
+//!   EVM circuit, the MPT circuit, the tx circuit, the bytecode circuit and the
+//!   PI circuit.
+//!
+//! All the circuits share the same powers of randomness, given in the first
+//! instance columns, followed by the instance column of the ECDSA chip of the
+//! tx circuit and the digest of the public data exposed by the PI circuit.
+
+use crate::bytecode_circuit::bytecode_unroller::{
+    unroll, Config as BytecodeConfig, UnrolledBytecode,
+};
+use crate::copy_circuit::CopyCircuit;
+use crate::evm_circuit::{
+    table::FixedTableTag,
+    witness::{block_convert, Block},
+    EvmCircuit,
+};
+use crate::exp_circuit::ExpCircuit;
+use crate::keccak_circuit::KeccakCircuit;
+use crate::mpt_circuit::{hash_rlc, mpt_updates, MptCircuit, MptPath};
+use crate::pi_circuit::{PiCircuit, PiCircuitConfig, PublicData};
+use crate::rw_table::RwTable;
+use crate::state_circuit::{StateCircuit, StateConfig};
+use crate::table::{BlockTable, CopyTable, ExpTable, KeccakTable, MptTable, TxTable};
+use crate::tx_circuit::{TxCircuit, TxCircuitConfig, POW_RAND_SIZE};
+use crate::util::Expr;
+use bus_mapping::{
+    circuit_input_builder::{self, CircuitsParams},
+    state_db::CodeDB,
+};
+use eth_types::{evm_types::HardforkSpec, geth_types, Field};
+use group::{Curve, Group};
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    circuit::{Layouter, SimpleFloorPlanner},
+    pairing::bn256::Fr,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Expression, Instance},
+    poly::Rotation,
+};
+use itertools::Itertools;
+use log::error;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use rayon::prelude::*;
+use secp256k1::Secp256k1Affine;
+use std::{iter::once, marker::PhantomData};
+use strum::IntoEnumIterator;
+use tracing::info_span;
+
+/// Number of powers of randomness used by the EVM circuit, the state circuit
+/// and the bytecode circuit.
+const N_POWER_OF_RANDOMNESS: usize = 31;
+
+/// Return the minimum `k` such that `2^k` rows fit `num_rows` rows, with the
+/// last 64 rows left for the blinding factors.
+pub fn min_k(num_rows: usize) -> u32 {
+    let n = num_rows + 64;
+    u32::BITS - (n as u32).leading_zeros() - (n & (n - 1) == 0) as u32
+}
+
+/// Return the instance columns of the powers of randomness, and the
+/// expressions querying them.
+pub(crate) fn power_of_randomness_from_instance<F: Field>(
+    meta: &mut ConstraintSystem<F>,
+) -> (
+    [Column<Instance>; POW_RAND_SIZE],
+    [Expression<F>; POW_RAND_SIZE],
+) {
+    // This gate is used just to get the array of expressions from the power of
+    // randomness instance column, so that later on we don't need to query
+    // columns everywhere, and can pass the power of randomness array
+    // expression everywhere.  The gate itself doesn't add any constraints.
+    let power_of_randomness_columns = [(); POW_RAND_SIZE].map(|_| meta.instance_column());
+    let mut power_of_randomness = None;
+
+    meta.create_gate("power of randomness", |meta| {
+        power_of_randomness = Some(
+            power_of_randomness_columns.map(|column| meta.query_instance(column, Rotation::cur())),
+        );
+
+        [0.expr()]
+    });
+
+    (power_of_randomness_columns, power_of_randomness.unwrap())
+}
+
+/// Config of the super circuit
+#[derive(Clone)]
+pub struct SuperCircuitConfig<F: Field> {
+    evm_circuit: EvmCircuit<F>,
+    state_circuit: StateConfig,
+    mpt_circuit: MptCircuit<F>,
+    tx_circuit: TxCircuitConfig<F>,
+    bytecode_circuit: BytecodeConfig<F>,
+    copy_circuit: CopyCircuit<F>,
+    exp_circuit: ExpCircuit<F>,
+    keccak_circuit: KeccakCircuit<F>,
+    pi_circuit: PiCircuitConfig<F>,
+}
+
+/// Super circuit proving a block with all the sub-circuits, sharing their
+/// tables and their randomness, with the capacities given by the
+/// `CircuitsParams` of the block.  The EVM circuit is configured for the
+/// blocks of the hardfork of `H`.
+#[derive(Default)]
+pub struct SuperCircuit<F: Field, H: HardforkSpec, const MAX_WITHDRAWALS: usize> {
+    /// Witness block of the EVM circuit, the copy circuit and the exp circuit
+    pub block: Block<F>,
+    /// State circuit
+    pub state_circuit: StateCircuit<F>,
+    /// Tx circuit
+    pub tx_circuit: TxCircuit<F>,
+    /// Bytecodes of the block unrolled for the bytecode circuit
+    pub(crate) bytecodes: Vec<UnrolledBytecode<F>>,
+    /// PI circuit
+    pub pi_circuit: PiCircuit<F, 1, MAX_WITHDRAWALS>,
+    _hardfork: PhantomData<H>,
+}
+
+impl<F: Field, H: HardforkSpec, const MAX_WITHDRAWALS: usize> SuperCircuit<F, H, MAX_WITHDRAWALS> {
+    /// Return the minimum number of rows of each sub-circuit, by name, with
+    /// the capacities of `params`.
+    pub fn min_num_rows_per_circuit(params: &CircuitsParams) -> [(&'static str, usize); 9] {
+        [
+            (
+                "evm",
+                EvmCircuit::<F>::min_num_rows(params, &FixedTableTag::iter().collect_vec()),
+            ),
+            ("state", StateCircuit::<F>::min_num_rows(params)),
+            ("mpt", MptCircuit::<F>::min_num_rows(params)),
+            ("tx", TxCircuit::<F>::min_num_rows(params)),
+            ("bytecode", params.max_bytecode),
+            // The copy circuit pads the copy table with two rows.
+            ("copy", params.max_copy_rows + 2),
+            // The exp circuit range checks its bytes with a u8 table, and pads
+            // the exp table with a row.
+            ("exp", (params.max_exp_rows + 1).max(256)),
+            ("keccak", params.max_keccak_rows),
+            (
+                "pi",
+                PiCircuit::<F, 1, MAX_WITHDRAWALS>::min_num_rows(params),
+            ),
+        ]
+    }
+
+    /// Return the minimum `k` such that `2^k` rows fit all the sub-circuits
+    /// with the capacities of `params`.
+    pub fn min_k(params: &CircuitsParams) -> u32 {
+        let num_rows = Self::min_num_rows_per_circuit(params)
+            .into_iter()
+            .map(|(_, num_rows)| num_rows)
+            .max()
+            .unwrap();
+        min_k(num_rows)
+    }
+
+    /// Return the capacities of the block reduced to the witnesses of the
+    /// circuit, with which the sub-circuits assign no more padding rows than
+    /// the blinding factors require, or an error if the block exceeds them.
+    fn fitted_params(&self) -> Result<CircuitsParams, Error> {
+        let mut meta = ConstraintSystem::default();
+        let config = Self::configure(&mut meta);
+        let block = &self.block;
+        let params = &block.circuits_params;
+
+        let num_txs = block.txs.len();
+        let num_calldata = block.txs.iter().map(|tx| tx.call_data.len()).sum();
+        // The rw table starts with at least one Start row.
+        let num_rws = 1 + block.rws.0.values().map(|rws| rws.len()).sum::<usize>();
+        // The bytecode circuit leaves the last rows of its region unused.
+        let num_bytecode_rows = block
+            .bytecodes
+            .values()
+            .map(|bytecode| bytecode.bytes.len() + 1)
+            .sum::<usize>()
+            + meta.minimum_rows();
+        let num_copy_rows = block
+            .copy_events
+            .values()
+            .map(|copy_event| copy_event.steps.len())
+            .sum();
+        let num_exp_rows = ExpCircuit::<F>::num_rows(&block.exp_events);
+        let mpt_paths = self.mpt_paths()?;
+        let num_mpt_rows = MptCircuit::<F>::num_rows(&mpt_paths);
+        let num_keccak_rows = KeccakCircuit::<F>::num_rows(&self.keccak_inputs(&mpt_paths)?);
+        let num_evm_rows = config.evm_circuit.get_num_rows_required(block);
+
+        let fitted_params = CircuitsParams {
+            max_txs: num_txs,
+            max_calldata: num_calldata,
+            max_rws: num_rws,
+            max_bytecode: num_bytecode_rows,
+            max_copy_rows: num_copy_rows,
+            max_exp_rows: num_exp_rows,
+            max_mpt_rows: num_mpt_rows,
+            max_keccak_rows: num_keccak_rows,
+            max_evm_rows: num_evm_rows,
+        };
+        for (name, required, capacity) in [
+            ("txs", fitted_params.max_txs, params.max_txs),
+            ("calldata", fitted_params.max_calldata, params.max_calldata),
+            ("rws", fitted_params.max_rws, params.max_rws),
+            ("bytecode", fitted_params.max_bytecode, params.max_bytecode),
+            (
+                "copy rows",
+                fitted_params.max_copy_rows,
+                params.max_copy_rows,
+            ),
+            ("exp rows", fitted_params.max_exp_rows, params.max_exp_rows),
+            ("mpt rows", fitted_params.max_mpt_rows, params.max_mpt_rows),
+            (
+                "keccak rows",
+                fitted_params.max_keccak_rows,
+                params.max_keccak_rows,
+            ),
+            ("evm rows", fitted_params.max_evm_rows, params.max_evm_rows),
+        ] {
+            if required > capacity {
+                error!("{} {} exceed the capacity of {}", name, required, capacity);
+                return Err(Error::Synthesis);
+            }
+        }
+        Ok(fitted_params)
+    }
+
+    /// Return the instance columns required by the circuit with `2^k` rows:
+    /// the powers of randomness, followed by the MainGate instance column of
+    /// the tx circuit and the high and low 128 bits of the digest of the
+    /// public data.
+    pub fn instance(&self, k: u32) -> Vec<Vec<F>> {
+        Self::public_instance(
+            k,
+            self.block.randomness,
+            &self.pi_circuit.public_data,
+            &self.block.circuits_params,
+        )
+    }
+
+    /// Return the instance columns of the circuit with `2^k` rows proving the
+    /// block of `public_data` with `randomness` and the capacities `params`,
+    /// from which a verifier rebuilds the public inputs of a proof.
+    pub fn public_instance(
+        k: u32,
+        randomness: F,
+        public_data: &PublicData,
+        params: &CircuitsParams,
+    ) -> Vec<Vec<F>> {
+        let mut instance: Vec<Vec<F>> = (1..POW_RAND_SIZE + 1)
+            .map(|exp| vec![randomness.pow(&[exp as u64, 0, 0, 0]); (1 << k) - 64])
+            .collect();
+        // Tx circuit: SignVerifyChip -> ECDSAChip -> MainGate instance column
+        instance.push(vec![]);
+        instance.push(
+            public_data
+                .digest(
+                    1,
+                    params.max_txs,
+                    params.max_calldata,
+                    MAX_WITHDRAWALS,
+                    randomness,
+                )
+                .to_vec(),
+        );
+        instance
+    }
+
+    /// Return the paths of the MPT circuit proving the updates of the state
+    /// trie by the block, from the proofs of the block at its previous state
+    /// root.
+    fn mpt_paths(&self) -> Result<Vec<MptPath<F>>, Error> {
+        let map_err = |err| {
+            error!("mpt witness error {:?}", err);
+            Error::Synthesis
+        };
+        let mut paths = vec![];
+        for update in mpt_updates(&self.block).map_err(map_err)? {
+            paths.extend(update.paths(self.block.randomness).map_err(map_err)?);
+        }
+        Ok(paths)
+    }
+
+    /// Return the inputs of the keccak hashes looked up by the sub-circuits.
+    fn keccak_inputs(&self, mpt_paths: &[MptPath<F>]) -> Result<Vec<Vec<u8>>, Error> {
+        let mut keccak_inputs = self.tx_circuit.keccak_inputs()?;
+        keccak_inputs.extend(self.bytecodes.iter().map(|bytecode| bytecode.bytes.clone()));
+        // The EVM circuit looks up the addresses created by the contract
+        // creation transactions.
+        keccak_inputs.extend(
+            self.block
+                .txs
+                .iter()
+                .filter(|tx| tx.is_create)
+                .map(|tx| geth_types::create_address_rlp(&tx.caller_address, tx.nonce)),
+        );
+        keccak_inputs.extend(
+            mpt_paths
+                .iter()
+                .flat_map(|path| path.keccak_inputs.iter().cloned()),
+        );
+        keccak_inputs.push(self.pi_circuit.public_data.rpi_bytes(
+            1,
+            self.pi_circuit.max_txs,
+            self.pi_circuit.max_calldata,
+            MAX_WITHDRAWALS,
+            self.block.randomness,
+        ));
+        Ok(keccak_inputs)
+    }
+
+    /// Check that the witnesses of the block fit in the capacities of its
+    /// `CircuitsParams`.
+    fn check_capacities(
+        &self,
+        config: &SuperCircuitConfig<F>,
+        mpt_paths: &[MptPath<F>],
+        keccak_inputs: &[Vec<u8>],
+    ) -> Result<(), Error> {
+        let params = &self.block.circuits_params;
+        let num_copy_rows: usize = self
+            .block
+            .copy_events
+            .values()
+            .map(|copy_event| copy_event.steps.len())
+            .sum();
+        if num_copy_rows > params.max_copy_rows {
+            error!(
+                "copy rows {} exceed the capacity of {}",
+                num_copy_rows, params.max_copy_rows
+            );
+            return Err(Error::Synthesis);
+        }
+        let num_exp_rows = ExpCircuit::<F>::num_rows(&self.block.exp_events);
+        if num_exp_rows > params.max_exp_rows {
+            error!(
+                "exp rows {} exceed the capacity of {}",
+                num_exp_rows, params.max_exp_rows
+            );
+            return Err(Error::Synthesis);
+        }
+        let num_mpt_rows = MptCircuit::<F>::num_rows(mpt_paths);
+        if num_mpt_rows > params.max_mpt_rows {
+            error!(
+                "mpt rows {} exceed the capacity of {}",
+                num_mpt_rows, params.max_mpt_rows
+            );
+            return Err(Error::Synthesis);
+        }
+        let num_keccak_rows = KeccakCircuit::<F>::num_rows(keccak_inputs);
+        if num_keccak_rows > params.max_keccak_rows {
+            error!(
+                "keccak rows {} exceed the capacity of {}",
+                num_keccak_rows, params.max_keccak_rows
+            );
+            return Err(Error::Synthesis);
+        }
+        let num_evm_rows = config.evm_circuit.get_num_rows_required(&self.block);
+        if num_evm_rows > params.max_evm_rows {
+            error!(
+                "evm rows {} exceed the capacity of {}",
+                num_evm_rows, params.max_evm_rows
+            );
+            return Err(Error::Synthesis);
+        }
+        Ok(())
+    }
+}
+
+impl<H: HardforkSpec, const MAX_WITHDRAWALS: usize> SuperCircuit<Fr, H, MAX_WITHDRAWALS> {
+    /// Build the witnesses of all the sub-circuits from a block built by the
+    /// circuit input builder, with the capacities of its `CircuitsParams` and
+    /// the `randomness` of the random linear combinations.
+    pub fn build(
+        builder_block: &circuit_input_builder::Block,
+        code_db: &CodeDB,
+        randomness: Fr,
+    ) -> Self {
+        Self::build_with_params(
+            builder_block,
+            code_db,
+            randomness,
+            builder_block.circuits_params,
+        )
+    }
+
+    /// Build the witnesses of all the sub-circuits from a block built by the
+    /// circuit input builder, with the capacities `params` and the
+    /// `randomness` of the random linear combinations.
+    pub fn build_with_params(
+        builder_block: &circuit_input_builder::Block,
+        code_db: &CodeDB,
+        randomness: Fr,
+        params: CircuitsParams,
+    ) -> Self {
+        let mut block = block_convert(builder_block, code_db);
+        block.randomness = randomness;
+        block.circuits_params = params;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let aux_generator =
+            <Secp256k1Affine as CurveAffine>::CurveExt::random(&mut rng).to_affine();
+        let tx_circuit = TxCircuit::new(
+            aux_generator,
+            randomness,
+            block.context.chain_id.as_u64(),
+            builder_block
+                .txs
+                .iter()
+                .map(geth_types::Transaction::from)
+                .collect(),
+            params.max_txs,
+            params.max_calldata,
+        );
+        let state_circuit = StateCircuit::new_from_block(&block);
+        // The bytecodes are unrolled independently of each other.
+        let bytecodes = block
+            .bytecodes
+            .par_iter()
+            .map(|(_, bytecode)| unroll(bytecode.bytes.clone(), randomness))
+            .collect();
+        let pi_circuit = PiCircuit::new(
+            randomness,
+            PublicData {
+                blocks: vec![block.public_data()],
+            },
+            params.max_txs,
+            params.max_calldata,
+        );
+
+        Self {
+            block,
+            state_circuit,
+            tx_circuit,
+            bytecodes,
+            pi_circuit,
+            _hardfork: PhantomData,
+        }
+    }
+
+    /// Return the capacities of the `CircuitsParams` of a block built by the
+    /// circuit input builder reduced to its content, with which the
+    /// sub-circuits assign no more padding rows than the blinding factors
+    /// require, or an error if the block exceeds them.
+    pub fn params_for_block(
+        builder_block: &circuit_input_builder::Block,
+        code_db: &CodeDB,
+    ) -> Result<CircuitsParams, Error> {
+        let params = Self::build(builder_block, code_db, Fr::from(1)).fitted_params()?;
+        // The tx circuit and the PI circuit hash the transactions and the call
+        // data padded to their capacities, so the keccak rows are fitted again
+        // once these are fitted.
+        Self::build_with_params(
+            builder_block,
+            code_db,
+            Fr::from(1),
+            CircuitsParams {
+                max_keccak_rows: builder_block.circuits_params.max_keccak_rows,
+                ..params
+            },
+        )
+        .fitted_params()
+    }
+
+    /// Return the minimum `k` such that `2^k` rows fit all the sub-circuits
+    /// proving a block built by the circuit input builder, with the
+    /// capacities of its `CircuitsParams` reduced to its content by
+    /// [`Self::params_for_block`].
+    pub fn k_for_block(
+        builder_block: &circuit_input_builder::Block,
+        code_db: &CodeDB,
+    ) -> Result<u32, Error> {
+        Ok(Self::min_k(&Self::params_for_block(
+            builder_block,
+            code_db,
+        )?))
+    }
+
+    /// Build the witnesses of all the sub-circuits from a block built by the
+    /// circuit input builder, with the capacities of its `CircuitsParams`
+    /// reduced to its content by [`Self::params_for_block`].
+    pub fn build_fitted(
+        builder_block: &circuit_input_builder::Block,
+        code_db: &CodeDB,
+        randomness: Fr,
+    ) -> Result<Self, Error> {
+        let params = Self::params_for_block(builder_block, code_db)?;
+        Ok(Self::build_with_params(
+            builder_block,
+            code_db,
+            randomness,
+            params,
+        ))
+    }
+}
+
+impl<F: Field, H: HardforkSpec, const MAX_WITHDRAWALS: usize> Circuit<F>
+    for SuperCircuit<F, H, MAX_WITHDRAWALS>
+{
+    type Config = SuperCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        // The capacities fix the layout of the circuit, which must be the same
+        // in the verifying key.
+        Self {
+            block: Block {
+                hardfork: H::HARDFORK,
+                circuits_params: self.block.circuits_params,
+                ..Block::default()
+            },
+            state_circuit: self.state_circuit.without_witnesses(),
+            tx_circuit: self.tx_circuit.without_witnesses(),
+            bytecodes: vec![],
+            pi_circuit: self.pi_circuit.without_witnesses(),
+            _hardfork: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let (power_of_randomness_columns, power_of_randomness) =
+            power_of_randomness_from_instance(meta);
+        let power_of_randomness_word: [Expression<F>; N_POWER_OF_RANDOMNESS] =
+            array_init::array_init(|idx| power_of_randomness[idx].clone());
+
+        let tx_table = TxTable::construct(meta);
+        let rw_table = RwTable::construct(meta);
+        let bytecode_table = [(); 5].map(|_| meta.advice_column());
+        let block_table = BlockTable::construct(meta);
+        let copy_table = CopyTable::construct(meta);
+        let exp_table = ExpTable::construct(meta);
+        let keccak_table = KeccakTable::construct(meta);
+        let mpt_table = MptTable::construct(meta);
+        let tx_circuit_tx_table = TxTable::construct(meta);
+
+        let evm_circuit = EvmCircuit::configure(
+            meta,
+            H::HARDFORK,
+            power_of_randomness_word.clone(),
+            &tx_table,
+            &rw_table,
+            &bytecode_table,
+            &block_table,
+            &copy_table,
+            &keccak_table,
+        );
+        let state_circuit = StateConfig::configure(
+            meta,
+            array_init::array_init(|idx| power_of_randomness_columns[idx]),
+            rw_table,
+            mpt_table,
+        );
+        let mpt_circuit = MptCircuit::configure(
+            meta,
+            mpt_table,
+            keccak_table,
+            power_of_randomness_word.clone(),
+        );
+        // The tx circuit is configured before the PI circuit, so that the
+        // instance column of its ECDSA chip precedes the digest.
+        let tx_circuit = TxCircuitConfig::new(
+            meta,
+            power_of_randomness.clone(),
+            tx_circuit_tx_table,
+            block_table,
+            keccak_table,
+            tx_table,
+        );
+        let bytecode_circuit = BytecodeConfig::configure(
+            meta,
+            power_of_randomness_word.clone(),
+            bytecode_table,
+            keccak_table,
+        );
+        let copy_circuit = CopyCircuit::configure(
+            meta,
+            &tx_table,
+            &rw_table,
+            &bytecode_table,
+            copy_table,
+            power_of_randomness[0].clone(),
+        );
+        let exp_circuit = ExpCircuit::configure(meta, exp_table);
+        let keccak_circuit = KeccakCircuit::configure(meta, keccak_table, power_of_randomness_word);
+        let pi_circuit = PiCircuitConfig::new(
+            meta,
+            block_table,
+            tx_table,
+            [(); 4].map(|_| meta.advice_column()),
+            keccak_table,
+            power_of_randomness[0].clone(),
+        );
+
+        Self::Config {
+            evm_circuit,
+            state_circuit,
+            mpt_circuit,
+            tx_circuit,
+            bytecode_circuit,
+            copy_circuit,
+            exp_circuit,
+            keccak_circuit,
+            pi_circuit,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let randomness = self.block.randomness;
+        let mpt_paths = self.mpt_paths()?;
+        let keccak_inputs = self.keccak_inputs(&mpt_paths)?;
+        self.check_capacities(&config, &mpt_paths, &keccak_inputs)?;
+        info_span!("keccak circuit").in_scope(|| {
+            config.keccak_circuit.assign(
+                &mut layouter,
+                self.block.circuits_params.max_keccak_rows,
+                &keccak_inputs,
+                randomness,
+            )
+        })?;
+
+        info_span!("evm circuit").in_scope(|| {
+            config
+                .evm_circuit
+                .load_fixed_table(&mut layouter, FixedTableTag::iter().collect())?;
+            config.evm_circuit.load_byte_table(&mut layouter)?;
+            config.evm_circuit.assign_block(&mut layouter, &self.block)
+        })?;
+
+        // The state circuit chains the state roots of the updates proven by
+        // the MPT circuit, from the root before the block.
+        let state_roots: Vec<_> = once(hash_rlc(&self.block.prev_state_root, randomness))
+            .chain(
+                mpt_paths
+                    .iter()
+                    .filter(|path| path.is_first_of_update())
+                    .map(|path| path.new_state_root),
+            )
+            .collect();
+        info_span!("state circuit").in_scope(|| {
+            self.state_circuit
+                .assign(&config.state_circuit, &mut layouter, &state_roots)
+        })?;
+
+        info_span!("mpt circuit").in_scope(|| {
+            config.mpt_circuit.load_tables(&mut layouter)?;
+            config.mpt_circuit.assign(&mut layouter, &mpt_paths)
+        })?;
+
+        info_span!("tx circuit")
+            .in_scope(|| self.tx_circuit.assign(&config.tx_circuit, &mut layouter))?;
+
+        info_span!("bytecode circuit").in_scope(|| {
+            config.bytecode_circuit.load(&mut layouter)?;
+            config.bytecode_circuit.assign(
+                &mut layouter,
+                self.block.circuits_params.max_bytecode,
+                &self.bytecodes,
+                randomness,
+            )
+        })?;
+
+        info_span!("copy circuit")
+            .in_scope(|| config.copy_circuit.assign_block(&mut layouter, &self.block))?;
+
+        info_span!("exp circuit").in_scope(|| {
+            config.exp_circuit.load_u8_table(&mut layouter)?;
+            config.exp_circuit.assign_block(&mut layouter, &self.block)
+        })?;
+
+        info_span!("pi circuit").in_scope(|| {
+            config.pi_circuit.load_tables(&mut layouter)?;
+            config.pi_circuit.assign(
+                &mut layouter,
+                &self.pi_circuit.public_data,
+                1,
+                self.pi_circuit.max_txs,
+                self.pi_circuit.max_calldata,
+                MAX_WITHDRAWALS,
+                randomness,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod super_circuit_tests {
+    use super::*;
+    use crate::mpt_circuit::{dev_state_proofs, replay_updates, KEY_NIBBLES};
+    use bus_mapping::{circuit_input_builder::CircuitInputBuilder, mock::BlockData};
+    use eth_types::{bytecode, evm_types::LondonSpec, geth_types::GethData, Word};
+    use ethers_core::{
+        types::{NameOrAddress, TransactionRequest},
+        utils::keccak256,
+    };
+    use ethers_signers::{LocalWallet, Signer};
+    use halo2_proofs::dev::MockProver;
+    use mock::{eth, TestContext, MOCK_CHAIN_ID, MOCK_GASPRICE};
+    use rand_chacha::ChaCha20Rng;
+
+    const MAX_WITHDRAWALS: usize = 1;
+
+    const CIRCUITS_PARAMS: CircuitsParams = CircuitsParams {
+        max_txs: 1,
+        max_calldata: 32,
+        max_rws: 256,
+        max_bytecode: 256,
+        max_copy_rows: 256,
+        max_exp_rows: 256,
+        max_mpt_rows: 1 << 14,
+        max_keccak_rows: 1 << 14,
+        max_evm_rows: 1024,
+    };
+
+    fn signed_transfer_ctx() -> TestContext<2, 1> {
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        let chain_id = MOCK_CHAIN_ID.as_u64();
+        let wallet = LocalWallet::new(&mut rng).with_chain_id(chain_id);
+        let to = mock::MOCK_ACCOUNTS[0];
+        let value = Word::from(1000);
+        let gas = Word::from(1_000_000);
+        let tx = TransactionRequest::new()
+            .from(wallet.address())
+            .to(NameOrAddress::Address(to))
+            .nonce(0)
+            .value(value)
+            .gas(gas)
+            .gas_price(*MOCK_GASPRICE);
+        let sighash = keccak256(tx.rlp(chain_id).as_ref()).into();
+        let sig = wallet.sign_hash(sighash, true);
+
+        TestContext::new(
+            None,
+            |accs| {
+                accs[0].address(wallet.address()).balance(eth(10));
+                accs[1].address(to).balance(eth(10)).code(bytecode! {
+                    PUSH1(0x01)
+                    STOP
+                });
+            },
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[0].address)
+                    .to(accs[1].address)
+                    .value(value)
+                    .gas(gas)
+                    .sig_data((sig.v, sig.r, sig.s));
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+    }
+
+    /// Returns the inputs of the signed transfer built with `params`, on the
+    /// state trie of the mock accounts proven by the MPT circuit.
+    fn signed_transfer_builder(params: CircuitsParams) -> CircuitInputBuilder {
+        let block: GethData = signed_transfer_ctx().into();
+        let mut builder = BlockData::new_from_geth_data_with_params(block.clone(), params)
+            .new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        let (prev_state_root, state_proofs) = dev_state_proofs(&block.accounts);
+        builder.block.prev_state_root = prev_state_root;
+        builder.block.state_proofs = state_proofs;
+        let (_, state_root) =
+            replay_updates(&block_convert(&builder.block, &builder.code_db)).unwrap();
+        builder.block.state_root = state_root;
+        builder
+    }
+
+    #[test]
+    fn k_for_block_fits_content() {
+        let params = CircuitsParams {
+            max_txs: 4,
+            max_calldata: 1024,
+            max_rws: 1 << 17,
+            max_bytecode: 1024,
+            max_copy_rows: 1024,
+            max_exp_rows: 1024,
+            max_mpt_rows: 1 << 15,
+            max_keccak_rows: 1 << 14,
+            max_evm_rows: 1 << 17,
+        };
+        let mut builder = signed_transfer_builder(params);
+
+        let fitted_params = SuperCircuit::<Fr, LondonSpec, MAX_WITHDRAWALS>::params_for_block(
+            &builder.block,
+            &builder.code_db,
+        )
+        .unwrap();
+        assert_eq!(fitted_params.max_txs, 1);
+        assert!(fitted_params.max_rws <= params.max_rws);
+        // The transfer updates the nonce and the balance of the sender and
+        // the balance of the receiver.
+        assert!(fitted_params.max_mpt_rows >= 3 * KEY_NIBBLES);
+        assert!(fitted_params.max_keccak_rows < params.max_keccak_rows);
+        assert!(
+            SuperCircuit::<Fr, LondonSpec, MAX_WITHDRAWALS>::k_for_block(
+                &builder.block,
+                &builder.code_db
+            )
+            .unwrap()
+                < SuperCircuit::<Fr, LondonSpec, MAX_WITHDRAWALS>::min_k(&params)
+        );
+
+        builder.block.circuits_params.max_txs = 0;
+        assert!(
+            SuperCircuit::<Fr, LondonSpec, MAX_WITHDRAWALS>::k_for_block(
+                &builder.block,
+                &builder.code_db
+            )
+            .is_err()
+        );
+    }
+
+    // High memory usage test.  Run in serial with:
+    // `cargo test [...] serial_ -- --ignored --test-threads 1`
+    #[ignore]
+    #[test]
+    fn serial_test_super_circuit() {
+        let builder = signed_transfer_builder(CIRCUITS_PARAMS);
+        let circuit = SuperCircuit::<Fr, LondonSpec, MAX_WITHDRAWALS>::build(
+            &builder.block,
+            &builder.code_db,
+            Fr::from(0x100),
+        );
+
+        let k = SuperCircuit::<Fr, LondonSpec, MAX_WITHDRAWALS>::min_k(&CIRCUITS_PARAMS);
+        let instance = circuit.instance(k);
+        let prover = MockProver::run(k, &circuit, instance).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    // High memory usage test.  Run in serial with:
+    // `cargo test [...] serial_ -- --ignored --test-threads 1`
+    #[ignore]
+    #[test]
+    fn serial_test_super_circuit_fitted_params() {
+        let builder = signed_transfer_builder(CIRCUITS_PARAMS);
+        let circuit = SuperCircuit::<Fr, LondonSpec, MAX_WITHDRAWALS>::build_fitted(
+            &builder.block,
+            &builder.code_db,
+            Fr::from(0x100),
+        )
+        .unwrap();
+
+        let k =
+            SuperCircuit::<Fr, LondonSpec, MAX_WITHDRAWALS>::min_k(&circuit.block.circuits_params);
+        assert!(k <= SuperCircuit::<Fr, LondonSpec, MAX_WITHDRAWALS>::min_k(&CIRCUITS_PARAMS));
+        let instance = circuit.instance(k);
+        let prover = MockProver::run(k, &circuit, instance).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
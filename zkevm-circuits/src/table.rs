@@ -0,0 +1,608 @@
+//! Tables shared between the circuits.
+
+use crate::evm_circuit::{
+    table::LookupTable,
+    witness::{BlockContext, Transaction},
+};
+use bus_mapping::circuit_input_builder::CopyDataType;
+use eth_types::{Field, Rlc};
+use ethers_core::utils::keccak256;
+use gadgets::binary_number::{BinaryNumberChip, BinaryNumberConfig};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use itertools::Itertools;
+use rayon::prelude::*;
+
+/// The tx table shared between the tx circuit, which constrains its content,
+/// and the EVM circuit, which looks up the transaction fields from it.
+#[derive(Clone, Copy, Debug)]
+pub struct TxTable {
+    /// Transaction id, starting at 1, or 0 in the all-zero row.
+    pub tx_id: Column<Advice>,
+    /// Tag of the transaction field.
+    pub tag: Column<Advice>,
+    /// Index of the call data byte, or 0 for the other fields.
+    pub index: Column<Advice>,
+    /// Value of the transaction field.
+    pub value: Column<Advice>,
+}
+
+impl<F: Field> LookupTable<F> for TxTable {
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        vec![
+            meta.query_advice(self.tx_id, Rotation::cur()),
+            meta.query_advice(self.tag, Rotation::cur()),
+            meta.query_advice(self.index, Rotation::cur()),
+            meta.query_advice(self.value, Rotation::cur()),
+        ]
+    }
+}
+
+impl TxTable {
+    /// Construct a new TxTable.
+    pub fn construct<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            tx_id: meta.advice_column(),
+            tag: meta.advice_column(),
+            index: meta.advice_column(),
+            value: meta.advice_column(),
+        }
+    }
+
+    /// Assign a row of the tx table made of `[tx_id, tag, index, value]`.
+    pub fn assign_row<F: Field>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: [F; 4],
+    ) -> Result<(), Error> {
+        for (column, value) in [self.tx_id, self.tag, self.index, self.value]
+            .into_iter()
+            .zip_eq(row)
+        {
+            region.assign_advice(
+                || format!("tx table row {}", offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Load the tx table with the all-zero row followed by the rows of each
+    /// transaction.  The unused transaction slots up to `max_txs` are filled
+    /// with padding transactions whose fields are all zero.
+    pub fn load<F: Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        txs: &[Transaction],
+        max_txs: usize,
+        max_calldata: usize,
+        randomness: F,
+    ) -> Result<(), Error> {
+        assert!(txs.len() <= max_txs);
+        assert!(txs.iter().map(|tx| tx.call_data.len()).sum::<usize>() <= max_calldata);
+
+        let padding_txs: Vec<Transaction> = (txs.len()..max_txs)
+            .map(|i| Transaction {
+                id: i + 1,
+                ..Transaction::default()
+            })
+            .collect();
+
+        // The rows of each transaction are computed in parallel before being
+        // assigned in order.
+        let tx_rows: Vec<Vec<[F; 4]>> = txs
+            .par_iter()
+            .chain(padding_txs.par_iter())
+            .map(|tx| tx.table_assignments(randomness))
+            .collect();
+
+        layouter.assign_region(
+            || "tx table",
+            |mut region| {
+                let rows = std::iter::once([F::zero(); 4]).chain(tx_rows.iter().flatten().copied());
+                for (offset, row) in rows.enumerate() {
+                    self.assign_row(&mut region, offset, row)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+pub use eth_types::public_data::MAX_HISTORY_HASHES;
+
+/// The block table shared between the PI circuit, which constrains its content,
+/// and the EVM circuit and the tx circuit, which look up the block constants
+/// and the ancestor block hashes from it.
+#[derive(Clone, Copy, Debug)]
+pub struct BlockTable {
+    /// Tag of the block field.
+    pub tag: Column<Advice>,
+    /// Number of the ancestor block for the block hashes, or 0 for the other
+    /// fields.
+    pub index: Column<Advice>,
+    /// Value of the block field.
+    pub value: Column<Advice>,
+}
+
+impl<F: Field> LookupTable<F> for BlockTable {
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        vec![
+            meta.query_advice(self.tag, Rotation::cur()),
+            meta.query_advice(self.index, Rotation::cur()),
+            meta.query_advice(self.value, Rotation::cur()),
+        ]
+    }
+}
+
+impl BlockTable {
+    /// Construct a new BlockTable.
+    pub fn construct<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            tag: meta.advice_column(),
+            index: meta.advice_column(),
+            value: meta.advice_column(),
+        }
+    }
+
+    /// Returns the rows of the block table: the all-zero row followed by the
+    /// rows of the block constants and of the history hashes, padded with
+    /// all-zero rows up to `MAX_HISTORY_HASHES` hashes.
+    pub fn assignments<F: Field>(context: &BlockContext, randomness: F) -> Vec<[F; 3]> {
+        context.padded_table_assignments(randomness)
+    }
+
+    /// Load the block table with the rows of `context`, returning the assigned
+    /// cells row by row.
+    pub fn load<F: Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        context: &BlockContext,
+        randomness: F,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "block table",
+            |mut region| {
+                let mut cells = Vec::new();
+                for (offset, row) in Self::assignments(context, randomness).iter().enumerate() {
+                    for (column, value) in [self.tag, self.index, self.value].into_iter().zip(row) {
+                        cells.push(region.assign_advice(
+                            || format!("block table row {}", offset),
+                            column,
+                            offset,
+                            || Ok(*value),
+                        )?);
+                    }
+                }
+                Ok(cells)
+            },
+        )
+    }
+}
+
+/// The copy table shared between the copy circuit, which constrains the
+/// read-write steps of each copy event, and the EVM circuit, which looks up
+/// the copy events from their first row.
+#[derive(Clone, Copy, Debug)]
+pub struct CopyTable {
+    /// Whether the row is enabled or not.
+    pub q_enable: Column<Fixed>,
+    /// Whether the row is the first read-write pair for a copy event.
+    pub is_first: Column<Advice>,
+    /// The relevant ID for the read-write row, represented as a random linear
+    /// combination. The ID may be one of the below:
+    /// 1. Call ID/Caller ID for CopyDataType::Memory
+    /// 2. RLC encoding of bytecode hash for CopyDataType::Bytecode
+    /// 3. Transaction ID for CopyDataType::TxCalldata, CopyDataType::TxLog
+    pub id: Column<Advice>,
+    /// Binary chip to constrain the copy table conditionally depending on the
+    /// current row's tag, whether it is Bytecode, Memory, TxCalldata, TxLog
+    /// or RlcAcc.
+    pub tag: BinaryNumberConfig<CopyDataType, 3>,
+    /// The source/destination address for this copy step.
+    pub addr: Column<Advice>,
+    /// The end of the source buffer for the copy event.
+    pub src_addr_end: Column<Advice>,
+    /// The number of bytes left to be copied.
+    pub bytes_left: Column<Advice>,
+    /// The associated read-write counter for this row.
+    pub rw_counter: Column<Advice>,
+    /// Decrementing counter denoting reverse read-write counter.
+    pub rwc_inc_left: Column<Advice>,
+    /// The random linear combination of all the copied bytes when the
+    /// destination is CopyDataType::RlcAcc, or 0 otherwise.
+    pub rlc_acc: Column<Advice>,
+}
+
+impl<F: Field> LookupTable<F> for CopyTable {
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        vec![
+            meta.query_advice(self.is_first, Rotation::cur()),
+            meta.query_advice(self.id, Rotation::cur()), // src_id
+            self.tag.value(Rotation::cur())(meta),       // src_tag
+            meta.query_advice(self.id, Rotation::next()), // dst_id
+            self.tag.value(Rotation::next())(meta),      // dst_tag
+            meta.query_advice(self.addr, Rotation::cur()), // src_addr
+            meta.query_advice(self.src_addr_end, Rotation::cur()), // src_addr_end
+            meta.query_advice(self.addr, Rotation::next()), // dst_addr
+            meta.query_advice(self.bytes_left, Rotation::cur()), // length
+            meta.query_advice(self.rw_counter, Rotation::cur()), // rw_counter
+            meta.query_advice(self.rwc_inc_left, Rotation::cur()), // rwc_inc_left
+            meta.query_advice(self.rlc_acc, Rotation::cur()), // rlc_acc
+        ]
+    }
+}
+
+impl CopyTable {
+    /// Construct a new CopyTable.
+    pub fn construct<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        let q_enable = meta.fixed_column();
+        Self {
+            q_enable,
+            is_first: meta.advice_column(),
+            id: meta.advice_column(),
+            tag: BinaryNumberChip::configure(meta, q_enable),
+            addr: meta.advice_column(),
+            src_addr_end: meta.advice_column(),
+            bytes_left: meta.advice_column(),
+            rw_counter: meta.advice_column(),
+            rwc_inc_left: meta.advice_column(),
+            rlc_acc: meta.advice_column(),
+        }
+    }
+}
+
+/// The exp table shared between the exp circuit, which constrains the steps of
+/// each exponentiation event, and the EVM circuit, which looks up the
+/// exponentiation events from their first row.
+#[derive(Clone, Copy, Debug)]
+pub struct ExpTable {
+    /// Whether the row is enabled or not.
+    pub q_enable: Column<Fixed>,
+    /// Identifier of the exponentiation event, which is the RW counter of the
+    /// EXP step that triggered it.
+    pub identifier: Column<Advice>,
+    /// Whether the row is the first step of an exponentiation event.
+    pub is_first: Column<Advice>,
+    /// Whether the row is the last step of an exponentiation event.
+    pub is_last: Column<Advice>,
+    /// Low 128 bits of the base.
+    pub base_lo: Column<Advice>,
+    /// High 128 bits of the base.
+    pub base_hi: Column<Advice>,
+    /// Low 128 bits of the exponent at this step.
+    pub exponent_lo: Column<Advice>,
+    /// High 128 bits of the exponent at this step.
+    pub exponent_hi: Column<Advice>,
+    /// Low 128 bits of the exponentiation at this step.
+    pub exponentiation_lo: Column<Advice>,
+    /// High 128 bits of the exponentiation at this step.
+    pub exponentiation_hi: Column<Advice>,
+}
+
+impl<F: Field> LookupTable<F> for ExpTable {
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        vec![
+            meta.query_fixed(self.q_enable, Rotation::cur()),
+            meta.query_advice(self.identifier, Rotation::cur()),
+            meta.query_advice(self.is_first, Rotation::cur()),
+            meta.query_advice(self.base_lo, Rotation::cur()),
+            meta.query_advice(self.base_hi, Rotation::cur()),
+            meta.query_advice(self.exponent_lo, Rotation::cur()),
+            meta.query_advice(self.exponent_hi, Rotation::cur()),
+            meta.query_advice(self.exponentiation_lo, Rotation::cur()),
+            meta.query_advice(self.exponentiation_hi, Rotation::cur()),
+        ]
+    }
+}
+
+impl ExpTable {
+    /// Construct a new ExpTable.
+    pub fn construct<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            q_enable: meta.fixed_column(),
+            identifier: meta.advice_column(),
+            is_first: meta.advice_column(),
+            is_last: meta.advice_column(),
+            base_lo: meta.advice_column(),
+            base_hi: meta.advice_column(),
+            exponent_lo: meta.advice_column(),
+            exponent_hi: meta.advice_column(),
+            exponentiation_lo: meta.advice_column(),
+            exponentiation_hi: meta.advice_column(),
+        }
+    }
+}
+
+/// The keccak table shared between the keccak circuit, which proves the digest
+/// held at the output row of the last permutation of each input, and the
+/// circuits looking up the digests of their hashed bytes: the tx circuit, the
+/// bytecode circuit, the MPT circuit and the PI circuit.
+#[derive(Clone, Copy, Debug)]
+pub struct KeccakTable {
+    /// Whether the row is an output row of the keccak circuit, on which
+    /// `is_enabled` is constrained.
+    pub q_enable: Column<Fixed>,
+    /// Whether the row holds a digest, or 0 in the all-zero row.
+    pub is_enabled: Column<Advice>,
+    /// RLC of the hashed bytes, where the first byte has the highest power of
+    /// randomness.
+    pub input_rlc: Column<Advice>,
+    /// Number of hashed bytes.
+    pub input_len: Column<Advice>,
+    /// RLC of the digest as a little endian word.
+    pub output_rlc: Column<Advice>,
+}
+
+impl<F: FieldExt> LookupTable<F> for KeccakTable {
+    /// Returns `[q_enable * is_enabled, input_rlc, input_len, output_rlc]`, so
+    /// that a digest can only be found on an output row of the keccak circuit.
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        let [is_enabled, input_rlc, input_len, output_rlc] = self
+            .columns()
+            .map(|column| meta.query_advice(column, Rotation::cur()));
+        vec![
+            meta.query_fixed(self.q_enable, Rotation::cur()) * is_enabled,
+            input_rlc,
+            input_len,
+            output_rlc,
+        ]
+    }
+}
+
+impl KeccakTable {
+    /// Construct a new KeccakTable.
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            q_enable: meta.fixed_column(),
+            is_enabled: meta.advice_column(),
+            input_rlc: meta.advice_column(),
+            input_len: meta.advice_column(),
+            output_rlc: meta.advice_column(),
+        }
+    }
+
+    /// Returns the advice columns `[is_enabled, input_rlc, input_len,
+    /// output_rlc]`.
+    pub fn columns(&self) -> [Column<Advice>; 4] {
+        [
+            self.is_enabled,
+            self.input_rlc,
+            self.input_len,
+            self.output_rlc,
+        ]
+    }
+
+    /// Returns the row of the keccak table holding the digest of `input`.
+    pub fn assignments<F: FieldExt>(input: &[u8], randomness: F) -> [F; 4] {
+        let mut digest = keccak256(input);
+        digest.reverse();
+        [
+            F::one(),
+            input.rlc_stream(randomness),
+            F::from(input.len() as u64),
+            digest.rlc(randomness),
+        ]
+    }
+
+    /// Load the keccak table with the all-zero row followed by the digest of
+    /// each of the `inputs`, without the keccak circuit proving them.  Only
+    /// the test circuits of the sub-circuits looking up digests use it.
+    pub fn dev_load<F: FieldExt>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        inputs: &[Vec<u8>],
+        randomness: F,
+    ) -> Result<(), Error> {
+        let rows: Vec<[F; 4]> = inputs
+            .par_iter()
+            .map(|input| Self::assignments(input, randomness))
+            .collect();
+
+        layouter.assign_region(
+            || "keccak table",
+            |mut region| {
+                for (offset, row) in std::iter::once([F::zero(); 4])
+                    .chain(rows.iter().copied())
+                    .enumerate()
+                {
+                    region.assign_fixed(
+                        || format!("keccak table q_enable {}", offset),
+                        self.q_enable,
+                        offset,
+                        || Ok(F::one()),
+                    )?;
+                    for (column, value) in self.columns().into_iter().zip(row) {
+                        region.assign_advice(
+                            || format!("keccak table row {}", offset),
+                            column,
+                            offset,
+                            || Ok(value),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// The MPT table shared between the MPT circuit, which proves the trie updates
+/// held at the first row of their paths, and the state circuit, which looks up
+/// the update of the last access to each account field and storage slot.
+#[derive(Clone, Copy, Debug)]
+pub struct MptTable {
+    /// Whether the row holds an update.
+    pub q_enable: Column<Fixed>,
+    /// Address of the updated account.
+    pub address: Column<Advice>,
+    /// RLC of the storage key for a storage update, or 0 otherwise.
+    pub storage_key: Column<Advice>,
+    /// Type of the update, being the modified field of the account.
+    pub proof_type: Column<Advice>,
+    /// RLC of the state root before the update.
+    pub old_root: Column<Advice>,
+    /// RLC of the state root after the update.
+    pub new_root: Column<Advice>,
+    /// Value before the update, encoded as in the rw table.
+    pub old_value: Column<Advice>,
+    /// Value after the update, encoded as in the rw table.
+    pub new_value: Column<Advice>,
+}
+
+impl<F: Field> LookupTable<F> for MptTable {
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        vec![
+            meta.query_fixed(self.q_enable, Rotation::cur()),
+            meta.query_advice(self.address, Rotation::cur()),
+            meta.query_advice(self.storage_key, Rotation::cur()),
+            meta.query_advice(self.proof_type, Rotation::cur()),
+            meta.query_advice(self.old_root, Rotation::cur()),
+            meta.query_advice(self.new_root, Rotation::cur()),
+            meta.query_advice(self.old_value, Rotation::cur()),
+            meta.query_advice(self.new_value, Rotation::cur()),
+        ]
+    }
+}
+
+impl MptTable {
+    /// Construct a new MptTable.
+    pub fn construct<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            q_enable: meta.fixed_column(),
+            address: meta.advice_column(),
+            storage_key: meta.advice_column(),
+            proof_type: meta.advice_column(),
+            old_root: meta.advice_column(),
+            new_root: meta.advice_column(),
+            old_value: meta.advice_column(),
+            new_value: meta.advice_column(),
+        }
+    }
+
+    /// Assign a row of the mpt table made of `[address, storage_key,
+    /// proof_type, old_root, new_root, old_value, new_value]`.
+    pub fn assign_row<F: Field>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        q_enable: bool,
+        row: [F; 7],
+    ) -> Result<(), Error> {
+        region.assign_fixed(
+            || format!("mpt table q_enable {}", offset),
+            self.q_enable,
+            offset,
+            || Ok(F::from(q_enable)),
+        )?;
+        for (column, value) in [
+            self.address,
+            self.storage_key,
+            self.proof_type,
+            self.old_root,
+            self.new_root,
+            self.old_value,
+            self.new_value,
+        ]
+        .into_iter()
+        .zip_eq(row)
+        {
+            region.assign_advice(
+                || format!("mpt table row {}", offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Load the mpt table with the enabled `rows`, without the MPT circuit
+    /// proving their updates.  Only the test circuit of the state circuit
+    /// uses it.
+    pub fn dev_load<F: Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        rows: &[[F; 7]],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "mpt table",
+            |mut region| {
+                for (offset, row) in rows.iter().enumerate() {
+                    self.assign_row(&mut region, offset, true, *row)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// The Poseidon table shared between the Poseidon circuit, which constrains
+/// the hash of each pair of field elements, and the zkTrie circuit, which looks
+/// up the hashes of the nodes, the keys and the values of its tries.
+#[derive(Clone, Copy, Debug)]
+pub struct PoseidonTable {
+    /// Whether the row holds a hash.
+    pub q_enable: Column<Fixed>,
+    /// First input of the hash.
+    pub left: Column<Advice>,
+    /// Second input of the hash.
+    pub right: Column<Advice>,
+    /// Hash of the inputs.
+    pub hash: Column<Advice>,
+}
+
+impl<F: Field> LookupTable<F> for PoseidonTable {
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        vec![
+            meta.query_fixed(self.q_enable, Rotation::cur()),
+            meta.query_advice(self.left, Rotation::cur()),
+            meta.query_advice(self.right, Rotation::cur()),
+            meta.query_advice(self.hash, Rotation::cur()),
+        ]
+    }
+}
+
+impl PoseidonTable {
+    /// Construct a new PoseidonTable.
+    pub fn construct<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            q_enable: meta.fixed_column(),
+            left: meta.advice_column(),
+            right: meta.advice_column(),
+            hash: meta.advice_column(),
+        }
+    }
+
+    /// Assign a row of the poseidon table made of `[left, right, hash]`.
+    pub fn assign_row<F: Field>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        q_enable: bool,
+        row: [F; 3],
+    ) -> Result<(), Error> {
+        region.assign_fixed(
+            || format!("poseidon table q_enable {}", offset),
+            self.q_enable,
+            offset,
+            || Ok(F::from(q_enable)),
+        )?;
+        for (column, value) in [self.left, self.right, self.hash].into_iter().zip_eq(row) {
+            region.assign_advice(
+                || format!("poseidon table row {}", offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -64,6 +64,13 @@ pub fn run_test_circuits<const NACC: usize, const NTX: usize>(
     test_ctx: TestContext<NACC, NTX>,
     config: Option<BytecodeTestConfig>,
 ) -> Result<(), Vec<VerifyFailure>> {
+    // finish required tests according to config using the witness block
+    test_circuits_using_witness_block(witness_block(test_ctx), config.unwrap_or_default())
+}
+
+pub fn witness_block<const NACC: usize, const NTX: usize>(
+    test_ctx: TestContext<NACC, NTX>,
+) -> Block<Fr> {
     let block: GethData = test_ctx.into();
     let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
     builder
@@ -71,10 +78,7 @@ pub fn run_test_circuits<const NACC: usize, const NTX: usize>(
         .unwrap();
 
     // build a witness block from trace result
-    let block = crate::evm_circuit::witness::block_convert(&builder.block, &builder.code_db);
-
-    // finish required tests according to config using this witness block
-    test_circuits_using_witness_block(block, config.unwrap_or_default())
+    crate::evm_circuit::witness::block_convert(&builder.block, &builder.code_db)
 }
 
 pub fn test_circuits_using_witness_block(
@@ -91,7 +95,7 @@ pub fn test_circuits_using_witness_block(
     // state circuit and evm circuit must be same
     if config.enable_state_circuit_test {
         const N_ROWS: usize = 1 << 16;
-        let state_circuit = StateCircuit::<Fr, N_ROWS>::new(block.randomness, block.rws);
+        let state_circuit = StateCircuit::<Fr>::new(block.randomness, block.rws, N_ROWS);
         let power_of_randomness = state_circuit.instance();
         let prover = MockProver::<Fr>::run(18, &state_circuit, power_of_randomness).unwrap();
         prover.verify_at_rows(
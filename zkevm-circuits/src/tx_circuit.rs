@@ -6,15 +6,34 @@
 
 pub mod sign_verify;
 
+use crate::evm_circuit::{
+    table::{BlockContextFieldTag, LookupTable, TxContextFieldTag},
+    util::constraint_builder::BaseConstraintBuilder,
+    witness::{self, BlockContext},
+};
+use crate::impl_expr;
+use crate::rlp_circuit::{rlp_rows, tx_rlp_fields, RlpCircuitConfig, RlpDataType};
+use crate::table::{BlockTable, KeccakTable, TxTable};
 use crate::util::{random_linear_combine_word as rlc, Expr};
+use bus_mapping::circuit_input_builder::CircuitsParams;
+pub(crate) use eth_types::geth_types::{
+    TransactionType, ACCESS_LIST_TX_TYPE, DYNAMIC_FEE_TX_TYPE, LEGACY_TX_TYPE,
+};
 use eth_types::{
-    geth_types::Transaction, Address, Field, ToBigEndian, ToLittleEndian, ToScalar, Word,
+    geth_types::Transaction, Address, Field, Rlc, ToBigEndian, ToLittleEndian, ToScalar, Word,
 };
 use ff::PrimeField;
+use gadgets::{
+    is_equal::{IsEqualChip, IsEqualConfig, IsEqualInstruction},
+    is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction},
+    less_than::{LtChip, LtConfig, LtInstruction},
+    util::not,
+};
 use group::GroupEncoding;
 use halo2_proofs::{
+    arithmetic::FieldExt,
     circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner},
-    plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
     poly::Rotation,
 };
 use itertools::Itertools;
@@ -23,7 +42,6 @@ use libsecp256k1;
 use log::error;
 use num::Integer;
 use num_bigint::BigUint;
-use rlp::RlpStream;
 use secp256k1::Secp256k1Affine;
 use sha3::{Digest, Keccak256};
 use sign_verify::{pk_bytes_swap_endianness, SignData, SignVerifyChip, SignVerifyConfig};
@@ -85,6 +103,32 @@ fn ct_option_ok_or<T, E>(v: CtOption<T>, err: E) -> Result<T, E> {
     Option::<T>::from(v).ok_or(err)
 }
 
+/// Return the recovery id of the signature encoded in `v`, which is `27 +
+/// rec_id` before EIP-155 and `35 + 2 * chain_id + rec_id` after it for
+/// legacy transactions, and `rec_id` for typed transactions.  Returns an error
+/// if `v` doesn't encode a recovery id of 0 or 1 for `chain_id`, as for a
+/// legacy transaction signed for another chain.
+fn recovery_id(tx: &Transaction, chain_id: u64) -> Result<u8, Error> {
+    let rec_id = match tx.transaction_type {
+        TransactionType::Legacy if tx.is_pre_eip155() => tx.v.checked_sub(27),
+        TransactionType::Legacy => chain_id
+            .checked_mul(2)
+            .and_then(|double_chain_id| double_chain_id.checked_add(35))
+            .and_then(|offset| tx.v.checked_sub(offset)),
+        _ => Some(tx.v),
+    };
+    match rec_id {
+        Some(rec_id @ (0 | 1)) => Ok(rec_id as u8),
+        _ => {
+            error!(
+                "signature v {} of a {:?} tx doesn't match the chain id {}",
+                tx.v, tx.transaction_type, chain_id
+            );
+            Err(Error::Synthesis)
+        }
+    }
+}
+
 fn tx_to_sign_data(tx: &Transaction, chain_id: u64) -> Result<SignData, Error> {
     let sig_r_le = tx.r.to_le_bytes();
     let sig_s_le = tx.s.to_le_bytes();
@@ -98,25 +142,13 @@ fn tx_to_sign_data(tx: &Transaction, chain_id: u64) -> Result<SignData, Error> {
             error!("Invalid 's' signature value");
             e
         })?;
-    // msg = rlp([nonce, gasPrice, gas, to, value, data, sig_v, r, s])
-    let mut stream = RlpStream::new_list(9);
-    stream
-        .append(&tx.nonce)
-        .append(&tx.gas_price)
-        .append(&tx.gas_limit)
-        .append(&tx.to.unwrap_or_else(Address::zero))
-        .append(&tx.value)
-        .append(&tx.call_data.0)
-        .append(&chain_id)
-        .append(&0u32)
-        .append(&0u32);
-    let msg = stream.out();
+    let msg = tx.sign_rlp(chain_id);
     let msg_hash: [u8; 32] = Keccak256::digest(&msg)
         .as_slice()
         .to_vec()
         .try_into()
         .expect("hash length isn't 32 bytes");
-    let v = (tx.v - 35 - chain_id * 2) as u8;
+    let v = recovery_id(tx, chain_id)?;
     let pk = recover_pk(v, &tx.r, &tx.s, &msg_hash)?;
     // msg_hash = msg_hash % q
     let msg_hash = BigUint::from_bytes_be(msg_hash.as_slice());
@@ -138,7 +170,7 @@ fn tx_to_sign_data(tx: &Transaction, chain_id: u64) -> Result<SignData, Error> {
 // `zkevm-circuits/src/evm_circuit/table.rs::TxContextFieldTag`.
 /// Tag used to identify each field in the transaction in a row of the
 /// transaction table.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum TxFieldTag {
     /// Unused tag
     Null = 0,
@@ -158,61 +190,535 @@ pub enum TxFieldTag {
     Value,
     /// CallDataLength
     CallDataLength,
+    /// TxSignLength: Length of the RLP encoding of the transaction without
+    /// the signature.
+    TxSignLength,
+    /// TxSignRLC: RLC of the RLP encoding of the transaction without the
+    /// signature.
+    TxSignRLC,
     /// TxSignHash: Hash of the transaction without the signature, used for
     /// signing.
     TxSignHash,
+    /// SigV: "v" value of the transaction signature.
+    SigV,
+    /// SigR: "r" value of the transaction signature.
+    SigR,
+    /// SigS: "s" value of the transaction signature.
+    SigS,
+    /// TxHashLength: Length of the RLP encoding of the signed transaction.
+    TxHashLength,
+    /// TxHashRLC: RLC of the RLP encoding of the signed transaction.
+    TxHashRLC,
+    /// TxHash: Hash of the signed transaction.
+    TxHash,
+    /// TxType: Type of the transaction (EIP-2718), 0 for legacy transactions.
+    TxType,
+    /// ChainID: Chain id encoded in typed transactions.
+    ChainID,
+    /// AccessListRLC: RLC of the RLP encoding of the access list items
+    /// (EIP-2930).
+    AccessListRLC,
+    /// MaxFeePerGas: Maximum fee per gas of dynamic fee transactions
+    /// (EIP-1559).
+    MaxFeePerGas,
+    /// MaxPriorityFeePerGas: Maximum priority fee per gas of dynamic fee
+    /// transactions (EIP-1559).
+    MaxPriorityFeePerGas,
     /// CallData
     CallData,
 }
+impl_expr!(TxFieldTag);
+
+/// Return the tag of the field `tag` in the tx table of the EVM circuit, for
+/// the fields shared with it.
+fn evm_tag(tag: TxFieldTag) -> Option<TxContextFieldTag> {
+    match tag {
+        TxFieldTag::Nonce => Some(TxContextFieldTag::Nonce),
+        TxFieldTag::Gas => Some(TxContextFieldTag::Gas),
+        TxFieldTag::GasPrice => Some(TxContextFieldTag::GasPrice),
+        TxFieldTag::CallerAddress => Some(TxContextFieldTag::CallerAddress),
+        TxFieldTag::CalleeAddress => Some(TxContextFieldTag::CalleeAddress),
+        TxFieldTag::IsCreate => Some(TxContextFieldTag::IsCreate),
+        TxFieldTag::Value => Some(TxContextFieldTag::Value),
+        TxFieldTag::CallDataLength => Some(TxContextFieldTag::CallDataLength),
+        TxFieldTag::TxType => Some(TxContextFieldTag::TxType),
+        TxFieldTag::MaxFeePerGas => Some(TxContextFieldTag::MaxFeePerGas),
+        TxFieldTag::MaxPriorityFeePerGas => Some(TxContextFieldTag::MaxPriorityFeePerGas),
+        _ => None,
+    }
+}
 
 /// Config for TxCircuit
 #[derive(Clone, Debug)]
 pub struct TxCircuitConfig<F: Field> {
-    tx_id: Column<Advice>,
-    tag: Column<Advice>,
-    index: Column<Advice>,
-    value: Column<Advice>,
+    q_keccak: Selector,
+    keccak_length_is_zero: IsZeroConfig<F>,
+    q_field: Column<Fixed>,
+    field_tx_id: Column<Fixed>,
+    field_tag: Column<Fixed>,
+    q_evm_field: Column<Fixed>,
+    evm_tag: Column<Fixed>,
+    q_sig: Selector,
+    q_chain_id: Selector,
+    tx_type_is_zero: IsZeroConfig<F>,
+    is_pre_eip155: Column<Advice>,
+    rec_id: Column<Advice>,
+    q_rlp_sign_field: Selector,
+    q_rlp_hash_field: Selector,
+    q_rlp_sign_encoding: Selector,
+    q_rlp_hash_encoding: Selector,
+    q_calldata_length: Selector,
+    q_calldata_first: Selector,
+    q_calldata: Selector,
+    calldata_length_is_zero: IsZeroConfig<F>,
+    calldata_tx_id_is_zero: IsZeroConfig<F>,
+    calldata_tx_id_unchanged: IsEqualConfig<F>,
+    calldata_tx_id_lt: LtConfig<F, 2>,
+    tx_table: TxTable,
+    evm_tx_table: TxTable,
     sign_verify: SignVerifyConfig<F>,
+    keccak_table: KeccakTable,
+    block_table: BlockTable,
+    rlp: RlpCircuitConfig<F>,
     _marker: PhantomData<F>,
 }
 
 impl<F: Field> TxCircuitConfig<F> {
-    fn new(meta: &mut ConstraintSystem<F>) -> Self {
-        let tx_id = meta.advice_column();
-        let tag = meta.advice_column();
-        let index = meta.advice_column();
-        let value = meta.advice_column();
-        meta.enable_equality(value);
+    /// Configure the tx circuit assigning the `tx_table`, looking up the chain
+    /// id in the `block_table`, the hashes in the `keccak_table` and the
+    /// fields shared with the EVM circuit in the `evm_tx_table`, which is
+    /// assigned by the PI circuit.
+    pub fn new(
+        meta: &mut ConstraintSystem<F>,
+        power_of_randomness: [Expression<F>; POW_RAND_SIZE],
+        tx_table: TxTable,
+        block_table: BlockTable,
+        keccak_table: KeccakTable,
+        evm_tx_table: TxTable,
+    ) -> Self {
+        let TxTable {
+            tx_id,
+            tag,
+            index,
+            value,
+        } = tx_table;
 
-        // This gate is used just to get the array of expressions from the power of
-        // randomness instance column, so that later on we don't need to query
-        // columns everywhere, and can pass the power of randomness array
-        // expression everywhere.  The gate itself doesn't add any constraints.
-        let power_of_randomness = {
-            let columns = [(); sign_verify::POW_RAND_SIZE].map(|_| meta.instance_column());
-            let mut power_of_randomness = None;
+        let rlp = RlpCircuitConfig::configure(meta, power_of_randomness[0].clone());
+        let sign_verify = SignVerifyConfig::new(meta, power_of_randomness, keccak_table);
 
-            meta.create_gate("power of randomness", |meta| {
-                power_of_randomness =
-                    Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
+        // Ref. spec 0. Verify that the signature fields and the caller address are in
+        // the signature table of the SignVerifyChip.  The rows of the signature are
+        // laid out as [TxType, ChainID, TxSignLength, TxSignRLC, TxSignHash, SigV,
+        // SigR, SigS, CallerAddress], and q_sig is enabled in the TxSignHash row of
+        // the non-padding transactions, where the recovery id decoded from SigV is
+        // assigned.
+        let q_sig = meta.complex_selector();
+        let is_pre_eip155 = meta.advice_column();
+        let rec_id = meta.advice_column();
+        let tx_type_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_sig),
+            |meta| meta.query_advice(value, Rotation(-4)),
+            meta.advice_column(),
+        );
+        meta.create_gate("tx signature v", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
 
-                [0.expr()]
+            let chain_id = meta.query_advice(value, Rotation(-3));
+            let sig_v = meta.query_advice(value, Rotation::next());
+            let is_pre_eip155 = meta.query_advice(is_pre_eip155, Rotation::cur());
+            let rec_id = meta.query_advice(rec_id, Rotation::cur());
+            let is_legacy = tx_type_is_zero.is_zero_expression.clone();
+
+            cb.require_boolean("rec_id is boolean", rec_id.clone());
+            cb.require_boolean("is_pre_eip155 is boolean", is_pre_eip155.clone());
+            cb.require_zero(
+                "is_pre_eip155 => tx_type == LEGACY_TX_TYPE",
+                is_pre_eip155.clone() * not::expr(is_legacy.clone()),
+            );
+            cb.condition(not::expr(is_legacy.clone()), |cb| {
+                cb.require_equal("v == rec_id", sig_v.clone(), rec_id.clone());
+            });
+            cb.condition(is_pre_eip155.clone(), |cb| {
+                cb.require_equal(
+                    "v == 27 + rec_id",
+                    sig_v.clone(),
+                    27.expr() + rec_id.clone(),
+                );
+            });
+            cb.condition(is_legacy * not::expr(is_pre_eip155), |cb| {
+                cb.require_equal(
+                    "v == 35 + 2 * chain_id + rec_id",
+                    sig_v,
+                    35.expr() + chain_id * 2.expr() + rec_id,
+                );
             });
 
-            power_of_randomness.unwrap()
-        };
-        let sign_verify = SignVerifyConfig::new(meta, power_of_randomness);
+            cb.gate(meta.query_selector(q_sig))
+        });
+        meta.lookup_any("tx signature in sig table", |meta| {
+            let q_sig = meta.query_selector(q_sig);
+            let table = sign_verify.sig_table_exprs(meta);
+            let [is_valid, msg_hash_rlc, v, sig_r_rlc, sig_s_rlc, address]: [Expression<F>; 6] =
+                table.try_into().expect("sig table has 6 columns");
+            vec![
+                (1.expr(), is_valid),
+                (meta.query_advice(value, Rotation::cur()), msg_hash_rlc),
+                (meta.query_advice(rec_id, Rotation::cur()), v),
+                (meta.query_advice(value, Rotation(2)), sig_r_rlc),
+                (meta.query_advice(value, Rotation(3)), sig_s_rlc),
+                (meta.query_advice(value, Rotation(4)), address),
+            ]
+            .into_iter()
+            .map(|(arg, table)| (q_sig.clone() * arg, table))
+            .collect()
+        });
+
+        // Ref. spec 4. Verify that the chain id of the transactions is the one of the
+        // block.
+        let q_chain_id = meta.complex_selector();
+        meta.lookup_any("tx chain id in block table", |meta| {
+            let q_chain_id = meta.query_selector(q_chain_id);
+            vec![
+                BlockContextFieldTag::ChainId.expr(),
+                0.expr(),
+                meta.query_advice(value, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(block_table.table_exprs(meta))
+            .map(|(arg, table)| (q_chain_id.clone() * arg, table))
+            .collect()
+        });
+
+        // Ref. spec 1. Verify that the TxSignHash and TxHash values are the keccak of
+        // the corresponding RLP encodings by keccak table lookup.  The rows of
+        // each hash are laid out as [*Length, *RLC, *Hash], and q_keccak is
+        // enabled in the *Hash row.  Padding transactions have all values set
+        // to 0, and an encoding of a transaction is never empty, so the lookup
+        // is disabled when the length is 0.
+        //
+        // NOTE: The TxSignHash is the RLC of the message hash reduced modulo the
+        // secp256k1 scalar field order, which differs from keccak(TxSign RLP)
+        // only with negligible probability.
+        let q_keccak = meta.complex_selector();
+        let keccak_length_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_keccak),
+            |meta| meta.query_advice(value, Rotation(-2)),
+            meta.advice_column(),
+        );
+        meta.lookup_any("tx hashes in keccak table", |meta| {
+            let is_enabled = meta.query_selector(q_keccak)
+                * not::expr(keccak_length_is_zero.is_zero_expression.clone());
+            let length = meta.query_advice(value, Rotation(-2));
+            let rlc = meta.query_advice(value, Rotation::prev());
+            let hash = meta.query_advice(value, Rotation::cur());
+
+            vec![1.expr(), rlc, length, hash]
+                .into_iter()
+                .zip(keccak_table.table_exprs(meta))
+                .map(|(arg, table)| (is_enabled.clone() * arg, table))
+                .collect()
+        });
+
+        // Verify that the rows of the fields of each transaction follow the
+        // fixed layout of the circuit, and that the fields shared with the EVM
+        // circuit are the ones of its tx table.
+        let q_field = meta.fixed_column();
+        let field_tx_id = meta.fixed_column();
+        let field_tag = meta.fixed_column();
+        let q_evm_field = meta.fixed_column();
+        let evm_tag = meta.fixed_column();
+        meta.create_gate("tx field layout", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            cb.require_equal(
+                "tx_id == field_tx_id",
+                meta.query_advice(tx_id, Rotation::cur()),
+                meta.query_fixed(field_tx_id, Rotation::cur()),
+            );
+            cb.require_equal(
+                "tag == field_tag",
+                meta.query_advice(tag, Rotation::cur()),
+                meta.query_fixed(field_tag, Rotation::cur()),
+            );
+            cb.require_zero("index == 0", meta.query_advice(index, Rotation::cur()));
+
+            cb.gate(meta.query_fixed(q_field, Rotation::cur()))
+        });
+        meta.lookup_any("tx fields in evm tx table", |meta| {
+            let q_evm_field = meta.query_fixed(q_evm_field, Rotation::cur());
+            vec![
+                q_evm_field.clone() * meta.query_advice(tx_id, Rotation::cur()),
+                meta.query_fixed(evm_tag, Rotation::cur()),
+                0.expr(),
+                q_evm_field * meta.query_advice(value, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(evm_tx_table.table_exprs(meta))
+            .collect()
+        });
+
+        // Ref. spec 2. Verify that the fields of the tx table are the ones RLP encoded
+        // in the TxSign and TxHash encodings, and that the *Length and *RLC
+        // values of each hash correspond to the RLP encodings.
+        let q_rlp_sign_field = meta.complex_selector();
+        let q_rlp_hash_field = meta.complex_selector();
+        for (name, q_rlp_field, data_type) in [
+            (
+                "tx sign fields in rlp",
+                q_rlp_sign_field,
+                RlpDataType::TxSign,
+            ),
+            (
+                "tx hash fields in rlp",
+                q_rlp_hash_field,
+                RlpDataType::TxHash,
+            ),
+        ] {
+            meta.lookup_any(name, |meta| {
+                let q_rlp_field = meta.query_selector(q_rlp_field);
+                vec![
+                    1.expr(),
+                    meta.query_advice(tx_id, Rotation::cur()),
+                    data_type.expr(),
+                    meta.query_advice(tag, Rotation::cur()),
+                    meta.query_advice(value, Rotation::cur()),
+                ]
+                .into_iter()
+                .zip(rlp.field_table_exprs(meta).into_iter())
+                .map(|(arg, table)| (q_rlp_field.clone() * arg, table))
+                .collect()
+            });
+        }
+        let q_rlp_sign_encoding = meta.complex_selector();
+        let q_rlp_hash_encoding = meta.complex_selector();
+        for (name, q_rlp_encoding, data_type) in [
+            (
+                "tx sign encoding in rlp",
+                q_rlp_sign_encoding,
+                RlpDataType::TxSign,
+            ),
+            (
+                "tx hash encoding in rlp",
+                q_rlp_hash_encoding,
+                RlpDataType::TxHash,
+            ),
+        ] {
+            meta.lookup_any(name, |meta| {
+                let q_rlp_encoding = meta.query_selector(q_rlp_encoding);
+                vec![
+                    1.expr(),
+                    meta.query_advice(tx_id, Rotation::cur()),
+                    data_type.expr(),
+                    meta.query_advice(value, Rotation::prev()),
+                    meta.query_advice(value, Rotation::cur()),
+                ]
+                .into_iter()
+                .zip(rlp.encoding_table_exprs(meta).into_iter())
+                .map(|(arg, table)| (q_rlp_encoding.clone() * arg, table))
+                .collect()
+            });
+        }
+
+        // Ref. spec 3. Verify that the call data rows of each transaction have
+        // consecutive indexes from 0 to CallDataLength - 1, grouped by transaction
+        // in increasing tx_id order, and followed by padding rows with tx_id = 0.
+        // Together with the lookup of the last byte from the CallDataLength row,
+        // this guarantees that the call data bytes of each transaction are
+        // uniquely defined by the table.
+        let q_calldata_length = meta.complex_selector();
+        let calldata_length_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_calldata_length),
+            |meta| meta.query_advice(value, Rotation::cur()),
+            meta.advice_column(),
+        );
+        meta.lookup_any("tx call data last byte", |meta| {
+            let cond = meta.query_selector(q_calldata_length)
+                * not::expr(calldata_length_is_zero.is_zero_expression.clone());
+            vec![
+                meta.query_advice(tx_id, Rotation::cur()),
+                TxFieldTag::CallData.expr(),
+                meta.query_advice(value, Rotation::cur()) - 1.expr(),
+            ]
+            .into_iter()
+            .zip([tx_id, tag, index])
+            .map(|(arg, column)| {
+                (
+                    cond.clone() * arg,
+                    meta.query_advice(column, Rotation::cur()),
+                )
+            })
+            .collect()
+        });
+
+        let q_calldata_first = meta.selector();
+        let q_calldata = meta.complex_selector();
+        let calldata_tx_id_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_calldata),
+            |meta| meta.query_advice(tx_id, Rotation::cur()),
+            meta.advice_column(),
+        );
+        let calldata_tx_id_unchanged = IsEqualChip::configure(
+            meta,
+            |meta| meta.query_selector(q_calldata),
+            |meta| meta.query_advice(tx_id, Rotation::next()),
+            |meta| meta.query_advice(tx_id, Rotation::cur()),
+            meta.advice_column(),
+        );
+        let calldata_tx_id_lt = LtChip::configure(
+            meta,
+            |meta| meta.query_selector(q_calldata),
+            |meta| meta.query_advice(tx_id, Rotation::cur()),
+            |meta| meta.query_advice(tx_id, Rotation::next()),
+            rlp.range_check.u8,
+        );
+        meta.create_gate("tx call data first row", |meta| {
+            let q_calldata_first = meta.query_selector(q_calldata_first);
+            // tx_id != 0 => index == 0
+            vec![
+                q_calldata_first
+                    * meta.query_advice(tx_id, Rotation::cur())
+                    * meta.query_advice(index, Rotation::cur()),
+            ]
+        });
+        meta.create_gate("tx call data rows", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let tx_id_next = meta.query_advice(tx_id, Rotation::next());
+            let index_cur = meta.query_advice(index, Rotation::cur());
+            let index_next = meta.query_advice(index, Rotation::next());
+            let tx_id_is_zero = calldata_tx_id_is_zero.is_zero_expression.clone();
+            let tx_id_unchanged = calldata_tx_id_unchanged.expr();
+            let tx_id_lt = calldata_tx_id_lt.is_lt(meta, None);
+
+            cb.require_equal(
+                "tag == CallData",
+                meta.query_advice(tag, Rotation::cur()),
+                TxFieldTag::CallData.expr(),
+            );
+            cb.condition(tx_id_unchanged.clone(), |cb| {
+                cb.require_equal(
+                    "index_next == index + 1",
+                    index_next.clone(),
+                    index_cur + 1.expr(),
+                );
+            });
+            cb.condition(not::expr(tx_id_unchanged), |cb| {
+                // tx_id_next != 0 => index_next == 0 && tx_id < tx_id_next
+                cb.require_zero("index_next == 0", tx_id_next.clone() * index_next);
+                cb.require_zero(
+                    "tx_id < tx_id_next",
+                    tx_id_next.clone() * not::expr(tx_id_lt),
+                );
+            });
+            cb.condition(tx_id_is_zero, |cb| {
+                cb.require_zero("padding rows are at the end", tx_id_next);
+            });
+
+            cb.gate(meta.query_selector(q_calldata))
+        });
+        meta.lookup_any("tx call data length", |meta| {
+            // The padding rows have the same tx_id as the next row, so the last row of
+            // the call data of each transaction is the only one where tx_id changes.
+            let cond = meta.query_selector(q_calldata) * not::expr(calldata_tx_id_unchanged.expr());
+            vec![
+                meta.query_advice(tx_id, Rotation::cur()),
+                TxFieldTag::CallDataLength.expr(),
+                0.expr(),
+                meta.query_advice(index, Rotation::cur()) + 1.expr(),
+            ]
+            .into_iter()
+            .zip([tx_id, tag, index, value])
+            .map(|(arg, column)| {
+                (
+                    cond.clone() * arg,
+                    meta.query_advice(column, Rotation::cur()),
+                )
+            })
+            .collect()
+        });
+        meta.lookup_any("tx call data in evm tx table", |meta| {
+            let cond = meta.query_selector(q_calldata)
+                * not::expr(calldata_tx_id_is_zero.is_zero_expression.clone());
+            vec![
+                meta.query_advice(tx_id, Rotation::cur()),
+                TxContextFieldTag::CallData.expr(),
+                meta.query_advice(index, Rotation::cur()),
+                meta.query_advice(value, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(evm_tx_table.table_exprs(meta))
+            .map(|(arg, table)| (cond.clone() * arg, table))
+            .collect()
+        });
 
         Self {
-            tx_id,
-            tag,
-            index,
-            value,
+            q_keccak,
+            keccak_length_is_zero,
+            q_field,
+            field_tx_id,
+            field_tag,
+            q_evm_field,
+            evm_tag,
+            q_sig,
+            q_chain_id,
+            tx_type_is_zero,
+            is_pre_eip155,
+            rec_id,
+            q_rlp_sign_field,
+            q_rlp_hash_field,
+            q_rlp_sign_encoding,
+            q_rlp_hash_encoding,
+            q_calldata_length,
+            q_calldata_first,
+            q_calldata,
+            calldata_length_is_zero,
+            calldata_tx_id_is_zero,
+            calldata_tx_id_unchanged,
+            calldata_tx_id_lt,
+            tx_table,
+            evm_tx_table,
             sign_verify,
+            keccak_table,
+            block_table,
+            rlp,
             _marker: PhantomData,
         }
     }
 
+    /// Assigns the fixed layout of the row at `offset` holding the field `tag`
+    /// of the transaction `tx_id`.
+    fn assign_field_layout(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        tx_id: usize,
+        tag: TxFieldTag,
+    ) -> Result<(), Error> {
+        let evm_tag = evm_tag(tag);
+        for (name, column, value) in [
+            ("q_field", self.q_field, F::one()),
+            ("field_tx_id", self.field_tx_id, F::from(tx_id as u64)),
+            ("field_tag", self.field_tag, F::from(tag as u64)),
+            (
+                "q_evm_field",
+                self.q_evm_field,
+                F::from(evm_tag.is_some() as u64),
+            ),
+            (
+                "evm_tag",
+                self.evm_tag,
+                F::from(evm_tag.map_or(0, |tag| tag as u64)),
+            ),
+        ] {
+            region.assign_fixed(|| name, column, offset, || Ok(value))?;
+        }
+        Ok(())
+    }
+
     /// Assigns a tx circuit row and returns the assigned cell of the value in
     /// the row.
     fn assign_row(
@@ -224,46 +730,163 @@ impl<F: Field> TxCircuitConfig<F> {
         index: usize,
         value: F,
     ) -> Result<AssignedCell<F, F>, Error> {
-        region.assign_advice(|| "tx_id", self.tx_id, offset, || Ok(F::from(tx_id as u64)))?;
-        region.assign_advice(|| "tag", self.tag, offset, || Ok(F::from(tag as u64)))?;
-        region.assign_advice(|| "index", self.index, offset, || Ok(F::from(index as u64)))?;
-        region.assign_advice(|| "value", self.value, offset, || Ok(value))
+        let tx_table = &self.tx_table;
+        region.assign_advice(
+            || "tx_id",
+            tx_table.tx_id,
+            offset,
+            || Ok(F::from(tx_id as u64)),
+        )?;
+        region.assign_advice(|| "tag", tx_table.tag, offset, || Ok(F::from(tag as u64)))?;
+        region.assign_advice(
+            || "index",
+            tx_table.index,
+            offset,
+            || Ok(F::from(index as u64)),
+        )?;
+        region.assign_advice(|| "value", tx_table.value, offset, || Ok(value))
+    }
+}
+
+/// Return the fields of the transaction `tx` with id `tx_id` in the tx table of
+/// the EVM circuit.
+fn evm_tx(tx_id: usize, tx: &Transaction) -> witness::Transaction {
+    witness::Transaction {
+        id: tx_id,
+        nonce: tx.nonce.as_u64(),
+        gas: tx.gas_limit.as_u64(),
+        gas_price: tx.gas_price,
+        tx_type: tx.transaction_type as u64,
+        max_fee_per_gas: tx.gas_fee_cap,
+        max_priority_fee_per_gas: tx.gas_tip_cap,
+        caller_address: tx.from,
+        callee_address: tx.to.unwrap_or_else(Address::zero),
+        is_create: tx.to.is_none(),
+        value: tx.value,
+        call_data: tx.call_data.to_vec(),
+        call_data_length: tx.call_data.len(),
+        ..witness::Transaction::default()
     }
 }
 
 /// Tx Circuit for verifying transaction signatures
 #[derive(Default)]
-pub struct TxCircuit<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> {
+pub struct TxCircuit<F: Field> {
     /// SignVerify chip
-    pub sign_verify: SignVerifyChip<F, MAX_TXS>,
+    pub sign_verify: SignVerifyChip<F>,
     /// Randomness for RLC encoding
     pub randomness: F,
     /// List of Transactions
     pub txs: Vec<Transaction>,
     /// Chain ID
     pub chain_id: u64,
+    /// Max number of transactions
+    pub max_txs: usize,
+    /// Max number of bytes of call data of all the transactions
+    pub max_calldata: usize,
 }
 
-impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
-    for TxCircuit<F, MAX_TXS, MAX_CALLDATA>
-{
-    type Config = TxCircuitConfig<F>;
-    type FloorPlanner = SimpleFloorPlanner;
+impl<F: Field> TxCircuit<F> {
+    /// Create a new tx circuit verifying `txs` with a capacity of `max_txs`
+    /// transactions and `max_calldata` bytes of call data.
+    pub fn new(
+        aux_generator: Secp256k1Affine,
+        randomness: F,
+        chain_id: u64,
+        txs: Vec<Transaction>,
+        max_txs: usize,
+        max_calldata: usize,
+    ) -> Self {
+        Self {
+            sign_verify: SignVerifyChip {
+                aux_generator,
+                window_size: 2,
+                max_verif: max_txs,
+                _marker: PhantomData,
+            },
+            randomness,
+            txs,
+            chain_id,
+            max_txs,
+            max_calldata,
+        }
+    }
 
-    fn without_witnesses(&self) -> Self {
-        Self::default()
+    /// Return the minimum number of rows required to verify the signatures of
+    /// `params.max_txs` transactions.
+    pub fn min_num_rows(params: &CircuitsParams) -> usize {
+        // Approximate value, adjust with changes on the SignVerifyChip.
+        const ROWS_PER_SIGNATURE: usize = 175_000;
+        params.max_txs * ROWS_PER_SIGNATURE
     }
 
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        TxCircuitConfig::new(meta)
+    /// Return the instance columns required by the circuit: the powers of
+    /// randomness, followed by the MainGate instance column of the
+    /// SignVerifyChip.
+    pub fn instance(&self) -> Vec<Vec<F>> {
+        let rlp_rows: usize = self
+            .txs
+            .iter()
+            .map(|tx| tx.sign_rlp(self.chain_id).len() + tx.rlp(self.chain_id).len())
+            .sum();
+        // The RLP circuit region requires 2 rows of padding.
+        let num_rows = std::cmp::max(self.max_txs * VERIF_HEIGHT, rlp_rows + 2);
+        let mut instance: Vec<Vec<F>> = (1..POW_RAND_SIZE + 1)
+            .map(|exp| vec![self.randomness.pow(&[exp as u64, 0, 0, 0]); num_rows])
+            .collect();
+        // SignVerifyChip -> ECDSAChip -> MainGate instance column
+        instance.push(vec![]);
+        instance
     }
 
-    fn synthesize(
+    /// Check that the circuit supports the type of each transaction, the
+    /// blob transactions (EIP-4844) not being encoded by the RLP circuit yet.
+    fn check_tx_types(&self) -> Result<(), Error> {
+        if let Some(tx) = self
+            .txs
+            .iter()
+            .find(|tx| tx.transaction_type == TransactionType::Blob)
+        {
+            error!(
+                "blob transaction not supported by the tx circuit yet: {:?}",
+                tx
+            );
+            return Err(Error::Synthesis);
+        }
+        Ok(())
+    }
+
+    /// Return the inputs of the keccak hashes verified by the circuit: the
+    /// public keys of the signatures, followed by the TxSign and TxHash RLP
+    /// encodings of the transactions.
+    pub fn keccak_inputs(&self) -> Result<Vec<Vec<u8>>, Error> {
+        self.check_tx_types()?;
+        let sign_datas: Vec<SignData> = self
+            .txs
+            .iter()
+            .map(|tx| {
+                tx_to_sign_data(tx, self.chain_id).map_err(|e| {
+                    error!("tx_to_sign_data error for tx {:?}", tx);
+                    e
+                })
+            })
+            .try_collect()?;
+        let mut keccak_inputs = SignVerifyChip::<F>::keccak_inputs(&sign_datas);
+        keccak_inputs.extend(self.txs.iter().map(|tx| tx.sign_rlp(self.chain_id)));
+        keccak_inputs.extend(self.txs.iter().map(|tx| tx.rlp(self.chain_id)));
+        Ok(keccak_inputs)
+    }
+
+    /// Assign the tx table, the RLP circuit and the signature verifications.
+    /// The keccak table, the block table and the tx table of the EVM circuit
+    /// are expected to be loaded by the caller.
+    pub fn assign(
         &self,
-        config: Self::Config,
-        mut layouter: impl Layouter<F>,
+        config: &TxCircuitConfig<F>,
+        layouter: &mut impl Layouter<F>,
     ) -> Result<(), Error> {
-        assert!(self.txs.len() <= MAX_TXS);
+        assert!(self.txs.len() <= self.max_txs);
+        self.check_tx_types()?;
         let sign_datas: Vec<SignData> = self
             .txs
             .iter()
@@ -274,13 +897,39 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
                 })
             })
             .try_collect()?;
-        let assigned_sig_verifs = self.sign_verify.assign(
-            &config.sign_verify,
-            &mut layouter,
-            self.randomness,
-            &sign_datas,
-        )?;
+        let assigned_sig_verifs =
+            self.sign_verify
+                .assign(&config.sign_verify, layouter, self.randomness, &sign_datas)?;
 
+        let tx_sign_rlps: Vec<Vec<u8>> = self
+            .txs
+            .iter()
+            .map(|tx| tx.sign_rlp(self.chain_id))
+            .collect();
+        let tx_rlps: Vec<Vec<u8>> = self.txs.iter().map(|tx| tx.rlp(self.chain_id)).collect();
+        let tx_hashes: Vec<Word> = tx_rlps
+            .iter()
+            .map(|tx_rlp| Word::from_big_endian(Keccak256::digest(tx_rlp).as_slice()))
+            .collect();
+
+        let mut rlp_witness = Vec::new();
+        for (i, tx) in self.txs.iter().enumerate() {
+            for data_type in [RlpDataType::TxSign, RlpDataType::TxHash] {
+                rlp_witness.extend(rlp_rows(
+                    i + 1,
+                    tx.transaction_type as u64,
+                    data_type,
+                    &tx_rlp_fields(tx, data_type, self.chain_id),
+                    self.randomness,
+                ));
+            }
+        }
+        config.rlp.load(layouter)?;
+        config.rlp.assign(layouter, &rlp_witness)?;
+
+        // The tx circuit assigns the tx table itself instead of using
+        // `TxTable::load`, since its rows include the signature and hash fields
+        // that are computed here and constrained by the tx circuit gates.
         layouter.assign_region(
             || "tx table",
             |mut region| {
@@ -288,35 +937,44 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
                 // Empty entry
                 config.assign_row(&mut region, offset, 0, TxFieldTag::Null, 0, F::zero())?;
                 offset += 1;
-                // Assign al Tx fields except for call data
-                let tx_default = Transaction::default();
-                // for i in 0..MAX_TXS
+                // Assign al Tx fields except for call data.  Padding transactions
+                // have all their fields set to 0, as in the tx table of the EVM
+                // circuit.
+                let tx_default = Transaction {
+                    to: Some(Address::zero()),
+                    ..Transaction::default()
+                };
+                let calldata_length_is_zero_chip =
+                    IsZeroChip::construct(config.calldata_length_is_zero.clone());
+                let tx_type_is_zero_chip = IsZeroChip::construct(config.tx_type_is_zero.clone());
+                let keccak_length_is_zero_chip =
+                    IsZeroChip::construct(config.keccak_length_is_zero.clone());
+                // for i in 0..max_txs
                 for (i, assigned_sig_verif) in assigned_sig_verifs.iter().enumerate() {
-                    let tx = if i < self.txs.len() {
-                        &self.txs[i]
+                    let (tx, tx_sign_rlp, tx_rlp, tx_hash) = if i < self.txs.len() {
+                        (
+                            &self.txs[i],
+                            &tx_sign_rlps[i][..],
+                            &tx_rlps[i][..],
+                            tx_hashes[i],
+                        )
                     } else {
-                        &tx_default
+                        (&tx_default, &[][..], &[][..], Word::zero())
                     };
-                    let address_cell = assigned_sig_verif.address.cell();
-                    let msg_hash_rlc_cell = assigned_sig_verif.msg_hash_rlc.cell();
                     let msg_hash_rlc_value = assigned_sig_verif.msg_hash_rlc.value();
                     for (tag, value) in &[
                         (
                             TxFieldTag::Nonce,
-                            rlc(tx.nonce.to_le_bytes(), self.randomness),
+                            tx.nonce.to_scalar().expect("tx.nonce too big"),
                         ),
                         (
                             TxFieldTag::Gas,
-                            rlc(tx.gas_limit.to_le_bytes(), self.randomness),
+                            tx.gas_limit.to_scalar().expect("tx.gas_limit too big"),
                         ),
                         (
                             TxFieldTag::GasPrice,
                             rlc(tx.gas_price.to_le_bytes(), self.randomness),
                         ),
-                        (
-                            TxFieldTag::CallerAddress,
-                            tx.from.to_scalar().expect("tx.from too big"),
-                        ),
                         (
                             TxFieldTag::CalleeAddress,
                             tx.to
@@ -333,57 +991,196 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
                             TxFieldTag::CallDataLength,
                             F::from(tx.call_data.0.len() as u64),
                         ),
+                        (
+                            TxFieldTag::AccessListRLC,
+                            tx.access_list_items_rlp()
+                                .concat()
+                                .rlc_stream(self.randomness),
+                        ),
+                        (
+                            TxFieldTag::MaxFeePerGas,
+                            rlc(tx.gas_fee_cap.to_le_bytes(), self.randomness),
+                        ),
+                        (
+                            TxFieldTag::MaxPriorityFeePerGas,
+                            rlc(tx.gas_tip_cap.to_le_bytes(), self.randomness),
+                        ),
+                        (TxFieldTag::TxType, F::from(tx.transaction_type as u64)),
+                        (TxFieldTag::ChainID, F::from(self.chain_id)),
+                        (TxFieldTag::TxSignLength, F::from(tx_sign_rlp.len() as u64)),
+                        (
+                            TxFieldTag::TxSignRLC,
+                            tx_sign_rlp.rlc_stream(self.randomness),
+                        ),
                         (
                             TxFieldTag::TxSignHash,
                             *msg_hash_rlc_value.unwrap_or(&F::zero()),
                         ),
+                        (TxFieldTag::SigV, F::from(tx.v)),
+                        (TxFieldTag::SigR, rlc(tx.r.to_le_bytes(), self.randomness)),
+                        (TxFieldTag::SigS, rlc(tx.s.to_le_bytes(), self.randomness)),
+                        (
+                            TxFieldTag::CallerAddress,
+                            tx.from.to_scalar().expect("tx.from too big"),
+                        ),
+                        (TxFieldTag::TxHashLength, F::from(tx_rlp.len() as u64)),
+                        (TxFieldTag::TxHashRLC, tx_rlp.rlc_stream(self.randomness)),
+                        (
+                            TxFieldTag::TxHash,
+                            rlc(tx_hash.to_le_bytes(), self.randomness),
+                        ),
                     ] {
-                        let assigned_cell =
-                            config.assign_row(&mut region, offset, i + 1, *tag, 0, *value)?;
-                        offset += 1;
-
-                        // Ref. spec 0. Copy constraints using fixed offsets between the tx rows and
-                        // the SignVerifyChip
-                        match tag {
-                            TxFieldTag::CallerAddress => {
-                                region.constrain_equal(assigned_cell.cell(), address_cell)?
-                            }
-                            TxFieldTag::TxSignHash => {
-                                region.constrain_equal(assigned_cell.cell(), msg_hash_rlc_cell)?
+                        config.assign_row(&mut region, offset, i + 1, *tag, 0, *value)?;
+                        config.assign_field_layout(&mut region, offset, i + 1, *tag)?;
+                        if matches!(tag, TxFieldTag::TxSignHash | TxFieldTag::TxHash) {
+                            config.q_keccak.enable(&mut region, offset)?;
+                            let length = if *tag == TxFieldTag::TxSignHash {
+                                tx_sign_rlp.len()
+                            } else {
+                                tx_rlp.len()
+                            };
+                            keccak_length_is_zero_chip.assign(
+                                &mut region,
+                                offset,
+                                Some(F::from(length as u64)),
+                            )?;
+                        }
+                        if i < self.txs.len() {
+                            match tag {
+                                TxFieldTag::Nonce
+                                | TxFieldTag::Gas
+                                | TxFieldTag::CalleeAddress
+                                | TxFieldTag::Value => {
+                                    config.q_rlp_sign_field.enable(&mut region, offset)?;
+                                    config.q_rlp_hash_field.enable(&mut region, offset)?;
+                                }
+                                // Dynamic fee transactions encode the fee caps instead of the
+                                // gas price, which is then the effective gas price checked
+                                // in BeginTx.
+                                TxFieldTag::GasPrice
+                                    if tx.transaction_type != TransactionType::DynamicFee =>
+                                {
+                                    config.q_rlp_sign_field.enable(&mut region, offset)?;
+                                    config.q_rlp_hash_field.enable(&mut region, offset)?;
+                                }
+                                TxFieldTag::MaxFeePerGas | TxFieldTag::MaxPriorityFeePerGas
+                                    if tx.transaction_type == TransactionType::DynamicFee =>
+                                {
+                                    config.q_rlp_sign_field.enable(&mut region, offset)?;
+                                    config.q_rlp_hash_field.enable(&mut region, offset)?;
+                                }
+                                // The type byte and the access list are only encoded in
+                                // typed transactions.
+                                TxFieldTag::TxType | TxFieldTag::AccessListRLC
+                                    if tx.transaction_type != TransactionType::Legacy =>
+                                {
+                                    config.q_rlp_sign_field.enable(&mut region, offset)?;
+                                    config.q_rlp_hash_field.enable(&mut region, offset)?;
+                                }
+                                // The chain id is encoded in both encodings of typed
+                                // transactions, and in the TxSign encoding of legacy
+                                // transactions after EIP-155.
+                                TxFieldTag::ChainID => {
+                                    config.q_chain_id.enable(&mut region, offset)?;
+                                    if !tx.is_pre_eip155() {
+                                        config.q_rlp_sign_field.enable(&mut region, offset)?;
+                                    }
+                                    if tx.transaction_type != TransactionType::Legacy {
+                                        config.q_rlp_hash_field.enable(&mut region, offset)?;
+                                    }
+                                }
+                                TxFieldTag::SigV | TxFieldTag::SigR | TxFieldTag::SigS => {
+                                    config.q_rlp_hash_field.enable(&mut region, offset)?;
+                                }
+                                TxFieldTag::TxSignRLC => {
+                                    config.q_rlp_sign_encoding.enable(&mut region, offset)?;
+                                }
+                                TxFieldTag::TxSignHash => {
+                                    config.q_sig.enable(&mut region, offset)?;
+                                    region.assign_advice(
+                                        || "is_pre_eip155",
+                                        config.is_pre_eip155,
+                                        offset,
+                                        || Ok(F::from(tx.is_pre_eip155() as u64)),
+                                    )?;
+                                    let rec_id = recovery_id(tx, self.chain_id)?;
+                                    region.assign_advice(
+                                        || "rec_id",
+                                        config.rec_id,
+                                        offset,
+                                        || Ok(F::from(rec_id as u64)),
+                                    )?;
+                                    tx_type_is_zero_chip.assign(
+                                        &mut region,
+                                        offset,
+                                        Some(F::from(tx.transaction_type as u64)),
+                                    )?;
+                                }
+                                TxFieldTag::TxHashRLC => {
+                                    config.q_rlp_hash_encoding.enable(&mut region, offset)?;
+                                }
+                                _ => (),
                             }
-                            _ => (),
                         }
+                        if let TxFieldTag::CallDataLength = tag {
+                            config.q_calldata_length.enable(&mut region, offset)?;
+                            calldata_length_is_zero_chip.assign(
+                                &mut region,
+                                offset,
+                                Some(F::from(tx.call_data.0.len() as u64)),
+                            )?;
+                        }
+                        offset += 1;
                     }
                 }
 
                 // Assign call data
-                let mut calldata_count = 0;
+                let mut calldata_rows = Vec::new();
                 for (i, tx) in self.txs.iter().enumerate() {
                     for (index, byte) in tx.call_data.0.iter().enumerate() {
-                        assert!(calldata_count < MAX_CALLDATA);
-                        config.assign_row(
-                            &mut region,
-                            offset,
-                            i + 1, // tx_id
-                            TxFieldTag::CallData,
-                            index,
-                            F::from(*byte as u64),
-                        )?;
-                        offset += 1;
-                        calldata_count += 1;
+                        calldata_rows.push((i + 1, index, *byte));
                     }
                 }
-                for _ in calldata_count..MAX_CALLDATA {
+                assert!(calldata_rows.len() <= self.max_calldata);
+                calldata_rows.resize(self.max_calldata, (0, 0, 0));
+                let calldata_tx_id_is_zero_chip =
+                    IsZeroChip::construct(config.calldata_tx_id_is_zero.clone());
+                let calldata_tx_id_unchanged_chip =
+                    IsEqualChip::construct(config.calldata_tx_id_unchanged.clone());
+                let calldata_tx_id_lt_chip = LtChip::construct(config.calldata_tx_id_lt);
+                config.q_calldata_first.enable(&mut region, offset)?;
+                for (idx, (tx_id, index, byte)) in calldata_rows.iter().enumerate() {
+                    let tx_id_next = calldata_rows.get(idx + 1).map_or(0, |row| row.0);
+                    config.q_calldata.enable(&mut region, offset)?;
                     config.assign_row(
                         &mut region,
                         offset,
-                        0, // tx_id
+                        *tx_id,
                         TxFieldTag::CallData,
-                        0,
-                        F::zero(),
+                        *index,
+                        F::from(*byte as u64),
+                    )?;
+                    calldata_tx_id_is_zero_chip.assign(
+                        &mut region,
+                        offset,
+                        Some(F::from(*tx_id as u64)),
+                    )?;
+                    calldata_tx_id_unchanged_chip.assign(
+                        &mut region,
+                        offset,
+                        Some(F::from(tx_id_next as u64)),
+                        Some(F::from(*tx_id as u64)),
+                    )?;
+                    calldata_tx_id_lt_chip.assign(
+                        &mut region,
+                        offset,
+                        F::from(*tx_id as u64),
+                        F::from(tx_id_next as u64),
                     )?;
                     offset += 1;
                 }
+                // Padding row after the call data, queried by the last call data row.
+                config.assign_row(&mut region, offset, 0, TxFieldTag::CallData, 0, F::zero())?;
                 Ok(())
             },
         )?;
@@ -391,12 +1188,107 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
     }
 }
 
+impl<F: Field> Circuit<F> for TxCircuit<F> {
+    type Config = TxCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        // The capacities fix the layout of the circuit, which must be the same
+        // in the verifying key.
+        Self {
+            sign_verify: SignVerifyChip {
+                window_size: self.sign_verify.window_size,
+                max_verif: self.sign_verify.max_verif,
+                ..SignVerifyChip::default()
+            },
+            max_txs: self.max_txs,
+            max_calldata: self.max_calldata,
+            ..Self::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        // This gate is used just to get the array of expressions from the power of
+        // randomness instance column, so that later on we don't need to query
+        // columns everywhere, and can pass the power of randomness array
+        // expression everywhere.  The gate itself doesn't add any constraints.
+        let power_of_randomness = {
+            let columns = [(); POW_RAND_SIZE].map(|_| meta.instance_column());
+            let mut power_of_randomness = None;
+
+            meta.create_gate("power of randomness", |meta| {
+                power_of_randomness =
+                    Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
+
+                [0.expr()]
+            });
+
+            power_of_randomness.unwrap()
+        };
+        let tx_table = TxTable::construct(meta);
+        let block_table = BlockTable::construct(meta);
+        // The standalone circuit loads the keccak table and the tx table of the
+        // EVM circuit itself, which are proven by the keccak circuit and the PI
+        // circuit in the super circuit.
+        let keccak_table = KeccakTable::construct(meta);
+        let evm_tx_table = TxTable::construct(meta);
+        TxCircuitConfig::new(
+            meta,
+            power_of_randomness,
+            tx_table,
+            block_table,
+            keccak_table,
+            evm_tx_table,
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let keccak_inputs = self.keccak_inputs()?;
+        config
+            .keccak_table
+            .dev_load(&mut layouter, &keccak_inputs, self.randomness)?;
+        let evm_txs: Vec<witness::Transaction> = self
+            .txs
+            .iter()
+            .enumerate()
+            .map(|(idx, tx)| evm_tx(idx + 1, tx))
+            .collect();
+        config.evm_tx_table.load(
+            &mut layouter,
+            &evm_txs,
+            self.max_txs,
+            self.max_calldata,
+            self.randomness,
+        )?;
+        config.block_table.load(
+            &mut layouter,
+            &BlockContext {
+                chain_id: self.chain_id.into(),
+                ..BlockContext::default()
+            },
+            self.randomness,
+        )?;
+        self.assign(&config, &mut layouter)
+    }
+}
+
 #[cfg(test)]
 mod tx_circuit_tests {
     use super::*;
-    use eth_types::{address, word, Bytes};
+    use eth_types::{address, word, AccessList, Bytes, H256};
     use ethers_core::{
-        types::{NameOrAddress, TransactionRequest},
+        types::{
+            transaction::{
+                eip1559::Eip1559TransactionRequest,
+                eip2718::TypedTransaction,
+                eip2930::{AccessListItem, Eip2930TransactionRequest},
+            },
+            NameOrAddress, TransactionRequest,
+        },
         utils::keccak256,
     };
     use ethers_signers::{LocalWallet, Signer};
@@ -420,22 +1312,16 @@ mod tx_circuit_tests {
             <Secp256k1Affine as CurveAffine>::CurveExt::random(&mut rng).to_affine();
 
         let randomness = F::random(&mut rng);
-        let mut instance: Vec<Vec<F>> = (1..POW_RAND_SIZE + 1)
-            .map(|exp| vec![randomness.pow(&[exp as u64, 0, 0, 0]); txs.len() * VERIF_HEIGHT])
-            .collect();
-        // SignVerifyChip -> ECDSAChip -> MainGate instance column
-        instance.push(vec![]);
-        let circuit = TxCircuit::<F, MAX_TXS, MAX_CALLDATA> {
-            sign_verify: SignVerifyChip {
-                aux_generator,
-                window_size: 2,
-                _marker: PhantomData,
-            },
+        let circuit = TxCircuit::<F>::new(
+            aux_generator,
             randomness,
-            txs,
             chain_id,
-        };
+            txs,
+            MAX_TXS,
+            MAX_CALLDATA,
+        );
 
+        let instance = circuit.instance();
         let prover = match MockProver::run(k, &circuit, instance) {
             Ok(prover) => prover,
             Err(e) => panic!("{:#?}", e),
@@ -479,6 +1365,234 @@ mod tx_circuit_tests {
         }
     }
 
+    /// Return a random signed EIP-2930 transaction, together with its RLP
+    /// encoding for signing computed by ethers.
+    fn rand_access_list_tx<R: Rng + CryptoRng>(
+        mut rng: R,
+        chain_id: u64,
+    ) -> (Transaction, Vec<u8>) {
+        let wallet0 = LocalWallet::new(&mut rng).with_chain_id(chain_id);
+        let wallet1 = LocalWallet::new(&mut rng).with_chain_id(chain_id);
+        let from = wallet0.address();
+        let to = wallet1.address();
+        let access_list = AccessList(vec![
+            AccessListItem {
+                address: to,
+                storage_keys: vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)],
+            },
+            AccessListItem {
+                address: from,
+                storage_keys: vec![],
+            },
+        ]);
+        let tx = TransactionRequest::new()
+            .from(from)
+            .to(to)
+            .nonce(3)
+            .value(1000)
+            .data(b"hello")
+            .gas(500_000)
+            .gas_price(1234);
+        let typed_tx = TypedTransaction::Eip2930(Eip2930TransactionRequest::new(
+            tx.clone(),
+            access_list.clone(),
+        ));
+        let tx_sign_rlp = typed_tx.rlp(chain_id).to_vec();
+        let sig = wallet0.sign_hash(keccak256(&tx_sign_rlp).into(), false);
+        let to = tx.to.map(|to| match to {
+            NameOrAddress::Address(a) => a,
+            _ => unreachable!(),
+        });
+        let tx = Transaction {
+            from: tx.from.unwrap(),
+            to,
+            gas_limit: tx.gas.unwrap(),
+            gas_price: tx.gas_price.unwrap(),
+            value: tx.value.unwrap(),
+            call_data: tx.data.unwrap(),
+            nonce: tx.nonce.unwrap(),
+            transaction_type: TransactionType::AccessList,
+            access_list: Some(access_list),
+            // The signature of typed transactions encodes the y parity in `v`.
+            v: sig.v - 27,
+            r: sig.r,
+            s: sig.s,
+            ..Transaction::default()
+        };
+        (tx, tx_sign_rlp)
+    }
+
+    /// Return a random signed EIP-1559 transaction, together with its RLP
+    /// encoding for signing computed by ethers.
+    fn rand_dynamic_fee_tx<R: Rng + CryptoRng>(
+        mut rng: R,
+        chain_id: u64,
+    ) -> (Transaction, Vec<u8>) {
+        let wallet0 = LocalWallet::new(&mut rng).with_chain_id(chain_id);
+        let wallet1 = LocalWallet::new(&mut rng).with_chain_id(chain_id);
+        let from = wallet0.address();
+        let to = wallet1.address();
+        let access_list = AccessList(vec![AccessListItem {
+            address: to,
+            storage_keys: vec![H256::from_low_u64_be(1)],
+        }]);
+        let tx = Eip1559TransactionRequest::new()
+            .from(from)
+            .to(to)
+            .nonce(3)
+            .value(1000)
+            .data(b"hello")
+            .gas(500_000)
+            .max_fee_per_gas(2000)
+            .max_priority_fee_per_gas(1000)
+            .access_list(access_list.clone());
+        let typed_tx = TypedTransaction::Eip1559(tx.clone());
+        let tx_sign_rlp = typed_tx.rlp(chain_id).to_vec();
+        let sig = wallet0.sign_hash(keccak256(&tx_sign_rlp).into(), false);
+        let to = tx.to.map(|to| match to {
+            NameOrAddress::Address(a) => a,
+            _ => unreachable!(),
+        });
+        let tx = Transaction {
+            from: tx.from.unwrap(),
+            to,
+            gas_limit: tx.gas.unwrap(),
+            // The effective gas price with a zero base fee.
+            gas_price: tx.max_priority_fee_per_gas.unwrap(),
+            gas_fee_cap: tx.max_fee_per_gas.unwrap(),
+            gas_tip_cap: tx.max_priority_fee_per_gas.unwrap(),
+            value: tx.value.unwrap(),
+            call_data: tx.data.unwrap(),
+            nonce: tx.nonce.unwrap(),
+            transaction_type: TransactionType::DynamicFee,
+            access_list: Some(access_list),
+            v: sig.v - 27,
+            r: sig.r,
+            s: sig.s,
+            ..Transaction::default()
+        };
+        (tx, tx_sign_rlp)
+    }
+
+    /// Return a random signed contract creation transaction, together with
+    /// its RLP encoding for signing computed by ethers.
+    fn rand_create_tx<R: Rng + CryptoRng>(mut rng: R, chain_id: u64) -> (Transaction, Vec<u8>) {
+        let wallet0 = LocalWallet::new(&mut rng).with_chain_id(chain_id);
+        let from = wallet0.address();
+        let tx = TransactionRequest::new()
+            .from(from)
+            .nonce(3)
+            .value(1000)
+            .data(vec![0x60, 0x00, 0x60, 0x00, 0xf3])
+            .gas(500_000)
+            .gas_price(1234);
+        let tx_sign_rlp = tx.rlp(chain_id).to_vec();
+        let sig = wallet0.sign_hash(keccak256(&tx_sign_rlp).into(), true);
+        let tx = Transaction {
+            from: tx.from.unwrap(),
+            to: None,
+            gas_limit: tx.gas.unwrap(),
+            gas_price: tx.gas_price.unwrap(),
+            value: tx.value.unwrap(),
+            call_data: tx.data.unwrap(),
+            nonce: tx.nonce.unwrap(),
+            v: sig.v,
+            r: sig.r,
+            s: sig.s,
+            ..Transaction::default()
+        };
+        (tx, tx_sign_rlp)
+    }
+
+    #[test]
+    fn tx_recovery_id() {
+        let chain_id: u64 = 1337;
+        for (transaction_type, v, rec_id) in [
+            (TransactionType::Legacy, 27, 0),
+            (TransactionType::Legacy, 28, 1),
+            (TransactionType::Legacy, 35 + 2 * chain_id, 0),
+            (TransactionType::Legacy, 36 + 2 * chain_id, 1),
+            (TransactionType::AccessList, 1, 1),
+            (TransactionType::DynamicFee, 0, 0),
+        ] {
+            let tx = Transaction {
+                transaction_type,
+                v,
+                ..Transaction::default()
+            };
+            assert_eq!(recovery_id(&tx, chain_id).unwrap(), rec_id);
+        }
+    }
+
+    #[test]
+    fn tx_recovery_id_invalid_v() {
+        let chain_id: u64 = 1337;
+        for (transaction_type, v) in [
+            // Legacy txs signed for chain ids 1 and 1338.
+            (TransactionType::Legacy, 37),
+            (TransactionType::Legacy, 35 + 2 * 1338),
+            (TransactionType::Legacy, 0),
+            (TransactionType::Legacy, 37 + 2 * chain_id),
+            (TransactionType::DynamicFee, 27),
+        ] {
+            let tx = Transaction {
+                transaction_type,
+                v,
+                ..Transaction::default()
+            };
+            assert!(matches!(recovery_id(&tx, chain_id), Err(Error::Synthesis)));
+        }
+    }
+
+    #[test]
+    fn tx_to_sign_data_other_chain_id() {
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        let (tx, _) = rand_create_tx(&mut rng, 1);
+        assert!(tx_to_sign_data(&tx, 1).is_ok());
+        assert!(matches!(tx_to_sign_data(&tx, 1337), Err(Error::Synthesis)));
+    }
+
+    #[test]
+    fn tx_sign_rlp_access_list() {
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        let chain_id: u64 = 1337;
+        let (tx, expected) = rand_access_list_tx(&mut rng, chain_id);
+        assert_eq!(tx.sign_rlp(chain_id), expected);
+    }
+
+    #[test]
+    fn tx_sign_rlp_dynamic_fee() {
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        let chain_id: u64 = 1337;
+        let (tx, expected) = rand_dynamic_fee_tx(&mut rng, chain_id);
+        assert_eq!(tx.sign_rlp(chain_id), expected);
+    }
+
+    #[test]
+    fn tx_sign_rlp_create() {
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        let chain_id: u64 = 1337;
+        let (tx, expected) = rand_create_tx(&mut rng, chain_id);
+        assert_eq!(tx.sign_rlp(chain_id), expected);
+    }
+
+    #[test]
+    fn tx_circuit_rejects_blob_tx() {
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        let chain_id: u64 = 1337;
+        let aux_generator =
+            <Secp256k1Affine as CurveAffine>::CurveExt::random(&mut rng).to_affine();
+        let (tx, _) = rand_dynamic_fee_tx(&mut rng, chain_id);
+        let tx = Transaction {
+            transaction_type: TransactionType::Blob,
+            max_fee_per_blob_gas: word!("0x1"),
+            blob_versioned_hashes: vec![H256::from_low_u64_be(0x0100)],
+            ..tx
+        };
+        let circuit = TxCircuit::<Fr>::new(aux_generator, Fr::one(), chain_id, vec![tx], 1, 32);
+        assert!(matches!(circuit.keccak_inputs(), Err(Error::Synthesis)));
+    }
+
     // High memory usage test.  Run in serial with:
     // `cargo test [...] serial_ -- --ignored --test-threads 1`
     #[ignore]
@@ -499,6 +1613,63 @@ mod tx_circuit_tests {
         assert_eq!(run::<Fr, MAX_TXS, MAX_CALLDATA>(k, txs, chain_id), Ok(()));
     }
 
+    // High memory usage test.  Run in serial with:
+    // `cargo test [...] serial_ -- --ignored --test-threads 1`
+    #[ignore]
+    #[test]
+    fn serial_test_tx_circuit_access_list() {
+        const MAX_TXS: usize = 2;
+        const MAX_CALLDATA: usize = 32;
+
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        let chain_id: u64 = 1337;
+        let txs = vec![
+            rand_tx(&mut rng, chain_id),
+            rand_access_list_tx(&mut rng, chain_id).0,
+        ];
+
+        let k = 19;
+        assert_eq!(run::<Fr, MAX_TXS, MAX_CALLDATA>(k, txs, chain_id), Ok(()));
+    }
+
+    // High memory usage test.  Run in serial with:
+    // `cargo test [...] serial_ -- --ignored --test-threads 1`
+    #[ignore]
+    #[test]
+    fn serial_test_tx_circuit_dynamic_fee() {
+        const MAX_TXS: usize = 2;
+        const MAX_CALLDATA: usize = 32;
+
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        let chain_id: u64 = 1337;
+        let txs = vec![
+            rand_access_list_tx(&mut rng, chain_id).0,
+            rand_dynamic_fee_tx(&mut rng, chain_id).0,
+        ];
+
+        let k = 19;
+        assert_eq!(run::<Fr, MAX_TXS, MAX_CALLDATA>(k, txs, chain_id), Ok(()));
+    }
+
+    // High memory usage test.  Run in serial with:
+    // `cargo test [...] serial_ -- --ignored --test-threads 1`
+    #[ignore]
+    #[test]
+    fn serial_test_tx_circuit_create() {
+        const MAX_TXS: usize = 2;
+        const MAX_CALLDATA: usize = 32;
+
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        let chain_id: u64 = 1337;
+        let txs = vec![
+            rand_tx(&mut rng, chain_id),
+            rand_create_tx(&mut rng, chain_id).0,
+        ];
+
+        let k = 19;
+        assert_eq!(run::<Fr, MAX_TXS, MAX_CALLDATA>(k, txs, chain_id), Ok(()));
+    }
+
     // High memory usage test.  Run in serial with:
     // `cargo test [...] serial_ -- --ignored --test-threads 1`
     #[ignore]
@@ -520,7 +1691,10 @@ mod tx_circuit_tests {
             gas_fee_cap: word!("0x0"),
             gas_tip_cap: word!("0x0"),
             call_data: Bytes::from(b"hello"),
+            transaction_type: TransactionType::Legacy,
             access_list: None,
+            max_fee_per_blob_gas: word!("0x0"),
+            blob_versioned_hashes: Vec::new(),
             v: 2710,
             r: word!("0xaf180d27f90b2b20808bc7670ce0aca862bc2b5fa39c195ab7b1a96225ee14d7"),
             s: word!("0x61159fa4664b698ea7d518526c96cd94cf4d8adf418000754be106a3a133f866"),
@@ -553,7 +1727,10 @@ mod tx_circuit_tests {
             gas_fee_cap: word!("0x0"),
             gas_tip_cap: word!("0x0"),
             call_data: Bytes::from(b"hello"),
+            transaction_type: TransactionType::Legacy,
             access_list: None,
+            max_fee_per_blob_gas: word!("0x0"),
+            blob_versioned_hashes: Vec::new(),
             v: 2710,
             r: word!("0xaf180d27f90b2b20808bc7670ce0aca862bc2b5fa39c195ab7b1a96225ee14d7"),
             s: word!("0x61159fa4664b698ea7d518526c96cd94cf4d8adf418000754be106a3a133f866"),
@@ -562,4 +1739,37 @@ mod tx_circuit_tests {
         let k = 19;
         assert!(run::<Fr, MAX_TXS, MAX_CALLDATA>(k, vec![tx], chain_id).is_err(),);
     }
+
+    // High memory usage test.  Run in serial with:
+    // `cargo test [...] serial_ -- --ignored --test-threads 1`
+    #[ignore]
+    #[test]
+    fn serial_test_tx_circuit_bad_signature() {
+        const MAX_TXS: usize = 1;
+        const MAX_CALLDATA: usize = 32;
+
+        let chain_id: u64 = 1337;
+        let tx = Transaction {
+            from: address!("0x5f9b7e36af4ff81688f712fb738bbbc1b7348aae"),
+            to: Some(address!("0x701653d7ae8ddaa5c8cee1ee056849f271827926")),
+            nonce: word!("0x3"),
+            gas_limit: word!("0x7a120"),
+            value: word!("0x3e8"),
+            gas_price: word!("0x4d2"),
+            gas_fee_cap: word!("0x0"),
+            gas_tip_cap: word!("0x0"),
+            call_data: Bytes::from(b"hello"),
+            transaction_type: TransactionType::Legacy,
+            access_list: None,
+            max_fee_per_blob_gas: word!("0x0"),
+            blob_versioned_hashes: Vec::new(),
+            v: 2710,
+            r: word!("0xaf180d27f90b2b20808bc7670ce0aca862bc2b5fa39c195ab7b1a96225ee14d7"),
+            // This "s" value doesn't correspond to the signature of this tx.
+            s: word!("0x61159fa4664b698ea7d518526c96cd94cf4d8adf418000754be106a3a133f867"),
+        };
+
+        let k = 19;
+        assert!(run::<Fr, MAX_TXS, MAX_CALLDATA>(k, vec![tx], chain_id).is_err(),);
+    }
 }
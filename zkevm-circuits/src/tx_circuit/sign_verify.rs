@@ -5,7 +5,11 @@
 // - *_le: Little-Endian bytes
 
 use crate::{
-    evm_circuit::util::{not, RandomLinearCombination, Word},
+    evm_circuit::{
+        table::LookupTable,
+        util::{not, RandomLinearCombination, Word},
+    },
+    table::KeccakTable,
     util::Expr,
 };
 use ecc::{EccConfig, GeneralEccChip};
@@ -15,7 +19,7 @@ use group::{ff::Field, prime::PrimeCurveAffine, Curve};
 use halo2_proofs::{
     arithmetic::{BaseExt, Coordinates, CurveAffine, FieldExt},
     circuit::{AssignedCell, Layouter, Region},
-    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, VirtualCells},
     poly::Rotation,
 };
 use integer::{
@@ -47,19 +51,21 @@ pub const VERIF_HEIGHT: usize = 1;
 /// Auxiliary Gadget to verify a that a message hash is signed by the public
 /// key corresponding to an Ethereum Address.
 #[derive(Default, Debug)]
-pub struct SignVerifyChip<F: FieldExt, const MAX_VERIF: usize> {
+pub struct SignVerifyChip<F: FieldExt> {
     /// Aux generator for EccChip
     pub aux_generator: Secp256k1Affine,
     /// Window size for EccChip
     pub window_size: usize,
+    /// Max number of verifications
+    pub max_verif: usize,
     /// Marker
     pub _marker: PhantomData<F>,
 }
 
-const KECCAK_IS_ENABLED: usize = 0;
-const KECCAK_INPUT_RLC: usize = 1;
-const KECCAK_INPUT_LEN: usize = 2;
-const KECCAK_OUTPUT_RLC: usize = 3;
+pub(crate) const KECCAK_IS_ENABLED: usize = 0;
+pub(crate) const KECCAK_INPUT_RLC: usize = 1;
+pub(crate) const KECCAK_INPUT_LEN: usize = 2;
+pub(crate) const KECCAK_OUTPUT_RLC: usize = 3;
 
 const NUMBER_OF_LIMBS: usize = 4;
 const BIT_LEN_LIMB: usize = 72;
@@ -75,6 +81,22 @@ pub(crate) fn pk_bytes_swap_endianness<T: Clone>(pk: &[T]) -> [T; 64] {
     pk_swap
 }
 
+/// Return the serialized public key in big endian, as it's hashed to obtain the
+/// Ethereum Address.
+pub(crate) fn pk_bytes_be(pk: &Secp256k1Affine) -> [u8; 64] {
+    let pk_coord = Option::<Coordinates<_>>::from(pk.coordinates()).expect("point is the identity");
+    let mut pk_le = [0u8; 64];
+    pk_coord
+        .x()
+        .write(&mut Cursor::new(&mut pk_le[..32]))
+        .expect("cannot write bytes to array");
+    pk_coord
+        .y()
+        .write(&mut Cursor::new(&mut pk_le[32..]))
+        .expect("cannot write bytes to array");
+    pk_bytes_swap_endianness(&pk_le)
+}
+
 /// Return an expression that builds an integer element in the field from the
 /// `bytes` in big endian.
 fn int_from_bytes_be<F: FieldExt>(bytes: &[Expression<F>]) -> Expression<F> {
@@ -126,16 +148,29 @@ pub(crate) struct SignVerifyConfig<F: FieldExt> {
     // endian.
     pk: [[Column<Advice>; 32]; 2],
     msg_hash: [Column<Advice>; 32],
+    // Signature (r, s) values in little endian, copied from the ECDSA chip so that they can be
+    // bound to the transaction table.
+    sig_r: [Column<Advice>; 32],
+    sig_s: [Column<Advice>; 32],
+    sig_r_rlc: Column<Advice>,
+    sig_s_rlc: Column<Advice>,
+    // Recovery id of the signature, which is the parity of the public key y
+    // coordinate, obtained from pk_y[0] = 2 * pk_y_byte0_hi + v.
+    v: Column<Advice>,
+    pk_y_byte0_hi: Column<Advice>,
+    u8_table: Column<Fixed>,
     power_of_randomness: [Expression<F>; POW_RAND_SIZE],
 
-    // [is_enabled, input_rlc, input_len, output_rlc]
-    keccak_table: [Column<Advice>; 4],
+    keccak_table: KeccakTable,
 }
 
 impl<F: FieldExt> SignVerifyConfig<F> {
+    /// Configure the SignVerifyChip.  The `keccak_table` is shared with the
+    /// parent circuit, and its digests are proven by the keccak circuit.
     pub(crate) fn new(
         meta: &mut ConstraintSystem<F>,
         power_of_randomness: [Expression<F>; POW_RAND_SIZE],
+        keccak_table: KeccakTable,
     ) -> Self {
         let q_enable = meta.complex_selector();
 
@@ -154,6 +189,20 @@ impl<F: FieldExt> SignVerifyConfig<F> {
         let msg_hash_rlc = meta.advice_column();
         meta.enable_equality(msg_hash_rlc);
 
+        let sig_r = [(); 32].map(|_| meta.advice_column());
+        sig_r.iter().for_each(|c| meta.enable_equality(*c));
+        let sig_s = [(); 32].map(|_| meta.advice_column());
+        sig_s.iter().for_each(|c| meta.enable_equality(*c));
+
+        let sig_r_rlc = meta.advice_column();
+        meta.enable_equality(sig_r_rlc);
+        let sig_s_rlc = meta.advice_column();
+        meta.enable_equality(sig_s_rlc);
+
+        let v = meta.advice_column();
+        let pk_y_byte0_hi = meta.advice_column();
+        let u8_table = meta.fixed_column();
+
         let address_inv = meta.advice_column();
         let address_is_zero = IsZeroChip::configure(
             meta,
@@ -164,9 +213,6 @@ impl<F: FieldExt> SignVerifyConfig<F> {
         // is_not_padding == address != 0
         let is_not_padding = not::expr(address_is_zero.is_zero_expression.clone());
 
-        // lookup keccak table
-        let keccak_table = [(); 4].map(|_| meta.advice_column());
-
         // Ref. spec SignVerifyChip 1. Verify that keccak(pub_key_bytes) = pub_key_hash
         // by keccak table lookup, where pub_key_bytes is built from the pub_key
         // in the ecdsa_chip
@@ -174,16 +220,10 @@ impl<F: FieldExt> SignVerifyConfig<F> {
         meta.lookup_any("keccak", |meta| {
             let q_enable = meta.query_selector(q_enable);
             let selector = q_enable * is_not_padding.clone();
-            let mut table_map = Vec::new();
-
-            // Column 0: is_enabled
-            let keccak_is_enabled =
-                meta.query_advice(keccak_table[KECCAK_IS_ENABLED], Rotation::cur());
-            table_map.push((selector.clone(), keccak_is_enabled));
+            let table = keccak_table.table_exprs(meta);
 
-            // Column 1: input_rlc (pk_rlc)
-            let keccak_input_rlc =
-                meta.query_advice(keccak_table[KECCAK_INPUT_RLC], Rotation::cur());
+            // input_rlc (pk_rlc).  The first byte of the input has the highest power of
+            // randomness.
             let pk_le: [Expression<F>; 64] = pk
                 .map(|coord| coord.map(|c| meta.query_advice(c, Rotation::cur())))
                 .iter()
@@ -192,25 +232,30 @@ impl<F: FieldExt> SignVerifyConfig<F> {
                 .collect::<Vec<Expression<F>>>()
                 .try_into()
                 .expect("vector to array of size 64");
-            let pk_be = pk_bytes_swap_endianness(&pk_le);
-            let pk_rlc =
-                RandomLinearCombination::random_linear_combine_expr(pk_be, &power_of_randomness);
-            table_map.push((selector.clone() * pk_rlc, keccak_input_rlc));
-
-            // Column 2: input_len (64)
-            let keccak_input_len =
-                meta.query_advice(keccak_table[KECCAK_INPUT_LEN], Rotation::cur());
-            table_map.push((selector.clone() * 64usize.expr(), keccak_input_len));
-
-            // Column 3: output_rlc (pk_hash_rlc)
-            let keccak_output_rlc =
-                meta.query_advice(keccak_table[KECCAK_OUTPUT_RLC], Rotation::cur());
-            let pk_hash = pk_hash.map(|c| meta.query_advice(c, Rotation::cur()));
-            let pk_hash_rlc =
-                RandomLinearCombination::random_linear_combine_expr(pk_hash, &power_of_randomness);
-            table_map.push((selector * pk_hash_rlc, keccak_output_rlc));
+            let mut pk_be_rev = pk_bytes_swap_endianness(&pk_le);
+            pk_be_rev.reverse();
+            let pk_rlc = RandomLinearCombination::random_linear_combine_expr(
+                pk_be_rev,
+                &power_of_randomness,
+            );
 
-            table_map
+            // output_rlc (pk_hash_rlc).  The output is encoded as a little endian word.
+            let mut pk_hash_le = pk_hash.map(|c| meta.query_advice(c, Rotation::cur()));
+            pk_hash_le.reverse();
+            let pk_hash_rlc = RandomLinearCombination::random_linear_combine_expr(
+                pk_hash_le,
+                &power_of_randomness,
+            );
+
+            [
+                selector.clone(),
+                selector.clone() * pk_rlc,
+                selector.clone() * 64usize.expr(),
+                selector * pk_hash_rlc,
+            ]
+            .into_iter()
+            .zip(table)
+            .collect()
         });
 
         // Ref. spec SignVerifyChip 2. Verify that the first 20 bytes of the
@@ -239,6 +284,52 @@ impl<F: FieldExt> SignVerifyConfig<F> {
             vec![q_enable * (msg_hash_rlc - is_not_padding.clone() * expected_msg_hash_rlc)]
         });
 
+        // Ref. spec SignVerifyChip 5. Verify that the signature (r, s) in the
+        // ecdsa_chip with RLC encoding corresponds to (sig_r_rlc, sig_s_rlc)
+        meta.create_gate(
+            "(sig_r_rlc, sig_s_rlc) = is_not_padding * (RLC(sig_r), RLC(sig_s))",
+            |meta| {
+                let q_enable = meta.query_selector(q_enable);
+                let sig_r = sig_r.map(|c| meta.query_advice(c, Rotation::cur()));
+                let sig_s = sig_s.map(|c| meta.query_advice(c, Rotation::cur()));
+                let sig_r_rlc = meta.query_advice(sig_r_rlc, Rotation::cur());
+                let sig_s_rlc = meta.query_advice(sig_s_rlc, Rotation::cur());
+
+                let expected_sig_r_rlc = RandomLinearCombination::random_linear_combine_expr(
+                    sig_r,
+                    &power_of_randomness[..32],
+                );
+                let expected_sig_s_rlc = RandomLinearCombination::random_linear_combine_expr(
+                    sig_s,
+                    &power_of_randomness[..32],
+                );
+                vec![
+                    q_enable.clone() * (sig_r_rlc - is_not_padding.clone() * expected_sig_r_rlc),
+                    q_enable * (sig_s_rlc - is_not_padding.clone() * expected_sig_s_rlc),
+                ]
+            },
+        );
+
+        // Ref. spec SignVerifyChip 6. Verify that the recovery id v is the parity of
+        // the public key y coordinate.  Since pk_y[0] is a byte, range checking
+        // pk_y_byte0_hi to 8 bits is enough to prevent overflows.
+        meta.create_gate("pk_y[0] = 2 * pk_y_byte0_hi + v", |meta| {
+            let q_enable = meta.query_selector(q_enable);
+            let pk_y_byte0 = meta.query_advice(pk[1][0], Rotation::cur());
+            let pk_y_byte0_hi = meta.query_advice(pk_y_byte0_hi, Rotation::cur());
+            let v = meta.query_advice(v, Rotation::cur());
+
+            vec![
+                q_enable.clone() * v.clone() * (1.expr() - v.clone()),
+                q_enable * (pk_y_byte0 - pk_y_byte0_hi * 2.expr() - v),
+            ]
+        });
+        meta.lookup("pk_y_byte0_hi in u8 table", |meta| {
+            let q_enable = meta.query_selector(q_enable);
+            let pk_y_byte0_hi = meta.query_advice(pk_y_byte0_hi, Rotation::cur());
+            vec![(q_enable * pk_y_byte0_hi, u8_table)]
+        });
+
         // ECDSA config
         let (rns_base, rns_scalar) =
             GeneralEccChip::<Secp256k1Affine, F, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::rns();
@@ -259,15 +350,39 @@ impl<F: FieldExt> SignVerifyConfig<F> {
             main_gate_config,
             pk,
             msg_hash,
+            sig_r,
+            sig_s,
+            sig_r_rlc,
+            sig_s_rlc,
+            v,
+            pk_y_byte0_hi,
+            u8_table,
             power_of_randomness,
             keccak_table,
         }
     }
-}
 
-pub(crate) struct KeccakAux {
-    input: [u8; 64],
-    output: [u8; 32],
+    /// Expressions of the signature table, with one row per verified
+    /// signature, to be looked up with `[is_valid, msg_hash_rlc, v, sig_r_rlc,
+    /// sig_s_rlc, address]`, where `v` is the recovery id and `address` is the
+    /// address recovered from the signature.  This table is shared by all
+    /// the consumers of the signature verification (the tx circuit and the
+    /// ecrecover precompile).
+    ///
+    /// NOTE: The ECDSA chip only supports valid signatures, so `is_valid` is
+    /// 1 for all the verified signatures.
+    pub(crate) fn sig_table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        let is_valid = meta.query_selector(self.q_enable)
+            * not::expr(self.address_is_zero.is_zero_expression.clone());
+        vec![
+            is_valid.clone(),
+            is_valid.clone() * meta.query_advice(self.msg_hash_rlc, Rotation::cur()),
+            is_valid.clone() * meta.query_advice(self.v, Rotation::cur()),
+            is_valid.clone() * meta.query_advice(self.sig_r_rlc, Rotation::cur()),
+            is_valid.clone() * meta.query_advice(self.sig_s_rlc, Rotation::cur()),
+            is_valid * meta.query_advice(self.address, Rotation::cur()),
+        ]
+    }
 }
 
 impl<F: FieldExt> SignVerifyConfig<F> {
@@ -277,67 +392,21 @@ impl<F: FieldExt> SignVerifyConfig<F> {
         range_chip.load_limb_range_table(layouter)?;
         range_chip.load_overflow_range_tables(layouter)?;
 
-        Ok(())
-    }
-
-    fn keccak_assign_row(
-        &self,
-        region: &mut Region<'_, F>,
-        offset: usize,
-        is_enabled: F,
-        input_rlc: F,
-        input_len: usize,
-        output_rlc: F,
-    ) -> Result<(), Error> {
-        for (name, column, value) in &[
-            ("is_enabled", self.keccak_table[0], is_enabled),
-            ("input_rlc", self.keccak_table[1], input_rlc),
-            ("input_len", self.keccak_table[2], F::from(input_len as u64)),
-            ("output_rlc", self.keccak_table[3], output_rlc),
-        ] {
-            region.assign_advice(
-                || format!("Keccak table assign {} {}", name, offset),
-                *column,
-                offset,
-                || Ok(*value),
-            )?;
-        }
-        Ok(())
-    }
-
-    pub(crate) fn load_keccak(
-        &self,
-        layouter: &mut impl Layouter<F>,
-        auxs: Vec<KeccakAux>,
-        randomness: F,
-    ) -> Result<(), Error> {
         layouter.assign_region(
-            || "keccak table",
+            || "sign verify u8 table",
             |mut region| {
-                let mut offset = 0;
-
-                // All zero row to allow simulating a disabled lookup.
-                self.keccak_assign_row(&mut region, offset, F::zero(), F::zero(), 0, F::zero())?;
-                offset += 1;
-
-                for aux in &auxs {
-                    let KeccakAux { input, output } = aux;
-                    let input_rlc =
-                        RandomLinearCombination::random_linear_combine(*input, randomness);
-                    let output_rlc = Word::random_linear_combine(*output, randomness);
-                    self.keccak_assign_row(
-                        &mut region,
-                        offset,
-                        F::one(),
-                        input_rlc,
-                        input.len(),
-                        output_rlc,
+                for value in 0..256 {
+                    region.assign_fixed(
+                        || "u8 table",
+                        self.u8_table,
+                        value,
+                        || Ok(F::from(value as u64)),
                     )?;
-                    offset += 1;
                 }
                 Ok(())
             },
         )?;
+
         Ok(())
     }
 
@@ -354,12 +423,16 @@ pub(crate) struct AssignedECDSA<F: FieldExt> {
     pk_x_le: [AssignedValue<F>; 32],
     pk_y_le: [AssignedValue<F>; 32],
     msg_hash_le: [AssignedValue<F>; 32],
+    sig_r_le: [AssignedValue<F>; 32],
+    sig_s_le: [AssignedValue<F>; 32],
 }
 
 #[derive(Debug)]
 pub(crate) struct AssignedSignatureVerify<F: FieldExt> {
     pub(crate) address: AssignedCell<F, F>,
     pub(crate) msg_hash_rlc: AssignedCell<F, F>,
+    pub(crate) sig_r_rlc: AssignedCell<F, F>,
+    pub(crate) sig_s_rlc: AssignedCell<F, F>,
 }
 
 // Returns assigned constants [256^1, 256^2, .., 256^{n-1}]
@@ -420,7 +493,7 @@ struct ChipsRef<'a, F: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIM
     ecdsa_chip: &'a EcdsaChip<Secp256k1Affine, F, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
 }
 
-impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
+impl<F: FieldExt> SignVerifyChip<F> {
     fn assign_aux(
         &self,
         region: &mut Region<'_, F>,
@@ -472,9 +545,11 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
         };
         let msg_hash = scalar_chip.assign_integer(ctx, msg_hash)?;
 
-        // Convert (msg_hash, pk_x, pk_y) integers to little endian bytes
+        // Convert (msg_hash, pk_x, pk_y, sig_r, sig_s) integers to little endian bytes
         let pows_256 = assign_pows_256(ctx, main_gate, 9)?;
         let msg_hash_le = integer_to_bytes_le(ctx, main_gate, range_chip, &pows_256, &msg_hash)?;
+        let sig_r_le = integer_to_bytes_le(ctx, main_gate, range_chip, &pows_256, &sig.r)?;
+        let sig_s_le = integer_to_bytes_le(ctx, main_gate, range_chip, &pows_256, &sig.s)?;
         let pk_x = pk_assigned.point.get_x();
         let pk_x_le = integer_to_bytes_le(ctx, main_gate, range_chip, &pows_256, &pk_x)?;
         let pk_y = pk_assigned.point.get_y();
@@ -491,6 +566,8 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
             pk_x_le,
             pk_y_le,
             msg_hash_le,
+            sig_r_le,
+            sig_s_le,
         })
     }
 
@@ -504,19 +581,20 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
         address_is_zero_chip: &IsZeroChip<F>,
         sign_data: Option<&SignData>,
         assigned_ecdsa: &AssignedECDSA<F>,
-    ) -> Result<(AssignedSignatureVerify<F>, KeccakAux), Error> {
+    ) -> Result<AssignedSignatureVerify<F>, Error> {
         let (padding, sign_data) = match sign_data {
             Some(sign_data) => (false, sign_data.clone()),
             None => (true, SignData::default()),
         };
         let SignData {
-            signature: _,
+            signature,
             pk,
             msg_hash,
         } = sign_data;
+        let (sig_r, sig_s) = signature;
 
-        // Ref. spec SignVerifyChip 0. Copy constraints between pub_key and msg_hash
-        // bytes of this chip and the ECDSA chip
+        // Ref. spec SignVerifyChip 0. Copy constraints between pub_key, msg_hash and
+        // signature bytes of this chip and the ECDSA chip
         copy_integer_bytes_le(
             region,
             "pk_x",
@@ -538,6 +616,20 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
             &config.msg_hash,
             offset,
         )?;
+        copy_integer_bytes_le(
+            region,
+            "sig_r",
+            &assigned_ecdsa.sig_r_le,
+            &config.sig_r,
+            offset,
+        )?;
+        copy_integer_bytes_le(
+            region,
+            "sig_s",
+            &assigned_ecdsa.sig_s_le,
+            &config.sig_s,
+            offset,
+        )?;
 
         config.q_enable.enable(region, offset)?;
 
@@ -555,19 +647,46 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
             || Ok(msg_hash_rlc),
         )?;
 
-        // Assign pk
-        let pk_coord =
-            Option::<Coordinates<_>>::from(pk.coordinates()).expect("point is the identity");
-        let mut pk_x_le = [0u8; 32];
-        let mut pk_y_le = [0u8; 32];
-        pk_coord
-            .x()
-            .write(&mut Cursor::new(&mut pk_x_le[..]))
+        // Assign sig_r_rlc and sig_s_rlc
+        let mut sig_r_le = [0u8; 32];
+        let mut sig_s_le = [0u8; 32];
+        sig_r
+            .write(&mut Cursor::new(&mut sig_r_le[..]))
             .expect("cannot write bytes to array");
-        pk_coord
-            .y()
-            .write(&mut Cursor::new(&mut pk_y_le[..]))
+        sig_s
+            .write(&mut Cursor::new(&mut sig_s_le[..]))
             .expect("cannot write bytes to array");
+        let (sig_r_rlc, sig_s_rlc) = if !padding {
+            (
+                Word::random_linear_combine(sig_r_le, randomness),
+                Word::random_linear_combine(sig_s_le, randomness),
+            )
+        } else {
+            (F::zero(), F::zero())
+        };
+        let sig_r_rlc_assigned =
+            region.assign_advice(|| "sig_r_rlc", config.sig_r_rlc, offset, || Ok(sig_r_rlc))?;
+        let sig_s_rlc_assigned =
+            region.assign_advice(|| "sig_s_rlc", config.sig_s_rlc, offset, || Ok(sig_s_rlc))?;
+
+        // Assign pk
+        let pk_be = pk_bytes_be(&pk);
+        let pk_le = pk_bytes_swap_endianness(&pk_be);
+        let (pk_x_le, pk_y_le) = pk_le.split_at(32);
+
+        // Assign the recovery id
+        region.assign_advice(
+            || "v",
+            config.v,
+            offset,
+            || Ok(F::from((pk_y_le[0] & 1) as u64)),
+        )?;
+        region.assign_advice(
+            || "pk_y_byte0_hi",
+            config.pk_y_byte0_hi,
+            offset,
+            || Ok(F::from((pk_y_le[0] >> 1) as u64)),
+        )?;
         for (i, byte) in pk_x_le.iter().enumerate() {
             region.assign_advice(
                 || format!("pk x byte {}", i),
@@ -585,10 +704,6 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
             )?;
         }
 
-        let mut pk_le = [0u8; 64];
-        pk_le[..32].copy_from_slice(&pk_x_le);
-        pk_le[32..].copy_from_slice(&pk_y_le);
-        let pk_be = pk_bytes_swap_endianness(&pk_le);
         let mut keccak = Keccak::default();
         keccak.update(&pk_be);
         let pk_hash = keccak.digest();
@@ -621,16 +736,21 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
             )?;
         }
 
-        Ok((
-            AssignedSignatureVerify {
-                address: address_assigned,
-                msg_hash_rlc: msg_hash_rlc_assigned,
-            },
-            KeccakAux {
-                input: pk_be,
-                output: pk_hash.try_into().expect("vec to array of size 32"),
-            },
-        ))
+        Ok(AssignedSignatureVerify {
+            address: address_assigned,
+            msg_hash_rlc: msg_hash_rlc_assigned,
+            sig_r_rlc: sig_r_rlc_assigned,
+            sig_s_rlc: sig_s_rlc_assigned,
+        })
+    }
+
+    /// Return the inputs to the keccak table required to verify the public
+    /// key hashes of the `signatures`.
+    pub(crate) fn keccak_inputs(signatures: &[SignData]) -> Vec<Vec<u8>> {
+        signatures
+            .iter()
+            .map(|sign_data| pk_bytes_be(&sign_data.pk).to_vec())
+            .collect()
     }
 
     pub(crate) fn assign(
@@ -640,11 +760,11 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
         randomness: F,
         signatures: &[SignData],
     ) -> Result<Vec<AssignedSignatureVerify<F>>, Error> {
-        if signatures.len() > MAX_VERIF {
+        if signatures.len() > self.max_verif {
             error!(
-                "signatures.len() = {} > MAX_VERIF = {}",
+                "signatures.len() = {} > max_verif = {}",
                 signatures.len(),
-                MAX_VERIF
+                self.max_verif
             );
             return Err(Error::Synthesis);
         }
@@ -666,7 +786,6 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
         let address_is_zero_chip = IsZeroChip::construct(config.address_is_zero.clone());
 
         let mut assigned_ecdsas = Vec::new();
-        let mut keccak_auxs = Vec::new();
 
         let chips = ChipsRef {
             main_gate: &main_gate,
@@ -680,10 +799,9 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
             || "ecdsa chip verification",
             |mut region| {
                 assigned_ecdsas.clear();
-                keccak_auxs.clear();
                 let offset = &mut 0;
                 let mut ctx = RegionCtx::new(&mut region, offset);
-                for i in 0..MAX_VERIF {
+                for i in 0..self.max_verif {
                     let signature = if i < signatures.len() {
                         signatures[i].clone()
                     } else {
@@ -702,10 +820,10 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
             || "signature address verify",
             |mut region| {
                 assigned_sig_verifs.clear();
-                // for i in 0..MAX_VERIF
+                // for i in 0..max_verif
                 for (i, assigned_ecdsa) in assigned_ecdsas.iter().enumerate() {
                     let sign_data = signatures.get(i); // None when padding (enabled when address == 0)
-                    let (assigned_sig_verif, keccak_aux) = self.assign_signature_verify(
+                    let assigned_sig_verif = self.assign_signature_verify(
                         config,
                         &mut region,
                         i, // offset
@@ -714,9 +832,6 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
                         sign_data,
                         assigned_ecdsa,
                     )?;
-                    if i < signatures.len() {
-                        keccak_auxs.push(keccak_aux);
-                    }
                     assigned_sig_verifs.push(assigned_sig_verif);
                 }
 
@@ -724,7 +839,6 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
             },
         )?;
 
-        config.load_keccak(layouter, keccak_auxs, randomness)?;
         config.load_range(layouter)?;
 
         Ok(assigned_sig_verifs)
@@ -812,6 +926,7 @@ mod sign_verify_tests {
     #[derive(Clone, Debug)]
     struct TestCircuitSignVerifyConfig<F: FieldExt> {
         sign_verify: SignVerifyConfig<F>,
+        keccak_table: KeccakTable,
     }
 
     impl<F: FieldExt> TestCircuitSignVerifyConfig<F> {
@@ -834,19 +949,23 @@ mod sign_verify_tests {
                 power_of_randomness.unwrap()
             };
 
-            let sign_verify = SignVerifyConfig::new(meta, power_of_randomness);
-            TestCircuitSignVerifyConfig { sign_verify }
+            let keccak_table = KeccakTable::construct(meta);
+            let sign_verify = SignVerifyConfig::new(meta, power_of_randomness, keccak_table);
+            TestCircuitSignVerifyConfig {
+                sign_verify,
+                keccak_table,
+            }
         }
     }
 
     #[derive(Default)]
-    struct TestCircuitSignVerify<F: FieldExt, const MAX_VERIF: usize> {
-        sign_verify: SignVerifyChip<F, MAX_VERIF>,
+    struct TestCircuitSignVerify<F: FieldExt> {
+        sign_verify: SignVerifyChip<F>,
         randomness: F,
         signatures: Vec<SignData>,
     }
 
-    impl<F: FieldExt, const MAX_VERIF: usize> Circuit<F> for TestCircuitSignVerify<F, MAX_VERIF> {
+    impl<F: FieldExt> Circuit<F> for TestCircuitSignVerify<F> {
         type Config = TestCircuitSignVerifyConfig<F>;
         type FloorPlanner = SimpleFloorPlanner;
 
@@ -869,6 +988,11 @@ mod sign_verify_tests {
                 self.randomness,
                 &self.signatures,
             )?;
+            config.keccak_table.dev_load(
+                &mut layouter,
+                &SignVerifyChip::<F>::keccak_inputs(&self.signatures),
+                self.randomness,
+            )?;
             Ok(())
         }
     }
@@ -886,10 +1010,11 @@ mod sign_verify_tests {
             .collect();
         // SignVerifyChip -> ECDSAChip -> MainGate instance column
         power_of_randomness.push(vec![]);
-        let circuit = TestCircuitSignVerify::<F, MAX_VERIF> {
+        let circuit = TestCircuitSignVerify::<F> {
             sign_verify: SignVerifyChip {
                 aux_generator,
                 window_size: 2,
+                max_verif: MAX_VERIF,
                 _marker: PhantomData,
             },
             randomness,
@@ -0,0 +1,66 @@
+//! Golden tests of the witnesses assigned by the circuits for small fixed
+//! blocks, compared with the snapshots checked in `snapshots/`, so that the
+//! refactors changing the row layouts or the contents of the tables show up
+//! in review.  The snapshots are rewritten with
+//! `UPDATE_SNAPSHOTS=1 cargo test witness_snapshot`.
+
+use crate::{
+    evm_circuit::{
+        test::{test_circuit_instance, test_circuit_k, TestCircuit},
+        witness::Block,
+    },
+    state_circuit::StateCircuit,
+    test_util::witness_block,
+};
+use eth_types::bytecode;
+use gadgets::snapshot::{assert_snapshot, witness_snapshot};
+use halo2_proofs::pairing::bn256::Fr;
+use mock::TestContext;
+use std::path::PathBuf;
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("snapshots")
+        .join(format!("{}.txt", name))
+}
+
+/// Witness block of a transaction adding two numbers, with a fixed
+/// randomness for the witness to be deterministic.
+fn add_block() -> Block<Fr> {
+    let mut block = witness_block(
+        TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode! {
+            PUSH1(0x12)
+            PUSH1(0x34)
+            ADD
+            POP
+            STOP
+        })
+        .unwrap(),
+    );
+    block.randomness = Fr::from(0x100);
+    block
+}
+
+#[test]
+fn evm_circuit_witness_snapshot() {
+    let block = add_block();
+    let k = test_circuit_k(&block, &[]);
+    let instance = test_circuit_instance(&block, k);
+    let circuit = TestCircuit::new(block, vec![]);
+
+    let snapshot = witness_snapshot(k, &circuit, instance, |region| {
+        ["Execution step", "rw table", "bytecode table"].contains(&region)
+    });
+    assert_snapshot(snapshot_path("evm_circuit_add"), &snapshot);
+}
+
+#[test]
+fn state_circuit_witness_snapshot() {
+    let block = add_block();
+    let n_rows = block.rws.0.values().map(Vec::len).sum::<usize>() + 1;
+    let circuit = StateCircuit::new(block.randomness, block.rws, n_rows);
+    let instance = circuit.instance();
+
+    let snapshot = witness_snapshot(17, &circuit, instance, |region| region == "rw table");
+    assert_snapshot(snapshot_path("state_circuit_add"), &snapshot);
+}
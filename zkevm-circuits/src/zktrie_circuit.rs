@@ -0,0 +1,1353 @@
+//! The zkTrie circuit implements constraints for the updates of the zkTries of
+//! the state, the sparse binary Merkle tries hashed with Poseidon replacing the
+//! Merkle Patricia Tries for the L2s proving their state with a zk-friendly
+//! hash.  It proves the same updates as the MPT circuit into the same
+//! [`MptTable`], so that the state circuit looks them up unchanged.
+//!
+//! Each update is laid out as the path of its key in the storage trie (for a
+//! storage update) followed by its path in the account trie, where every path
+//! takes one row per bit of the key, starting from the least significant bit:
+//!   - a branch node takes the row of the bit selecting its child, and holds
+//!     the sibling of the child,
+//!   - the leaf at the end of the path takes the rows of the remaining bits of
+//!     the key.
+//!
+//! Every row holds the hashes of its node before and after the update.  The
+//! hash of a branch is the hash of its children, one of them being the node of
+//! the next row and the other one the sibling, which is the same before and
+//! after the update.  The hash of a leaf is the hash of the hash of its key
+//! (prefixed by 1) with the hash of its value, and a missing leaf (for an
+//! insertion or a deletion) is the empty node of hash 0.
+//!
+//! The key of an account is the hash of its address, and the key of a storage
+//! slot is the hash of the 128-bit halves of the storage key.  The hash of a
+//! storage value is the hash of its halves, and the hash of an account is made
+//! of the hashes of the pairs of its fields: the nonce with the hash of the
+//! balance, and the storage root with the hash of the code hash.  The bits of
+//! the key are constrained to be its canonical bits, being at most the bits of
+//! the modulus minus one.
+//!
+//! Every hash is looked up in the [`PoseidonTable`], constrained by the
+//! Poseidon circuit.
+
+mod trie;
+mod witness;
+
+pub use trie::ZkTrie;
+pub use witness::{
+    zktrie_account_key, zktrie_storage_key, zktrie_storage_value_hash, ZkTrieAccount, ZkTriePath,
+    ZkTrieProof, ZkTrieRow, ZkTrieUpdate, ZKTRIE_KEY_BITS,
+};
+
+use eth_types::{Field, ToLittleEndian, Word};
+use gadgets::{
+    binary_number::{BinaryNumberChip, BinaryNumberConfig},
+    util::{expr_from_bytes, not, sum, Expr},
+};
+use halo2_proofs::{
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{
+    evm_circuit::{
+        table::LookupTable,
+        util::{constraint_builder::BaseConstraintBuilder, pow_of_two, rlc},
+    },
+    mpt_circuit::{MptProofType, ACCOUNT_FIELDS},
+    table::{MptTable, PoseidonTable},
+};
+use witness::key_bits;
+
+/// The zkTrie circuit, which constrains the paths of every trie update.
+#[derive(Clone, Debug)]
+pub struct ZkTrieCircuit<F> {
+    /// Whether the row belongs to a path.
+    pub q_enable: Column<Fixed>,
+    /// Whether the row is the first one of a path, at the root.
+    pub q_first: Column<Fixed>,
+    /// Whether the row is the last one of a path.
+    pub q_last: Column<Fixed>,
+    /// Whether the row holds a word: the storage key at the first row, the
+    /// value after the update at the row before the last one, and the value
+    /// before the update at the last row.
+    pub q_word: Column<Fixed>,
+    /// The bit of the modulus minus one at the depth of the row.
+    pub modulus_bit: Column<Fixed>,
+    /// The multiplier of the bit of the key, which is 2^i at depth i.
+    pub bit_mult: Column<Fixed>,
+    /// The table of the updates, holding the address, the storage key, the
+    /// state roots and the values of the path on every row.
+    pub mpt_table: MptTable,
+    /// The poseidon table, holding the hashes of the nodes, the keys and the
+    /// values.
+    pub poseidon_table: PoseidonTable,
+    /// The type of the update the path belongs to.
+    pub proof_type: BinaryNumberConfig<MptProofType, 3>,
+    /// Whether the path is in a storage trie.
+    pub is_storage: Column<Advice>,
+    /// The root of the trie before the update.
+    pub old_root: Column<Advice>,
+    /// The root of the trie after the update.
+    pub new_root: Column<Advice>,
+    /// The key of the path.
+    pub key: Column<Advice>,
+    /// The hash of the leaf prefix with the key.
+    pub key_hash: Column<Advice>,
+    /// Whether the key has no value before the update.
+    pub is_old_empty: Column<Advice>,
+    /// Whether the key has no value after the update.
+    pub is_new_empty: Column<Advice>,
+    /// The hash of the value before the update.
+    pub old_value_hash: Column<Advice>,
+    /// The hash of the value after the update.
+    pub new_value_hash: Column<Advice>,
+    /// Whether the path is an account path modifying each field of the
+    /// account, in the order of the fields of the account.
+    pub is_account_field: [Column<Advice>; 4],
+    /// The fields of the account before the update: the nonce, the hash of
+    /// the balance, the storage root and the hash of the code hash.
+    pub old_fields: [Column<Advice>; 4],
+    /// The fields of the account after the update.
+    pub new_fields: [Column<Advice>; 4],
+    /// The hashes of the pairs of fields of the account before the update.
+    pub old_field_hashes: [Column<Advice>; 2],
+    /// The hashes of the pairs of fields of the account after the update.
+    pub new_field_hashes: [Column<Advice>; 2],
+    /// The bit of the key at the depth of the row.
+    pub bit: Column<Advice>,
+    /// The sum of the key bits up to the depth of the row.
+    pub key_acc: Column<Advice>,
+    /// Whether the key bits up to the depth of the row are at most the bits
+    /// of the modulus minus one.
+    pub is_key_canonical: Column<Advice>,
+    /// Whether the node of the row is a branch, or otherwise the leaf at the
+    /// end of the path.
+    pub is_branch: Column<Advice>,
+    /// The sibling of the child of the branch selected by the bit.
+    pub sibling: Column<Advice>,
+    /// The hash of the node before the update.
+    pub old_hash: Column<Advice>,
+    /// The hash of the node after the update.
+    pub new_hash: Column<Advice>,
+    /// The little endian bytes of the word of the row.
+    pub word_bytes: [Column<Advice>; 32],
+    /// Fixed table of the values 0..256 to range check the word bytes.
+    pub u8_table: Column<Fixed>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> ZkTrieCircuit<F> {
+    /// Configure the zkTrie circuit constraining the paths of the trie
+    /// updates.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        mpt_table: MptTable,
+        poseidon_table: PoseidonTable,
+        power_of_randomness: [Expression<F>; 31],
+    ) -> Self {
+        let MptTable {
+            address,
+            storage_key,
+            old_root: old_state_root,
+            new_root: new_state_root,
+            old_value,
+            new_value,
+            ..
+        } = mpt_table;
+
+        let q_enable = meta.fixed_column();
+        let q_first = meta.fixed_column();
+        let q_last = meta.fixed_column();
+        let q_word = meta.fixed_column();
+        let modulus_bit = meta.fixed_column();
+        let bit_mult = meta.fixed_column();
+        let proof_type = BinaryNumberChip::configure(meta, q_enable);
+        let is_storage = meta.advice_column();
+        let old_root = meta.advice_column();
+        let new_root = meta.advice_column();
+        let key = meta.advice_column();
+        let key_hash = meta.advice_column();
+        let is_old_empty = meta.advice_column();
+        let is_new_empty = meta.advice_column();
+        let old_value_hash = meta.advice_column();
+        let new_value_hash = meta.advice_column();
+        let is_account_field = [(); 4].map(|_| meta.advice_column());
+        let old_fields = [(); 4].map(|_| meta.advice_column());
+        let new_fields = [(); 4].map(|_| meta.advice_column());
+        let old_field_hashes = [(); 2].map(|_| meta.advice_column());
+        let new_field_hashes = [(); 2].map(|_| meta.advice_column());
+        let bit = meta.advice_column();
+        let key_acc = meta.advice_column();
+        let is_key_canonical = meta.advice_column();
+        let is_branch = meta.advice_column();
+        let sibling = meta.advice_column();
+        let old_hash = meta.advice_column();
+        let new_hash = meta.advice_column();
+        let word_bytes = [(); 32].map(|_| meta.advice_column());
+        let u8_table = meta.fixed_column();
+
+        // The word of a row, as its RLC and its low and high 128 bits.
+        let word = |meta: &mut VirtualCells<F>, rotation: Rotation| {
+            let bytes = word_bytes.map(|column| meta.query_advice(column, rotation));
+            (
+                rlc::expr(&bytes, &power_of_randomness),
+                expr_from_bytes(&bytes[..16]),
+                expr_from_bytes(&bytes[16..]),
+            )
+        };
+
+        meta.create_gate("zktrie path values and key", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+            let is_storage_expr = meta.query_advice(is_storage, Rotation::cur());
+            let is_old_empty_expr = meta.query_advice(is_old_empty, Rotation::cur());
+            let is_new_empty_expr = meta.query_advice(is_new_empty, Rotation::cur());
+            let is_storage_mod =
+                proof_type.value_equals(MptProofType::StorageMod, Rotation::cur())(meta);
+
+            cb.require_boolean("is_storage is boolean", is_storage_expr.clone());
+            cb.require_boolean("is_old_empty is boolean", is_old_empty_expr.clone());
+            cb.require_boolean("is_new_empty is boolean", is_new_empty_expr.clone());
+            cb.require_zero(
+                "key has a value before or after the update",
+                is_old_empty_expr * is_new_empty_expr,
+            );
+
+            cb.require_equal(
+                "proof_type in the mpt table is the proof type of the path",
+                meta.query_advice(mpt_table.proof_type, Rotation::cur()),
+                proof_type.value(Rotation::cur())(meta),
+            );
+            cb.require_zero(
+                "storage_key is 0 for an account update",
+                not::expr(is_storage_mod.clone()) * meta.query_advice(storage_key, Rotation::cur()),
+            );
+
+            // The update is in the table at the first row of its first path,
+            // which is its storage path for a storage update.
+            cb.require_equal(
+                "update is enabled in the mpt table at the first row of its first path",
+                meta.query_fixed(mpt_table.q_enable, Rotation::cur()),
+                q_first.clone() * (1.expr() - not::expr(is_storage_expr.clone()) * is_storage_mod),
+            );
+
+            for (column, field) in is_account_field.iter().zip(ACCOUNT_FIELDS) {
+                cb.require_equal(
+                    "is_account_field is the field modified by an account path",
+                    meta.query_advice(*column, Rotation::cur()),
+                    not::expr(is_storage_expr.clone())
+                        * proof_type.value_equals(field, Rotation::cur())(meta),
+                );
+            }
+
+            cb.condition(is_storage_expr.clone(), |cb| {
+                cb.require_equal(
+                    "storage path belongs to a storage update",
+                    proof_type.value(Rotation::cur())(meta),
+                    MptProofType::StorageMod.expr(),
+                );
+            });
+            cb.condition(not::expr(is_storage_expr), |cb| {
+                cb.require_equal(
+                    "old_state_root == old_root for an account path",
+                    meta.query_advice(old_state_root, Rotation::cur()),
+                    meta.query_advice(old_root, Rotation::cur()),
+                );
+                cb.require_equal(
+                    "new_state_root == new_root for an account path",
+                    meta.query_advice(new_state_root, Rotation::cur()),
+                    meta.query_advice(new_root, Rotation::cur()),
+                );
+            });
+
+            cb.condition(not::expr(q_last.clone()), |cb| {
+                cb.require_equal(
+                    "proof_type_next == proof_type",
+                    proof_type.value(Rotation::next())(meta),
+                    proof_type.value(Rotation::cur())(meta),
+                );
+                for column in [
+                    is_storage,
+                    address,
+                    storage_key,
+                    old_root,
+                    new_root,
+                    old_state_root,
+                    new_state_root,
+                    key,
+                    old_value,
+                    new_value,
+                    is_old_empty,
+                    is_new_empty,
+                ] {
+                    cb.require_equal(
+                        "path value is the same for all rows of the path",
+                        meta.query_advice(column, Rotation::next()),
+                        meta.query_advice(column, Rotation::cur()),
+                    );
+                }
+            });
+
+            // key_acc accumulates the bits of the key, starting from the least
+            // significant bit.
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let key_acc_expr = meta.query_advice(key_acc, Rotation::cur());
+            let bit_mult = meta.query_fixed(bit_mult, Rotation::cur());
+            cb.require_boolean("bit is boolean", bit.clone());
+            cb.condition(q_first.clone(), |cb| {
+                cb.require_equal(
+                    "key_acc == bit at the root",
+                    key_acc_expr.clone(),
+                    bit.clone(),
+                );
+            });
+            cb.condition(not::expr(q_first.clone()), |cb| {
+                cb.require_equal(
+                    "key_acc == key_acc_prev + bit * bit_mult",
+                    key_acc_expr.clone(),
+                    meta.query_advice(key_acc, Rotation::prev()) + bit.clone() * bit_mult,
+                );
+            });
+
+            // The key bits up to a row are at most the bits of the modulus
+            // minus one when the bit of the row is lower than the bit of the
+            // modulus, or when they are equal and the bits below are at most
+            // the bits of the modulus.
+            let modulus_bit = meta.query_fixed(modulus_bit, Rotation::cur());
+            let is_key_canonical_expr = meta.query_advice(is_key_canonical, Rotation::cur());
+            let is_key_canonical_prev = q_first.clone()
+                + not::expr(q_first.clone())
+                    * meta.query_advice(is_key_canonical, Rotation::prev());
+            cb.require_equal(
+                "is_key_canonical compares the key bits to the bits of the modulus minus one",
+                is_key_canonical_expr.clone(),
+                modulus_bit.clone()
+                    * (not::expr(bit.clone()) + bit.clone() * is_key_canonical_prev.clone())
+                    + not::expr(modulus_bit) * not::expr(bit) * is_key_canonical_prev,
+            );
+            cb.condition(q_last, |cb| {
+                cb.require_equal(
+                    "key == key_acc at the last bit",
+                    meta.query_advice(key, Rotation::cur()),
+                    key_acc_expr,
+                );
+                cb.require_equal(
+                    "key bits are the canonical bits of the key",
+                    is_key_canonical_expr,
+                    1.expr(),
+                );
+            });
+
+            cb.condition(q_first, |cb| {
+                cb.require_equal(
+                    "old_root is the hash of the root node before the update",
+                    meta.query_advice(old_root, Rotation::cur()),
+                    meta.query_advice(old_hash, Rotation::cur()),
+                );
+                cb.require_equal(
+                    "new_root is the hash of the root node after the update",
+                    meta.query_advice(new_root, Rotation::cur()),
+                    meta.query_advice(new_hash, Rotation::cur()),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("zktrie nodes", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+            let is_branch_expr = meta.query_advice(is_branch, Rotation::cur());
+
+            cb.require_boolean("is_branch is boolean", is_branch_expr.clone());
+            cb.condition(not::expr(q_last.clone()), |cb| {
+                cb.require_zero(
+                    "a leaf is followed by the same leaf",
+                    not::expr(is_branch_expr.clone())
+                        * meta.query_advice(is_branch, Rotation::next()),
+                );
+                cb.condition(not::expr(is_branch_expr.clone()), |cb| {
+                    for hash in [old_hash, new_hash] {
+                        cb.require_equal(
+                            "rows of a leaf hold the hash of the leaf",
+                            meta.query_advice(hash, Rotation::next()),
+                            meta.query_advice(hash, Rotation::cur()),
+                        );
+                    }
+                });
+            });
+
+            cb.condition(q_last, |cb| {
+                cb.require_zero("path ends with a leaf", is_branch_expr);
+                for (is_empty, hash, value, fields) in [
+                    (is_old_empty, old_hash, old_value, old_fields),
+                    (is_new_empty, new_hash, new_value, new_fields),
+                ] {
+                    cb.condition(meta.query_advice(is_empty, Rotation::cur()), |cb| {
+                        cb.require_zero(
+                            "missing leaf is the empty node of hash 0",
+                            meta.query_advice(hash, Rotation::cur()),
+                        );
+                        cb.require_zero(
+                            "missing value is 0",
+                            meta.query_advice(value, Rotation::cur()),
+                        );
+                        for field in fields {
+                            cb.require_zero(
+                                "fields of a missing account are 0",
+                                meta.query_advice(field, Rotation::cur()),
+                            );
+                        }
+                    });
+                }
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("zktrie leaf values", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+            let is_storage_expr = meta.query_advice(is_storage, Rotation::cur());
+            let is_account_field =
+                is_account_field.map(|column| meta.query_advice(column, Rotation::cur()));
+
+            cb.condition(q_first * is_storage_expr.clone(), |cb| {
+                cb.require_equal(
+                    "storage_key is the RLC of the word of the first row",
+                    meta.query_advice(storage_key, Rotation::cur()),
+                    word(meta, Rotation::cur()).0,
+                );
+            });
+
+            // The storage value, the balance and the code hash are the words
+            // of the last two rows, and the nonce and the storage root are
+            // fields of the account.
+            let is_word_value = sum::expr([
+                is_storage_expr.clone(),
+                is_account_field[1].clone(),
+                is_account_field[3].clone(),
+            ]);
+            cb.condition(q_last.clone() * is_word_value, |cb| {
+                cb.require_equal(
+                    "old_value is the RLC of the word of the last row",
+                    meta.query_advice(old_value, Rotation::cur()),
+                    word(meta, Rotation::cur()).0,
+                );
+                cb.require_equal(
+                    "new_value is the RLC of the word of the row before the last one",
+                    meta.query_advice(new_value, Rotation::cur()),
+                    word(meta, Rotation::prev()).0,
+                );
+            });
+            for idx in [0, 2] {
+                cb.condition(q_last.clone() * is_account_field[idx].clone(), |cb| {
+                    for (value, fields) in [(old_value, old_fields), (new_value, new_fields)] {
+                        cb.require_equal(
+                            "modified field is the value of the update",
+                            meta.query_advice(fields[idx], Rotation::cur()),
+                            meta.query_advice(value, Rotation::cur()),
+                        );
+                    }
+                });
+            }
+            for (idx, is_account_field) in is_account_field.iter().enumerate() {
+                cb.condition(
+                    q_last.clone()
+                        * not::expr(is_storage_expr.clone())
+                        * not::expr(is_account_field.clone()),
+                    |cb| {
+                        cb.require_equal(
+                            "fields not modified by the update are the same",
+                            meta.query_advice(new_fields[idx], Rotation::cur()),
+                            meta.query_advice(old_fields[idx], Rotation::cur()),
+                        );
+                    },
+                );
+            }
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("zktrie update chaining", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+            let is_storage_expr = meta.query_advice(is_storage, Rotation::cur());
+            let is_storage_mod =
+                proof_type.value_equals(MptProofType::StorageMod, Rotation::cur())(meta);
+
+            // The storage path is followed by the path of its account, holding
+            // the storage roots as values.
+            cb.condition(q_last.clone() * is_storage_expr.clone(), |cb| {
+                cb.require_equal(
+                    "storage path is followed by a path",
+                    meta.query_fixed(q_enable, Rotation::next()),
+                    1.expr(),
+                );
+                cb.require_zero(
+                    "storage path is followed by an account path",
+                    meta.query_advice(is_storage, Rotation::next()),
+                );
+                cb.require_equal(
+                    "account path of a storage path is a storage update",
+                    proof_type.value(Rotation::next())(meta),
+                    MptProofType::StorageMod.expr(),
+                );
+                for (value, root) in [(old_value, old_root), (new_value, new_root)] {
+                    cb.require_equal(
+                        "account value is the storage root",
+                        meta.query_advice(value, Rotation::next()),
+                        meta.query_advice(root, Rotation::cur()),
+                    );
+                }
+                for column in [address, storage_key, old_state_root, new_state_root] {
+                    cb.require_equal(
+                        "account path has the keys and the state roots of the storage path",
+                        meta.query_advice(column, Rotation::next()),
+                        meta.query_advice(column, Rotation::cur()),
+                    );
+                }
+            });
+            cb.condition(
+                q_first * not::expr(is_storage_expr.clone()) * is_storage_mod,
+                |cb| {
+                    cb.require_equal(
+                        "account path of a storage update follows a path",
+                        meta.query_fixed(q_enable, Rotation::prev()),
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "account path of a storage update follows a storage path",
+                        meta.query_advice(is_storage, Rotation::prev()),
+                        1.expr(),
+                    );
+                },
+            );
+
+            // The new state root of an update is the old one of the next update.
+            cb.condition(
+                q_last * not::expr(is_storage_expr) * meta.query_fixed(q_enable, Rotation::next()),
+                |cb| {
+                    cb.require_equal(
+                        "old_state_root_next == new_state_root",
+                        meta.query_advice(old_state_root, Rotation::next()),
+                        meta.query_advice(new_state_root, Rotation::cur()),
+                    );
+                },
+            );
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        for byte in word_bytes {
+            meta.lookup_any("word byte fits into u8", |meta| {
+                let q_word = meta.query_fixed(q_word, Rotation::cur());
+                let byte = meta.query_advice(byte, Rotation::cur());
+                let u8_table = meta.query_fixed(u8_table, Rotation::cur());
+                vec![(q_word * byte, u8_table)]
+            });
+        }
+
+        // A branch is the hash of its children, ordered by the bit selecting
+        // the node of the next row.
+        for hash in [old_hash, new_hash] {
+            lookup_poseidon(meta, "zktrie branch hash", poseidon_table, |meta| {
+                let bit = meta.query_advice(bit, Rotation::cur());
+                let sibling = meta.query_advice(sibling, Rotation::cur());
+                let child = meta.query_advice(hash, Rotation::next());
+                (
+                    meta.query_fixed(q_enable, Rotation::cur())
+                        * meta.query_advice(is_branch, Rotation::cur()),
+                    [
+                        child.clone() + bit.clone() * (sibling.clone() - child.clone()),
+                        sibling.clone() + bit * (child - sibling),
+                        meta.query_advice(hash, Rotation::cur()),
+                    ],
+                )
+            });
+        }
+
+        lookup_poseidon(meta, "zktrie account key hash", poseidon_table, |meta| {
+            (
+                meta.query_fixed(q_first, Rotation::cur())
+                    * not::expr(meta.query_advice(is_storage, Rotation::cur())),
+                [
+                    meta.query_advice(address, Rotation::cur()),
+                    0.expr(),
+                    meta.query_advice(key, Rotation::cur()),
+                ],
+            )
+        });
+        lookup_poseidon(meta, "zktrie storage key hash", poseidon_table, |meta| {
+            let (_, lo, hi) = word(meta, Rotation::cur());
+            (
+                meta.query_fixed(q_first, Rotation::cur())
+                    * meta.query_advice(is_storage, Rotation::cur()),
+                [lo, hi, meta.query_advice(key, Rotation::cur())],
+            )
+        });
+        lookup_poseidon(meta, "zktrie leaf key hash", poseidon_table, |meta| {
+            (
+                meta.query_fixed(q_last, Rotation::cur()),
+                [
+                    1.expr(),
+                    meta.query_advice(key, Rotation::cur()),
+                    meta.query_advice(key_hash, Rotation::cur()),
+                ],
+            )
+        });
+
+        for (is_empty, hash, value_hash, fields, field_hashes, rotation) in [
+            (
+                is_old_empty,
+                old_hash,
+                old_value_hash,
+                old_fields,
+                old_field_hashes,
+                Rotation::cur(),
+            ),
+            (
+                is_new_empty,
+                new_hash,
+                new_value_hash,
+                new_fields,
+                new_field_hashes,
+                Rotation::prev(),
+            ),
+        ] {
+            let is_leaf = move |meta: &mut VirtualCells<F>| {
+                meta.query_fixed(q_last, Rotation::cur())
+                    * not::expr(meta.query_advice(is_empty, Rotation::cur()))
+            };
+            lookup_poseidon(meta, "zktrie leaf hash", poseidon_table, |meta| {
+                (
+                    is_leaf(meta),
+                    [
+                        meta.query_advice(key_hash, Rotation::cur()),
+                        meta.query_advice(value_hash, Rotation::cur()),
+                        meta.query_advice(hash, Rotation::cur()),
+                    ],
+                )
+            });
+            lookup_poseidon(meta, "zktrie storage value hash", poseidon_table, |meta| {
+                let (_, lo, hi) = word(meta, rotation);
+                (
+                    is_leaf(meta) * meta.query_advice(is_storage, Rotation::cur()),
+                    [lo, hi, meta.query_advice(value_hash, Rotation::cur())],
+                )
+            });
+            for (inputs, output) in [
+                ([fields[0], fields[1]], field_hashes[0]),
+                ([fields[2], fields[3]], field_hashes[1]),
+                (field_hashes, value_hash),
+            ] {
+                lookup_poseidon(meta, "zktrie account hash", poseidon_table, |meta| {
+                    (
+                        is_leaf(meta) * not::expr(meta.query_advice(is_storage, Rotation::cur())),
+                        [
+                            meta.query_advice(inputs[0], Rotation::cur()),
+                            meta.query_advice(inputs[1], Rotation::cur()),
+                            meta.query_advice(output, Rotation::cur()),
+                        ],
+                    )
+                });
+            }
+            // The modified balance or code hash is the word hashed into its
+            // field.
+            for idx in [1, 3] {
+                lookup_poseidon(meta, "zktrie account field hash", poseidon_table, |meta| {
+                    let (_, lo, hi) = word(meta, rotation);
+                    (
+                        is_leaf(meta) * meta.query_advice(is_account_field[idx], Rotation::cur()),
+                        [lo, hi, meta.query_advice(fields[idx], Rotation::cur())],
+                    )
+                });
+            }
+        }
+
+        Self {
+            q_enable,
+            q_first,
+            q_last,
+            q_word,
+            modulus_bit,
+            bit_mult,
+            mpt_table,
+            poseidon_table,
+            proof_type,
+            is_storage,
+            old_root,
+            new_root,
+            key,
+            key_hash,
+            is_old_empty,
+            is_new_empty,
+            old_value_hash,
+            new_value_hash,
+            is_account_field,
+            old_fields,
+            new_fields,
+            old_field_hashes,
+            new_field_hashes,
+            bit,
+            key_acc,
+            is_key_canonical,
+            is_branch,
+            sibling,
+            old_hash,
+            new_hash,
+            word_bytes,
+            u8_table,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Load the fixed table of the values 0..256.
+    pub fn load_u8_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "zktrie circuit u8 table",
+            |mut region| {
+                for value in 0..256 {
+                    region.assign_fixed(
+                        || "u8 table",
+                        self.u8_table,
+                        value,
+                        || Ok(F::from(value as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the rows of every path of the trie updates.
+    pub fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        paths: &[ZkTriePath<F>],
+    ) -> Result<(), Error> {
+        let proof_type_chip = BinaryNumberChip::construct(self.proof_type);
+        let modulus_bits = key_bits(&-F::one());
+
+        layouter.assign_region(
+            || "assign zktrie paths",
+            |mut region| {
+                let mut offset = 0;
+                for path in paths.iter() {
+                    for (depth, row) in path.rows.iter().enumerate() {
+                        self.assign_row(
+                            &mut region,
+                            offset,
+                            path,
+                            depth,
+                            row,
+                            modulus_bits[depth],
+                            &proof_type_chip,
+                        )?;
+                        offset += 1;
+                    }
+                }
+                // pad a row in the end to satisfy Halo2 cell assignment check
+                self.assign_padding_row(&mut region, offset, &proof_type_chip)
+            },
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn assign_row(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        path: &ZkTriePath<F>,
+        depth: usize,
+        row: &ZkTrieRow<F>,
+        modulus_bit: bool,
+        proof_type_chip: &BinaryNumberChip<F, MptProofType, 3>,
+    ) -> Result<(), Error> {
+        let is_last = depth == ZKTRIE_KEY_BITS - 1;
+        let word = match depth {
+            0 => path.key_word,
+            _ if is_last => path.old_word,
+            _ if depth == ZKTRIE_KEY_BITS - 2 => path.new_word,
+            _ => Word::zero(),
+        };
+        for (name, column, value) in [
+            ("q_enable", self.q_enable, F::one()),
+            ("q_first", self.q_first, F::from(depth == 0)),
+            ("q_last", self.q_last, F::from(is_last)),
+            (
+                "q_word",
+                self.q_word,
+                F::from(depth == 0 || depth + 2 >= ZKTRIE_KEY_BITS),
+            ),
+            ("modulus_bit", self.modulus_bit, F::from(modulus_bit)),
+            ("bit_mult", self.bit_mult, pow_of_two(depth)),
+        ] {
+            region.assign_fixed(
+                || format!("assign {} {}", name, offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        proof_type_chip.assign(region, offset, &path.proof_type)?;
+        self.mpt_table.assign_row(
+            region,
+            offset,
+            depth == 0 && (path.is_storage || path.proof_type != MptProofType::StorageMod),
+            [
+                path.address,
+                path.storage_key,
+                F::from(path.proof_type as u64),
+                path.old_state_root,
+                path.new_state_root,
+                path.old_value,
+                path.new_value,
+            ],
+        )?;
+
+        for (name, column, value) in [
+            ("is_storage", self.is_storage, F::from(path.is_storage)),
+            ("old_root", self.old_root, path.old_root),
+            ("new_root", self.new_root, path.new_root),
+            ("key", self.key, path.key),
+            ("key_hash", self.key_hash, path.key_hash),
+            (
+                "is_old_empty",
+                self.is_old_empty,
+                F::from(path.is_old_empty),
+            ),
+            (
+                "is_new_empty",
+                self.is_new_empty,
+                F::from(path.is_new_empty),
+            ),
+            ("old_value_hash", self.old_value_hash, path.old_value_hash),
+            ("new_value_hash", self.new_value_hash, path.new_value_hash),
+            ("bit", self.bit, F::from(row.bit)),
+            ("key_acc", self.key_acc, row.key_acc),
+            (
+                "is_key_canonical",
+                self.is_key_canonical,
+                F::from(row.is_key_canonical),
+            ),
+            ("is_branch", self.is_branch, F::from(row.is_branch)),
+            ("sibling", self.sibling, row.sibling),
+            ("old_hash", self.old_hash, row.old_hash),
+            ("new_hash", self.new_hash, row.new_hash),
+        ]
+        .into_iter()
+        .chain((0..4).flat_map(|idx| {
+            [
+                (
+                    "is_account_field",
+                    self.is_account_field[idx],
+                    F::from(!path.is_storage && path.proof_type == ACCOUNT_FIELDS[idx]),
+                ),
+                ("old_fields", self.old_fields[idx], path.old_fields[idx]),
+                ("new_fields", self.new_fields[idx], path.new_fields[idx]),
+            ]
+        }))
+        .chain((0..2).flat_map(|idx| {
+            [
+                (
+                    "old_field_hashes",
+                    self.old_field_hashes[idx],
+                    path.old_field_hashes[idx],
+                ),
+                (
+                    "new_field_hashes",
+                    self.new_field_hashes[idx],
+                    path.new_field_hashes[idx],
+                ),
+            ]
+        }))
+        .chain(
+            self.word_bytes
+                .into_iter()
+                .zip(word.to_le_bytes())
+                .map(|(column, byte)| ("word_bytes", column, F::from(byte as u64))),
+        ) {
+            region.assign_advice(
+                || format!("assign {} {}", name, offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn assign_padding_row(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        proof_type_chip: &BinaryNumberChip<F, MptProofType, 3>,
+    ) -> Result<(), Error> {
+        for column in [
+            self.q_enable,
+            self.q_first,
+            self.q_last,
+            self.q_word,
+            self.modulus_bit,
+            self.bit_mult,
+        ] {
+            region.assign_fixed(
+                || format!("assign padding row {}", offset),
+                column,
+                offset,
+                || Ok(F::zero()),
+            )?;
+        }
+        proof_type_chip.assign(region, offset, &MptProofType::default())?;
+        self.mpt_table
+            .assign_row(region, offset, false, [F::zero(); 7])?;
+        for column in [
+            self.is_storage,
+            self.old_root,
+            self.new_root,
+            self.key,
+            self.key_hash,
+            self.is_old_empty,
+            self.is_new_empty,
+            self.old_value_hash,
+            self.new_value_hash,
+            self.bit,
+            self.key_acc,
+            self.is_key_canonical,
+            self.is_branch,
+            self.sibling,
+            self.old_hash,
+            self.new_hash,
+        ]
+        .into_iter()
+        .chain(self.is_account_field)
+        .chain(self.old_fields)
+        .chain(self.new_fields)
+        .chain(self.old_field_hashes)
+        .chain(self.new_field_hashes)
+        .chain(self.word_bytes)
+        {
+            region.assign_advice(
+                || format!("assign padding row {}", offset),
+                column,
+                offset,
+                || Ok(F::zero()),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Looks up the hash of a pair of inputs in the poseidon table when the
+/// condition of `lookup` holds.
+fn lookup_poseidon<F: Field>(
+    meta: &mut ConstraintSystem<F>,
+    name: &'static str,
+    poseidon_table: PoseidonTable,
+    lookup: impl FnOnce(&mut VirtualCells<F>) -> (Expression<F>, [Expression<F>; 3]),
+) {
+    meta.lookup_any(name, |meta| {
+        let (condition, inputs) = lookup(meta);
+        std::iter::once(condition.clone())
+            .chain(inputs.map(|input| condition.clone() * input))
+            .zip(poseidon_table.table_exprs(meta))
+            .collect()
+    });
+}
+
+#[cfg(test)]
+mod zktrie_circuit_tests {
+    use eth_types::{Address, Field, Word};
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::{MockProver, VerifyFailure},
+        pairing::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+        poly::Rotation,
+    };
+    use std::collections::BTreeMap;
+
+    use super::{
+        witness::key_bits, zktrie_account_key, zktrie_storage_key, zktrie_storage_value_hash,
+        ZkTrie, ZkTrieAccount, ZkTrieCircuit, ZkTriePath, ZkTrieProof, ZkTrieUpdate,
+    };
+    use crate::{
+        mpt_circuit::{MptProofType, MptWitnessError},
+        poseidon_circuit::PoseidonCircuit,
+        table::{MptTable, PoseidonTable},
+        util::Expr,
+    };
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        paths: Vec<ZkTriePath<F>>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = (ZkTrieCircuit<F>, PoseidonCircuit<F>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let power_of_randomness = {
+                let columns = [(); 31].map(|_| meta.instance_column());
+                let mut power_of_randomness = None;
+
+                meta.create_gate("", |meta| {
+                    power_of_randomness =
+                        Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
+
+                    [0.expr()]
+                });
+
+                power_of_randomness.unwrap()
+            };
+
+            let mpt_table = MptTable::construct(meta);
+            let poseidon_table = PoseidonTable::construct(meta);
+            (
+                ZkTrieCircuit::configure(meta, mpt_table, poseidon_table, power_of_randomness),
+                PoseidonCircuit::configure(meta, poseidon_table),
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (zktrie, poseidon): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            zktrie.load_u8_table(&mut layouter)?;
+            let hashes: Vec<_> = self
+                .paths
+                .iter()
+                .flat_map(|path| path.hashes.iter().copied())
+                .collect();
+            poseidon.assign(&mut layouter, &hashes)?;
+            zktrie.assign(&mut layouter, &self.paths)
+        }
+    }
+
+    fn randomness() -> Fr {
+        Fr::from(0xcafeu64)
+    }
+
+    fn paths(updates: &[ZkTrieUpdate<Fr>]) -> Vec<ZkTriePath<Fr>> {
+        updates
+            .iter()
+            .flat_map(|update| update.paths(randomness()).unwrap())
+            .collect()
+    }
+
+    fn run_circuit<F: Field>(
+        paths: Vec<ZkTriePath<F>>,
+        randomness: F,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let k = 15;
+        let power_of_randomness = (1..32)
+            .map(|exp| vec![randomness.pow(&[exp, 0, 0, 0]); (1 << k) - 64])
+            .collect();
+        let circuit = MyCircuit::<F> { paths };
+        let prover = MockProver::<F>::run(k, &circuit, power_of_randomness).unwrap();
+        prover.verify()
+    }
+
+    /// Returns the proof of the update of the value at `key` of the trie.
+    fn prove(
+        trie: &mut ZkTrie<Fr>,
+        key: Fr,
+        update: impl FnOnce(&mut ZkTrie<Fr>),
+    ) -> ZkTrieProof<Fr> {
+        let old_root = trie.root();
+        let (old_siblings, old_leaf) = trie.prove(key);
+        update(trie);
+        let (new_siblings, new_leaf) = trie.prove(key);
+        ZkTrieProof {
+            key,
+            old_root,
+            new_root: trie.root(),
+            old_siblings,
+            new_siblings,
+            old_leaf,
+            new_leaf,
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct Account {
+        account: ZkTrieAccount<Fr>,
+        storage: ZkTrie<Fr>,
+        slots: BTreeMap<Word, Word>,
+    }
+
+    /// State trie keeping the accounts along the trie, to generate the proofs
+    /// of the updates.
+    #[derive(Default)]
+    struct State {
+        trie: ZkTrie<Fr>,
+        accounts: BTreeMap<Address, Account>,
+    }
+
+    impl State {
+        fn update(
+            &mut self,
+            address: Address,
+            proof_type: MptProofType,
+            storage_key: Word,
+            new_value: Word,
+        ) -> ZkTrieUpdate<Fr> {
+            let old_account = self.accounts.get(&address).map(|account| account.account);
+            let account = self.accounts.entry(address).or_default();
+            let (old_value, storage_proof) = match proof_type {
+                MptProofType::NonceMod => (
+                    std::mem::replace(&mut account.account.nonce, new_value),
+                    None,
+                ),
+                MptProofType::BalanceMod => (
+                    std::mem::replace(&mut account.account.balance, new_value),
+                    None,
+                ),
+                MptProofType::CodeHashMod => (
+                    std::mem::replace(&mut account.account.code_hash, new_value),
+                    None,
+                ),
+                MptProofType::StorageMod => {
+                    let key = zktrie_storage_key(&storage_key);
+                    let storage_proof = prove(&mut account.storage, key, |trie| {
+                        if new_value.is_zero() {
+                            trie.remove(key)
+                        } else {
+                            trie.insert(key, zktrie_storage_value_hash(&new_value))
+                        }
+                    });
+                    account.account.storage_root = account.storage.root();
+                    let old_value = account
+                        .slots
+                        .insert(storage_key, new_value)
+                        .unwrap_or_default();
+                    (old_value, Some(storage_proof))
+                }
+            };
+            let new_account = account.account;
+            let key = zktrie_account_key(&address);
+            let account_proof = prove(&mut self.trie, key, |trie| {
+                trie.insert(key, new_account.value_hash())
+            });
+
+            ZkTrieUpdate {
+                address,
+                storage_key,
+                proof_type,
+                old_value,
+                new_value,
+                old_account,
+                new_account: Some(new_account),
+                storage_proof,
+                account_proof,
+            }
+        }
+    }
+
+    /// Returns the addresses whose key starts with `bit`.
+    fn addresses(bit: bool) -> impl Iterator<Item = Address> {
+        (1u64..)
+            .map(Address::from_low_u64_be)
+            .filter(move |address| key_bits(&zktrie_account_key::<Fr>(address))[0] == bit)
+    }
+
+    /// Returns the storage keys whose key starts with `bit`.
+    fn slots(bit: bool) -> impl Iterator<Item = Word> {
+        (1u64..)
+            .map(Word::from)
+            .filter(move |slot| key_bits(&zktrie_storage_key::<Fr>(slot))[0] == bit)
+    }
+
+    /// Returns a state with a few accounts, the first one having a few
+    /// storage slots.  The keys of the accounts and of the slots all start
+    /// with the bit 0, so that every key starting with the bit 1 is inserted
+    /// into the empty child of the root.
+    fn state() -> (State, [Address; 3], [Word; 3]) {
+        let mut addresses = addresses(false);
+        let addresses = [(); 3].map(|_| addresses.next().unwrap());
+        let mut slots = slots(false);
+        let slots = [(); 3].map(|_| slots.next().unwrap());
+        let mut state = State::default();
+        for (idx, address) in addresses.iter().enumerate() {
+            state.update(
+                *address,
+                MptProofType::BalanceMod,
+                Word::zero(),
+                Word::from(1000 + idx),
+            );
+        }
+        for slot in slots {
+            state.update(addresses[0], MptProofType::StorageMod, slot, slot + 1);
+        }
+        (state, addresses, slots)
+    }
+
+    #[test]
+    fn zktrie_trie_roots() {
+        let keys = [1u64, 2, 3].map(Fr::from);
+        let mut trie = ZkTrie::default();
+        assert_eq!(trie.root(), Fr::zero());
+        for key in keys {
+            trie.insert(key, key);
+        }
+        let root = trie.root();
+        trie.insert(Fr::from(4), Fr::from(4));
+        trie.remove(Fr::from(4));
+        assert_eq!(trie.root(), root);
+        for key in keys.iter().rev() {
+            trie.remove(*key);
+        }
+        assert_eq!(trie.root(), Fr::zero());
+    }
+
+    #[test]
+    fn zktrie_circuit_valid_updates() {
+        let (mut state, [a, b, c], [_, slot, _]) = state();
+        let new_slot = slots(true).next().unwrap();
+        let updates = vec![
+            state.update(a, MptProofType::NonceMod, Word::zero(), Word::from(1)),
+            state.update(b, MptProofType::BalanceMod, Word::zero(), Word::from(2000)),
+            state.update(a, MptProofType::StorageMod, slot, Word::MAX),
+            // insertion into the empty child of the root
+            state.update(a, MptProofType::StorageMod, new_slot, Word::from(3)),
+            // deletion from a branch keeping its other child
+            state.update(a, MptProofType::StorageMod, new_slot, Word::zero()),
+            state.update(
+                c,
+                MptProofType::CodeHashMod,
+                Word::zero(),
+                Word::from_big_endian(&[0xab; 32]),
+            ),
+        ];
+        assert_eq!(run_circuit(paths(&updates), randomness()), Ok(()));
+    }
+
+    #[test]
+    fn zktrie_circuit_empty_storage_trie() {
+        let (mut state, [_, b, _], _) = state();
+        let updates = vec![
+            // insertion into the empty storage trie
+            state.update(b, MptProofType::StorageMod, Word::from(1), Word::from(5)),
+            state.update(b, MptProofType::StorageMod, Word::from(1), Word::from(6)),
+            // deletion of the only slot
+            state.update(b, MptProofType::StorageMod, Word::from(1), Word::zero()),
+        ];
+        assert_eq!(run_circuit(paths(&updates), randomness()), Ok(()));
+    }
+
+    #[test]
+    fn zktrie_circuit_new_account() {
+        let (mut state, _, _) = state();
+        let address = addresses(true).next().unwrap();
+        let updates =
+            vec![state.update(address, MptProofType::NonceMod, Word::zero(), Word::from(1))];
+        assert_eq!(run_circuit(paths(&updates), randomness()), Ok(()));
+    }
+
+    #[test]
+    fn zktrie_circuit_unsupported_update() {
+        let (mut state, [a, _, _], _) = state();
+        // inserting at the leaf of another slot splits it
+        let storage = &state.accounts[&a].storage;
+        let slot = slots(false)
+            .find(|slot| {
+                let key = zktrie_storage_key(slot);
+                matches!(storage.prove(key).1, Some((leaf_key, _)) if leaf_key != key)
+            })
+            .unwrap();
+        let update = state.update(a, MptProofType::StorageMod, slot, Word::one());
+        assert_eq!(
+            update.paths(randomness()).unwrap_err(),
+            MptWitnessError::UnsupportedUpdate
+        );
+    }
+
+    #[test]
+    fn zktrie_circuit_invalid_value() {
+        let (mut state, [a, _, _], [_, slot, _]) = state();
+        let mut update = state.update(a, MptProofType::StorageMod, slot, Word::one());
+        update.old_value = Word::from(7);
+        assert_eq!(
+            update.paths(randomness()).unwrap_err(),
+            MptWitnessError::ValueMismatch
+        );
+    }
+
+    #[test]
+    fn zktrie_circuit_invalid_sibling() {
+        let (mut state, [a, _, _], [_, slot, _]) = state();
+        let updates = vec![state.update(a, MptProofType::StorageMod, slot, Word::one())];
+        let mut paths = paths(&updates);
+        let row = paths[0].rows.iter_mut().find(|row| row.is_branch).unwrap();
+        row.sibling += Fr::from(1);
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+
+    #[test]
+    fn zktrie_circuit_invalid_leaf_value() {
+        let (mut state, [a, _, _], [_, slot, _]) = state();
+        let updates = vec![state.update(a, MptProofType::StorageMod, slot, Word::one())];
+        let mut paths = paths(&updates);
+        paths[0].new_value += Fr::from(1);
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+
+    #[test]
+    fn zktrie_circuit_missing_hash() {
+        let (mut state, [a, _, _], [_, slot, _]) = state();
+        let updates = vec![state.update(a, MptProofType::StorageMod, slot, Word::one())];
+        let mut paths = paths(&updates);
+        paths[1].hashes.clear();
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+
+    #[test]
+    fn zktrie_circuit_invalid_storage_root() {
+        let (mut state, [a, _, _], [_, slot, _]) = state();
+        let updates = vec![state.update(a, MptProofType::StorageMod, slot, Word::one())];
+        let mut paths = paths(&updates);
+        paths[1].new_value += Fr::from(1);
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+
+    #[test]
+    fn zktrie_circuit_invalid_account_address() {
+        let (mut state, [a, _, _], [_, slot, _]) = state();
+        let updates = vec![state.update(a, MptProofType::StorageMod, slot, Word::one())];
+        let mut paths = paths(&updates);
+        paths[1].address += Fr::from(1);
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+
+    #[test]
+    fn zktrie_circuit_invalid_state_root_chain() {
+        let (mut state, [a, b, _], _) = state();
+        let updates = vec![
+            state.update(a, MptProofType::NonceMod, Word::zero(), Word::from(1)),
+            state.update(b, MptProofType::NonceMod, Word::zero(), Word::from(1)),
+        ];
+        // skip the second update
+        let mut paths = paths(&updates[..1]);
+        paths.extend(paths.clone());
+        assert!(run_circuit(paths, randomness()).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! A minimal in-memory zkTrie, the sparse binary Merkle trie hashed with
+//! Poseidon, used to compute the roots and the proofs of the trie updates
+//! witnessed by the zkTrie circuit.
+
+use eth_types::Field;
+
+use super::witness::{key_bits, leaf_hash};
+use crate::poseidon_circuit::poseidon_hash;
+
+/// A node of the trie. A leaf is stored at the first depth where its key
+/// diverges from the keys of the other leaves.
+#[derive(Clone, Debug)]
+enum Node<F> {
+    Empty,
+    Leaf { key: F, value_hash: F },
+    Branch { children: Box<[Node<F>; 2]> },
+}
+
+impl<F> Default for Node<F> {
+    fn default() -> Self {
+        Self::Empty
+    }
+}
+
+impl<F: Field> Node<F> {
+    /// Returns the hash of the node, which is 0 for the empty node.
+    fn hash(&self) -> F {
+        match self {
+            Self::Empty => F::zero(),
+            Self::Leaf { key, value_hash } => leaf_hash(*key, *value_hash),
+            Self::Branch { children } => poseidon_hash([children[0].hash(), children[1].hash()]),
+        }
+    }
+
+    /// Returns the node with `value_hash` stored at `key`, whose bits below
+    /// `depth` select the node.
+    fn insert(self, key: F, bits: &[bool], depth: usize, value_hash: F) -> Self {
+        match self {
+            Self::Empty => Self::Leaf { key, value_hash },
+            Self::Leaf { key: leaf_key, .. } if leaf_key == key => Self::Leaf { key, value_hash },
+            Self::Leaf {
+                key: leaf_key,
+                value_hash: leaf_value_hash,
+            } => {
+                // The leaf is pushed down until its key diverges from `key`.
+                let mut children: Box<[Node<F>; 2]> = Default::default();
+                children[key_bits(&leaf_key)[depth] as usize] = Self::Leaf {
+                    key: leaf_key,
+                    value_hash: leaf_value_hash,
+                };
+                Self::Branch { children }.insert(key, bits, depth, value_hash)
+            }
+            Self::Branch { mut children } => {
+                let bit = bits[depth] as usize;
+                let child = std::mem::take(&mut children[bit]);
+                children[bit] = child.insert(key, bits, depth + 1, value_hash);
+                Self::Branch { children }
+            }
+        }
+    }
+
+    /// Returns the node without the value stored at `key`, collapsing the
+    /// branches left with a single leaf.
+    fn remove(self, key: F, bits: &[bool], depth: usize) -> Self {
+        match self {
+            Self::Leaf { key: leaf_key, .. } if leaf_key == key => Self::Empty,
+            Self::Branch { mut children } => {
+                let bit = bits[depth] as usize;
+                let child = std::mem::take(&mut children[bit]);
+                children[bit] = child.remove(key, bits, depth + 1);
+                match *children {
+                    [Self::Empty, Self::Empty] => Self::Empty,
+                    [Self::Empty, leaf @ Self::Leaf { .. }]
+                    | [leaf @ Self::Leaf { .. }, Self::Empty] => leaf,
+                    children => Self::Branch {
+                        children: Box::new(children),
+                    },
+                }
+            }
+            node => node,
+        }
+    }
+
+    /// Appends the siblings of the nodes on the path of `key` below `depth` to
+    /// `siblings`, and returns the leaf at the end of the path.
+    fn prove(&self, bits: &[bool], depth: usize, siblings: &mut Vec<F>) -> Option<(F, F)> {
+        match self {
+            Self::Empty => None,
+            Self::Leaf { key, value_hash } => Some((*key, *value_hash)),
+            Self::Branch { children } => {
+                let bit = bits[depth] as usize;
+                siblings.push(children[1 - bit].hash());
+                children[bit].prove(bits, depth + 1, siblings)
+            }
+        }
+    }
+}
+
+/// An in-memory zkTrie.
+#[derive(Clone, Debug, Default)]
+pub struct ZkTrie<F> {
+    root: Node<F>,
+}
+
+impl<F: Field> ZkTrie<F> {
+    /// Returns the root hash of the trie.
+    pub fn root(&self) -> F {
+        self.root.hash()
+    }
+
+    /// Stores the value of hash `value_hash` at `key`.
+    pub fn insert(&mut self, key: F, value_hash: F) {
+        let root = std::mem::take(&mut self.root);
+        self.root = root.insert(key, &key_bits(&key), 0, value_hash);
+    }
+
+    /// Removes the value stored at `key`.
+    pub fn remove(&mut self, key: F) {
+        let root = std::mem::take(&mut self.root);
+        self.root = root.remove(key, &key_bits(&key), 0);
+    }
+
+    /// Returns the siblings of the nodes from the root on the path of `key`,
+    /// and the key and the value hash of the leaf at the end of the path,
+    /// which is another leaf or none when `key` has no value.
+    pub fn prove(&self, key: F) -> (Vec<F>, Option<(F, F)>) {
+        let mut siblings = Vec::new();
+        let leaf = self.root.prove(&key_bits(&key), 0, &mut siblings);
+        (siblings, leaf)
+    }
+}
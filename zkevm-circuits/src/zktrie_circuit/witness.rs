@@ -0,0 +1,512 @@
+//! Witness of the zkTrie circuit: the trie updates, each proven by the
+//! siblings of the nodes on the path of its key before and after the update,
+//! and the rows laid out for every path.
+
+use eth_types::{Address, Field, ToLittleEndian, ToScalar, Word};
+
+use crate::{
+    evm_circuit::util::{split_u256, RandomLinearCombination},
+    mpt_circuit::{MptProofType, MptWitnessError},
+    poseidon_circuit::PoseidonParams,
+};
+
+/// Number of bits of the keys of the trie, which is the number of rows of
+/// every path.
+pub const ZKTRIE_KEY_BITS: usize = 254;
+
+/// An account of the state zkTrie.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZkTrieAccount<F> {
+    /// The nonce of the account.
+    pub nonce: Word,
+    /// The balance of the account.
+    pub balance: Word,
+    /// The root of the storage trie of the account.
+    pub storage_root: F,
+    /// The code hash of the account.
+    pub code_hash: Word,
+}
+
+impl<F: Field> ZkTrieAccount<F> {
+    /// Returns the hash of the account, which is the value hash of its leaf.
+    pub fn value_hash(&self) -> F {
+        Hasher::default().account(self).2
+    }
+}
+
+/// Proof of the update of the value at `key` of a trie, given by the siblings
+/// of the nodes on the path of the key from the root, and by the leaf at the
+/// end of the path.  A missing leaf is proven by the empty node in its place.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ZkTrieProof<F> {
+    /// The key of the updated value in the trie.
+    pub key: F,
+    /// The root of the trie before the update.
+    pub old_root: F,
+    /// The root of the trie after the update.
+    pub new_root: F,
+    /// The siblings on the path of the key before the update.
+    pub old_siblings: Vec<F>,
+    /// The siblings on the path of the key after the update.
+    pub new_siblings: Vec<F>,
+    /// The key and the value hash of the leaf at the end of the path before
+    /// the update.
+    pub old_leaf: Option<(F, F)>,
+    /// The key and the value hash of the leaf at the end of the path after
+    /// the update.
+    pub new_leaf: Option<(F, F)>,
+}
+
+/// An update of the state zkTrie, modifying one value of an account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZkTrieUpdate<F> {
+    /// The address of the account.
+    pub address: Address,
+    /// The storage key of the slot, or zero for any other proof type.
+    pub storage_key: Word,
+    /// The type of the update.
+    pub proof_type: MptProofType,
+    /// The value before the update.
+    pub old_value: Word,
+    /// The value after the update.
+    pub new_value: Word,
+    /// The account before the update, which is missing from a new account.
+    pub old_account: Option<ZkTrieAccount<F>>,
+    /// The account after the update.
+    pub new_account: Option<ZkTrieAccount<F>>,
+    /// The proof of the storage trie update for [`MptProofType::StorageMod`].
+    pub storage_proof: Option<ZkTrieProof<F>>,
+    /// The proof of the account trie update.
+    pub account_proof: ZkTrieProof<F>,
+}
+
+/// The path of a key in a trie, laid out as one row per bit of the key.
+#[derive(Clone, Debug)]
+pub struct ZkTriePath<F> {
+    /// The type of the update the path belongs to.
+    pub proof_type: MptProofType,
+    /// Whether the path is in a storage trie, in which case it's followed by
+    /// the path of its account in the account trie.
+    pub is_storage: bool,
+    /// The address of the account.
+    pub address: F,
+    /// The RLC of the storage key for a storage update, or 0 otherwise.
+    pub storage_key: F,
+    /// The root of the trie before the update.
+    pub old_root: F,
+    /// The root of the trie after the update.
+    pub new_root: F,
+    /// The root of the state trie before the update.
+    pub old_state_root: F,
+    /// The root of the state trie after the update.
+    pub new_state_root: F,
+    /// The key, which is the hash of the address or of the storage key.
+    pub key: F,
+    /// The storage key for a storage path, or zero otherwise.
+    pub key_word: Word,
+    /// The value at the key before the update, encoded as in the rw table:
+    /// the storage value, the modified account field, or the storage root of
+    /// the account for a storage update.
+    pub old_value: F,
+    /// The value at the key after the update.
+    pub new_value: F,
+    /// The word of the storage value or of the modified balance or code hash
+    /// before the update, or zero otherwise.
+    pub old_word: Word,
+    /// The word of the value after the update.
+    pub new_word: Word,
+    /// Whether the key has no value before the update.
+    pub is_old_empty: bool,
+    /// Whether the key has no value after the update.
+    pub is_new_empty: bool,
+    /// The hash of the leaf prefix with the key.
+    pub key_hash: F,
+    /// The hash of the value before the update.
+    pub old_value_hash: F,
+    /// The hash of the value after the update.
+    pub new_value_hash: F,
+    /// The fields of the account before the update for an account path: the
+    /// nonce, the hash of the balance, the storage root and the hash of the
+    /// code hash.
+    pub old_fields: [F; 4],
+    /// The fields of the account after the update.
+    pub new_fields: [F; 4],
+    /// The hashes of the pairs of fields of the account before the update.
+    pub old_field_hashes: [F; 2],
+    /// The hashes of the pairs of fields of the account after the update.
+    pub new_field_hashes: [F; 2],
+    /// The rows of the path, one for each bit of the key.
+    pub rows: Vec<ZkTrieRow<F>>,
+    /// The inputs of every hash of the path, which are the inputs of the
+    /// poseidon table.
+    pub hashes: Vec<[F; 2]>,
+}
+
+/// A row of a path in the trie.
+#[derive(Clone, Debug)]
+pub struct ZkTrieRow<F> {
+    /// The bit of the key at the depth of the row.
+    pub bit: bool,
+    /// The sum of the key bits up to the depth of the row.
+    pub key_acc: F,
+    /// Whether the key bits up to the depth of the row are at most the bits
+    /// of the modulus minus one.
+    pub is_key_canonical: bool,
+    /// Whether the node of the row is a branch, or otherwise the leaf (or the
+    /// empty node in its place) at the end of the path.
+    pub is_branch: bool,
+    /// The sibling of the child of the branch selected by the bit.
+    pub sibling: F,
+    /// The hash of the node before the update.
+    pub old_hash: F,
+    /// The hash of the node after the update.
+    pub new_hash: F,
+}
+
+/// A value stored in the leaf of a trie.
+#[derive(Clone, Copy, Debug)]
+enum Leaf<F> {
+    Storage(Word),
+    Account(ZkTrieAccount<F>),
+}
+
+/// The fields of a leaf value laid out on a path.
+#[derive(Clone, Copy, Debug, Default)]
+struct LeafValue<F> {
+    value: F,
+    word: Word,
+    value_hash: F,
+    fields: [F; 4],
+    field_hashes: [F; 2],
+}
+
+/// Poseidon hasher recording the inputs of every hash.
+struct Hasher<F> {
+    params: PoseidonParams<F>,
+    inputs: Vec<[F; 2]>,
+}
+
+impl<F: Field> Default for Hasher<F> {
+    fn default() -> Self {
+        Self {
+            params: PoseidonParams::default(),
+            inputs: Vec::new(),
+        }
+    }
+}
+
+impl<F: Field> Hasher<F> {
+    fn hash(&mut self, inputs: [F; 2]) -> F {
+        self.inputs.push(inputs);
+        self.params.hash(inputs)
+    }
+
+    /// Returns the hash of the low and high 128 bits of `word`.
+    fn word(&mut self, word: Word) -> F {
+        let (lo, hi) = split_u256(&word);
+        self.hash([lo.to_scalar().unwrap(), hi.to_scalar().unwrap()])
+    }
+
+    /// Returns the fields of the account, the hashes of the pairs of fields
+    /// and the hash of the account.
+    fn account(&mut self, account: &ZkTrieAccount<F>) -> ([F; 4], [F; 2], F) {
+        let fields = [
+            account.nonce.to_scalar().unwrap(),
+            self.word(account.balance),
+            account.storage_root,
+            self.word(account.code_hash),
+        ];
+        let field_hashes = [
+            self.hash([fields[0], fields[1]]),
+            self.hash([fields[2], fields[3]]),
+        ];
+        (fields, field_hashes, self.hash(field_hashes))
+    }
+}
+
+/// Returns the bits of `key`, starting from the least significant bit.
+pub(crate) fn key_bits<F: Field>(key: &F) -> Vec<bool> {
+    let repr = key.to_repr();
+    (0..ZKTRIE_KEY_BITS)
+        .map(|idx| (repr[idx / 8] >> (idx % 8)) & 1 == 1)
+        .collect()
+}
+
+/// Returns the hash of the leaf of `key` holding a value of hash
+/// `value_hash`.
+pub(crate) fn leaf_hash<F: Field>(key: F, value_hash: F) -> F {
+    let mut hasher = Hasher::default();
+    let key_hash = hasher.hash([F::one(), key]);
+    hasher.hash([key_hash, value_hash])
+}
+
+/// Returns the key of the account of `address` in the state trie.
+pub fn zktrie_account_key<F: Field>(address: &Address) -> F {
+    Hasher::default().hash([address.to_scalar().unwrap(), F::zero()])
+}
+
+/// Returns the key of the slot of `storage_key` in a storage trie.
+pub fn zktrie_storage_key<F: Field>(storage_key: &Word) -> F {
+    Hasher::default().word(*storage_key)
+}
+
+/// Returns the value hash of the leaf of a storage slot holding `value`.
+pub fn zktrie_storage_value_hash<F: Field>(value: &Word) -> F {
+    Hasher::default().word(*value)
+}
+
+impl<F: Field> ZkTrieUpdate<F> {
+    /// Returns the paths laid out for the update, which are the path of the
+    /// storage trie followed by the path of the account trie for a storage
+    /// update, and the path of the account trie otherwise.
+    pub fn paths(&self, randomness: F) -> Result<Vec<ZkTriePath<F>>, MptWitnessError> {
+        let state_roots = (self.account_proof.old_root, self.account_proof.new_root);
+        let keys = (
+            self.address.to_scalar().unwrap(),
+            RandomLinearCombination::random_linear_combine(
+                self.storage_key.to_le_bytes(),
+                randomness,
+            ),
+        );
+        let old_account = self.old_account.unwrap_or_default();
+        let new_account = self.new_account.unwrap_or_default();
+
+        let mut paths = Vec::with_capacity(2);
+        match (self.proof_type, &self.storage_proof) {
+            (MptProofType::StorageMod, Some(storage_proof)) => {
+                let leaf = |value: Word| (!value.is_zero()).then(|| Leaf::Storage(value));
+                paths.push(storage_proof.path(
+                    self.proof_type,
+                    true,
+                    keys,
+                    self.storage_key,
+                    (leaf(self.old_value), leaf(self.new_value)),
+                    state_roots,
+                    randomness,
+                )?);
+                if (old_account.storage_root, new_account.storage_root)
+                    != (storage_proof.old_root, storage_proof.new_root)
+                {
+                    return Err(MptWitnessError::ValueMismatch);
+                }
+            }
+            (MptProofType::StorageMod, None) | (_, Some(_)) => {
+                return Err(MptWitnessError::InvalidProof)
+            }
+            _ => {
+                if (
+                    account_field(self.proof_type, &old_account),
+                    account_field(self.proof_type, &new_account),
+                ) != (self.old_value, self.new_value)
+                {
+                    return Err(MptWitnessError::ValueMismatch);
+                }
+            }
+        }
+        // The fields not modified by the update are the same, which are the
+        // fields of the empty account for a new account.
+        let mut modified_account = old_account;
+        match self.proof_type {
+            MptProofType::NonceMod => modified_account.nonce = new_account.nonce,
+            MptProofType::BalanceMod => modified_account.balance = new_account.balance,
+            MptProofType::StorageMod => modified_account.storage_root = new_account.storage_root,
+            MptProofType::CodeHashMod => modified_account.code_hash = new_account.code_hash,
+        }
+        if modified_account != new_account {
+            return Err(MptWitnessError::ValueMismatch);
+        }
+
+        paths.push(self.account_proof.path(
+            self.proof_type,
+            false,
+            keys,
+            Word::zero(),
+            (
+                self.old_account.map(Leaf::Account),
+                self.new_account.map(Leaf::Account),
+            ),
+            state_roots,
+            randomness,
+        )?);
+        Ok(paths)
+    }
+}
+
+impl<F: Field> ZkTrieProof<F> {
+    #[allow(clippy::too_many_arguments)]
+    fn path(
+        &self,
+        proof_type: MptProofType,
+        is_storage: bool,
+        (address, storage_key): (F, F),
+        key_word: Word,
+        (old_leaf, new_leaf): (Option<Leaf<F>>, Option<Leaf<F>>),
+        (old_state_root, new_state_root): (F, F),
+        randomness: F,
+    ) -> Result<ZkTriePath<F>, MptWitnessError> {
+        let mut hasher = Hasher::default();
+        let key = if is_storage {
+            hasher.word(key_word)
+        } else {
+            hasher.hash([address, F::zero()])
+        };
+        if key != self.key {
+            return Err(MptWitnessError::InvalidProof);
+        }
+
+        let depth = self.old_siblings.len();
+        if (old_leaf.is_none() && new_leaf.is_none())
+            || self.new_siblings.len() != depth
+            || depth >= ZKTRIE_KEY_BITS
+        {
+            return Err(MptWitnessError::UnsupportedUpdate);
+        }
+        if self.new_siblings != self.old_siblings {
+            return Err(MptWitnessError::InvalidProof);
+        }
+
+        let key_hash = hasher.hash([F::one(), key]);
+        let bits = key_bits(&key);
+        let mut sides = Vec::with_capacity(2);
+        for (leaf, proof_leaf, root) in [
+            (old_leaf, self.old_leaf, self.old_root),
+            (new_leaf, self.new_leaf, self.new_root),
+        ] {
+            let value = match leaf {
+                None => LeafValue::default(),
+                Some(Leaf::Storage(word)) => LeafValue {
+                    value: RandomLinearCombination::random_linear_combine(
+                        word.to_le_bytes(),
+                        randomness,
+                    ),
+                    word,
+                    value_hash: hasher.word(word),
+                    ..Default::default()
+                },
+                Some(Leaf::Account(account)) => {
+                    let (fields, field_hashes, value_hash) = hasher.account(&account);
+                    let word = match proof_type {
+                        MptProofType::BalanceMod | MptProofType::CodeHashMod => {
+                            account_field(proof_type, &account)
+                        }
+                        _ => Word::zero(),
+                    };
+                    let value = match proof_type {
+                        MptProofType::NonceMod => fields[0],
+                        MptProofType::StorageMod => fields[2],
+                        _ => RandomLinearCombination::random_linear_combine(
+                            word.to_le_bytes(),
+                            randomness,
+                        ),
+                    };
+                    LeafValue {
+                        value,
+                        word,
+                        value_hash,
+                        fields,
+                        field_hashes,
+                    }
+                }
+            };
+            // A leaf of another key at the end of the path would be split by
+            // an insertion.
+            match (leaf, proof_leaf) {
+                (None, None) => {}
+                (_, Some((leaf_key, _))) if leaf_key != key => {
+                    return Err(MptWitnessError::UnsupportedUpdate)
+                }
+                (Some(_), Some((_, value_hash))) if value_hash == value.value_hash => {}
+                _ => return Err(MptWitnessError::ValueMismatch),
+            }
+
+            // The hashes of the nodes on the path, from the leaf up to the
+            // root.
+            let mut hashes = vec![if leaf.is_some() {
+                hasher.hash([key_hash, value.value_hash])
+            } else {
+                F::zero()
+            }];
+            for (bit, sibling) in bits[..depth].iter().zip(self.old_siblings.iter()).rev() {
+                let child = *hashes.last().unwrap();
+                hashes.push(hasher.hash(if *bit {
+                    [*sibling, child]
+                } else {
+                    [child, *sibling]
+                }));
+            }
+            hashes.reverse();
+            if hashes[0] != root {
+                return Err(MptWitnessError::InvalidProof);
+            }
+            sides.push((value, hashes));
+        }
+        let (new_value, new_hashes) = sides.pop().unwrap();
+        let (old_value, old_hashes) = sides.pop().unwrap();
+
+        let modulus_bits = key_bits(&-F::one());
+        let mut key_acc = F::zero();
+        let mut bit_mult = F::one();
+        let mut is_key_canonical = true;
+        let rows = (0..ZKTRIE_KEY_BITS)
+            .map(|idx| {
+                let bit = bits[idx];
+                if bit {
+                    key_acc += bit_mult;
+                }
+                bit_mult = bit_mult.double();
+                is_key_canonical = if modulus_bits[idx] {
+                    !bit || is_key_canonical
+                } else {
+                    !bit && is_key_canonical
+                };
+                ZkTrieRow {
+                    bit,
+                    key_acc,
+                    is_key_canonical,
+                    is_branch: idx < depth,
+                    sibling: self.old_siblings.get(idx).copied().unwrap_or_default(),
+                    old_hash: old_hashes[idx.min(depth)],
+                    new_hash: new_hashes[idx.min(depth)],
+                }
+            })
+            .collect();
+
+        Ok(ZkTriePath {
+            proof_type,
+            is_storage,
+            address,
+            storage_key,
+            old_root: self.old_root,
+            new_root: self.new_root,
+            old_state_root,
+            new_state_root,
+            key,
+            key_word,
+            old_value: old_value.value,
+            new_value: new_value.value,
+            old_word: old_value.word,
+            new_word: new_value.word,
+            is_old_empty: old_leaf.is_none(),
+            is_new_empty: new_leaf.is_none(),
+            key_hash,
+            old_value_hash: old_value.value_hash,
+            new_value_hash: new_value.value_hash,
+            old_fields: old_value.fields,
+            new_fields: new_value.fields,
+            old_field_hashes: old_value.field_hashes,
+            new_field_hashes: new_value.field_hashes,
+            rows,
+            hashes: hasher.inputs,
+        })
+    }
+}
+
+/// Returns the field of the account modified by an account update.
+fn account_field<F>(proof_type: MptProofType, account: &ZkTrieAccount<F>) -> Word {
+    match proof_type {
+        MptProofType::NonceMod => account.nonce,
+        MptProofType::BalanceMod => account.balance,
+        MptProofType::CodeHashMod => account.code_hash,
+        MptProofType::StorageMod => unreachable!("storage root isn't a word"),
+    }
+}